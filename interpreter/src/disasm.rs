@@ -0,0 +1,138 @@
+//! Bytecode disassembly, using the opcode definitions of [crate::opcode].
+
+use core::fmt;
+
+use crate::opcode::Opcode;
+
+/// Disassemble the code into its instructions, with EIP-663 as given (see
+/// [Opcode::immediate_size]).
+#[must_use]
+pub const fn disassemble(code: &[u8], eip_663: bool) -> Disassembly<'_> {
+	Disassembly {
+		code,
+		position: 0,
+		eip_663,
+	}
+}
+
+/// An instruction of disassembled code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Instruction<'code> {
+	/// Position of the opcode in the code.
+	pub position: usize,
+	/// The opcode.
+	pub opcode: Opcode,
+	/// Immediate data of the opcode. Shorter than [Opcode::immediate_size]
+	/// if the code ends before the immediate data does.
+	pub immediate: &'code [u8],
+}
+
+impl<'code> Instruction<'code> {
+	/// Whether the instruction is a jump destination.
+	#[must_use]
+	pub fn is_jumpdest(&self) -> bool {
+		self.opcode == Opcode::JUMPDEST
+	}
+}
+
+impl<'code> fmt::Display for Instruction<'code> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.opcode)?;
+		if !self.immediate.is_empty() {
+			f.write_str(" 0x")?;
+			for byte in self.immediate {
+				write!(f, "{:02x}", byte)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Iterator of the instructions of code, see [disassemble].
+///
+/// It is also displayed as a listing, one instruction per line with its
+/// position, and jump destinations annotated.
+#[derive(Clone, Debug)]
+pub struct Disassembly<'code> {
+	code: &'code [u8],
+	position: usize,
+	eip_663: bool,
+}
+
+impl<'code> Iterator for Disassembly<'code> {
+	type Item = Instruction<'code>;
+
+	fn next(&mut self) -> Option<Instruction<'code>> {
+		let position = self.position;
+		let opcode = Opcode(*self.code.get(position)?);
+		let start = position + 1;
+		let end = start
+			.saturating_add(opcode.immediate_size(self.eip_663))
+			.min(self.code.len());
+		self.position = end;
+
+		Some(Instruction {
+			position,
+			opcode,
+			immediate: &self.code[start..end],
+		})
+	}
+}
+
+impl<'code> fmt::Display for Disassembly<'code> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for instruction in self.clone() {
+			write!(f, "{:04x}: {}", instruction.position, instruction)?;
+			if instruction.is_jumpdest() {
+				f.write_str(" ; jump destination")?;
+			}
+			writeln!(f)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::string::ToString;
+
+	use super::disassemble;
+	use crate::opcode::Opcode;
+
+	#[test]
+	fn test_disassemble() {
+		// PUSH1 0x04 JUMP INVALID JUMPDEST 0x0c PUSH2 0x01 (truncated)
+		let code = [0x60, 0x04, 0x56, 0xfe, 0x5b, 0x0c, 0x61, 0x01];
+		let instructions = disassemble(&code, false).collect::<alloc::vec::Vec<_>>();
+
+		assert_eq!(instructions.len(), 6);
+		assert_eq!(instructions[0].opcode, Opcode::PUSH1);
+		assert_eq!(instructions[0].immediate, &[0x04]);
+		assert!(instructions[3].is_jumpdest());
+		assert_eq!(instructions[5].position, 6);
+		assert_eq!(instructions[5].immediate, &[0x01]);
+
+		assert_eq!(
+			disassemble(&code, false).to_string(),
+			"0000: PUSH1 0x04\n\
+			 0002: JUMP\n\
+			 0003: INVALID\n\
+			 0004: JUMPDEST ; jump destination\n\
+			 0005: UNKNOWN(0x0c)\n\
+			 0006: PUSH2 0x01\n"
+		);
+	}
+
+	#[test]
+	fn test_disassemble_eip_663() {
+		// DUPN 0x5b, JUMPDEST
+		let code = [0xe6, 0x5b, 0x5b];
+
+		assert_eq!(
+			disassemble(&code, true).to_string(),
+			"0000: DUPN 0x5b\n\
+			 0002: JUMPDEST ; jump destination\n"
+		);
+		assert_eq!(disassemble(&code, false).count(), 3);
+	}
+}
@@ -29,15 +29,40 @@ impl From<ExitError> for ExitResult {
 	}
 }
 
+impl ExitError {
+	/// Stable numeric code of the error. Exceptions are `0x100` plus the
+	/// exception code, revert is `0x200`, and fatal errors are `0x300` plus
+	/// the fatal code.
+	#[must_use]
+	pub const fn code(&self) -> u16 {
+		match self {
+			Self::Exception(e) => 0x100 + e.code() as u16,
+			Self::Reverted => 0x200,
+			Self::Fatal(e) => 0x300 + e.code() as u16,
+		}
+	}
+
+	/// Stable name of the error, the name of the exception or the fatal
+	/// error, or `reverted`.
+	#[must_use]
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Exception(e) => e.name(),
+			Self::Reverted => "reverted",
+			Self::Fatal(e) => e.name(),
+		}
+	}
+}
+
 #[cfg(feature = "std")]
 impl std::error::Error for ExitError {}
 
 impl fmt::Display for ExitError {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
-			Self::Exception(_) => f.write_str("EVM exit exception"),
+			Self::Exception(e) => write!(f, "EVM exit exception: {e}"),
 			Self::Reverted => f.write_str("EVM internal revert"),
-			Self::Fatal(_) => f.write_str("EVM fatal error"),
+			Self::Fatal(e) => write!(f, "EVM fatal error: {e}"),
 		}
 	}
 }
@@ -126,11 +151,94 @@ pub enum ExitException {
 	#[cfg_attr(feature = "scale", codec(index = 14))]
 	MaxNonce,
 
+	/// `AUTHCALL` without an address authorized by `AUTH`
+	/// ([EIP-3074](https://eips.ethereum.org/EIPS/eip-3074)).
+	#[cfg_attr(feature = "scale", codec(index = 16))]
+	Unauthorized,
+
 	/// Other normal errors.
 	#[cfg_attr(feature = "scale", codec(index = 13))]
 	Other(Cow<'static, str>),
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for ExitException {}
+
+impl ExitException {
+	/// Stable numeric code of the exception, the same as its SCALE index.
+	/// Codes are never reused.
+	#[must_use]
+	pub const fn code(&self) -> u8 {
+		match self {
+			Self::StackUnderflow => 0,
+			Self::StackOverflow => 1,
+			Self::InvalidJump => 2,
+			Self::InvalidRange => 3,
+			Self::DesignatedInvalid => 4,
+			Self::CallTooDeep => 5,
+			Self::CreateCollision => 6,
+			Self::CreateContractLimit => 7,
+			Self::OutOfOffset => 8,
+			Self::OutOfGas => 9,
+			Self::OutOfFund => 10,
+			Self::PCUnderflow => 11,
+			Self::CreateEmpty => 12,
+			Self::Other(_) => 13,
+			Self::MaxNonce => 14,
+			Self::InvalidOpcode(_) => 15,
+			Self::Unauthorized => 16,
+		}
+	}
+
+	/// Stable name of the exception.
+	#[must_use]
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::StackUnderflow => "stack_underflow",
+			Self::StackOverflow => "stack_overflow",
+			Self::InvalidJump => "invalid_jump",
+			Self::InvalidRange => "invalid_range",
+			Self::DesignatedInvalid => "designated_invalid",
+			Self::CallTooDeep => "call_too_deep",
+			Self::CreateCollision => "create_collision",
+			Self::CreateContractLimit => "create_contract_limit",
+			Self::OutOfOffset => "out_of_offset",
+			Self::OutOfGas => "out_of_gas",
+			Self::OutOfFund => "out_of_fund",
+			Self::PCUnderflow => "pc_underflow",
+			Self::CreateEmpty => "create_empty",
+			Self::Other(_) => "other",
+			Self::MaxNonce => "max_nonce",
+			Self::InvalidOpcode(_) => "invalid_opcode",
+			Self::Unauthorized => "unauthorized",
+		}
+	}
+}
+
+impl fmt::Display for ExitException {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::StackUnderflow => f.write_str("stack underflow"),
+			Self::StackOverflow => f.write_str("stack overflow"),
+			Self::InvalidJump => f.write_str("invalid jump destination"),
+			Self::InvalidRange => f.write_str("invalid memory range"),
+			Self::DesignatedInvalid => f.write_str("designated invalid opcode"),
+			Self::CallTooDeep => f.write_str("call stack too deep"),
+			Self::CreateCollision => f.write_str("create collision"),
+			Self::CreateContractLimit => f.write_str("contract size limit exceeded"),
+			Self::InvalidOpcode(opcode) => write!(f, "invalid opcode 0x{:02x}", opcode.as_u8()),
+			Self::OutOfOffset => f.write_str("out of offset"),
+			Self::OutOfGas => f.write_str("out of gas"),
+			Self::OutOfFund => f.write_str("out of fund"),
+			Self::PCUnderflow => f.write_str("pc underflow"),
+			Self::CreateEmpty => f.write_str("create empty account"),
+			Self::MaxNonce => f.write_str("nonce at maximum"),
+			Self::Unauthorized => f.write_str("no authorized address"),
+			Self::Other(reason) => f.write_str(reason),
+		}
+	}
+}
+
 impl From<ExitException> for ExitResult {
 	fn from(s: ExitException) -> Self {
 		Err(ExitError::Exception(s))
@@ -166,6 +274,50 @@ pub enum ExitFatal {
 	Other(Cow<'static, str>),
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for ExitFatal {}
+
+impl ExitFatal {
+	/// Stable numeric code of the fatal error. Codes are never reused.
+	#[must_use]
+	pub const fn code(&self) -> u8 {
+		match self {
+			Self::NotSupported => 0,
+			Self::UnhandledInterrupt => 1,
+			Self::ExceptionAsFatal(_) => 2,
+			Self::AlreadyExited => 3,
+			Self::Unfinished => 4,
+			Self::Other(_) => 5,
+		}
+	}
+
+	/// Stable name of the fatal error.
+	#[must_use]
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::NotSupported => "not_supported",
+			Self::UnhandledInterrupt => "unhandled_interrupt",
+			Self::ExceptionAsFatal(_) => "exception_as_fatal",
+			Self::AlreadyExited => "already_exited",
+			Self::Unfinished => "unfinished",
+			Self::Other(_) => "fatal_other",
+		}
+	}
+}
+
+impl fmt::Display for ExitFatal {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NotSupported => f.write_str("operation not supported"),
+			Self::UnhandledInterrupt => f.write_str("unhandled interrupt"),
+			Self::ExceptionAsFatal(e) => write!(f, "exception as fatal: {e}"),
+			Self::AlreadyExited => f.write_str("already exited"),
+			Self::Unfinished => f.write_str("unfinished execution"),
+			Self::Other(reason) => f.write_str(reason),
+		}
+	}
+}
+
 impl From<ExitFatal> for ExitResult {
 	fn from(s: ExitFatal) -> Self {
 		Err(ExitError::Fatal(s))
@@ -7,13 +7,13 @@ use core::{
 };
 
 use primitive_types::{H160, H256, U256};
-use sha3::{Digest, Keccak256};
 
 use crate::{
 	error::{ExitError, ExitException, ExitResult},
 	interpreter::Interpreter,
 	machine::{Machine, Memory},
-	runtime::{Context, RuntimeBackend, RuntimeState, Transfer},
+	opcode::Opcode,
+	runtime::{Address, Context, RuntimeBackend, RuntimeBaseBackend, RuntimeState, Transfer},
 	utils::{h256_to_u256, u256_to_usize},
 };
 
@@ -34,6 +34,23 @@ pub enum CallCreateTrap {
 	CallCode,
 	DelegateCall,
 	StaticCall,
+	AuthCall,
+}
+
+impl CallCreateTrap {
+	/// Opcode of the trap.
+	#[must_use]
+	pub const fn opcode(&self) -> Opcode {
+		match self {
+			Self::Create => Opcode::CREATE,
+			Self::Create2 => Opcode::CREATE2,
+			Self::Call => Opcode::CALL,
+			Self::CallCode => Opcode::CALLCODE,
+			Self::DelegateCall => Opcode::DELEGATECALL,
+			Self::StaticCall => Opcode::STATICCALL,
+			Self::AuthCall => Opcode::AUTHCALL,
+		}
+	}
 }
 
 impl TrapConstruct<CallCreateTrap> for CallCreateTrap {
@@ -51,14 +68,16 @@ impl TrapConsume<CallCreateTrap> for CallCreateTrap {
 }
 
 /// Combined call create trap data.
-pub enum CallCreateTrapData {
+pub enum CallCreateTrapData<A = H160> {
 	/// A call trap data.
-	Call(CallTrapData),
+	Call(CallTrapData<A>),
 	/// A create trap data.
-	Create(CreateTrapData),
+	Create(CreateTrapData<A>),
 }
 
-impl CallCreateTrapData {
+impl<A: Address> CallCreateTrapData<A> {
+	/// Gas requested by a call. `None` for a create, which is given all the
+	/// gas available.
 	#[must_use]
 	pub const fn target_gas(&self) -> Option<U256> {
 		match self {
@@ -67,13 +86,21 @@ impl CallCreateTrapData {
 		}
 	}
 
-	pub fn new_from<S: AsRef<RuntimeState> + AsMut<RuntimeState>>(
+	/// Pop the arguments of the trapped opcode from the machine, and read
+	/// the input or init code from its memory.
+	pub fn new_from<
+		S: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>>,
+		H: RuntimeBaseBackend<A>,
+	>(
 		opcode: CallCreateTrap,
 		machine: &mut Machine<S>,
+		handler: &H,
 	) -> Result<Self, ExitError> {
 		match opcode {
 			CallCreateTrap::Create => Ok(Self::Create(CreateTrapData::new_create_from(machine)?)),
-			CallCreateTrap::Create2 => Ok(Self::Create(CreateTrapData::new_create2_from(machine)?)),
+			CallCreateTrap::Create2 => Ok(Self::Create(CreateTrapData::new_create2_from(
+				machine, handler,
+			)?)),
 			CallCreateTrap::Call => Ok(Self::Call(CallTrapData::new_from(
 				CallScheme::Call,
 				machine,
@@ -90,10 +117,15 @@ impl CallCreateTrapData {
 				CallScheme::StaticCall,
 				machine,
 			)?)),
+			CallCreateTrap::AuthCall => Ok(Self::Call(CallTrapData::new_from(
+				CallScheme::AuthCall,
+				machine,
+			)?)),
 		}
 	}
 
-	pub fn code<H: RuntimeBackend>(&self, handler: &H) -> Vec<u8> {
+	/// Code to execute: the code of the call target, or the init code.
+	pub fn code<H: RuntimeBackend<A>>(&self, handler: &H) -> Vec<u8> {
 		match self {
 			Self::Call(trap) => handler.code(trap.target),
 			Self::Create(trap) => trap.code.clone(),
@@ -112,23 +144,26 @@ pub enum CallScheme {
 	DelegateCall,
 	/// `STATICCALL`
 	StaticCall,
+	/// `AUTHCALL`
+	AuthCall,
 }
 
+/// Arguments of a trapped call opcode.
 #[derive(Clone, Debug)]
-pub struct CallTrapData {
-	pub target: H160,
-	pub transfer: Option<Transfer>,
+pub struct CallTrapData<A = H160> {
+	pub target: A,
+	pub transfer: Option<Transfer<A>>,
 	pub input: Vec<u8>,
 	pub gas: U256,
 	pub is_static: bool,
 	pub out_offset: U256,
 	pub out_len: U256,
-	pub context: Context,
+	pub context: Context<A>,
 }
 
-impl CallTrapData {
+impl<A: Address> CallTrapData<A> {
 	#[allow(clippy::too_many_arguments)]
-	fn new_from_params<S: AsRef<RuntimeState> + AsMut<RuntimeState>>(
+	fn new_from_params<S: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>>>(
 		scheme: CallScheme,
 		memory: &mut Memory,
 		state: &mut S,
@@ -168,7 +203,7 @@ impl CallTrapData {
 
 		let context = match scheme {
 			CallScheme::Call | CallScheme::StaticCall => Context {
-				address: (*to).into(),
+				address: A::from_word(*to),
 				caller: state.as_ref().context.address,
 				apparent_value: value,
 			},
@@ -182,12 +217,27 @@ impl CallTrapData {
 				caller: state.as_ref().context.caller,
 				apparent_value: state.as_ref().context.apparent_value,
 			},
+			CallScheme::AuthCall => Context {
+				address: A::from_word(*to),
+				caller: state
+					.as_ref()
+					.authorized
+					.ok_or(ExitException::Unauthorized)?,
+				apparent_value: value,
+			},
 		};
 
 		let transfer = if scheme == CallScheme::Call {
 			Some(Transfer {
 				source: state.as_ref().context.address,
-				target: (*to).into(),
+				target: A::from_word(*to),
+				value,
+			})
+		} else if scheme == CallScheme::AuthCall {
+			// The value is sent from the authorized account.
+			Some(Transfer {
+				source: context.caller,
+				target: A::from_word(*to),
 				value,
 			})
 		} else if scheme == CallScheme::CallCode {
@@ -205,7 +255,7 @@ impl CallTrapData {
 		Ok((
 			(),
 			Self {
-				target: (*to).into(),
+				target: A::from_word(*to),
 				transfer,
 				input,
 				gas,
@@ -217,7 +267,8 @@ impl CallTrapData {
 		))
 	}
 
-	pub fn new_from<S: AsRef<RuntimeState> + AsMut<RuntimeState>>(
+	/// Pop the arguments of a call with the scheme from the machine.
+	pub fn new_from<S: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>>>(
 		scheme: CallScheme,
 		machine: &mut Machine<S>,
 	) -> Result<Self, ExitError> {
@@ -226,8 +277,8 @@ impl CallTrapData {
 		let state = &mut machine.state;
 
 		match scheme {
-			CallScheme::Call | CallScheme::CallCode => stack.perform_pop7_push0(
-				|gas, to, value, in_offset, in_len, out_offset, out_len| {
+			CallScheme::Call | CallScheme::CallCode | CallScheme::AuthCall => stack
+				.perform_pop7_push0(|gas, to, value, in_offset, in_len, out_offset, out_len| {
 					Self::new_from_params(
 						scheme,
 						memory,
@@ -240,8 +291,7 @@ impl CallTrapData {
 						out_offset,
 						out_len,
 					)
-				},
-			),
+				}),
 			CallScheme::DelegateCall | CallScheme::StaticCall => {
 				stack.perform_pop6_push0(|gas, to, in_offset, in_len, out_offset, out_len| {
 					Self::new_from_params(
@@ -253,6 +303,10 @@ impl CallTrapData {
 		}
 	}
 
+	/// Feed the result of the call back to the calling interpreter: push the
+	/// success flag, copy the return data into the output range of memory,
+	/// set the return data buffer, and advance past the opcode. A fatal
+	/// error is returned after the flag is pushed.
 	pub fn feedback<I: Interpreter>(
 		self,
 		reason: ExitResult,
@@ -260,7 +314,7 @@ impl CallTrapData {
 		interpreter: &mut I,
 	) -> Result<(), ExitError>
 	where
-		I::State: AsRef<RuntimeState> + AsMut<RuntimeState>,
+		I::State: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>>,
 	{
 		let target_len = min(self.out_len, U256::from(retbuf.len()));
 		let out_offset = self.out_offset;
@@ -332,16 +386,16 @@ impl CallTrapData {
 
 /// Create scheme.
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
-pub enum CreateScheme {
+pub enum CreateScheme<A = H160> {
 	/// Legacy create scheme of `CREATE`.
 	Legacy {
 		/// Caller of the create call.
-		caller: H160,
+		caller: A,
 	},
 	/// Create scheme of `CREATE2`.
 	Create2 {
 		/// Caller of the create call.
-		caller: H160,
+		caller: A,
 		/// Code hash.
 		code_hash: H256,
 		/// Salt.
@@ -349,33 +403,42 @@ pub enum CreateScheme {
 	},
 }
 
-impl CreateScheme {
-	pub fn address<H: RuntimeBackend>(&self, handler: &H) -> H160 {
+impl<A: Address> CreateScheme<A> {
+	/// Opcode of the scheme.
+	#[must_use]
+	pub const fn opcode(&self) -> Opcode {
+		match self {
+			Self::Legacy { .. } => Opcode::CREATE,
+			Self::Create2 { .. } => Opcode::CREATE2,
+		}
+	}
+
+	pub fn address<H: RuntimeBackend<A>>(&self, handler: &H) -> A {
 		match self {
 			Self::Create2 {
 				caller,
 				code_hash,
 				salt,
 			} => {
-				let mut hasher = Keccak256::new();
-				hasher.update([0xff]);
-				hasher.update(&caller[..]);
-				hasher.update(&salt[..]);
-				hasher.update(&code_hash[..]);
-				H256::from_slice(hasher.finalize().as_slice()).into()
+				let mut preimage = Vec::with_capacity(1 + caller.as_ref().len() + 32 + 32);
+				preimage.push(0xff);
+				preimage.extend_from_slice(caller.as_ref());
+				preimage.extend_from_slice(&salt[..]);
+				preimage.extend_from_slice(&code_hash[..]);
+				A::from_word(handler.keccak256(&preimage))
 			}
 			Self::Legacy { caller } => {
 				let nonce = handler.nonce(*caller);
 				let mut stream = rlp::RlpStream::new_list(2);
 				stream.append(caller);
 				stream.append(&nonce);
-				H256::from_slice(Keccak256::digest(&stream.out()).as_slice()).into()
+				A::from_word(handler.keccak256(&stream.out()))
 			}
 		}
 	}
 
 	#[must_use]
-	pub const fn caller(&self) -> H160 {
+	pub const fn caller(&self) -> A {
 		match self {
 			Self::Create2 { caller, .. } => *caller,
 			Self::Legacy { caller } => *caller,
@@ -383,15 +446,16 @@ impl CreateScheme {
 	}
 }
 
+/// Arguments of a trapped create opcode.
 #[derive(Clone, Debug)]
-pub struct CreateTrapData {
-	pub scheme: CreateScheme,
+pub struct CreateTrapData<A = H160> {
+	pub scheme: CreateScheme<A>,
 	pub value: U256,
 	pub code: Vec<u8>,
 }
 
-impl CreateTrapData {
-	pub fn new_create_from<S: AsRef<RuntimeState> + AsMut<RuntimeState>>(
+impl<A: Address> CreateTrapData<A> {
+	pub fn new_create_from<S: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>>>(
 		machine: &mut Machine<S>,
 	) -> Result<Self, ExitError> {
 		let stack = &mut machine.stack;
@@ -430,8 +494,14 @@ impl CreateTrapData {
 		})
 	}
 
-	pub fn new_create2_from<S: AsRef<RuntimeState> + AsMut<RuntimeState>>(
+	/// Construct the create trap data of `CREATE2`. The init code hash is
+	/// computed with the handler.
+	pub fn new_create2_from<
+		S: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>>,
+		H: RuntimeBaseBackend<A>,
+	>(
 		machine: &mut Machine<S>,
+		handler: &H,
 	) -> Result<Self, ExitError> {
 		let stack = &mut machine.stack;
 		let memory = &mut machine.memory;
@@ -452,7 +522,7 @@ impl CreateTrapData {
 				.map(|(code_offset, code_len)| memory.get(code_offset, code_len))
 				.unwrap_or(Vec::new());
 
-			let code_hash = H256::from_slice(Keccak256::digest(&code).as_slice());
+			let code_hash = handler.keccak256(&code);
 
 			let scheme = CreateScheme::Create2 {
 				caller: state.as_ref().context.address,
@@ -473,18 +543,22 @@ impl CreateTrapData {
 		})
 	}
 
+	/// Feed the result of the create back to the creating interpreter: push
+	/// the created address, or zero on failure, set the return data buffer,
+	/// and advance past the opcode. A fatal error is returned after the
+	/// address is pushed.
 	pub fn feedback<I: Interpreter>(
 		self,
-		reason: Result<H160, ExitError>,
+		reason: Result<A, ExitError>,
 		retbuf: Vec<u8>,
 		interpreter: &mut I,
 	) -> Result<(), ExitError>
 	where
-		I::State: AsRef<RuntimeState> + AsMut<RuntimeState>,
+		I::State: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>>,
 	{
 		let ret = match reason {
 			Ok(address) => {
-				interpreter.machine_mut().stack.push(address.into())?;
+				interpreter.machine_mut().stack.push(address.to_word())?;
 				Ok(())
 			}
 			Err(ExitError::Reverted) => {
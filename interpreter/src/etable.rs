@@ -3,12 +3,17 @@ use core::{
 	ops::{Deref, DerefMut},
 };
 
+use primitive_types::H160;
+
+#[cfg(feature = "fusion")]
+use crate::interpreter::Superinstruction;
 use crate::{
 	error::{CallCreateTrap, ExitResult, TrapConstruct},
 	eval::*,
+	interpreter::Instruction,
 	machine::Machine,
 	opcode::Opcode,
-	runtime::{GasState, RuntimeBackend, RuntimeEnvironment, RuntimeState},
+	runtime::{Address, GasState, RuntimeBackend, RuntimeEnvironment, RuntimeState},
 };
 
 pub trait EtableSet {
@@ -23,6 +28,45 @@ pub trait EtableSet {
 		opcode: Opcode,
 		position: usize,
 	) -> Control<Self::Trap>;
+
+	/// Evaluate a decoded instruction, with its immediate data and static gas
+	/// already known.
+	///
+	/// By default, this evaluates the opcode at the instruction position.
+	/// Etable sets can override this to skip re-reading the code.
+	fn eval_instruction(
+		&self,
+		machine: &mut Machine<Self::State>,
+		handle: &mut Self::Handle,
+		instruction: &Instruction,
+	) -> Control<Self::Trap> {
+		self.eval(machine, handle, instruction.opcode, instruction.position)
+	}
+
+	/// Evaluate a superinstruction starting at `position`. Returns the offset
+	/// of the opcode that the control applies to, relative to `position`.
+	///
+	/// By default, this evaluates the fused opcodes one by one and stops at
+	/// the first one that does not continue to the next. Etable sets can
+	/// override this with combined handlers.
+	#[cfg(feature = "fusion")]
+	fn eval_superinstruction(
+		&self,
+		machine: &mut Machine<Self::State>,
+		handle: &mut Self::Handle,
+		superinstruction: &Superinstruction,
+		position: usize,
+	) -> (usize, Control<Self::Trap>) {
+		for (opcode, offset, next) in superinstruction.steps() {
+			match self.eval(machine, handle, opcode, position + offset) {
+				Control::Continue if next == offset + 1 => (),
+				Control::ContinueN(n) if next == offset + n => (),
+				control => return (offset, control),
+			}
+		}
+
+		(0, Control::ContinueN(superinstruction.len()))
+	}
 }
 
 impl<S, H, Tr, F> EtableSet for Etable<S, H, Tr, F>
@@ -256,6 +300,10 @@ impl<S, H, Tr> Etable<S, H, Tr> {
 		table[Opcode::SWAP15.as_usize()] = eval_swap15 as _;
 		table[Opcode::SWAP16.as_usize()] = eval_swap16 as _;
 
+		table[Opcode::DUPN.as_usize()] = eval_dupn as _;
+		table[Opcode::SWAPN.as_usize()] = eval_swapn as _;
+		table[Opcode::EXCHANGE.as_usize()] = eval_exchange as _;
+
 		table[Opcode::RETURN.as_usize()] = eval_return as _;
 
 		table[Opcode::REVERT.as_usize()] = eval_revert as _;
@@ -268,51 +316,63 @@ impl<S, H, Tr> Etable<S, H, Tr> {
 
 impl<S, H: RuntimeEnvironment + RuntimeBackend, Tr: TrapConstruct<CallCreateTrap>> Etable<S, H, Tr>
 where
-	S: AsRef<RuntimeState> + GasState,
+	S: AsRef<RuntimeState> + AsMut<RuntimeState> + GasState,
 {
 	/// Runtime Etable.
 	#[must_use]
 	pub const fn runtime() -> Self {
+		Self::runtime_with_address::<H160>()
+	}
+}
+
+impl<S, H, Tr: TrapConstruct<CallCreateTrap>> Etable<S, H, Tr> {
+	/// Runtime Etable, for a runtime with the address type `A`.
+	#[must_use]
+	pub const fn runtime_with_address<A: Address>() -> Self
+	where
+		S: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>> + GasState,
+		H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	{
 		let mut table = Self::core();
 
-		table.0[Opcode::SHA3.as_usize()] = eval_sha3 as _;
-
-		table.0[Opcode::ADDRESS.as_usize()] = eval_address as _;
-		table.0[Opcode::BALANCE.as_usize()] = eval_balance as _;
-		table.0[Opcode::ORIGIN.as_usize()] = eval_origin as _;
-		table.0[Opcode::CALLER.as_usize()] = eval_caller as _;
-		table.0[Opcode::CALLVALUE.as_usize()] = eval_callvalue as _;
-
-		table.0[Opcode::GASPRICE.as_usize()] = eval_gasprice as _;
-		table.0[Opcode::EXTCODESIZE.as_usize()] = eval_extcodesize as _;
-		table.0[Opcode::EXTCODECOPY.as_usize()] = eval_extcodecopy as _;
-		table.0[Opcode::RETURNDATASIZE.as_usize()] = eval_returndatasize as _;
-		table.0[Opcode::RETURNDATACOPY.as_usize()] = eval_returndatacopy as _;
-		table.0[Opcode::EXTCODEHASH.as_usize()] = eval_extcodehash as _;
-
-		table.0[Opcode::BLOCKHASH.as_usize()] = eval_blockhash as _;
-		table.0[Opcode::COINBASE.as_usize()] = eval_coinbase as _;
-		table.0[Opcode::TIMESTAMP.as_usize()] = eval_timestamp as _;
-		table.0[Opcode::NUMBER.as_usize()] = eval_number as _;
-		table.0[Opcode::DIFFICULTY.as_usize()] = eval_difficulty as _;
-		table.0[Opcode::GASLIMIT.as_usize()] = eval_gaslimit as _;
-		table.0[Opcode::CHAINID.as_usize()] = eval_chainid as _;
-		table.0[Opcode::SELFBALANCE.as_usize()] = eval_selfbalance as _;
-		table.0[Opcode::BASEFEE.as_usize()] = eval_basefee as _;
-
-		table.0[Opcode::SLOAD.as_usize()] = eval_sload as _;
-		table.0[Opcode::SSTORE.as_usize()] = eval_sstore as _;
-
-		table.0[Opcode::GAS.as_usize()] = eval_gas as _;
-
-		table.0[Opcode::TLOAD.as_usize()] = eval_tload as _;
-		table.0[Opcode::TSTORE.as_usize()] = eval_tstore as _;
-
-		table.0[Opcode::LOG0.as_usize()] = eval_log0 as _;
-		table.0[Opcode::LOG1.as_usize()] = eval_log1 as _;
-		table.0[Opcode::LOG2.as_usize()] = eval_log2 as _;
-		table.0[Opcode::LOG3.as_usize()] = eval_log3 as _;
-		table.0[Opcode::LOG4.as_usize()] = eval_log4 as _;
+		table.0[Opcode::SHA3.as_usize()] = eval_sha3::<A, S, H, Tr> as _;
+
+		table.0[Opcode::ADDRESS.as_usize()] = eval_address::<A, S, H, Tr> as _;
+		table.0[Opcode::BALANCE.as_usize()] = eval_balance::<A, S, H, Tr> as _;
+		table.0[Opcode::ORIGIN.as_usize()] = eval_origin::<A, S, H, Tr> as _;
+		table.0[Opcode::CALLER.as_usize()] = eval_caller::<A, S, H, Tr> as _;
+		table.0[Opcode::CALLVALUE.as_usize()] = eval_callvalue::<A, S, H, Tr> as _;
+
+		table.0[Opcode::GASPRICE.as_usize()] = eval_gasprice::<A, S, H, Tr> as _;
+		table.0[Opcode::EXTCODESIZE.as_usize()] = eval_extcodesize::<A, S, H, Tr> as _;
+		table.0[Opcode::EXTCODECOPY.as_usize()] = eval_extcodecopy::<A, S, H, Tr> as _;
+		table.0[Opcode::RETURNDATASIZE.as_usize()] = eval_returndatasize::<A, S, H, Tr> as _;
+		table.0[Opcode::RETURNDATACOPY.as_usize()] = eval_returndatacopy::<A, S, H, Tr> as _;
+		table.0[Opcode::EXTCODEHASH.as_usize()] = eval_extcodehash::<A, S, H, Tr> as _;
+
+		table.0[Opcode::BLOCKHASH.as_usize()] = eval_blockhash::<A, S, H, Tr> as _;
+		table.0[Opcode::COINBASE.as_usize()] = eval_coinbase::<A, S, H, Tr> as _;
+		table.0[Opcode::TIMESTAMP.as_usize()] = eval_timestamp::<A, S, H, Tr> as _;
+		table.0[Opcode::NUMBER.as_usize()] = eval_number::<A, S, H, Tr> as _;
+		table.0[Opcode::DIFFICULTY.as_usize()] = eval_difficulty::<A, S, H, Tr> as _;
+		table.0[Opcode::GASLIMIT.as_usize()] = eval_gaslimit::<A, S, H, Tr> as _;
+		table.0[Opcode::CHAINID.as_usize()] = eval_chainid::<A, S, H, Tr> as _;
+		table.0[Opcode::SELFBALANCE.as_usize()] = eval_selfbalance::<A, S, H, Tr> as _;
+		table.0[Opcode::BASEFEE.as_usize()] = eval_basefee::<A, S, H, Tr> as _;
+
+		table.0[Opcode::SLOAD.as_usize()] = eval_sload::<A, S, H, Tr> as _;
+		table.0[Opcode::SSTORE.as_usize()] = eval_sstore::<A, S, H, Tr> as _;
+
+		table.0[Opcode::GAS.as_usize()] = eval_gas::<A, S, H, Tr> as _;
+
+		table.0[Opcode::TLOAD.as_usize()] = eval_tload::<A, S, H, Tr> as _;
+		table.0[Opcode::TSTORE.as_usize()] = eval_tstore::<A, S, H, Tr> as _;
+
+		table.0[Opcode::LOG0.as_usize()] = eval_log0::<A, S, H, Tr> as _;
+		table.0[Opcode::LOG1.as_usize()] = eval_log1::<A, S, H, Tr> as _;
+		table.0[Opcode::LOG2.as_usize()] = eval_log2::<A, S, H, Tr> as _;
+		table.0[Opcode::LOG3.as_usize()] = eval_log3::<A, S, H, Tr> as _;
+		table.0[Opcode::LOG4.as_usize()] = eval_log4::<A, S, H, Tr> as _;
 
 		table.0[Opcode::CREATE.as_usize()] = eval_call_create_trap as _;
 		table.0[Opcode::CALL.as_usize()] = eval_call_create_trap as _;
@@ -321,9 +381,12 @@ where
 		table.0[Opcode::DELEGATECALL.as_usize()] = eval_call_create_trap as _;
 		table.0[Opcode::CREATE2.as_usize()] = eval_call_create_trap as _;
 
+		table.0[Opcode::AUTH.as_usize()] = eval_auth::<A, S, H, Tr> as _;
+		table.0[Opcode::AUTHCALL.as_usize()] = eval_call_create_trap as _;
+
 		table.0[Opcode::STATICCALL.as_usize()] = eval_call_create_trap as _;
 
-		table.0[Opcode::SUICIDE.as_usize()] = eval_suicide as _;
+		table.0[Opcode::SUICIDE.as_usize()] = eval_suicide::<A, S, H, Tr> as _;
 
 		table
 	}
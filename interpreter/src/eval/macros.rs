@@ -53,61 +53,45 @@ macro_rules! push_u256 {
 	)
 }
 
-macro_rules! op1_u256_fn {
+macro_rules! op2_u256_fn {
 	($machine:expr, $op:path) => {{
-		pop_u256!($machine, op1);
-		let ret = $op(op1);
-		push_u256!($machine, ret);
-
-		Control::Continue
-	}};
-}
-
-macro_rules! op2_u256_bool_ref {
-	($machine:expr, $op:ident) => {{
-		pop_u256!($machine, op1, op2);
-		let ret = op1.$op(&op2);
-		push_u256!($machine, if ret { U256::one() } else { U256::zero() });
-
-		Control::Continue
-	}};
-}
-
-macro_rules! op2_u256 {
-	($machine:expr, $op:ident) => {{
 		pop_u256!($machine, op1, op2);
-		let ret = op1.$op(op2);
+		let ret = $op(op1, op2);
 		push_u256!($machine, ret);
 
 		Control::Continue
 	}};
 }
 
-macro_rules! op2_u256_tuple {
-	($machine:expr, $op:ident) => {{
-		pop_u256!($machine, op1, op2);
-		let (ret, ..) = op1.$op(op2);
-		push_u256!($machine, ret);
+macro_rules! op1_word_fn {
+	($machine:expr, $op:path) => {{
+		pop!($machine, op1);
+		let ret = $op(self::word::from_h256(&op1));
+		push!($machine, self::word::to_h256(ret));
 
 		Control::Continue
 	}};
 }
 
-macro_rules! op2_u256_fn {
+macro_rules! op2_word_fn {
 	($machine:expr, $op:path) => {{
-		pop_u256!($machine, op1, op2);
-		let ret = $op(op1, op2);
-		push_u256!($machine, ret);
+		pop!($machine, op1, op2);
+		let ret = $op(self::word::from_h256(&op1), self::word::from_h256(&op2));
+		push!($machine, self::word::to_h256(ret));
 
 		Control::Continue
 	}};
 }
 
-macro_rules! op3_u256_fn {
+macro_rules! op3_word_fn {
 	($machine:expr, $op:path) => {{
-		pop_u256!($machine, op1, op2, op3);
-		let ret = $op(op1, op2, op3);
-		push_u256!($machine, ret);
+		pop!($machine, op1, op2, op3);
+		let ret = $op(
+			self::word::from_h256(&op1),
+			self::word::from_h256(&op2),
+			self::word::from_h256(&op3),
+		);
+		push!($machine, self::word::to_h256(ret));
 
 		Control::Continue
 	}};
@@ -216,6 +216,54 @@ pub fn swap<S, Tr>(state: &mut Machine<S>, n: usize) -> Control<Tr> {
 	Control::Continue
 }
 
+/// The immediate operand of the opcode at the position. Reading past the end
+/// of the code gives zero, like `PUSH`.
+#[inline]
+fn immediate<S>(state: &Machine<S>, position: usize) -> usize {
+	state.code.get(position + 1).copied().unwrap_or(0) as usize
+}
+
+#[inline]
+pub fn dupn<S, Tr>(state: &mut Machine<S>, position: usize) -> Control<Tr> {
+	match dup(state, immediate(state, position) + 1) {
+		Control::Continue => Control::ContinueN(2),
+		control => control,
+	}
+}
+
+#[inline]
+pub fn swapn<S, Tr>(state: &mut Machine<S>, position: usize) -> Control<Tr> {
+	match swap(state, immediate(state, position) + 1) {
+		Control::Continue => Control::ContinueN(2),
+		control => control,
+	}
+}
+
+#[inline]
+pub fn exchange<S, Tr>(state: &mut Machine<S>, position: usize) -> Control<Tr> {
+	let imm = immediate(state, position);
+	let n = (imm >> 4) + 1;
+	let m = (imm & 0x0f) + 1;
+
+	let val1 = match state.stack.peek(n) {
+		Ok(value) => value,
+		Err(e) => return Control::Exit(e.into()),
+	};
+	let val2 = match state.stack.peek(n + m) {
+		Ok(value) => value,
+		Err(e) => return Control::Exit(e.into()),
+	};
+	match state.stack.set(n, val2) {
+		Ok(()) => (),
+		Err(e) => return Control::Exit(e.into()),
+	}
+	match state.stack.set(n + m, val1) {
+		Ok(()) => (),
+		Err(e) => return Control::Exit(e.into()),
+	}
+	Control::ContinueN(2)
+}
+
 #[inline]
 pub fn ret<S, Tr>(state: &mut Machine<S>) -> Control<Tr> {
 	pop_u256!(state, start, len);
@@ -4,8 +4,7 @@ mod arithmetic;
 mod bitwise;
 mod misc;
 mod system;
-
-use core::ops::{BitAnd, BitOr, BitXor};
+mod word;
 
 use primitive_types::{H256, U256};
 
@@ -14,7 +13,7 @@ use crate::{
 	etable::Control,
 	machine::Machine,
 	opcode::Opcode,
-	runtime::{GasState, RuntimeBackend, RuntimeEnvironment, RuntimeState},
+	runtime::{Address, GasState, RuntimeBackend, RuntimeEnvironment, RuntimeState},
 };
 
 pub fn eval_pass<S, H, Tr>(
@@ -41,7 +40,7 @@ pub fn eval_add<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_tuple!(machine, overflowing_add)
+	op2_word_fn!(machine, self::word::add)
 }
 
 pub fn eval_mul<S, H, Tr>(
@@ -50,7 +49,7 @@ pub fn eval_mul<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_tuple!(machine, overflowing_mul)
+	op2_word_fn!(machine, self::word::mul)
 }
 
 pub fn eval_sub<S, H, Tr>(
@@ -59,7 +58,7 @@ pub fn eval_sub<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_tuple!(machine, overflowing_sub)
+	op2_word_fn!(machine, self::word::sub)
 }
 
 pub fn eval_div<S, H, Tr>(
@@ -68,7 +67,7 @@ pub fn eval_div<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_fn!(machine, self::arithmetic::div)
+	op2_word_fn!(machine, self::word::div)
 }
 
 pub fn eval_sdiv<S, H, Tr>(
@@ -86,7 +85,7 @@ pub fn eval_mod<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_fn!(machine, self::arithmetic::rem)
+	op2_word_fn!(machine, self::word::rem)
 }
 
 pub fn eval_smod<S, H, Tr>(
@@ -104,7 +103,7 @@ pub fn eval_addmod<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op3_u256_fn!(machine, self::arithmetic::addmod)
+	op3_word_fn!(machine, self::word::addmod)
 }
 
 pub fn eval_mulmod<S, H, Tr>(
@@ -113,7 +112,7 @@ pub fn eval_mulmod<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op3_u256_fn!(machine, self::arithmetic::mulmod)
+	op3_word_fn!(machine, self::word::mulmod)
 }
 
 pub fn eval_exp<S, H, Tr>(
@@ -122,7 +121,7 @@ pub fn eval_exp<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_fn!(machine, self::arithmetic::exp)
+	op2_word_fn!(machine, self::word::exp)
 }
 
 pub fn eval_signextend<S, H, Tr>(
@@ -140,7 +139,7 @@ pub fn eval_lt<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_bool_ref!(machine, lt)
+	op2_word_fn!(machine, self::word::lt)
 }
 
 pub fn eval_gt<S, H, Tr>(
@@ -149,7 +148,7 @@ pub fn eval_gt<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_bool_ref!(machine, gt)
+	op2_word_fn!(machine, self::word::gt)
 }
 
 pub fn eval_slt<S, H, Tr>(
@@ -176,7 +175,7 @@ pub fn eval_eq<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_bool_ref!(machine, eq)
+	op2_word_fn!(machine, self::word::eq)
 }
 
 pub fn eval_iszero<S, H, Tr>(
@@ -185,7 +184,7 @@ pub fn eval_iszero<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op1_u256_fn!(machine, self::bitwise::iszero)
+	op1_word_fn!(machine, self::word::iszero)
 }
 
 pub fn eval_and<S, H, Tr>(
@@ -194,7 +193,7 @@ pub fn eval_and<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256!(machine, bitand)
+	op2_word_fn!(machine, self::word::and)
 }
 
 pub fn eval_or<S, H, Tr>(
@@ -203,7 +202,7 @@ pub fn eval_or<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256!(machine, bitor)
+	op2_word_fn!(machine, self::word::or)
 }
 
 pub fn eval_xor<S, H, Tr>(
@@ -212,7 +211,7 @@ pub fn eval_xor<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256!(machine, bitxor)
+	op2_word_fn!(machine, self::word::xor)
 }
 
 pub fn eval_not<S, H, Tr>(
@@ -221,7 +220,7 @@ pub fn eval_not<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op1_u256_fn!(machine, self::bitwise::not)
+	op1_word_fn!(machine, self::word::not)
 }
 
 pub fn eval_byte<S, H, Tr>(
@@ -239,7 +238,7 @@ pub fn eval_shl<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_fn!(machine, self::bitwise::shl)
+	op2_word_fn!(machine, self::word::shl)
 }
 
 pub fn eval_shr<S, H, Tr>(
@@ -248,7 +247,7 @@ pub fn eval_shr<S, H, Tr>(
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	op2_u256_fn!(machine, self::bitwise::shr)
+	op2_word_fn!(machine, self::word::shr)
 }
 
 pub fn eval_sar<S, H, Tr>(
@@ -449,6 +448,33 @@ macro_rules! eval_swap {
 
 eval_swap! { 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16 }
 
+pub fn eval_dupn<S, H, Tr>(
+	machine: &mut Machine<S>,
+	_handle: &mut H,
+	_opcode: Opcode,
+	position: usize,
+) -> Control<Tr> {
+	self::misc::dupn(machine, position)
+}
+
+pub fn eval_swapn<S, H, Tr>(
+	machine: &mut Machine<S>,
+	_handle: &mut H,
+	_opcode: Opcode,
+	position: usize,
+) -> Control<Tr> {
+	self::misc::swapn(machine, position)
+}
+
+pub fn eval_exchange<S, H, Tr>(
+	machine: &mut Machine<S>,
+	_handle: &mut H,
+	_opcode: Opcode,
+	position: usize,
+) -> Control<Tr> {
+	self::misc::exchange(machine, position)
+}
+
 pub fn eval_return<S, H, Tr>(
 	machine: &mut Machine<S>,
 	_handle: &mut H,
@@ -485,16 +511,26 @@ pub fn eval_unknown<S, H, Tr>(
 	Control::Exit(ExitException::InvalidOpcode(opcode).into())
 }
 
-pub fn eval_sha3<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_sha3<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
-	_handle: &mut H,
+	handle: &mut H,
 	_opcode: Opcode,
 	_position: usize,
 ) -> Control<Tr> {
-	self::system::sha3(machine)
+	self::system::sha3(machine, handle)
 }
 
-pub fn eval_address<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_address<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	_handle: &mut H,
 	_opcode: Opcode,
@@ -503,7 +539,12 @@ pub fn eval_address<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacke
 	self::system::address(machine)
 }
 
-pub fn eval_balance<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_balance<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -512,7 +553,12 @@ pub fn eval_balance<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacke
 	self::system::balance(machine, handle)
 }
 
-pub fn eval_selfbalance<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_selfbalance<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -521,7 +567,12 @@ pub fn eval_selfbalance<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeB
 	self::system::selfbalance(machine, handle)
 }
 
-pub fn eval_origin<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_origin<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -530,7 +581,12 @@ pub fn eval_origin<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacken
 	self::system::origin(machine, handle)
 }
 
-pub fn eval_caller<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_caller<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	_handle: &mut H,
 	_opcode: Opcode,
@@ -539,7 +595,12 @@ pub fn eval_caller<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacken
 	self::system::caller(machine)
 }
 
-pub fn eval_callvalue<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_callvalue<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	_handle: &mut H,
 	_opcode: Opcode,
@@ -548,7 +609,12 @@ pub fn eval_callvalue<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBac
 	self::system::callvalue(machine)
 }
 
-pub fn eval_gasprice<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_gasprice<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -557,7 +623,12 @@ pub fn eval_gasprice<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBack
 	self::system::gasprice(machine, handle)
 }
 
-pub fn eval_extcodesize<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_extcodesize<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -566,7 +637,12 @@ pub fn eval_extcodesize<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeB
 	self::system::extcodesize(machine, handle)
 }
 
-pub fn eval_extcodehash<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_extcodehash<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -575,7 +651,12 @@ pub fn eval_extcodehash<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeB
 	self::system::extcodehash(machine, handle)
 }
 
-pub fn eval_extcodecopy<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_extcodecopy<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -584,7 +665,12 @@ pub fn eval_extcodecopy<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeB
 	self::system::extcodecopy(machine, handle)
 }
 
-pub fn eval_returndatasize<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_returndatasize<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	_handle: &mut H,
 	_opcode: Opcode,
@@ -593,7 +679,12 @@ pub fn eval_returndatasize<S: AsRef<RuntimeState>, H: RuntimeEnvironment + Runti
 	self::system::returndatasize(machine)
 }
 
-pub fn eval_returndatacopy<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_returndatacopy<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	_handle: &mut H,
 	_opcode: Opcode,
@@ -602,7 +693,12 @@ pub fn eval_returndatacopy<S: AsRef<RuntimeState>, H: RuntimeEnvironment + Runti
 	self::system::returndatacopy(machine)
 }
 
-pub fn eval_blockhash<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_blockhash<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -611,7 +707,12 @@ pub fn eval_blockhash<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBac
 	self::system::blockhash(machine, handle)
 }
 
-pub fn eval_coinbase<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_coinbase<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -620,7 +721,12 @@ pub fn eval_coinbase<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBack
 	self::system::coinbase(machine, handle)
 }
 
-pub fn eval_timestamp<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_timestamp<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -629,7 +735,12 @@ pub fn eval_timestamp<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBac
 	self::system::timestamp(machine, handle)
 }
 
-pub fn eval_number<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_number<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -638,7 +749,12 @@ pub fn eval_number<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacken
 	self::system::number(machine, handle)
 }
 
-pub fn eval_difficulty<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_difficulty<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -647,7 +763,12 @@ pub fn eval_difficulty<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBa
 	self::system::prevrandao(machine, handle)
 }
 
-pub fn eval_gaslimit<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_gaslimit<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -656,7 +777,12 @@ pub fn eval_gaslimit<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBack
 	self::system::gaslimit(machine, handle)
 }
 
-pub fn eval_sload<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_sload<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -665,7 +791,12 @@ pub fn eval_sload<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend
 	self::system::sload(machine, handle)
 }
 
-pub fn eval_sstore<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_sstore<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -674,7 +805,7 @@ pub fn eval_sstore<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacken
 	self::system::sstore(machine, handle)
 }
 
-pub fn eval_gas<S: GasState, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_gas<A: Address, S: GasState, H: RuntimeEnvironment<A> + RuntimeBackend<A>, Tr>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -683,7 +814,12 @@ pub fn eval_gas<S: GasState, H: RuntimeEnvironment + RuntimeBackend, Tr>(
 	self::system::gas(machine, handle)
 }
 
-pub fn eval_tload<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_tload<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -692,7 +828,12 @@ pub fn eval_tload<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend
 	self::system::tload(machine, handle)
 }
 
-pub fn eval_tstore<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_tstore<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -704,7 +845,7 @@ pub fn eval_tstore<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacken
 macro_rules! eval_log {
     ($($num:expr),*) => {
 		$(paste::paste! {
-			pub fn [<eval_log $num>]<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+			pub fn [<eval_log $num>]<A: Address, S: AsRef<RuntimeState<A>>, H: RuntimeEnvironment<A> + RuntimeBackend<A>, Tr>(
 				machine: &mut Machine<S>,
 				handle: &mut H,
 				_opcode: Opcode,
@@ -718,7 +859,12 @@ macro_rules! eval_log {
 
 eval_log! { 0, 1, 2, 3, 4 }
 
-pub fn eval_suicide<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_suicide<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -727,7 +873,12 @@ pub fn eval_suicide<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacke
 	self::system::suicide(machine, handle)
 }
 
-pub fn eval_chainid<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_chainid<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -736,7 +887,12 @@ pub fn eval_chainid<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacke
 	self::system::chainid(machine, handle)
 }
 
-pub fn eval_basefee<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn eval_basefee<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handle: &mut H,
 	_opcode: Opcode,
@@ -745,6 +901,20 @@ pub fn eval_basefee<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacke
 	self::system::basefee(machine, handle)
 }
 
+pub fn eval_auth<
+	A: Address,
+	S: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
+	machine: &mut Machine<S>,
+	handle: &mut H,
+	_opcode: Opcode,
+	_position: usize,
+) -> Control<Tr> {
+	self::system::auth(machine, handle)
+}
+
 pub fn eval_call_create_trap<S, H, Tr: TrapConstruct<CallCreateTrap>>(
 	_machine: &mut Machine<S>,
 	_handle: &mut H,
@@ -758,6 +928,7 @@ pub fn eval_call_create_trap<S, H, Tr: TrapConstruct<CallCreateTrap>>(
 		Opcode::CALLCODE => CallCreateTrap::CallCode,
 		Opcode::DELEGATECALL => CallCreateTrap::DelegateCall,
 		Opcode::STATICCALL => CallCreateTrap::StaticCall,
+		Opcode::AUTHCALL => CallCreateTrap::AuthCall,
 		_ => return Control::Exit(Err(ExitException::InvalidOpcode(opcode).into())),
 	};
 
@@ -1,16 +1,22 @@
 use alloc::vec::Vec;
+use core::cmp::min;
 
 use primitive_types::{H256, U256};
-use sha3::{Digest, Keccak256};
 
 use crate::{
 	error::{ExitException, ExitFatal, ExitSucceed},
 	etable::Control,
 	machine::Machine,
-	runtime::{GasState, Log, RuntimeBackend, RuntimeEnvironment, RuntimeState, Transfer},
+	runtime::{
+		recent_block_hash, Address, GasState, Log, RuntimeBackend, RuntimeEnvironment,
+		RuntimeState, Transfer,
+	},
 };
 
-pub fn sha3<S: AsRef<RuntimeState>, Tr>(machine: &mut Machine<S>) -> Control<Tr> {
+pub fn sha3<A: Address, S: AsRef<RuntimeState<A>>, H: RuntimeBackend<A>, Tr>(
+	machine: &mut Machine<S>,
+	handler: &H,
+) -> Control<Tr> {
 	pop_u256!(machine, from, len);
 
 	try_or_fail!(machine.memory.resize_offset(from, len));
@@ -23,39 +29,55 @@ pub fn sha3<S: AsRef<RuntimeState>, Tr>(machine: &mut Machine<S>) -> Control<Tr>
 		machine.memory.get(from, len)
 	};
 
-	let ret = Keccak256::digest(data.as_slice());
-	push!(machine, H256::from_slice(ret.as_slice()));
+	let ret = handler.keccak256(data.as_slice());
+	push!(machine, ret);
 
 	Control::Continue
 }
 
-pub fn chainid<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn chainid<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
-	handler: &H,
+	_handler: &H,
 ) -> Control<Tr> {
-	push_u256!(machine, handler.chain_id());
+	let chain_id = machine.state.as_ref().transaction_context.chain_id;
+	push_u256!(machine, chain_id);
 
 	Control::Continue
 }
 
-pub fn address<S: AsRef<RuntimeState>, Tr>(machine: &mut Machine<S>) -> Control<Tr> {
-	let ret = H256::from(machine.state.as_ref().context.address);
+pub fn address<A: Address, S: AsRef<RuntimeState<A>>, Tr>(machine: &mut Machine<S>) -> Control<Tr> {
+	let ret = machine.state.as_ref().context.address.to_word();
 	push!(machine, ret);
 
 	Control::Continue
 }
 
-pub fn balance<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn balance<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
 ) -> Control<Tr> {
 	pop!(machine, address);
-	push_u256!(machine, handler.balance(address.into()));
+	push_u256!(machine, handler.balance(A::from_word(address)));
 
 	Control::Continue
 }
 
-pub fn selfbalance<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn selfbalance<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &H,
 ) -> Control<Tr> {
@@ -67,24 +89,31 @@ pub fn selfbalance<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacken
 	Control::Continue
 }
 
-pub fn origin<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn origin<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	_handler: &H,
 ) -> Control<Tr> {
-	let ret = H256::from(machine.state.as_ref().transaction_context.origin);
+	let ret = machine.state.as_ref().transaction_context.origin.to_word();
 	push!(machine, ret);
 
 	Control::Continue
 }
 
-pub fn caller<S: AsRef<RuntimeState>, Tr>(machine: &mut Machine<S>) -> Control<Tr> {
-	let ret = H256::from(machine.state.as_ref().context.caller);
+pub fn caller<A: Address, S: AsRef<RuntimeState<A>>, Tr>(machine: &mut Machine<S>) -> Control<Tr> {
+	let ret = machine.state.as_ref().context.caller.to_word();
 	push!(machine, ret);
 
 	Control::Continue
 }
 
-pub fn callvalue<S: AsRef<RuntimeState>, Tr>(machine: &mut Machine<S>) -> Control<Tr> {
+pub fn callvalue<A: Address, S: AsRef<RuntimeState<A>>, Tr>(
+	machine: &mut Machine<S>,
+) -> Control<Tr> {
 	let mut ret = H256::default();
 	machine
 		.state
@@ -97,7 +126,12 @@ pub fn callvalue<S: AsRef<RuntimeState>, Tr>(machine: &mut Machine<S>) -> Contro
 	Control::Continue
 }
 
-pub fn gasprice<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn gasprice<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	_handler: &H,
 ) -> Control<Tr> {
@@ -113,7 +147,12 @@ pub fn gasprice<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend,
 	Control::Continue
 }
 
-pub fn basefee<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn basefee<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &H,
 ) -> Control<Tr> {
@@ -124,29 +163,44 @@ pub fn basefee<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, T
 	Control::Continue
 }
 
-pub fn extcodesize<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn extcodesize<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
 ) -> Control<Tr> {
 	pop!(machine, address);
-	let code_size = handler.code_size(address.into());
+	let code_size = handler.code_size(A::from_word(address));
 	push_u256!(machine, code_size);
 
 	Control::Continue
 }
 
-pub fn extcodehash<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn extcodehash<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
 ) -> Control<Tr> {
 	pop!(machine, address);
-	let code_hash = handler.code_hash(address.into());
+	let code_hash = handler.code_hash(A::from_word(address));
 	push!(machine, code_hash);
 
 	Control::Continue
 }
 
-pub fn extcodecopy<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn extcodecopy<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
 ) -> Control<Tr> {
@@ -154,7 +208,7 @@ pub fn extcodecopy<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacken
 	pop_u256!(machine, memory_offset, code_offset, len);
 	try_or_fail!(machine.memory.resize_offset(memory_offset, len));
 
-	let code = handler.code(address.into());
+	let code = handler.code(A::from_word(address));
 	match machine
 		.memory
 		.copy_large(memory_offset, code_offset, len, &code)
@@ -166,14 +220,18 @@ pub fn extcodecopy<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBacken
 	Control::Continue
 }
 
-pub fn returndatasize<S: AsRef<RuntimeState>, Tr>(machine: &mut Machine<S>) -> Control<Tr> {
+pub fn returndatasize<A: Address, S: AsRef<RuntimeState<A>>, Tr>(
+	machine: &mut Machine<S>,
+) -> Control<Tr> {
 	let size = U256::from(machine.state.as_ref().retbuf.len());
 	push_u256!(machine, size);
 
 	Control::Continue
 }
 
-pub fn returndatacopy<S: AsRef<RuntimeState>, Tr>(machine: &mut Machine<S>) -> Control<Tr> {
+pub fn returndatacopy<A: Address, S: AsRef<RuntimeState<A>>, Tr>(
+	machine: &mut Machine<S>,
+) -> Control<Tr> {
 	pop_u256!(machine, memory_offset, data_offset, len);
 
 	try_or_fail!(machine.memory.resize_offset(memory_offset, len));
@@ -194,25 +252,40 @@ pub fn returndatacopy<S: AsRef<RuntimeState>, Tr>(machine: &mut Machine<S>) -> C
 	}
 }
 
-pub fn blockhash<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn blockhash<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &H,
 ) -> Control<Tr> {
 	pop_u256!(machine, number);
-	push!(machine, handler.block_hash(number));
+	push!(machine, recent_block_hash(handler, number));
 
 	Control::Continue
 }
 
-pub fn coinbase<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn coinbase<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &H,
 ) -> Control<Tr> {
-	push!(machine, handler.block_coinbase().into());
+	push!(machine, handler.block_coinbase().to_word());
 	Control::Continue
 }
 
-pub fn timestamp<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn timestamp<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &H,
 ) -> Control<Tr> {
@@ -220,7 +293,12 @@ pub fn timestamp<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend,
 	Control::Continue
 }
 
-pub fn number<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn number<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &H,
 ) -> Control<Tr> {
@@ -228,7 +306,12 @@ pub fn number<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr
 	Control::Continue
 }
 
-pub fn difficulty<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn difficulty<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &H,
 ) -> Control<Tr> {
@@ -236,10 +319,21 @@ pub fn difficulty<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend
 	Control::Continue
 }
 
-pub fn prevrandao<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn prevrandao<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &H,
 ) -> Control<Tr> {
+	if !machine.state.as_ref().transaction_context.prevrandao {
+		return difficulty(machine, handler);
+	}
+
+	// Environments without the randomness, for example of chains that never
+	// had a beacon chain, still get the difficulty.
 	if let Some(rand) = handler.block_randomness() {
 		push!(machine, rand);
 		Control::Continue
@@ -248,7 +342,12 @@ pub fn prevrandao<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend
 	}
 }
 
-pub fn gaslimit<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn gaslimit<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &H,
 ) -> Control<Tr> {
@@ -256,7 +355,12 @@ pub fn gaslimit<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend,
 	Control::Continue
 }
 
-pub fn sload<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn sload<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
 ) -> Control<Tr> {
@@ -267,7 +371,12 @@ pub fn sload<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>
 	Control::Continue
 }
 
-pub fn sstore<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn sstore<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
 ) -> Control<Tr> {
@@ -279,16 +388,21 @@ pub fn sstore<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr
 	}
 }
 
-pub fn gas<S: GasState, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn gas<A: Address, S: GasState, H: RuntimeEnvironment<A> + RuntimeBackend<A>, Tr>(
 	machine: &mut Machine<S>,
 	_handler: &H,
 ) -> Control<Tr> {
-	push_u256!(machine, machine.state.gas());
+	push_u256!(machine, U256::from(machine.state.gas()));
 
 	Control::Continue
 }
 
-pub fn tload<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn tload<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
 ) -> Control<Tr> {
@@ -299,7 +413,12 @@ pub fn tload<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>
 	Control::Continue
 }
 
-pub fn tstore<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn tstore<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
 ) -> Control<Tr> {
@@ -310,7 +429,12 @@ pub fn tstore<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr
 	}
 }
 
-pub fn log<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn log<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	n: u8,
 	handler: &mut H,
@@ -347,7 +471,12 @@ pub fn log<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
 	}
 }
 
-pub fn suicide<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, Tr>(
+pub fn suicide<
+	A: Address,
+	S: AsRef<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
 ) -> Control<Tr> {
@@ -358,7 +487,7 @@ pub fn suicide<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, T
 
 		handler.transfer(Transfer {
 			source: address,
-			target: (*target).into(),
+			target: A::from_word(*target),
 			value: balance,
 		})?;
 
@@ -371,3 +500,75 @@ pub fn suicide<S: AsRef<RuntimeState>, H: RuntimeEnvironment + RuntimeBackend, T
 		Err(e) => Control::Exit(Err(e)),
 	}
 }
+
+/// Magic prefix of the `AUTH` message (EIP-3074).
+const AUTH_MAGIC: u8 = 0x04;
+
+/// Half of the secp256k1 curve order. Signatures with a greater `s` are
+/// malleable, and rejected by `AUTH`.
+const SECP256K1N_HALF: H256 = H256([
+	0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+	0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+]);
+
+pub fn auth<
+	A: Address,
+	S: AsRef<RuntimeState<A>> + AsMut<RuntimeState<A>>,
+	H: RuntimeEnvironment<A> + RuntimeBackend<A>,
+	Tr,
+>(
+	machine: &mut Machine<S>,
+	handler: &H,
+) -> Control<Tr> {
+	pop!(machine, authority);
+	pop_u256!(machine, offset, len);
+
+	try_or_fail!(machine.memory.resize_offset(offset, len));
+	// `y_parity || r || s || commit`, padded with zeros.
+	let mut input = [0u8; 97];
+	if len != U256::zero() {
+		let offset = as_usize_or_fail!(offset);
+		let len = as_usize_or_fail!(len);
+
+		let data = machine.memory.get(offset, min(len, input.len()));
+		input[..data.len()].copy_from_slice(&data);
+	}
+
+	let authority = A::from_word(authority);
+	let y_parity = input[0];
+	let r = H256::from_slice(&input[1..33]);
+	let s = H256::from_slice(&input[33..65]);
+
+	let authorized =
+		if y_parity <= 1 && s <= SECP256K1N_HALF && handler.code_size(authority).is_zero() {
+			let mut message = [0u8; 129];
+			message[0] = AUTH_MAGIC;
+			machine
+				.state
+				.as_ref()
+				.transaction_context
+				.chain_id
+				.to_big_endian(&mut message[1..33]);
+			handler.nonce(authority).to_big_endian(&mut message[33..65]);
+			message[65..97].copy_from_slice(&machine.state.as_ref().context.address.to_word()[..]);
+			message[97..129].copy_from_slice(&input[65..97]);
+
+			handler
+				.ecrecover(handler.keccak256(&message), y_parity, r, s)
+				.filter(|signer| *signer == authority)
+		} else {
+			None
+		};
+
+	machine.state.as_mut().authorized = authorized;
+	push_u256!(
+		machine,
+		if authorized.is_some() {
+			U256::one()
+		} else {
+			U256::zero()
+		}
+	);
+
+	Control::Continue
+}
@@ -0,0 +1,326 @@
+//! 256-bit word type used by the arithmetic and bitwise opcodes.
+//!
+//! By default this is [primitive_types::U256]. With the `ruint` feature, it is
+//! `ruint::aliases::U256` instead. Stack values and all public interfaces
+//! stay [primitive_types::H256] and [primitive_types::U256] either way. Only
+//! the opcode implementations below switch backend.
+
+#[cfg_attr(feature = "ruint", allow(dead_code))]
+mod primitive {
+	use primitive_types::{H256, U256};
+
+	use crate::eval::{arithmetic, bitwise};
+
+	pub type Word = U256;
+
+	#[inline]
+	pub fn from_h256(value: &H256) -> Word {
+		U256::from_big_endian(&value[..])
+	}
+
+	#[inline]
+	pub fn to_h256(value: Word) -> H256 {
+		let mut ret = H256::default();
+		value.to_big_endian(&mut ret[..]);
+		ret
+	}
+
+	#[inline]
+	fn from_bool(value: bool) -> Word {
+		if value {
+			U256::one()
+		} else {
+			U256::zero()
+		}
+	}
+
+	#[inline]
+	pub fn add(op1: Word, op2: Word) -> Word {
+		op1.overflowing_add(op2).0
+	}
+
+	#[inline]
+	pub fn mul(op1: Word, op2: Word) -> Word {
+		op1.overflowing_mul(op2).0
+	}
+
+	#[inline]
+	pub fn sub(op1: Word, op2: Word) -> Word {
+		op1.overflowing_sub(op2).0
+	}
+
+	#[inline]
+	pub fn div(op1: Word, op2: Word) -> Word {
+		arithmetic::div(op1, op2)
+	}
+
+	#[inline]
+	pub fn rem(op1: Word, op2: Word) -> Word {
+		arithmetic::rem(op1, op2)
+	}
+
+	#[inline]
+	pub fn addmod(op1: Word, op2: Word, op3: Word) -> Word {
+		arithmetic::addmod(op1, op2, op3)
+	}
+
+	#[inline]
+	pub fn mulmod(op1: Word, op2: Word, op3: Word) -> Word {
+		arithmetic::mulmod(op1, op2, op3)
+	}
+
+	#[inline]
+	pub fn exp(op1: Word, op2: Word) -> Word {
+		arithmetic::exp(op1, op2)
+	}
+
+	#[inline]
+	pub fn lt(op1: Word, op2: Word) -> Word {
+		from_bool(op1 < op2)
+	}
+
+	#[inline]
+	pub fn gt(op1: Word, op2: Word) -> Word {
+		from_bool(op1 > op2)
+	}
+
+	#[inline]
+	pub fn eq(op1: Word, op2: Word) -> Word {
+		from_bool(op1 == op2)
+	}
+
+	#[inline]
+	pub fn iszero(op1: Word) -> Word {
+		bitwise::iszero(op1)
+	}
+
+	#[inline]
+	pub fn and(op1: Word, op2: Word) -> Word {
+		op1 & op2
+	}
+
+	#[inline]
+	pub fn or(op1: Word, op2: Word) -> Word {
+		op1 | op2
+	}
+
+	#[inline]
+	pub fn xor(op1: Word, op2: Word) -> Word {
+		op1 ^ op2
+	}
+
+	#[inline]
+	pub fn not(op1: Word) -> Word {
+		bitwise::not(op1)
+	}
+
+	#[inline]
+	pub fn shl(shift: Word, value: Word) -> Word {
+		bitwise::shl(shift, value)
+	}
+
+	#[inline]
+	pub fn shr(shift: Word, value: Word) -> Word {
+		bitwise::shr(shift, value)
+	}
+}
+
+#[cfg(feature = "ruint")]
+mod ruint_backend {
+	use primitive_types::H256;
+
+	pub type Word = ruint::aliases::U256;
+
+	const ONE: Word = Word::from_limbs([1, 0, 0, 0]);
+	const BITS: Word = Word::from_limbs([256, 0, 0, 0]);
+
+	#[inline]
+	pub fn from_h256(value: &H256) -> Word {
+		Word::from_be_bytes(value.0)
+	}
+
+	#[inline]
+	pub fn to_h256(value: Word) -> H256 {
+		H256(value.to_be_bytes::<32>())
+	}
+
+	#[inline]
+	fn from_bool(value: bool) -> Word {
+		if value {
+			ONE
+		} else {
+			Word::ZERO
+		}
+	}
+
+	#[inline]
+	pub fn add(op1: Word, op2: Word) -> Word {
+		op1.wrapping_add(op2)
+	}
+
+	#[inline]
+	pub fn mul(op1: Word, op2: Word) -> Word {
+		op1.wrapping_mul(op2)
+	}
+
+	#[inline]
+	pub fn sub(op1: Word, op2: Word) -> Word {
+		op1.wrapping_sub(op2)
+	}
+
+	#[inline]
+	pub fn div(op1: Word, op2: Word) -> Word {
+		op1.checked_div(op2).unwrap_or(Word::ZERO)
+	}
+
+	#[inline]
+	pub fn rem(op1: Word, op2: Word) -> Word {
+		op1.checked_rem(op2).unwrap_or(Word::ZERO)
+	}
+
+	#[inline]
+	pub fn addmod(op1: Word, op2: Word, op3: Word) -> Word {
+		op1.add_mod(op2, op3)
+	}
+
+	#[inline]
+	pub fn mulmod(op1: Word, op2: Word, op3: Word) -> Word {
+		op1.mul_mod(op2, op3)
+	}
+
+	#[inline]
+	pub fn exp(op1: Word, op2: Word) -> Word {
+		op1.wrapping_pow(op2)
+	}
+
+	#[inline]
+	pub fn lt(op1: Word, op2: Word) -> Word {
+		from_bool(op1 < op2)
+	}
+
+	#[inline]
+	pub fn gt(op1: Word, op2: Word) -> Word {
+		from_bool(op1 > op2)
+	}
+
+	#[inline]
+	pub fn eq(op1: Word, op2: Word) -> Word {
+		from_bool(op1 == op2)
+	}
+
+	#[inline]
+	pub fn iszero(op1: Word) -> Word {
+		from_bool(op1 == Word::ZERO)
+	}
+
+	#[inline]
+	pub fn and(op1: Word, op2: Word) -> Word {
+		op1 & op2
+	}
+
+	#[inline]
+	pub fn or(op1: Word, op2: Word) -> Word {
+		op1 | op2
+	}
+
+	#[inline]
+	pub fn xor(op1: Word, op2: Word) -> Word {
+		op1 ^ op2
+	}
+
+	#[inline]
+	pub fn not(op1: Word) -> Word {
+		!op1
+	}
+
+	#[inline]
+	pub fn shl(shift: Word, value: Word) -> Word {
+		if shift >= BITS {
+			Word::ZERO
+		} else {
+			value << shift.as_limbs()[0] as usize
+		}
+	}
+
+	#[inline]
+	pub fn shr(shift: Word, value: Word) -> Word {
+		if shift >= BITS {
+			Word::ZERO
+		} else {
+			value >> shift.as_limbs()[0] as usize
+		}
+	}
+}
+
+#[cfg(not(feature = "ruint"))]
+pub use self::primitive::*;
+#[cfg(feature = "ruint")]
+pub use self::ruint_backend::*;
+
+#[cfg(all(test, feature = "ruint"))]
+mod tests {
+	use primitive_types::H256;
+	use sha3::Digest;
+
+	use super::{primitive, ruint_backend};
+
+	fn values() -> Vec<H256> {
+		let mut values = vec![
+			H256::zero(),
+			H256::from_low_u64_be(1),
+			H256::from_low_u64_be(2),
+			H256::from_low_u64_be(255),
+			H256::from_low_u64_be(256),
+			H256::from_low_u64_be(u64::MAX),
+			H256::repeat_byte(0xff),
+			H256::repeat_byte(0x7f),
+		];
+		let mut seed = H256::from_low_u64_be(0x5eed);
+		for _ in 0..8 {
+			seed = H256::from_slice(&sha3::Keccak256::digest(seed.as_bytes()));
+			values.push(seed);
+		}
+		values
+	}
+
+	macro_rules! assert_same {
+		($op:ident, $($arg:ident),+) => {
+			assert_eq!(
+				primitive::to_h256(primitive::$op($(primitive::from_h256(&$arg)),+)),
+				ruint_backend::to_h256(ruint_backend::$op($(ruint_backend::from_h256(&$arg)),+)),
+				stringify!($op),
+			);
+		};
+	}
+
+	#[test]
+	fn same_as_primitive() {
+		let values = values();
+		for a in &values {
+			assert_same!(iszero, a);
+			assert_same!(not, a);
+
+			for b in &values {
+				assert_same!(add, a, b);
+				assert_same!(mul, a, b);
+				assert_same!(sub, a, b);
+				assert_same!(div, a, b);
+				assert_same!(rem, a, b);
+				assert_same!(exp, a, b);
+				assert_same!(lt, a, b);
+				assert_same!(gt, a, b);
+				assert_same!(eq, a, b);
+				assert_same!(and, a, b);
+				assert_same!(or, a, b);
+				assert_same!(xor, a, b);
+				assert_same!(shl, a, b);
+				assert_same!(shr, a, b);
+
+				for c in &values {
+					assert_same!(addmod, a, b, c);
+					assert_same!(mulmod, a, b, c);
+				}
+			}
+		}
+	}
+}
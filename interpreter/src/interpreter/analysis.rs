@@ -0,0 +1,78 @@
+use alloc::vec::Vec;
+
+use crate::opcode::Opcode;
+
+/// Analysis of code, independent of a machine. Currently the valid jump
+/// destinations.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CodeAnalysis {
+	jumpdests: Vec<bool>,
+}
+
+impl CodeAnalysis {
+	/// Analyze the given code bytes. Immediate data is skipped as sized by
+	/// [Opcode::immediate_size], with EIP-663 as given.
+	#[must_use]
+	pub fn new(code: &[u8], eip_663: bool) -> Self {
+		let mut jumpdests: Vec<bool> = Vec::with_capacity(code.len());
+		jumpdests.resize(code.len(), false);
+
+		let mut i = 0;
+		while i < code.len() {
+			let opcode = Opcode(code[i]);
+			if opcode == Opcode::JUMPDEST {
+				jumpdests[i] = true;
+			}
+			i += 1 + opcode.immediate_size(eip_663);
+		}
+
+		Self { jumpdests }
+	}
+
+	/// Length of the analyzed code.
+	#[must_use]
+	pub fn code_len(&self) -> usize {
+		self.jumpdests.len()
+	}
+
+	/// Returns `true` if the position is a valid jump destination.
+	/// If not, returns `false`.
+	#[must_use]
+	pub fn is_jumpdest(&self, position: usize) -> bool {
+		if position >= self.jumpdests.len() {
+			return false;
+		}
+
+		self.jumpdests[position]
+	}
+
+	/// Positions of the valid jump destinations, in order.
+	pub fn jumpdests(&self) -> impl Iterator<Item = usize> + '_ {
+		self.jumpdests
+			.iter()
+			.enumerate()
+			.filter_map(|(position, valid)| if *valid { Some(position) } else { None })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::CodeAnalysis;
+
+	#[test]
+	fn immediate_jumpdest_bytes_are_not_destinations() {
+		// PUSH1 0x5b, JUMPDEST, DUPN 0x5b, JUMPDEST
+		let code = [0x60, 0x5b, 0x5b, 0xe6, 0x5b, 0x5b];
+
+		let analysis = CodeAnalysis::new(&code, true);
+		assert_eq!(analysis.jumpdests().collect::<alloc::vec::Vec<_>>(), [2, 5]);
+
+		// Without EIP-663, DUPN is a single opcode and its next byte is a
+		// jump destination.
+		let analysis = CodeAnalysis::new(&code, false);
+		assert_eq!(
+			analysis.jumpdests().collect::<alloc::vec::Vec<_>>(),
+			[2, 4, 5]
+		);
+	}
+}
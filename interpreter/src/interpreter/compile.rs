@@ -0,0 +1,378 @@
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+
+use primitive_types::H256;
+use sha3::{Digest, Keccak256};
+
+use crate::{interpreter::CodeAnalysis, opcode::Opcode};
+
+/// Version of the format of [CompiledCode::to_bytes].
+pub const COMPILED_CODE_VERSION: u8 = 1;
+
+/// A basic block of compiled code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CompiledBlock {
+	/// Position of the first opcode.
+	pub start: usize,
+	/// Position after the last opcode.
+	pub end: usize,
+	/// Sum of the static gas of the opcodes of the block, a lower bound of the
+	/// gas the block uses if it runs to the end.
+	pub static_gas: u64,
+	/// The jump destination of the block, if it ends with a push followed by
+	/// a valid jump destination and a jump. The interpreter still checks the
+	/// destination of every jump.
+	pub dest: Option<usize>,
+}
+
+fn opcode_len(opcode: Opcode, eip_663: bool) -> usize {
+	1 + opcode.immediate_size(eip_663)
+}
+
+/// Whether the block ends after the opcode: jumps, exits, and opcodes that
+/// trap into a substack.
+fn is_terminator(opcode: Opcode) -> bool {
+	matches!(
+		opcode,
+		Opcode::STOP
+			| Opcode::JUMP
+			| Opcode::JUMPI
+			| Opcode::RETURN
+			| Opcode::REVERT
+			| Opcode::INVALID
+			| Opcode::SUICIDE
+			| Opcode::CREATE
+			| Opcode::CREATE2
+			| Opcode::CALL
+			| Opcode::CALLCODE
+			| Opcode::DELEGATECALL
+			| Opcode::STATICCALL
+	)
+}
+
+/// Push value as a jump destination, if it fits in `usize`.
+fn push_dest(code: &[u8], position: usize) -> Option<usize> {
+	let n = Opcode(code[position]).is_push()? as usize;
+	let data = code.get((position + 1)..(position + 1 + n))?;
+	let mut dest: usize = 0;
+	for byte in data {
+		dest = dest.checked_mul(256)?.checked_add(*byte as usize)?;
+	}
+	Some(dest)
+}
+
+/// Code compiled into basic blocks, for dispatch with
+/// [crate::EtableInterpreter::with_compiled].
+///
+/// This is an intermediate representation verified during compilation, not
+/// native code. A block is dispatched as a whole when the gas left covers
+/// its static gas, and stepped opcode by opcode otherwise, so that the
+/// result is always the same as the interpreter.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompiledCode {
+	code_hash: H256,
+	code_len: usize,
+	blocks: Vec<CompiledBlock>,
+	starts: Vec<Option<u32>>,
+}
+
+impl CompiledCode {
+	/// Compile the code, with the static gas of each opcode, `None` for
+	/// opcodes whose gas is dynamic, and EIP-663 as given.
+	#[must_use]
+	pub fn new(code: &[u8], static_costs: &[Option<u64>; 256], eip_663: bool) -> Self {
+		let analysis = CodeAnalysis::new(code, eip_663);
+		let mut blocks = Vec::new();
+
+		let mut start = 0;
+		let mut static_gas: u64 = 0;
+		let mut last_push = None;
+		let mut i = 0;
+		while i < code.len() {
+			let opcode = Opcode(code[i]);
+			if opcode == Opcode::JUMPDEST && i != start {
+				blocks.push(CompiledBlock {
+					start,
+					end: i,
+					static_gas,
+					dest: None,
+				});
+				start = i;
+				static_gas = 0;
+			}
+
+			static_gas = static_gas.saturating_add(static_costs[opcode.as_usize()].unwrap_or(0));
+			let next = i + opcode_len(opcode, eip_663);
+
+			if is_terminator(opcode) || next >= code.len() {
+				let dest = match (opcode, last_push) {
+					(Opcode::JUMP, Some(push)) | (Opcode::JUMPI, Some(push)) => {
+						push_dest(code, push).filter(|dest| analysis.is_jumpdest(*dest))
+					}
+					_ => None,
+				};
+				blocks.push(CompiledBlock {
+					start,
+					end: next.min(code.len()),
+					static_gas,
+					dest,
+				});
+				start = next;
+				static_gas = 0;
+			}
+
+			last_push = opcode.is_push().map(|_| i);
+			i = next;
+		}
+
+		Self::from_blocks(
+			H256::from_slice(Keccak256::digest(code).as_slice()),
+			code.len(),
+			blocks,
+		)
+	}
+
+	fn from_blocks(code_hash: H256, code_len: usize, blocks: Vec<CompiledBlock>) -> Self {
+		let mut starts = Vec::with_capacity(code_len);
+		starts.resize(code_len, None);
+		for (index, block) in blocks.iter().enumerate() {
+			starts[block.start] = Some(index as u32);
+		}
+
+		Self {
+			code_hash,
+			code_len,
+			blocks,
+			starts,
+		}
+	}
+
+	/// Hash of the compiled code.
+	pub const fn code_hash(&self) -> H256 {
+		self.code_hash
+	}
+
+	/// Length of the compiled code.
+	#[allow(clippy::len_without_is_empty)]
+	pub const fn len(&self) -> usize {
+		self.code_len
+	}
+
+	/// Blocks of the code, in order.
+	pub fn blocks(&self) -> &[CompiledBlock] {
+		&self.blocks
+	}
+
+	/// The block starting at the given position, if any.
+	pub fn block(&self, position: usize) -> Option<&CompiledBlock> {
+		let index = (*self.starts.get(position)?)?;
+		self.blocks.get(index as usize)
+	}
+
+	/// Encode the compiled code, so that embedders can persist it and skip
+	/// the compilation later with [CompiledCode::from_bytes].
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::with_capacity(1 + 32 + 16 + self.blocks.len() * 32);
+		bytes.push(COMPILED_CODE_VERSION);
+		bytes.extend_from_slice(&self.code_hash[..]);
+		bytes.extend_from_slice(&(self.code_len as u64).to_be_bytes());
+		bytes.extend_from_slice(&(self.blocks.len() as u64).to_be_bytes());
+		for block in &self.blocks {
+			bytes.extend_from_slice(&(block.start as u64).to_be_bytes());
+			bytes.extend_from_slice(&(block.end as u64).to_be_bytes());
+			bytes.extend_from_slice(&block.static_gas.to_be_bytes());
+			bytes.extend_from_slice(
+				&block
+					.dest
+					.map_or(u64::MAX, |dest| dest as u64)
+					.to_be_bytes(),
+			);
+		}
+		bytes
+	}
+
+	/// Decode compiled code encoded by [CompiledCode::to_bytes]. Return
+	/// `None` if the bytes are malformed or of another version.
+	///
+	/// The blocks are not trusted: the interpreter only dispatches a block
+	/// at the start of a decoded instruction of the same code hash, steps
+	/// through it by the decoded instructions, and checks every jump.
+	pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+		let (version, bytes) = bytes.split_first()?;
+		if *version != COMPILED_CODE_VERSION || bytes.len() < 48 {
+			return None;
+		}
+
+		let read = |position: usize| -> Option<u64> {
+			let word = bytes.get(position..(position + 8))?;
+			let mut buf = [0u8; 8];
+			buf.copy_from_slice(word);
+			Some(u64::from_be_bytes(buf))
+		};
+		let to_usize = |value: u64| -> Option<usize> { usize::try_from(value).ok() };
+
+		let code_hash = H256::from_slice(&bytes[..32]);
+		let code_len = to_usize(read(32)?)?;
+		let count = to_usize(read(40)?)?;
+		if bytes.len() != count.checked_mul(32)?.checked_add(48)? {
+			return None;
+		}
+
+		let mut blocks = Vec::with_capacity(count);
+		for index in 0..count {
+			let position = 48 + index * 32;
+			let block = CompiledBlock {
+				start: to_usize(read(position)?)?,
+				end: to_usize(read(position + 8)?)?,
+				static_gas: read(position + 16)?,
+				dest: match read(position + 24)? {
+					u64::MAX => None,
+					dest => Some(to_usize(dest)?),
+				},
+			};
+			if block.start >= block.end || block.end > code_len {
+				return None;
+			}
+			blocks.push(block);
+		}
+
+		Some(Self::from_blocks(code_hash, code_len, blocks))
+	}
+}
+
+/// Cache of compiled code, keyed by code hash. Code is only compiled once it
+/// is hot, after it has been requested a number of times. Until then, it
+/// runs in the interpreter.
+#[derive(Clone, Debug)]
+pub struct CompileCache {
+	threshold: usize,
+	counts: BTreeMap<H256, usize>,
+	compiled: BTreeMap<H256, Arc<CompiledCode>>,
+}
+
+impl CompileCache {
+	/// Create an empty cache, compiling code after it has been requested
+	/// `threshold` times.
+	pub fn new(threshold: usize) -> Self {
+		Self {
+			threshold,
+			counts: BTreeMap::new(),
+			compiled: BTreeMap::new(),
+		}
+	}
+
+	/// Get the compiled code from the cache, or compile and insert it if it
+	/// is hot. Return `None` for cold code.
+	pub fn get_or_compile(
+		&mut self,
+		code: &[u8],
+		static_costs: &[Option<u64>; 256],
+		eip_663: bool,
+	) -> Option<Arc<CompiledCode>> {
+		let code_hash = H256::from_slice(Keccak256::digest(code).as_slice());
+		if let Some(compiled) = self.compiled.get(&code_hash) {
+			return Some(compiled.clone());
+		}
+
+		let count = self.counts.entry(code_hash).or_insert(0);
+		*count += 1;
+		if *count < self.threshold {
+			return None;
+		}
+
+		self.counts.remove(&code_hash);
+		let compiled = Arc::new(CompiledCode::new(code, static_costs, eip_663));
+		self.compiled.insert(code_hash, compiled.clone());
+		Some(compiled)
+	}
+
+	/// Get the compiled code by code hash, if it is cached.
+	pub fn get(&self, code_hash: &H256) -> Option<Arc<CompiledCode>> {
+		self.compiled.get(code_hash).cloned()
+	}
+
+	/// Insert compiled code, for example loaded with
+	/// [CompiledCode::from_bytes].
+	pub fn insert(&mut self, compiled: Arc<CompiledCode>) {
+		self.counts.remove(&compiled.code_hash());
+		self.compiled.insert(compiled.code_hash(), compiled);
+	}
+
+	/// All compiled code, to persist it.
+	pub fn compiled(&self) -> impl Iterator<Item = &Arc<CompiledCode>> {
+		self.compiled.values()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const COSTS: [Option<u64>; 256] = [Some(1); 256];
+
+	#[test]
+	fn splits_blocks() {
+		// PUSH1 0x04, JUMP, INVALID, JUMPDEST, PUSH1 1, PUSH1 0x04, JUMPI, STOP
+		let code = [
+			0x60, 0x04, 0x56, 0xfe, 0x5b, 0x60, 0x01, 0x60, 0x04, 0x57, 0x00,
+		];
+		let compiled = CompiledCode::new(&code, &COSTS, false);
+
+		assert_eq!(
+			compiled.blocks(),
+			&[
+				CompiledBlock {
+					start: 0,
+					end: 3,
+					static_gas: 2,
+					dest: Some(4)
+				},
+				CompiledBlock {
+					start: 3,
+					end: 4,
+					static_gas: 1,
+					dest: None
+				},
+				CompiledBlock {
+					start: 4,
+					end: 10,
+					static_gas: 4,
+					dest: Some(4)
+				},
+				CompiledBlock {
+					start: 10,
+					end: 11,
+					static_gas: 1,
+					dest: None
+				},
+			]
+		);
+		assert_eq!(compiled.block(5), None);
+		assert_eq!(
+			CompiledCode::from_bytes(&compiled.to_bytes()),
+			Some(compiled)
+		);
+	}
+
+	#[test]
+	fn compiles_hot_code() {
+		let mut cache = CompileCache::new(2);
+		let code = [0x60, 0x01, 0x00];
+
+		assert!(cache.get_or_compile(&code, &COSTS, false).is_none());
+		assert!(cache.get_or_compile(&code, &COSTS, false).is_some());
+		assert_eq!(cache.compiled().count(), 1);
+	}
+
+	#[test]
+	fn skips_immediates_as_configured() {
+		// DUPN 0x5b, JUMPDEST, STOP
+		let code = [0xe6, 0x5b, 0x5b, 0x00];
+
+		let compiled = CompiledCode::new(&code, &COSTS, true);
+		assert_eq!(compiled.block(1), None);
+		assert_eq!(compiled.block(2).map(|block| block.end), Some(4));
+
+		let compiled = CompiledCode::new(&code, &COSTS, false);
+		assert_eq!(compiled.block(1).map(|block| block.end), Some(2));
+	}
+}
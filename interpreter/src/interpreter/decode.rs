@@ -0,0 +1,200 @@
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+
+use primitive_types::H256;
+use sha3::{Digest, Keccak256};
+
+use crate::{interpreter::CodeAnalysis, opcode::Opcode};
+
+/// An opcode decoded from the code, together with its immediate data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Instruction {
+	/// The opcode.
+	pub opcode: Opcode,
+	/// Position of the opcode in the code.
+	pub position: usize,
+	/// Immediate data, right-aligned, with the bytes past the end of the code
+	/// as zeros. The push value for push opcodes.
+	pub immediate: H256,
+	/// Static gas of the opcode, `None` if its gas is dynamic.
+	pub static_gas: Option<u64>,
+	/// Stack inputs and outputs of the opcode (see [Opcode::stack_io]).
+	pub stack_io: Option<(u8, u8)>,
+	/// Size of the immediate data in the code (see [Opcode::immediate_size]).
+	pub immediate_size: usize,
+}
+
+impl Instruction {
+	/// Position of the instruction that follows.
+	pub const fn next(&self) -> usize {
+		self.position + 1 + self.immediate_size
+	}
+
+	/// Change of the stack height, if known.
+	pub fn stack_delta(&self) -> Option<i16> {
+		self.stack_io
+			.map(|(inputs, outputs)| outputs as i16 - inputs as i16)
+	}
+}
+
+/// Code decoded into a flat array of instructions, executed by index with
+/// [crate::EtableInterpreter::with_decoded] instead of reading the opcode
+/// bytes at each step.
+#[derive(Clone, Debug)]
+pub struct DecodedCode {
+	code: Vec<u8>,
+	code_hash: H256,
+	analysis: CodeAnalysis,
+	instructions: Vec<Instruction>,
+	indices: Vec<u32>,
+}
+
+impl DecodedCode {
+	/// Decode the code, with the static gas of each opcode, `None` for
+	/// opcodes whose gas is dynamic, and EIP-663 as given.
+	#[must_use]
+	pub fn new(code: &[u8], static_costs: &[Option<u64>; 256], eip_663: bool) -> Self {
+		let mut instructions = Vec::new();
+		let mut indices = Vec::with_capacity(code.len());
+		indices.resize(code.len(), u32::MAX);
+
+		let mut i = 0;
+		while i < code.len() {
+			let opcode = Opcode(code[i]);
+			let size = opcode.immediate_size(eip_663);
+
+			let mut immediate = H256::default();
+			let data = &code[(i + 1).min(code.len())..(i + 1 + size).min(code.len())];
+			immediate[(32 - size)..(32 - size + data.len())].copy_from_slice(data);
+
+			indices[i] = instructions.len() as u32;
+			instructions.push(Instruction {
+				opcode,
+				position: i,
+				immediate,
+				static_gas: static_costs[opcode.as_usize()],
+				stack_io: opcode.stack_io(),
+				immediate_size: size,
+			});
+
+			i += 1 + size;
+		}
+
+		Self {
+			code: code.to_vec(),
+			code_hash: H256::from_slice(Keccak256::digest(code).as_slice()),
+			analysis: CodeAnalysis::new(code, eip_663),
+			instructions,
+			indices,
+		}
+	}
+
+	/// The decoded code.
+	pub fn code(&self) -> &[u8] {
+		&self.code
+	}
+
+	/// Hash of the decoded code.
+	pub const fn code_hash(&self) -> H256 {
+		self.code_hash
+	}
+
+	/// Length of the decoded code.
+	#[allow(clippy::len_without_is_empty)]
+	pub fn len(&self) -> usize {
+		self.indices.len()
+	}
+
+	/// Jump destination analysis of the code, to create an interpreter with
+	/// [crate::EtableInterpreter::new_with_analysis].
+	pub const fn analysis(&self) -> &CodeAnalysis {
+		&self.analysis
+	}
+
+	/// The instructions, in order.
+	pub fn instructions(&self) -> &[Instruction] {
+		&self.instructions
+	}
+
+	/// Index of the instruction at the given position, if an instruction
+	/// starts there.
+	pub fn index(&self, position: usize) -> Option<usize> {
+		match self.indices.get(position) {
+			Some(index) if *index != u32::MAX => Some(*index as usize),
+			_ => None,
+		}
+	}
+}
+
+/// Cache of decoded code, keyed by code hash. The static gas is of the config
+/// the code was first decoded with, so a cache must only be used with one
+/// config.
+#[derive(Clone, Debug, Default)]
+pub struct DecodeCache(BTreeMap<H256, Arc<DecodedCode>>);
+
+impl DecodeCache {
+	/// Create an empty cache.
+	pub fn new() -> Self {
+		Self(BTreeMap::new())
+	}
+
+	/// Get the decoded code from the cache by the hash of the code, for
+	/// example as stored by the backend, or decode and insert it.
+	pub fn get_or_decode(
+		&mut self,
+		code_hash: H256,
+		code: &[u8],
+		static_costs: &[Option<u64>; 256],
+		eip_663: bool,
+	) -> Arc<DecodedCode> {
+		self.0
+			.entry(code_hash)
+			.or_insert_with(|| Arc::new(DecodedCode::new(code, static_costs, eip_663)))
+			.clone()
+	}
+
+	/// Get the decoded code by code hash, if it is cached.
+	pub fn get(&self, code_hash: &H256) -> Option<Arc<DecodedCode>> {
+		self.0.get(code_hash).cloned()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decodes_immediates() {
+		// PUSH2 0x0102, DUP1, ADD, PUSH2 0x03 (truncated)
+		let code = [0x61, 0x01, 0x02, 0x80, 0x01, 0x61, 0x03];
+		let decoded = DecodedCode::new(&code, &[None; 256], false);
+
+		let instructions = decoded.instructions();
+		assert_eq!(instructions.len(), 4);
+		assert_eq!(instructions[0].immediate, H256::from_low_u64_be(0x0102));
+		assert_eq!(instructions[1].stack_delta(), Some(1));
+		assert_eq!(instructions[2].position, 4);
+		assert_eq!(instructions[3].immediate, H256::from_low_u64_be(0x0300));
+		assert_eq!(decoded.index(3), Some(1));
+		assert_eq!(decoded.index(1), None);
+	}
+
+	#[test]
+	fn skips_immediates_as_configured() {
+		// DUPN 0x5b, JUMPDEST
+		let code = [0xe6, 0x5b, 0x5b];
+
+		let decoded = DecodedCode::new(&code, &[None; 256], true);
+		assert_eq!(decoded.instructions().len(), 2);
+		assert_eq!(
+			decoded.instructions()[0].immediate,
+			H256::from_low_u64_be(0x5b)
+		);
+		assert_eq!(decoded.index(1), None);
+		assert!(!decoded.analysis().is_jumpdest(1));
+
+		let decoded = DecodedCode::new(&code, &[None; 256], false);
+		assert_eq!(decoded.instructions().len(), 3);
+		assert_eq!(decoded.index(1), Some(1));
+		assert!(decoded.analysis().is_jumpdest(1));
+	}
+}
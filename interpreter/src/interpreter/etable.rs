@@ -1,19 +1,39 @@
-use alloc::vec::Vec;
+use alloc::{sync::Arc, vec::Vec};
 use core::ops::{Deref, DerefMut};
 
+#[cfg(feature = "fusion")]
+use crate::interpreter::FusedCode;
 use crate::{
 	error::{Capture, ExitError, ExitException, ExitFatal, ExitResult, ExitSucceed},
 	etable::{Control, EtableSet},
-	interpreter::{valids::Valids, Interpreter, RunInterpreter, StepInterpreter},
+	interpreter::{CodeAnalysis, DecodedCode, Interpreter, RunInterpreter, StepInterpreter},
 	machine::{Machine, Stack},
 	opcode::Opcode,
 };
+#[cfg(feature = "compile")]
+use crate::{
+	interpreter::{CompiledBlock, CompiledCode},
+	runtime::GasState,
+};
+
+/// Gas left of a state, for the gas check before a compiled block.
+#[cfg(feature = "compile")]
+type GasFn<S> = fn(&S) -> u64;
+
+/// Compiled code, with the decoded code its blocks are executed as.
+#[cfg(feature = "compile")]
+type Compiled<S> = (Arc<CompiledCode>, Arc<DecodedCode>, GasFn<S>);
 
 pub struct EtableInterpreter<'etable, ES: EtableSet> {
-	valids: Valids,
+	analysis: CodeAnalysis,
 	position: usize,
 	machine: Machine<ES::State>,
 	etable: &'etable ES,
+	decoded: Option<Arc<DecodedCode>>,
+	#[cfg(feature = "fusion")]
+	fused: Option<Arc<FusedCode>>,
+	#[cfg(feature = "compile")]
+	compiled: Option<Compiled<ES::State>>,
 }
 
 impl<'etable, ES: EtableSet> Deref for EtableInterpreter<'etable, ES> {
@@ -39,15 +59,82 @@ where
 		self.position
 	}
 
+	/// Create a new interpreter, analyzing the code without EIP-663. Use
+	/// [Self::new_with_analysis] for code with EIP-663 immediates.
 	pub fn new(machine: Machine<ES::State>, etable: &'etable ES) -> Self {
-		let valids = Valids::new(&machine.code[..]);
+		let analysis = CodeAnalysis::new(&machine.code[..], false);
+		Self::new_with_analysis(machine, etable, analysis)
+	}
 
+	/// Create a new interpreter with the code already analyzed. The analysis
+	/// must be of the machine code.
+	pub fn new_with_analysis(
+		machine: Machine<ES::State>,
+		etable: &'etable ES,
+		analysis: CodeAnalysis,
+	) -> Self {
 		Self {
 			machine,
-			valids,
+			analysis,
 			position: 0,
 			etable,
+			decoded: None,
+			#[cfg(feature = "fusion")]
+			fused: None,
+			#[cfg(feature = "compile")]
+			compiled: None,
+		}
+	}
+
+	/// Execute the decoded instructions by index when the machine is run,
+	/// instead of reading the opcodes from the code. Single stepping always
+	/// reads the code.
+	///
+	/// The decoded code is ignored if it is not of the machine code.
+	#[must_use]
+	pub fn with_decoded(mut self, decoded: Arc<DecodedCode>) -> Self {
+		if decoded.code() == &self.machine.code[..] {
+			self.decoded = Some(decoded);
+		}
+		self
+	}
+
+	/// Use the pre-analyzed code for superinstruction dispatch when the
+	/// machine is run. Single stepping always dispatches opcodes one by one.
+	///
+	/// The analysis must be of the machine code. It is ignored if its length
+	/// does not match.
+	#[cfg(feature = "fusion")]
+	#[must_use]
+	pub fn with_fusion(mut self, fused: Arc<FusedCode>) -> Self {
+		if fused.len() == self.machine.code.len() {
+			self.fused = Some(fused);
+		}
+		self
+	}
+
+	/// Use the compiled code for block dispatch when the machine is run,
+	/// instead of the superinstructions, with the blocks executed as the
+	/// decoded instructions. Single stepping always dispatches opcodes one by
+	/// one.
+	///
+	/// Both are ignored if the decoded code is not of the machine code, or
+	/// the compiled code is not of the same code hash.
+	#[cfg(feature = "compile")]
+	#[must_use]
+	pub fn with_compiled(mut self, compiled: Arc<CompiledCode>, decoded: Arc<DecodedCode>) -> Self
+	where
+		ES::State: GasState,
+	{
+		if decoded.code() == &self.machine.code[..] && compiled.code_hash() == decoded.code_hash() {
+			self.compiled = Some((compiled, decoded, <ES::State as GasState>::gas));
 		}
+		self
+	}
+
+	/// Analysis of the machine code.
+	pub const fn analysis(&self) -> &CodeAnalysis {
+		&self.analysis
 	}
 
 	pub fn deconstruct(self) -> Machine<ES::State> {
@@ -85,6 +172,44 @@ where
 	pub fn peek_opcode(&self) -> Option<Opcode> {
 		self.code.get(self.position).map(|opcode| Opcode(*opcode))
 	}
+
+	/// Apply the control of the opcode at `position`.
+	fn apply_control<Tr>(
+		&mut self,
+		position: usize,
+		control: Control<Tr>,
+	) -> Result<(), Capture<ExitResult, Tr>> {
+		match control {
+			Control::Continue => {
+				self.position = position + 1;
+			}
+			Control::ContinueN(p) => {
+				self.position = position + p;
+			}
+			Control::Exit(e) => {
+				self.position = self.code.len();
+				return Err(Capture::Exit(e));
+			}
+			Control::Jump(p) => {
+				if self.analysis.is_jumpdest(p) {
+					self.position = p;
+				} else {
+					self.position = self.code.len();
+					return Err(Capture::Exit(ExitException::InvalidJump.into()));
+				}
+			}
+			Control::Trap(opcode) => {
+				self.position = position;
+				return Err(Capture::Trap(opcode));
+			}
+		};
+
+		if self.position >= self.code.len() {
+			return Err(Capture::Exit(ExitSucceed::Stopped.into()));
+		}
+
+		Ok(())
+	}
 }
 
 impl<'etable, ES: EtableSet> Interpreter for EtableInterpreter<'etable, ES> {
@@ -98,6 +223,14 @@ impl<'etable, ES: EtableSet> Interpreter for EtableInterpreter<'etable, ES> {
 		&mut self.machine
 	}
 
+	fn position(&self) -> usize {
+		self.position
+	}
+
+	fn peek_opcode(&self) -> Option<Opcode> {
+		self.code.get(self.position).map(|opcode| Opcode(*opcode))
+	}
+
 	fn deconstruct(self) -> (ES::State, Vec<u8>) {
 		(self.machine.state, self.machine.retval)
 	}
@@ -116,6 +249,20 @@ where
 	ES: EtableSet<Handle = H, Trap = Tr>,
 {
 	fn run(&mut self, handle: &mut H) -> Capture<ExitResult, Tr> {
+		#[cfg(feature = "compile")]
+		if let Some((compiled, decoded, gas)) = self.compiled.clone() {
+			return self.run_compiled(&compiled, &decoded, gas, handle);
+		}
+
+		if let Some(decoded) = self.decoded.clone() {
+			return self.run_decoded(&decoded, handle);
+		}
+
+		#[cfg(feature = "fusion")]
+		if let Some(fused) = self.fused.clone() {
+			return self.run_fused(&fused, handle);
+		}
+
 		loop {
 			match self.step(handle) {
 				Ok(()) => (),
@@ -145,32 +292,128 @@ where
 			.etable
 			.eval(&mut self.machine, handle, opcode, self.position);
 
-		match control {
-			Control::Continue => {
-				self.position += 1;
-			}
-			Control::ContinueN(p) => {
-				self.position = position + p;
-			}
-			Control::Exit(e) => {
-				self.position = self.code.len();
-				return Err(Capture::Exit(e));
+		self.apply_control(position, control)
+	}
+}
+
+impl<'etable, H, Tr, ES> EtableInterpreter<'etable, ES>
+where
+	ES: EtableSet<Handle = H, Trap = Tr>,
+{
+	fn run_decoded(&mut self, decoded: &DecodedCode, handle: &mut H) -> Capture<ExitResult, Tr> {
+		let instructions = decoded.instructions();
+		loop {
+			let mut index = match decoded.index(self.position) {
+				Some(index) => index,
+				None => match self.step(handle) {
+					Ok(()) => continue,
+					Err(res) => return res,
+				},
+			};
+
+			// Instructions that continue to the next one are executed by
+			// index. Jumps, exits and traps go through the program counter.
+			let ret = loop {
+				let instruction = &instructions[index];
+				let position = instruction.position;
+				let control = self
+					.etable
+					.eval_instruction(&mut self.machine, handle, instruction);
+
+				let next = match control {
+					Control::Continue => position + 1,
+					Control::ContinueN(n) => position + n,
+					control => break self.apply_control(position, control),
+				};
+				if next != instruction.next() || index + 1 >= instructions.len() {
+					break self.apply_control(position, control);
+				}
+				index += 1;
+			};
+
+			if let Err(res) = ret {
+				return res;
 			}
-			Control::Jump(p) => {
-				if self.valids.is_valid(p) {
-					self.position = p;
-				} else {
-					self.position = self.code.len();
-					return Err(Capture::Exit(ExitException::InvalidJump.into()));
+		}
+	}
+}
+
+#[cfg(feature = "fusion")]
+impl<'etable, H, Tr, ES> EtableInterpreter<'etable, ES>
+where
+	ES: EtableSet<Handle = H, Trap = Tr>,
+{
+	fn run_fused(&mut self, fused: &FusedCode, handle: &mut H) -> Capture<ExitResult, Tr> {
+		loop {
+			let position = self.position;
+			let ret = match fused.get(position) {
+				Some(superinstruction) => {
+					let (offset, control) = self.etable.eval_superinstruction(
+						&mut self.machine,
+						handle,
+						superinstruction,
+						position,
+					);
+					self.apply_control(position + offset, control)
 				}
+				None => self.step(handle),
+			};
+
+			if let Err(res) = ret {
+				return res;
 			}
-			Control::Trap(opcode) => return Err(Capture::Trap(opcode)),
-		};
+		}
+	}
+}
 
-		if self.position >= self.code.len() {
-			return Err(Capture::Exit(ExitSucceed::Stopped.into()));
+#[cfg(feature = "compile")]
+impl<'etable, H, Tr, ES> EtableInterpreter<'etable, ES>
+where
+	ES: EtableSet<Handle = H, Trap = Tr>,
+{
+	fn run_compiled(
+		&mut self,
+		compiled: &CompiledCode,
+		decoded: &DecodedCode,
+		gas: GasFn<ES::State>,
+		handle: &mut H,
+	) -> Capture<ExitResult, Tr> {
+		loop {
+			let ret = match (compiled.block(self.position), decoded.index(self.position)) {
+				(Some(block), Some(index)) if gas(&self.machine.state) >= block.static_gas => {
+					self.run_block(block, decoded, index, handle)
+				}
+				_ => self.step(handle),
+			};
+
+			if let Err(res) = ret {
+				return res;
+			}
 		}
+	}
 
-		Ok(())
+	/// Evaluate the decoded instructions of the block back to back, from the
+	/// one at `index`, until one of them does not continue inside the block.
+	fn run_block(
+		&mut self,
+		block: &CompiledBlock,
+		decoded: &DecodedCode,
+		mut index: usize,
+		handle: &mut H,
+	) -> Result<(), Capture<ExitResult, Tr>> {
+		let instructions = decoded.instructions();
+		loop {
+			let instruction = &instructions[index];
+			let position = instruction.position;
+			let next = instruction.next();
+			match self
+				.etable
+				.eval_instruction(&mut self.machine, handle, instruction)
+			{
+				Control::Continue if position + 1 == next && next < block.end => index += 1,
+				Control::ContinueN(n) if position + n == next && next < block.end => index += 1,
+				control => return self.apply_control(position, control),
+			}
+		}
 	}
 }
@@ -0,0 +1,322 @@
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+
+use primitive_types::H256;
+use sha3::{Digest, Keccak256};
+
+use crate::{interpreter::CodeAnalysis, opcode::Opcode};
+
+/// Maximum number of opcodes in a superinstruction.
+pub const MAX_SUPERINSTRUCTION_OPCODES: usize = 3;
+
+/// Kind of a superinstruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SuperinstructionKind {
+	/// `PUSHn` followed by `JUMP`. The destination is checked during
+	/// analysis.
+	PushJump {
+		/// Jump destination.
+		dest: usize,
+	},
+	/// `PUSHn`, `PUSHm` followed by a binary operation.
+	PushPushBinary {
+		/// The binary operation.
+		op: Opcode,
+	},
+	/// `ISZERO`, `PUSHn` followed by `JUMPI`. The destination is checked
+	/// during analysis.
+	IszeroPushJumpi {
+		/// Jump destination.
+		dest: usize,
+	},
+	/// `DUPn` followed by `SWAPm`.
+	DupSwap,
+}
+
+/// A sequence of opcodes that is dispatched as one unit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Superinstruction {
+	/// Kind of the superinstruction.
+	pub kind: SuperinstructionKind,
+	opcodes: [(Opcode, usize); MAX_SUPERINSTRUCTION_OPCODES],
+	count: usize,
+	len: usize,
+}
+
+impl Superinstruction {
+	fn new(
+		kind: SuperinstructionKind,
+		code: &[u8],
+		position: usize,
+		count: usize,
+		eip_663: bool,
+	) -> Self {
+		let mut opcodes = [(Opcode::STOP, 0); MAX_SUPERINSTRUCTION_OPCODES];
+		let mut offset = 0;
+		for item in opcodes.iter_mut().take(count) {
+			let opcode = Opcode(code[position + offset]);
+			*item = (opcode, offset);
+			offset += opcode_len(opcode, eip_663);
+		}
+
+		Self {
+			kind,
+			opcodes,
+			count,
+			len: offset,
+		}
+	}
+
+	/// The fused opcodes, together with their offsets from the start of the
+	/// superinstruction.
+	pub fn opcodes(&self) -> &[(Opcode, usize)] {
+		&self.opcodes[..self.count]
+	}
+
+	/// The fused opcodes, together with their offsets and the offsets of the
+	/// opcodes that follow them.
+	pub fn steps(&self) -> impl Iterator<Item = (Opcode, usize, usize)> + '_ {
+		self.opcodes()
+			.iter()
+			.enumerate()
+			.map(|(i, (opcode, offset))| {
+				let next = self
+					.opcodes()
+					.get(i + 1)
+					.map_or(self.len, |(_, next)| *next);
+				(*opcode, *offset, next)
+			})
+	}
+
+	/// Length of the superinstruction in bytes, including push data.
+	#[allow(clippy::len_without_is_empty)]
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+
+	/// The jump destination of the superinstruction, if it ends with a jump.
+	pub const fn dest(&self) -> Option<usize> {
+		match self.kind {
+			SuperinstructionKind::PushJump { dest } => Some(dest),
+			SuperinstructionKind::IszeroPushJumpi { dest } => Some(dest),
+			_ => None,
+		}
+	}
+}
+
+fn opcode_len(opcode: Opcode, eip_663: bool) -> usize {
+	1 + opcode.immediate_size(eip_663)
+}
+
+fn is_dup(opcode: Opcode) -> bool {
+	(0x80..=0x8f).contains(&opcode.0)
+}
+
+fn is_swap(opcode: Opcode) -> bool {
+	(0x90..=0x9f).contains(&opcode.0)
+}
+
+fn is_binary(opcode: Opcode) -> bool {
+	[
+		Opcode::ADD,
+		Opcode::MUL,
+		Opcode::SUB,
+		Opcode::DIV,
+		Opcode::LT,
+		Opcode::GT,
+		Opcode::EQ,
+		Opcode::AND,
+		Opcode::OR,
+		Opcode::XOR,
+		Opcode::SHL,
+		Opcode::SHR,
+	]
+	.contains(&opcode)
+}
+
+/// Push value as a jump destination, if it fits in `usize`.
+fn push_dest(code: &[u8], position: usize) -> Option<usize> {
+	let n = Opcode(code[position]).is_push()? as usize;
+	let data = &code[(position + 1)..(position + 1 + n)];
+	let mut dest: usize = 0;
+	for byte in data {
+		dest = dest.checked_mul(256)?.checked_add(*byte as usize)?;
+	}
+	Some(dest)
+}
+
+/// Opcodes starting at `position`, if they are all fully inside the code.
+fn opcodes_at(code: &[u8], position: usize, eip_663: bool) -> [Option<(Opcode, usize)>; 3] {
+	let mut ret = [None; MAX_SUPERINSTRUCTION_OPCODES];
+	let mut offset = 0;
+	for item in &mut ret {
+		let opcode = match code.get(position + offset) {
+			Some(byte) => Opcode(*byte),
+			None => break,
+		};
+		let len = opcode_len(opcode, eip_663);
+		if position + offset + len > code.len() {
+			break;
+		}
+		*item = Some((opcode, offset));
+		offset += len;
+	}
+	ret
+}
+
+/// Pre-analyzed code, with superinstructions at the positions where they
+/// start.
+#[derive(Clone, Debug)]
+pub struct FusedCode {
+	code_hash: H256,
+	superinstructions: Vec<Option<Superinstruction>>,
+}
+
+impl FusedCode {
+	/// Analyze the code and find all fusable sequences, with EIP-663 as
+	/// given.
+	#[must_use]
+	pub fn new(code: &[u8], eip_663: bool) -> Self {
+		let analysis = CodeAnalysis::new(code, eip_663);
+		let mut superinstructions = Vec::with_capacity(code.len());
+		superinstructions.resize(code.len(), None);
+
+		let mut i = 0;
+		while i < code.len() {
+			let opcodes = opcodes_at(code, i, eip_663);
+			let kind = match opcodes {
+				[Some((first, _)), Some((Opcode::JUMP, _)), _] if first.is_push().is_some() => {
+					push_dest(code, i)
+						.filter(|dest| analysis.is_jumpdest(*dest))
+						.map(|dest| (SuperinstructionKind::PushJump { dest }, 2))
+				}
+				[Some((first, _)), Some((second, _)), Some((op, _))]
+					if first.is_push().is_some() && second.is_push().is_some() && is_binary(op) =>
+				{
+					Some((SuperinstructionKind::PushPushBinary { op }, 3))
+				}
+				[Some((Opcode::ISZERO, _)), Some((second, offset)), Some((Opcode::JUMPI, _))]
+					if second.is_push().is_some() =>
+				{
+					push_dest(code, i + offset)
+						.filter(|dest| analysis.is_jumpdest(*dest))
+						.map(|dest| (SuperinstructionKind::IszeroPushJumpi { dest }, 3))
+				}
+				[Some((first, _)), Some((second, _)), _] if is_dup(first) && is_swap(second) => {
+					Some((SuperinstructionKind::DupSwap, 2))
+				}
+				_ => None,
+			};
+
+			if let Some((kind, count)) = kind {
+				superinstructions[i] = Some(Superinstruction::new(kind, code, i, count, eip_663));
+			}
+
+			i += opcode_len(Opcode(code[i]), eip_663);
+		}
+
+		Self {
+			code_hash: H256::from_slice(Keccak256::digest(code).as_slice()),
+			superinstructions,
+		}
+	}
+
+	/// Hash of the analyzed code.
+	pub const fn code_hash(&self) -> H256 {
+		self.code_hash
+	}
+
+	/// Length of the analyzed code.
+	#[allow(clippy::len_without_is_empty)]
+	pub fn len(&self) -> usize {
+		self.superinstructions.len()
+	}
+
+	/// The superinstruction starting at the given position, if any.
+	pub fn get(&self, position: usize) -> Option<&Superinstruction> {
+		self.superinstructions
+			.get(position)
+			.and_then(|superinstruction| superinstruction.as_ref())
+	}
+}
+
+/// Cache of analyzed code, keyed by code hash.
+#[derive(Clone, Debug, Default)]
+pub struct FusionCache(BTreeMap<H256, Arc<FusedCode>>);
+
+impl FusionCache {
+	/// Create an empty cache.
+	pub fn new() -> Self {
+		Self(BTreeMap::new())
+	}
+
+	/// Get the analyzed code from the cache, or analyze and insert it.
+	pub fn get_or_analyze(&mut self, code: &[u8], eip_663: bool) -> Arc<FusedCode> {
+		let code_hash = H256::from_slice(Keccak256::digest(code).as_slice());
+		self.0
+			.entry(code_hash)
+			.or_insert_with(|| Arc::new(FusedCode::new(code, eip_663)))
+			.clone()
+	}
+
+	/// Get the analyzed code by code hash, if it is cached.
+	pub fn get(&self, code_hash: &H256) -> Option<Arc<FusedCode>> {
+		self.0.get(code_hash).cloned()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn analyzes_patterns() {
+		// PUSH1 0x04, JUMP, INVALID, JUMPDEST, PUSH1 1, PUSH1 2, ADD, DUP1,
+		// SWAP1, ISZERO, PUSH1 0x04, JUMPI
+		let code = [
+			0x60, 0x04, 0x56, 0xfe, 0x5b, 0x60, 0x01, 0x60, 0x02, 0x01, 0x80, 0x90, 0x15, 0x60,
+			0x04, 0x57,
+		];
+		let fused = FusedCode::new(&code, false);
+
+		assert_eq!(
+			fused.get(0).map(|s| (s.kind, s.len())),
+			Some((SuperinstructionKind::PushJump { dest: 4 }, 3))
+		);
+		assert_eq!(
+			fused.get(5).map(|s| (s.kind, s.len())),
+			Some((SuperinstructionKind::PushPushBinary { op: Opcode::ADD }, 5))
+		);
+		assert_eq!(
+			fused.get(10).map(|s| (s.kind, s.len())),
+			Some((SuperinstructionKind::DupSwap, 2))
+		);
+		assert_eq!(
+			fused.get(12).map(|s| (s.kind, s.len())),
+			Some((SuperinstructionKind::IszeroPushJumpi { dest: 4 }, 4))
+		);
+		assert_eq!(fused.get(1), None);
+	}
+
+	#[test]
+	fn skips_invalid_destination() {
+		// PUSH1 0x03, JUMP, STOP
+		let fused = FusedCode::new(&[0x60, 0x03, 0x56, 0x00], false);
+		assert_eq!(fused.get(0), None);
+	}
+
+	#[test]
+	fn skips_immediates_as_configured() {
+		// DUPN 0x5b, JUMPDEST, PUSH1 0x01, JUMP
+		let code = [0xe6, 0x5b, 0x5b, 0x60, 0x01, 0x56];
+
+		// The jump is to the immediate byte of DUPN.
+		let fused = FusedCode::new(&code, true);
+		assert_eq!(fused.get(3), None);
+
+		let fused = FusedCode::new(&code, false);
+		assert_eq!(
+			fused.get(3).map(|s| s.kind),
+			Some(SuperinstructionKind::PushJump { dest: 1 })
+		);
+	}
+}
@@ -1,12 +1,28 @@
+mod analysis;
+#[cfg(feature = "compile")]
+mod compile;
+mod decode;
 mod etable;
-mod valids;
+#[cfg(feature = "fusion")]
+mod fusion;
 
 use alloc::vec::Vec;
 
-pub use self::etable::EtableInterpreter;
+#[cfg(feature = "compile")]
+pub use self::compile::{CompileCache, CompiledBlock, CompiledCode, COMPILED_CODE_VERSION};
+#[cfg(feature = "fusion")]
+pub use self::fusion::{
+	FusedCode, FusionCache, Superinstruction, SuperinstructionKind, MAX_SUPERINSTRUCTION_OPCODES,
+};
+pub use self::{
+	analysis::CodeAnalysis,
+	decode::{DecodeCache, DecodedCode, Instruction},
+	etable::EtableInterpreter,
+};
 use crate::{
 	error::{Capture, ExitResult},
 	machine::Machine,
+	opcode::Opcode,
 };
 
 pub trait Interpreter {
@@ -15,6 +31,17 @@ pub trait Interpreter {
 	fn machine(&self) -> &Machine<Self::State>;
 	fn machine_mut(&mut self) -> &mut Machine<Self::State>;
 
+	/// Program counter of the next opcode. The default is zero, for
+	/// interpreters that do not track it.
+	fn position(&self) -> usize {
+		0
+	}
+	/// Next opcode to execute. `None` if the machine has exited, or, as by
+	/// default, if the interpreter does not track it.
+	fn peek_opcode(&self) -> Option<Opcode> {
+		None
+	}
+
 	fn deconstruct(self) -> (Self::State, Vec<u8>);
 	fn advance(&mut self);
 }
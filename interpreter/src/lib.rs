@@ -6,6 +6,7 @@
 
 extern crate alloc;
 
+pub mod disasm;
 pub mod error;
 pub mod etable;
 pub mod eval;
@@ -15,4 +16,13 @@ pub mod opcode;
 pub mod runtime;
 pub mod utils;
 
-pub use self::interpreter::{EtableInterpreter, Interpreter, RunInterpreter, StepInterpreter};
+pub use self::interpreter::{
+	CodeAnalysis, DecodeCache, DecodedCode, EtableInterpreter, Instruction, Interpreter,
+	RunInterpreter, StepInterpreter,
+};
+#[cfg(feature = "compile")]
+pub use self::interpreter::{CompileCache, CompiledBlock, CompiledCode, COMPILED_CODE_VERSION};
+#[cfg(feature = "fusion")]
+pub use self::interpreter::{
+	FusedCode, FusionCache, Superinstruction, SuperinstructionKind, MAX_SUPERINSTRUCTION_OPCODES,
+};
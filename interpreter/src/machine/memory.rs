@@ -65,9 +65,9 @@ impl Memory {
 		self.effective_len = U256::zero();
 	}
 
-	/// Resize the memory, making it cover the memory region of `offset..(offset
-	/// + len)`, with 32 bytes as the step. If the length is zero, this function
-	/// does nothing.
+	/// Resize the memory, making it cover the memory region of
+	/// `offset..(offset + len)`, with 32 bytes as the step. If the length is
+	/// zero, this function does nothing.
 	pub fn resize_offset(&mut self, offset: U256, len: U256) -> Result<(), ExitException> {
 		if len == U256::zero() {
 			return Ok(());
@@ -139,6 +139,17 @@ impl Memory {
 		ret
 	}
 
+	/// View of the memory region at given offset, without copying. The view
+	/// ends at the end of the current memory, so it is shorter than `size` if
+	/// the region goes past it. Unlike [Memory::get], the rest of the region
+	/// is not filled with zeros.
+	#[must_use]
+	pub fn slice(&self, offset: usize, size: usize) -> &[u8] {
+		let start = min(offset, self.data.len());
+		let end = min(offset.saturating_add(size), self.data.len());
+		&self.data[start..end]
+	}
+
 	/// Set memory region at given offset. The offset and value is considered
 	/// untrusted.
 	pub fn set(
@@ -296,6 +307,18 @@ mod tests {
 		assert_eq!(memory.data(), &[1u8, 0u8, 0u8, 1u8, 2u8, 3u8, 4u8].to_vec());
 	}
 
+	#[test]
+	fn test_memory_slice() {
+		let mut memory = Memory::new(100usize);
+		memory.set(3usize, &[1u8, 2u8, 3u8, 4u8], None).unwrap();
+
+		// The view is cut at the end of the current memory.
+		assert_eq!(memory.slice(2usize, 3usize), &[0u8, 1u8, 2u8]);
+		assert_eq!(memory.slice(5usize, 10usize), &[3u8, 4u8]);
+		assert_eq!(memory.slice(10usize, 10usize), &[] as &[u8]);
+		assert_eq!(memory.slice(usize::MAX, usize::MAX), &[] as &[u8]);
+	}
+
 	#[test]
 	fn test_memory_copy_resize() {
 		// Create a new instance of memory
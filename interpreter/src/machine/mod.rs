@@ -1,16 +1,17 @@
 mod memory;
 mod stack;
 
-use alloc::{rc::Rc, vec::Vec};
+use alloc::{sync::Arc, vec::Vec};
 
 pub use self::{memory::Memory, stack::Stack};
+use crate::runtime::RuntimeState;
 
 /// Core execution layer for EVM.
 pub struct Machine<S> {
 	/// Program data.
-	pub(crate) data: Rc<Vec<u8>>,
+	pub(crate) data: Arc<Vec<u8>>,
 	/// Program code.
-	pub(crate) code: Rc<Vec<u8>>,
+	pub(crate) code: Arc<Vec<u8>>,
 	/// Return value. Note the difference between `retbuf`.
 	/// A `retval` holds what's returned by the current machine, with `RETURN` or `REVERT` opcode.
 	/// A `retbuf` holds the buffer of returned value by sub-calls.
@@ -26,8 +27,8 @@ pub struct Machine<S> {
 impl<S> Machine<S> {
 	/// Create a new machine with given code and data.
 	pub fn new(
-		code: Rc<Vec<u8>>,
-		data: Rc<Vec<u8>>,
+		code: Arc<Vec<u8>>,
+		data: Arc<Vec<u8>>,
 		stack_limit: usize,
 		memory_limit: usize,
 		state: S,
@@ -47,6 +48,24 @@ impl<S> Machine<S> {
 		&self.code
 	}
 
+	/// Machine call data.
+	pub fn data(&self) -> &[u8] {
+		&self.data
+	}
+
+	/// Value returned by the machine, with `RETURN` or `REVERT` opcode.
+	pub fn return_value(&self) -> &[u8] {
+		&self.retval
+	}
+
+	/// Buffer of the value returned by the last sub-call.
+	pub fn return_buffer<'a, A: 'a>(&'a self) -> &'a [u8]
+	where
+		S: AsRef<RuntimeState<A>>,
+	{
+		&self.state.as_ref().retbuf
+	}
+
 	/// Whether the machine has empty code.
 	#[must_use]
 	pub fn is_empty(&self) -> bool {
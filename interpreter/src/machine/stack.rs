@@ -86,6 +86,14 @@ impl Stack {
 		&self.data
 	}
 
+	/// The `n` topmost values of the stack, with the top of the stack last.
+	/// The whole stack if it has fewer values.
+	#[inline]
+	#[must_use]
+	pub fn top(&self, n: usize) -> &[H256] {
+		&self.data[self.data.len().saturating_sub(n)..]
+	}
+
 	/// Clear the stack.
 	pub fn clear(&mut self) {
 		self.data.clear();
@@ -1,3 +1,5 @@
+use core::fmt;
+
 /// Opcode enum. One-to-one corresponding to an `u8` value.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[cfg_attr(
@@ -169,6 +171,13 @@ impl Opcode {
 	pub const SWAP15: Opcode = Opcode(0x9e);
 	pub const SWAP16: Opcode = Opcode(0x9f);
 
+	/// `DUPN`, see [EIP-663](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-663.md)
+	pub const DUPN: Opcode = Opcode(0xe6);
+	/// `SWAPN`
+	pub const SWAPN: Opcode = Opcode(0xe7);
+	/// `EXCHANGE`
+	pub const EXCHANGE: Opcode = Opcode(0xe8);
+
 	/// See [EIP-3541](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3541.md)
 	pub const EOFMAGIC: Opcode = Opcode(0xef);
 
@@ -229,6 +238,10 @@ impl Opcode {
 	pub const SELFBALANCE: Opcode = Opcode(0x47);
 	/// `BASEFEE`
 	pub const BASEFEE: Opcode = Opcode(0x48);
+	/// `BLOBHASH`, not executed by the standard Etable.
+	pub const BLOBHASH: Opcode = Opcode(0x49);
+	/// `BLOBBASEFEE`, not executed by the standard Etable.
+	pub const BLOBBASEFEE: Opcode = Opcode(0x4a);
 
 	/// `SLOAD`
 	pub const SLOAD: Opcode = Opcode(0x54);
@@ -261,6 +274,10 @@ impl Opcode {
 	pub const DELEGATECALL: Opcode = Opcode(0xf4);
 	/// `CREATE2`
 	pub const CREATE2: Opcode = Opcode(0xf5);
+	/// `AUTH`
+	pub const AUTH: Opcode = Opcode(0xf6);
+	/// `AUTHCALL`
+	pub const AUTHCALL: Opcode = Opcode(0xf7);
 
 	/// `STATICCALL`
 	pub const STATICCALL: Opcode = Opcode(0xfa);
@@ -292,4 +309,272 @@ impl Opcode {
 	pub const fn as_usize(&self) -> usize {
 		self.0 as usize
 	}
+
+	/// Mnemonic of the opcode. `None` if the opcode is not defined.
+	#[must_use]
+	pub const fn name(&self) -> Option<&'static str> {
+		Some(match *self {
+			Self::STOP => "STOP",
+			Self::ADD => "ADD",
+			Self::MUL => "MUL",
+			Self::SUB => "SUB",
+			Self::DIV => "DIV",
+			Self::SDIV => "SDIV",
+			Self::MOD => "MOD",
+			Self::SMOD => "SMOD",
+			Self::ADDMOD => "ADDMOD",
+			Self::MULMOD => "MULMOD",
+			Self::EXP => "EXP",
+			Self::SIGNEXTEND => "SIGNEXTEND",
+			Self::LT => "LT",
+			Self::GT => "GT",
+			Self::SLT => "SLT",
+			Self::SGT => "SGT",
+			Self::EQ => "EQ",
+			Self::ISZERO => "ISZERO",
+			Self::AND => "AND",
+			Self::OR => "OR",
+			Self::XOR => "XOR",
+			Self::NOT => "NOT",
+			Self::BYTE => "BYTE",
+			Self::SHL => "SHL",
+			Self::SHR => "SHR",
+			Self::SAR => "SAR",
+			Self::CALLDATALOAD => "CALLDATALOAD",
+			Self::CALLDATASIZE => "CALLDATASIZE",
+			Self::CALLDATACOPY => "CALLDATACOPY",
+			Self::CODESIZE => "CODESIZE",
+			Self::CODECOPY => "CODECOPY",
+			Self::POP => "POP",
+			Self::MLOAD => "MLOAD",
+			Self::MSTORE => "MSTORE",
+			Self::MSTORE8 => "MSTORE8",
+			Self::JUMP => "JUMP",
+			Self::JUMPI => "JUMPI",
+			Self::PC => "PC",
+			Self::MSIZE => "MSIZE",
+			Self::JUMPDEST => "JUMPDEST",
+			Self::MCOPY => "MCOPY",
+			Self::PUSH0 => "PUSH0",
+			Self::PUSH1 => "PUSH1",
+			Self::PUSH2 => "PUSH2",
+			Self::PUSH3 => "PUSH3",
+			Self::PUSH4 => "PUSH4",
+			Self::PUSH5 => "PUSH5",
+			Self::PUSH6 => "PUSH6",
+			Self::PUSH7 => "PUSH7",
+			Self::PUSH8 => "PUSH8",
+			Self::PUSH9 => "PUSH9",
+			Self::PUSH10 => "PUSH10",
+			Self::PUSH11 => "PUSH11",
+			Self::PUSH12 => "PUSH12",
+			Self::PUSH13 => "PUSH13",
+			Self::PUSH14 => "PUSH14",
+			Self::PUSH15 => "PUSH15",
+			Self::PUSH16 => "PUSH16",
+			Self::PUSH17 => "PUSH17",
+			Self::PUSH18 => "PUSH18",
+			Self::PUSH19 => "PUSH19",
+			Self::PUSH20 => "PUSH20",
+			Self::PUSH21 => "PUSH21",
+			Self::PUSH22 => "PUSH22",
+			Self::PUSH23 => "PUSH23",
+			Self::PUSH24 => "PUSH24",
+			Self::PUSH25 => "PUSH25",
+			Self::PUSH26 => "PUSH26",
+			Self::PUSH27 => "PUSH27",
+			Self::PUSH28 => "PUSH28",
+			Self::PUSH29 => "PUSH29",
+			Self::PUSH30 => "PUSH30",
+			Self::PUSH31 => "PUSH31",
+			Self::PUSH32 => "PUSH32",
+			Self::DUP1 => "DUP1",
+			Self::DUP2 => "DUP2",
+			Self::DUP3 => "DUP3",
+			Self::DUP4 => "DUP4",
+			Self::DUP5 => "DUP5",
+			Self::DUP6 => "DUP6",
+			Self::DUP7 => "DUP7",
+			Self::DUP8 => "DUP8",
+			Self::DUP9 => "DUP9",
+			Self::DUP10 => "DUP10",
+			Self::DUP11 => "DUP11",
+			Self::DUP12 => "DUP12",
+			Self::DUP13 => "DUP13",
+			Self::DUP14 => "DUP14",
+			Self::DUP15 => "DUP15",
+			Self::DUP16 => "DUP16",
+			Self::SWAP1 => "SWAP1",
+			Self::SWAP2 => "SWAP2",
+			Self::SWAP3 => "SWAP3",
+			Self::SWAP4 => "SWAP4",
+			Self::SWAP5 => "SWAP5",
+			Self::SWAP6 => "SWAP6",
+			Self::SWAP7 => "SWAP7",
+			Self::SWAP8 => "SWAP8",
+			Self::SWAP9 => "SWAP9",
+			Self::SWAP10 => "SWAP10",
+			Self::SWAP11 => "SWAP11",
+			Self::SWAP12 => "SWAP12",
+			Self::SWAP13 => "SWAP13",
+			Self::SWAP14 => "SWAP14",
+			Self::SWAP15 => "SWAP15",
+			Self::SWAP16 => "SWAP16",
+			Self::DUPN => "DUPN",
+			Self::SWAPN => "SWAPN",
+			Self::EXCHANGE => "EXCHANGE",
+			Self::EOFMAGIC => "EOFMAGIC",
+			Self::RETURN => "RETURN",
+			Self::REVERT => "REVERT",
+			Self::INVALID => "INVALID",
+			Self::SHA3 => "SHA3",
+			Self::ADDRESS => "ADDRESS",
+			Self::BALANCE => "BALANCE",
+			Self::ORIGIN => "ORIGIN",
+			Self::CALLER => "CALLER",
+			Self::CALLVALUE => "CALLVALUE",
+			Self::GASPRICE => "GASPRICE",
+			Self::EXTCODESIZE => "EXTCODESIZE",
+			Self::EXTCODECOPY => "EXTCODECOPY",
+			Self::RETURNDATASIZE => "RETURNDATASIZE",
+			Self::RETURNDATACOPY => "RETURNDATACOPY",
+			Self::EXTCODEHASH => "EXTCODEHASH",
+			Self::BLOCKHASH => "BLOCKHASH",
+			Self::COINBASE => "COINBASE",
+			Self::TIMESTAMP => "TIMESTAMP",
+			Self::NUMBER => "NUMBER",
+			Self::DIFFICULTY => "DIFFICULTY",
+			Self::GASLIMIT => "GASLIMIT",
+			Self::CHAINID => "CHAINID",
+			Self::SELFBALANCE => "SELFBALANCE",
+			Self::BASEFEE => "BASEFEE",
+			Self::BLOBHASH => "BLOBHASH",
+			Self::BLOBBASEFEE => "BLOBBASEFEE",
+			Self::SLOAD => "SLOAD",
+			Self::SSTORE => "SSTORE",
+			Self::GAS => "GAS",
+			Self::TLOAD => "TLOAD",
+			Self::TSTORE => "TSTORE",
+			Self::LOG0 => "LOG0",
+			Self::LOG1 => "LOG1",
+			Self::LOG2 => "LOG2",
+			Self::LOG3 => "LOG3",
+			Self::LOG4 => "LOG4",
+			Self::CREATE => "CREATE",
+			Self::CALL => "CALL",
+			Self::CALLCODE => "CALLCODE",
+			Self::DELEGATECALL => "DELEGATECALL",
+			Self::CREATE2 => "CREATE2",
+			Self::AUTH => "AUTH",
+			Self::AUTHCALL => "AUTHCALL",
+			Self::STATICCALL => "STATICCALL",
+			Self::SUICIDE => "SUICIDE",
+			_ => return None,
+		})
+	}
+
+	/// Number of stack items the opcode takes and pushes, as (inputs,
+	/// outputs). `None` if the opcode is not defined, or if the numbers depend
+	/// on its immediate data.
+	#[must_use]
+	pub const fn stack_io(&self) -> Option<(u8, u8)> {
+		Some(match *self {
+			Self::STOP | Self::JUMPDEST => (0, 0),
+			Self::ADD
+			| Self::MUL
+			| Self::SUB
+			| Self::DIV
+			| Self::SDIV
+			| Self::MOD
+			| Self::SMOD
+			| Self::EXP
+			| Self::SIGNEXTEND
+			| Self::LT
+			| Self::GT
+			| Self::SLT
+			| Self::SGT
+			| Self::EQ
+			| Self::AND
+			| Self::OR
+			| Self::XOR
+			| Self::BYTE
+			| Self::SHL
+			| Self::SHR
+			| Self::SAR
+			| Self::SHA3 => (2, 1),
+			Self::ADDMOD | Self::MULMOD => (3, 1),
+			Self::ISZERO
+			| Self::NOT
+			| Self::BALANCE
+			| Self::CALLDATALOAD
+			| Self::EXTCODESIZE
+			| Self::EXTCODEHASH
+			| Self::BLOCKHASH
+			| Self::BLOBHASH
+			| Self::MLOAD
+			| Self::SLOAD
+			| Self::TLOAD => (1, 1),
+			Self::ADDRESS
+			| Self::ORIGIN
+			| Self::CALLER
+			| Self::CALLVALUE
+			| Self::CALLDATASIZE
+			| Self::CODESIZE
+			| Self::GASPRICE
+			| Self::RETURNDATASIZE
+			| Self::COINBASE
+			| Self::TIMESTAMP
+			| Self::NUMBER
+			| Self::DIFFICULTY
+			| Self::GASLIMIT
+			| Self::CHAINID
+			| Self::SELFBALANCE
+			| Self::BASEFEE
+			| Self::BLOBBASEFEE
+			| Self::PC
+			| Self::MSIZE
+			| Self::GAS => (0, 1),
+			Self::CALLDATACOPY | Self::CODECOPY | Self::RETURNDATACOPY | Self::MCOPY => (3, 0),
+			Self::EXTCODECOPY => (4, 0),
+			Self::POP | Self::JUMP | Self::SUICIDE => (1, 0),
+			Self::MSTORE
+			| Self::MSTORE8
+			| Self::SSTORE
+			| Self::TSTORE
+			| Self::JUMPI
+			| Self::RETURN
+			| Self::REVERT => (2, 0),
+			Self::CREATE | Self::AUTH => (3, 1),
+			Self::CREATE2 => (4, 1),
+			Self::CALL | Self::CALLCODE | Self::AUTHCALL => (7, 1),
+			Self::DELEGATECALL | Self::STATICCALL => (6, 1),
+			Self::INVALID => (0, 0),
+			_ if self.0 >= 0x5f && self.0 <= 0x7f => (0, 1),
+			_ if self.0 >= 0x80 && self.0 <= 0x8f => (self.0 - 0x7f, self.0 - 0x7e),
+			_ if self.0 >= 0x90 && self.0 <= 0x9f => (self.0 - 0x8e, self.0 - 0x8e),
+			_ if self.0 >= 0xa0 && self.0 <= 0xa4 => (self.0 - 0x9e, 0),
+			_ => return None,
+		})
+	}
+
+	/// Size of the immediate data that follows the opcode in the code. `DUPN`,
+	/// `SWAPN` and `EXCHANGE` only take immediate data if EIP-663 is enabled,
+	/// otherwise they are single invalid opcodes.
+	#[must_use]
+	pub const fn immediate_size(&self, eip_663: bool) -> usize {
+		match *self {
+			Self::DUPN | Self::SWAPN | Self::EXCHANGE if eip_663 => 1,
+			_ if self.0 >= 0x60 && self.0 <= 0x7f => (self.0 - 0x5f) as usize,
+			_ => 0,
+		}
+	}
+}
+
+impl fmt::Display for Opcode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.name() {
+			Some(name) => f.write_str(name),
+			None => write!(f, "UNKNOWN(0x{:02x})", self.0),
+		}
+	}
 }
@@ -1,184 +1,298 @@
-use alloc::{rc::Rc, vec::Vec};
+use alloc::{sync::Arc, vec::Vec};
+use core::{fmt::Debug, hash::Hash};
 
 use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
 
 use crate::error::ExitError;
 
+/// Account address type. The runtime types, the backend traits, the call and
+/// create traps, the runtime etable and the transaction arguments of the `evm`
+/// crate are generic over it, defaulting to the 20-byte Ethereum address
+/// [H160], so that chains with other account identifiers can reuse them with
+/// their own invoker.
+///
+/// The standard invoker and the backends of the `evm` crate implement
+/// Ethereum, including its address derivation, precompile and system
+/// addresses, and only use [H160].
+///
+/// On the stack, an address is a 32-byte word.
+pub trait Address: Copy + Debug + Default + Eq + Ord + Hash + AsRef<[u8]> + rlp::Encodable {
+	/// Address of a stack word. Bytes that do not fit in the address are
+	/// discarded from the front.
+	fn from_word(word: H256) -> Self;
+	/// Stack word of the address, padded with zeros at the front.
+	fn to_word(self) -> H256;
+}
+
+impl Address for H160 {
+	fn from_word(word: H256) -> Self {
+		word.into()
+	}
+
+	fn to_word(self) -> H256 {
+		self.into()
+	}
+}
+
+impl Address for H256 {
+	fn from_word(word: H256) -> Self {
+		word
+	}
+
+	fn to_word(self) -> H256 {
+		self
+	}
+}
+
 /// Gas state.
 pub trait GasState {
-	fn gas(&self) -> U256;
+	/// Left gas. Gas always fits in `u64`, so that the runtime does not need
+	/// big integer arithmetic for it. Transaction gas limits are converted
+	/// with a check when the transaction starts, and larger limits are
+	/// rejected as out of gas.
+	fn gas(&self) -> u64;
 }
 
 /// Runtime state.
 #[derive(Clone, Debug)]
-pub struct RuntimeState {
+pub struct RuntimeState<A = H160> {
 	/// Runtime context.
-	pub context: Context,
+	pub context: Context<A>,
 	/// Transaction context.
-	pub transaction_context: Rc<TransactionContext>,
+	pub transaction_context: Arc<TransactionContext<A>>,
 	/// Return data buffer.
 	pub retbuf: Vec<u8>,
+	/// Address authorized by `AUTH` for `AUTHCALL`
+	/// ([EIP-3074](https://eips.ethereum.org/EIPS/eip-3074)). Every call frame
+	/// starts without one.
+	pub authorized: Option<A>,
 }
 
-impl AsRef<Self> for RuntimeState {
+impl<A> AsRef<Self> for RuntimeState<A> {
 	fn as_ref(&self) -> &Self {
 		self
 	}
 }
 
-impl AsMut<Self> for RuntimeState {
+impl<A> AsMut<Self> for RuntimeState<A> {
 	fn as_mut(&mut self) -> &mut Self {
 		self
 	}
 }
 
-impl GasState for RuntimeState {
-	fn gas(&self) -> U256 {
-		U256::zero()
+impl<A> GasState for RuntimeState<A> {
+	fn gas(&self) -> u64 {
+		0
 	}
 }
 
 /// Context of the runtime.
 #[derive(Clone, Debug)]
-pub struct Context {
+pub struct Context<A = H160> {
 	/// Execution address.
-	pub address: H160,
+	pub address: A,
 	/// Caller of the EVM.
-	pub caller: H160,
+	pub caller: A,
 	/// Apparent value of the EVM.
 	pub apparent_value: U256,
 }
 
 #[derive(Clone, Debug)]
-pub struct TransactionContext {
+pub struct TransactionContext<A = H160> {
 	/// Gas price.
 	pub gas_price: U256,
 	/// Origin.
-	pub origin: H160,
+	pub origin: A,
+	/// Chain ID, for `CHAINID` and `AUTH`.
+	pub chain_id: U256,
+	/// Whether `DIFFICULTY` is `PREVRANDAO` (EIP-4399).
+	pub prevrandao: bool,
 }
 
 /// Transfer from source to target, with given value.
 #[derive(Clone, Debug)]
-pub struct Transfer {
+pub struct Transfer<A = H160> {
 	/// Source address.
-	pub source: H160,
+	pub source: A,
 	/// Target address.
-	pub target: H160,
+	pub target: A,
 	/// Transfer value.
 	pub value: U256,
 }
 
 /// Log
 #[derive(Clone, Debug)]
-pub struct Log {
-	pub address: H160,
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub struct Log<A = H160> {
+	pub address: A,
 	pub topics: Vec<H256>,
 	pub data: Vec<u8>,
 }
 
 // Identify if the origin of set_code() comes from a transact or subcall.
 #[derive(Clone, Debug)]
-pub enum SetCodeOrigin {
+pub enum SetCodeOrigin<A = H160> {
 	Transaction,
-	Subcall(H160),
+	Subcall(A),
 }
 
+/// Number of the most recent blocks whose hashes are available to
+/// `BLOCKHASH`.
+pub const BLOCK_HASH_WINDOW: u64 = 256;
+
+/// Provider of the hashes of previous blocks.
 #[auto_impl::auto_impl(&, Box)]
-pub trait RuntimeEnvironment {
-	/// Get environmental block hash.
+pub trait BlockHashProvider {
+	/// Get the hash of the block with the number. This is only called for the
+	/// [BLOCK_HASH_WINDOW] most recent blocks, see [recent_block_hash].
 	fn block_hash(&self, number: U256) -> H256;
+}
+
+/// Hash of the block with the number, as served by `BLOCKHASH`. This is the
+/// zero hash unless the block is one of the [BLOCK_HASH_WINDOW] blocks before
+/// the current one.
+pub fn recent_block_hash<A, H: RuntimeEnvironment<A> + ?Sized>(handler: &H, number: U256) -> H256 {
+	let current = handler.block_number();
+	if number >= current || number.saturating_add(U256::from(BLOCK_HASH_WINDOW)) < current {
+		return H256::default();
+	}
+
+	handler.block_hash(number)
+}
+
+#[auto_impl::auto_impl(&, Box)]
+pub trait RuntimeEnvironment<A = H160>: BlockHashProvider {
 	/// Get environmental block number.
 	fn block_number(&self) -> U256;
 	/// Get environmental coinbase.
-	fn block_coinbase(&self) -> H160;
+	fn block_coinbase(&self) -> A;
 	/// Get environmental block timestamp.
 	fn block_timestamp(&self) -> U256;
 	/// Get environmental block difficulty.
 	fn block_difficulty(&self) -> U256;
-	/// Get environmental block randomness.
+	/// Get environmental block randomness, for `PREVRANDAO` (see EIP-4399).
+	/// `None` before the merge, or on chains without it.
 	fn block_randomness(&self) -> Option<H256>;
+	/// Get the parent beacon block root (EIP-4788). `None` before Cancun.
+	fn block_parent_beacon_root(&self) -> Option<H256>;
 	/// Get environmental gas limit.
 	fn block_gas_limit(&self) -> U256;
 	/// Environmental block base fee.
 	fn block_base_fee_per_gas(&self) -> U256;
-	/// Get environmental chain ID.
-	fn chain_id(&self) -> U256;
+	/// Environmental blob base fee (EIP-4844). Zero before Cancun.
+	fn blob_base_fee(&self) -> U256;
+	/// Get environmental chain ID. The chain ID of the config of the
+	/// standard invoker takes precedence, and this is only read if the config
+	/// does not set one. The default is 1, the chain ID of Ethereum mainnet.
+	fn chain_id(&self) -> U256 {
+		U256::one()
+	}
 }
 
 #[auto_impl::auto_impl(&, Box)]
-pub trait RuntimeBaseBackend {
+pub trait RuntimeBaseBackend<A = H160> {
 	/// Get balance of address.
-	fn balance(&self, address: H160) -> U256;
+	fn balance(&self, address: A) -> U256;
 	/// Get code size of address.
-	fn code_size(&self, address: H160) -> U256 {
+	fn code_size(&self, address: A) -> U256 {
 		U256::from(self.code(address).len())
 	}
 	/// Get code hash of address.
-	fn code_hash(&self, address: H160) -> H256 {
-		H256::from_slice(&Keccak256::digest(&self.code(address)[..]))
+	fn code_hash(&self, address: A) -> H256 {
+		self.keccak256(&self.code(address)[..])
+	}
+	/// Compute the keccak256 hash of data. This is used by the `SHA3` opcode
+	/// and the `CREATE`/`CREATE2` address derivation. Override it to plug in a
+	/// faster implementation, or to memoize results.
+	fn keccak256(&self, data: &[u8]) -> H256 {
+		H256::from_slice(Keccak256::digest(data).as_slice())
+	}
+	/// Recover the signer of a message hash from a secp256k1 signature. This
+	/// is used by the `AUTH` opcode (EIP-3074). The default recovers nothing,
+	/// so that `AUTH` always fails unless it is overridden, for example with
+	/// `evm::standard::signed::ecrecover`.
+	fn ecrecover(&self, _hash: H256, _y_parity: u8, _r: H256, _s: H256) -> Option<A> {
+		None
 	}
 	/// Get code of address.
-	fn code(&self, address: H160) -> Vec<u8>;
+	fn code(&self, address: A) -> Vec<u8>;
 	/// Get storage value of address at index.
-	fn storage(&self, address: H160, index: H256) -> H256;
+	fn storage(&self, address: A, index: H256) -> H256;
 	/// Get transient storage value of address at index.
-	fn transient_storage(&self, address: H160, index: H256) -> H256;
+	fn transient_storage(&self, address: A, index: H256) -> H256;
 
 	/// Check whether an address exists.
-	fn exists(&self, address: H160) -> bool;
+	fn exists(&self, address: A) -> bool;
 
 	/// Get the current nonce of an account.
-	fn nonce(&self, address: H160) -> U256;
+	fn nonce(&self, address: A) -> U256;
+
+	/// Hint that the accounts and storage slots are going to be read, so that
+	/// a database backend can load them in one batch instead of one read at a
+	/// time. The default ignores the hint.
+	fn prefetch(&self, _addresses: &[A], _keys: &[(A, H256)]) {}
 }
 
 /// The distinguish between `RuntimeBaseBackend` and `RuntimeBackend` is for the implementation of
 /// overlays.
-pub trait RuntimeBackend: RuntimeBaseBackend {
+pub trait RuntimeBackend<A = H160>: RuntimeBaseBackend<A> {
 	/// Get original storage value of address at index.
-	fn original_storage(&self, address: H160, index: H256) -> H256;
+	fn original_storage(&self, address: A, index: H256) -> H256;
 	/// Check whether an address has already been deleted.
-	fn deleted(&self, address: H160) -> bool;
+	fn deleted(&self, address: A) -> bool;
 	/// Checks if the address or (address, index) pair has been previously accessed.
-	fn is_cold(&self, address: H160, index: Option<H256>) -> bool;
-	fn is_hot(&self, address: H160, index: Option<H256>) -> bool {
+	fn is_cold(&self, address: A, index: Option<H256>) -> bool;
+	fn is_hot(&self, address: A, index: Option<H256>) -> bool {
 		!self.is_cold(address, index)
 	}
 
 	/// Mark an address or (address, index) pair as hot.
-	fn mark_hot(&mut self, address: H160, index: Option<H256>);
+	fn mark_hot(&mut self, address: A, index: Option<H256>);
 	/// Set storage value of address at index.
-	fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError>;
+	fn set_storage(&mut self, address: A, index: H256, value: H256) -> Result<(), ExitError>;
 	/// Set transient storage value of address at index, transient storage gets discarded after every transaction. (see EIP-1153)
 	fn set_transient_storage(
 		&mut self,
-		address: H160,
+		address: A,
 		index: H256,
 		value: H256,
 	) -> Result<(), ExitError>;
 	/// Create a log owned by address with given topics and data.
-	fn log(&mut self, log: Log) -> Result<(), ExitError>;
+	fn log(&mut self, log: Log<A>) -> Result<(), ExitError>;
 	/// Mark an address to be deleted.
-	fn mark_delete(&mut self, address: H160);
+	fn mark_delete(&mut self, address: A);
 	/// Fully delete storages of an account.
-	fn reset_storage(&mut self, address: H160);
+	fn reset_storage(&mut self, address: A);
 	/// Set code of an account.
 	fn set_code(
 		&mut self,
-		address: H160,
+		address: A,
 		code: Vec<u8>,
-		origin: SetCodeOrigin,
+		origin: SetCodeOrigin<A>,
 	) -> Result<(), ExitError>;
 	/// Reset balance of an account.
-	fn reset_balance(&mut self, address: H160);
-	fn deposit(&mut self, target: H160, value: U256);
-	fn withdrawal(&mut self, source: H160, value: U256) -> Result<(), ExitError>;
+	fn reset_balance(&mut self, address: A);
+	fn deposit(&mut self, target: A, value: U256);
+	fn withdrawal(&mut self, source: A, value: U256) -> Result<(), ExitError>;
 	/// Initiate a transfer.
-	fn transfer(&mut self, transfer: Transfer) -> Result<(), ExitError> {
+	fn transfer(&mut self, transfer: Transfer<A>) -> Result<(), ExitError> {
 		self.withdrawal(transfer.source, transfer.value)?;
 		self.deposit(transfer.target, transfer.value);
 		Ok(())
 	}
 	/// Increase the nonce value.
-	fn inc_nonce(&mut self, address: H160) -> Result<(), ExitError>;
+	fn inc_nonce(&mut self, address: A) -> Result<(), ExitError>;
+	/// Mark an account as touched (see EIP-161). Touches in reverted
+	/// substates are undone. Balance changes, including zero-value ones,
+	/// touch the account.
+	fn touch(&mut self, _address: A) {}
+	/// Take the accounts touched since the last call, to delete the empty
+	/// ones at the end of the transaction.
+	fn take_touched(&mut self) -> Vec<A> {
+		Vec::new()
+	}
 }
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use evm_interpreter::{
 	error::{Capture, ExitSucceed},
@@ -16,7 +16,7 @@ macro_rules! ret_test {
 			let code = hex::decode($code).unwrap();
 			let data = hex::decode($data).unwrap();
 
-			let machine = Machine::new(Rc::new(code), Rc::new(data), 1024, 10000, ());
+			let machine = Machine::new(Arc::new(code), Arc::new(data), 1024, 10000, ());
 			let mut vm = EtableInterpreter::new(machine, &ETABLE);
 			assert_eq!(
 				vm.run(&mut ()),
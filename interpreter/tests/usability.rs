@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use evm_interpreter::{
 	error::{CallCreateTrap, Capture, ExitError, ExitSucceed},
@@ -6,8 +6,8 @@ use evm_interpreter::{
 	machine::Machine,
 	opcode::Opcode,
 	runtime::{
-		Context, Log, RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment, RuntimeState,
-		SetCodeOrigin, TransactionContext,
+		BlockHashProvider, Context, Log, RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment,
+		RuntimeState, SetCodeOrigin, TransactionContext,
 	},
 	EtableInterpreter, RunInterpreter,
 };
@@ -30,7 +30,7 @@ fn etable_wrap() {
 		}
 	});
 
-	let machine = Machine::new(Rc::new(code), Rc::new(data), 1024, 10000, ());
+	let machine = Machine::new(Arc::new(code), Arc::new(data), 1024, 10000, ());
 	let mut vm = EtableInterpreter::new(machine, &wrapped_etable);
 	let result = vm.run(&mut ());
 	assert_eq!(result, Capture::Exit(Ok(ExitSucceed::Returned)));
@@ -60,18 +60,153 @@ fn etable_wrap2() {
 		},
 	);
 
-	let machine = Machine::new(Rc::new(code), Rc::new(data), 1024, 10000, ());
+	let machine = Machine::new(Arc::new(code), Arc::new(data), 1024, 10000, ());
 	let mut vm = EtableInterpreter::new(machine, &wrapped_etable);
 	let result = vm.run(&mut ());
 	assert_eq!(result, Capture::Trap(Opcode(0x50)));
 }
 
+#[cfg(feature = "fusion")]
+#[test]
+fn etable_fusion() {
+	use std::cell::Cell;
+
+	use evm_interpreter::FusedCode;
+
+	let code = hex::decode(CODE1).unwrap();
+	let data = hex::decode(DATA1).unwrap();
+
+	let run = |fused: bool| {
+		let count = Cell::new(0usize);
+		let counted_etable = Etable::<_, _, Opcode>::core().wrap(|f, _opcode_t| {
+			let count = &count;
+			move |machine, handle, opcode, position| {
+				count.set(count.get() + 1);
+				f(machine, handle, opcode, position)
+			}
+		});
+
+		let machine = Machine::new(
+			Arc::new(code.clone()),
+			Arc::new(data.clone()),
+			1024,
+			10000,
+			(),
+		);
+		let mut vm = EtableInterpreter::new(machine, &counted_etable);
+		if fused {
+			vm = vm.with_fusion(Arc::new(FusedCode::new(&code, false)));
+		}
+		let result = vm.run(&mut ());
+		(result, vm.retval.clone(), count.get())
+	};
+
+	let (result, retval, count) = run(true);
+	assert_eq!(result, Capture::Exit(Ok(ExitSucceed::Returned)));
+	assert_eq!(retval, hex::decode(RET1).unwrap());
+	assert_eq!(count, run(false).2);
+}
+
+#[test]
+fn etable_decoded() {
+	use evm_interpreter::DecodedCode;
+
+	let code = hex::decode(CODE1).unwrap();
+	let data = hex::decode(DATA1).unwrap();
+	let etable = Etable::<_, _, Opcode>::core();
+
+	let run = |code: Vec<u8>, decoded: &Arc<DecodedCode>| {
+		let machine = Machine::new(Arc::new(code), Arc::new(data.clone()), 1024, 10000, ());
+		let mut vm = EtableInterpreter::new(machine, &etable).with_decoded(decoded.clone());
+		let result = vm.run(&mut ());
+		(result, vm.retval.clone())
+	};
+
+	let decoded = Arc::new(DecodedCode::new(&code, &[None; 256], false));
+	let (result, retval) = run(code.clone(), &decoded);
+	assert_eq!(result, Capture::Exit(Ok(ExitSucceed::Returned)));
+	assert_eq!(retval, hex::decode(RET1).unwrap());
+
+	// Decoded code of a different code of the same length is ignored.
+	let mut other = code.clone();
+	other[0] = Opcode::STOP.0;
+	let (result, retval) = run(other, &decoded);
+	assert_eq!(result, Capture::Exit(Ok(ExitSucceed::Stopped)));
+	assert!(retval.is_empty());
+}
+
+#[cfg(feature = "compile")]
+#[test]
+fn etable_compiled() {
+	use std::cell::Cell;
+
+	use evm_interpreter::{error::ExitException, runtime::GasState, CompiledCode, DecodedCode};
+
+	struct Gas;
+
+	impl GasState for Gas {
+		fn gas(&self) -> u64 {
+			u64::MAX
+		}
+	}
+
+	let run = |code: &[u8], data: &[u8], compiled: Option<CompiledCode>| {
+		let count = Cell::new(0usize);
+		let counted_etable = Etable::<Gas, _, Opcode>::core().wrap(|f, _opcode_t| {
+			let count = &count;
+			move |machine, handle, opcode, position| {
+				count.set(count.get() + 1);
+				f(machine, handle, opcode, position)
+			}
+		});
+
+		let machine = Machine::new(
+			Arc::new(code.to_vec()),
+			Arc::new(data.to_vec()),
+			1024,
+			10000,
+			Gas,
+		);
+		let mut vm = EtableInterpreter::new(machine, &counted_etable);
+		if let Some(compiled) = compiled {
+			let decoded = DecodedCode::new(code, &[Some(1); 256], false);
+			vm = vm.with_compiled(Arc::new(compiled), Arc::new(decoded));
+		}
+		let result = vm.run(&mut ());
+		(result, vm.retval.clone(), count.get())
+	};
+
+	let code = hex::decode(CODE1).unwrap();
+	let data = hex::decode(DATA1).unwrap();
+	let compiled = CompiledCode::new(&code, &[Some(1); 256], false);
+	let (result, retval, count) = run(&code, &data, Some(compiled));
+	assert_eq!(result, Capture::Exit(Ok(ExitSucceed::Returned)));
+	assert_eq!(retval, hex::decode(RET1).unwrap());
+	assert_eq!((result, retval, count), run(&code, &data, None));
+
+	// PUSH1 0x03, JUMP, STOP, with a persisted jump destination to the STOP
+	// that is not a valid one.
+	let code = [0x60, 0x03, 0x56, 0x00];
+	let mut bytes = CompiledCode::new(&code, &[Some(1); 256], false).to_bytes();
+	bytes[73..81].copy_from_slice(&3u64.to_be_bytes());
+	let compiled = CompiledCode::from_bytes(&bytes).unwrap();
+	assert_eq!(compiled.blocks()[0].dest, Some(3));
+	let (result, _, _) = run(&code, &[], Some(compiled));
+	assert_eq!(
+		result,
+		Capture::Exit(Err(ExitException::InvalidJump.into()))
+	);
+}
+
 pub struct UnimplementedHandler;
 
-impl RuntimeEnvironment for UnimplementedHandler {
+impl BlockHashProvider for UnimplementedHandler {
 	fn block_hash(&self, _number: U256) -> H256 {
 		unimplemented!()
 	}
+}
+
+impl RuntimeEnvironment for UnimplementedHandler {
 	fn block_number(&self) -> U256 {
 		unimplemented!()
 	}
@@ -87,13 +222,16 @@ impl RuntimeEnvironment for UnimplementedHandler {
 	fn block_randomness(&self) -> Option<H256> {
 		unimplemented!()
 	}
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		unimplemented!()
+	}
 	fn block_gas_limit(&self) -> U256 {
 		unimplemented!()
 	}
 	fn block_base_fee_per_gas(&self) -> U256 {
 		unimplemented!()
 	}
-	fn chain_id(&self) -> U256 {
+	fn blob_base_fee(&self) -> U256 {
 		unimplemented!()
 	}
 }
@@ -200,8 +338,8 @@ fn etable_runtime() {
 	let mut handler = UnimplementedHandler;
 
 	let machine = Machine::new(
-		Rc::new(code),
-		Rc::new(data),
+		Arc::new(code),
+		Arc::new(data),
 		1024,
 		10000,
 		RuntimeState {
@@ -213,9 +351,12 @@ fn etable_runtime() {
 			transaction_context: TransactionContext {
 				gas_price: U256::default(),
 				origin: H160::default(),
+				chain_id: U256::default(),
+				prevrandao: false,
 			}
 			.into(),
 			retbuf: Vec::new(),
+			authorized: None,
 		},
 	);
 	let mut vm = EtableInterpreter::new(machine, &RUNTIME_ETABLE);
@@ -224,3 +365,196 @@ fn etable_runtime() {
 	assert_eq!(res, Ok(ExitSucceed::Returned));
 	assert_eq!(vm.retval, hex::decode(RET1).unwrap());
 }
+
+#[test]
+fn interpreter_is_send() {
+	fn assert_send<T: Send>(_: &T) {}
+
+	let machine = Machine::new(
+		Arc::new(Vec::new()),
+		Arc::new(Vec::new()),
+		1024,
+		10000,
+		RuntimeState {
+			context: Context {
+				address: H160::default(),
+				caller: H160::default(),
+				apparent_value: U256::default(),
+			},
+			transaction_context: TransactionContext {
+				gas_price: U256::default(),
+				origin: H160::default(),
+				chain_id: U256::default(),
+				prevrandao: false,
+			}
+			.into(),
+			retbuf: Vec::new(),
+			authorized: None,
+		},
+	);
+	let vm = EtableInterpreter::new(machine, &RUNTIME_ETABLE);
+
+	assert_send(&vm);
+	std::thread::spawn(move || drop(vm)).join().unwrap();
+}
+
+/// Handler of a chain with 32-byte addresses, which only has balances.
+pub struct WideAddressHandler {
+	account: H256,
+}
+
+impl BlockHashProvider for WideAddressHandler {
+	fn block_hash(&self, _number: U256) -> H256 {
+		unimplemented!()
+	}
+}
+
+impl RuntimeEnvironment<H256> for WideAddressHandler {
+	fn block_number(&self) -> U256 {
+		unimplemented!()
+	}
+	fn block_coinbase(&self) -> H256 {
+		unimplemented!()
+	}
+	fn block_timestamp(&self) -> U256 {
+		unimplemented!()
+	}
+	fn block_difficulty(&self) -> U256 {
+		unimplemented!()
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		unimplemented!()
+	}
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		unimplemented!()
+	}
+	fn block_gas_limit(&self) -> U256 {
+		unimplemented!()
+	}
+	fn block_base_fee_per_gas(&self) -> U256 {
+		unimplemented!()
+	}
+	fn blob_base_fee(&self) -> U256 {
+		unimplemented!()
+	}
+}
+
+impl RuntimeBaseBackend<H256> for WideAddressHandler {
+	fn balance(&self, address: H256) -> U256 {
+		if address == self.account {
+			U256::from(42)
+		} else {
+			U256::zero()
+		}
+	}
+	fn code(&self, _address: H256) -> Vec<u8> {
+		unimplemented!()
+	}
+	fn storage(&self, _address: H256, _index: H256) -> H256 {
+		unimplemented!()
+	}
+	fn transient_storage(&self, _address: H256, _index: H256) -> H256 {
+		unimplemented!()
+	}
+	fn exists(&self, _address: H256) -> bool {
+		unimplemented!()
+	}
+	fn nonce(&self, _address: H256) -> U256 {
+		unimplemented!()
+	}
+}
+
+impl RuntimeBackend<H256> for WideAddressHandler {
+	fn original_storage(&self, _address: H256, _index: H256) -> H256 {
+		unimplemented!()
+	}
+	fn deleted(&self, _address: H256) -> bool {
+		unimplemented!()
+	}
+	fn is_cold(&self, _address: H256, _index: Option<H256>) -> bool {
+		unimplemented!()
+	}
+	fn mark_hot(&mut self, _address: H256, _index: Option<H256>) {
+		unimplemented!()
+	}
+	fn set_storage(&mut self, _address: H256, _index: H256, _value: H256) -> Result<(), ExitError> {
+		unimplemented!()
+	}
+	fn set_transient_storage(
+		&mut self,
+		_address: H256,
+		_index: H256,
+		_value: H256,
+	) -> Result<(), ExitError> {
+		unimplemented!()
+	}
+	fn log(&mut self, _log: Log<H256>) -> Result<(), ExitError> {
+		unimplemented!()
+	}
+	fn mark_delete(&mut self, _address: H256) {
+		unimplemented!()
+	}
+	fn reset_storage(&mut self, _address: H256) {
+		unimplemented!()
+	}
+	fn set_code(
+		&mut self,
+		_address: H256,
+		_code: Vec<u8>,
+		_origin: SetCodeOrigin<H256>,
+	) -> Result<(), ExitError> {
+		unimplemented!()
+	}
+	fn reset_balance(&mut self, _address: H256) {
+		unimplemented!()
+	}
+	fn deposit(&mut self, _address: H256, _value: U256) {
+		unimplemented!()
+	}
+	fn withdrawal(&mut self, _address: H256, _value: U256) -> Result<(), ExitError> {
+		unimplemented!()
+	}
+	fn inc_nonce(&mut self, _address: H256) -> Result<(), ExitError> {
+		unimplemented!()
+	}
+}
+
+#[test]
+fn etable_runtime_wide_address() {
+	static WIDE_ETABLE: Etable<RuntimeState<H256>, WideAddressHandler, CallCreateTrap> =
+		Etable::runtime_with_address::<H256>();
+
+	// CALLER BALANCE CALLER PUSH1 0x20 MSTORE PUSH1 0 MSTORE PUSH1 0x40 PUSH1 0 RETURN
+	let code = hex::decode("33313360205260005260406000f3").unwrap();
+	let caller = H256::repeat_byte(0xaa);
+	let mut handler = WideAddressHandler { account: caller };
+
+	let machine = Machine::new(
+		Arc::new(code),
+		Arc::new(Vec::new()),
+		1024,
+		10000,
+		RuntimeState {
+			context: Context {
+				address: H256::default(),
+				caller,
+				apparent_value: U256::default(),
+			},
+			transaction_context: TransactionContext {
+				gas_price: U256::default(),
+				origin: caller,
+				chain_id: U256::default(),
+				prevrandao: false,
+			}
+			.into(),
+			retbuf: Vec::new(),
+			authorized: None,
+		},
+	);
+	let mut vm = EtableInterpreter::new(machine, &WIDE_ETABLE);
+
+	let res = vm.run(&mut handler).exit().unwrap();
+	assert_eq!(res, Ok(ExitSucceed::Returned));
+	assert_eq!(&vm.retval[..32], &H256::from_low_u64_be(42)[..]);
+	assert_eq!(&vm.retval[32..], &caller[..]);
+}
@@ -0,0 +1,406 @@
+//! `BlockchainTests` runner.
+//!
+//! The blocks of a test are imported in order on top of its pre-state with
+//! [evm::standard::block::execute_block], and the gas used and the state root
+//! of each block are compared to its header. Transactions are decoded from
+//! the block RLP, and their senders recovered from their signatures.
+//!
+//! Blocks that the fixture expects to be rejected are not imported. Most of
+//! them are rejected by header and body checks of a client, which are not part
+//! of this crate.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs::File,
+	io::BufReader,
+};
+
+use evm::{
+	backend::OverlayedBackend,
+	standard::{
+		blob,
+		block::{execute_block, BlockTransaction, Ommer, Withdrawal},
+		signed::SignedTransaction,
+		Config, Etable, EtableResolver, Invoker,
+	},
+};
+use evm_precompile::StandardPrecompileSet;
+use primitive_types::U256;
+use rlp::Rlp;
+
+use crate::{
+	error::{Error, TestError},
+	in_memory::{InMemoryBackend, InMemoryEnvironment},
+	run::{fork_config, pre_state},
+	types::{BlockchainTest, Fork, TestBlock, TestBlockHeader, TestCompletionStatus, TestConfig},
+};
+
+/// Run all tests of a `BlockchainTests` json file.
+pub fn run_file(filename: &str, debug: bool) -> Result<TestCompletionStatus, Error> {
+	let tests: BTreeMap<String, BlockchainTest> =
+		serde_json::from_reader(BufReader::new(File::open(filename)?))?;
+	let mut tests_status = TestCompletionStatus::default();
+
+	for (test_name, test) in tests {
+		print!("[{}] {}: ", test.network, test_name);
+		match run_test(&test, debug) {
+			Ok(()) => {
+				tests_status.inc_completed();
+				println!("ok")
+			}
+			Err(Error::UnsupportedFork) => {
+				tests_status.inc_skipped();
+				println!("skipped")
+			}
+			Err(err) => {
+				println!("ERROR: {:?}", err);
+				return Err(err);
+			}
+		}
+	}
+
+	tests_status.print_completion();
+	Ok(tests_status)
+}
+
+/// Run a single blockchain test.
+pub fn run_test(test: &BlockchainTest, debug: bool) -> Result<(), Error> {
+	let fork = test.fork().ok_or(Error::UnsupportedFork)?;
+	let config = fork_config(fork).ok_or(Error::UnsupportedFork)?;
+	let chain_id = TestConfig::chain_id(test.config.as_ref());
+
+	let precompiles = StandardPrecompileSet::new(&config);
+	let genesis = &test.genesis_block_header;
+	let mut backend = InMemoryBackend {
+		environment: block_environment(genesis, fork, &config, chain_id),
+		state: pre_state(&test.pre),
+	};
+	let mut block_hashes = BTreeMap::new();
+	block_hashes.insert(genesis.number, genesis.hash);
+
+	for block in &test.blocks {
+		if block.expect_exception.is_some() {
+			continue;
+		}
+		let header = block
+			.block_header
+			.as_ref()
+			.ok_or(TestError::MissingBlockHeader)?;
+
+		backend.environment = block_environment(header, fork, &config, chain_id);
+		backend.environment.block_hashes = block_hashes.clone();
+		let base_fee = backend.environment.block_base_fee_per_gas;
+
+		let transactions = block_transactions(block, chain_id, base_fee)?;
+		let withdrawals = block
+			.withdrawals
+			.iter()
+			.flatten()
+			.map(|withdrawal| Withdrawal {
+				index: withdrawal.index.as_u64(),
+				validator_index: withdrawal.validator_index.as_u64(),
+				address: withdrawal.address,
+				amount: withdrawal.amount.as_u64(),
+			})
+			.collect::<Vec<_>>();
+		let ommers = block
+			.uncle_headers
+			.iter()
+			.flatten()
+			.map(|ommer| Ommer {
+				coinbase: ommer.coinbase,
+				number: ommer.number,
+			})
+			.collect::<Vec<_>>();
+
+		// The etable is typed by the backend, which borrows the state of this
+		// block only.
+		let gas_etable = Etable::single(evm::standard::eval_gasometer);
+		let exec_etable = Etable::runtime();
+		let etable = (gas_etable, exec_etable);
+		let resolver = EtableResolver::new(&config, &precompiles, &etable);
+		let invoker = Invoker::new(&config, &resolver);
+
+		let mut run_backend = OverlayedBackend::new(&backend, BTreeSet::new());
+		let outcome = execute_block(
+			&invoker,
+			transactions,
+			&withdrawals,
+			&ommers,
+			fork_blob_params(fork).as_ref(),
+			Some(4),
+			&mut run_backend,
+		)?;
+		let changeset = run_backend.deconstruct().1;
+		backend.apply_overlayed(&changeset);
+		for account in backend.state.values_mut() {
+			account.transient_storage.clear();
+		}
+
+		if debug {
+			println!(
+				"block {}: gas used {}, {} transactions",
+				header.number,
+				outcome.gas_used,
+				outcome.values.len()
+			);
+		}
+		if outcome.gas_used != header.gas_used {
+			return Err(TestError::GasUsedMismatch.into());
+		}
+		if crate::hash::state_root(&backend) != header.state_root {
+			return Err(TestError::StateMismatch.into());
+		}
+
+		block_hashes.insert(header.number, header.hash);
+	}
+
+	Ok(())
+}
+
+/// Environment of the block with the header.
+fn block_environment(
+	header: &TestBlockHeader,
+	fork: Fork,
+	config: &Config,
+	chain_id: U256,
+) -> InMemoryEnvironment {
+	let blob_base_fee = match (fork_blob_params(fork), header.excess_blob_gas) {
+		(Some(params), Some(excess_blob_gas)) => {
+			blob::blob_base_fee(excess_blob_gas.as_u64(), &params)
+		}
+		_ => U256::zero(),
+	};
+
+	InMemoryEnvironment {
+		block_hashes: BTreeMap::new(),
+		block_number: header.number,
+		block_coinbase: header.coinbase,
+		block_timestamp: header.timestamp,
+		block_difficulty: header.difficulty,
+		block_randomness: Some(header.mix_hash),
+		block_parent_beacon_root: header.parent_beacon_block_root,
+		block_gas_limit: header.gas_limit,
+		block_base_fee_per_gas: if config.eip_1559_enabled {
+			header.base_fee_per_gas.unwrap_or_default()
+		} else {
+			U256::zero()
+		},
+		blob_base_fee,
+		chain_id,
+	}
+}
+
+/// Blob gas market parameters of the fork.
+fn fork_blob_params(fork: Fork) -> Option<blob::BlobParams> {
+	match fork {
+		Fork::Cancun => Some(blob::BlobParams::cancun()),
+		_ => None,
+	}
+}
+
+/// Decode the transactions of the block RLP, and recover their senders.
+fn block_transactions(
+	block: &TestBlock,
+	chain_id: U256,
+	base_fee: U256,
+) -> Result<Vec<BlockTransaction>, Error> {
+	let rlp = Rlp::new(&block.rlp.0);
+
+	rlp.at(1)?
+		.iter()
+		.map(|item| {
+			// Typed transactions are byte strings of their envelope, and
+			// legacy transactions are lists.
+			let raw = if item.is_list() {
+				item.as_raw()
+			} else {
+				item.data()?
+			};
+			let transaction = SignedTransaction::decode(raw, chain_id)?;
+			Ok(BlockTransaction {
+				transaction_type: transaction.transaction_type,
+				blob_count: transaction.blob_versioned_hashes.len() as u64,
+				args: transaction.into_transact_args(base_fee),
+			})
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+
+	use evm::standard::Config;
+	use k256::ecdsa::SigningKey;
+	use primitive_types::{H160, H256, U256};
+	use rlp::RlpStream;
+	use serde_json::json;
+	use sha3::{Digest, Keccak256};
+
+	use super::{block_environment, run_test};
+	use crate::{
+		error::{Error, TestError},
+		in_memory::{InMemoryAccount, InMemoryBackend},
+		types::{BlockchainTest, Fork},
+	};
+
+	const TO: H160 = H160([0x35; 20]);
+	const COINBASE: H160 = H160([0xcc; 20]);
+	const WITHDRAWAL: H160 = H160([0xee; 20]);
+	const BALANCE: u64 = 1_000_000_000_000_000_000;
+
+	fn keccak(data: &[u8]) -> H256 {
+		H256::from_slice(&Keccak256::digest(data))
+	}
+
+	fn sender(key: &SigningKey) -> H160 {
+		let public = key.verifying_key().to_encoded_point(false);
+		H160::from_slice(&keccak(&public.as_bytes()[1..])[12..])
+	}
+
+	/// A legacy transfer of 1 wei with gas price 10, signed for chain 1.
+	fn transfer(key: &SigningKey) -> Vec<u8> {
+		let fields = |stream: &mut RlpStream| {
+			stream.append(&0u8);
+			stream.append(&10u8);
+			stream.append(&21_000u32);
+			stream.append(&TO);
+			stream.append(&1u8);
+			stream.append(&Vec::<u8>::new());
+		};
+
+		let mut unsigned = RlpStream::new_list(9);
+		fields(&mut unsigned);
+		unsigned.append(&1u8);
+		unsigned.append(&0u8);
+		unsigned.append(&0u8);
+		let (signature, recovery_id) = key
+			.sign_prehash_recoverable(keccak(&unsigned.out()).as_bytes())
+			.unwrap();
+
+		let mut signed = RlpStream::new_list(9);
+		fields(&mut signed);
+		signed.append(&(37 + recovery_id.to_byte()));
+		signed.append(&U256::from_big_endian(&signature.r().to_bytes()));
+		signed.append(&U256::from_big_endian(&signature.s().to_bytes()));
+		signed.out().to_vec()
+	}
+
+	/// The RLP of a block with the transactions. The header is not decoded by
+	/// the runner, and left empty.
+	fn block_rlp(transactions: &[Vec<u8>]) -> String {
+		let mut stream = RlpStream::new_list(4);
+		stream.begin_list(0);
+		stream.begin_list(transactions.len());
+		for transaction in transactions {
+			stream.append_raw(transaction, 1);
+		}
+		stream.begin_list(0);
+		stream.begin_list(0);
+		format!("0x{}", hex::encode(stream.out()))
+	}
+
+	fn header(number: u64, state_root: H256, gas_used: u64) -> serde_json::Value {
+		json!({
+			"baseFeePerGas": "0x07",
+			"coinbase": COINBASE,
+			"difficulty": "0x00",
+			"gasLimit": "0x01c9c380",
+			"gasUsed": format!("{:#x}", gas_used),
+			"hash": H256::from_low_u64_be(number + 1),
+			"mixHash": H256::zero(),
+			"number": format!("{:#x}", number),
+			"stateRoot": state_root,
+			"timestamp": format!("{:#x}", number * 12),
+		})
+	}
+
+	/// A Shanghai chain importing a block with a transfer and a withdrawal,
+	/// followed by an invalid block, with the expected state root of the
+	/// transfer block.
+	fn test(state_root: H256, gas_used: u64) -> BlockchainTest {
+		let key = SigningKey::from_slice(&[0x46; 32]).unwrap();
+		let sender = sender(&key);
+
+		serde_json::from_value(json!({
+			"blocks": [
+				{
+					"blockHeader": header(1, state_root, gas_used),
+					"rlp": block_rlp(&[transfer(&key)]),
+					"uncleHeaders": [],
+					"withdrawals": [{
+						"index": "0x00",
+						"validatorIndex": "0x00",
+						"address": WITHDRAWAL,
+						"amount": "0x02",
+					}],
+				},
+				{
+					"expectException": "TransactionException.NONCE_MISMATCH_TOO_LOW",
+					"rlp": block_rlp(&[transfer(&key)]),
+				},
+			],
+			"genesisBlockHeader": header(0, H256::zero(), 0),
+			"lastblockhash": H256::from_low_u64_be(2),
+			"network": "Shanghai",
+			"pre": {
+				format!("{:?}", sender): {
+					"balance": format!("{:#x}", BALANCE),
+					"code": "0x",
+					"nonce": "0x00",
+					"storage": {},
+				},
+			},
+		}))
+		.unwrap()
+	}
+
+	/// State root after the transfer block, computed from the expected
+	/// balances.
+	fn expected_state_root() -> H256 {
+		let key = SigningKey::from_slice(&[0x46; 32]).unwrap();
+		let account = |balance: u64, nonce: u64| InMemoryAccount {
+			balance: U256::from(balance),
+			nonce: U256::from(nonce),
+			..Default::default()
+		};
+
+		let mut state = BTreeMap::new();
+		state.insert(sender(&key), account(BALANCE - 1 - 21_000 * 10, 1));
+		state.insert(TO, account(1, 0));
+		// The priority fee of 3 per gas, over the base fee.
+		state.insert(COINBASE, account(21_000 * 3, 0));
+		// 2 Gwei.
+		state.insert(WITHDRAWAL, account(2_000_000_000, 0));
+
+		let test = test(H256::zero(), 0);
+		let backend = InMemoryBackend {
+			environment: block_environment(
+				&test.genesis_block_header,
+				Fork::Shanghai,
+				&Config::shanghai(),
+				U256::one(),
+			),
+			state,
+		};
+		crate::hash::state_root(&backend)
+	}
+
+	#[test]
+	fn blocks_are_imported_against_their_headers() {
+		run_test(&test(expected_state_root(), 21_000), false).unwrap();
+	}
+
+	#[test]
+	fn state_root_mismatches_are_reported() {
+		let result = run_test(&test(H256::repeat_byte(0x01), 21_000), false);
+		assert!(matches!(result, Err(Error::Test(TestError::StateMismatch))));
+
+		let result = run_test(&test(expected_state_root(), 42_000), false);
+		assert!(matches!(
+			result,
+			Err(Error::Test(TestError::GasUsedMismatch))
+		));
+	}
+}
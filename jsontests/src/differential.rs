@@ -0,0 +1,478 @@
+//! Differential execution against revm.
+//!
+//! The same test transaction is run through this crate and through revm on
+//! identical pre-state, and the results are compared. The first divergence is
+//! reported together with an opcode trace of this crate's execution. Both
+//! call and create transactions are compared.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs::File,
+	io::BufReader,
+};
+
+use evm::{
+	backend::OverlayedBackend,
+	interpreter::{error::Capture, runtime::GasState, Interpreter},
+	standard::{validate_transaction, Config, Etable, EtableResolver, Invoker, TransactValue},
+};
+use evm_precompile::StandardPrecompileSet;
+use primitive_types::{H160, H256, U256};
+use revm::{
+	db::{CacheDB, EmptyDB},
+	primitives::{
+		AccountInfo, Address, Bytecode, Bytes, ExecutionResult, SpecId, TransactTo, B256,
+		U256 as RU256,
+	},
+};
+
+use crate::{
+	error::Error,
+	in_memory::InMemoryBackend,
+	run::{fork_config, test_args, test_backend},
+	types::{Fork, TestData, TestMulti},
+};
+
+/// A difference between this crate and revm.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Divergence {
+	/// One side considers the transaction invalid, and the other does not.
+	Validity { evm: bool, revm: bool },
+	/// Execution status differs.
+	Status { evm: bool, revm: bool },
+	/// Used gas differs.
+	UsedGas { evm: U256, revm: U256 },
+	/// Return value differs.
+	Output { evm: Vec<u8>, revm: Vec<u8> },
+	/// Logs differ.
+	Logs,
+	/// Balance of the account differs.
+	Balance {
+		address: H160,
+		evm: U256,
+		revm: U256,
+	},
+	/// Nonce of the account differs.
+	Nonce {
+		address: H160,
+		evm: U256,
+		revm: U256,
+	},
+	/// Code of the account differs.
+	Code { address: H160 },
+	/// Storage value differs.
+	Storage {
+		address: H160,
+		index: H256,
+		evm: H256,
+		revm: H256,
+	},
+}
+
+/// A single step of the opcode trace.
+#[derive(Clone, Debug)]
+pub struct TraceStep {
+	pub position: usize,
+	pub opcode: Option<u8>,
+	pub gas: u64,
+}
+
+/// The first divergence and the trace leading to it.
+#[derive(Clone, Debug)]
+pub struct DifferentialReport {
+	pub divergence: Divergence,
+	pub trace: Vec<TraceStep>,
+}
+
+fn spec_id(fork: Fork) -> Option<SpecId> {
+	match fork {
+		Fork::Istanbul => Some(SpecId::ISTANBUL),
+		Fork::Berlin => Some(SpecId::BERLIN),
+		Fork::London => Some(SpecId::LONDON),
+		Fork::Merge => Some(SpecId::MERGE),
+		Fork::Shanghai => Some(SpecId::SHANGHAI),
+		Fork::Cancun => Some(SpecId::CANCUN),
+		_ => None,
+	}
+}
+
+fn to_address(address: H160) -> Address {
+	Address::from_slice(address.as_bytes())
+}
+
+fn from_address(address: Address) -> H160 {
+	H160::from_slice(address.as_slice())
+}
+
+fn to_u256(value: U256) -> RU256 {
+	let mut bytes = [0u8; 32];
+	value.to_big_endian(&mut bytes);
+	RU256::from_be_bytes(bytes)
+}
+
+fn from_u256(value: RU256) -> U256 {
+	U256::from_big_endian(&value.to_be_bytes::<32>())
+}
+
+fn h256_to_ru256(value: H256) -> RU256 {
+	RU256::from_be_bytes(value.0)
+}
+
+fn ru256_to_h256(value: RU256) -> H256 {
+	H256(value.to_be_bytes::<32>())
+}
+
+fn run_evm(
+	test: &TestData,
+	config: &Config,
+	base_backend: &InMemoryBackend,
+) -> (Option<TransactValue>, InMemoryBackend, Vec<TraceStep>) {
+	let gas_etable = Etable::single(evm::standard::eval_gasometer);
+	let exec_etable = Etable::runtime();
+	let etable = (gas_etable, exec_etable);
+	let precompiles = StandardPrecompileSet::new(config);
+	let resolver = EtableResolver::new(config, &precompiles, &etable);
+	let invoker = Invoker::new(config, &resolver);
+	let args = test_args(test);
+
+	let mut trace = Vec::new();
+	let mut backend = OverlayedBackend::new(base_backend, BTreeSet::new());
+	let result = match validate_transaction(&args, &backend, config) {
+		Ok(()) => evm::HeapTransact::new(args, &invoker, &mut backend)
+			.and_then(|mut stepper| loop {
+				if let Some(machine) = stepper.last_interpreter() {
+					trace.push(TraceStep {
+						position: machine.position(),
+						opcode: machine.peek_opcode().map(|opcode| opcode.0),
+						gas: machine.machine().state.gas(),
+					});
+				}
+				if let Err(Capture::Exit(result)) = stepper.step() {
+					break result;
+				}
+			})
+			.ok(),
+		Err(_) => None,
+	};
+
+	let changeset = backend.deconstruct().1;
+	let mut post = base_backend.clone();
+	post.apply_overlayed(&changeset);
+
+	(result, post, trace)
+}
+
+/// Run the test through both this crate and revm, and return the first
+/// divergence, if any.
+pub fn diff_test(test: &TestData) -> Result<Option<DifferentialReport>, Error> {
+	let config = fork_config(test.fork).ok_or(Error::UnsupportedFork)?;
+	let spec_id = spec_id(test.fork).ok_or(Error::UnsupportedFork)?;
+	let base_backend = test_backend(test, &config);
+
+	let (evm_result, evm_post, trace) = run_evm(test, &config, &base_backend);
+	let report = |divergence| {
+		Ok(Some(DifferentialReport {
+			divergence,
+			trace: trace.clone(),
+		}))
+	};
+
+	let mut db = CacheDB::new(EmptyDB::default());
+	for (address, account) in &base_backend.state {
+		let code = Bytecode::new_raw(Bytes::from(account.code.clone()));
+		db.insert_account_info(
+			to_address(*address),
+			AccountInfo {
+				balance: to_u256(account.balance),
+				nonce: account.nonce.as_u64(),
+				code_hash: code.hash_slow(),
+				code: Some(code),
+			},
+		);
+		for (index, value) in &account.storage {
+			db.insert_account_storage(
+				to_address(*address),
+				h256_to_ru256(*index),
+				h256_to_ru256(*value),
+			)
+			.expect("in-memory database is infallible");
+		}
+	}
+
+	let mut revm = revm::new();
+	revm.database(db);
+	revm.env.cfg.spec_id = spec_id;
+	revm.env.cfg.chain_id = test.chain_id.as_u64();
+	revm.env.block.number = to_u256(test.env.current_number);
+	revm.env.block.coinbase = to_address(test.env.current_coinbase);
+	revm.env.block.timestamp = to_u256(test.env.current_timestamp);
+	revm.env.block.gas_limit = to_u256(test.env.current_gas_limit);
+	revm.env.block.basefee = to_u256(base_backend.environment.block_base_fee_per_gas);
+	revm.env.block.difficulty = to_u256(test.env.current_difficulty);
+	revm.env.block.prevrandao = Some(B256::from(test.env.current_random.0));
+	revm.env.tx.caller = to_address(test.transaction.sender);
+	revm.env.tx.gas_limit = test.transaction.gas_limit.as_u64();
+	revm.env.tx.gas_price = to_u256(test.transaction.gas_price);
+	revm.env.tx.transact_to = match test.transaction.to {
+		Some(to) => TransactTo::Call(to_address(to)),
+		None => TransactTo::create(),
+	};
+	revm.env.tx.value = to_u256(test.transaction.value);
+	revm.env.tx.data = Bytes::from(test.transaction.data.clone());
+	revm.env.tx.nonce = Some(test.transaction.nonce.as_u64());
+	revm.env.tx.access_list = test
+		.transaction
+		.access_list
+		.iter()
+		.map(|access| {
+			(
+				to_address(access.address),
+				access
+					.storage_keys
+					.iter()
+					.map(|key| h256_to_ru256(*key))
+					.collect(),
+			)
+		})
+		.collect();
+
+	let revm_result = revm.transact().ok();
+
+	let (evm_value, revm_result) = match (evm_result, revm_result) {
+		(Some(evm_value), Some(revm_result)) => (evm_value, revm_result),
+		(None, None) => return Ok(None),
+		(evm_value, revm_result) => {
+			return report(Divergence::Validity {
+				evm: evm_value.is_some(),
+				revm: revm_result.is_some(),
+			})
+		}
+	};
+
+	let (revm_status, revm_used_gas, revm_output, revm_logs) = match &revm_result.result {
+		ExecutionResult::Success {
+			gas_used,
+			logs,
+			output,
+			..
+		} => (true, *gas_used, output.data().to_vec(), logs.clone()),
+		ExecutionResult::Revert { gas_used, output } => {
+			(false, *gas_used, output.to_vec(), Vec::new())
+		}
+		ExecutionResult::Halt { gas_used, .. } => (false, *gas_used, Vec::new(), Vec::new()),
+	};
+
+	if evm_value.exit.is_ok() != revm_status {
+		return report(Divergence::Status {
+			evm: evm_value.exit.is_ok(),
+			revm: revm_status,
+		});
+	}
+
+	if evm_value.used_gas != U256::from(revm_used_gas) {
+		return report(Divergence::UsedGas {
+			evm: evm_value.used_gas,
+			revm: U256::from(revm_used_gas),
+		});
+	}
+
+	if evm_value.exit.is_ok() && evm_value.retval != revm_output {
+		return report(Divergence::Output {
+			evm: evm_value.retval.clone(),
+			revm: revm_output,
+		});
+	}
+
+	let logs_match = evm_value.logs.len() == revm_logs.len()
+		&& evm_value.logs.iter().zip(revm_logs.iter()).all(|(a, b)| {
+			a.address == from_address(b.address)
+				&& a.topics.len() == b.topics.len()
+				&& a.topics
+					.iter()
+					.zip(b.topics.iter())
+					.all(|(x, y)| x.0 == y.0)
+				&& a.data[..] == b.data[..]
+		});
+	if !logs_match {
+		return report(Divergence::Logs);
+	}
+
+	for (address, account) in &revm_result.state {
+		if !account.is_touched() {
+			continue;
+		}
+
+		let address = from_address(*address);
+		let evm_account = evm_post.state.get(&address).cloned().unwrap_or_default();
+
+		if account.is_selfdestructed() || (account.is_empty() && spec_id >= SpecId::SPURIOUS_DRAGON)
+		{
+			if evm_account.balance != U256::zero() || evm_account.nonce != U256::zero() {
+				return report(Divergence::Balance {
+					address,
+					evm: evm_account.balance,
+					revm: U256::zero(),
+				});
+			}
+			continue;
+		}
+
+		if evm_account.balance != from_u256(account.info.balance) {
+			return report(Divergence::Balance {
+				address,
+				evm: evm_account.balance,
+				revm: from_u256(account.info.balance),
+			});
+		}
+
+		if evm_account.nonce != U256::from(account.info.nonce) {
+			return report(Divergence::Nonce {
+				address,
+				evm: evm_account.nonce,
+				revm: U256::from(account.info.nonce),
+			});
+		}
+
+		if let Some(code) = &account.info.code {
+			if code.original_bytes()[..] != evm_account.code[..] {
+				return report(Divergence::Code { address });
+			}
+		}
+
+		for (index, slot) in &account.storage {
+			let index = ru256_to_h256(*index);
+			let revm_value = ru256_to_h256(slot.present_value());
+			let evm_value = evm_account.storage.get(&index).cloned().unwrap_or_default();
+			if evm_value != revm_value {
+				return report(Divergence::Storage {
+					address,
+					index,
+					evm: evm_value,
+					revm: revm_value,
+				});
+			}
+		}
+	}
+
+	Ok(None)
+}
+
+/// Run differential execution for all tests in the json file, and print the
+/// first divergence of each test.
+pub fn diff_file(filename: &str) -> Result<usize, Error> {
+	let test_multi: BTreeMap<String, TestMulti> =
+		serde_json::from_reader(BufReader::new(File::open(filename)?))?;
+	let mut divergences = 0;
+
+	for (test_name, test_multi) in test_multi {
+		for test in test_multi.tests() {
+			print!("[{:?}] {}/{}: ", test.fork, test_name, test.index);
+			match diff_test(&test) {
+				Ok(None) => println!("ok"),
+				Ok(Some(report)) => {
+					divergences += 1;
+					println!("DIVERGED: {:?}", report.divergence);
+					for step in &report.trace {
+						println!(
+							"pc: {}, opcode: {:?}, gas: 0x{:x}",
+							step.position, step.opcode, step.gas
+						);
+					}
+				}
+				Err(Error::UnsupportedFork) => println!("skipped"),
+				Err(err) => return Err(err),
+			}
+		}
+	}
+
+	Ok(divergences)
+}
+
+#[cfg(test)]
+mod tests {
+	use primitive_types::U256;
+
+	use super::{diff_test, run_evm};
+	use crate::{
+		run::{fork_config, test_backend},
+		types::TestMulti,
+	};
+
+	const CREATE_TEST: &str = r#"{
+		"_info": {
+			"comment": "",
+			"filling-rpc-server": "",
+			"filling-tool-version": "",
+			"generatedTestHash": "",
+			"lllcversion": "",
+			"solidity": "",
+			"source": "",
+			"sourceHash": ""
+		},
+		"env": {
+			"currentBaseFee": "0x07",
+			"currentBeaconRoot": "0x0000000000000000000000000000000000000000000000000000000000000000",
+			"currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+			"currentDifficulty": "0x00",
+			"currentGasLimit": "0x05f5e100",
+			"currentNumber": "0x01",
+			"currentRandom": "0x0000000000000000000000000000000000000000000000000000000000020000",
+			"currentTimestamp": "0x03e8",
+			"currentWithdrawalsRoot": "0x0000000000000000000000000000000000000000000000000000000000000000",
+			"previousHash": "0x0000000000000000000000000000000000000000000000000000000000000000"
+		},
+		"post": {
+			"Cancun": [{
+				"hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+				"indexes": { "data": 0, "gas": 0, "value": 0 },
+				"logs": "0x0000000000000000000000000000000000000000000000000000000000000000",
+				"txbytes": "0x"
+			}]
+		},
+		"pre": {
+			"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b": {
+				"balance": "0x0de0b6b3a7640000",
+				"code": "0x",
+				"nonce": "0x00",
+				"storage": {}
+			}
+		},
+		"transaction": {
+			"data": ["0x60016000526001601ff3"],
+			"gasLimit": ["0x0186a0"],
+			"gasPrice": "0x0a",
+			"nonce": "0x00",
+			"secretKey": "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+			"sender": "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b",
+			"to": "",
+			"value": ["0x01"]
+		}
+	}"#;
+
+	#[test]
+	fn create_transactions_are_diffed() {
+		let test_multi: TestMulti = serde_json::from_str(CREATE_TEST).unwrap();
+		let test = test_multi.tests().remove(0);
+		assert_eq!(test.transaction.to, None);
+
+		let config = fork_config(test.fork).unwrap();
+		let (value, post, _) = run_evm(&test, &config, &test_backend(&test, &config));
+		let address = value.unwrap().create_address.unwrap();
+		assert_eq!(post.state[&address].code, vec![0x01]);
+
+		let report = diff_test(&test).unwrap();
+		assert!(report.is_none(), "{:?}", report.map(|r| r.divergence));
+	}
+
+	#[test]
+	fn creates_below_the_intrinsic_gas_are_invalid_on_both_sides() {
+		let test_multi: TestMulti = serde_json::from_str(CREATE_TEST).unwrap();
+		let mut test = test_multi.tests().remove(0);
+		test.transaction.gas_limit = U256::from(21_000);
+
+		let config = fork_config(test.fork).unwrap();
+		let (value, _, _) = run_evm(&test, &config, &test_backend(&test, &config));
+		assert!(value.is_none());
+		assert!(diff_test(&test).unwrap().is_none());
+	}
+}
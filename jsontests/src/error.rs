@@ -3,8 +3,14 @@
 pub enum TestError {
 	#[error("state root is different")]
 	StateMismatch,
+	#[error("logs hash is different")]
+	LogsMismatch,
 	#[error("expect error, but got okay")]
 	ExpectException,
+	#[error("block gas used is different")]
+	GasUsedMismatch,
+	#[error("valid block without header")]
+	MissingBlockHeader,
 }
 
 #[allow(clippy::upper_case_acronyms)]
@@ -22,4 +28,10 @@ pub enum Error {
 	NonUtf8Filename,
 	#[error("test error")]
 	Test(#[from] TestError),
+	#[error("rlp error")]
+	RLP(#[from] rlp::DecoderError),
+	#[error("transaction error")]
+	Transaction(#[from] evm::standard::signed::SignedTransactionError),
+	#[error("block error")]
+	Block(#[from] evm::standard::block::BlockError),
 }
@@ -1,4 +1,4 @@
-use evm::interpreter::utils::h256_to_u256;
+use evm::interpreter::{runtime::Log, utils::h256_to_u256};
 use primitive_types::{H256, U256};
 use sha3::{Digest, Keccak256};
 
@@ -92,3 +92,15 @@ pub fn state_root(backend: &InMemoryBackend) -> H256 {
 
 	ethereum::util::sec_trie_root(tree)
 }
+
+pub fn logs_hash(logs: &[Log]) -> H256 {
+	let mut stream = rlp::RlpStream::new_list(logs.len());
+	for log in logs {
+		stream.begin_list(3);
+		stream.append(&log.address);
+		stream.append_list(&log.topics);
+		stream.append(&log.data);
+	}
+
+	H256::from_slice(&Keccak256::digest(stream.out()))
+}
@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 
 use evm::{
 	backend::OverlayedChangeSet,
-	interpreter::runtime::{RuntimeBaseBackend, RuntimeEnvironment},
+	interpreter::runtime::{BlockHashProvider, RuntimeBaseBackend, RuntimeEnvironment},
 };
 use primitive_types::{H160, H256, U256};
 
@@ -14,8 +14,10 @@ pub struct InMemoryEnvironment {
 	pub block_timestamp: U256,
 	pub block_difficulty: U256,
 	pub block_randomness: Option<H256>,
+	pub block_parent_beacon_root: Option<H256>,
 	pub block_gas_limit: U256,
 	pub block_base_fee_per_gas: U256,
+	pub blob_base_fee: U256,
 	pub chain_id: U256,
 }
 
@@ -41,6 +43,10 @@ pub struct InMemoryBackend {
 
 impl InMemoryBackend {
 	pub fn apply_overlayed(&mut self, changeset: &OverlayedChangeSet) {
+		for address in changeset.deletes.clone() {
+			self.state.remove(&address);
+		}
+
 		for (address, balance) in changeset.balances.clone() {
 			self.state.entry(address).or_default().balance = balance;
 		}
@@ -54,7 +60,10 @@ impl InMemoryBackend {
 		}
 
 		for address in changeset.storage_resets.clone() {
-			self.state.entry(address).or_default().storage = BTreeMap::new();
+			// A reset alone, as left by a delete, does not create the account.
+			if let Some(account) = self.state.get_mut(&address) {
+				account.storage = BTreeMap::new();
+			}
 		}
 
 		for ((address, key), value) in changeset.storages.clone() {
@@ -76,14 +85,10 @@ impl InMemoryBackend {
 				account.transient_storage.insert(key, value);
 			}
 		}
-
-		for address in changeset.deletes.clone() {
-			self.state.remove(&address);
-		}
 	}
 }
 
-impl RuntimeEnvironment for InMemoryBackend {
+impl BlockHashProvider for InMemoryBackend {
 	fn block_hash(&self, number: U256) -> H256 {
 		self.environment
 			.block_hashes
@@ -91,7 +96,9 @@ impl RuntimeEnvironment for InMemoryBackend {
 			.cloned()
 			.unwrap_or(H256::default())
 	}
+}
 
+impl RuntimeEnvironment for InMemoryBackend {
 	fn block_number(&self) -> U256 {
 		self.environment.block_number
 	}
@@ -112,6 +119,10 @@ impl RuntimeEnvironment for InMemoryBackend {
 		self.environment.block_randomness
 	}
 
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.environment.block_parent_beacon_root
+	}
+
 	fn block_gas_limit(&self) -> U256 {
 		self.environment.block_gas_limit
 	}
@@ -120,6 +131,10 @@ impl RuntimeEnvironment for InMemoryBackend {
 		self.environment.block_base_fee_per_gas
 	}
 
+	fn blob_base_fee(&self) -> U256 {
+		self.environment.blob_base_fee
+	}
+
 	fn chain_id(&self) -> U256 {
 		self.environment.chain_id
 	}
@@ -143,7 +158,7 @@ impl RuntimeBaseBackend for InMemoryBackend {
 	}
 
 	fn exists(&self, address: H160) -> bool {
-		self.state.get(&address).is_some()
+		self.state.contains_key(&address)
 	}
 
 	fn storage(&self, address: H160, index: H256) -> H256 {
@@ -1,3 +1,6 @@
+pub mod blockchain;
+#[cfg(feature = "revm")]
+pub mod differential;
 pub mod error;
 pub mod hash;
 pub mod in_memory;
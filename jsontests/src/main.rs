@@ -1,3 +1,6 @@
+mod blockchain;
+#[cfg(feature = "revm")]
+mod differential;
 mod error;
 mod hash;
 mod in_memory;
@@ -15,14 +18,35 @@ struct Cli {
 
 	#[arg(short, long, default_value_t = false)]
 	debug: bool,
+
+	/// Run the files as `BlockchainTests` instead of `GeneralStateTests`.
+	#[arg(long, default_value_t = false)]
+	blockchain: bool,
+
+	/// Compare execution results against revm instead of the expected post-state.
+	#[cfg(feature = "revm")]
+	#[arg(long, default_value_t = false)]
+	differential: bool,
 }
 
 fn main() -> Result<(), Error> {
 	let cli = Cli::parse();
 
+	#[cfg(feature = "revm")]
+	if cli.differential {
+		for filename in cli.filenames {
+			differential::diff_file(&filename)?;
+		}
+		return Ok(());
+	}
+
 	let mut tests_status = TestCompletionStatus::default();
 	for filename in cli.filenames {
-		tests_status += run::run_single(&filename, cli.debug)?;
+		if cli.blockchain {
+			tests_status += blockchain::run_file(&filename, cli.debug)?;
+		} else {
+			tests_status += run::run_single(&filename, cli.debug)?;
+		}
 	}
 	tests_status.print_total();
 
@@ -6,16 +6,21 @@ use std::{
 
 use evm::{
 	backend::OverlayedBackend,
-	interpreter::{error::Capture, runtime::GasState, utils::u256_to_h256, Interpreter},
-	standard::{Config, Etable, EtableResolver, Invoker, TransactArgs},
+	interpreter::{
+		error::{Capture, ExitError, ExitException},
+		runtime::GasState,
+		utils::u256_to_h256,
+		Interpreter,
+	},
+	standard::{validate_transaction, Config, Etable, EtableResolver, Invoker, TransactArgs},
 };
 use evm_precompile::StandardPrecompileSet;
-use primitive_types::U256;
+use primitive_types::{H160, U256};
 
 use crate::{
 	error::{Error, TestError},
 	in_memory::{InMemoryAccount, InMemoryBackend, InMemoryEnvironment},
-	types::{Fork, TestCompletionStatus, TestData, TestExpectException, TestMulti},
+	types::{Fork, TestCompletionStatus, TestData, TestExpectException, TestMulti, TestPreState},
 };
 
 const BASIC_FILE_PATH_TO_TRIM: [&str; 2] = [
@@ -95,24 +100,21 @@ pub fn run_single(filename: &str, debug: bool) -> Result<TestCompletionStatus, E
 	}
 }
 
-/// Run single test
-pub fn run_test(
-	_filename: &str,
-	_test_name: &str,
-	test: TestData,
-	debug: bool,
-) -> Result<(), Error> {
-	let config = match test.fork {
-		Fork::Berlin => Config::berlin(),
-		_ => return Err(Error::UnsupportedFork),
-	};
-
-	if test.post.expect_exception == Some(TestExpectException::TR_TypeNotSupported) {
-		// The `evm` crate does not understand transaction format, only the `ethereum` crate. So
-		// there's nothing for us to test here for `TR_TypeNotSupported`.
-		return Ok(());
+/// Config of the fork, if the fork is supported.
+pub fn fork_config(fork: Fork) -> Option<Config> {
+	match fork {
+		Fork::Istanbul => Some(Config::istanbul()),
+		Fork::Berlin => Some(Config::berlin()),
+		Fork::London => Some(Config::london()),
+		Fork::Merge => Some(Config::merge()),
+		Fork::Shanghai => Some(Config::shanghai()),
+		Fork::Cancun => Some(Config::cancun()),
+		_ => None,
 	}
+}
 
+/// Pre-state backend of the test.
+pub fn test_backend(test: &TestData, config: &Config) -> InMemoryBackend {
 	let env = InMemoryEnvironment {
 		block_hashes: BTreeMap::new(), // TODO: fill in this field.
 		block_number: test.env.current_number,
@@ -120,14 +122,26 @@ pub fn run_test(
 		block_timestamp: test.env.current_timestamp,
 		block_difficulty: test.env.current_difficulty,
 		block_randomness: Some(test.env.current_random),
+		block_parent_beacon_root: Some(test.env.current_beacon_root),
 		block_gas_limit: test.env.current_gas_limit,
-		block_base_fee_per_gas: test.transaction.gas_price,
-		chain_id: U256::zero(), // TODO: fill in this field.
+		block_base_fee_per_gas: if config.eip_1559_enabled {
+			test.env.current_base_fee
+		} else {
+			test.transaction.gas_price
+		},
+		blob_base_fee: U256::zero(),
+		chain_id: test.chain_id,
 	};
 
-	let state = test
-		.pre
-		.clone()
+	InMemoryBackend {
+		environment: env,
+		state: pre_state(&test.pre),
+	}
+}
+
+/// Accounts of the pre-state of a test.
+pub fn pre_state(pre: &BTreeMap<H160, TestPreState>) -> BTreeMap<H160, InMemoryAccount> {
+	pre.clone()
 		.into_iter()
 		.map(|(address, account)| {
 			let storage = account
@@ -148,7 +162,64 @@ pub fn run_test(
 				},
 			)
 		})
-		.collect::<BTreeMap<_, _>>();
+		.collect()
+}
+
+/// Transaction arguments of the test.
+pub fn test_args(test: &TestData) -> TransactArgs {
+	let access_list = test
+		.transaction
+		.access_list
+		.iter()
+		.map(|access| (access.address, access.storage_keys.clone()))
+		.collect();
+
+	match test.transaction.to {
+		Some(address) => TransactArgs::Call {
+			caller: test.transaction.sender,
+			address,
+			nonce: Some(test.transaction.nonce),
+			chain_id: None,
+			value: test.transaction.value,
+			data: test.transaction.data.clone(),
+			gas_limit: test.transaction.gas_limit,
+			gas_price: test.transaction.gas_price,
+			access_list,
+			system: false,
+			mint: None,
+			fee_payer: None,
+		},
+		None => TransactArgs::Create {
+			caller: test.transaction.sender,
+			nonce: Some(test.transaction.nonce),
+			chain_id: None,
+			value: test.transaction.value,
+			init_code: test.transaction.data.clone(),
+			salt: None,
+			gas_limit: test.transaction.gas_limit,
+			gas_price: test.transaction.gas_price,
+			access_list,
+			system: false,
+			mint: None,
+			fee_payer: None,
+		},
+	}
+}
+
+/// Run single test
+pub fn run_test(
+	_filename: &str,
+	_test_name: &str,
+	test: TestData,
+	debug: bool,
+) -> Result<(), Error> {
+	let config = fork_config(test.fork).ok_or(Error::UnsupportedFork)?;
+
+	if test.post.expect_exception == Some(TestExpectException::TR_TypeNotSupported) {
+		// The `evm` crate does not understand transaction format, only the `ethereum` crate. So
+		// there's nothing for us to test here for `TR_TypeNotSupported`.
+		return Ok(());
+	}
 
 	let gas_etable = Etable::single(evm::standard::eval_gasometer);
 	let exec_etable = Etable::runtime();
@@ -156,39 +227,20 @@ pub fn run_test(
 	let precompiles = StandardPrecompileSet::new(&config);
 	let resolver = EtableResolver::new(&config, &precompiles, &etable);
 	let invoker = Invoker::new(&config, &resolver);
-	let args = TransactArgs::Call {
-		caller: test.transaction.sender,
-		address: test.transaction.to,
-		value: test.transaction.value,
-		data: test.transaction.data,
-		gas_limit: test.transaction.gas_limit,
-		gas_price: test.transaction.gas_price,
-		access_list: test
-			.transaction
-			.access_list
-			.into_iter()
-			.map(|access| (access.address, access.storage_keys))
-			.collect(),
-	};
-
-	let initial_accessed = {
-		let mut hots = BTreeSet::new();
-		for i in 1..10 {
-			hots.insert((u256_to_h256(U256::from(i)).into(), None));
-		}
-		hots
-	};
+	let args = test_args(&test);
 
-	let base_backend = InMemoryBackend {
-		environment: env,
-		state,
-	};
+	let base_backend = test_backend(&test, &config);
 
-	let mut run_backend = OverlayedBackend::new(&base_backend, initial_accessed.clone());
-	let mut step_backend = OverlayedBackend::new(&base_backend, initial_accessed.clone());
+	let mut run_backend = OverlayedBackend::new(&base_backend, BTreeSet::new());
+	let mut step_backend = OverlayedBackend::new(&base_backend, BTreeSet::new());
 
 	// Run
-	let run_result = evm::transact(args.clone(), Some(4), &mut run_backend, &invoker);
+	let run_result = match validate_transaction(&args, &run_backend, &config) {
+		Ok(()) => evm::transact_hybrid(args.clone(), Some(4), &mut run_backend, &invoker),
+		Err(err) => Err(ExitError::from(ExitException::Other(
+			format!("invalid transaction: {:?}", err).into(),
+		))),
+	};
 	let run_changeset = run_backend.deconstruct().1;
 	let mut run_backend = base_backend.clone();
 	run_backend.apply_overlayed(&run_changeset);
@@ -244,5 +296,13 @@ pub fn run_test(
 		return Err(TestError::StateMismatch.into());
 	}
 
+	let logs = match &run_result {
+		Ok(value) => &value.logs[..],
+		Err(_) => &[],
+	};
+	if crate::hash::logs_hash(logs) != test.post.logs {
+		return Err(TestError::LogsMismatch.into());
+	}
+
 	Ok(())
 }
@@ -73,6 +73,7 @@ pub struct TestMulti {
 	pub post: BTreeMap<Fork, Vec<TestPostState>>,
 	pub pre: BTreeMap<H160, TestPreState>,
 	pub transaction: TestMultiTransaction,
+	pub config: Option<TestConfig>,
 }
 
 impl TestMulti {
@@ -91,13 +92,19 @@ impl TestMulti {
 					index,
 					post: post_state.clone(),
 					pre: self.pre.clone(),
+					chain_id: TestConfig::chain_id(self.config.as_ref()),
 					transaction: TestTransaction {
 						data: self.transaction.data[post_state.indexes.data].0.clone(),
 						gas_limit: self.transaction.gas_limit[post_state.indexes.gas],
-						gas_price: self
-							.transaction
-							.gas_price
-							.unwrap_or(self.env.current_base_fee),
+						gas_price: self.transaction.gas_price.unwrap_or_else(|| {
+							// Effective gas price of an EIP-1559 transaction.
+							let max_fee = self.transaction.max_fee_per_gas.unwrap_or_default();
+							let priority_fee = self
+								.transaction
+								.max_priority_fee_per_gas
+								.unwrap_or_default();
+							max_fee.min(self.env.current_base_fee.saturating_add(priority_fee))
+						}),
 						gas_priority_fee: self.transaction.max_priority_fee_per_gas,
 						nonce: self.transaction.nonce,
 						secret_key: self.transaction.secret_key,
@@ -126,6 +133,7 @@ pub struct TestData {
 	pub index: usize,
 	pub post: TestPostState,
 	pub pre: BTreeMap<H160, TestPreState>,
+	pub chain_id: U256,
 	pub transaction: TestTransaction,
 }
 
@@ -145,6 +153,24 @@ pub struct TestInfo {
 	pub source_hash: String,
 }
 
+/// Chain configuration of a test, only present in newer fixtures.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+pub struct TestConfig {
+	pub chainid: Option<U256>,
+}
+
+impl TestConfig {
+	/// Chain ID of fixtures without a chain configuration.
+	pub const DEFAULT_CHAIN_ID: u64 = 1;
+
+	/// Chain ID of the test with the configuration.
+	pub fn chain_id(config: Option<&Self>) -> U256 {
+		config
+			.and_then(|config| config.chainid)
+			.unwrap_or_else(|| U256::from(Self::DEFAULT_CHAIN_ID))
+	}
+}
+
 /// `TestEnv` represents Ethereum environment data
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -223,7 +249,8 @@ pub struct TestMultiTransaction {
 	pub nonce: U256,
 	pub secret_key: H256,
 	pub sender: H160,
-	pub to: H160,
+	#[serde(deserialize_with = "deserialize_to")]
+	pub to: Option<H160>,
 	pub value: Vec<U256>,
 	pub access_lists: Option<Vec<Vec<TestAccessListItem>>>,
 }
@@ -244,11 +271,79 @@ pub struct TestTransaction {
 	pub nonce: U256,
 	pub secret_key: H256,
 	pub sender: H160,
-	pub to: H160,
+	/// Callee of the transaction, `None` for a create transaction.
+	pub to: Option<H160>,
 	pub value: U256,
 	pub access_list: Vec<TestAccessListItem>,
 }
 
+/// `BlockchainTest` represents a test of a `BlockchainTests` fixture: a chain
+/// of blocks imported on top of the pre-state.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockchainTest {
+	pub blocks: Vec<TestBlock>,
+	pub genesis_block_header: TestBlockHeader,
+	pub lastblockhash: H256,
+	pub network: String,
+	pub pre: BTreeMap<H160, TestPreState>,
+	pub config: Option<TestConfig>,
+}
+
+impl BlockchainTest {
+	/// Fork of the test, if the network is a single supported fork.
+	pub fn fork(&self) -> Option<Fork> {
+		match &self.network[..] {
+			"Istanbul" => Some(Fork::Istanbul),
+			"Berlin" => Some(Fork::Berlin),
+			"London" => Some(Fork::London),
+			"Merge" | "Paris" => Some(Fork::Merge),
+			"Shanghai" => Some(Fork::Shanghai),
+			"Cancun" => Some(Fork::Cancun),
+			_ => None,
+		}
+	}
+}
+
+/// A block of a `BlockchainTest`. An invalid block only has its RLP and the
+/// expected exception.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestBlock {
+	pub block_header: Option<TestBlockHeader>,
+	pub expect_exception: Option<String>,
+	pub rlp: HexBytes,
+	pub uncle_headers: Option<Vec<TestBlockHeader>>,
+	pub withdrawals: Option<Vec<TestWithdrawal>>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestBlockHeader {
+	pub base_fee_per_gas: Option<U256>,
+	pub blob_gas_used: Option<U256>,
+	pub coinbase: H160,
+	pub difficulty: U256,
+	pub excess_blob_gas: Option<U256>,
+	pub gas_limit: U256,
+	pub gas_used: U256,
+	pub hash: H256,
+	pub mix_hash: H256,
+	pub number: U256,
+	pub parent_beacon_block_root: Option<H256>,
+	pub state_root: H256,
+	pub timestamp: U256,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestWithdrawal {
+	pub index: U256,
+	pub validator_index: U256,
+	pub address: H160,
+	pub amount: U256,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
 pub struct HexBytes(#[serde(deserialize_with = "deserialize_hex_bytes")] pub Vec<u8>);
 
@@ -290,3 +385,20 @@ where
 
 	deserializer.deserialize_str(HexStrVisitor)
 }
+
+/// Deserialize the `to` field of a transaction, which is empty for a create
+/// transaction.
+fn deserialize_to<'de, D>(deserializer: D) -> Result<Option<H160>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	let to = String::deserialize(deserializer)?;
+	if to.is_empty() {
+		return Ok(None);
+	}
+
+	let to = to.strip_prefix("0x").unwrap_or(&to);
+	<[u8; 20]>::from_hex(to)
+		.map(|to| Some(H160(to)))
+		.map_err(Error::custom)
+}
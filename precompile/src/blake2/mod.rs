@@ -7,11 +7,8 @@ use evm::{
 
 use crate::PurePrecompile;
 
-pub struct Blake2F;
-
-impl Blake2F {
-	const GAS_COST_PER_ROUND: u64 = 1; // https://eips.ethereum.org/EIPS/eip-152#gas-costs-and-benchmarks
-}
+/// The Blake2F builtin, with its gas cost per round.
+pub struct Blake2F(pub u64);
 
 impl<G: GasMutState> PurePrecompile<G> for Blake2F {
 	/// Format of `input`:
@@ -33,8 +30,8 @@ impl<G: GasMutState> PurePrecompile<G> for Blake2F {
 		rounds_buf.copy_from_slice(&input[0..4]);
 		let rounds: u32 = u32::from_be_bytes(rounds_buf);
 
-		let gas_cost: u64 = (rounds as u64) * Blake2F::GAS_COST_PER_ROUND;
-		try_some!(gasometer.record_gas(gas_cost.into()));
+		let gas_cost: u64 = (rounds as u64).saturating_mul(self.0);
+		try_some!(gasometer.record_gas64(gas_cost));
 
 		// we use from_le_bytes below to effectively swap byte order to LE if architecture is BE
 		let mut h_buf: [u8; 64] = [0; 64];
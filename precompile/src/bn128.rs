@@ -52,18 +52,14 @@ fn read_point(input: &[u8], start_inx: usize) -> Result<bn::G1, ExitError> {
 	})
 }
 
-/// The Bn128Add builtin
-pub struct Bn128Add;
-
-impl Bn128Add {
-	const GAS_COST: u64 = 150; // https://eips.ethereum.org/EIPS/eip-1108
-}
+/// The Bn128Add builtin, with its gas cost.
+pub struct Bn128Add(pub u64);
 
 impl<G: GasMutState> PurePrecompile<G> for Bn128Add {
 	fn execute(&self, input: &[u8], gasometer: &mut G) -> (ExitResult, Vec<u8>) {
 		use bn::AffineG1;
 
-		try_some!(gasometer.record_gas(Bn128Add::GAS_COST.into()));
+		try_some!(gasometer.record_gas64(self.0));
 
 		let p1 = try_some!(read_point(input, 0));
 		let p2 = try_some!(read_point(input, 64));
@@ -86,18 +82,14 @@ impl<G: GasMutState> PurePrecompile<G> for Bn128Add {
 	}
 }
 
-/// The Bn128Mul builtin
-pub struct Bn128Mul;
-
-impl Bn128Mul {
-	const GAS_COST: u64 = 6_000; // https://eips.ethereum.org/EIPS/eip-1108
-}
+/// The Bn128Mul builtin, with its gas cost.
+pub struct Bn128Mul(pub u64);
 
 impl<G: GasMutState> PurePrecompile<G> for Bn128Mul {
 	fn execute(&self, input: &[u8], gasometer: &mut G) -> (ExitResult, Vec<u8>) {
 		use bn::AffineG1;
 
-		try_some!(gasometer.record_gas(Bn128Mul::GAS_COST.into()));
+		try_some!(gasometer.record_gas64(self.0));
 
 		let p = try_some!(read_point(input, 0));
 		let fr = try_some!(read_fr(input, 64));
@@ -120,13 +112,12 @@ impl<G: GasMutState> PurePrecompile<G> for Bn128Mul {
 	}
 }
 
-/// The Bn128Pairing builtin
-pub struct Bn128Pairing;
-
-impl Bn128Pairing {
-	// https://eips.ethereum.org/EIPS/eip-1108
-	const BASE_GAS_COST: u64 = 45_000;
-	const GAS_COST_PER_PAIRING: u64 = 34_000;
+/// The Bn128Pairing builtin.
+pub struct Bn128Pairing {
+	/// Base gas cost.
+	pub base_cost: u64,
+	/// Gas cost per pair of points.
+	pub per_point_cost: u64,
 }
 
 impl<G: GasMutState> PurePrecompile<G> for Bn128Pairing {
@@ -134,7 +125,7 @@ impl<G: GasMutState> PurePrecompile<G> for Bn128Pairing {
 		use bn::{pairing_batch, AffineG1, AffineG2, Fq, Fq2, Group, Gt, G1, G2};
 
 		let ret_val = if input.is_empty() {
-			try_some!(gasometer.record_gas(Bn128Pairing::BASE_GAS_COST.into()));
+			try_some!(gasometer.record_gas64(self.base_cost));
 			U256::one()
 		} else {
 			if input.len() % 192 > 0 {
@@ -147,10 +138,11 @@ impl<G: GasMutState> PurePrecompile<G> for Bn128Pairing {
 			// (a, b_a, b_b - each 64-byte affine coordinates)
 			let elements = input.len() / 192;
 
-			let gas_cost: u64 = Bn128Pairing::BASE_GAS_COST
-				+ (elements as u64 * Bn128Pairing::GAS_COST_PER_PAIRING);
+			let gas_cost: u64 = self
+				.base_cost
+				.saturating_add((elements as u64).saturating_mul(self.per_point_cost));
 
-			try_some!(gasometer.record_gas(gas_cost.into()));
+			try_some!(gasometer.record_gas64(gas_cost));
 
 			let mut vals = Vec::new();
 			for idx in 0..elements {
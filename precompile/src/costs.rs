@@ -0,0 +1,93 @@
+use evm::interpreter::error::ExitError;
+
+/// Gas cost linear in the input length: a base cost plus a cost per 32-byte
+/// word of input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LinearCost {
+	/// Base cost.
+	pub base: u64,
+	/// Cost per word of input.
+	pub word: u64,
+}
+
+impl LinearCost {
+	/// Cost for an input of the length.
+	pub fn cost(&self, len: u64) -> Result<u64, ExitError> {
+		crate::linear_cost(len, self.base, self.word)
+	}
+}
+
+/// Gas cost parameters of the standard precompiles. Chains that reprice or
+/// subsidize precompiles can pass their own parameters to
+/// [crate::StandardPrecompileSet::with_gas_costs].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PrecompileGasCosts {
+	/// Cost of `ECRECOVER`.
+	pub ecrecover: LinearCost,
+	/// Cost of `SHA256`.
+	pub sha256: LinearCost,
+	/// Cost of `RIPEMD160`.
+	pub ripemd160: LinearCost,
+	/// Cost of `IDENTITY`.
+	pub identity: LinearCost,
+	/// Minimum cost of `MODEXP`.
+	pub modexp_min: u64,
+	/// Multiplier of the `MODEXP` cost when the modulus is even.
+	pub modexp_even_multiplier: u64,
+	/// Cost of `BN128ADD`.
+	pub bn128_add: u64,
+	/// Cost of `BN128MUL`.
+	pub bn128_mul: u64,
+	/// Base cost of `BN128PAIRING`.
+	pub bn128_pairing_base: u64,
+	/// Cost of `BN128PAIRING` per pair of points.
+	pub bn128_pairing_per_point: u64,
+	/// Cost of `BLAKE2F` per round.
+	pub blake2f_per_round: u64,
+	/// Cost of `P256VERIFY` (RIP-7212), with the `secp256r1` feature.
+	pub p256_verify: u64,
+}
+
+impl PrecompileGasCosts {
+	/// Costs before Istanbul, with the alt_bn128 costs of EIP-196 and
+	/// EIP-197.
+	pub const fn byzantium() -> Self {
+		Self {
+			bn128_add: 500,
+			bn128_mul: 40_000,
+			bn128_pairing_base: 100_000,
+			bn128_pairing_per_point: 80_000,
+			..Self::istanbul()
+		}
+	}
+
+	/// Costs since Istanbul, with the alt_bn128 costs repriced by EIP-1108.
+	pub const fn istanbul() -> Self {
+		Self {
+			ecrecover: LinearCost {
+				base: 3000,
+				word: 0,
+			},
+			sha256: LinearCost {
+				base: 600,
+				word: 120,
+			},
+			ripemd160: LinearCost { base: 60, word: 12 },
+			identity: LinearCost { base: 15, word: 3 },
+			modexp_min: 200,
+			modexp_even_multiplier: 20,
+			bn128_add: 150,
+			bn128_mul: 6_000,
+			bn128_pairing_base: 45_000,
+			bn128_pairing_per_point: 34_000,
+			blake2f_per_round: 1,
+			p256_verify: 3450,
+		}
+	}
+}
+
+impl Default for PrecompileGasCosts {
+	fn default() -> Self {
+		Self::istanbul()
+	}
+}
@@ -17,7 +17,10 @@ extern crate alloc;
 
 mod blake2;
 mod bn128;
+mod costs;
 mod modexp;
+#[cfg(feature = "secp256r1")]
+mod secp256r1;
 mod simple;
 
 use alloc::vec::Vec;
@@ -25,31 +28,83 @@ use alloc::vec::Vec;
 use evm::{
 	interpreter::{
 		error::{ExitError, ExitException, ExitResult},
-		runtime::RuntimeState,
+		runtime::{GasState, RuntimeBackend, RuntimeState},
 	},
 	standard::{Config, PrecompileSet},
 	GasMutState,
 };
-use primitive_types::H160;
+use primitive_types::{H160, U256};
 
+#[cfg(feature = "secp256r1")]
+pub use crate::secp256r1::P256Verify;
 pub use crate::{
 	blake2::Blake2F,
 	bn128::{Bn128Add, Bn128Mul, Bn128Pairing},
+	costs::{LinearCost, PrecompileGasCosts},
 	modexp::Modexp,
 	simple::{ECRecover, Identity, Ripemd160, Sha256},
 };
 
+/// Address of the P256VERIFY precompile (RIP-7212), in the standard set with
+/// the `secp256r1` feature, if [Config::rip_7212_enabled] is set.
+#[cfg(feature = "secp256r1")]
+pub const P256_VERIFY_ADDRESS: H160 = H160([
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00,
+]);
+
 pub trait PurePrecompile<G> {
 	fn execute(&self, input: &[u8], gasometer: &mut G) -> (ExitResult, Vec<u8>);
 }
 
 pub struct StandardPrecompileSet<'config> {
-	_config: &'config Config,
+	config: &'config Config,
+	gas_costs: PrecompileGasCosts,
 }
 
 impl<'config> StandardPrecompileSet<'config> {
 	pub fn new(config: &'config Config) -> Self {
-		Self { _config: config }
+		Self::with_gas_costs(config, PrecompileGasCosts::default())
+	}
+
+	/// Create the precompile set with custom gas cost parameters.
+	pub fn with_gas_costs(config: &'config Config, gas_costs: PrecompileGasCosts) -> Self {
+		Self { config, gas_costs }
+	}
+
+	/// Gas cost parameters of the precompiles.
+	pub fn gas_costs(&self) -> &PrecompileGasCosts {
+		&self.gas_costs
+	}
+}
+
+/// Gasometer passed to a precompile whose cost is overridden in the config.
+/// The override is charged up front, so the costs the precompile itself
+/// records are ignored. Refunds are still passed through.
+struct OverriddenGasometer<'a, G>(&'a mut G);
+
+impl<'a, G: GasState> GasState for OverriddenGasometer<'a, G> {
+	fn gas(&self) -> u64 {
+		self.0.gas()
+	}
+}
+
+impl<'a, G: GasMutState> GasMutState for OverriddenGasometer<'a, G> {
+	fn record_gas(&mut self, _gas: U256) -> Result<(), ExitError> {
+		Ok(())
+	}
+
+	fn record_memory_expansion(&mut self, _offset: U256, _len: U256) -> Result<(), ExitError> {
+		Ok(())
+	}
+
+	fn record_refund(&mut self, refund: i64) -> Result<(), ExitError> {
+		self.0.record_refund(refund)
+	}
+}
+
+impl<'a, G: AsRef<RuntimeState>> AsRef<RuntimeState> for OverriddenGasometer<'a, G> {
+	fn as_ref(&self) -> &RuntimeState {
+		self.0.as_ref()
 	}
 }
 
@@ -63,29 +118,110 @@ impl<'config, G: AsRef<RuntimeState> + GasMutState, H> PrecompileSet<G, H>
 		gasometer: &mut G,
 		_handler: &mut H,
 	) -> Option<(ExitResult, Vec<u8>)> {
-		// TODO: selectively disable precompiles based on config.
-
-		if code_address == address(1) {
-			Some(ECRecover.execute(input, gasometer))
-		} else if code_address == address(2) {
-			Some(Sha256.execute(input, gasometer))
-		} else if code_address == address(3) {
-			Some(Ripemd160.execute(input, gasometer))
-		} else if code_address == address(4) {
-			Some(Identity.execute(input, gasometer))
-		} else if code_address == address(5) {
-			Some(Modexp.execute(input, gasometer))
-		} else if code_address == address(6) {
-			Some(Bn128Add.execute(input, gasometer))
-		} else if code_address == address(7) {
-			Some(Bn128Mul.execute(input, gasometer))
-		} else if code_address == address(8) {
-			Some(Bn128Pairing.execute(input, gasometer))
-		} else if code_address == address(9) {
-			Some(Blake2F.execute(input, gasometer))
-		} else {
-			None
+		if !<Self as PrecompileSet<G, H>>::is_precompile(self, code_address) {
+			return None;
+		}
+
+		if let Some((base, word)) = self.config.precompile_gas_override(code_address) {
+			if let Err(err) = linear_cost(input.len() as u64, base, word)
+				.and_then(|cost| gasometer.record_gas64(cost))
+			{
+				return Some((Err(err), Vec::new()));
+			}
+
+			return execute_standard(
+				code_address,
+				input,
+				&self.gas_costs,
+				self.config,
+				&mut OverriddenGasometer(gasometer),
+			);
+		}
+
+		execute_standard(code_address, input, &self.gas_costs, self.config, gasometer)
+	}
+
+	fn addresses(&self) -> Vec<H160> {
+		#[allow(unused_mut)]
+		let mut addresses = (1..=9).map(address).collect::<Vec<_>>();
+		#[cfg(feature = "secp256r1")]
+		if self.config.rip_7212_enabled {
+			addresses.push(P256_VERIFY_ADDRESS);
 		}
+		addresses
+	}
+
+	fn is_precompile(&self, code_address: H160) -> bool {
+		#[cfg(feature = "secp256r1")]
+		if code_address == P256_VERIFY_ADDRESS {
+			return self.config.rip_7212_enabled;
+		}
+
+		(1..=9).any(|i| code_address == address(i))
+	}
+
+	fn warm_addresses(&self, handler: &mut H)
+	where
+		H: RuntimeBackend,
+	{
+		for i in 1..=9 {
+			handler.mark_hot(address(i), None);
+		}
+		#[cfg(feature = "secp256r1")]
+		if self.config.rip_7212_enabled {
+			handler.mark_hot(P256_VERIFY_ADDRESS, None);
+		}
+	}
+}
+
+fn execute_standard<G: AsRef<RuntimeState> + GasMutState>(
+	code_address: H160,
+	input: &[u8],
+	costs: &PrecompileGasCosts,
+	config: &Config,
+	gasometer: &mut G,
+) -> Option<(ExitResult, Vec<u8>)> {
+	// TODO: selectively disable precompiles based on config.
+
+	#[cfg(feature = "secp256r1")]
+	if code_address == P256_VERIFY_ADDRESS && config.rip_7212_enabled {
+		return Some(P256Verify(costs.p256_verify).execute(input, gasometer));
+	}
+
+	if code_address == address(1) {
+		Some(ECRecover(costs.ecrecover).execute(input, gasometer))
+	} else if code_address == address(2) {
+		Some(Sha256(costs.sha256).execute(input, gasometer))
+	} else if code_address == address(3) {
+		Some(Ripemd160(costs.ripemd160).execute(input, gasometer))
+	} else if code_address == address(4) {
+		Some(Identity(costs.identity).execute(input, gasometer))
+	} else if code_address == address(5) {
+		Some(
+			Modexp {
+				min_cost: costs.modexp_min,
+				even_multiplier: costs.modexp_even_multiplier,
+				eip_2565: config.eip_2565_enabled,
+				max_len: config.modexp_max_len,
+			}
+			.execute(input, gasometer),
+		)
+	} else if code_address == address(6) {
+		Some(Bn128Add(costs.bn128_add).execute(input, gasometer))
+	} else if code_address == address(7) {
+		Some(Bn128Mul(costs.bn128_mul).execute(input, gasometer))
+	} else if code_address == address(8) {
+		Some(
+			Bn128Pairing {
+				base_cost: costs.bn128_pairing_base,
+				per_point_cost: costs.bn128_pairing_per_point,
+			}
+			.execute(input, gasometer),
+		)
+	} else if code_address == address(9) {
+		Some(Blake2F(costs.blake2f_per_round).execute(input, gasometer))
+	} else {
+		None
 	}
 }
 
@@ -1,77 +1,93 @@
 use alloc::{vec, vec::Vec};
-use core::cmp::max;
+use core::cmp::{max, min};
 
 use evm::{
 	interpreter::error::{ExitException, ExitResult, ExitSucceed},
 	GasMutState,
 };
-use num::{BigUint, FromPrimitive, Integer, One, ToPrimitive, Zero};
+use num::{BigUint, One, Zero};
+use primitive_types::U256;
 
 use crate::PurePrecompile;
 
-pub struct Modexp;
-
-const MIN_GAS_COST: u64 = 200;
-
-// Calculate gas cost according to EIP 2565:
-// https://eips.ethereum.org/EIPS/eip-2565
-fn calculate_gas_cost(
-	base_length: u64,
-	mod_length: u64,
-	exponent: &BigUint,
-	exponent_bytes: &[u8],
-	mod_is_even: bool,
-) -> u64 {
-	fn calculate_multiplication_complexity(base_length: u64, mod_length: u64) -> u64 {
-		let max_length = max(base_length, mod_length);
-		let mut words = max_length / 8;
-		if max_length % 8 > 0 {
-			words += 1;
-		}
+/// The Modexp builtin.
+pub struct Modexp {
+	/// Minimum gas cost. Only used with the EIP-2565 pricing.
+	pub min_cost: u64,
+	/// Multiplier of the gas cost when the modulus is even.
+	pub even_multiplier: u64,
+	/// Price with the formula of EIP-2565, instead of the one of EIP-198.
+	pub eip_2565: bool,
+	/// Maximum length of each of the base, exponent and modulus, in bytes.
+	/// `None` for no limit.
+	pub max_len: Option<u64>,
+}
+
+impl Modexp {
+	/// Gas cost of the lengths, the first 32 bytes of the exponent and the
+	/// parity of the modulus.
+	fn gas_cost(
+		&self,
+		base_len: u64,
+		exp_len: u64,
+		mod_len: u64,
+		exponent_head: &BigUint,
+		mod_is_even: bool,
+	) -> u64 {
+		let max_len = max(base_len, mod_len);
+		let iteration_count = max(iteration_count(exp_len, exponent_head), 1);
+
+		let gas_cost = if self.eip_2565 {
+			// See https://eips.ethereum.org/EIPS/eip-2565
+			let words = max_len / 8 + u64::from(max_len % 8 > 0);
+			let multiplication_complexity = words.saturating_mul(words);
+			max(
+				self.min_cost,
+				multiplication_complexity.saturating_mul(iteration_count) / 3,
+			)
+		} else {
+			// See https://eips.ethereum.org/EIPS/eip-198
+			let square = max_len.saturating_mul(max_len);
+			let multiplication_complexity = if max_len <= 64 {
+				square
+			} else if max_len <= 1024 {
+				square / 4 + 96 * max_len - 3072
+			} else {
+				(square / 16)
+					.saturating_add(max_len.saturating_mul(480))
+					.saturating_sub(199_680)
+			};
+			multiplication_complexity.saturating_mul(iteration_count) / 20
+		};
 
-		// Note: can't overflow because we take words to be some u64 value / 8, which is
-		// necessarily less than sqrt(u64::MAX).
-		// Additionally, both base_length and mod_length are bounded to 1024, so this has
-		// an upper bound of roughly (1024 / 8) squared
-		words * words
+		gas_cost.saturating_mul(if mod_is_even { self.even_multiplier } else { 1 })
 	}
+}
 
-	fn calculate_iteration_count(exponent: &BigUint, exponent_bytes: &[u8]) -> u64 {
-		let mut iteration_count: u64 = 0;
-		let exp_length = exponent_bytes.len() as u64;
-
-		if exp_length <= 32 && exponent.is_zero() {
-			iteration_count = 0;
-		} else if exp_length <= 32 {
-			iteration_count = exponent.bits() - 1;
-		} else if exp_length > 32 {
-			// from the EIP spec:
-			// (8 * (exp_length - 32)) + ((exponent & (2**256 - 1)).bit_length() - 1)
-			//
-			// Notes:
-			// * exp_length is bounded to 1024 and is > 32
-			// * exponent can be zero, so we subtract 1 after adding the other terms (whose sum
-			//   must be > 0)
-			// * the addition can't overflow because the terms are both capped at roughly
-			//   8 * max size of exp_length (1024)
-			// * the EIP spec is written in python, in which (exponent & (2**256 - 1)) takes the
-			//   FIRST 32 bytes. However this `BigUint` `&` operator takes the LAST 32 bytes.
-			//   We thus instead take the bytes manually.
-			let exponent_head = BigUint::from_bytes_be(&exponent_bytes[..32]);
-
-			iteration_count = (8 * (exp_length - 32)) + exponent_head.bits() - 1;
-		}
+/// Adjusted exponent length: the index of the highest bit of the exponent,
+/// approximated from its first 32 bytes for longer exponents.
+fn iteration_count(exp_len: u64, exponent_head: &BigUint) -> u64 {
+	let head_index = exponent_head.bits().saturating_sub(1);
+	if exp_len <= 32 {
+		head_index
+	} else {
+		(exp_len - 32).saturating_mul(8).saturating_add(head_index)
+	}
+}
 
-		max(iteration_count, 1)
+/// Length word, saturated to `u64`.
+fn read_len(buf: &[u8; 32]) -> u64 {
+	let len = U256::from_big_endian(buf);
+	if len > U256::from(u64::MAX) {
+		u64::MAX
+	} else {
+		len.as_u64()
 	}
+}
 
-	let multiplication_complexity = calculate_multiplication_complexity(base_length, mod_length);
-	let iteration_count = calculate_iteration_count(exponent, exponent_bytes);
-	max(
-		MIN_GAS_COST,
-		multiplication_complexity * iteration_count / 3,
-	)
-	.saturating_mul(if mod_is_even { 20 } else { 1 })
+/// Offset in the input, saturated to `usize`.
+fn offset(offset: u64) -> usize {
+	usize::try_from(offset).unwrap_or(usize::MAX)
 }
 
 /// Copy bytes from input to target.
@@ -79,7 +95,7 @@ fn read_input(source: &[u8], target: &mut [u8], source_offset: &mut usize) {
 	// We move the offset by the len of the target, regardless of what we
 	// actually copy.
 	let offset = *source_offset;
-	*source_offset += target.len();
+	*source_offset = source_offset.saturating_add(target.len());
 
 	// Out of bounds, nothing to copy.
 	if source.len() <= offset {
@@ -104,74 +120,79 @@ impl<G: GasMutState> PurePrecompile<G> for Modexp {
 		let mut mod_len_buf = [0u8; 32];
 		read_input(input, &mut mod_len_buf, &mut input_offset);
 
-		// reasonable assumption: this must fit within the Ethereum EVM's max stack size
-		let max_size_big = BigUint::from_u32(1024).expect("can't create BigUint");
+		let base_len = read_len(&base_len_buf);
+		let exp_len = read_len(&exp_len_buf);
+		let mod_len = read_len(&mod_len_buf);
 
-		let base_len_big = BigUint::from_bytes_be(&base_len_buf);
-		if base_len_big > max_size_big {
-			try_some!(Err(ExitException::Other(
-				"unreasonably large base length".into()
-			)));
+		if let Some(max_len) = self.max_len {
+			if base_len > max_len {
+				try_some!(Err(ExitException::Other(
+					"unreasonably large base length".into()
+				)));
+			}
+			if exp_len > max_len {
+				try_some!(Err(ExitException::Other(
+					"unreasonably large exponent length".into()
+				)));
+			}
+			if mod_len > max_len {
+				try_some!(Err(ExitException::Other(
+					"unreasonably large modulus length".into()
+				)));
+			}
 		}
 
-		let exp_len_big = BigUint::from_bytes_be(&exp_len_buf);
-		if exp_len_big > max_size_big {
-			try_some!(Err(ExitException::Other(
-				"unreasonably large exponent length".into()
-			)));
-		}
+		// Charge the gas before reading the numbers, so that large lengths run
+		// out of gas before they are allocated.
+		let mut exp_offset = offset(96u64.saturating_add(base_len));
+		let mod_offset = offset(96u64.saturating_add(base_len).saturating_add(exp_len));
+
+		let mut exp_head_buf = vec![0u8; offset(min(exp_len, 32))];
+		read_input(input, &mut exp_head_buf, &mut exp_offset);
+		let exponent_head = BigUint::from_bytes_be(&exp_head_buf);
 
-		let mod_len_big = BigUint::from_bytes_be(&mod_len_buf);
-		if mod_len_big > max_size_big {
-			try_some!(Err(ExitException::Other(
-				"unreasonably large modulus length".into()
-			)));
+		let mut mod_last_buf = [0u8; 1];
+		if mod_len > 0 {
+			let mut mod_last_offset = mod_offset.saturating_add(offset(mod_len - 1));
+			read_input(input, &mut mod_last_buf, &mut mod_last_offset);
 		}
+		let mod_is_even = mod_last_buf[0] % 2 == 0;
 
-		// bounds check handled above
-		let base_len = base_len_big.to_usize().expect("base_len out of bounds");
-		let exp_len = exp_len_big.to_usize().expect("exp_len out of bounds");
-		let mod_len = mod_len_big.to_usize().expect("mod_len out of bounds");
+		try_some!(gasometer.record_gas64(self.gas_cost(
+			base_len,
+			exp_len,
+			mod_len,
+			&exponent_head,
+			mod_is_even
+		)));
 
 		// if mod_len is 0 output must be empty
 		if mod_len == 0 {
 			return (ExitSucceed::Returned.into(), Vec::new());
 		}
 
-		// Gas formula allows arbitrary large exp_len when base and modulus are empty, so we need to handle empty base first.
-		let r = if base_len == 0 && mod_len == 0 {
-			try_some!(gasometer.record_gas(MIN_GAS_COST.into()));
+		// The lengths are bounded by the gas charged above.
+		let base_len = offset(base_len);
+		let exp_len = offset(exp_len);
+		let mod_len = offset(mod_len);
+
+		// read the numbers themselves.
+		let mut base_buf = vec![0u8; base_len];
+		read_input(input, &mut base_buf, &mut input_offset);
+		let base = BigUint::from_bytes_be(&base_buf);
+
+		let mut exp_buf = vec![0u8; exp_len];
+		read_input(input, &mut exp_buf, &mut input_offset);
+		let exponent = BigUint::from_bytes_be(&exp_buf);
+
+		let mut mod_buf = vec![0u8; mod_len];
+		read_input(input, &mut mod_buf, &mut input_offset);
+		let modulus = BigUint::from_bytes_be(&mod_buf);
+
+		let r = if modulus.is_zero() || modulus.is_one() {
 			BigUint::zero()
 		} else {
-			// read the numbers themselves.
-			let mut base_buf = vec![0u8; base_len];
-			read_input(input, &mut base_buf, &mut input_offset);
-			let base = BigUint::from_bytes_be(&base_buf);
-
-			let mut exp_buf = vec![0u8; exp_len];
-			read_input(input, &mut exp_buf, &mut input_offset);
-			let exponent = BigUint::from_bytes_be(&exp_buf);
-
-			let mut mod_buf = vec![0u8; mod_len];
-			read_input(input, &mut mod_buf, &mut input_offset);
-			let modulus = BigUint::from_bytes_be(&mod_buf);
-
-			// do our gas accounting
-			let gas_cost = calculate_gas_cost(
-				base_len as u64,
-				mod_len as u64,
-				&exponent,
-				&exp_buf,
-				modulus.is_even(),
-			);
-
-			try_some!(gasometer.record_gas(gas_cost.into()));
-
-			if modulus.is_zero() || modulus.is_one() {
-				BigUint::zero()
-			} else {
-				base.modpow(&exponent, &modulus)
-			}
+			base.modpow(&exponent, &modulus)
 		};
 
 		// write output to given memory, left padded and same length as the modulus.
@@ -0,0 +1,139 @@
+use alloc::vec::Vec;
+
+use evm::{
+	interpreter::error::{ExitResult, ExitSucceed},
+	GasMutState,
+};
+use p256::{
+	ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey},
+	EncodedPoint,
+};
+
+use crate::PurePrecompile;
+
+/// The P256VERIFY builtin of RIP-7212, verifying a secp256r1 signature, with
+/// its gas cost.
+///
+/// The input is the message hash, the `r` and `s` signature values, and the
+/// `x` and `y` public key coordinates, 32 bytes each. A valid signature
+/// returns 1 as a 32-byte word, anything else returns empty data.
+pub struct P256Verify(pub u64);
+
+impl<G: GasMutState> PurePrecompile<G> for P256Verify {
+	fn execute(&self, input: &[u8], gasometer: &mut G) -> (ExitResult, Vec<u8>) {
+		try_some!(gasometer.record_gas64(self.0));
+
+		if verify(input) {
+			let mut ret = [0u8; 32];
+			ret[31] = 1;
+			(ExitSucceed::Returned.into(), ret.to_vec())
+		} else {
+			(ExitSucceed::Returned.into(), Vec::new())
+		}
+	}
+}
+
+fn verify(input: &[u8]) -> bool {
+	if input.len() != 160 {
+		return false;
+	}
+
+	let signature = match Signature::from_slice(&input[32..96]) {
+		Ok(signature) => signature,
+		Err(_) => return false,
+	};
+	let point =
+		EncodedPoint::from_affine_coordinates(input[96..128].into(), input[128..160].into(), false);
+	let key = match VerifyingKey::from_encoded_point(&point) {
+		Ok(key) => key,
+		Err(_) => return false,
+	};
+
+	key.verify_prehash(&input[..32], &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::vec::Vec;
+
+	use evm::standard::{Config, PrecompileSet, State};
+
+	use super::verify;
+	use crate::{StandardPrecompileSet, P256_VERIFY_ADDRESS};
+
+	/// Valid signatures of the RIP-7212 test vectors, as message hash, `r`,
+	/// `s`, `x` and `y`.
+	const VALID: [&str; 3] = [
+		"4cee90eb86eaa050036147a12d49004b6b9c72bd725d39d4785011fe190f0b4da73bd4903f0ce3b639bbbf6e8e80d16931ff4bcf5993d58468e8fb19086e8cac36dbcd03009df8c59286b162af3bd7fcc0450c9aa81be5d10d312af6c66b1d604aebd3099c618202fcfe16ae7770b0c49ab5eadf74b754204a3bb6060e44eff37618b065f9832de4ca6ca971a7a1adc826d0f7c00181a5fb2ddf79ae00b4e10e",
+		"3fec5769b5cf4e310a7d150508e82fb8e3eda1c2c94c61492d3bd8aea99e06c9e22466e928fdccef0de49e3503d2657d00494a00e764fd437bdafa05f5922b1fbbb77c6817ccf50748419477e843d5bac67e6a70e97dde5a57e0c983b777e1ad31a80482dadf89de6302b1988c82c29544c9c07bb910596158f6062517eb089a2f54c9a0f348752950094d3228d3b940258c75fe2a413cb70baa21dc2e352fc5",
+		"e775723953ead4a90411a02908fd1a629db584bc600664c609061f221ef6bf7c440066c8626b49daaa7bf2bcc0b74be4f7a1e3dcf0e869f1542fe821498cbf2de73ad398194129f635de4424a07ca715838aefe8fe69d1a391cfa70470795a80dd056866e6e1125aff94413921880c437c9e2570a28ced7267c8beef7e9b2d8d1547d76dfcf4bee592f5fefe10ddfb6aeb0991c5b9dbbee6ec80d11b17c0eb1a",
+	];
+
+	fn decode(hex: &str) -> Vec<u8> {
+		(0..hex.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+			.collect()
+	}
+
+	#[test]
+	fn verifies_valid_signatures() {
+		for input in VALID {
+			assert!(verify(&decode(input)), "{}", input);
+		}
+	}
+
+	#[test]
+	fn rejects_invalid_inputs() {
+		let valid = decode(VALID[0]);
+		let with = |index: usize, byte: u8| {
+			let mut input = valid.clone();
+			input[index] = byte;
+			input
+		};
+
+		// Wrong message hash, signature and public key.
+		assert!(!verify(&with(31, valid[31] ^ 1)));
+		assert!(!verify(&with(63, valid[63] ^ 1)));
+		assert!(!verify(&with(95, valid[95] ^ 1)));
+		// A point that is not on the curve.
+		assert!(!verify(&with(159, valid[159] ^ 1)));
+		// Signature of another vector.
+		let mut input = valid.clone();
+		input[32..96].copy_from_slice(&decode(VALID[1])[32..96]);
+		assert!(!verify(&input));
+		// Zero `r` and `s`.
+		let mut input = valid.clone();
+		input[32..96].fill(0);
+		assert!(!verify(&input));
+		// `r` above the group order.
+		let mut input = valid.clone();
+		input[32..64].fill(0xff);
+		assert!(!verify(&input));
+		// Short and long input.
+		assert!(!verify(&valid[..159]));
+		let mut input = valid;
+		input.push(0);
+		assert!(!verify(&input));
+		assert!(!verify(&[]));
+	}
+
+	#[test]
+	fn is_a_precompile_only_if_enabled() {
+		let mut config = Config::cancun();
+		let set = StandardPrecompileSet::new(&config);
+		assert!(!PrecompileSet::<State, ()>::is_precompile(
+			&set,
+			P256_VERIFY_ADDRESS
+		));
+		assert!(!PrecompileSet::<State, ()>::addresses(&set).contains(&P256_VERIFY_ADDRESS));
+
+		config.rip_7212_enabled = true;
+		let set = StandardPrecompileSet::new(&config);
+		assert!(PrecompileSet::<State, ()>::is_precompile(
+			&set,
+			P256_VERIFY_ADDRESS
+		));
+		assert!(PrecompileSet::<State, ()>::addresses(&set).contains(&P256_VERIFY_ADDRESS));
+	}
+}
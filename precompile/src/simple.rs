@@ -5,22 +5,17 @@ use evm::{
 	GasMutState,
 };
 use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
-use primitive_types::{H256, U256};
+use primitive_types::H256;
 use sha3::{Digest, Keccak256};
 
-use crate::{linear_cost, PurePrecompile};
+use crate::{LinearCost, PurePrecompile};
 
-pub struct ECRecover;
+/// The ECRecover builtin, with its gas cost.
+pub struct ECRecover(pub LinearCost);
 
 impl<G: GasMutState> PurePrecompile<G> for ECRecover {
 	fn execute(&self, i: &[u8], gasometer: &mut G) -> (ExitResult, Vec<u8>) {
-		const COST_BASE: u64 = 3000;
-		const COST_WORD: u64 = 0;
-		try_some!(gasometer.record_gas(U256::from(try_some!(linear_cost(
-			i.len() as u64,
-			COST_BASE,
-			COST_WORD
-		)))));
+		try_some!(gasometer.record_gas64(try_some!(self.0.cost(i.len() as u64))));
 
 		let mut input = [0u8; 128];
 		input[..min(i.len(), 128)].copy_from_slice(&i[..min(i.len(), 128)]);
@@ -52,17 +47,12 @@ impl<G: GasMutState> PurePrecompile<G> for ECRecover {
 	}
 }
 
-pub struct Sha256;
+/// The Sha256 builtin, with its gas cost.
+pub struct Sha256(pub LinearCost);
 
 impl<G: GasMutState> PurePrecompile<G> for Sha256 {
 	fn execute(&self, input: &[u8], gasometer: &mut G) -> (ExitResult, Vec<u8>) {
-		const COST_BASE: u64 = 600;
-		const COST_WORD: u64 = 120;
-		try_some!(gasometer.record_gas(U256::from(try_some!(linear_cost(
-			input.len() as u64,
-			COST_BASE,
-			COST_WORD
-		)))));
+		try_some!(gasometer.record_gas64(try_some!(self.0.cost(input.len() as u64))));
 
 		let mut ret = [0u8; 32];
 		let hash = ripemd::Ripemd160::digest(input);
@@ -72,17 +62,12 @@ impl<G: GasMutState> PurePrecompile<G> for Sha256 {
 	}
 }
 
-pub struct Ripemd160;
+/// The Ripemd160 builtin, with its gas cost.
+pub struct Ripemd160(pub LinearCost);
 
 impl<G: GasMutState> PurePrecompile<G> for Ripemd160 {
 	fn execute(&self, input: &[u8], gasometer: &mut G) -> (ExitResult, Vec<u8>) {
-		const COST_BASE: u64 = 60;
-		const COST_WORD: u64 = 12;
-		try_some!(gasometer.record_gas(U256::from(try_some!(linear_cost(
-			input.len() as u64,
-			COST_BASE,
-			COST_WORD
-		)))));
+		try_some!(gasometer.record_gas64(try_some!(self.0.cost(input.len() as u64))));
 
 		let hash = sha2::Sha256::digest(input);
 
@@ -90,17 +75,12 @@ impl<G: GasMutState> PurePrecompile<G> for Ripemd160 {
 	}
 }
 
-pub struct Identity;
+/// The Identity builtin, with its gas cost.
+pub struct Identity(pub LinearCost);
 
 impl<G: GasMutState> PurePrecompile<G> for Identity {
 	fn execute(&self, input: &[u8], gasometer: &mut G) -> (ExitResult, Vec<u8>) {
-		const COST_BASE: u64 = 15;
-		const COST_WORD: u64 = 3;
-		try_some!(gasometer.record_gas(U256::from(try_some!(linear_cost(
-			input.len() as u64,
-			COST_BASE,
-			COST_WORD
-		)))));
+		try_some!(gasometer.record_gas64(try_some!(self.0.cost(input.len() as u64))));
 
 		(ExitSucceed::Returned.into(), input.to_vec())
 	}
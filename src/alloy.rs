@@ -0,0 +1,283 @@
+//! # alloy interoperability
+//!
+//! Conversions between the primitive types used by this crate and the ones of
+//! `alloy-primitives`, enabled by the `alloy` feature. As both sides are
+//! foreign types, the conversions are the [ToAlloy] and [FromAlloy] traits
+//! instead of `From`.
+//!
+//! Transactions of `alloy-consensus`, with their recovered senders, are
+//! converted into [TransactArgs] with [TransactArgs::from_alloy].
+//!
+//! The `alloy` feature needs Rust 1.81.
+
+use alloc::vec::Vec;
+use core::cmp::min;
+
+use alloy_consensus::{transaction::Recovered, Transaction};
+use alloy_primitives::{Address, TxKind, B256};
+use primitive_types::{H160, H256, U256};
+
+use crate::standard::TransactArgs;
+
+/// Convert a value into its `alloy-primitives` counterpart.
+pub trait ToAlloy {
+	/// The `alloy-primitives` type.
+	type Alloy;
+
+	/// Convert the value.
+	fn to_alloy(self) -> Self::Alloy;
+}
+
+/// Convert a value from its `alloy-primitives` counterpart.
+pub trait FromAlloy<T> {
+	/// Convert the value.
+	fn from_alloy(value: T) -> Self;
+}
+
+impl ToAlloy for H160 {
+	type Alloy = Address;
+
+	fn to_alloy(self) -> Address {
+		Address::from(self.0)
+	}
+}
+
+impl FromAlloy<Address> for H160 {
+	fn from_alloy(value: Address) -> Self {
+		Self(value.into_array())
+	}
+}
+
+impl ToAlloy for H256 {
+	type Alloy = B256;
+
+	fn to_alloy(self) -> B256 {
+		B256::from(self.0)
+	}
+}
+
+impl FromAlloy<B256> for H256 {
+	fn from_alloy(value: B256) -> Self {
+		Self(value.0)
+	}
+}
+
+impl ToAlloy for U256 {
+	type Alloy = alloy_primitives::U256;
+
+	fn to_alloy(self) -> alloy_primitives::U256 {
+		// Both are little endian 64-bit limbs.
+		alloy_primitives::U256::from_limbs(self.0)
+	}
+}
+
+impl FromAlloy<alloy_primitives::U256> for U256 {
+	fn from_alloy(value: alloy_primitives::U256) -> Self {
+		Self(*value.as_limbs())
+	}
+}
+
+impl TransactArgs {
+	/// Transaction arguments to execute an alloy transaction, sent by its
+	/// recovered signer, in a block with the base fee. For dynamic fee
+	/// transactions, the priority fee is capped so that the gas price is at
+	/// most the maximum fee.
+	pub fn from_alloy<T: Transaction>(transaction: &Recovered<T>, base_fee: U256) -> Self {
+		let caller = H160::from_alloy(transaction.signer());
+		let transaction = transaction.inner();
+
+		let max_fee_per_gas = U256::from(transaction.max_fee_per_gas());
+		let gas_price = if transaction.is_dynamic_fee() {
+			let max_priority_fee_per_gas =
+				U256::from(transaction.max_priority_fee_per_gas().unwrap_or_default());
+			min(
+				max_fee_per_gas,
+				base_fee.saturating_add(max_priority_fee_per_gas),
+			)
+		} else {
+			max_fee_per_gas
+		};
+		let access_list = transaction
+			.access_list()
+			.map(|access_list| {
+				access_list
+					.iter()
+					.map(|item| {
+						(
+							H160::from_alloy(item.address),
+							item.storage_keys
+								.iter()
+								.map(|key| H256::from_alloy(*key))
+								.collect(),
+						)
+					})
+					.collect()
+			})
+			.unwrap_or_default();
+		let nonce = Some(U256::from(transaction.nonce()));
+		let chain_id = transaction.chain_id().map(U256::from);
+		let value = U256::from_alloy(transaction.value());
+		let data: Vec<u8> = transaction.input().to_vec();
+		let gas_limit = U256::from(transaction.gas_limit());
+
+		match transaction.kind() {
+			TxKind::Call(address) => Self::Call {
+				caller,
+				address: H160::from_alloy(address),
+				nonce,
+				chain_id,
+				value,
+				data,
+				gas_limit,
+				gas_price,
+				access_list,
+				system: false,
+				mint: None,
+				fee_payer: None,
+			},
+			TxKind::Create => Self::Create {
+				caller,
+				nonce,
+				chain_id,
+				value,
+				init_code: data,
+				salt: None,
+				gas_limit,
+				gas_price,
+				access_list,
+				system: false,
+				mint: None,
+				fee_payer: None,
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::vec;
+
+	use alloy_consensus::{transaction::Recovered, TxEip1559, TxLegacy};
+	use alloy_eips::eip2930::{AccessList, AccessListItem};
+	use alloy_primitives::{Address, Bytes, TxKind, B256};
+	use primitive_types::{H160, H256, U256};
+
+	use super::{FromAlloy, ToAlloy};
+	use crate::standard::TransactArgs;
+
+	#[test]
+	fn converts_primitives_both_ways() {
+		let address = H160::repeat_byte(0x11);
+		let hash = H256::repeat_byte(0x22);
+		let value = U256::MAX - U256::from(7);
+
+		assert_eq!(address.to_alloy(), Address::repeat_byte(0x11));
+		assert_eq!(H160::from_alloy(address.to_alloy()), address);
+		assert_eq!(hash.to_alloy(), B256::repeat_byte(0x22));
+		assert_eq!(H256::from_alloy(hash.to_alloy()), hash);
+		assert_eq!(
+			value.to_alloy(),
+			alloy_primitives::U256::MAX - alloy_primitives::U256::from(7)
+		);
+		assert_eq!(U256::from_alloy(value.to_alloy()), value);
+	}
+
+	#[test]
+	fn converts_dynamic_fee_call() {
+		let signer = Address::repeat_byte(0x10);
+		let transaction = TxEip1559 {
+			chain_id: 1,
+			nonce: 3,
+			gas_limit: 50_000,
+			max_fee_per_gas: 30,
+			max_priority_fee_per_gas: 2,
+			to: TxKind::Call(Address::repeat_byte(0x20)),
+			value: alloy_primitives::U256::from(5),
+			access_list: AccessList(vec![AccessListItem {
+				address: Address::repeat_byte(0x30),
+				storage_keys: vec![B256::repeat_byte(0x01)],
+			}]),
+			input: Bytes::from(vec![0xaa, 0xbb]),
+		};
+
+		let args =
+			TransactArgs::from_alloy(&Recovered::new_unchecked(transaction, signer), 10.into());
+		match args {
+			TransactArgs::Call {
+				caller,
+				address,
+				nonce,
+				chain_id,
+				value,
+				data,
+				gas_limit,
+				gas_price,
+				access_list,
+				..
+			} => {
+				assert_eq!(caller, H160::repeat_byte(0x10));
+				assert_eq!(address, H160::repeat_byte(0x20));
+				assert_eq!(nonce, Some(U256::from(3)));
+				assert_eq!(chain_id, Some(U256::one()));
+				assert_eq!(value, U256::from(5));
+				assert_eq!(data, vec![0xaa, 0xbb]);
+				assert_eq!(gas_limit, U256::from(50_000));
+				assert_eq!(gas_price, U256::from(12));
+				assert_eq!(
+					access_list,
+					vec![(H160::repeat_byte(0x30), vec![H256::repeat_byte(0x01)])]
+				);
+			}
+			TransactArgs::Create { .. } => panic!("expected a call"),
+		}
+
+		// The priority fee is capped by the maximum fee.
+		let args = TransactArgs::from_alloy(
+			&Recovered::new_unchecked(
+				TxEip1559 {
+					max_fee_per_gas: 30,
+					max_priority_fee_per_gas: 2,
+					..Default::default()
+				},
+				signer,
+			),
+			29.into(),
+		);
+		assert_eq!(args.gas_price(), U256::from(30));
+	}
+
+	#[test]
+	fn converts_legacy_create() {
+		let transaction = TxLegacy {
+			chain_id: None,
+			nonce: 0,
+			gas_price: 7,
+			gas_limit: 100_000,
+			to: TxKind::Create,
+			value: alloy_primitives::U256::ZERO,
+			input: Bytes::from(vec![0x60, 0x00]),
+		};
+
+		let args = TransactArgs::from_alloy(
+			&Recovered::new_unchecked(transaction, Address::repeat_byte(0x10)),
+			100.into(),
+		);
+		match args {
+			TransactArgs::Create {
+				caller,
+				chain_id,
+				init_code,
+				gas_price,
+				access_list,
+				..
+			} => {
+				assert_eq!(caller, H160::repeat_byte(0x10));
+				assert_eq!(chain_id, None);
+				assert_eq!(init_code, vec![0x60, 0x00]);
+				assert_eq!(gas_price, U256::from(7));
+				assert!(access_list.is_empty());
+			}
+			TransactArgs::Call { .. } => panic!("expected a create"),
+		}
+	}
+}
@@ -0,0 +1,47 @@
+use evm_interpreter::runtime::{BlockHashProvider, BLOCK_HASH_WINDOW};
+use primitive_types::{H256, U256};
+
+const WINDOW: usize = BLOCK_HASH_WINDOW as usize;
+
+/// Block hashes of the [BLOCK_HASH_WINDOW] most recent blocks, in a ring
+/// buffer. A block executor pushes the hash of each block after processing
+/// it, and older hashes are overwritten.
+///
+/// Blocks that were not pushed, or that were overwritten, get the zero hash.
+#[derive(Clone, Debug)]
+pub struct BlockHashRing {
+	entries: [Option<(U256, H256)>; WINDOW],
+}
+
+impl Default for BlockHashRing {
+	fn default() -> Self {
+		Self {
+			entries: [None; WINDOW],
+		}
+	}
+}
+
+impl BlockHashRing {
+	/// Create an empty ring buffer.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Record the hash of the block with the number.
+	pub fn push(&mut self, number: U256, hash: H256) {
+		self.entries[Self::index(number)] = Some((number, hash));
+	}
+
+	fn index(number: U256) -> usize {
+		(number % U256::from(WINDOW)).as_usize()
+	}
+}
+
+impl BlockHashProvider for BlockHashRing {
+	fn block_hash(&self, number: U256) -> H256 {
+		match self.entries[Self::index(number)] {
+			Some((recorded, hash)) if recorded == number => hash,
+			_ => H256::default(),
+		}
+	}
+}
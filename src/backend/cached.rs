@@ -0,0 +1,399 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::cell::RefCell;
+
+use evm_interpreter::{
+	error::ExitError,
+	runtime::{
+		BlockHashProvider, Log, RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment,
+		SetCodeOrigin, Transfer,
+	},
+};
+use primitive_types::{H160, H256, U256};
+
+use super::TransactionalBackend;
+use crate::MergeStrategy;
+
+/// Hit and miss counts of a cache.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+	/// Reads served from the cache.
+	pub hits: u64,
+	/// Reads forwarded to the wrapped backend.
+	pub misses: u64,
+}
+
+impl CacheStats {
+	/// Total number of reads.
+	pub fn reads(&self) -> u64 {
+		self.hits.saturating_add(self.misses)
+	}
+
+	/// Ratio of reads served from the cache, between 0 and 1. Zero if there
+	/// was no read.
+	pub fn hit_rate(&self) -> f64 {
+		match self.reads() {
+			0 => 0.0,
+			reads => self.hits as f64 / reads as f64,
+		}
+	}
+}
+
+/// Cache statistics of a [CachedBackend], by kind of read.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CachedBackendStats {
+	/// Balance, nonce and existence reads.
+	pub accounts: CacheStats,
+	/// Code, code size and code hash reads.
+	pub code: CacheStats,
+	/// Storage reads.
+	pub storage: CacheStats,
+}
+
+#[derive(Default)]
+struct Cache {
+	balances: BTreeMap<H160, U256>,
+	nonces: BTreeMap<H160, U256>,
+	exists: BTreeMap<H160, bool>,
+	codes: BTreeMap<H160, Vec<u8>>,
+	code_sizes: BTreeMap<H160, U256>,
+	code_hashes: BTreeMap<H160, H256>,
+	storages: BTreeMap<(H160, H256), H256>,
+	stats: CachedBackendStats,
+}
+
+impl Cache {
+	fn invalidate_balance(&mut self, address: H160) {
+		self.balances.remove(&address);
+		self.exists.remove(&address);
+	}
+
+	fn invalidate_code(&mut self, address: H160) {
+		self.codes.remove(&address);
+		self.code_sizes.remove(&address);
+		self.code_hashes.remove(&address);
+		self.exists.remove(&address);
+	}
+}
+
+fn cached<K: Ord, V: Clone, F: FnOnce() -> V>(
+	map: &mut BTreeMap<K, V>,
+	stats: &mut CacheStats,
+	key: K,
+	read: F,
+) -> V {
+	if let Some(value) = map.get(&key) {
+		stats.hits += 1;
+		return value.clone();
+	}
+
+	stats.misses += 1;
+	let value = read();
+	map.insert(key, value.clone());
+	value
+}
+
+/// A backend that memoizes account, code and storage reads of the wrapped
+/// backend, for example a database.
+///
+/// The cache is never evicted. Call [CachedBackend::clear] when the wrapped
+/// state changes outside of this backend, for example between blocks. Writes
+/// made through this backend, when the wrapped backend is a
+/// [RuntimeBackend], invalidate the affected entries, and reverted substates
+/// of a wrapped [TransactionalBackend] clear the cache. Environment, block
+/// hash and transient storage reads are not cached.
+pub struct CachedBackend<B> {
+	backend: B,
+	cache: RefCell<Cache>,
+}
+
+impl<B> CachedBackend<B> {
+	/// Create a new caching backend with an empty cache.
+	pub fn new(backend: B) -> Self {
+		Self {
+			backend,
+			cache: RefCell::new(Cache::default()),
+		}
+	}
+
+	/// The wrapped backend.
+	pub fn backend(&self) -> &B {
+		&self.backend
+	}
+
+	/// Mutable reference to the wrapped backend. The cache is cleared, as the
+	/// state might be changed through it.
+	pub fn backend_mut(&mut self) -> &mut B {
+		self.clear();
+		&mut self.backend
+	}
+
+	/// Cache statistics since creation, or the last
+	/// [CachedBackend::reset_stats].
+	pub fn stats(&self) -> CachedBackendStats {
+		self.cache.borrow().stats
+	}
+
+	/// Reset the cache statistics.
+	pub fn reset_stats(&mut self) {
+		self.cache.get_mut().stats = CachedBackendStats::default();
+	}
+
+	/// Drop all cached values. Statistics are kept.
+	pub fn clear(&mut self) {
+		let stats = self.stats();
+		*self.cache.get_mut() = Cache {
+			stats,
+			..Cache::default()
+		};
+	}
+
+	/// Return the wrapped backend.
+	pub fn into_inner(self) -> B {
+		self.backend
+	}
+}
+
+impl<B: BlockHashProvider> BlockHashProvider for CachedBackend<B> {
+	fn block_hash(&self, number: U256) -> H256 {
+		self.backend.block_hash(number)
+	}
+}
+
+impl<B: RuntimeEnvironment> RuntimeEnvironment for CachedBackend<B> {
+	fn block_number(&self) -> U256 {
+		self.backend.block_number()
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.backend.block_coinbase()
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.backend.block_timestamp()
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.backend.block_difficulty()
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.backend.block_randomness()
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.backend.block_parent_beacon_root()
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.backend.block_gas_limit()
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.backend.block_base_fee_per_gas()
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		self.backend.blob_base_fee()
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.backend.chain_id()
+	}
+}
+
+impl<B: RuntimeBaseBackend> RuntimeBaseBackend for CachedBackend<B> {
+	fn balance(&self, address: H160) -> U256 {
+		let cache = &mut *self.cache.borrow_mut();
+		cached(
+			&mut cache.balances,
+			&mut cache.stats.accounts,
+			address,
+			|| self.backend.balance(address),
+		)
+	}
+
+	fn code_size(&self, address: H160) -> U256 {
+		let cache = &mut *self.cache.borrow_mut();
+		cached(
+			&mut cache.code_sizes,
+			&mut cache.stats.code,
+			address,
+			|| self.backend.code_size(address),
+		)
+	}
+
+	fn code_hash(&self, address: H160) -> H256 {
+		let cache = &mut *self.cache.borrow_mut();
+		cached(
+			&mut cache.code_hashes,
+			&mut cache.stats.code,
+			address,
+			|| self.backend.code_hash(address),
+		)
+	}
+
+	fn keccak256(&self, data: &[u8]) -> H256 {
+		self.backend.keccak256(data)
+	}
+
+	fn ecrecover(&self, hash: H256, y_parity: u8, r: H256, s: H256) -> Option<H160> {
+		self.backend.ecrecover(hash, y_parity, r, s)
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		let cache = &mut *self.cache.borrow_mut();
+		cached(&mut cache.codes, &mut cache.stats.code, address, || {
+			self.backend.code(address)
+		})
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		let cache = &mut *self.cache.borrow_mut();
+		cached(
+			&mut cache.storages,
+			&mut cache.stats.storage,
+			(address, index),
+			|| self.backend.storage(address, index),
+		)
+	}
+
+	fn transient_storage(&self, address: H160, index: H256) -> H256 {
+		self.backend.transient_storage(address, index)
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		let cache = &mut *self.cache.borrow_mut();
+		cached(
+			&mut cache.exists,
+			&mut cache.stats.accounts,
+			address,
+			|| self.backend.exists(address),
+		)
+	}
+
+	fn nonce(&self, address: H160) -> U256 {
+		let cache = &mut *self.cache.borrow_mut();
+		cached(
+			&mut cache.nonces,
+			&mut cache.stats.accounts,
+			address,
+			|| self.backend.nonce(address),
+		)
+	}
+
+	fn prefetch(&self, addresses: &[H160], keys: &[(H160, H256)]) {
+		self.backend.prefetch(addresses, keys)
+	}
+}
+
+impl<B: RuntimeBackend> RuntimeBackend for CachedBackend<B> {
+	fn original_storage(&self, address: H160, index: H256) -> H256 {
+		self.backend.original_storage(address, index)
+	}
+
+	fn deleted(&self, address: H160) -> bool {
+		self.backend.deleted(address)
+	}
+
+	fn is_cold(&self, address: H160, index: Option<H256>) -> bool {
+		self.backend.is_cold(address, index)
+	}
+
+	fn mark_hot(&mut self, address: H160, index: Option<H256>) {
+		self.backend.mark_hot(address, index)
+	}
+
+	fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError> {
+		self.cache.get_mut().storages.remove(&(address, index));
+		self.backend.set_storage(address, index, value)
+	}
+
+	fn set_transient_storage(
+		&mut self,
+		address: H160,
+		index: H256,
+		value: H256,
+	) -> Result<(), ExitError> {
+		self.backend.set_transient_storage(address, index, value)
+	}
+
+	fn log(&mut self, log: Log) -> Result<(), ExitError> {
+		self.backend.log(log)
+	}
+
+	fn mark_delete(&mut self, address: H160) {
+		self.backend.mark_delete(address)
+	}
+
+	fn reset_storage(&mut self, address: H160) {
+		self.cache
+			.get_mut()
+			.storages
+			.retain(|(cached, _), _| *cached != address);
+		self.backend.reset_storage(address)
+	}
+
+	fn set_code(
+		&mut self,
+		address: H160,
+		code: Vec<u8>,
+		origin: SetCodeOrigin,
+	) -> Result<(), ExitError> {
+		self.cache.get_mut().invalidate_code(address);
+		self.backend.set_code(address, code, origin)
+	}
+
+	fn reset_balance(&mut self, address: H160) {
+		self.cache.get_mut().invalidate_balance(address);
+		self.backend.reset_balance(address)
+	}
+
+	fn deposit(&mut self, target: H160, value: U256) {
+		self.cache.get_mut().invalidate_balance(target);
+		self.backend.deposit(target, value)
+	}
+
+	fn withdrawal(&mut self, source: H160, value: U256) -> Result<(), ExitError> {
+		self.cache.get_mut().invalidate_balance(source);
+		self.backend.withdrawal(source, value)
+	}
+
+	fn transfer(&mut self, transfer: Transfer) -> Result<(), ExitError> {
+		let cache = self.cache.get_mut();
+		cache.invalidate_balance(transfer.source);
+		cache.invalidate_balance(transfer.target);
+		self.backend.transfer(transfer)
+	}
+
+	fn inc_nonce(&mut self, address: H160) -> Result<(), ExitError> {
+		let cache = self.cache.get_mut();
+		cache.nonces.remove(&address);
+		cache.exists.remove(&address);
+		self.backend.inc_nonce(address)
+	}
+
+	fn touch(&mut self, address: H160) {
+		self.backend.touch(address)
+	}
+
+	fn take_touched(&mut self) -> Vec<H160> {
+		self.backend.take_touched()
+	}
+}
+
+impl<B: TransactionalBackend> TransactionalBackend for CachedBackend<B> {
+	fn push_substate(&mut self) {
+		self.backend.push_substate()
+	}
+
+	fn pop_substate(&mut self, strategy: MergeStrategy) {
+		self.backend.pop_substate(strategy);
+		if !matches!(strategy, MergeStrategy::Commit) {
+			self.clear();
+		}
+	}
+
+	fn substate_logs(&self) -> Vec<Log> {
+		self.backend.substate_logs()
+	}
+}
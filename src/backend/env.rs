@@ -0,0 +1,198 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use evm_interpreter::runtime::{BlockHashProvider, RuntimeBaseBackend, RuntimeEnvironment};
+use primitive_types::{H160, H256, U256};
+
+/// Block environment as a plain value. This implements
+/// [RuntimeEnvironment], so that a backend only has to provide the state,
+/// see [BlockEnvBackend].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockEnv {
+	/// Block number.
+	pub number: U256,
+	/// Block timestamp.
+	pub timestamp: U256,
+	/// Block coinbase.
+	pub coinbase: H160,
+	/// Block gas limit.
+	pub gas_limit: U256,
+	/// Block base fee per gas. Zero before London.
+	pub base_fee: U256,
+	/// Blob base fee. Zero before Cancun.
+	pub blob_base_fee: U256,
+	/// Block difficulty. Only used before the merge.
+	pub difficulty: U256,
+	/// Block randomness (see EIP-4399). `None` before the merge.
+	pub prevrandao: Option<H256>,
+	/// Parent beacon block root (see EIP-4788). `None` before Cancun.
+	pub parent_beacon_root: Option<H256>,
+	/// Chain ID.
+	pub chain_id: U256,
+	/// Hashes of the previous blocks, by number. Blocks without a hash get
+	/// the zero hash.
+	pub block_hashes: BTreeMap<U256, H256>,
+}
+
+impl BlockHashProvider for BlockEnv {
+	fn block_hash(&self, number: U256) -> H256 {
+		self.block_hashes.get(&number).cloned().unwrap_or_default()
+	}
+}
+
+impl RuntimeEnvironment for BlockEnv {
+	fn block_number(&self) -> U256 {
+		self.number
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.coinbase
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.timestamp
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.difficulty
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.prevrandao
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.parent_beacon_root
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.gas_limit
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.base_fee
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		self.blob_base_fee
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.chain_id
+	}
+}
+
+/// A backend combining a [BlockEnv] with a state-only backend. The
+/// environment is read from the block environment, and the state from the
+/// wrapped backend, which only has to implement [RuntimeBaseBackend].
+///
+/// Like other base backends, wrap it in an [crate::backend::OverlayedBackend]
+/// to execute transactions.
+#[derive(Clone, Debug)]
+pub struct BlockEnvBackend<B> {
+	/// Block environment.
+	pub env: BlockEnv,
+	/// State backend.
+	pub state: B,
+}
+
+impl<B> BlockEnvBackend<B> {
+	/// Create a new backend with the block environment and the state.
+	pub fn new(env: BlockEnv, state: B) -> Self {
+		Self { env, state }
+	}
+}
+
+impl<B> BlockHashProvider for BlockEnvBackend<B> {
+	fn block_hash(&self, number: U256) -> H256 {
+		self.env.block_hash(number)
+	}
+}
+
+impl<B> RuntimeEnvironment for BlockEnvBackend<B> {
+	fn block_number(&self) -> U256 {
+		self.env.block_number()
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.env.block_coinbase()
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.env.block_timestamp()
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.env.block_difficulty()
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.env.block_randomness()
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.env.block_parent_beacon_root()
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.env.block_gas_limit()
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.env.block_base_fee_per_gas()
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		self.env.blob_base_fee()
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.env.chain_id()
+	}
+}
+
+impl<B: RuntimeBaseBackend> RuntimeBaseBackend for BlockEnvBackend<B> {
+	fn balance(&self, address: H160) -> U256 {
+		self.state.balance(address)
+	}
+
+	fn code_size(&self, address: H160) -> U256 {
+		self.state.code_size(address)
+	}
+
+	fn code_hash(&self, address: H160) -> H256 {
+		self.state.code_hash(address)
+	}
+
+	fn keccak256(&self, data: &[u8]) -> H256 {
+		self.state.keccak256(data)
+	}
+
+	fn ecrecover(&self, hash: H256, y_parity: u8, r: H256, s: H256) -> Option<H160> {
+		self.state.ecrecover(hash, y_parity, r, s)
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.state.code(address)
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.state.storage(address, index)
+	}
+
+	fn transient_storage(&self, address: H160, index: H256) -> H256 {
+		self.state.transient_storage(address, index)
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.state.exists(address)
+	}
+
+	fn nonce(&self, address: H160) -> U256 {
+		self.state.nonce(address)
+	}
+
+	fn prefetch(&self, addresses: &[H160], keys: &[(H160, H256)]) {
+		self.state.prefetch(addresses, keys)
+	}
+}
@@ -0,0 +1,164 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use primitive_types::{H160, H256, U256};
+
+/// Accounts of a genesis state, by address. This is the `alloc` section of
+/// a geth `genesis.json`.
+pub type GenesisAlloc = BTreeMap<H160, GenesisAccount>;
+
+/// An account of a genesis state.
+///
+/// With the `serde` feature, this uses the format of geth: quantities are hex
+/// or decimal strings, code is a hex string, and storage keys and values can
+/// omit leading zeros. Empty fields can be omitted, and are not serialized.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenesisAccount {
+	#[cfg_attr(feature = "serde", serde(default, with = "serde_quantity"))]
+	pub balance: U256,
+	#[cfg_attr(
+		feature = "serde",
+		serde(
+			default,
+			with = "serde_quantity",
+			skip_serializing_if = "U256::is_zero"
+		)
+	)]
+	pub nonce: U256,
+	#[cfg_attr(
+		feature = "serde",
+		serde(default, with = "serde_bytes", skip_serializing_if = "Vec::is_empty")
+	)]
+	pub code: Vec<u8>,
+	#[cfg_attr(
+		feature = "serde",
+		serde(
+			default,
+			with = "serde_storage",
+			skip_serializing_if = "BTreeMap::is_empty"
+		)
+	)]
+	pub storage: BTreeMap<H256, H256>,
+}
+
+/// A geth `genesis.json`. Only the `alloc` section is read, the other
+/// fields are ignored.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Genesis {
+	pub alloc: GenesisAlloc,
+}
+
+#[cfg(feature = "serde")]
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+	let value = value.strip_prefix("0x").unwrap_or(value);
+	let digits = value
+		.chars()
+		.map(|c| c.to_digit(16).map(|d| d as u8))
+		.collect::<Option<Vec<_>>>()?;
+
+	// An odd number of digits has an implicit leading zero.
+	let mut bytes = Vec::with_capacity((digits.len() + 1) / 2);
+	let (head, rest) = digits.split_at(digits.len() % 2);
+	bytes.extend_from_slice(head);
+	for pair in rest.chunks(2) {
+		bytes.push((pair[0] << 4) | pair[1]);
+	}
+	Some(bytes)
+}
+
+#[cfg(feature = "serde")]
+fn decode_word<E: serde::de::Error>(value: &str) -> Result<H256, E> {
+	let bytes = decode_hex(value).ok_or_else(|| E::custom("invalid hex string"))?;
+	if bytes.len() > 32 {
+		return Err(E::custom("hex string longer than 32 bytes"));
+	}
+	let mut word = H256::default();
+	word[(32 - bytes.len())..].copy_from_slice(&bytes);
+	Ok(word)
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod serde_quantity {
+	use alloc::format;
+	use core::fmt;
+
+	use primitive_types::U256;
+	use serde::{de, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&format!("{value:#x}"))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+		struct Visitor;
+
+		impl<'de> de::Visitor<'de> for Visitor {
+			type Value = U256;
+
+			fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				f.write_str("a hex or decimal quantity")
+			}
+
+			fn visit_u64<E: de::Error>(self, value: u64) -> Result<U256, E> {
+				Ok(U256::from(value))
+			}
+
+			fn visit_str<E: de::Error>(self, value: &str) -> Result<U256, E> {
+				let value = match value.strip_prefix("0x") {
+					Some(hex) => U256::from_str_radix(hex, 16).ok(),
+					None => U256::from_dec_str(value).ok(),
+				};
+				value.ok_or_else(|| E::custom("invalid quantity"))
+			}
+		}
+
+		deserializer.deserialize_any(Visitor)
+	}
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod serde_bytes {
+	use alloc::{string::String, vec::Vec};
+	use core::fmt::Write;
+
+	use serde::{de, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+		let mut hex = String::with_capacity(2 + value.len() * 2);
+		hex.push_str("0x");
+		for byte in value {
+			let _ = write!(hex, "{byte:02x}");
+		}
+		serializer.serialize_str(&hex)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		super::decode_hex(&value).ok_or_else(|| de::Error::custom("invalid hex string"))
+	}
+}
+
+#[cfg(feature = "serde")]
+mod serde_storage {
+	use alloc::{collections::BTreeMap, string::String};
+
+	use primitive_types::H256;
+	use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+	pub fn serialize<S: Serializer>(
+		value: &BTreeMap<H256, H256>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		value.serialize(serializer)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<BTreeMap<H256, H256>, D::Error> {
+		BTreeMap::<String, String>::deserialize(deserializer)?
+			.iter()
+			.map(|(index, value)| Ok((super::decode_word(index)?, super::decode_word(value)?)))
+			.collect()
+	}
+}
@@ -11,11 +11,34 @@
 //! pushing/poping layers are dealt by extern functions), layers are handled
 //! internally inside a backend.
 
+mod block_hash;
+mod cached;
+mod env;
+mod genesis;
+mod original;
 mod overlayed;
+mod proof;
+mod replay;
+mod testing;
 
-pub use evm_interpreter::runtime::{RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment};
+use alloc::vec::Vec;
 
-pub use self::overlayed::{OverlayedBackend, OverlayedChangeSet};
+use evm_interpreter::runtime::Log;
+pub use evm_interpreter::runtime::{
+	BlockHashProvider, RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment,
+};
+
+pub use self::{
+	block_hash::BlockHashRing,
+	cached::{CacheStats, CachedBackend, CachedBackendStats},
+	env::{BlockEnv, BlockEnvBackend},
+	genesis::{Genesis, GenesisAccount, GenesisAlloc},
+	original::OriginalStorage,
+	overlayed::{Apply, OverlayedBackend, OverlayedChangeSet},
+	proof::{AccountProof, StorageProof},
+	replay::{RecordedAccount, RecordedEnvironment, Recording, RecordingBackend, ReplayBackend},
+	testing::{TestAccount, TestBackend, TestEnvironment},
+};
 
 /// Backend with layers that can transactionally be committed or discarded.
 pub trait TransactionalBackend {
@@ -27,4 +50,25 @@ pub trait TransactionalBackend {
 	/// The caller is expected to maintain balance of push/pop, and the backend
 	/// are free to panic if it does not.
 	fn pop_substate(&mut self, strategy: crate::MergeStrategy);
+	/// Logs emitted in the current substate layer, including the ones from
+	/// committed child layers. Logs of reverted or discarded layers are not
+	/// included.
+	fn substate_logs(&self) -> Vec<Log>;
+}
+
+/// Identifier of a backend snapshot.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SnapshotId(pub usize);
+
+/// Backend that can snapshot its whole state and later revert to it.
+///
+/// Unlike substates of [TransactionalBackend], snapshots work across whole
+/// transactions. They should only be taken or reverted to when no transaction
+/// is in progress.
+pub trait SnapshotBackend {
+	/// Take a snapshot of the current state.
+	fn snapshot(&mut self) -> SnapshotId;
+	/// Revert to the given snapshot. The snapshot and all the ones taken
+	/// after it are removed. Returns `false` if the snapshot does not exist.
+	fn revert_to(&mut self, id: SnapshotId) -> bool;
 }
@@ -0,0 +1,45 @@
+use alloc::collections::BTreeMap;
+
+use primitive_types::{H160, H256};
+
+/// Original values of storage slots, the values at the start of the current
+/// transaction, as needed by the `SSTORE` gas and refund rules (see EIP-2200
+/// and EIP-3529).
+///
+/// A backend records the current value of a slot before writing it. Only the
+/// first write of each slot in a transaction is recorded, and slots that were
+/// not written still have their original value. Clear it at the end of each
+/// transaction.
+#[derive(Clone, Debug, Default)]
+pub struct OriginalStorage {
+	values: BTreeMap<(H160, H256), H256>,
+}
+
+impl OriginalStorage {
+	/// Create an empty record.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Whether the original value of the slot is recorded.
+	pub fn is_recorded(&self, address: H160, index: H256) -> bool {
+		self.values.contains_key(&(address, index))
+	}
+
+	/// Record the value of the slot before it is written. This has no effect
+	/// if the slot was already written in the transaction.
+	pub fn record(&mut self, address: H160, index: H256, current: H256) {
+		self.values.entry((address, index)).or_insert(current);
+	}
+
+	/// Original value of the slot, if it was written in the transaction.
+	/// Otherwise, the original value is the current value.
+	pub fn get(&self, address: H160, index: H256) -> Option<H256> {
+		self.values.get(&(address, index)).cloned()
+	}
+
+	/// Forget all recorded values, at the end of a transaction.
+	pub fn clear(&mut self) {
+		self.values.clear();
+	}
+}
@@ -1,17 +1,30 @@
 use alloc::{
-	boxed::Box,
 	collections::{BTreeMap, BTreeSet},
 	vec::Vec,
 };
-use core::mem;
+use core::cell::RefCell;
 
 use evm_interpreter::{
 	error::{ExitError, ExitException},
-	runtime::{Log, RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment, SetCodeOrigin},
+	runtime::{
+		BlockHashProvider, Log, RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment,
+		SetCodeOrigin,
+	},
 };
 use primitive_types::{H160, H256, U256};
 
-use crate::{backend::TransactionalBackend, MergeStrategy};
+use crate::{
+	backend::{OriginalStorage, SnapshotBackend, SnapshotId, TransactionalBackend},
+	MergeStrategy,
+};
+
+/// Address of the RIPEMD-160 precompile. Its touch survives a revert, as on
+/// mainnet, where it was touched in a call that ran out of gas before the
+/// touch was journaled (see EIP-716).
+const RIPEMD_ADDRESS: H160 = H160([
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+	0x00, 0x00, 0x00, 0x03,
+]);
 
 #[derive(Clone, Debug)]
 pub struct OverlayedChangeSet {
@@ -22,46 +35,205 @@ pub struct OverlayedChangeSet {
 	pub storage_resets: BTreeSet<H160>,
 	pub storages: BTreeMap<(H160, H256), H256>,
 	pub transient_storage: BTreeMap<(H160, H256), H256>,
+	/// Deleted accounts. Deletes apply before the other changes, which are
+	/// the changes made after the deletion, by later transactions.
 	pub deletes: BTreeSet<H160>,
+	/// Warm addresses and storage keys of the last transaction, to pass to
+	/// [OverlayedBackend::new] of a following execution.
+	pub accessed: BTreeSet<(H160, Option<H256>)>,
+}
+
+/// Change to an account, as returned by [OverlayedBackend::into_changes].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Apply {
+	/// Create or modify the account.
+	Modify {
+		address: H160,
+		/// Balance after the execution, whether it changed or not.
+		balance: U256,
+		/// Nonce after the execution, whether it changed or not.
+		nonce: U256,
+		/// New code, if it changed.
+		code: Option<Vec<u8>>,
+		/// Storage writes, with zero values for cleared slots.
+		storage: BTreeMap<H256, H256>,
+		/// Whether the storage was reset before the writes.
+		reset_storage: bool,
+	},
+	/// Delete the account and its storage.
+	Delete { address: H160 },
 }
 
+type Accessed = BTreeSet<(H160, Option<H256>)>;
+
 pub struct OverlayedBackend<B> {
 	backend: B,
-	substate: Box<Substate>,
+	overlay: Overlay,
+	original: OriginalStorage,
 	accessed: BTreeSet<(H160, Option<H256>)>,
+	last_accessed: BTreeSet<(H160, Option<H256>)>,
+	snapshots: Vec<(Overlay, Accessed)>,
+	keccak_cache: Option<RefCell<KeccakCache>>,
 }
 
 impl<B> OverlayedBackend<B> {
 	pub fn new(backend: B, accessed: BTreeSet<(H160, Option<H256>)>) -> Self {
 		Self {
 			backend,
-			substate: Box::new(Substate::new()),
+			overlay: Overlay::default(),
+			original: OriginalStorage::new(),
 			accessed,
+			last_accessed: BTreeSet::new(),
+			snapshots: Vec::new(),
+			keccak_cache: None,
 		}
 	}
 
+	/// Memoize keccak256 results of up to `capacity` distinct inputs, for the
+	/// `SHA3` opcode and contract address derivation. Hashes are still
+	/// computed by the underlying backend.
+	#[must_use]
+	pub fn with_keccak_cache(mut self, capacity: usize) -> Self {
+		self.keccak_cache = Some(RefCell::new(KeccakCache::new(capacity)));
+		self
+	}
+
+	/// Reference to the underlying backend.
+	pub fn backend(&self) -> &B {
+		&self.backend
+	}
+
+	/// Mutable reference to the underlying backend. Changes made through it
+	/// are not tracked by the overlay.
+	pub fn backend_mut(&mut self) -> &mut B {
+		&mut self.backend
+	}
+
+	/// Set the balance of an account in the current substate.
+	pub fn set_balance(&mut self, address: H160, balance: U256) {
+		self.overlay.set_balance(address, balance);
+	}
+
+	/// Set the nonce of an account in the current substate.
+	pub fn set_nonce(&mut self, address: H160, nonce: U256) {
+		self.overlay.set_nonce(address, nonce);
+	}
+
+	/// Number of substates pushed on top of the base substate.
+	pub fn substate_depth(&self) -> usize {
+		self.overlay.checkpoints.len()
+	}
+
+	/// Addresses, and storage keys with their address, warmed so far in the
+	/// current transaction (see EIP-2929), including the ones passed to
+	/// [OverlayedBackend::new] for the first transaction. Empty between
+	/// transactions, see [OverlayedBackend::last_accessed].
+	pub fn accessed(&self) -> &BTreeSet<(H160, Option<H256>)> {
+		&self.accessed
+	}
+
+	/// Addresses, and storage keys with their address, warmed by the last
+	/// finished transaction.
+	pub fn last_accessed(&self) -> &BTreeSet<(H160, Option<H256>)> {
+		&self.last_accessed
+	}
+
+	/// Addresses and storage keys warmed by the last finished transaction as
+	/// an access list, in the format of
+	/// [crate::standard::TransactArgs::access_list].
+	pub fn access_list(&self) -> Vec<(H160, Vec<H256>)> {
+		let mut list: BTreeMap<H160, Vec<H256>> = BTreeMap::new();
+		for (address, index) in self.last_accessed() {
+			let keys = list.entry(*address).or_default();
+			if let Some(index) = index {
+				keys.push(*index);
+			}
+		}
+		list.into_iter().collect()
+	}
+
 	pub fn deconstruct(self) -> (B, OverlayedChangeSet) {
+		let accessed = self.last_accessed;
+		let mut deletes = self.overlay.destroyed;
+		deletes.extend(self.overlay.deletes);
 		(
 			self.backend,
 			OverlayedChangeSet {
-				logs: self.substate.logs,
-				balances: self.substate.balances,
-				codes: self.substate.codes,
-				nonces: self.substate.nonces,
-				storage_resets: self.substate.storage_resets,
-				storages: self.substate.storages,
-				transient_storage: self.substate.transient_storage,
-				deletes: self.substate.deletes,
+				logs: self.overlay.logs,
+				balances: self.overlay.balances,
+				codes: self.overlay.codes,
+				nonces: self.overlay.nonces,
+				storage_resets: self.overlay.storage_resets,
+				storages: self.overlay.storages,
+				transient_storage: self.overlay.transient_storage,
+				deletes,
+				accessed,
 			},
 		)
 	}
 }
 
-impl<B: RuntimeEnvironment> RuntimeEnvironment for OverlayedBackend<B> {
+impl<B: RuntimeBaseBackend> OverlayedBackend<B> {
+	/// Consume the backend, and return the underlying backend with the
+	/// changes of the execution as account changes, in address order, and
+	/// logs, to persist them to a database without diffing the state.
+	pub fn into_changes(self) -> (B, Vec<Apply>, Vec<Log>) {
+		let mut addresses = BTreeSet::new();
+		addresses.extend(self.overlay.balances.keys());
+		addresses.extend(self.overlay.nonces.keys());
+		addresses.extend(self.overlay.codes.keys());
+		addresses.extend(self.overlay.storage_resets.iter());
+		addresses.extend(self.overlay.storages.keys().map(|(address, _)| *address));
+		addresses.extend(self.overlay.deletes.iter());
+		addresses.extend(self.overlay.destroyed.iter());
+
+		let applies = addresses
+			.into_iter()
+			.map(|address| {
+				// An account deleted by an earlier transaction is recreated
+				// if a later one wrote to it.
+				let written = self.overlay.known_exists(address) == Some(true)
+					|| self.overlay.has_storage(address);
+				let recreated = self.overlay.destroyed.contains(&address);
+				if self.overlay.deletes.contains(&address) || (recreated && !written) {
+					return Apply::Delete { address };
+				}
+
+				let storage = self
+					.overlay
+					.storages
+					.range((address, H256::zero())..=(address, H256::repeat_byte(0xff)))
+					.map(|((_, index), value)| (*index, *value))
+					.collect();
+				Apply::Modify {
+					address,
+					balance: self.balance(address),
+					nonce: self.nonce(address),
+					// The code of a recreated account is replaced, if only by
+					// the empty code.
+					code: if recreated {
+						Some(self.code(address))
+					} else {
+						self.overlay.codes.get(&address).cloned()
+					},
+					storage,
+					reset_storage: self.overlay.storage_resets.contains(&address),
+				}
+			})
+			.collect();
+
+		(self.backend, applies, self.overlay.logs)
+	}
+}
+
+impl<B: BlockHashProvider> BlockHashProvider for OverlayedBackend<B> {
 	fn block_hash(&self, number: U256) -> H256 {
 		self.backend.block_hash(number)
 	}
+}
 
+impl<B: RuntimeEnvironment> RuntimeEnvironment for OverlayedBackend<B> {
 	fn block_number(&self) -> U256 {
 		self.backend.block_number()
 	}
@@ -82,6 +254,10 @@ impl<B: RuntimeEnvironment> RuntimeEnvironment for OverlayedBackend<B> {
 		self.backend.block_randomness()
 	}
 
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.backend.block_parent_beacon_root()
+	}
+
 	fn block_gas_limit(&self) -> U256 {
 		self.backend.block_gas_limit()
 	}
@@ -90,6 +266,10 @@ impl<B: RuntimeEnvironment> RuntimeEnvironment for OverlayedBackend<B> {
 		self.backend.block_base_fee_per_gas()
 	}
 
+	fn blob_base_fee(&self) -> U256 {
+		self.backend.blob_base_fee()
+	}
+
 	fn chain_id(&self) -> U256 {
 		self.backend.chain_id()
 	}
@@ -97,23 +277,27 @@ impl<B: RuntimeEnvironment> RuntimeEnvironment for OverlayedBackend<B> {
 
 impl<B: RuntimeBaseBackend> RuntimeBaseBackend for OverlayedBackend<B> {
 	fn balance(&self, address: H160) -> U256 {
-		if let Some(balance) = self.substate.known_balance(address) {
+		if let Some(balance) = self.overlay.balances.get(&address).cloned() {
 			balance
+		} else if self.overlay.destroyed.contains(&address) {
+			U256::zero()
 		} else {
 			self.backend.balance(address)
 		}
 	}
 
 	fn code(&self, address: H160) -> Vec<u8> {
-		if let Some(code) = self.substate.known_code(address) {
+		if let Some(code) = self.overlay.codes.get(&address).cloned() {
 			code
+		} else if self.overlay.destroyed.contains(&address) {
+			Vec::new()
 		} else {
 			self.backend.code(address)
 		}
 	}
 
 	fn storage(&self, address: H160, index: H256) -> H256 {
-		if let Some(value) = self.substate.known_storage(address, index) {
+		if let Some(value) = self.overlay.known_storage(address, index) {
 			value
 		} else {
 			self.backend.storage(address, index)
@@ -121,7 +305,12 @@ impl<B: RuntimeBaseBackend> RuntimeBaseBackend for OverlayedBackend<B> {
 	}
 
 	fn transient_storage(&self, address: H160, index: H256) -> H256 {
-		if let Some(value) = self.substate.known_transient_storage(address, index) {
+		if let Some(value) = self
+			.overlay
+			.transient_storage
+			.get(&(address, index))
+			.cloned()
+		{
 			value
 		} else {
 			self.backend.transient_storage(address, index)
@@ -129,7 +318,7 @@ impl<B: RuntimeBaseBackend> RuntimeBaseBackend for OverlayedBackend<B> {
 	}
 
 	fn exists(&self, address: H160) -> bool {
-		if let Some(exists) = self.substate.known_exists(address) {
+		if let Some(exists) = self.overlay.known_exists(address) {
 			exists
 		} else {
 			self.backend.exists(address)
@@ -137,21 +326,48 @@ impl<B: RuntimeBaseBackend> RuntimeBaseBackend for OverlayedBackend<B> {
 	}
 
 	fn nonce(&self, address: H160) -> U256 {
-		if let Some(nonce) = self.substate.known_nonce(address) {
+		if let Some(nonce) = self.overlay.nonces.get(&address).cloned() {
 			nonce
+		} else if self.overlay.destroyed.contains(&address) {
+			U256::zero()
 		} else {
 			self.backend.nonce(address)
 		}
 	}
+
+	fn prefetch(&self, addresses: &[H160], keys: &[(H160, H256)]) {
+		self.backend.prefetch(addresses, keys)
+	}
+
+	fn keccak256(&self, data: &[u8]) -> H256 {
+		match &self.keccak_cache {
+			Some(cache) => {
+				if let Some(hash) = cache.borrow().get(data) {
+					return hash;
+				}
+
+				let hash = self.backend.keccak256(data);
+				cache.borrow_mut().insert(data, hash);
+				hash
+			}
+			None => self.backend.keccak256(data),
+		}
+	}
+
+	fn ecrecover(&self, hash: H256, y_parity: u8, r: H256, s: H256) -> Option<H160> {
+		self.backend.ecrecover(hash, y_parity, r, s)
+	}
 }
 
 impl<B: RuntimeBaseBackend> RuntimeBackend for OverlayedBackend<B> {
 	fn original_storage(&self, address: H160, index: H256) -> H256 {
-		self.backend.storage(address, index)
+		self.original
+			.get(address, index)
+			.unwrap_or_else(|| self.storage(address, index))
 	}
 
 	fn deleted(&self, address: H160) -> bool {
-		self.substate.deleted(address)
+		self.overlay.deletes.contains(&address)
 	}
 
 	fn is_cold(&self, address: H160, index: Option<H256>) -> bool {
@@ -163,7 +379,12 @@ impl<B: RuntimeBaseBackend> RuntimeBackend for OverlayedBackend<B> {
 	}
 
 	fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError> {
-		self.substate.storages.insert((address, index), value);
+		// Writes outside of a transaction have no original value to keep.
+		if !self.overlay.checkpoints.is_empty() && !self.original.is_recorded(address, index) {
+			let current = self.storage(address, index);
+			self.original.record(address, index, current);
+		}
+		self.overlay.set_storage(address, index, value);
 		Ok(())
 	}
 
@@ -173,23 +394,21 @@ impl<B: RuntimeBaseBackend> RuntimeBackend for OverlayedBackend<B> {
 		index: H256,
 		value: H256,
 	) -> Result<(), ExitError> {
-		self.substate
-			.transient_storage
-			.insert((address, index), value);
+		self.overlay.set_transient_storage(address, index, value);
 		Ok(())
 	}
 
 	fn log(&mut self, log: Log) -> Result<(), ExitError> {
-		self.substate.logs.push(log);
+		self.overlay.logs.push(log);
 		Ok(())
 	}
 
 	fn mark_delete(&mut self, address: H160) {
-		self.substate.deletes.insert(address);
+		self.overlay.mark_delete(address);
 	}
 
 	fn reset_storage(&mut self, address: H160) {
-		self.substate.storage_resets.insert(address);
+		self.overlay.reset_storage(address);
 	}
 
 	fn set_code(
@@ -198,26 +417,27 @@ impl<B: RuntimeBaseBackend> RuntimeBackend for OverlayedBackend<B> {
 		code: Vec<u8>,
 		_origin: SetCodeOrigin,
 	) -> Result<(), ExitError> {
-		self.substate.codes.insert(address, code);
+		self.overlay.set_code(address, code);
 		Ok(())
 	}
 
 	fn reset_balance(&mut self, address: H160) {
-		self.substate.balances.insert(address, U256::zero());
+		self.overlay.set_balance(address, U256::zero());
 	}
 
 	fn deposit(&mut self, target: H160, value: U256) {
+		self.overlay.touch(target);
 		if value == U256::zero() {
 			return;
 		}
 
 		let current_balance = self.balance(target);
-		self.substate
-			.balances
-			.insert(target, current_balance.saturating_add(value));
+		self.overlay
+			.set_balance(target, current_balance.saturating_add(value));
 	}
 
 	fn withdrawal(&mut self, source: H160, value: U256) -> Result<(), ExitError> {
+		self.overlay.touch(source);
 		if value == U256::zero() {
 			return Ok(());
 		}
@@ -227,65 +447,103 @@ impl<B: RuntimeBaseBackend> RuntimeBackend for OverlayedBackend<B> {
 			return Err(ExitException::OutOfFund.into());
 		}
 		let new_balance = current_balance - value;
-		self.substate.balances.insert(source, new_balance);
+		self.overlay.set_balance(source, new_balance);
 		Ok(())
 	}
 
 	fn inc_nonce(&mut self, address: H160) -> Result<(), ExitError> {
 		let new_nonce = self.nonce(address).saturating_add(U256::from(1));
-		self.substate.nonces.insert(address, new_nonce);
+		self.overlay.set_nonce(address, new_nonce);
 		Ok(())
 	}
+
+	fn touch(&mut self, address: H160) {
+		self.overlay.touch(address);
+	}
+
+	fn take_touched(&mut self) -> Vec<H160> {
+		core::mem::take(&mut self.overlay.touched)
+			.into_iter()
+			.collect()
+	}
 }
 
 impl<B: RuntimeBaseBackend> TransactionalBackend for OverlayedBackend<B> {
 	fn push_substate(&mut self) {
-		let mut parent = Box::new(Substate::new());
-		mem::swap(&mut parent, &mut self.substate);
-		self.substate.parent = Some(parent);
+		self.overlay.push_checkpoint();
 	}
 
 	fn pop_substate(&mut self, strategy: MergeStrategy) {
-		let mut child = self.substate.parent.take().expect("uneven substate pop");
-		mem::swap(&mut child, &mut self.substate);
-		let child = child;
+		self.overlay.pop_checkpoint(strategy);
+
+		// The transaction ended: the storage is the original storage of the
+		// next one, which starts cold, with empty transient storage, and
+		// without the accounts deleted by this one.
+		if self.overlay.checkpoints.is_empty() {
+			self.original.clear();
+			self.overlay.end_transaction();
+			self.last_accessed = core::mem::take(&mut self.accessed);
+		}
+	}
 
-		match strategy {
-			MergeStrategy::Commit => {
-				for log in child.logs {
-					self.substate.logs.push(log);
-				}
-				for (address, balance) in child.balances {
-					self.substate.balances.insert(address, balance);
-				}
-				for (address, code) in child.codes {
-					self.substate.codes.insert(address, code);
-				}
-				for (address, nonce) in child.nonces {
-					self.substate.nonces.insert(address, nonce);
-				}
-				for address in child.storage_resets {
-					self.substate.storage_resets.insert(address);
-				}
-				for ((address, key), value) in child.storages {
-					self.substate.storages.insert((address, key), value);
-				}
-				for ((address, key), value) in child.transient_storage {
-					self.substate
-						.transient_storage
-						.insert((address, key), value);
-				}
-				for address in child.deletes {
-					self.substate.deletes.insert(address);
-				}
-			}
-			MergeStrategy::Revert | MergeStrategy::Discard => {}
+	fn substate_logs(&self) -> Vec<Log> {
+		self.overlay.substate_logs().to_vec()
+	}
+}
+
+impl<B> SnapshotBackend for OverlayedBackend<B> {
+	fn snapshot(&mut self) -> SnapshotId {
+		self.snapshots
+			.push((self.overlay.clone(), self.accessed.clone()));
+		SnapshotId(self.snapshots.len() - 1)
+	}
+
+	fn revert_to(&mut self, id: SnapshotId) -> bool {
+		if id.0 >= self.snapshots.len() {
+			return false;
+		}
+
+		self.snapshots.truncate(id.0 + 1);
+		let (overlay, accessed) = self
+			.snapshots
+			.pop()
+			.expect("checked snapshot exists above; qed");
+		self.overlay = overlay;
+		self.accessed = accessed;
+		true
+	}
+}
+
+/// Memoized keccak256 results, keyed by input.
+struct KeccakCache {
+	capacity: usize,
+	hashes: BTreeMap<Vec<u8>, H256>,
+}
+
+impl KeccakCache {
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			capacity,
+			hashes: BTreeMap::new(),
+		}
+	}
+
+	pub fn get(&self, data: &[u8]) -> Option<H256> {
+		self.hashes.get(data).cloned()
+	}
+
+	pub fn insert(&mut self, data: &[u8], hash: H256) {
+		if self.hashes.len() < self.capacity {
+			self.hashes.insert(data.to_vec(), hash);
 		}
 	}
 }
 
-struct Substate {
-	parent: Option<Box<Substate>>,
+/// Changes on top of the backend, flattened over all substates. A pushed
+/// substate is a checkpoint of the undo journal, which the substate rolls
+/// back to if it is reverted, so that reads never walk the substates.
+#[derive(Clone, Default)]
+struct Overlay {
 	logs: Vec<Log>,
 	balances: BTreeMap<H160, U256>,
 	codes: BTreeMap<H160, Vec<u8>>,
@@ -293,71 +551,131 @@ struct Substate {
 	storage_resets: BTreeSet<H160>,
 	storages: BTreeMap<(H160, H256), H256>,
 	transient_storage: BTreeMap<(H160, H256), H256>,
+	/// Accounts deleted by the current transaction, deleted when it ends.
 	deletes: BTreeSet<H160>,
+	/// Accounts deleted by previous transactions. They read as empty, except
+	/// for the changes made after the deletion.
+	destroyed: BTreeSet<H160>,
+	touched: BTreeSet<H160>,
+	journal: Vec<Undo>,
+	checkpoints: Vec<Checkpoint>,
 }
 
-impl Substate {
-	pub fn new() -> Self {
-		Self {
-			parent: None,
-			logs: Vec::new(),
-			balances: Default::default(),
-			codes: Default::default(),
-			nonces: Default::default(),
-			storage_resets: Default::default(),
-			storages: Default::default(),
-			transient_storage: Default::default(),
-			deletes: Default::default(),
+#[derive(Clone, Copy)]
+struct Checkpoint {
+	journal_len: usize,
+	logs_len: usize,
+}
+
+/// Previous value of a change, restored when its substate is reverted.
+#[derive(Clone)]
+enum Undo {
+	Balance(H160, Option<U256>),
+	Code(H160, Option<Vec<u8>>),
+	Nonce(H160, Option<U256>),
+	StorageReset(H160),
+	Storage((H160, H256), Option<H256>),
+	TransientStorage((H160, H256), Option<H256>),
+	Delete(H160),
+	Touch(H160),
+}
+
+impl Overlay {
+	fn record(&mut self, undo: Undo) {
+		// Changes of the base substate are never reverted.
+		if !self.checkpoints.is_empty() {
+			self.journal.push(undo);
 		}
 	}
 
-	pub fn known_balance(&self, address: H160) -> Option<U256> {
-		if let Some(balance) = self.balances.get(&address) {
-			Some(*balance)
-		} else if let Some(parent) = self.parent.as_ref() {
-			parent.known_balance(address)
-		} else {
-			None
+	pub fn set_balance(&mut self, address: H160, balance: U256) {
+		let old = self.balances.insert(address, balance);
+		self.record(Undo::Balance(address, old));
+	}
+
+	pub fn set_code(&mut self, address: H160, code: Vec<u8>) {
+		let old = self.codes.insert(address, code);
+		self.record(Undo::Code(address, old));
+	}
+
+	pub fn set_nonce(&mut self, address: H160, nonce: U256) {
+		let old = self.nonces.insert(address, nonce);
+		self.record(Undo::Nonce(address, old));
+	}
+
+	pub fn set_storage(&mut self, address: H160, index: H256, value: H256) {
+		let old = self.storages.insert((address, index), value);
+		self.record(Undo::Storage((address, index), old));
+	}
+
+	pub fn set_transient_storage(&mut self, address: H160, index: H256, value: H256) {
+		let old = self.transient_storage.insert((address, index), value);
+		self.record(Undo::TransientStorage((address, index), old));
+	}
+
+	pub fn mark_delete(&mut self, address: H160) {
+		// Outside of a transaction, for example for the empty accounts
+		// deleted when it is finalized, the account is deleted right away.
+		if self.checkpoints.is_empty() {
+			self.destroy(address);
+		} else if self.deletes.insert(address) {
+			self.record(Undo::Delete(address));
 		}
 	}
 
-	pub fn known_code(&self, address: H160) -> Option<Vec<u8>> {
-		if let Some(code) = self.codes.get(&address) {
-			Some(code.clone())
-		} else if let Some(parent) = self.parent.as_ref() {
-			parent.known_code(address)
-		} else {
-			None
+	/// Delete the account: drop its changes, and read it as empty.
+	fn destroy(&mut self, address: H160) {
+		self.balances.remove(&address);
+		self.codes.remove(&address);
+		self.nonces.remove(&address);
+		self.reset_storage(address);
+		self.destroyed.insert(address);
+	}
+
+	fn has_storage(&self, address: H160) -> bool {
+		self.storages
+			.range((address, H256::zero())..=(address, H256::repeat_byte(0xff)))
+			.next()
+			.is_some()
+	}
+
+	/// End the transaction, once the base substate is reached.
+	fn end_transaction(&mut self) {
+		self.transient_storage.clear();
+		for address in core::mem::take(&mut self.deletes) {
+			self.destroy(address);
 		}
 	}
 
-	pub fn known_nonce(&self, address: H160) -> Option<U256> {
-		if let Some(nonce) = self.nonces.get(&address) {
-			Some(*nonce)
-		} else if let Some(parent) = self.parent.as_ref() {
-			parent.known_nonce(address)
-		} else {
-			None
+	pub fn touch(&mut self, address: H160) {
+		if self.touched.insert(address) {
+			self.record(Undo::Touch(address));
 		}
 	}
 
-	pub fn known_storage(&self, address: H160, key: H256) -> Option<H256> {
-		if let Some(value) = self.storages.get(&(address, key)) {
-			Some(*value)
-		} else if self.storage_resets.contains(&address) {
-			Some(H256::default())
-		} else if let Some(parent) = self.parent.as_ref() {
-			parent.known_storage(address, key)
-		} else {
-			None
+	/// Reset the storage of the account. Storage changes made before are
+	/// dropped, and the rest of the storage reads as zero.
+	pub fn reset_storage(&mut self, address: H160) {
+		if self.storage_resets.insert(address) {
+			self.record(Undo::StorageReset(address));
+		}
+
+		let keys = self
+			.storages
+			.range((address, H256::zero())..=(address, H256::repeat_byte(0xff)))
+			.map(|(key, _)| *key)
+			.collect::<Vec<_>>();
+		for key in keys {
+			let old = self.storages.remove(&key);
+			self.record(Undo::Storage(key, old));
 		}
 	}
 
-	pub fn known_transient_storage(&self, address: H160, key: H256) -> Option<H256> {
-		if let Some(value) = self.transient_storage.get(&(address, key)) {
+	pub fn known_storage(&self, address: H160, index: H256) -> Option<H256> {
+		if let Some(value) = self.storages.get(&(address, index)) {
 			Some(*value)
-		} else if let Some(parent) = self.parent.as_ref() {
-			parent.known_transient_storage(address, key)
+		} else if self.storage_resets.contains(&address) {
+			Some(H256::default())
 		} else {
 			None
 		}
@@ -369,20 +687,241 @@ impl Substate {
 			|| self.codes.contains_key(&address)
 		{
 			Some(true)
-		} else if let Some(parent) = self.parent.as_ref() {
-			parent.known_exists(address)
+		} else if self.destroyed.contains(&address) {
+			Some(false)
 		} else {
 			None
 		}
 	}
 
-	pub fn deleted(&self, address: H160) -> bool {
-		if self.deletes.contains(&address) {
-			true
-		} else if let Some(parent) = self.parent.as_ref() {
-			parent.deleted(address)
-		} else {
-			false
+	/// Logs of the current substate, including the ones committed by its
+	/// substates.
+	pub fn substate_logs(&self) -> &[Log] {
+		let logs_len = self.checkpoints.last().map_or(0, |c| c.logs_len);
+		&self.logs[logs_len..]
+	}
+
+	pub fn push_checkpoint(&mut self) {
+		self.checkpoints.push(Checkpoint {
+			journal_len: self.journal.len(),
+			logs_len: self.logs.len(),
+		});
+	}
+
+	pub fn pop_checkpoint(&mut self, strategy: MergeStrategy) {
+		let checkpoint = self.checkpoints.pop().expect("uneven substate pop");
+
+		match strategy {
+			MergeStrategy::Commit => {
+				// The changes now belong to the parent substate, and are
+				// reverted with it. The base substate keeps no journal.
+				if self.checkpoints.is_empty() {
+					self.journal.clear();
+				}
+			}
+			MergeStrategy::Revert | MergeStrategy::Discard => {
+				self.logs.truncate(checkpoint.logs_len);
+				for undo in self
+					.journal
+					.split_off(checkpoint.journal_len)
+					.into_iter()
+					.rev()
+				{
+					self.undo(undo);
+				}
+			}
+		}
+	}
+
+	fn undo(&mut self, undo: Undo) {
+		fn restore<K: Ord, V>(map: &mut BTreeMap<K, V>, key: K, old: Option<V>) {
+			match old {
+				Some(old) => {
+					map.insert(key, old);
+				}
+				None => {
+					map.remove(&key);
+				}
+			}
+		}
+
+		match undo {
+			Undo::Balance(address, old) => restore(&mut self.balances, address, old),
+			Undo::Code(address, old) => restore(&mut self.codes, address, old),
+			Undo::Nonce(address, old) => restore(&mut self.nonces, address, old),
+			Undo::StorageReset(address) => {
+				self.storage_resets.remove(&address);
+			}
+			Undo::Storage(key, old) => restore(&mut self.storages, key, old),
+			Undo::TransientStorage(key, old) => restore(&mut self.transient_storage, key, old),
+			Undo::Delete(address) => {
+				self.deletes.remove(&address);
+			}
+			Undo::Touch(address) => {
+				if address != RIPEMD_ADDRESS {
+					self.touched.remove(&address);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{collections::BTreeSet, vec, vec::Vec};
+
+	use primitive_types::{H160, H256, U256};
+
+	use super::{Apply, OverlayedBackend};
+	use crate::{
+		backend::{RuntimeBackend, RuntimeBaseBackend, TestBackend, TestEnvironment},
+		standard::{
+			testing::{self, call_args, etable, CONTRACT},
+			Config, EtableResolver, Invoker,
+		},
+		MergeStrategy, TransactionalBackend,
+	};
+
+	fn call(backend: &mut OverlayedBackend<&TestBackend>) -> (Vec<u8>, U256) {
+		let config = Config::cancun();
+		let etable = etable();
+		let resolver = EtableResolver::new(&config, &(), &etable);
+		let invoker = Invoker::new(&config, &resolver);
+		let args = call_args(CONTRACT, Vec::new(), 100_000, 0);
+
+		let value = crate::transact_hybrid(args, None, backend, &invoker).unwrap();
+		assert!(value.exit.is_ok());
+		(value.retval, value.used_gas)
+	}
+
+	#[test]
+	fn transactions_start_cold_and_without_transient_storage() {
+		let mut base = testing::backend();
+		// Return TLOAD(0) before TSTORE(0, 1), with a cold SLOAD(1).
+		base.set_code(
+			CONTRACT,
+			vec![
+				0x60, 0x00, 0x5c, 0x60, 0x00, 0x52, 0x60, 0x01, 0x60, 0x00, 0x5d, 0x60, 0x01, 0x54,
+				0x50, 0x60, 0x20, 0x60, 0x00, 0xf3,
+			],
+		);
+		let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+
+		let first = call(&mut backend);
+		let second = call(&mut backend);
+		assert_eq!(first.0, vec![0; 32]);
+		assert_eq!(first, second);
+		assert!(backend.accessed().is_empty());
+		assert!(backend
+			.last_accessed()
+			.contains(&(CONTRACT, Some(H256::from_low_u64_be(1)))));
+	}
+
+	#[test]
+	fn deleted_accounts_read_as_empty_in_later_transactions() {
+		let mut base = TestBackend::new(TestEnvironment::default());
+		base.set_balance(CONTRACT, U256::from(5));
+		base.set_code(CONTRACT, vec![0x00]);
+		base.set_storage(CONTRACT, H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+		let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+
+		backend.push_substate();
+		backend.mark_delete(CONTRACT);
+		assert!(backend.deleted(CONTRACT));
+		assert!(backend.exists(CONTRACT));
+		backend.pop_substate(MergeStrategy::Commit);
+
+		assert!(!backend.deleted(CONTRACT));
+		assert!(!backend.exists(CONTRACT));
+		assert_eq!(backend.balance(CONTRACT), U256::zero());
+		assert_eq!(backend.code(CONTRACT), Vec::<u8>::new());
+		assert_eq!(
+			backend.storage(CONTRACT, H256::from_low_u64_be(1)),
+			H256::zero()
+		);
+	}
+
+	#[test]
+	fn changes_of_an_account_recreated_after_its_deletion() {
+		const OTHER: H160 = H160([0x30; 20]);
+		let key = H256::from_low_u64_be;
+
+		let mut base = TestBackend::new(TestEnvironment::default());
+		base.set_balance(CONTRACT, U256::from(5));
+		base.set_code(CONTRACT, vec![0x00]);
+		base.set_storage(CONTRACT, key(1), key(2));
+		base.set_balance(OTHER, U256::from(1));
+
+		// The first transaction deletes both accounts, the second one funds
+		// and writes to the contract again.
+		let run = |base| {
+			let mut backend = OverlayedBackend::new(base, BTreeSet::new());
+			backend.push_substate();
+			backend.mark_delete(CONTRACT);
+			backend.mark_delete(OTHER);
+			backend.pop_substate(MergeStrategy::Commit);
+
+			backend.push_substate();
+			backend.deposit(CONTRACT, U256::from(7));
+			backend.set_storage(CONTRACT, key(3), key(4)).unwrap();
+			backend.pop_substate(MergeStrategy::Commit);
+			backend
+		};
+
+		let (_, applies, _) = run(&base).into_changes();
+		assert_eq!(
+			applies,
+			vec![
+				Apply::Modify {
+					address: CONTRACT,
+					balance: U256::from(7),
+					nonce: U256::zero(),
+					code: Some(Vec::new()),
+					storage: [(key(3), key(4))].into_iter().collect(),
+					reset_storage: true,
+				},
+				Apply::Delete { address: OTHER },
+			]
+		);
+
+		let (_, changeset) = run(&base).deconstruct();
+		let mut deconstructed = base.clone();
+		deconstructed.apply(changeset);
+
+		let mut applied = base.clone();
+		for apply in applies {
+			match apply {
+				Apply::Modify {
+					address,
+					balance,
+					nonce,
+					code,
+					storage,
+					reset_storage,
+				} => {
+					let account = applied.state.entry(address).or_default();
+					account.balance = balance;
+					account.nonce = nonce;
+					if let Some(code) = code {
+						account.code = code;
+					}
+					if reset_storage {
+						account.storage.clear();
+					}
+					account.storage.extend(storage);
+				}
+				Apply::Delete { address } => {
+					applied.state.remove(&address);
+				}
+			}
+		}
+
+		for state in [&deconstructed.state, &applied.state] {
+			let account = &state[&CONTRACT];
+			assert_eq!(account.balance, U256::from(7));
+			assert!(account.code.is_empty());
+			assert_eq!(account.storage, [(key(3), key(4))].into_iter().collect());
+			assert!(!state.contains_key(&OTHER));
 		}
 	}
 }
@@ -0,0 +1,446 @@
+//! Merkle Patricia proofs of the accounts and storage of a [TestBackend], in
+//! the format of `eth_getProof`.
+//!
+//! The state is kept in maps rather than in a trie, so the secure tries are
+//! rebuilt for each root and proof, and the nodes on the path of the key are
+//! recorded on the way.
+
+use alloc::vec::Vec;
+
+use evm_interpreter::utils::h256_to_u256;
+use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+
+use crate::backend::{TestAccount, TestBackend};
+
+/// Proof of an account and some of its storage, as returned by
+/// `eth_getProof`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize),
+	serde(rename_all = "camelCase")
+)]
+pub struct AccountProof {
+	pub address: H160,
+	#[cfg_attr(feature = "serde", serde(with = "super::genesis::serde_quantity"))]
+	pub balance: U256,
+	pub code_hash: H256,
+	#[cfg_attr(feature = "serde", serde(with = "super::genesis::serde_quantity"))]
+	pub nonce: U256,
+	pub storage_hash: H256,
+	/// Nodes of the state trie from the root to the account, or to where the
+	/// path of a missing account ends.
+	#[cfg_attr(feature = "serde", serde(serialize_with = "serialize_nodes"))]
+	pub account_proof: Vec<Vec<u8>>,
+	pub storage_proof: Vec<StorageProof>,
+}
+
+/// Proof of a storage slot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StorageProof {
+	pub key: H256,
+	#[cfg_attr(feature = "serde", serde(with = "super::genesis::serde_quantity"))]
+	pub value: U256,
+	/// Nodes of the storage trie from the storage root to the slot.
+	#[cfg_attr(feature = "serde", serde(serialize_with = "serialize_nodes"))]
+	pub proof: Vec<Vec<u8>>,
+}
+
+#[cfg(feature = "serde")]
+fn serialize_nodes<S: serde::Serializer>(
+	nodes: &[Vec<u8>],
+	serializer: S,
+) -> Result<S::Ok, S::Error> {
+	struct Node<'a>(&'a [u8]);
+
+	impl<'a> serde::Serialize for Node<'a> {
+		fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			super::genesis::serde_bytes::serialize(self.0, serializer)
+		}
+	}
+
+	serializer.collect_seq(nodes.iter().map(|node| Node(node)))
+}
+
+impl TestBackend {
+	/// Root of the state trie.
+	pub fn state_root(&self) -> H256 {
+		root(self.state_entries())
+	}
+
+	/// Prove an account and the storage slots of `keys` against the
+	/// [TestBackend::state_root]. Missing accounts and slots are proven
+	/// absent, with zero values.
+	pub fn account_proof(&self, address: H160, keys: &[H256]) -> AccountProof {
+		let default = TestAccount::default();
+		let account = self.state.get(&address).unwrap_or(&default);
+
+		let storage_proof = keys
+			.iter()
+			.map(|key| {
+				let proof = prove(storage_entries(account), key.as_bytes());
+				StorageProof {
+					key: *key,
+					value: h256_to_u256(account.storage.get(key).copied().unwrap_or_default()),
+					proof,
+				}
+			})
+			.collect();
+		let account_proof = prove(self.state_entries(), address.as_bytes());
+
+		AccountProof {
+			address,
+			balance: account.balance,
+			code_hash: keccak(&account.code),
+			nonce: account.nonce,
+			storage_hash: storage_root(account),
+			account_proof,
+			storage_proof,
+		}
+	}
+
+	fn state_entries(&self) -> impl Iterator<Item = (H160, Vec<u8>)> + '_ {
+		self.state.iter().map(|(address, account)| {
+			let mut stream = rlp::RlpStream::new_list(4);
+			stream.append(&account.nonce);
+			stream.append(&account.balance);
+			stream.append(&storage_root(account));
+			stream.append(&keccak(&account.code));
+			(*address, stream.out().to_vec())
+		})
+	}
+}
+
+fn storage_entries(account: &TestAccount) -> impl Iterator<Item = (H256, Vec<u8>)> + '_ {
+	account
+		.storage
+		.iter()
+		.map(|(key, value)| (*key, rlp::encode(&h256_to_u256(*value)).to_vec()))
+}
+
+fn storage_root(account: &TestAccount) -> H256 {
+	root(storage_entries(account))
+}
+
+fn keccak(data: &[u8]) -> H256 {
+	H256::from_slice(&Keccak256::digest(data))
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+	key.iter()
+		.flat_map(|byte| [byte >> 4, byte & 0x0f])
+		.collect()
+}
+
+/// Hex prefix encoding of the path of a leaf or extension node.
+fn hex_prefix(path: &[u8], leaf: bool) -> Vec<u8> {
+	let flag = if leaf { 0x20 } else { 0x00 };
+	let mut encoded = Vec::with_capacity(path.len() / 2 + 1);
+	let rest = if path.len() % 2 == 1 {
+		encoded.push(flag | 0x10 | path[0]);
+		&path[1..]
+	} else {
+		encoded.push(flag);
+		path
+	};
+	encoded.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+	encoded
+}
+
+/// Append a reference to a child node: the node itself if its encoding is
+/// shorter than a hash, or its hash.
+fn append_child(stream: &mut rlp::RlpStream, node: &[u8]) {
+	if node.len() < 32 {
+		stream.append_raw(node, 1);
+	} else {
+		stream.append(&keccak(node));
+	}
+}
+
+/// Trie of a set of keys, with the nodes on the path of one of them.
+struct Trie<'a> {
+	target: &'a [u8],
+	proof: Vec<Vec<u8>>,
+}
+
+impl<'a> Trie<'a> {
+	/// Encode the node of the items, whose keys share their first `depth`
+	/// nibbles. The items are sorted by key.
+	fn node(&mut self, items: &[(Vec<u8>, Vec<u8>)], depth: usize, on_path: bool) -> Vec<u8> {
+		// Nodes are recorded deepest first, and reversed at the end.
+		let node = match items {
+			[] => rlp::NULL_RLP.to_vec(),
+			[(key, value)] => {
+				let mut stream = rlp::RlpStream::new_list(2);
+				stream.append(&hex_prefix(&key[depth..], true));
+				stream.append(value);
+				stream.out().to_vec()
+			}
+			_ => {
+				let first = &items[0].0;
+				let last = &items[items.len() - 1].0;
+				let shared = first[depth..]
+					.iter()
+					.zip(&last[depth..])
+					.take_while(|(a, b)| a == b)
+					.count();
+
+				if shared > 0 {
+					let path = &first[depth..depth + shared];
+					let on_path = on_path && self.target[depth..].starts_with(path);
+					let child = self.node(items, depth + shared, on_path);
+
+					let mut stream = rlp::RlpStream::new_list(2);
+					stream.append(&hex_prefix(path, false));
+					append_child(&mut stream, &child);
+					stream.out().to_vec()
+				} else {
+					let mut stream = rlp::RlpStream::new_list(17);
+					let mut rest = items;
+					for nibble in 0..16 {
+						let count = rest
+							.iter()
+							.take_while(|(key, _)| key[depth] == nibble)
+							.count();
+						let (children, next) = rest.split_at(count);
+						rest = next;
+
+						if children.is_empty() {
+							stream.append_empty_data();
+						} else {
+							let on_path = on_path && self.target.get(depth) == Some(&nibble);
+							let child = self.node(children, depth + 1, on_path);
+							append_child(&mut stream, &child);
+						}
+					}
+					// Keys all have the same length, so branches have no value.
+					stream.append_empty_data();
+					stream.out().to_vec()
+				}
+			}
+		};
+
+		// Inlined nodes are part of their parent, except for the root.
+		if on_path && (node.len() >= 32 || depth == 0) {
+			self.proof.push(node.clone());
+		}
+		node
+	}
+}
+
+/// Keys of the secure trie of the entries, as nibbles, sorted.
+fn items<K: AsRef<[u8]>>(
+	entries: impl IntoIterator<Item = (K, Vec<u8>)>,
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+	let mut items = entries
+		.into_iter()
+		.map(|(key, value)| (nibbles(keccak(key.as_ref()).as_bytes()), value))
+		.collect::<Vec<_>>();
+	items.sort();
+	items
+}
+
+/// Root of the secure trie of the entries.
+fn root<K: AsRef<[u8]>>(entries: impl IntoIterator<Item = (K, Vec<u8>)>) -> H256 {
+	let mut trie = Trie {
+		target: &[],
+		proof: Vec::new(),
+	};
+	keccak(&trie.node(&items(entries), 0, false))
+}
+
+/// Build the secure trie of the entries, and return the proof of `key`.
+fn prove<K: AsRef<[u8]>>(
+	entries: impl IntoIterator<Item = (K, Vec<u8>)>,
+	key: &[u8],
+) -> Vec<Vec<u8>> {
+	let target = nibbles(keccak(key).as_bytes());
+	let mut trie = Trie {
+		target: &target,
+		proof: Vec::new(),
+	};
+	trie.node(&items(entries), 0, true);
+
+	trie.proof.into_iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{vec, vec::Vec};
+
+	use primitive_types::{H160, H256, U256};
+	use rlp::Rlp;
+
+	use super::{keccak, nibbles};
+	use crate::backend::{TestBackend, TestEnvironment};
+
+	/// Follow the proof of `key` from the root, and return the value at the
+	/// key, or `None` if the proof shows that the key is absent. Panics if a
+	/// node of the proof does not match the reference from its parent.
+	fn verify(root: H256, key: &[u8], proof: &[Vec<u8>]) -> Option<Vec<u8>> {
+		let path = nibbles(keccak(key).as_bytes());
+		let mut depth = 0;
+		let mut nodes = proof.iter();
+		let mut hash = Some(root);
+		let mut inlined = Vec::new();
+
+		loop {
+			let encoded = match hash.take() {
+				Some(hash) => {
+					let node = nodes.next().expect("proof is too short");
+					assert_eq!(keccak(node), hash, "node does not match its reference");
+					node.clone()
+				}
+				None => inlined.clone(),
+			};
+			let node = Rlp::new(&encoded);
+
+			let child = match node.item_count().unwrap() {
+				0 => return None,
+				17 => {
+					let child = node.at(path[depth] as usize).unwrap();
+					depth += 1;
+					child
+				}
+				2 => {
+					let encoded_path: Vec<u8> = node.val_at(0).unwrap();
+					let mut node_path = nibbles(&encoded_path);
+					let leaf = node_path[0] & 0x2 != 0;
+					let skip = if node_path[0] & 0x1 != 0 { 1 } else { 2 };
+					node_path.drain(..skip);
+
+					if !path[depth..].starts_with(&node_path) {
+						return None;
+					}
+					depth += node_path.len();
+					if leaf {
+						assert!(nodes.next().is_none(), "proof is too long");
+						return Some(node.val_at(1).unwrap());
+					}
+					node.at(1).unwrap()
+				}
+				_ => panic!("invalid node"),
+			};
+
+			if child.is_empty() {
+				return None;
+			} else if child.is_data() {
+				hash = Some(H256::from_slice(child.data().unwrap()));
+			} else {
+				inlined = child.as_raw().to_vec();
+			}
+		}
+	}
+
+	fn hash(hex: &str) -> H256 {
+		let bytes = (0..hex.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+			.collect::<Vec<_>>();
+		H256::from_slice(&bytes)
+	}
+
+	fn backend() -> TestBackend {
+		let mut backend = TestBackend::new(TestEnvironment::default());
+		let a = H160::repeat_byte(0x10);
+		backend.set_balance(a, U256::exp10(18));
+		backend.set_nonce(a, U256::one());
+		backend.set_code(a, vec![0x60, 0x00]);
+		backend.set_storage(a, H256::from_low_u64_be(1), H256::from_low_u64_be(0x2a));
+		backend.set_storage(a, H256::from_low_u64_be(2), H256::repeat_byte(0xff));
+		backend.set_balance(H160::repeat_byte(0x20), U256::from(5));
+		backend.set_nonce(H160::repeat_byte(0x30), U256::from(2));
+		backend
+	}
+
+	#[test]
+	fn state_root_matches_reference() {
+		assert_eq!(
+			TestBackend::new(TestEnvironment::default()).state_root(),
+			hash("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")
+		);
+		assert_eq!(
+			backend().state_root(),
+			hash("63d5de98d328710d7e463fa83a725edab1aedba1af25c17059a6dc8c37be5b5b")
+		);
+	}
+
+	#[test]
+	fn proofs_verify_against_the_root() {
+		let backend = backend();
+		let root = backend.state_root();
+		let a = H160::repeat_byte(0x10);
+		let keys = [
+			H256::from_low_u64_be(1),
+			H256::from_low_u64_be(2),
+			H256::from_low_u64_be(3),
+		];
+
+		let proof = backend.account_proof(a, &keys);
+		assert_eq!(proof.balance, U256::exp10(18));
+		assert_eq!(proof.nonce, U256::one());
+		assert_eq!(
+			proof.storage_hash,
+			hash("b56077d81ae9812460f3e5ae6a3ab1f33b1ccefb366555ba9da568a52f1d31a1")
+		);
+
+		let account = verify(root, a.as_bytes(), &proof.account_proof).unwrap();
+		let account = Rlp::new(&account);
+		assert_eq!(account.val_at::<U256>(0).unwrap(), proof.nonce);
+		assert_eq!(account.val_at::<U256>(1).unwrap(), proof.balance);
+		assert_eq!(account.val_at::<H256>(2).unwrap(), proof.storage_hash);
+		assert_eq!(account.val_at::<H256>(3).unwrap(), proof.code_hash);
+
+		let values = [U256::from(0x2a), U256::MAX, U256::zero()];
+		for (storage, value) in proof.storage_proof.iter().zip(values) {
+			assert_eq!(storage.value, value);
+			let proven = verify(proof.storage_hash, storage.key.as_bytes(), &storage.proof);
+			assert_eq!(
+				proven,
+				(!value.is_zero()).then(|| rlp::encode(&value).to_vec())
+			);
+		}
+
+		// A missing account is proven absent.
+		let missing = backend.account_proof(H160::repeat_byte(0x50), &keys[..1]);
+		assert_eq!(missing.balance, U256::zero());
+		assert_eq!(verify(root, &[0x50; 20], &missing.account_proof), None);
+		assert_eq!(missing.storage_proof[0].value, U256::zero());
+	}
+
+	#[test]
+	fn proofs_verify_in_a_deep_trie() {
+		let mut backend = TestBackend::new(TestEnvironment::default());
+		let address = H160::repeat_byte(0x40);
+		for i in 0..300 {
+			backend.set_storage(
+				address,
+				H256::from_low_u64_be(i),
+				H256::from_low_u64_be(i + 1),
+			);
+		}
+		assert_eq!(
+			backend.state_root(),
+			hash("fd47988a9437ada4cb445c1c4662536018ab3779b01f5dd12c9625b0b47d14e6")
+		);
+
+		let keys = (0..300)
+			.step_by(7)
+			.map(H256::from_low_u64_be)
+			.collect::<Vec<_>>();
+		let proof = backend.account_proof(address, &keys);
+		assert_eq!(
+			proof.storage_hash,
+			hash("0132b964525c9305c290c00f8135340df7d022ebfaf4efb11a59e6b08e432f99")
+		);
+		for storage in &proof.storage_proof {
+			let value = U256::from(storage.key.to_low_u64_be() + 1);
+			assert_eq!(
+				verify(proof.storage_hash, storage.key.as_bytes(), &storage.proof),
+				Some(rlp::encode(&value).to_vec())
+			);
+		}
+	}
+}
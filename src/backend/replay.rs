@@ -0,0 +1,399 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::cell::{Cell, RefCell};
+
+use evm_interpreter::runtime::{BlockHashProvider, RuntimeBaseBackend, RuntimeEnvironment};
+use primitive_types::{H160, H256, U256};
+
+/// Environment values read during a recorded execution. `None` for values
+/// that were never read.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedEnvironment {
+	pub block_hashes: BTreeMap<U256, H256>,
+	pub block_number: Option<U256>,
+	pub block_coinbase: Option<H160>,
+	pub block_timestamp: Option<U256>,
+	pub block_difficulty: Option<U256>,
+	pub block_randomness: Option<Option<H256>>,
+	pub block_parent_beacon_root: Option<Option<H256>>,
+	pub block_gas_limit: Option<U256>,
+	pub block_base_fee_per_gas: Option<U256>,
+	pub blob_base_fee: Option<U256>,
+	pub chain_id: Option<U256>,
+}
+
+/// Account values read during a recorded execution. `None` for values that
+/// were never read.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedAccount {
+	pub balance: Option<U256>,
+	pub nonce: Option<U256>,
+	pub exists: Option<bool>,
+	pub code: Option<Vec<u8>>,
+	pub code_size: Option<U256>,
+	pub code_hash: Option<H256>,
+	pub storage: BTreeMap<H256, H256>,
+	pub transient_storage: BTreeMap<H256, H256>,
+}
+
+/// All backend reads of an execution. Together with the transaction
+/// arguments and the config, this is enough to replay the execution with a
+/// [ReplayBackend]. With the `serde` feature, it can be serialized into a
+/// self-contained artifact.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recording {
+	pub environment: RecordedEnvironment,
+	pub accounts: BTreeMap<H160, RecordedAccount>,
+}
+
+/// A backend that forwards all reads to the wrapped backend, and records
+/// them.
+///
+/// This records reads of the base backend. Wrap it in an
+/// [crate::backend::OverlayedBackend] to execute transactions.
+pub struct RecordingBackend<B> {
+	backend: B,
+	recording: RefCell<Recording>,
+}
+
+impl<B> RecordingBackend<B> {
+	/// Create a new recording backend with an empty recording.
+	pub fn new(backend: B) -> Self {
+		Self {
+			backend,
+			recording: RefCell::new(Recording::default()),
+		}
+	}
+
+	/// The wrapped backend.
+	pub fn backend(&self) -> &B {
+		&self.backend
+	}
+
+	/// Values recorded so far.
+	pub fn recording(&self) -> Recording {
+		self.recording.borrow().clone()
+	}
+
+	/// Stop recording, and return the wrapped backend and the recording.
+	pub fn deconstruct(self) -> (B, Recording) {
+		(self.backend, self.recording.into_inner())
+	}
+
+	fn record_environment<T, F>(&self, value: T, f: F) -> T
+	where
+		T: Clone,
+		F: FnOnce(&mut RecordedEnvironment, T),
+	{
+		f(&mut self.recording.borrow_mut().environment, value.clone());
+		value
+	}
+
+	fn record_account<T, F>(&self, address: H160, value: T, f: F) -> T
+	where
+		T: Clone,
+		F: FnOnce(&mut RecordedAccount, T),
+	{
+		f(
+			self.recording
+				.borrow_mut()
+				.accounts
+				.entry(address)
+				.or_default(),
+			value.clone(),
+		);
+		value
+	}
+}
+
+impl<B: BlockHashProvider> BlockHashProvider for RecordingBackend<B> {
+	fn block_hash(&self, number: U256) -> H256 {
+		self.record_environment(self.backend.block_hash(number), |env, value| {
+			env.block_hashes.insert(number, value);
+		})
+	}
+}
+
+impl<B: RuntimeEnvironment> RuntimeEnvironment for RecordingBackend<B> {
+	fn block_number(&self) -> U256 {
+		self.record_environment(self.backend.block_number(), |env, value| {
+			env.block_number = Some(value)
+		})
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.record_environment(self.backend.block_coinbase(), |env, value| {
+			env.block_coinbase = Some(value)
+		})
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.record_environment(self.backend.block_timestamp(), |env, value| {
+			env.block_timestamp = Some(value)
+		})
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.record_environment(self.backend.block_difficulty(), |env, value| {
+			env.block_difficulty = Some(value)
+		})
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.record_environment(self.backend.block_randomness(), |env, value| {
+			env.block_randomness = Some(value)
+		})
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.record_environment(self.backend.block_parent_beacon_root(), |env, value| {
+			env.block_parent_beacon_root = Some(value)
+		})
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.record_environment(self.backend.block_gas_limit(), |env, value| {
+			env.block_gas_limit = Some(value)
+		})
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.record_environment(self.backend.block_base_fee_per_gas(), |env, value| {
+			env.block_base_fee_per_gas = Some(value)
+		})
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		self.record_environment(self.backend.blob_base_fee(), |env, value| {
+			env.blob_base_fee = Some(value)
+		})
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.record_environment(self.backend.chain_id(), |env, value| {
+			env.chain_id = Some(value)
+		})
+	}
+}
+
+impl<B: RuntimeBaseBackend> RuntimeBaseBackend for RecordingBackend<B> {
+	fn balance(&self, address: H160) -> U256 {
+		self.record_account(address, self.backend.balance(address), |account, value| {
+			account.balance = Some(value)
+		})
+	}
+
+	fn code_size(&self, address: H160) -> U256 {
+		self.record_account(
+			address,
+			self.backend.code_size(address),
+			|account, value| account.code_size = Some(value),
+		)
+	}
+
+	fn code_hash(&self, address: H160) -> H256 {
+		self.record_account(
+			address,
+			self.backend.code_hash(address),
+			|account, value| account.code_hash = Some(value),
+		)
+	}
+
+	fn keccak256(&self, data: &[u8]) -> H256 {
+		self.backend.keccak256(data)
+	}
+
+	fn ecrecover(&self, hash: H256, y_parity: u8, r: H256, s: H256) -> Option<H160> {
+		self.backend.ecrecover(hash, y_parity, r, s)
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.record_account(address, self.backend.code(address), |account, value| {
+			account.code = Some(value)
+		})
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.record_account(
+			address,
+			self.backend.storage(address, index),
+			|account, value| {
+				account.storage.insert(index, value);
+			},
+		)
+	}
+
+	fn transient_storage(&self, address: H160, index: H256) -> H256 {
+		self.record_account(
+			address,
+			self.backend.transient_storage(address, index),
+			|account, value| {
+				account.transient_storage.insert(index, value);
+			},
+		)
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.record_account(address, self.backend.exists(address), |account, value| {
+			account.exists = Some(value)
+		})
+	}
+
+	fn nonce(&self, address: H160) -> U256 {
+		self.record_account(address, self.backend.nonce(address), |account, value| {
+			account.nonce = Some(value)
+		})
+	}
+
+	fn prefetch(&self, addresses: &[H160], keys: &[(H160, H256)]) {
+		self.backend.prefetch(addresses, keys)
+	}
+}
+
+/// A backend that serves the values of a [Recording], without the original
+/// backend.
+///
+/// A deterministic replay reads exactly the recorded values. A read of a
+/// value that was not recorded returns the default, and marks the replay as
+/// diverged.
+pub struct ReplayBackend {
+	recording: Recording,
+	diverged: Cell<bool>,
+}
+
+impl ReplayBackend {
+	/// Create a new replay backend from the recording.
+	pub fn new(recording: Recording) -> Self {
+		Self {
+			recording,
+			diverged: Cell::new(false),
+		}
+	}
+
+	/// Whether any value that was not recorded has been read.
+	pub fn diverged(&self) -> bool {
+		self.diverged.get()
+	}
+
+	fn served<T: Clone + Default>(&self, value: Option<&T>) -> T {
+		match value {
+			Some(value) => value.clone(),
+			None => {
+				self.diverged.set(true);
+				T::default()
+			}
+		}
+	}
+
+	fn account(&self, address: H160) -> Option<&RecordedAccount> {
+		self.recording.accounts.get(&address)
+	}
+}
+
+impl BlockHashProvider for ReplayBackend {
+	fn block_hash(&self, number: U256) -> H256 {
+		self.served(self.recording.environment.block_hashes.get(&number))
+	}
+}
+
+impl RuntimeEnvironment for ReplayBackend {
+	fn block_number(&self) -> U256 {
+		self.served(self.recording.environment.block_number.as_ref())
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.served(self.recording.environment.block_coinbase.as_ref())
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.served(self.recording.environment.block_timestamp.as_ref())
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.served(self.recording.environment.block_difficulty.as_ref())
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.served(self.recording.environment.block_randomness.as_ref())
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.served(self.recording.environment.block_parent_beacon_root.as_ref())
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.served(self.recording.environment.block_gas_limit.as_ref())
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.served(self.recording.environment.block_base_fee_per_gas.as_ref())
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		self.served(self.recording.environment.blob_base_fee.as_ref())
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.served(self.recording.environment.chain_id.as_ref())
+	}
+}
+
+impl RuntimeBaseBackend for ReplayBackend {
+	fn balance(&self, address: H160) -> U256 {
+		self.served(
+			self.account(address)
+				.and_then(|account| account.balance.as_ref()),
+		)
+	}
+
+	fn code_size(&self, address: H160) -> U256 {
+		self.served(
+			self.account(address)
+				.and_then(|account| account.code_size.as_ref()),
+		)
+	}
+
+	fn code_hash(&self, address: H160) -> H256 {
+		self.served(
+			self.account(address)
+				.and_then(|account| account.code_hash.as_ref()),
+		)
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.served(
+			self.account(address)
+				.and_then(|account| account.code.as_ref()),
+		)
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.served(
+			self.account(address)
+				.and_then(|account| account.storage.get(&index)),
+		)
+	}
+
+	fn transient_storage(&self, address: H160, index: H256) -> H256 {
+		self.served(
+			self.account(address)
+				.and_then(|account| account.transient_storage.get(&index)),
+		)
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.served(
+			self.account(address)
+				.and_then(|account| account.exists.as_ref()),
+		)
+	}
+
+	fn nonce(&self, address: H160) -> U256 {
+		self.served(
+			self.account(address)
+				.and_then(|account| account.nonce.as_ref()),
+		)
+	}
+}
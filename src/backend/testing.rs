@@ -0,0 +1,286 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use evm_interpreter::runtime::{BlockHashProvider, RuntimeBaseBackend, RuntimeEnvironment};
+use primitive_types::{H160, H256, U256};
+
+use crate::backend::{GenesisAccount, GenesisAlloc, OverlayedChangeSet};
+
+/// Block environment of a [TestBackend].
+#[derive(Clone, Debug, Default)]
+pub struct TestEnvironment {
+	pub block_hashes: BTreeMap<U256, H256>,
+	pub block_number: U256,
+	pub block_coinbase: H160,
+	pub block_timestamp: U256,
+	pub block_difficulty: U256,
+	pub block_randomness: Option<H256>,
+	pub block_parent_beacon_root: Option<H256>,
+	pub block_gas_limit: U256,
+	pub block_base_fee_per_gas: U256,
+	pub blob_base_fee: U256,
+	pub chain_id: U256,
+}
+
+/// Account of a [TestBackend].
+#[derive(Clone, Debug, Default)]
+pub struct TestAccount {
+	pub balance: U256,
+	pub nonce: U256,
+	pub code: Vec<u8>,
+	pub storage: BTreeMap<H256, H256>,
+}
+
+/// An in-memory backend for contract test harnesses, with direct mutation
+/// APIs.
+///
+/// Wrap it in an [crate::backend::OverlayedBackend] to execute transactions,
+/// and write the results back with [TestBackend::apply].
+///
+/// The mutation APIs can also be exposed to contracts as cheatcodes, by
+/// registering a precompile in a [crate::standard::PrecompileRegistry]. The
+/// precompile handler is then the overlayed backend. Environment cheatcodes
+/// (warp, roll) can go through [crate::backend::OverlayedBackend::backend_mut]
+/// directly, because the overlay never shadows the environment. State
+/// cheatcodes must go through the overlay
+/// ([crate::backend::OverlayedBackend::set_balance],
+/// [crate::backend::OverlayedBackend::set_nonce] and the
+/// [crate::backend::RuntimeBackend] setters), so that they are seen by the
+/// rest of the transaction and reverted together with the calling frame.
+#[derive(Clone, Debug, Default)]
+pub struct TestBackend {
+	pub environment: TestEnvironment,
+	pub state: BTreeMap<H160, TestAccount>,
+	impersonated: Option<H160>,
+}
+
+impl TestBackend {
+	/// Create a new test backend with the given environment and no accounts.
+	pub fn new(environment: TestEnvironment) -> Self {
+		Self {
+			environment,
+			state: BTreeMap::new(),
+			impersonated: None,
+		}
+	}
+
+	/// Set the balance of an account.
+	pub fn set_balance(&mut self, address: H160, balance: U256) {
+		self.state.entry(address).or_default().balance = balance;
+	}
+
+	/// Set the nonce of an account.
+	pub fn set_nonce(&mut self, address: H160, nonce: U256) {
+		self.state.entry(address).or_default().nonce = nonce;
+	}
+
+	/// Set the code of an account.
+	pub fn set_code(&mut self, address: H160, code: Vec<u8>) {
+		self.state.entry(address).or_default().code = code;
+	}
+
+	/// Set a storage value of an account.
+	pub fn set_storage(&mut self, address: H160, index: H256, value: H256) {
+		let account = self.state.entry(address).or_default();
+
+		if value == H256::default() {
+			account.storage.remove(&index);
+		} else {
+			account.storage.insert(index, value);
+		}
+	}
+
+	/// Load the accounts of a genesis state. Existing accounts with the same
+	/// address are replaced.
+	pub fn load_genesis(&mut self, alloc: GenesisAlloc) {
+		for (address, account) in alloc {
+			let storage = account
+				.storage
+				.into_iter()
+				.filter(|(_, value)| *value != H256::default())
+				.collect();
+
+			self.state.insert(
+				address,
+				TestAccount {
+					balance: account.balance,
+					nonce: account.nonce,
+					code: account.code,
+					storage,
+				},
+			);
+		}
+	}
+
+	/// Export all accounts, with their code and storage, as a genesis state.
+	/// Loading it into an empty backend with [TestBackend::load_genesis]
+	/// gives back the same state.
+	pub fn dump_state(&self) -> GenesisAlloc {
+		self.state
+			.iter()
+			.map(|(address, account)| {
+				(
+					*address,
+					GenesisAccount {
+						balance: account.balance,
+						nonce: account.nonce,
+						code: account.code.clone(),
+						storage: account.storage.clone(),
+					},
+				)
+			})
+			.collect()
+	}
+
+	/// Set the block timestamp.
+	pub fn warp(&mut self, timestamp: U256) {
+		self.environment.block_timestamp = timestamp;
+	}
+
+	/// Set the block number.
+	pub fn roll(&mut self, number: U256) {
+		self.environment.block_number = number;
+	}
+
+	/// Make [TestBackend::sender] return the given address until
+	/// [TestBackend::stop_impersonating] is called.
+	pub fn impersonate(&mut self, address: H160) {
+		self.impersonated = Some(address);
+	}
+
+	/// Stop impersonating.
+	pub fn stop_impersonating(&mut self) {
+		self.impersonated = None;
+	}
+
+	/// The sender a harness should use for the next transaction: the
+	/// impersonated address if any, or otherwise the given caller.
+	pub fn sender(&self, caller: H160) -> H160 {
+		self.impersonated.unwrap_or(caller)
+	}
+
+	/// Write the changes of an overlayed execution back into the backend.
+	/// Logs and transient storage are dropped.
+	pub fn apply(&mut self, changeset: OverlayedChangeSet) {
+		for address in changeset.deletes {
+			self.state.remove(&address);
+		}
+
+		for (address, balance) in changeset.balances {
+			self.state.entry(address).or_default().balance = balance;
+		}
+
+		for (address, code) in changeset.codes {
+			self.state.entry(address).or_default().code = code;
+		}
+
+		for (address, nonce) in changeset.nonces {
+			self.state.entry(address).or_default().nonce = nonce;
+		}
+
+		for address in changeset.storage_resets {
+			// A reset alone, as left by a delete, does not create the account.
+			if let Some(account) = self.state.get_mut(&address) {
+				account.storage = BTreeMap::new();
+			}
+		}
+
+		for ((address, index), value) in changeset.storages {
+			self.set_storage(address, index, value);
+		}
+	}
+}
+
+impl BlockHashProvider for TestBackend {
+	fn block_hash(&self, number: U256) -> H256 {
+		self.environment
+			.block_hashes
+			.get(&number)
+			.cloned()
+			.unwrap_or_default()
+	}
+}
+
+impl RuntimeEnvironment for TestBackend {
+	fn block_number(&self) -> U256 {
+		self.environment.block_number
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.environment.block_coinbase
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.environment.block_timestamp
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.environment.block_difficulty
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.environment.block_randomness
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.environment.block_parent_beacon_root
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.environment.block_gas_limit
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.environment.block_base_fee_per_gas
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		self.environment.blob_base_fee
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.environment.chain_id
+	}
+}
+
+impl RuntimeBaseBackend for TestBackend {
+	fn balance(&self, address: H160) -> U256 {
+		self.state
+			.get(&address)
+			.map(|account| account.balance)
+			.unwrap_or_default()
+	}
+
+	#[cfg(feature = "signed-transaction")]
+	fn ecrecover(&self, hash: H256, y_parity: u8, r: H256, s: H256) -> Option<H160> {
+		crate::standard::signed::ecrecover(hash, y_parity, r, s)
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.state
+			.get(&address)
+			.map(|account| account.code.clone())
+			.unwrap_or_default()
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.state
+			.get(&address)
+			.and_then(|account| account.storage.get(&index).cloned())
+			.unwrap_or_default()
+	}
+
+	fn transient_storage(&self, _address: H160, _index: H256) -> H256 {
+		H256::default()
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.state.contains_key(&address)
+	}
+
+	fn nonce(&self, address: H160) -> U256 {
+		self.state
+			.get(&address)
+			.map(|account| account.nonce)
+			.unwrap_or_default()
+	}
+}
@@ -0,0 +1,308 @@
+//! Command line runner, in the style of geth's `evm run`, enabled by the `cli`
+//! feature:
+//!
+//! ```text
+//! cargo install evm --features cli
+//! ```
+//!
+//! `evm run` runs bytecode against an in-memory backend, and prints the
+//! result as JSON. With `--trace`, the steps are written to stderr as JSON
+//! lines with the `pc`, `op`, `opName`, `gas`, `memSize`, `stack` and `depth`
+//! of EIP-3155, but not its `gasCost`, `refund` or `returnData`.
+//! `evm repl` builds and steps through bytecode interactively.
+//! `evm statetest` runs the post-states of a state test fixture, and reports
+//! whether each one passes.
+//!
+//! The precompiles are in the `evm-precompile` crate, which depends on this
+//! one, so calls to precompile addresses run as calls to empty accounts. The
+//! `jsontests` runner runs state tests with the precompiles.
+
+mod repl;
+mod statetest;
+
+use std::{
+	cell::RefCell,
+	collections::BTreeSet,
+	error::Error as StdError,
+	fs,
+	io::{self, Write},
+};
+
+use clap::{Args, Parser, Subcommand};
+use evm::{
+	backend::{OverlayedBackend, TestBackend, TestEnvironment},
+	interpreter::{
+		error::{ExitError, ExitResult},
+		etable::Control,
+		opcode::Opcode,
+		runtime::GasState,
+	},
+	standard::{
+		inspector::{InspectedEtable, Inspector, InspectorMiddleware},
+		Config, Etable, EtableResolver, Fork, Invoker, Machine, State, SubstackInvoke,
+		TransactArgs, TransactValue,
+	},
+	MiddlewareInvoker,
+};
+use primitive_types::{H160, U256};
+use serde_json::json;
+
+type Error = Box<dyn StdError>;
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Run bytecode against an in-memory backend.
+	Run(RunArgs),
+	/// Build and step through bytecode interactively.
+	Repl {
+		/// Gas limit.
+		#[arg(long, default_value_t = 10_000_000)]
+		gas: u64,
+
+		/// Fork, by name.
+		#[arg(long, default_value = "prague")]
+		fork: String,
+	},
+	/// Run a state test fixture, and report each post-state as passed or
+	/// failed.
+	Statetest {
+		/// Fixture file.
+		file: String,
+	},
+}
+
+#[derive(Args)]
+struct RunArgs {
+	/// Code to run, as hex.
+	#[arg(long, required_unless_present = "codefile")]
+	code: Option<String>,
+
+	/// File with the code to run, as hex.
+	#[arg(long, conflicts_with = "code")]
+	codefile: Option<String>,
+
+	/// Call data, as hex.
+	#[arg(long, default_value = "")]
+	input: String,
+
+	/// Run the code as init code of a create transaction.
+	#[arg(long, default_value_t = false)]
+	create: bool,
+
+	/// Gas limit.
+	#[arg(long, default_value_t = 10_000_000)]
+	gas: u64,
+
+	/// Value sent with the call, in wei.
+	#[arg(long, default_value_t = 0)]
+	value: u128,
+
+	/// Fork, by name.
+	#[arg(long, default_value = "prague")]
+	fork: String,
+
+	/// Write every step to stderr as a line of JSON.
+	#[arg(long, default_value_t = false)]
+	trace: bool,
+}
+
+fn parse_hex(value: &str) -> Result<Vec<u8>, Error> {
+	let value = value.trim();
+	Ok(hex::decode(value.strip_prefix("0x").unwrap_or(value))?)
+}
+
+const CALLER: H160 = H160([0x10; 20]);
+const RECEIVER: H160 = H160([0x20; 20]);
+
+fn fork_config(name: &str) -> Result<&'static Config, Error> {
+	Fork::ALL
+		.iter()
+		.find(|fork| fork.name() == name)
+		.map(|fork| fork.config())
+		.ok_or_else(|| format!("unsupported fork {}", name).into())
+}
+
+/// An inspector that writes every step to the writer as a line of JSON, with
+/// a subset of the fields of EIP-3155.
+/// Writing stops at the first error, which is returned by [Self::finish].
+struct StepWriter<W> {
+	writer: W,
+	depth: usize,
+	error: Option<io::Error>,
+}
+
+impl<W: Write> StepWriter<W> {
+	fn new(writer: W) -> Self {
+		Self {
+			writer,
+			depth: 1,
+			error: None,
+		}
+	}
+
+	fn finish(mut self) -> io::Result<()> {
+		match self.error {
+			Some(error) => Err(error),
+			None => self.writer.flush(),
+		}
+	}
+}
+
+impl<'config, H, Tr, W: Write> Inspector<State<'config>, H, Tr> for StepWriter<W> {
+	fn step(
+		&mut self,
+		machine: &mut Machine<'config>,
+		_handler: &mut H,
+		opcode: Opcode,
+		position: usize,
+	) -> Option<Control<Tr>> {
+		if self.error.is_none() {
+			let stack = machine
+				.stack
+				.data()
+				.iter()
+				.map(|value| format!("0x{:x}", U256::from_big_endian(&value[..])))
+				.collect::<Vec<_>>();
+			let line = json!({
+				"pc": position,
+				"op": opcode.0,
+				"opName": opcode.name(),
+				"gas": format!("0x{:x}", machine.state.gas()),
+				"memSize": machine.memory.len(),
+				"stack": stack,
+				"depth": self.depth,
+			});
+			if let Err(error) = writeln!(self.writer, "{}", line) {
+				self.error = Some(error);
+			}
+		}
+		None
+	}
+
+	fn enter_substack(
+		&mut self,
+		_invoke: &SubstackInvoke,
+		_handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		self.depth += 1;
+		None
+	}
+
+	fn exit_substack(
+		&mut self,
+		_invoke: &SubstackInvoke,
+		_state: &State<'config>,
+		_result: &mut ExitResult,
+		_retval: &mut Vec<u8>,
+		_handler: &mut H,
+	) {
+		self.depth -= 1;
+	}
+}
+
+fn run_code(args: RunArgs) -> Result<(), Error> {
+	let config = fork_config(&args.fork)?;
+	let code = match (args.code, args.codefile) {
+		(Some(code), _) => parse_hex(&code)?,
+		(None, Some(codefile)) => parse_hex(&fs::read_to_string(codefile)?)?,
+		(None, None) => Vec::new(),
+	};
+
+	let mut base_backend = TestBackend::new(TestEnvironment {
+		block_gas_limit: U256::from(args.gas),
+		chain_id: U256::one(),
+		..Default::default()
+	});
+	base_backend.set_balance(CALLER, U256::MAX);
+	let transact_args = if args.create {
+		TransactArgs::Create {
+			caller: CALLER,
+			value: U256::from(args.value),
+			init_code: code,
+			salt: None,
+			gas_limit: U256::from(args.gas),
+			gas_price: U256::zero(),
+			access_list: Vec::new(),
+			nonce: None,
+			chain_id: None,
+			system: false,
+			mint: None,
+			fee_payer: None,
+		}
+	} else {
+		base_backend.set_code(RECEIVER, code);
+		TransactArgs::Call {
+			caller: CALLER,
+			address: RECEIVER,
+			value: U256::from(args.value),
+			data: parse_hex(&args.input)?,
+			gas_limit: U256::from(args.gas),
+			gas_price: U256::zero(),
+			access_list: Vec::new(),
+			nonce: None,
+			chain_id: None,
+			system: false,
+			mint: None,
+			fee_payer: None,
+		}
+	};
+
+	let mut backend = OverlayedBackend::new(&base_backend, BTreeSet::new());
+	let gas_etable = Etable::single(evm::standard::eval_gasometer);
+	let exec_etable = Etable::runtime();
+
+	let result = if args.trace {
+		let tracer = RefCell::new(StepWriter::new(io::stderr()));
+		let etable = InspectedEtable::new((gas_etable, exec_etable), &tracer);
+		let resolver = EtableResolver::new(config, &(), &etable);
+		let invoker = MiddlewareInvoker::new(
+			Invoker::new(config, &resolver),
+			InspectorMiddleware::new(&tracer),
+		);
+		let result = evm::transact_hybrid(transact_args, Some(4), &mut backend, &invoker);
+		tracer.into_inner().finish()?;
+		result
+	} else {
+		let etable = (gas_etable, exec_etable);
+		let resolver = EtableResolver::new(config, &(), &etable);
+		let invoker = Invoker::new(config, &resolver);
+		evm::transact_hybrid(transact_args, Some(4), &mut backend, &invoker)
+	};
+
+	println!("{}", format_result(&result?));
+	Ok(())
+}
+
+fn format_result(value: &TransactValue) -> String {
+	let error = match &value.exit {
+		Ok(_) => String::new(),
+		Err(ExitError::Reverted) => match &value.revert_reason {
+			Some(reason) => format!("execution reverted: {}", reason),
+			None => "execution reverted".to_string(),
+		},
+		Err(err) => err.to_string(),
+	};
+	json!({
+		"output": hex::encode(&value.retval),
+		"gasUsed": format!("0x{:x}", value.used_gas),
+		"error": error,
+	})
+	.to_string()
+}
+
+fn main() -> Result<(), Error> {
+	let cli = Cli::parse();
+
+	match cli.command {
+		Command::Run(args) => run_code(args),
+		Command::Repl { gas, fork } => repl::Repl::new(fork_config(&fork)?, gas).run(),
+		Command::Statetest { file } => statetest::run(&file),
+	}
+}
@@ -0,0 +1,410 @@
+//! Interactive bytecode REPL.
+//!
+//! The transaction is kept running between commands. The code can be
+//! extended at any time. The execution is then replayed from the start of the
+//! transaction on a fresh backend, up to the number of steps taken so far, so
+//! that new code continues from the same point.
+
+use std::{
+	collections::BTreeSet,
+	io::{self, BufRead, Write},
+};
+
+use evm::{
+	backend::{OverlayedBackend, TestBackend, TestEnvironment},
+	interpreter::{
+		disasm::disassemble,
+		error::{Capture, ExitError},
+		opcode::Opcode,
+		runtime::{GasState, RuntimeBaseBackend},
+		Interpreter, StepInterpreter,
+	},
+	standard::{Config, Etable, EtableResolver, Invoker, State, TransactArgs, TransactValue},
+	HeapTransact,
+};
+use primitive_types::{H256, U256};
+
+use crate::{format_result, parse_hex, Error, CALLER, RECEIVER};
+
+const HELP: &str = "\
+code <hex>          append bytecode
+asm <op> [<hex>]    append an opcode by mnemonic, with its immediate data
+input <hex>         set the call data
+step [<n>]          execute the next n opcodes, 1 by default
+run                 execute until the transaction exits
+reset               restart the execution
+disasm              list the code
+info                show the next opcode, gas and depth
+stack               show the stack, top first
+memory              show the memory
+storage             show the storage slots accessed by the code
+help                show this help
+quit                exit";
+
+pub struct Repl {
+	config: &'static Config,
+	gas: u64,
+	code: Vec<u8>,
+	input: Vec<u8>,
+	steps: usize,
+}
+
+/// What to do after a command.
+enum Next {
+	Continue,
+	/// Start the transaction again, and replay the steps taken so far.
+	Restart,
+	Quit,
+}
+
+impl Repl {
+	pub fn new(config: &'static Config, gas: u64) -> Self {
+		Self {
+			config,
+			gas,
+			code: Vec::new(),
+			input: Vec::new(),
+			steps: 0,
+		}
+	}
+
+	/// Start the transaction, replay the steps taken so far, and evaluate
+	/// commands on it until one of them changes the code or the call data.
+	fn session<L>(&mut self, lines: &mut L) -> Result<bool, Error>
+	where
+		L: Iterator<Item = io::Result<String>>,
+	{
+		let mut base_backend = TestBackend::new(TestEnvironment {
+			block_gas_limit: U256::from(self.gas),
+			chain_id: U256::one(),
+			..Default::default()
+		});
+		base_backend.set_balance(CALLER, U256::MAX);
+		base_backend.set_code(RECEIVER, self.code.clone());
+		let args = TransactArgs::Call {
+			caller: CALLER,
+			address: RECEIVER,
+			value: U256::zero(),
+			data: self.input.clone(),
+			gas_limit: U256::from(self.gas),
+			gas_price: U256::zero(),
+			access_list: Vec::new(),
+			nonce: None,
+			chain_id: None,
+			system: false,
+			mint: None,
+			fee_payer: None,
+		};
+
+		let mut backend = OverlayedBackend::new(&base_backend, BTreeSet::new());
+		let etable = (
+			Etable::single(evm::standard::eval_gasometer),
+			Etable::runtime(),
+		);
+		let resolver = EtableResolver::new(self.config, &(), &etable);
+		let invoker = Invoker::new(self.config, &resolver);
+
+		let mut session = match HeapTransact::new(args, &invoker, &mut backend) {
+			Ok(stepper) => Session {
+				stepper: Some(stepper),
+				steps: 0,
+				exit: None,
+			},
+			Err(err) => Session {
+				stepper: None,
+				steps: 0,
+				exit: Some(format!("{:?}", err)),
+			},
+		};
+		session.start();
+		if session.advance(self.steps) < self.steps {
+			println!("exited after {} steps", session.steps);
+		}
+		self.steps = session.steps;
+
+		loop {
+			print!("> ");
+			io::stdout().flush()?;
+
+			let line = match lines.next() {
+				Some(line) => line?,
+				None => return Ok(false),
+			};
+			match self.eval(&mut session, &line) {
+				Ok(Next::Continue) => (),
+				Ok(Next::Restart) => return Ok(true),
+				Ok(Next::Quit) => return Ok(false),
+				Err(err) => println!("error: {}", err),
+			}
+		}
+	}
+
+	fn append(&mut self, code: &[u8]) -> Next {
+		self.code.extend_from_slice(code);
+		println!("{} bytes of code", self.code.len());
+		Next::Restart
+	}
+
+	/// Evaluate a command.
+	fn eval<S: Stepper>(&mut self, session: &mut S, line: &str) -> Result<Next, Error> {
+		let mut words = line.split_whitespace();
+		let command = match words.next() {
+			Some(command) => command,
+			None => return Ok(Next::Continue),
+		};
+		let arg = words.next();
+
+		match command {
+			"code" => return Ok(self.append(&parse_hex(arg.unwrap_or(""))?)),
+			"asm" => {
+				let name = arg.unwrap_or("").to_uppercase();
+				let opcode = (0..=u8::MAX)
+					.map(Opcode)
+					.find(|opcode| opcode.name() == Some(name.as_str()));
+				match opcode {
+					Some(opcode) => {
+						let mut immediate = parse_immediate(words.next().unwrap_or(""))?;
+						let size = opcode.immediate_size(self.config.eip_663_enabled);
+						if immediate.len() > size {
+							println!("{} takes {} bytes of immediate data", name, size);
+							return Ok(Next::Continue);
+						}
+						// Immediate data is right-aligned, as for push values.
+						let mut code = vec![opcode.0];
+						code.resize(1 + size - immediate.len(), 0);
+						code.append(&mut immediate);
+						return Ok(self.append(&code));
+					}
+					None => println!("unknown opcode {}", name),
+				}
+			}
+			"input" => {
+				self.input = parse_hex(arg.unwrap_or(""))?;
+				self.steps = 0;
+				println!(
+					"{} bytes of call data, execution restarted",
+					self.input.len()
+				);
+				return Ok(Next::Restart);
+			}
+			"step" | "run" => {
+				let steps = match (command, arg.map(str::parse)) {
+					("run", _) => usize::MAX,
+					(_, None) => 1,
+					(_, Some(Ok(steps))) => steps,
+					(_, Some(Err(_))) => {
+						println!("invalid number of steps");
+						return Ok(Next::Continue);
+					}
+				};
+				self.steps = session.advance(steps);
+				match session.exit() {
+					Some(exit) => println!("exited: {}", exit),
+					None => print_info(&session.snapshot(), self.steps),
+				}
+			}
+			"reset" => {
+				self.steps = 0;
+				println!("execution restarted");
+				return Ok(Next::Restart);
+			}
+			"disasm" => print!("{}", disassemble(&self.code, self.config.eip_663_enabled)),
+			"info" | "stack" | "memory" | "storage" => {
+				if let Some(exit) = session.exit() {
+					println!("exited: {}", exit);
+					return Ok(Next::Continue);
+				}
+
+				let snapshot = session.snapshot();
+				match command {
+					"info" => print_info(&snapshot, self.steps),
+					"stack" => {
+						for (i, value) in snapshot.stack.iter().enumerate() {
+							println!("{:4}: 0x{:x}", i, U256::from_big_endian(&value[..]));
+						}
+					}
+					"memory" => {
+						for (i, word) in snapshot.memory.chunks(32).enumerate() {
+							println!("0x{:04x}: {}", i * 32, hex::encode(word));
+						}
+					}
+					_ => {
+						for (index, value) in snapshot.storage {
+							println!("0x{:x}: 0x{:x}", index, value);
+						}
+					}
+				}
+			}
+			"help" => println!("{}", HELP),
+			"quit" | "exit" => return Ok(Next::Quit),
+			_ => println!("unknown command {}, see help", command),
+		}
+
+		Ok(Next::Continue)
+	}
+
+	/// Read and evaluate commands until the input ends.
+	pub fn run(&mut self) -> Result<(), Error> {
+		let stdin = io::stdin();
+		let mut lines = stdin.lock().lines();
+		while self.session(&mut lines)? {}
+		Ok(())
+	}
+}
+
+/// View of the machine of a running transaction.
+struct Snapshot {
+	position: usize,
+	opcode: Option<Opcode>,
+	gas: u64,
+	depth: usize,
+	stack: Vec<H256>,
+	memory: Vec<u8>,
+	storage: Vec<(H256, H256)>,
+}
+
+/// A transaction being stepped through, kept between commands.
+struct Session<'backend, 'invoker, H, Tr, I: evm::Invoker<H, Tr>> {
+	/// The transaction, or `None` if it could not be created.
+	stepper: Option<HeapTransact<'backend, 'invoker, H, Tr, I>>,
+	steps: usize,
+	exit: Option<String>,
+}
+
+impl<'backend, 'invoker, H, Tr, I> Session<'backend, 'invoker, H, Tr, I>
+where
+	I: evm::Invoker<H, Tr, TransactValue = TransactValue>,
+	I::Interpreter: StepInterpreter<H, Tr>,
+{
+	/// Take a step, unless the transaction has exited. Return whether the
+	/// transaction is still running.
+	fn step(&mut self) -> bool {
+		if self.exit.is_none() {
+			match &mut self.stepper {
+				Some(stepper) => {
+					if let Err(capture) = stepper.step() {
+						self.exit = Some(format_capture(capture));
+					}
+				}
+				None => unreachable!("a session without a transaction has exited"),
+			}
+		}
+		self.exit.is_none()
+	}
+}
+
+/// The operations of a [Session] used by the commands.
+trait Stepper {
+	/// Start the transaction. The first step sets up the call frame, and
+	/// does not execute any opcode.
+	fn start(&mut self);
+
+	/// Take at most `steps` steps, and return the number of steps taken so
+	/// far. The step that exits the transaction is not counted, so that
+	/// appended code continues from there.
+	fn advance(&mut self, steps: usize) -> usize;
+
+	/// The exit of the transaction, if it has exited.
+	fn exit(&self) -> Option<&str>;
+
+	/// View of the machine.
+	fn snapshot(&self) -> Snapshot;
+}
+
+impl<'backend, 'invoker, 'base, 'config, Tr, I> Stepper
+	for Session<'backend, 'invoker, OverlayedBackend<&'base TestBackend>, Tr, I>
+where
+	I: evm::Invoker<
+		OverlayedBackend<&'base TestBackend>,
+		Tr,
+		State = State<'config>,
+		TransactValue = TransactValue,
+	>,
+	I::Interpreter: StepInterpreter<OverlayedBackend<&'base TestBackend>, Tr>,
+{
+	fn start(&mut self) {
+		self.step();
+	}
+
+	fn advance(&mut self, steps: usize) -> usize {
+		let mut taken = 0;
+		while taken < steps && self.step() {
+			taken += 1;
+		}
+		self.steps += taken;
+		self.steps
+	}
+
+	fn exit(&self) -> Option<&str> {
+		self.exit.as_deref()
+	}
+
+	fn snapshot(&self) -> Snapshot {
+		let mut snapshot = Snapshot {
+			position: 0,
+			opcode: None,
+			gas: 0,
+			depth: 0,
+			stack: Vec::new(),
+			memory: Vec::new(),
+			storage: Vec::new(),
+		};
+		let stepper = match &self.stepper {
+			Some(stepper) => stepper,
+			None => return snapshot,
+		};
+		snapshot.depth = stepper.depth().unwrap_or(0);
+		if let Some(interpreter) = stepper.last_interpreter() {
+			let machine = interpreter.machine();
+			snapshot.position = interpreter.position();
+			snapshot.opcode = interpreter.peek_opcode();
+			snapshot.gas = machine.state.gas();
+			snapshot.stack = machine.stack.data().iter().rev().copied().collect();
+			snapshot.memory = machine.memory.data().to_vec();
+		}
+		if let Some(backend) = stepper.backend() {
+			snapshot.storage = backend
+				.accessed()
+				.iter()
+				.filter_map(|(address, index)| match index {
+					Some(index) if *address == RECEIVER => {
+						Some((*index, backend.storage(RECEIVER, *index)))
+					}
+					_ => None,
+				})
+				.collect();
+		}
+		snapshot
+	}
+}
+
+fn format_capture<T>(capture: Capture<Result<TransactValue, ExitError>, T>) -> String {
+	match capture {
+		Capture::Exit(Ok(value)) => format_result(&value),
+		Capture::Exit(Err(err)) => format!("{:?}", err),
+		Capture::Trap(_) => "interrupted".to_string(),
+	}
+}
+
+/// Parse immediate data, with an odd number of digits allowed.
+fn parse_immediate(value: &str) -> Result<Vec<u8>, Error> {
+	let value = value.strip_prefix("0x").unwrap_or(value);
+	if value.len() % 2 == 1 {
+		parse_hex(&format!("0{}", value))
+	} else {
+		parse_hex(value)
+	}
+}
+
+fn print_info(snapshot: &Snapshot, steps: usize) {
+	match snapshot.opcode {
+		Some(opcode) => println!(
+			"step {}, pc {}: {}, gas {}, depth {}",
+			steps, snapshot.position, opcode, snapshot.gas, snapshot.depth
+		),
+		None => println!(
+			"step {}, pc {}: end of code, gas {}",
+			steps, snapshot.position, snapshot.gas
+		),
+	}
+}
@@ -0,0 +1,317 @@
+//! State test runner.
+//!
+//! Runs the state test fixtures of `ethereum/tests` and
+//! `execution-spec-tests`: each test loads its pre-state into an in-memory
+//! backend, and each post-state of a supported fork runs the transaction with
+//! the indexed data, gas limit and value, and compares the state root and the
+//! logs hash. Results are printed as one JSON line per post-state.
+//!
+//! The fixture must give the `sender` of the transaction, as filled fixtures
+//! do. Calls to precompiles run as calls to empty accounts, and blob fees are
+//! not charged, so the post-states of those tests fail.
+
+use std::{collections::BTreeSet, fs};
+
+use evm::{
+	backend::{GenesisAlloc, OverlayedBackend, TestBackend, TestEnvironment},
+	interpreter::runtime::Log,
+	standard::{validate_transaction, Config, Etable, EtableResolver, Fork, Invoker, TransactArgs},
+};
+use primitive_types::{H160, H256, U256};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::{parse_hex, Error};
+
+/// Outcome of a post-state.
+struct Outcome {
+	pass: bool,
+	state_root: H256,
+	error: Option<String>,
+}
+
+/// Run all tests of the fixture file. Fails if any post-state fails.
+pub fn run(file: &str) -> Result<(), Error> {
+	let fixture: Value = serde_json::from_str(&fs::read_to_string(file)?)?;
+	let tests = fixture.as_object().ok_or("fixture is not a JSON object")?;
+
+	let mut failed = 0;
+	let mut total = 0;
+	for (name, test) in tests {
+		let post = test["post"]
+			.as_object()
+			.ok_or_else(|| format!("{}: missing post", name))?;
+
+		for (fork, states) in post {
+			let config = match fork_config(fork) {
+				Some(config) => config,
+				None => {
+					println!(
+						"{}",
+						json!({ "name": name, "fork": fork, "skipped": "unsupported fork" })
+					);
+					continue;
+				}
+			};
+
+			for (index, expected) in states
+				.as_array()
+				.ok_or_else(|| format!("{}: post of {} is not a list", name, fork))?
+				.iter()
+				.enumerate()
+			{
+				let outcome =
+					run_post_state(test, expected, config).unwrap_or_else(|error| Outcome {
+						pass: false,
+						state_root: H256::zero(),
+						error: Some(error.to_string()),
+					});
+
+				total += 1;
+				if !outcome.pass {
+					failed += 1;
+				}
+				println!(
+					"{}",
+					json!({
+						"name": name,
+						"fork": fork,
+						"index": index,
+						"pass": outcome.pass,
+						"stateRoot": format!("{:?}", outcome.state_root),
+						"error": outcome.error,
+					})
+				);
+			}
+		}
+	}
+
+	if failed > 0 {
+		return Err(format!("{} of {} post-states failed", failed, total).into());
+	}
+	Ok(())
+}
+
+/// Config of a fork, by its name in fixtures.
+fn fork_config(name: &str) -> Option<&'static Config> {
+	let name = if name == "Paris" { "merge" } else { name };
+	Fork::ALL
+		.iter()
+		.find(|fork| fork.name().eq_ignore_ascii_case(name))
+		.map(|fork| fork.config())
+}
+
+fn run_post_state(test: &Value, expected: &Value, config: &Config) -> Result<Outcome, Error> {
+	let env = &test["env"];
+	let mut base = TestBackend::new(TestEnvironment {
+		block_number: quantity(&env["currentNumber"])?,
+		block_coinbase: address(&env["currentCoinbase"])?,
+		block_timestamp: quantity(&env["currentTimestamp"])?,
+		block_difficulty: optional(&env["currentDifficulty"], quantity)?.unwrap_or_default(),
+		block_randomness: optional(&env["currentRandom"], hash)?,
+		block_parent_beacon_root: optional(&env["currentBeaconRoot"], hash)?,
+		block_gas_limit: quantity(&env["currentGasLimit"])?,
+		block_base_fee_per_gas: if config.eip_1559_enabled {
+			optional(&env["currentBaseFee"], quantity)?.unwrap_or_default()
+		} else {
+			U256::zero()
+		},
+		chain_id: U256::one(),
+		..Default::default()
+	});
+	base.load_genesis(serde_json::from_value::<GenesisAlloc>(test["pre"].clone())?);
+
+	let args = transact_args(&test["transaction"], &expected["indexes"], &base)?;
+
+	let etable = (
+		Etable::single(evm::standard::eval_gasometer),
+		Etable::runtime(),
+	);
+	let resolver = EtableResolver::new(config, &(), &etable);
+	let invoker = Invoker::new(config, &resolver);
+
+	let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+	let result = match validate_transaction(&args, &backend, config) {
+		Ok(()) => evm::transact_hybrid(args, Some(4), &mut backend, &invoker)
+			.map_err(|error| error.to_string()),
+		Err(error) => Err(error.to_string()),
+	};
+	let changeset = backend.deconstruct().1;
+	let mut post = base.clone();
+	post.apply(changeset);
+	let state_root = post.state_root();
+
+	if !expected["expectException"].is_null() {
+		return Ok(match result {
+			Err(_) => Outcome {
+				pass: true,
+				state_root,
+				error: None,
+			},
+			Ok(_) => Outcome {
+				pass: false,
+				state_root,
+				error: Some("expected an invalid transaction".to_string()),
+			},
+		});
+	}
+
+	let value = match result {
+		Ok(value) => value,
+		Err(error) => {
+			return Ok(Outcome {
+				pass: false,
+				state_root,
+				error: Some(format!("invalid transaction: {}", error)),
+			})
+		}
+	};
+
+	let logs_hash = logs_hash(&value.logs);
+	let error = if state_root != hash(&expected["hash"])? {
+		Some("state root mismatch".to_string())
+	} else if logs_hash != hash(&expected["logs"])? {
+		Some(format!("logs hash mismatch: got {:?}", logs_hash))
+	} else {
+		None
+	};
+	Ok(Outcome {
+		pass: error.is_none(),
+		state_root,
+		error,
+	})
+}
+
+/// Transaction of the post-state, with the data, gas limit and value at its
+/// indexes.
+fn transact_args(
+	transaction: &Value,
+	indexes: &Value,
+	base: &TestBackend,
+) -> Result<TransactArgs, Error> {
+	let indexed = |field: &str, index: &str| -> Result<&Value, Error> {
+		let index = indexes[index]
+			.as_u64()
+			.ok_or_else(|| format!("missing index of {}", field))?;
+		Ok(&transaction[field][index as usize])
+	};
+
+	let caller = address(&transaction["sender"])
+		.map_err(|_| "the transaction has no sender, which the runner needs")?;
+	let data = parse_hex(
+		indexed("data", "data")?
+			.as_str()
+			.ok_or("data is not a string")?,
+	)?;
+	let gas_limit = quantity(indexed("gasLimit", "gas")?)?;
+	let value = quantity(indexed("value", "value")?)?;
+	let nonce = Some(quantity(&transaction["nonce"])?);
+
+	let gas_price = match optional(&transaction["gasPrice"], quantity)? {
+		Some(gas_price) => gas_price,
+		None => {
+			let max_fee = quantity(&transaction["maxFeePerGas"])?;
+			let max_priority_fee = quantity(&transaction["maxPriorityFeePerGas"])?;
+			let base_fee = base.environment.block_base_fee_per_gas;
+			max_fee.min(base_fee.saturating_add(max_priority_fee))
+		}
+	};
+
+	let access_list = match indexes["data"]
+		.as_u64()
+		.and_then(|index| transaction["accessLists"][index as usize].as_array())
+	{
+		Some(list) => list
+			.iter()
+			.map(|access| {
+				let keys = access["storageKeys"]
+					.as_array()
+					.ok_or("storage keys are not a list")?
+					.iter()
+					.map(hash)
+					.collect::<Result<Vec<_>, _>>()?;
+				Ok((address(&access["address"])?, keys))
+			})
+			.collect::<Result<Vec<_>, Error>>()?,
+		None => Vec::new(),
+	};
+
+	let to = transaction["to"].as_str().unwrap_or("");
+	Ok(if to.is_empty() {
+		TransactArgs::Create {
+			caller,
+			value,
+			init_code: data,
+			salt: None,
+			gas_limit,
+			gas_price,
+			access_list,
+			nonce,
+			chain_id: None,
+			system: false,
+			mint: None,
+			fee_payer: None,
+		}
+	} else {
+		TransactArgs::Call {
+			caller,
+			address: address(&transaction["to"])?,
+			value,
+			data,
+			gas_limit,
+			gas_price,
+			access_list,
+			nonce,
+			chain_id: None,
+			system: false,
+			mint: None,
+			fee_payer: None,
+		}
+	})
+}
+
+/// Hash of the RLP list of the logs, as in the `logs` field of post-states.
+fn logs_hash(logs: &[Log]) -> H256 {
+	let mut stream = rlp::RlpStream::new_list(logs.len());
+	for log in logs {
+		stream.begin_list(3);
+		stream.append(&log.address);
+		stream.append_list(&log.topics);
+		stream.append(&log.data);
+	}
+
+	H256::from_slice(&Keccak256::digest(stream.out()))
+}
+
+fn optional<T>(
+	value: &Value,
+	parse: impl Fn(&Value) -> Result<T, Error>,
+) -> Result<Option<T>, Error> {
+	if value.is_null() {
+		Ok(None)
+	} else {
+		parse(value).map(Some)
+	}
+}
+
+fn quantity(value: &Value) -> Result<U256, Error> {
+	let value = value.as_str().ok_or("quantity is not a string")?;
+	Ok(match value.strip_prefix("0x") {
+		Some(hex) => U256::from_str_radix(hex, 16)?,
+		None => U256::from_dec_str(value)?,
+	})
+}
+
+fn address(value: &Value) -> Result<H160, Error> {
+	let bytes = parse_hex(value.as_str().ok_or("address is not a string")?)?;
+	if bytes.len() != 20 {
+		return Err(format!("invalid address length {}", bytes.len()).into());
+	}
+	Ok(H160::from_slice(&bytes))
+}
+
+fn hash(value: &Value) -> Result<H256, Error> {
+	let mut bytes = [0; 32];
+	quantity(value)?.to_big_endian(&mut bytes);
+	Ok(H256(bytes))
+}
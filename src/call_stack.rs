@@ -6,7 +6,11 @@ use evm_interpreter::{
 	Interpreter, RunInterpreter, StepInterpreter,
 };
 
-use crate::invoker::{Invoker, InvokerControl};
+use crate::{
+	backend::TransactionalBackend,
+	invoker::{Invoker, InvokerControl},
+	MergeStrategy,
+};
 
 struct Substack<M, TrD> {
 	invoke: TrD,
@@ -26,7 +30,7 @@ enum LastSubstackStatus<Tr> {
 
 // Note: this should not be exposed to public because it does not implement
 // Drop.
-struct CallStack<'backend, 'invoker, H, Tr, I: Invoker<H, Tr>> {
+struct CallStack<'backend, 'invoker, H, Tr, I: Invoker<H, Tr> + ?Sized> {
 	stack: Vec<Substack<I::Interpreter, I::SubstackInvoke>>,
 	last: Option<LastSubstack<I::Interpreter, Tr>>,
 	initial_depth: usize,
@@ -36,7 +40,7 @@ struct CallStack<'backend, 'invoker, H, Tr, I: Invoker<H, Tr>> {
 
 impl<'backend, 'invoker, H, Tr, I> CallStack<'backend, 'invoker, H, Tr, I>
 where
-	I: Invoker<H, Tr>,
+	I: Invoker<H, Tr> + ?Sized,
 {
 	pub fn new(
 		machine: I::Interpreter,
@@ -188,7 +192,7 @@ where
 
 impl<'backend, 'invoker, H, Tr, I> CallStack<'backend, 'invoker, H, Tr, I>
 where
-	I: Invoker<H, Tr>,
+	I: Invoker<H, Tr> + ?Sized,
 	I::Interpreter: RunInterpreter<H, Tr>,
 {
 	#[allow(clippy::type_complexity)]
@@ -217,7 +221,7 @@ where
 
 impl<'backend, 'invoker, H, Tr, I> CallStack<'backend, 'invoker, H, Tr, I>
 where
-	I: Invoker<H, Tr>,
+	I: Invoker<H, Tr> + ?Sized,
 	I::Interpreter: StepInterpreter<H, Tr>,
 {
 	#[allow(clippy::type_complexity)]
@@ -234,7 +238,7 @@ where
 	}
 }
 
-fn execute<H, Tr, I>(
+pub(crate) fn execute<H, Tr, I>(
 	mut machine: I::Interpreter,
 	initial_depth: usize,
 	heap_depth: Option<usize>,
@@ -242,7 +246,7 @@ fn execute<H, Tr, I>(
 	invoker: &I,
 ) -> Result<(ExitResult, I::Interpreter), ExitFatal>
 where
-	I: Invoker<H, Tr, Interrupt = Infallible>,
+	I: Invoker<H, Tr, Interrupt = Infallible> + ?Sized,
 	I::Interpreter: RunInterpreter<H, Tr>,
 {
 	let mut result = machine.run(backend);
@@ -304,7 +308,7 @@ where
 	}
 }
 
-enum HeapTransactState<'backend, 'invoker, H, Tr, I: Invoker<H, Tr>> {
+enum HeapTransactState<'backend, 'invoker, H, Tr, I: Invoker<H, Tr> + ?Sized> {
 	Created {
 		args: I::TransactArgs,
 		invoker: &'invoker I,
@@ -317,15 +321,15 @@ enum HeapTransactState<'backend, 'invoker, H, Tr, I: Invoker<H, Tr>> {
 }
 
 /// Heap-based call stack for a transaction. This is suitable for single
-/// stepping or debugging. The hybrid version [transact] uses a heap-based call
-/// stack internally after certain depth.
-pub struct HeapTransact<'backend, 'invoker, H, Tr, I: Invoker<H, Tr>>(
+/// stepping or debugging. The hybrid version [transact_hybrid] uses a
+/// heap-based call stack internally after certain depth.
+pub struct HeapTransact<'backend, 'invoker, H, Tr, I: Invoker<H, Tr> + ?Sized>(
 	Option<HeapTransactState<'backend, 'invoker, H, Tr, I>>,
 );
 
 impl<'backend, 'invoker, H, Tr, I> HeapTransact<'backend, 'invoker, H, Tr, I>
 where
-	I: Invoker<H, Tr>,
+	I: Invoker<H, Tr> + ?Sized,
 {
 	/// Create a new heap-based call stack.
 	pub fn new(
@@ -426,11 +430,43 @@ where
 			_ => None,
 		}
 	}
+
+	/// Mutable reference to the machine of the last item on the call stack.
+	/// This will be `None` if the heap stack is just created.
+	pub fn last_interpreter_mut(&mut self) -> Option<&mut I::Interpreter> {
+		match &mut self.0 {
+			Some(HeapTransactState::Running { call_stack, .. }) => match &mut call_stack.last {
+				Some(last) => Some(&mut last.machine),
+				None => None,
+			},
+			_ => None,
+		}
+	}
+
+	/// The backend, for inspecting the substate between steps.
+	pub fn backend(&self) -> Option<&H> {
+		match &self.0 {
+			Some(HeapTransactState::Created { backend, .. }) => Some(backend),
+			Some(HeapTransactState::Running { call_stack, .. }) => Some(call_stack.backend),
+			None => None,
+		}
+	}
+
+	/// The depth of the last item on the call stack. This will be `None` if
+	/// the heap stack is just created.
+	pub fn depth(&self) -> Option<usize> {
+		match &self.0 {
+			Some(HeapTransactState::Running { call_stack, .. }) => {
+				Some(call_stack.initial_depth + call_stack.stack.len())
+			}
+			_ => None,
+		}
+	}
 }
 
 impl<'backend, 'invoker, H, Tr, I> HeapTransact<'backend, 'invoker, H, Tr, I>
 where
-	I: Invoker<H, Tr>,
+	I: Invoker<H, Tr> + ?Sized,
 	I::Interpreter: RunInterpreter<H, Tr>,
 {
 	/// Step the call stack, but run the interpreter inside.
@@ -455,7 +491,7 @@ where
 
 impl<'backend, 'invoker, H, Tr, I> HeapTransact<'backend, 'invoker, H, Tr, I>
 where
-	I: Invoker<H, Tr>,
+	I: Invoker<H, Tr> + ?Sized,
 	I::Interpreter: StepInterpreter<H, Tr>,
 {
 	/// Step the call stack, and step the interpreter inside.
@@ -469,7 +505,7 @@ where
 
 impl<'backend, 'invoker, H, Tr, I> Drop for HeapTransact<'backend, 'invoker, H, Tr, I>
 where
-	I: Invoker<H, Tr>,
+	I: Invoker<H, Tr> + ?Sized,
 {
 	fn drop(&mut self) {
 		if let Some(HeapTransactState::Running {
@@ -508,7 +544,8 @@ where
 	}
 }
 
-/// Initiate a transaction, using a hybrid call stack.
+/// Initiate a transaction, using a hybrid call stack. Prefer [transact] unless
+/// the extra speed of a stack-based call stack is needed.
 ///
 /// Up until `heap_depth`, a stack-based call stack is used first. A stack-based
 /// call stack is faster, but for really deep calls, it can reach the default
@@ -520,14 +557,14 @@ where
 ///
 /// Because a stack-based call stack cannot handle interrupts, the [Invoker]
 /// type must have its `Interrupt` type set to [Infallible].
-pub fn transact<H, Tr, I>(
+pub fn transact_hybrid<H, Tr, I>(
 	args: I::TransactArgs,
 	heap_depth: Option<usize>,
 	backend: &mut H,
 	invoker: &I,
 ) -> Result<I::TransactValue, ExitError>
 where
-	I: Invoker<H, Tr, Interrupt = Infallible>,
+	I: Invoker<H, Tr, Interrupt = Infallible> + ?Sized,
 	I::Interpreter: RunInterpreter<H, Tr>,
 {
 	let (transact_invoke, control) = invoker.new_transact(args, backend)?;
@@ -543,3 +580,168 @@ where
 		}
 	}
 }
+
+/// Simulate a transaction, using the same hybrid call stack as
+/// [transact_hybrid], but
+/// discard all state changes it makes regardless of the outcome.
+///
+/// The whole transaction, including fee withdrawal and nonce increment, runs
+/// in a substate that is popped with [MergeStrategy::Discard]. The returned
+/// value still carries the output, used gas and logs of the execution. This
+/// is what RPC methods like `eth_call` and `eth_estimateGas` need.
+pub fn simulate<H, Tr, I>(
+	args: I::TransactArgs,
+	heap_depth: Option<usize>,
+	backend: &mut H,
+	invoker: &I,
+) -> Result<I::TransactValue, ExitError>
+where
+	H: TransactionalBackend,
+	I: Invoker<H, Tr, Interrupt = Infallible> + ?Sized,
+	I::Interpreter: RunInterpreter<H, Tr>,
+{
+	backend.push_substate();
+	let ret = transact_hybrid::<H, Tr, I>(args, heap_depth, backend, invoker);
+	backend.pop_substate(MergeStrategy::Discard);
+	ret
+}
+
+/// Outcome of a heap-based transaction driven by [transact].
+pub enum TransactOutcome<'backend, 'invoker, H, Tr, I: Invoker<H, Tr> + ?Sized> {
+	/// The transaction exited.
+	Exited(Result<I::TransactValue, ExitError>),
+	/// The invoker interrupted the transaction. It can be continued through
+	/// the resume handle.
+	Interrupted(I::Interrupt, Resume<'backend, 'invoker, H, Tr, I>),
+}
+
+impl<'backend, 'invoker, H, Tr, I> TransactOutcome<'backend, 'invoker, H, Tr, I>
+where
+	I: Invoker<H, Tr, Interrupt = Infallible> + ?Sized,
+{
+	/// The transaction result, for invokers that never interrupt.
+	pub fn into_result(self) -> Result<I::TransactValue, ExitError> {
+		match self {
+			Self::Exited(result) => result,
+			Self::Interrupted(infallible, _) => match infallible {},
+		}
+	}
+}
+
+/// Resume handle of an interrupted transaction.
+///
+/// Dropping the handle aborts the transaction, in the same way as dropping a
+/// [HeapTransact].
+pub struct Resume<'backend, 'invoker, H, Tr, I: Invoker<H, Tr> + ?Sized>(
+	HeapTransact<'backend, 'invoker, H, Tr, I>,
+);
+
+impl<'backend, 'invoker, H, Tr, I> Resume<'backend, 'invoker, H, Tr, I>
+where
+	I: Invoker<H, Tr> + ?Sized,
+{
+	/// The machine that was interrupted. Its state can be modified to feed
+	/// the interrupt result back before resuming.
+	pub fn interpreter_mut(&mut self) -> Option<&mut I::Interpreter> {
+		self.0.last_interpreter_mut()
+	}
+
+	/// The depth of the machine that was interrupted.
+	pub fn depth(&self) -> Option<usize> {
+		self.0.depth()
+	}
+}
+
+impl<'backend, 'invoker, H, Tr, I> Resume<'backend, 'invoker, H, Tr, I>
+where
+	I: Invoker<H, Tr> + ?Sized,
+	I::Interpreter: RunInterpreter<H, Tr>,
+{
+	/// Resume the transaction, until it exits or is interrupted again.
+	pub fn resume(mut self) -> TransactOutcome<'backend, 'invoker, H, Tr, I> {
+		match self.0.run() {
+			Capture::Exit(result) => TransactOutcome::Exited(result),
+			Capture::Trap(interrupt) => TransactOutcome::Interrupted(interrupt, self),
+		}
+	}
+}
+
+/// Initiate a transaction, using a heap-based call stack.
+///
+/// No host stack recursion happens, so any call stack limit can be configured
+/// in the invoker without the risk of a native stack overflow. Interrupts of
+/// the invoker are yielded to the caller together with a [Resume] handle. For
+/// invokers that never interrupt, use [TransactOutcome::into_result].
+pub fn transact<'backend, 'invoker, H, Tr, I>(
+	args: I::TransactArgs,
+	invoker: &'invoker I,
+	backend: &'backend mut H,
+) -> TransactOutcome<'backend, 'invoker, H, Tr, I>
+where
+	I: Invoker<H, Tr> + ?Sized,
+	I::Interpreter: RunInterpreter<H, Tr>,
+{
+	match HeapTransact::new(args, invoker, backend) {
+		Ok(heap) => Resume(heap).resume(),
+		Err(err) => TransactOutcome::Exited(Err(err)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{collections::BTreeSet, vec, vec::Vec};
+
+	use evm_interpreter::runtime::RuntimeBaseBackend;
+	use primitive_types::{H256, U256};
+
+	use crate::{
+		backend::OverlayedBackend,
+		standard::{
+			testing::{backend, call_args, etable, CALLER, CONTRACT},
+			Config, EtableResolver, Invoker, TransactArgs,
+		},
+	};
+
+	#[test]
+	fn simulations_return_logs_and_gas_and_discard_the_state() {
+		let mut base = backend();
+		base.set_balance(CALLER, U256::from(1_000_000));
+		// Store 1 at slot 0, log no data, and return 2.
+		base.set_code(
+			CONTRACT,
+			vec![
+				0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0xa0, 0x60, 0x02, 0x60, 0x00,
+				0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+			],
+		);
+
+		let config = Config::cancun();
+		let etable = etable();
+		let resolver = EtableResolver::new(&config, &(), &etable);
+		let invoker = Invoker::new(&config, &resolver);
+		let mut args = call_args(CONTRACT, Vec::new(), 100_000, 1);
+		if let TransactArgs::Call { value, .. } = &mut args {
+			*value = U256::from(3);
+		}
+
+		let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+		let value = crate::simulate(args, None, &mut backend, &invoker).unwrap();
+
+		assert!(value.exit.is_ok());
+		assert_eq!(U256::from_big_endian(&value.retval), U256::from(2));
+		assert!(value.used_gas > U256::from(21_000));
+		assert_eq!(value.logs.len(), 1);
+		assert_eq!(value.logs[0].address, CONTRACT);
+
+		assert_eq!(backend.balance(CALLER), U256::from(1_000_000));
+		assert_eq!(backend.nonce(CALLER), U256::zero());
+		assert_eq!(backend.balance(CONTRACT), U256::zero());
+		assert_eq!(backend.storage(CONTRACT, H256::zero()), H256::zero());
+
+		let (_, changes) = backend.deconstruct();
+		assert!(changes.logs.is_empty());
+		assert!(changes.balances.is_empty());
+		assert!(changes.nonces.is_empty());
+		assert!(changes.storages.is_empty());
+	}
+}
@@ -0,0 +1,549 @@
+//! # Compatibility with the 0.x API
+//!
+//! [StackExecutor] and [MemoryBackend] keep the entry points of the 0.x
+//! `StackExecutor` and `MemoryBackend`, on top of [crate::transact_hybrid]
+//! and the standard invoker, so that integrations written against them can
+//! migrate one call site at a time:
+//!
+//! ```ignore
+//! let backend = MemoryBackend::new(&vicinity, state);
+//! let mut executor = StackExecutor::new_with_precompiles(&backend, &config, &precompiles);
+//! let (reason, retval) = executor.transact_call(caller, address, value, data, gas_limit, access_list);
+//! let (values, logs) = executor.into_changes();
+//! backend.apply(values, logs, true);
+//! ```
+//!
+//! The behavior differs from 0.x in a few ways:
+//!
+//! * The exit reason is an [ExitResult], which is also used for the errors
+//!   of invalid transactions, for example a caller without the balance for
+//!   the fees.
+//! * The origin of a transaction is always its caller.
+//! * The return value of a successful create is the code of the created
+//!   contract, where 0.x returned no data.
+//! * The nonce and the chain ID of transactions are not checked.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use evm_interpreter::{
+	error::ExitResult,
+	runtime::{BlockHashProvider, Log, RuntimeBaseBackend, RuntimeEnvironment},
+};
+use primitive_types::{H160, H256, U256};
+
+use crate::{
+	backend::{Apply, OverlayedBackend, TestAccount},
+	standard::{
+		eval_gasometer, Config, Etable, EtableResolver, Invoker, PrecompileSet, State, TransactArgs,
+	},
+};
+
+/// Account of a [MemoryBackend].
+pub type MemoryAccount = TestAccount;
+
+/// Block environment of a [MemoryBackend], with the gas price of its
+/// transactions.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MemoryVicinity {
+	pub gas_price: U256,
+	pub chain_id: U256,
+	/// Hashes of the previous blocks, the parent block first.
+	pub block_hashes: Vec<H256>,
+	pub block_number: U256,
+	pub block_coinbase: H160,
+	pub block_timestamp: U256,
+	pub block_difficulty: U256,
+	pub block_randomness: Option<H256>,
+	pub block_gas_limit: U256,
+	pub block_base_fee_per_gas: U256,
+}
+
+/// Backend of a [StackExecutor], with a gas price for the transactions.
+pub trait Backend: RuntimeEnvironment + RuntimeBaseBackend {
+	fn gas_price(&self) -> U256;
+}
+
+impl<'a, B: Backend> Backend for &'a B {
+	fn gas_price(&self) -> U256 {
+		(*self).gas_price()
+	}
+}
+
+/// Backend that applies the changes of an execution.
+pub trait ApplyBackend {
+	/// Apply the account changes and logs. With `delete_empty`, accounts
+	/// left empty (see EIP-161) are deleted.
+	fn apply<A, L>(&mut self, values: A, logs: L, delete_empty: bool)
+	where
+		A: IntoIterator<Item = Apply>,
+		L: IntoIterator<Item = Log>;
+}
+
+/// An in-memory backend.
+#[derive(Clone, Debug)]
+pub struct MemoryBackend<'vicinity> {
+	vicinity: &'vicinity MemoryVicinity,
+	state: BTreeMap<H160, MemoryAccount>,
+	logs: Vec<Log>,
+}
+
+impl<'vicinity> MemoryBackend<'vicinity> {
+	pub fn new(vicinity: &'vicinity MemoryVicinity, state: BTreeMap<H160, MemoryAccount>) -> Self {
+		Self {
+			vicinity,
+			state,
+			logs: Vec::new(),
+		}
+	}
+
+	pub fn state(&self) -> &BTreeMap<H160, MemoryAccount> {
+		&self.state
+	}
+
+	pub fn state_mut(&mut self) -> &mut BTreeMap<H160, MemoryAccount> {
+		&mut self.state
+	}
+
+	/// Logs applied so far.
+	pub fn logs(&self) -> &[Log] {
+		&self.logs
+	}
+}
+
+impl<'vicinity> ApplyBackend for MemoryBackend<'vicinity> {
+	fn apply<A, L>(&mut self, values: A, logs: L, delete_empty: bool)
+	where
+		A: IntoIterator<Item = Apply>,
+		L: IntoIterator<Item = Log>,
+	{
+		for apply in values {
+			match apply {
+				Apply::Modify {
+					address,
+					balance,
+					nonce,
+					code,
+					storage,
+					reset_storage,
+				} => {
+					let account = self.state.entry(address).or_default();
+					account.balance = balance;
+					account.nonce = nonce;
+					if let Some(code) = code {
+						account.code = code;
+					}
+					if reset_storage {
+						account.storage = BTreeMap::new();
+					}
+					for (index, value) in storage {
+						if value == H256::default() {
+							account.storage.remove(&index);
+						} else {
+							account.storage.insert(index, value);
+						}
+					}
+
+					let empty = account.balance == U256::zero()
+						&& account.nonce == U256::zero()
+						&& account.code.is_empty();
+					if delete_empty && empty {
+						self.state.remove(&address);
+					}
+				}
+				Apply::Delete { address } => {
+					self.state.remove(&address);
+				}
+			}
+		}
+
+		self.logs.extend(logs);
+	}
+}
+
+impl<'vicinity> Backend for MemoryBackend<'vicinity> {
+	fn gas_price(&self) -> U256 {
+		self.vicinity.gas_price
+	}
+}
+
+impl<'vicinity> BlockHashProvider for MemoryBackend<'vicinity> {
+	fn block_hash(&self, number: U256) -> H256 {
+		let vicinity = self.vicinity;
+		if number >= vicinity.block_number
+			|| vicinity.block_number - number - U256::one()
+				>= U256::from(vicinity.block_hashes.len())
+		{
+			H256::default()
+		} else {
+			let index = (vicinity.block_number - number - U256::one()).as_usize();
+			vicinity.block_hashes[index]
+		}
+	}
+}
+
+impl<'vicinity> RuntimeEnvironment for MemoryBackend<'vicinity> {
+	fn block_number(&self) -> U256 {
+		self.vicinity.block_number
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.vicinity.block_coinbase
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.vicinity.block_timestamp
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.vicinity.block_difficulty
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.vicinity.block_randomness
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		None
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.vicinity.block_gas_limit
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.vicinity.block_base_fee_per_gas
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		U256::zero()
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.vicinity.chain_id
+	}
+}
+
+impl<'vicinity> RuntimeBaseBackend for MemoryBackend<'vicinity> {
+	fn balance(&self, address: H160) -> U256 {
+		self.state
+			.get(&address)
+			.map(|account| account.balance)
+			.unwrap_or_default()
+	}
+
+	#[cfg(feature = "signed-transaction")]
+	fn ecrecover(&self, hash: H256, y_parity: u8, r: H256, s: H256) -> Option<H160> {
+		crate::standard::signed::ecrecover(hash, y_parity, r, s)
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.state
+			.get(&address)
+			.map(|account| account.code.clone())
+			.unwrap_or_default()
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.state
+			.get(&address)
+			.and_then(|account| account.storage.get(&index).cloned())
+			.unwrap_or_default()
+	}
+
+	fn transient_storage(&self, _address: H160, _index: H256) -> H256 {
+		H256::default()
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.state.contains_key(&address)
+	}
+
+	fn nonce(&self, address: H160) -> U256 {
+		self.state
+			.get(&address)
+			.map(|account| account.nonce)
+			.unwrap_or_default()
+	}
+}
+
+/// Executor of transactions over a backend, with the state changes kept in
+/// an [OverlayedBackend] until they are taken with
+/// [StackExecutor::into_changes].
+pub struct StackExecutor<'config, 'precompile, B, P> {
+	config: &'config Config,
+	precompiles: &'precompile P,
+	state: OverlayedBackend<B>,
+	used_gas: U256,
+}
+
+impl<'config, 'precompile, B, P> StackExecutor<'config, 'precompile, B, P>
+where
+	B: Backend,
+	P: PrecompileSet<State<'config>, OverlayedBackend<B>>,
+{
+	pub fn new_with_precompiles(
+		backend: B,
+		config: &'config Config,
+		precompiles: &'precompile P,
+	) -> Self {
+		Self {
+			config,
+			precompiles,
+			state: OverlayedBackend::new(backend, Default::default()),
+			used_gas: U256::zero(),
+		}
+	}
+
+	pub fn config(&self) -> &'config Config {
+		self.config
+	}
+
+	pub fn precompiles(&self) -> &'precompile P {
+		self.precompiles
+	}
+
+	pub fn state(&self) -> &OverlayedBackend<B> {
+		&self.state
+	}
+
+	pub fn state_mut(&mut self) -> &mut OverlayedBackend<B> {
+		&mut self.state
+	}
+
+	pub fn into_state(self) -> OverlayedBackend<B> {
+		self.state
+	}
+
+	/// Account changes and logs of the executed transactions, to apply with
+	/// [ApplyBackend::apply].
+	pub fn into_changes(self) -> (Vec<Apply>, Vec<Log>) {
+		let (_, values, logs) = self.state.into_changes();
+		(values, logs)
+	}
+
+	/// Gas used by the last transaction, after refunds.
+	pub fn used_gas(&self) -> u64 {
+		self.used_gas.low_u64()
+	}
+
+	/// Execute a call transaction, at the gas price of the backend. Return the
+	/// exit reason and the return value.
+	pub fn transact_call(
+		&mut self,
+		caller: H160,
+		address: H160,
+		value: U256,
+		data: Vec<u8>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+	) -> (ExitResult, Vec<u8>) {
+		let gas_price = self.state.backend().gas_price();
+		self.transact(TransactArgs::Call {
+			caller,
+			address,
+			nonce: None,
+			chain_id: None,
+			value,
+			data,
+			gas_limit: U256::from(gas_limit),
+			gas_price,
+			access_list,
+			system: false,
+			mint: None,
+			fee_payer: None,
+		})
+	}
+
+	/// Execute a create transaction, at the gas price of the backend. Return
+	/// the exit reason and the code of the created contract, or the revert
+	/// data if it failed.
+	pub fn transact_create(
+		&mut self,
+		caller: H160,
+		value: U256,
+		init_code: Vec<u8>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+	) -> (ExitResult, Vec<u8>) {
+		self.transact_create_with_salt(caller, value, init_code, None, gas_limit, access_list)
+	}
+
+	/// Execute a create transaction with a `CREATE2` address, see
+	/// [StackExecutor::transact_create].
+	pub fn transact_create2(
+		&mut self,
+		caller: H160,
+		value: U256,
+		init_code: Vec<u8>,
+		salt: H256,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+	) -> (ExitResult, Vec<u8>) {
+		self.transact_create_with_salt(caller, value, init_code, Some(salt), gas_limit, access_list)
+	}
+
+	fn transact_create_with_salt(
+		&mut self,
+		caller: H160,
+		value: U256,
+		init_code: Vec<u8>,
+		salt: Option<H256>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+	) -> (ExitResult, Vec<u8>) {
+		let gas_price = self.state.backend().gas_price();
+		self.transact(TransactArgs::Create {
+			caller,
+			nonce: None,
+			chain_id: None,
+			value,
+			init_code,
+			salt,
+			gas_limit: U256::from(gas_limit),
+			gas_price,
+			access_list,
+			system: false,
+			mint: None,
+			fee_payer: None,
+		})
+	}
+
+	fn transact(&mut self, args: TransactArgs) -> (ExitResult, Vec<u8>) {
+		let etable: (
+			Etable<'config, OverlayedBackend<B>>,
+			Etable<'config, OverlayedBackend<B>>,
+		) = (Etable::single(eval_gasometer), Etable::runtime());
+		let resolver = EtableResolver::new(self.config, self.precompiles, &etable);
+		let invoker = Invoker::new(self.config, &resolver);
+
+		match crate::transact_hybrid(args, Some(4), &mut self.state, &invoker) {
+			Ok(value) => {
+				self.used_gas = value.used_gas;
+				(value.exit, value.retval)
+			}
+			Err(err) => {
+				self.used_gas = U256::zero();
+				(Err(err), Vec::new())
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+	use evm_interpreter::error::ExitSucceed;
+	use primitive_types::{H160, H256, U256};
+
+	use super::{ApplyBackend, MemoryAccount, MemoryBackend, MemoryVicinity, StackExecutor};
+	use crate::standard::Config;
+
+	const CALLER: H160 = H160([0x10; 20]);
+	const CONTRACT: H160 = H160([0x20; 20]);
+	const COINBASE: H160 = H160([0x30; 20]);
+
+	fn vicinity() -> MemoryVicinity {
+		MemoryVicinity {
+			gas_price: U256::from(2),
+			chain_id: U256::one(),
+			block_coinbase: COINBASE,
+			block_gas_limit: U256::from(1_000_000),
+			block_base_fee_per_gas: U256::one(),
+			..Default::default()
+		}
+	}
+
+	fn state() -> BTreeMap<H160, MemoryAccount> {
+		let mut state = BTreeMap::new();
+		state.insert(
+			CALLER,
+			MemoryAccount {
+				balance: U256::from(1_000_000),
+				..Default::default()
+			},
+		);
+		// Store the call value at slot 1, and log it.
+		state.insert(
+			CONTRACT,
+			MemoryAccount {
+				code: vec![
+					0x34, 0x60, 0x01, 0x55, 0x34, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xa0,
+				],
+				..Default::default()
+			},
+		);
+		state
+	}
+
+	#[test]
+	fn calls_round_trip_through_the_backend() {
+		let vicinity = vicinity();
+		let config = Config::cancun();
+		let mut backend = MemoryBackend::new(&vicinity, state());
+
+		let mut executor = StackExecutor::new_with_precompiles(&backend, &config, &());
+		let (reason, retval) = executor.transact_call(
+			CALLER,
+			CONTRACT,
+			U256::from(5),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+		assert_eq!(reason, Ok(ExitSucceed::Stopped));
+		assert!(retval.is_empty());
+		let used_gas = executor.used_gas();
+		assert!(used_gas > 21_000);
+
+		let (values, logs) = executor.into_changes();
+		backend.apply(values, logs, true);
+
+		let state = backend.state();
+		assert_eq!(
+			state[&CALLER].balance,
+			U256::from(1_000_000 - 5 - 2 * used_gas)
+		);
+		assert_eq!(state[&CALLER].nonce, U256::one());
+		assert_eq!(state[&CONTRACT].balance, U256::from(5));
+		assert_eq!(
+			state[&CONTRACT].storage[&H256::from_low_u64_be(1)],
+			H256::from_low_u64_be(5)
+		);
+		// The base fee is burnt, the coinbase gets the priority fee.
+		assert_eq!(state[&COINBASE].balance, U256::from(used_gas));
+		assert_eq!(backend.logs().len(), 1);
+		assert_eq!(backend.logs()[0].address, CONTRACT);
+		assert_eq!(backend.logs()[0].data, H256::from_low_u64_be(5).as_bytes());
+	}
+
+	#[test]
+	fn creates_round_trip_through_the_backend() {
+		let vicinity = vicinity();
+		let config = Config::cancun();
+		let mut backend = MemoryBackend::new(&vicinity, state());
+
+		// Return the code 0x00.
+		let init_code = vec![0x60, 0x00, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+		let mut executor = StackExecutor::new_with_precompiles(&backend, &config, &());
+		let (reason, retval) =
+			executor.transact_create(CALLER, U256::from(7), init_code, 100_000, Vec::new());
+		assert_eq!(reason, Ok(ExitSucceed::Returned));
+		assert_eq!(retval, vec![0x00]);
+
+		let (values, logs) = executor.into_changes();
+		backend.apply(values, logs, true);
+
+		let created = backend
+			.state()
+			.iter()
+			.filter(|(_, account)| account.code == vec![0x00])
+			.collect::<Vec<_>>();
+		assert_eq!(created.len(), 1);
+		let (address, account) = created[0];
+		assert!(![CALLER, CONTRACT, COINBASE].contains(address));
+		assert_eq!(account.balance, U256::from(7));
+		assert_eq!(account.nonce, U256::one());
+		assert_eq!(backend.state()[&CALLER].nonce, U256::one());
+	}
+}
@@ -0,0 +1,197 @@
+//! # Debugger
+//!
+//! A [Debugger] drives a [HeapTransact] one opcode at a time, and pauses at
+//! breakpoints or after stepping. While paused, the machine of the current
+//! call frame (stack, memory, call data and return data) and the backend
+//! (including its substate) can be inspected.
+
+use alloc::vec::Vec;
+
+use evm_interpreter::{
+	error::{Capture, ExitError},
+	etable::EtableSet,
+	opcode::Opcode,
+	runtime::RuntimeState,
+	EtableInterpreter, Interpreter,
+};
+use primitive_types::{H160, H256};
+
+use crate::{HeapTransact, Invoker};
+
+/// A condition to pause the execution at. All conditions are checked before
+/// the next opcode is executed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Breakpoint {
+	/// A call frame of the address is entered. This is the context address,
+	/// which for `DELEGATECALL` and `CALLCODE` is the address of the caller.
+	Address(H160),
+	/// The next opcode is at the position in a call frame of the address.
+	Position {
+		/// Context address of the call frame.
+		address: H160,
+		/// Position in the code.
+		position: usize,
+	},
+	/// The next opcode is the opcode.
+	Opcode(Opcode),
+	/// The next opcode writes to the storage slot of the address.
+	StorageWrite {
+		/// Context address of the call frame.
+		address: H160,
+		/// Storage slot.
+		index: H256,
+	},
+}
+
+/// Result of running the debugger.
+pub enum DebugEvent<V, Int> {
+	/// Paused before the next opcode, either at the breakpoint with the given
+	/// index, or because the step finished.
+	Paused(Option<usize>),
+	/// The transaction exited.
+	Exited(Result<V, ExitError>),
+	/// The invoker interrupted. Stepping again resumes the execution.
+	Interrupted(Int),
+}
+
+/// Debugger over a heap-based call stack.
+pub struct Debugger<'backend, 'invoker, 'etable, H, Tr, ES, I>
+where
+	ES: EtableSet + 'etable,
+	I: Invoker<H, Tr, Interpreter = EtableInterpreter<'etable, ES>> + ?Sized,
+{
+	transact: HeapTransact<'backend, 'invoker, H, Tr, I>,
+	breakpoints: Vec<Breakpoint>,
+}
+
+impl<'backend, 'invoker, 'etable, H, Tr, ES, I> Debugger<'backend, 'invoker, 'etable, H, Tr, ES, I>
+where
+	ES: EtableSet<Handle = H, Trap = Tr> + 'etable,
+	ES::State: AsRef<RuntimeState>,
+	I: Invoker<H, Tr, Interpreter = EtableInterpreter<'etable, ES>> + ?Sized,
+{
+	/// Create a new debugger, with no breakpoints. No opcode is executed
+	/// until the first step.
+	pub fn new(transact: HeapTransact<'backend, 'invoker, H, Tr, I>) -> Self {
+		Self {
+			transact,
+			breakpoints: Vec::new(),
+		}
+	}
+
+	/// Add a breakpoint, and return its index.
+	pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+		self.breakpoints.push(breakpoint);
+		self.breakpoints.len() - 1
+	}
+
+	/// Remove the breakpoint at the index. Indices of the breakpoints after it
+	/// are shifted down.
+	pub fn remove_breakpoint(&mut self, index: usize) -> Breakpoint {
+		self.breakpoints.remove(index)
+	}
+
+	/// All breakpoints.
+	pub fn breakpoints(&self) -> &[Breakpoint] {
+		&self.breakpoints
+	}
+
+	/// Machine of the current call frame. `None` before the first step.
+	pub fn interpreter(&self) -> Option<&EtableInterpreter<'etable, ES>> {
+		self.transact.last_interpreter()
+	}
+
+	/// Mutable machine of the current call frame. `None` before the first
+	/// step.
+	pub fn interpreter_mut(&mut self) -> Option<&mut EtableInterpreter<'etable, ES>> {
+		self.transact.last_interpreter_mut()
+	}
+
+	/// Depth of the current call frame. `None` before the first step.
+	pub fn depth(&self) -> Option<usize> {
+		self.transact.depth()
+	}
+
+	/// The backend.
+	pub fn backend(&self) -> Option<&H> {
+		self.transact.backend()
+	}
+
+	/// Execute the next opcode, entering a call frame if the opcode calls.
+	pub fn step_into(&mut self) -> DebugEvent<I::TransactValue, I::Interrupt> {
+		self.step_until(|_| true)
+	}
+
+	/// Execute the next opcode, and if it calls, run until the call returns.
+	pub fn step_over(&mut self) -> DebugEvent<I::TransactValue, I::Interrupt> {
+		let current = self.transact.depth().unwrap_or(0);
+		self.step_until(|depth| depth <= current)
+	}
+
+	/// Run until the current call frame returns.
+	pub fn step_out(&mut self) -> DebugEvent<I::TransactValue, I::Interrupt> {
+		let current = self.transact.depth().unwrap_or(0);
+		self.step_until(|depth| depth < current)
+	}
+
+	/// Run until a breakpoint is hit.
+	pub fn run(&mut self) -> DebugEvent<I::TransactValue, I::Interrupt> {
+		self.step_until(|_| false)
+	}
+
+	/// Stop debugging, and return the call stack.
+	pub fn into_inner(self) -> HeapTransact<'backend, 'invoker, H, Tr, I> {
+		self.transact
+	}
+
+	fn step_until<F: Fn(usize) -> bool>(
+		&mut self,
+		done: F,
+	) -> DebugEvent<I::TransactValue, I::Interrupt> {
+		loop {
+			let depth = self.transact.depth();
+			match self.transact.step() {
+				Ok(()) => (),
+				Err(Capture::Exit(result)) => return DebugEvent::Exited(result),
+				Err(Capture::Trap(interrupt)) => return DebugEvent::Interrupted(interrupt),
+			}
+			let entered = self.transact.depth() > depth;
+
+			if let Some(index) = self.hit(entered) {
+				return DebugEvent::Paused(Some(index));
+			}
+
+			if self.transact.depth().map_or(true, &done) {
+				return DebugEvent::Paused(None);
+			}
+		}
+	}
+
+	fn hit(&self, entered: bool) -> Option<usize> {
+		let interpreter = self.transact.last_interpreter()?;
+		let machine = interpreter.machine();
+		let address = AsRef::<RuntimeState>::as_ref(&machine.state)
+			.context
+			.address;
+		let opcode = interpreter.peek_opcode();
+
+		self.breakpoints
+			.iter()
+			.position(|breakpoint| match breakpoint {
+				Breakpoint::Address(target) => entered && *target == address,
+				Breakpoint::Position {
+					address: target,
+					position,
+				} => *target == address && *position == interpreter.position(),
+				Breakpoint::Opcode(target) => opcode == Some(*target),
+				Breakpoint::StorageWrite {
+					address: target,
+					index,
+				} => {
+					*target == address
+						&& opcode == Some(Opcode::SSTORE)
+						&& machine.stack.peek(0).ok() == Some(*index)
+				}
+			})
+	}
+}
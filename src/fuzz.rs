@@ -0,0 +1,389 @@
+//! # Fuzzing entry points
+//!
+//! [arbitrary::Arbitrary] implementations for bytecode, transaction arguments
+//! and backend state, together with [fuzz_transact], which runs a transaction
+//! and asserts the invariants that must hold for any input.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use evm_interpreter::{
+	error::Capture,
+	runtime::{BlockHashProvider, GasState, RuntimeBaseBackend, RuntimeEnvironment},
+	Interpreter,
+};
+use primitive_types::{H160, H256, U256};
+
+use crate::{
+	backend::OverlayedBackend,
+	standard::{self, validate_transaction, Config, Etable, EtableResolver, Invoker, TransactArgs},
+	HeapTransact,
+};
+
+/// Maximum gas limit of a fuzzed transaction.
+pub const MAX_GAS_LIMIT: u64 = 30_000_000;
+
+/// Maximum length of fuzzed bytecode, so that a single account does not use up
+/// all of the fuzzer input.
+pub const MAX_BYTECODE_LEN: usize = 1024;
+
+/// Number of distinct addresses a fuzzed input can refer to. Keeping this
+/// small makes transactions and calls hit accounts in the fuzzed state.
+pub const ADDRESS_SPACE: u64 = 16;
+
+fn arbitrary_address(u: &mut Unstructured<'_>) -> Result<H160> {
+	Ok(H160::from_low_u64_be(
+		0x1000 + u.int_in_range(0..=ADDRESS_SPACE - 1)?,
+	))
+}
+
+fn arbitrary_gas_limit(u: &mut Unstructured<'_>) -> Result<U256> {
+	Ok(U256::from(u.int_in_range(21_000..=MAX_GAS_LIMIT)?))
+}
+
+fn arbitrary_nonce(u: &mut Unstructured<'_>) -> Result<U256> {
+	Ok(U256::from(u.int_in_range(0..=16u64)?))
+}
+
+fn arbitrary_small_u256(u: &mut Unstructured<'_>) -> Result<U256> {
+	Ok(U256::from(u64::arbitrary(u)?))
+}
+
+fn arbitrary_access_list(u: &mut Unstructured<'_>) -> Result<Vec<(H160, Vec<H256>)>> {
+	let len = u.int_in_range(0..=ADDRESS_SPACE)?;
+	(0..len)
+		.map(|_| Ok((arbitrary_address(u)?, Vec::<H256>::arbitrary(u)?)))
+		.collect()
+}
+
+/// Bytecode where push opcodes are always followed by their full immediate
+/// data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Bytecode(pub Vec<u8>);
+
+impl<'a> Arbitrary<'a> for Bytecode {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		let mut code = Vec::new();
+		let len = u.arbitrary_len::<u8>()?.min(MAX_BYTECODE_LEN);
+		for _ in 0..len {
+			let opcode = u8::arbitrary(u)?;
+			code.push(opcode);
+
+			// PUSH1 to PUSH32.
+			if (0x60..=0x7f).contains(&opcode) {
+				for _ in 0..(opcode - 0x5f) {
+					code.push(u8::arbitrary(u)?);
+				}
+			}
+		}
+		Ok(Self(code))
+	}
+}
+
+impl<'a> Arbitrary<'a> for TransactArgs {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		let caller = arbitrary_address(u)?;
+		let nonce = if bool::arbitrary(u)? {
+			Some(arbitrary_nonce(u)?)
+		} else {
+			None
+		};
+		let value = arbitrary_small_u256(u)?;
+		let gas_limit = arbitrary_gas_limit(u)?;
+		let gas_price = U256::from(u.int_in_range(0..=1_000u64)?);
+		let access_list = arbitrary_access_list(u)?;
+
+		if bool::arbitrary(u)? {
+			Ok(TransactArgs::Call {
+				caller,
+				address: arbitrary_address(u)?,
+				nonce,
+				chain_id: None,
+				value,
+				data: Vec::arbitrary(u)?,
+				gas_limit,
+				gas_price,
+				access_list,
+				system: false,
+				mint: None,
+				fee_payer: None,
+			})
+		} else {
+			Ok(TransactArgs::Create {
+				caller,
+				nonce,
+				chain_id: None,
+				value,
+				init_code: Bytecode::arbitrary(u)?.0,
+				salt: Option::arbitrary(u)?,
+				gas_limit,
+				gas_price,
+				access_list,
+				system: false,
+				mint: None,
+				fee_payer: None,
+			})
+		}
+	}
+}
+
+/// Account of [FuzzBackend].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FuzzAccount {
+	pub balance: U256,
+	pub nonce: U256,
+	pub code: Vec<u8>,
+	pub storage: BTreeMap<H256, H256>,
+}
+
+impl<'a> Arbitrary<'a> for FuzzAccount {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		Ok(Self {
+			balance: arbitrary_small_u256(u)?,
+			nonce: arbitrary_nonce(u)?,
+			code: Bytecode::arbitrary(u)?.0,
+			storage: (0..u.int_in_range(0..=ADDRESS_SPACE)?)
+				.map(|_| Ok((H256::arbitrary(u)?, H256::arbitrary(u)?)))
+				.collect::<Result<_>>()?,
+		})
+	}
+}
+
+/// A simple in-memory backend with arbitrary block environment and accounts.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FuzzBackend {
+	pub block_number: U256,
+	pub block_coinbase: H160,
+	pub block_timestamp: U256,
+	pub block_difficulty: U256,
+	pub block_randomness: Option<H256>,
+	pub block_parent_beacon_root: Option<H256>,
+	pub block_gas_limit: U256,
+	pub block_base_fee_per_gas: U256,
+	pub blob_base_fee: U256,
+	pub chain_id: U256,
+	pub accounts: BTreeMap<H160, FuzzAccount>,
+}
+
+impl<'a> Arbitrary<'a> for FuzzBackend {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		let mut accounts = BTreeMap::new();
+		for _ in 0..u.int_in_range(0..=ADDRESS_SPACE)? {
+			accounts.insert(arbitrary_address(u)?, FuzzAccount::arbitrary(u)?);
+		}
+
+		Ok(Self {
+			block_number: arbitrary_small_u256(u)?,
+			block_coinbase: arbitrary_address(u)?,
+			block_timestamp: arbitrary_small_u256(u)?,
+			block_difficulty: U256::arbitrary(u)?,
+			block_randomness: Option::arbitrary(u)?,
+			block_parent_beacon_root: Option::arbitrary(u)?,
+			block_gas_limit: U256::from(MAX_GAS_LIMIT),
+			block_base_fee_per_gas: U256::from(u.int_in_range(0..=1_000u64)?),
+			blob_base_fee: U256::from(u.int_in_range(1..=1_000u64)?),
+			chain_id: U256::one(),
+			accounts,
+		})
+	}
+}
+
+impl BlockHashProvider for FuzzBackend {
+	fn block_hash(&self, number: U256) -> H256 {
+		let mut hash = H256::default();
+		number.to_big_endian(&mut hash[..]);
+		hash
+	}
+}
+
+impl RuntimeEnvironment for FuzzBackend {
+	fn block_number(&self) -> U256 {
+		self.block_number
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.block_coinbase
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.block_timestamp
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.block_difficulty
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.block_randomness
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.block_parent_beacon_root
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.block_gas_limit
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.block_base_fee_per_gas
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		self.blob_base_fee
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.chain_id
+	}
+}
+
+impl RuntimeBaseBackend for FuzzBackend {
+	fn balance(&self, address: H160) -> U256 {
+		self.accounts
+			.get(&address)
+			.map(|account| account.balance)
+			.unwrap_or_default()
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.accounts
+			.get(&address)
+			.map(|account| account.code.clone())
+			.unwrap_or_default()
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.accounts
+			.get(&address)
+			.and_then(|account| account.storage.get(&index).cloned())
+			.unwrap_or_default()
+	}
+
+	fn transient_storage(&self, _address: H160, _index: H256) -> H256 {
+		H256::default()
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.accounts.contains_key(&address)
+	}
+
+	fn nonce(&self, address: H160) -> U256 {
+		self.accounts
+			.get(&address)
+			.map(|account| account.nonce)
+			.unwrap_or_default()
+	}
+}
+
+/// Run the transaction against the backend, and assert that:
+///
+/// * No panic happens during execution.
+/// * Gas of each call frame never increases between steps.
+/// * Used gas never exceeds the gas limit.
+/// * Substate depth returns to zero after the transaction finishes.
+///
+/// Transactions that fail validation are skipped.
+pub fn fuzz_transact(config: &Config, backend: &FuzzBackend, args: TransactArgs) {
+	let gas_etable = Etable::single(standard::eval_gasometer);
+	let exec_etable = Etable::runtime();
+	let etable = (gas_etable, exec_etable);
+	let resolver = EtableResolver::new(config, &(), &etable);
+	let invoker = Invoker::new(config, &resolver);
+
+	let mut overlayed = OverlayedBackend::new(backend, Default::default());
+	if validate_transaction(&args, &overlayed, config).is_err() {
+		return;
+	}
+	let gas_limit = args.gas_limit();
+
+	let result = {
+		let mut frame_gas: Vec<u64> = Vec::new();
+		let mut stepper = match HeapTransact::new(args, &invoker, &mut overlayed) {
+			Ok(stepper) => stepper,
+			Err(_) => return,
+		};
+
+		loop {
+			if let (Some(machine), Some(depth)) = (stepper.last_interpreter(), stepper.depth()) {
+				let gas = machine.machine().state.gas();
+				frame_gas.truncate(depth + 1);
+				if let Some(last_gas) = frame_gas.get(depth) {
+					assert!(gas <= *last_gas, "gas increased within a call frame");
+					frame_gas[depth] = gas;
+				} else {
+					frame_gas.push(gas);
+				}
+			}
+
+			if let Err(Capture::Exit(result)) = stepper.step() {
+				break result;
+			}
+		}
+	};
+
+	if let Ok(value) = result {
+		assert!(value.used_gas <= gas_limit, "used gas exceeds gas limit");
+	}
+	assert_eq!(overlayed.substate_depth(), 0, "uneven substate depth");
+}
+
+#[cfg(test)]
+mod tests {
+	use arbitrary::{Arbitrary, Unstructured};
+	use primitive_types::U256;
+
+	use super::{fuzz_transact, FuzzBackend};
+	use crate::{
+		backend::OverlayedBackend,
+		standard::{validate_transaction, Config, TransactArgs},
+	};
+
+	/// Deterministic fuzzer input, from a xorshift generator.
+	fn input(seed: u64, len: usize) -> Vec<u8> {
+		let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+		(0..len)
+			.map(|_| {
+				state ^= state << 13;
+				state ^= state >> 7;
+				state ^= state << 17;
+				state as u8
+			})
+			.collect()
+	}
+
+	#[test]
+	fn fuzz_transact_holds_its_invariants() {
+		let configs = [Config::istanbul(), Config::london(), Config::cancun()];
+		let mut executed = 0;
+
+		for seed in 0..200 {
+			// The state takes all the input it is given, so the transaction
+			// has its own.
+			let data = input(seed, 8192);
+			let mut backend = FuzzBackend::arbitrary(&mut Unstructured::new(&data)).unwrap();
+			let data = input(seed + 1000, 1024);
+			let mut args = TransactArgs::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+			// Fund the sender and drop the nonce check, so that most
+			// transactions pass validation and execute.
+			match &mut args {
+				TransactArgs::Call { nonce, .. } | TransactArgs::Create { nonce, .. } => {
+					*nonce = None
+				}
+			}
+			let caller = backend.accounts.entry(args.caller()).or_default();
+			caller.balance = U256::from(u64::MAX) * U256::from(u64::MAX);
+			caller.code.clear();
+
+			let config = &configs[seed as usize % configs.len()];
+			let overlayed = OverlayedBackend::new(&backend, Default::default());
+			if validate_transaction(&args, &overlayed, config).is_ok() {
+				executed += 1;
+			}
+			fuzz_transact(config, &backend, args);
+		}
+
+		assert!(executed > 100, "only {executed} transactions executed");
+	}
+}
@@ -3,6 +3,34 @@
 use evm_interpreter::{error::ExitError, runtime::GasState};
 use primitive_types::U256;
 
+/// Gas recording on a call frame.
+///
+/// This is the interface for external code that charges gas within a call
+/// frame, like precompiles, or embedders handling an interrupt through
+/// [crate::Resume::interpreter_mut]. Costs recorded here are accounted in the
+/// same way as opcode costs.
 pub trait GasMutState: GasState {
+	/// Record an explicit cost.
 	fn record_gas(&mut self, gas: U256) -> Result<(), ExitError>;
+
+	/// Record gas given as `u64`. Gasometers that track gas natively as `u64`
+	/// should override this to avoid the conversion.
+	fn record_gas64(&mut self, gas: u64) -> Result<(), ExitError> {
+		self.record_gas(U256::from(gas))
+	}
+
+	/// Record the cost of expanding memory to cover the given range. Nothing
+	/// is charged if the memory is already large enough, or if `len` is zero.
+	/// This only charges gas. It does not resize the machine memory.
+	fn record_memory_expansion(&mut self, offset: U256, len: U256) -> Result<(), ExitError>;
+
+	/// Record a dynamic cost together with the memory expansion it needs.
+	fn record_dynamic_gas64(&mut self, gas: u64, offset: U256, len: U256) -> Result<(), ExitError> {
+		self.record_gas64(gas)?;
+		self.record_memory_expansion(offset, len)
+	}
+
+	/// Add to the refund counter. The refund can be negative, and is only
+	/// applied at the end of the transaction, subject to the refund cap.
+	fn record_refund(&mut self, refund: i64) -> Result<(), ExitError>;
 }
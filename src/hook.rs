@@ -0,0 +1,103 @@
+use core::cell::RefCell;
+
+use evm_interpreter::{
+	etable::{Control, EtableSet},
+	machine::Machine,
+	opcode::Opcode,
+	Instruction,
+};
+
+/// A hook around the evaluation of each opcode by a [HookedEtable]. This is
+/// the etable counterpart of an [crate::InvokerMiddleware], for hooks that
+/// observe or meter opcodes, such as timings, weight meters and tracers.
+pub trait EtableHook<S, H, Tr> {
+	/// Data kept from [EtableHook::before_eval] to [EtableHook::after_eval],
+	/// for example the time or the gas before the opcode.
+	type Data;
+
+	/// Called before the opcode is evaluated.
+	fn before_eval(
+		&mut self,
+		machine: &Machine<S>,
+		handle: &H,
+		opcode: Opcode,
+		position: usize,
+	) -> Self::Data;
+
+	/// Called after the opcode is evaluated, with its control. Return the
+	/// control to use, for example an exit to fail the call frame.
+	fn after_eval(
+		&mut self,
+		data: Self::Data,
+		machine: &Machine<S>,
+		handle: &H,
+		opcode: Opcode,
+		position: usize,
+		control: Control<Tr>,
+	) -> Control<Tr>;
+}
+
+/// An etable set that calls an [EtableHook] around each opcode of the
+/// wrapped etable set. The hook is shared, for example with a middleware of
+/// the invoker, so it is borrowed from a [RefCell]:
+///
+/// ```ignore
+/// let timings = RefCell::new(Timings::new(StdClock::new()));
+/// let etable = HookedEtable::new((gas_etable, exec_etable), &timings);
+/// ```
+pub struct HookedEtable<'hook, ES, K> {
+	etable: ES,
+	hook: &'hook RefCell<K>,
+}
+
+impl<'hook, ES, K> HookedEtable<'hook, ES, K> {
+	pub fn new(etable: ES, hook: &'hook RefCell<K>) -> Self {
+		Self { etable, hook }
+	}
+}
+
+impl<'hook, ES, K> EtableSet for HookedEtable<'hook, ES, K>
+where
+	ES: EtableSet,
+	K: EtableHook<ES::State, ES::Handle, ES::Trap>,
+{
+	type State = ES::State;
+	type Handle = ES::Handle;
+	type Trap = ES::Trap;
+
+	fn eval(
+		&self,
+		machine: &mut Machine<ES::State>,
+		handle: &mut ES::Handle,
+		opcode: Opcode,
+		position: usize,
+	) -> Control<ES::Trap> {
+		let data = self
+			.hook
+			.borrow_mut()
+			.before_eval(machine, handle, opcode, position);
+		let control = self.etable.eval(machine, handle, opcode, position);
+
+		self.hook
+			.borrow_mut()
+			.after_eval(data, machine, handle, opcode, position, control)
+	}
+
+	fn eval_instruction(
+		&self,
+		machine: &mut Machine<ES::State>,
+		handle: &mut ES::Handle,
+		instruction: &Instruction,
+	) -> Control<ES::Trap> {
+		let (opcode, position) = (instruction.opcode, instruction.position);
+		let data = self
+			.hook
+			.borrow_mut()
+			.before_eval(machine, handle, opcode, position);
+		let control = self.etable.eval_instruction(machine, handle, instruction);
+
+		self.hook
+			.borrow_mut()
+			.after_eval(data, machine, handle, opcode, position, control)
+	}
+}
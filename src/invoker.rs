@@ -83,3 +83,177 @@ pub trait Invoker<H, Tr> {
 		handler: &mut H,
 	) -> Result<(), ExitError>;
 }
+
+/// A decorator of an invoker. Each method is called instead of the one of
+/// the wrapped invoker, which it gets as `invoker`, and delegates to it by
+/// default. Implement only the methods to intercept, and wrap the invoker
+/// in a [MiddlewareInvoker].
+pub trait InvokerMiddleware<H, Tr, I: Invoker<H, Tr>> {
+	/// Intercept [Invoker::new_transact].
+	#[allow(clippy::type_complexity)]
+	fn new_transact(
+		&self,
+		invoker: &I,
+		args: I::TransactArgs,
+		handler: &mut H,
+	) -> Result<
+		(
+			I::TransactInvoke,
+			InvokerControl<I::Interpreter, (ExitResult, (I::State, Vec<u8>))>,
+		),
+		ExitError,
+	> {
+		invoker.new_transact(args, handler)
+	}
+
+	/// Intercept [Invoker::finalize_transact].
+	fn finalize_transact(
+		&self,
+		invoker: &I,
+		invoke: &I::TransactInvoke,
+		exit: ExitResult,
+		machine: (I::State, Vec<u8>),
+		handler: &mut H,
+	) -> Result<I::TransactValue, ExitError> {
+		invoker.finalize_transact(invoke, exit, machine, handler)
+	}
+
+	/// Intercept [Invoker::enter_substack].
+	#[allow(clippy::type_complexity)]
+	fn enter_substack(
+		&self,
+		invoker: &I,
+		trap: Tr,
+		machine: &mut I::Interpreter,
+		handler: &mut H,
+		depth: usize,
+	) -> Capture<
+		Result<
+			(
+				I::SubstackInvoke,
+				InvokerControl<I::Interpreter, (ExitResult, (I::State, Vec<u8>))>,
+			),
+			ExitError,
+		>,
+		I::Interrupt,
+	> {
+		invoker.enter_substack(trap, machine, handler, depth)
+	}
+
+	/// Intercept [Invoker::exit_substack].
+	fn exit_substack(
+		&self,
+		invoker: &I,
+		result: ExitResult,
+		child: (I::State, Vec<u8>),
+		trap_data: I::SubstackInvoke,
+		parent: &mut I::Interpreter,
+		handler: &mut H,
+	) -> Result<(), ExitError> {
+		invoker.exit_substack(result, child, trap_data, parent, handler)
+	}
+}
+
+/// An invoker that routes all calls through an [InvokerMiddleware] around
+/// the wrapped invoker. Middleware invokers can be nested, the outermost
+/// middleware is called first.
+///
+/// ```ignore
+/// let invoker = MiddlewareInvoker::new(Invoker::new(&config, &resolver), CallPolicy);
+/// let invoker = MiddlewareInvoker::new(invoker, Tracing);
+/// ```
+pub struct MiddlewareInvoker<I, M> {
+	invoker: I,
+	middleware: M,
+}
+
+impl<I, M> MiddlewareInvoker<I, M> {
+	/// Wrap the invoker with the middleware.
+	pub fn new(invoker: I, middleware: M) -> Self {
+		Self {
+			invoker,
+			middleware,
+		}
+	}
+
+	/// The wrapped invoker.
+	pub fn invoker(&self) -> &I {
+		&self.invoker
+	}
+
+	/// The middleware.
+	pub fn middleware(&self) -> &M {
+		&self.middleware
+	}
+}
+
+impl<H, Tr, I, M> Invoker<H, Tr> for MiddlewareInvoker<I, M>
+where
+	I: Invoker<H, Tr>,
+	M: InvokerMiddleware<H, Tr, I>,
+{
+	type State = I::State;
+	type Interpreter = I::Interpreter;
+	type Interrupt = I::Interrupt;
+	type TransactArgs = I::TransactArgs;
+	type TransactInvoke = I::TransactInvoke;
+	type TransactValue = I::TransactValue;
+	type SubstackInvoke = I::SubstackInvoke;
+
+	fn new_transact(
+		&self,
+		args: Self::TransactArgs,
+		handler: &mut H,
+	) -> Result<
+		(
+			Self::TransactInvoke,
+			InvokerControl<Self::Interpreter, (ExitResult, (Self::State, Vec<u8>))>,
+		),
+		ExitError,
+	> {
+		self.middleware.new_transact(&self.invoker, args, handler)
+	}
+
+	fn finalize_transact(
+		&self,
+		invoke: &Self::TransactInvoke,
+		exit: ExitResult,
+		machine: (Self::State, Vec<u8>),
+		handler: &mut H,
+	) -> Result<Self::TransactValue, ExitError> {
+		self.middleware
+			.finalize_transact(&self.invoker, invoke, exit, machine, handler)
+	}
+
+	fn enter_substack(
+		&self,
+		trap: Tr,
+		machine: &mut Self::Interpreter,
+		handler: &mut H,
+		depth: usize,
+	) -> Capture<
+		Result<
+			(
+				Self::SubstackInvoke,
+				InvokerControl<Self::Interpreter, (ExitResult, (Self::State, Vec<u8>))>,
+			),
+			ExitError,
+		>,
+		Self::Interrupt,
+	> {
+		self.middleware
+			.enter_substack(&self.invoker, trap, machine, handler, depth)
+	}
+
+	fn exit_substack(
+		&self,
+		result: ExitResult,
+		child: (Self::State, Vec<u8>),
+		trap_data: Self::SubstackInvoke,
+		parent: &mut Self::Interpreter,
+		handler: &mut H,
+	) -> Result<(), ExitError> {
+		self.middleware
+			.exit_substack(&self.invoker, result, child, trap_data, parent, handler)
+	}
+}
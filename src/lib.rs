@@ -6,8 +6,10 @@
 //! ## Basic usage
 //!
 //! The entrypoint of a normal EVM execution is through the [transact] function.
-//! The [transact] function implements a hybrid (stack-based, and then
-//! heap-based) call stack.
+//! The [transact] function implements a heap-based call stack, and yields
+//! invoker interrupts to the caller. The [transact_hybrid] function implements
+//! a faster hybrid (stack-based, and then heap-based) call stack, for invokers
+//! that never interrupt.
 //!
 //! To use the [transact] function, you will need to first implement a
 //! backend. This is anything that implements [RuntimeEnvironment],
@@ -26,6 +28,12 @@
 //!   given a code address for call or an init code for create. You may want
 //!   [standard::EtableResolver], which accepts a precompile set.
 //!
+//! To run a transaction without committing any of its state changes (for
+//! example, to implement `eth_call`), use the [simulate] function instead.
+//!
+//! Integrations written against the `StackExecutor` and `MemoryBackend` of
+//! 0.x can use the drop-in replacements of [compat] while they migrate.
+//!
 //! ## Debugging
 //!
 //! Rust EVM supports two different methods for debugging. You can either single
@@ -38,6 +46,9 @@
 //! heap-only call stack [HeapTransact]. Parameters passed to [HeapTransact] are
 //! the same as [transact].
 //!
+//! For breakpoints and stepping over call frames, wrap the [HeapTransact] in a
+//! [debugger::Debugger].
+//!
 //! ### Tracing
 //!
 //! The interpreter machine uses information from an [Etable] to decide how each
@@ -47,6 +58,35 @@
 //! If you also want to trace inside gasometers, simply create a wrapper struct
 //! of the gasometer you use, and pass that into the invoker.
 //!
+//! To modify the execution instead of only observing it, for example to mock
+//! calls or to force a frame to revert, enable the `inspector` feature and use
+//! `standard::inspector`. The validation rules of ERC-4337 bundlers are
+//! checked by an inspector in `standard::erc4337`.
+//!
+//! ### Counters
+//!
+//! For benchmarking, where a tracer costs too much, enable the `counters`
+//! feature. The standard gasometer then counts executed opcodes, and the
+//! counts of each transaction are returned in the `counters` field of
+//! [standard::TransactValue]. Without the feature, nothing is counted.
+//!
+//! Similarly, the `frames` feature records the gas usage of each exited
+//! substack, returned in the `frames` field of [standard::TransactValue].
+//!
+//! ### Metrics
+//!
+//! For node observability, wrap the backend with a [metrics::MeteredBackend]
+//! and the invoker with a [MiddlewareInvoker] around a [metrics::Metrics]
+//! sink, which then receives storage and code accesses, substates, calls and
+//! creates.
+//!
+//! ### Timing
+//!
+//! To find out where the interpreter spends its time, enable the `timing`
+//! feature, and share `timing::Timings` between a [HookedEtable] and a
+//! [MiddlewareInvoker]. They measure the time of each class of opcodes and of
+//! each call frame with a pluggable clock.
+//!
 //! ## Customization
 //!
 //! All aspects of the interpreter can be customized individually.
@@ -56,6 +96,9 @@
 //!   ones.
 //! * Code resolution and precompiles can be customized by [standard::Resolver].
 //! * Call invocation and transaction behavior can be customized via [standard::Invoker].
+//! * Parts of an invoker can be intercepted, without reimplementing it, by wrapping it with an
+//!   [InvokerMiddleware] in a [MiddlewareInvoker]. Similarly, an [EtableHook] in a
+//!   [HookedEtable] is called around each opcode of an etable.
 //! * Finally, each machine on the call stack has the concept of [Color], which allows you to
 //!   implement account versioning, or specialized precompiles that invoke subcalls.
 
@@ -65,20 +108,31 @@
 
 extern crate alloc;
 
+#[cfg(feature = "alloy")]
+pub mod alloy;
 pub mod backend;
+pub mod compat;
+pub mod debugger;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod metrics;
 pub mod standard;
+#[cfg(feature = "timing")]
+pub mod timing;
 
 mod call_stack;
 mod gasometer;
+mod hook;
 mod invoker;
 
 pub use evm_interpreter as interpreter;
 
 pub use crate::{
 	backend::TransactionalBackend,
-	call_stack::{transact, HeapTransact},
+	call_stack::{simulate, transact, transact_hybrid, HeapTransact, Resume, TransactOutcome},
 	gasometer::GasMutState,
-	invoker::{Invoker, InvokerControl},
+	hook::{EtableHook, HookedEtable},
+	invoker::{Invoker, InvokerControl, InvokerMiddleware, MiddlewareInvoker},
 };
 
 /// Merge strategy of a backend substate layer or a call stack gasometer layer.
@@ -0,0 +1,396 @@
+//! Metrics of the backend and the invoker, for node observability.
+//!
+//! A [Metrics] sink receives counter increments and timer events. Wrap the
+//! backend in a [MeteredBackend], and the invoker in a
+//! [crate::MiddlewareInvoker] with a reference to the sink as middleware, to
+//! report into it. Without the wrappers, nothing is reported, and the `()`
+//! sink discards everything.
+//!
+//! ```ignore
+//! let metrics = PrometheusMetrics::new(&registry);
+//! let mut backend = MeteredBackend::new(OverlayedBackend::new(base, accessed), &metrics);
+//! let invoker = MiddlewareInvoker::new(Invoker::new(&config, &resolver), &metrics);
+//! ```
+
+use alloc::vec::Vec;
+
+use evm_interpreter::{
+	error::{Capture, ExitError, ExitResult},
+	runtime::{
+		BlockHashProvider, Log, RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment,
+		SetCodeOrigin, Transfer,
+	},
+};
+use primitive_types::{H160, H256, U256};
+
+use crate::{
+	backend::TransactionalBackend,
+	invoker::{Invoker as InvokerT, InvokerControl, InvokerMiddleware},
+	standard::SubstackInvoke,
+	MergeStrategy,
+};
+
+/// Events counted by a [Metrics] sink.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Counter {
+	/// Storage read from the backend, by `SLOAD` or by `SSTORE` gas metering.
+	StorageRead,
+	/// Storage write to the backend, by `SSTORE`.
+	StorageWrite,
+	/// Code read from the backend.
+	CodeRead,
+	/// Substate pushed on the backend.
+	SubstatePush,
+	/// Call substack entered, including precompile calls.
+	Call,
+	/// Create substack entered.
+	Create,
+}
+
+/// Durations measured by a [Metrics] sink.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Timer {
+	/// From the start to the finalization of a transaction.
+	Transaction,
+	/// From the entry to the exit of a substack.
+	Substack,
+}
+
+/// A sink of backend and invoker metrics. All methods do nothing by default.
+///
+/// Methods take `&self`, so that a sink can be shared, and are expected to
+/// use interior mutability, for example atomic counters. Timers of the same
+/// kind nest: each [Metrics::start_timer] is followed by its
+/// [Metrics::stop_timer] after the ones of nested substacks. The sink
+/// measures time itself, as this crate does not have a clock.
+pub trait Metrics {
+	/// Count one event.
+	fn increment(&self, _counter: Counter) {}
+
+	/// Start measuring a duration.
+	fn start_timer(&self, _timer: Timer) {}
+
+	/// Stop measuring the last started duration of the kind.
+	fn stop_timer(&self, _timer: Timer) {}
+}
+
+impl Metrics for () {}
+
+impl<'a, M: Metrics + ?Sized> Metrics for &'a M {
+	fn increment(&self, counter: Counter) {
+		(**self).increment(counter)
+	}
+
+	fn start_timer(&self, timer: Timer) {
+		(**self).start_timer(timer)
+	}
+
+	fn stop_timer(&self, timer: Timer) {
+		(**self).stop_timer(timer)
+	}
+}
+
+/// A backend that reports storage reads and writes, code reads and substate
+/// pushes of the wrapped backend into a [Metrics] sink.
+pub struct MeteredBackend<B, M> {
+	backend: B,
+	metrics: M,
+}
+
+impl<B, M> MeteredBackend<B, M> {
+	/// Create a new metered backend reporting into the sink.
+	pub fn new(backend: B, metrics: M) -> Self {
+		Self { backend, metrics }
+	}
+
+	/// The wrapped backend.
+	pub fn backend(&self) -> &B {
+		&self.backend
+	}
+
+	/// Mutable reference to the wrapped backend. Accesses through it are not
+	/// reported.
+	pub fn backend_mut(&mut self) -> &mut B {
+		&mut self.backend
+	}
+
+	/// Return the wrapped backend.
+	pub fn into_inner(self) -> B {
+		self.backend
+	}
+}
+
+impl<B: BlockHashProvider, M> BlockHashProvider for MeteredBackend<B, M> {
+	fn block_hash(&self, number: U256) -> H256 {
+		self.backend.block_hash(number)
+	}
+}
+
+impl<B: RuntimeEnvironment, M> RuntimeEnvironment for MeteredBackend<B, M> {
+	fn block_number(&self) -> U256 {
+		self.backend.block_number()
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.backend.block_coinbase()
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.backend.block_timestamp()
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.backend.block_difficulty()
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.backend.block_randomness()
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.backend.block_parent_beacon_root()
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.backend.block_gas_limit()
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.backend.block_base_fee_per_gas()
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		self.backend.blob_base_fee()
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.backend.chain_id()
+	}
+}
+
+impl<B: RuntimeBaseBackend, M: Metrics> RuntimeBaseBackend for MeteredBackend<B, M> {
+	fn balance(&self, address: H160) -> U256 {
+		self.backend.balance(address)
+	}
+
+	fn code_size(&self, address: H160) -> U256 {
+		self.backend.code_size(address)
+	}
+
+	fn code_hash(&self, address: H160) -> H256 {
+		self.backend.code_hash(address)
+	}
+
+	fn keccak256(&self, data: &[u8]) -> H256 {
+		self.backend.keccak256(data)
+	}
+
+	fn ecrecover(&self, hash: H256, y_parity: u8, r: H256, s: H256) -> Option<H160> {
+		self.backend.ecrecover(hash, y_parity, r, s)
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.metrics.increment(Counter::CodeRead);
+		self.backend.code(address)
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.metrics.increment(Counter::StorageRead);
+		self.backend.storage(address, index)
+	}
+
+	fn transient_storage(&self, address: H160, index: H256) -> H256 {
+		self.backend.transient_storage(address, index)
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.backend.exists(address)
+	}
+
+	fn nonce(&self, address: H160) -> U256 {
+		self.backend.nonce(address)
+	}
+
+	fn prefetch(&self, addresses: &[H160], keys: &[(H160, H256)]) {
+		self.backend.prefetch(addresses, keys)
+	}
+}
+
+impl<B: RuntimeBackend, M: Metrics> RuntimeBackend for MeteredBackend<B, M> {
+	fn original_storage(&self, address: H160, index: H256) -> H256 {
+		self.metrics.increment(Counter::StorageRead);
+		self.backend.original_storage(address, index)
+	}
+
+	fn deleted(&self, address: H160) -> bool {
+		self.backend.deleted(address)
+	}
+
+	fn is_cold(&self, address: H160, index: Option<H256>) -> bool {
+		self.backend.is_cold(address, index)
+	}
+
+	fn mark_hot(&mut self, address: H160, index: Option<H256>) {
+		self.backend.mark_hot(address, index)
+	}
+
+	fn set_storage(&mut self, address: H160, index: H256, value: H256) -> Result<(), ExitError> {
+		self.metrics.increment(Counter::StorageWrite);
+		self.backend.set_storage(address, index, value)
+	}
+
+	fn set_transient_storage(
+		&mut self,
+		address: H160,
+		index: H256,
+		value: H256,
+	) -> Result<(), ExitError> {
+		self.backend.set_transient_storage(address, index, value)
+	}
+
+	fn log(&mut self, log: Log) -> Result<(), ExitError> {
+		self.backend.log(log)
+	}
+
+	fn mark_delete(&mut self, address: H160) {
+		self.backend.mark_delete(address)
+	}
+
+	fn reset_storage(&mut self, address: H160) {
+		self.backend.reset_storage(address)
+	}
+
+	fn set_code(
+		&mut self,
+		address: H160,
+		code: Vec<u8>,
+		origin: SetCodeOrigin,
+	) -> Result<(), ExitError> {
+		self.backend.set_code(address, code, origin)
+	}
+
+	fn reset_balance(&mut self, address: H160) {
+		self.backend.reset_balance(address)
+	}
+
+	fn deposit(&mut self, target: H160, value: U256) {
+		self.backend.deposit(target, value)
+	}
+
+	fn withdrawal(&mut self, source: H160, value: U256) -> Result<(), ExitError> {
+		self.backend.withdrawal(source, value)
+	}
+
+	fn transfer(&mut self, transfer: Transfer) -> Result<(), ExitError> {
+		self.backend.transfer(transfer)
+	}
+
+	fn inc_nonce(&mut self, address: H160) -> Result<(), ExitError> {
+		self.backend.inc_nonce(address)
+	}
+
+	fn touch(&mut self, address: H160) {
+		self.backend.touch(address)
+	}
+
+	fn take_touched(&mut self) -> Vec<H160> {
+		self.backend.take_touched()
+	}
+}
+
+impl<B: TransactionalBackend, M: Metrics> TransactionalBackend for MeteredBackend<B, M> {
+	fn push_substate(&mut self) {
+		self.metrics.increment(Counter::SubstatePush);
+		self.backend.push_substate()
+	}
+
+	fn pop_substate(&mut self, strategy: MergeStrategy) {
+		self.backend.pop_substate(strategy)
+	}
+
+	fn substate_logs(&self) -> Vec<Log> {
+		self.backend.substate_logs()
+	}
+}
+
+/// Reports calls and creates, and times transactions and substacks into the
+/// sink, in a [crate::MiddlewareInvoker].
+impl<'metrics, H, Tr, I, M> InvokerMiddleware<H, Tr, I> for &'metrics M
+where
+	I: InvokerT<H, Tr, SubstackInvoke = SubstackInvoke>,
+	M: Metrics + ?Sized,
+{
+	fn new_transact(
+		&self,
+		invoker: &I,
+		args: I::TransactArgs,
+		handler: &mut H,
+	) -> Result<
+		(
+			I::TransactInvoke,
+			InvokerControl<I::Interpreter, (ExitResult, (I::State, Vec<u8>))>,
+		),
+		ExitError,
+	> {
+		let ret = invoker.new_transact(args, handler);
+		if ret.is_ok() {
+			self.start_timer(Timer::Transaction);
+		}
+		ret
+	}
+
+	fn finalize_transact(
+		&self,
+		invoker: &I,
+		invoke: &I::TransactInvoke,
+		exit: ExitResult,
+		machine: (I::State, Vec<u8>),
+		handler: &mut H,
+	) -> Result<I::TransactValue, ExitError> {
+		let ret = invoker.finalize_transact(invoke, exit, machine, handler);
+		self.stop_timer(Timer::Transaction);
+		ret
+	}
+
+	fn enter_substack(
+		&self,
+		invoker: &I,
+		trap: Tr,
+		machine: &mut I::Interpreter,
+		handler: &mut H,
+		depth: usize,
+	) -> Capture<
+		Result<
+			(
+				I::SubstackInvoke,
+				InvokerControl<I::Interpreter, (ExitResult, (I::State, Vec<u8>))>,
+			),
+			ExitError,
+		>,
+		I::Interrupt,
+	> {
+		let ret = invoker.enter_substack(trap, machine, handler, depth);
+		if let Capture::Exit(Ok((invoke, _))) = &ret {
+			self.increment(match invoke {
+				SubstackInvoke::Call { .. } => Counter::Call,
+				SubstackInvoke::Create { .. } => Counter::Create,
+			});
+			self.start_timer(Timer::Substack);
+		}
+		ret
+	}
+
+	fn exit_substack(
+		&self,
+		invoker: &I,
+		result: ExitResult,
+		child: (I::State, Vec<u8>),
+		trap_data: I::SubstackInvoke,
+		parent: &mut I::Interpreter,
+		handler: &mut H,
+	) -> Result<(), ExitError> {
+		let ret = invoker.exit_substack(result, child, trap_data, parent, handler);
+		self.stop_timer(Timer::Substack);
+		ret
+	}
+}
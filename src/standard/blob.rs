@@ -0,0 +1,204 @@
+//! # Blob gas market
+//!
+//! Blob gas accounting and the blob base fee of EIP-4844, for building and
+//! verifying blocks with blob transactions.
+
+use core::fmt;
+
+use primitive_types::U256;
+
+/// Blob gas used by each blob.
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+/// Minimum blob base fee.
+pub const MIN_BLOB_BASE_FEE: u64 = 1;
+
+/// Blob gas market parameters of a hard fork.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub struct BlobParams {
+	/// Blob gas a block targets. Blocks using more increase the excess blob
+	/// gas, and blocks using less decrease it.
+	pub target_blob_gas_per_block: u64,
+	/// Maximum blob gas a block can use.
+	pub max_blob_gas_per_block: u64,
+	/// Denominator of the blob base fee exponent.
+	pub base_fee_update_fraction: u64,
+}
+
+impl BlobParams {
+	/// Cancun parameters, a target of 3 and a maximum of 6 blobs.
+	pub const fn cancun() -> Self {
+		Self {
+			target_blob_gas_per_block: 3 * GAS_PER_BLOB,
+			max_blob_gas_per_block: 6 * GAS_PER_BLOB,
+			base_fee_update_fraction: 3_338_477,
+		}
+	}
+
+	/// Prague parameters, a target of 6 and a maximum of 9 blobs (see
+	/// EIP-7691).
+	pub const fn prague() -> Self {
+		Self {
+			target_blob_gas_per_block: 6 * GAS_PER_BLOB,
+			max_blob_gas_per_block: 9 * GAS_PER_BLOB,
+			base_fee_update_fraction: 5_007_716,
+		}
+	}
+}
+
+/// Reason for the blob gas of a block to be invalid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlobGasError {
+	/// Blob gas used is more than the maximum of a block.
+	BlobGasLimitExceeded,
+	/// Blob gas used is not a multiple of the gas per blob.
+	NotBlobGasMultiple,
+	/// Excess blob gas does not match the one computed from the parent.
+	InvalidExcessBlobGas,
+	/// Blob gas of the number of blobs overflows.
+	BlobGasOverflow,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BlobGasError {}
+
+impl BlobGasError {
+	/// Stable numeric code of the error. Codes are never reused.
+	#[must_use]
+	pub const fn code(&self) -> u16 {
+		match self {
+			Self::BlobGasLimitExceeded => 0,
+			Self::NotBlobGasMultiple => 1,
+			Self::InvalidExcessBlobGas => 2,
+			Self::BlobGasOverflow => 3,
+		}
+	}
+
+	/// Stable name of the error.
+	#[must_use]
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::BlobGasLimitExceeded => "blob_gas_limit_exceeded",
+			Self::NotBlobGasMultiple => "not_blob_gas_multiple",
+			Self::InvalidExcessBlobGas => "invalid_excess_blob_gas",
+			Self::BlobGasOverflow => "blob_gas_overflow",
+		}
+	}
+}
+
+impl fmt::Display for BlobGasError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::BlobGasLimitExceeded => f.write_str("blob gas limit exceeded"),
+			Self::NotBlobGasMultiple => f.write_str("blob gas used not a multiple of gas per blob"),
+			Self::InvalidExcessBlobGas => f.write_str("invalid excess blob gas"),
+			Self::BlobGasOverflow => f.write_str("blob gas overflow"),
+		}
+	}
+}
+
+/// Blob gas used by a transaction with the given number of blobs. The number
+/// comes from the transaction, and is checked for overflow.
+pub const fn blob_gas(blob_count: u64) -> Result<u64, BlobGasError> {
+	match blob_count.checked_mul(GAS_PER_BLOB) {
+		Some(gas) => Ok(gas),
+		None => Err(BlobGasError::BlobGasOverflow),
+	}
+}
+
+/// Excess blob gas of a block, computed from its parent.
+pub fn calc_excess_blob_gas(
+	parent_excess_blob_gas: u64,
+	parent_blob_gas_used: u64,
+	params: &BlobParams,
+) -> u64 {
+	parent_excess_blob_gas
+		.saturating_add(parent_blob_gas_used)
+		.saturating_sub(params.target_blob_gas_per_block)
+}
+
+/// Approximate `factor * e ** (numerator / denominator)` with integer math,
+/// as the blob base fee is specified.
+pub fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+	let mut i = U256::one();
+	let mut output = U256::zero();
+	let mut numerator_accum = factor.saturating_mul(denominator);
+
+	while !numerator_accum.is_zero() {
+		output = output.saturating_add(numerator_accum);
+		numerator_accum = numerator_accum.saturating_mul(numerator) / denominator.saturating_mul(i);
+		i += U256::one();
+	}
+
+	output / denominator
+}
+
+/// Blob base fee of a block with the given excess blob gas.
+pub fn blob_base_fee(excess_blob_gas: u64, params: &BlobParams) -> U256 {
+	fake_exponential(
+		U256::from(MIN_BLOB_BASE_FEE),
+		U256::from(excess_blob_gas),
+		U256::from(params.base_fee_update_fraction),
+	)
+}
+
+/// Check the blob gas used by a block and its excess blob gas, against the
+/// values of its parent.
+pub fn check_block_blob_gas(
+	blob_gas_used: u64,
+	excess_blob_gas: u64,
+	parent_blob_gas_used: u64,
+	parent_excess_blob_gas: u64,
+	params: &BlobParams,
+) -> Result<(), BlobGasError> {
+	if blob_gas_used > params.max_blob_gas_per_block {
+		return Err(BlobGasError::BlobGasLimitExceeded);
+	}
+
+	if blob_gas_used % GAS_PER_BLOB != 0 {
+		return Err(BlobGasError::NotBlobGasMultiple);
+	}
+
+	if excess_blob_gas != calc_excess_blob_gas(parent_excess_blob_gas, parent_blob_gas_used, params)
+	{
+		return Err(BlobGasError::InvalidExcessBlobGas);
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use primitive_types::U256;
+
+	use super::{blob_gas, BlobGasError, BlobParams, GAS_PER_BLOB};
+	use crate::standard::block::BlockGasMeter;
+
+	#[test]
+	fn blob_gas_overflow_is_rejected() {
+		assert_eq!(blob_gas(6), Ok(6 * GAS_PER_BLOB));
+		assert_eq!(blob_gas(u64::MAX), Err(BlobGasError::BlobGasOverflow));
+		assert_eq!(
+			blob_gas(u64::MAX / GAS_PER_BLOB + 1),
+			Err(BlobGasError::BlobGasOverflow)
+		);
+
+		let mut meter =
+			BlockGasMeter::new(U256::from(30_000_000)).with_blob_params(&BlobParams::cancun());
+		assert!(meter.check(U256::from(21_000), u64::MAX).is_err());
+		assert!(meter.record(U256::from(21_000), u64::MAX).is_err());
+		assert_eq!(meter.gas_used(), U256::zero());
+
+		meter.record(U256::from(21_000), 6).unwrap();
+		assert_eq!(meter.blob_gas_used(), 6 * GAS_PER_BLOB);
+		assert!(meter.check(U256::from(21_000), 1).is_err());
+	}
+}
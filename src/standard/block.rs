@@ -0,0 +1,838 @@
+//! # Block execution
+//!
+//! Run all transactions of a block against one backend, together with the
+//! block-level state transitions (system calls, execution layer requests,
+//! withdrawals and rewards).
+
+use alloc::vec::Vec;
+use core::{convert::Infallible, fmt};
+
+use evm_interpreter::{
+	error::{CallCreateTrap, ExitError, TrapConsume},
+	runtime::{Log, RuntimeBackend, RuntimeEnvironment, RuntimeState},
+	RunInterpreter,
+};
+use primitive_types::{H160, H256, U256};
+
+use crate::{
+	backend::TransactionalBackend,
+	standard::{
+		blob::{self, BlobParams},
+		receipt::{Receipt, TransactionType},
+		validate_transaction, Bloom, Config, FeePolicy, Invoker, InvokerState, Resolver,
+		TransactArgs, TransactValue, ValidationError,
+	},
+	Invoker as InvokerT,
+};
+
+/// Request type of deposits (see EIP-6110).
+pub const DEPOSIT_REQUEST_TYPE: u8 = 0x00;
+/// Request type of withdrawal requests (see EIP-7002).
+pub const WITHDRAWAL_REQUEST_TYPE: u8 = 0x01;
+/// Request type of consolidation requests (see EIP-7251).
+pub const CONSOLIDATION_REQUEST_TYPE: u8 = 0x02;
+
+/// Topic of the `DepositEvent` log of the deposit contract.
+pub const DEPOSIT_EVENT_TOPIC: H256 = H256([
+	0x64, 0x9b, 0xbc, 0x62, 0xd0, 0xe3, 0x13, 0x42, 0xaf, 0xea, 0x4e, 0x5c, 0xd8, 0x2d, 0x40, 0x49,
+	0xe7, 0xe1, 0xee, 0x91, 0x2f, 0xc0, 0x88, 0x9a, 0xa7, 0x90, 0x80, 0x3b, 0xe3, 0x90, 0x38, 0xc5,
+]);
+
+/// Offsets and lengths of the fields of a `DepositEvent` log, in order: the
+/// public key, the withdrawal credentials, the amount, the signature and the
+/// index.
+const DEPOSIT_EVENT_FIELDS: [(usize, usize); 5] =
+	[(160, 48), (256, 32), (320, 8), (384, 96), (512, 8)];
+const DEPOSIT_EVENT_SIZE: usize = 576;
+
+/// Parse the deposit request data of a `DepositEvent` log, the concatenated
+/// fields of the event. Return `None` if the log is not ABI-encoded as the
+/// deposit contract does.
+pub fn parse_deposit_log(log: &Log) -> Option<Vec<u8>> {
+	if log.data.len() != DEPOSIT_EVENT_SIZE {
+		return None;
+	}
+
+	let word = |position: usize| U256::from_big_endian(&log.data[position..(position + 32)]);
+	let mut request = Vec::with_capacity(192);
+	for (index, (offset, len)) in DEPOSIT_EVENT_FIELDS.into_iter().enumerate() {
+		if word(index * 32) != U256::from(offset) || word(offset) != U256::from(len) {
+			return None;
+		}
+		request.extend_from_slice(&log.data[(offset + 32)..(offset + 32 + len)]);
+	}
+	Some(request)
+}
+
+/// A validator withdrawal (see EIP-4895).
+#[derive(Clone, Debug)]
+pub struct Withdrawal {
+	/// Index of the withdrawal.
+	pub index: u64,
+	/// Index of the validator.
+	pub validator_index: u64,
+	/// Recipient of the withdrawal.
+	pub address: H160,
+	/// Amount, in Gwei.
+	pub amount: u64,
+}
+
+/// An ommer (uncle) block header, used for pre-merge block rewards.
+#[derive(Clone, Debug)]
+pub struct Ommer {
+	/// Coinbase of the ommer block.
+	pub coinbase: H160,
+	/// Number of the ommer block.
+	pub number: U256,
+}
+
+/// A transaction of a block.
+#[derive(Clone, Debug)]
+pub struct BlockTransaction {
+	/// Type of the transaction, for its receipt.
+	pub transaction_type: TransactionType,
+	/// Transaction arguments.
+	pub args: TransactArgs,
+	/// Number of blobs of the transaction (see EIP-4844). Zero for
+	/// transactions without blobs.
+	pub blob_count: u64,
+}
+
+impl From<(TransactionType, TransactArgs)> for BlockTransaction {
+	fn from((transaction_type, args): (TransactionType, TransactArgs)) -> Self {
+		Self {
+			transaction_type,
+			args,
+			blob_count: 0,
+		}
+	}
+}
+
+/// Reason for a transaction not to fit in a block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BlockGasError {
+	/// Gas limit of the transaction is higher than the gas left in the block.
+	GasLimitReached,
+	/// Blob gas of the transaction is higher than the blob gas left in the
+	/// block.
+	BlobGasLimitReached,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BlockGasError {}
+
+impl fmt::Display for BlockGasError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::GasLimitReached => f.write_str("gas limit exceeds the gas left in the block"),
+			Self::BlobGasLimitReached => {
+				f.write_str("blob gas exceeds the blob gas left in the block")
+			}
+		}
+	}
+}
+
+/// Gas used by the transactions of a block, against the block gas limit and
+/// the maximum blob gas of the block.
+///
+/// Before each transaction, [BlockGasMeter::check] that it fits in the block.
+/// After it executes, [BlockGasMeter::record] the gas it used.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BlockGasMeter {
+	gas_limit: U256,
+	gas_used: U256,
+	blob_gas_limit: u64,
+	blob_gas_used: u64,
+}
+
+impl BlockGasMeter {
+	/// Create a meter for a block with the gas limit. No blob gas is
+	/// available, see [BlockGasMeter::with_blob_params].
+	pub fn new(gas_limit: U256) -> Self {
+		Self {
+			gas_limit,
+			gas_used: U256::zero(),
+			blob_gas_limit: 0,
+			blob_gas_used: 0,
+		}
+	}
+
+	/// Make the maximum blob gas of the parameters available to the block.
+	#[must_use]
+	pub fn with_blob_params(mut self, params: &BlobParams) -> Self {
+		self.blob_gas_limit = params.max_blob_gas_per_block;
+		self
+	}
+
+	/// Gas limit of the block.
+	pub fn gas_limit(&self) -> U256 {
+		self.gas_limit
+	}
+
+	/// Gas used by the recorded transactions.
+	pub fn gas_used(&self) -> U256 {
+		self.gas_used
+	}
+
+	/// Gas left in the block.
+	pub fn remaining_gas(&self) -> U256 {
+		self.gas_limit.saturating_sub(self.gas_used)
+	}
+
+	/// Blob gas used by the recorded transactions.
+	pub fn blob_gas_used(&self) -> u64 {
+		self.blob_gas_used
+	}
+
+	/// Blob gas left in the block.
+	pub fn remaining_blob_gas(&self) -> u64 {
+		self.blob_gas_limit.saturating_sub(self.blob_gas_used)
+	}
+
+	/// Check that a transaction with the gas limit and the number of blobs
+	/// fits in the block.
+	pub fn check(&self, gas_limit: U256, blob_count: u64) -> Result<(), BlockGasError> {
+		if gas_limit > self.remaining_gas() {
+			return Err(BlockGasError::GasLimitReached);
+		}
+		self.blob_gas(blob_count)?;
+		Ok(())
+	}
+
+	/// Record the gas used by a transaction, and the blob gas of its blobs.
+	/// The blob gas is checked as in [BlockGasMeter::check], and nothing is
+	/// recorded if it does not fit in the block.
+	pub fn record(&mut self, gas_used: U256, blob_count: u64) -> Result<(), BlockGasError> {
+		let blob_gas = self.blob_gas(blob_count)?;
+		self.gas_used = self.gas_used.saturating_add(gas_used);
+		self.blob_gas_used += blob_gas;
+		Ok(())
+	}
+
+	/// Blob gas of the number of blobs, if it fits in the block. A number of
+	/// blobs whose blob gas overflows does not fit in any block.
+	fn blob_gas(&self, blob_count: u64) -> Result<u64, BlockGasError> {
+		match blob::blob_gas(blob_count) {
+			Ok(blob_gas) if blob_gas <= self.remaining_blob_gas() => Ok(blob_gas),
+			_ => Err(BlockGasError::BlobGasLimitReached),
+		}
+	}
+}
+
+/// Error of a block execution.
+#[derive(Clone, Debug)]
+pub enum BlockError {
+	/// Transaction at the given index is invalid.
+	InvalidTransaction(usize, ValidationError),
+	/// Transaction at the given index has a gas limit higher than the gas left
+	/// in the block.
+	BlockGasLimitReached(usize),
+	/// Transaction at the given index has more blob gas than the blob gas left
+	/// in the block.
+	BlobGasLimitReached(usize),
+	/// Transaction at the given index failed with a fatal error.
+	Transaction(usize, ExitError),
+	/// A system call of the block failed.
+	SystemCall(ExitError),
+	/// Transaction at the given index emitted a deposit log that cannot be
+	/// parsed.
+	InvalidDepositLog(usize),
+	/// The block has no transaction at the given index.
+	MissingTransaction(usize),
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BlockError {}
+
+impl fmt::Display for BlockError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::InvalidTransaction(index, e) => write!(f, "transaction {index} invalid: {e}"),
+			Self::BlockGasLimitReached(index) => {
+				write!(f, "transaction {index} exceeds the gas left in the block")
+			}
+			Self::BlobGasLimitReached(index) => {
+				write!(
+					f,
+					"transaction {index} exceeds the blob gas left in the block"
+				)
+			}
+			Self::Transaction(index, e) => write!(f, "transaction {index} failed: {e}"),
+			Self::SystemCall(e) => write!(f, "system call failed: {e}"),
+			Self::InvalidDepositLog(index) => {
+				write!(f, "transaction {index} emitted an invalid deposit log")
+			}
+			Self::MissingTransaction(index) => write!(f, "block has no transaction {index}"),
+		}
+	}
+}
+
+/// Outcome of a block execution.
+#[derive(Clone, Debug)]
+pub struct BlockOutcome {
+	/// Transaction results, in order.
+	pub values: Vec<TransactValue>,
+	/// Transaction receipts, in order.
+	pub receipts: Vec<Receipt>,
+	/// Total gas used by the block.
+	pub gas_used: U256,
+	/// Total blob gas used by the block.
+	pub blob_gas_used: u64,
+	/// Bloom of all logs in the block.
+	pub logs_bloom: Bloom,
+	/// Execution layer requests of the block, each the request type followed
+	/// by the request data, for the requests hash of the header (see
+	/// EIP-7685). Requests with empty data are left out, and all are empty
+	/// before Prague.
+	pub requests: Vec<Vec<u8>>,
+}
+
+/// Check that the transaction at the index fits in the block and is valid.
+fn check_transaction<H: RuntimeEnvironment + RuntimeBackend>(
+	gas_meter: &BlockGasMeter,
+	index: usize,
+	args: &TransactArgs,
+	blob_count: u64,
+	handler: &H,
+	config: &Config,
+) -> Result<(), BlockError> {
+	gas_meter
+		.check(args.gas_limit(), blob_count)
+		.map_err(|err| block_gas_error(index, err))?;
+	validate_transaction(args, handler, config)
+		.map_err(|err| BlockError::InvalidTransaction(index, err))
+}
+
+fn block_gas_error(index: usize, err: BlockGasError) -> BlockError {
+	match err {
+		BlockGasError::GasLimitReached => BlockError::BlockGasLimitReached(index),
+		BlockGasError::BlobGasLimitReached => BlockError::BlobGasLimitReached(index),
+	}
+}
+
+/// Transactions of a block executed by [execute_transactions].
+struct ExecutedTransactions {
+	values: Vec<TransactValue>,
+	receipts: Vec<Receipt>,
+	gas_meter: BlockGasMeter,
+	logs_bloom: Bloom,
+	/// Transaction the execution stopped at, checked against the block but
+	/// not executed.
+	stopped: Option<TransactArgs>,
+}
+
+/// Perform the system calls at the start of the block, then validate and
+/// execute the transactions in order. If `stop_at` is set, the execution
+/// stops at the transaction of that index, which is only validated.
+fn execute_transactions<'config, 'resolver, H, Tr, R, F>(
+	invoker: &Invoker<'config, 'resolver, R, F>,
+	transactions: Vec<BlockTransaction>,
+	stop_at: Option<usize>,
+	blob_params: Option<&BlobParams>,
+	heap_depth: Option<usize>,
+	handler: &mut H,
+) -> Result<ExecutedTransactions, BlockError>
+where
+	R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+	R::Interpreter: RunInterpreter<H, Tr>,
+	H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+	R: Resolver<H>,
+	F: FeePolicy<H>,
+	Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+{
+	let config = invoker.config();
+
+	invoker
+		.process_beacon_block_root::<H, Tr>(handler)
+		.map_err(BlockError::SystemCall)?;
+	invoker
+		.process_parent_block_hash::<H, Tr>(handler)
+		.map_err(BlockError::SystemCall)?;
+
+	let mut executed = ExecutedTransactions {
+		values: Vec::with_capacity(transactions.len()),
+		receipts: Vec::with_capacity(transactions.len()),
+		gas_meter: BlockGasMeter::new(handler.block_gas_limit()),
+		logs_bloom: Bloom::default(),
+		stopped: None,
+	};
+	if let Some(blob_params) = blob_params {
+		executed.gas_meter = executed.gas_meter.with_blob_params(blob_params);
+	}
+
+	for (index, transaction) in transactions.into_iter().enumerate() {
+		let BlockTransaction {
+			transaction_type,
+			args,
+			blob_count,
+		} = transaction;
+
+		check_transaction(
+			&executed.gas_meter,
+			index,
+			&args,
+			blob_count,
+			handler,
+			config,
+		)?;
+		if stop_at == Some(index) {
+			executed.stopped = Some(args);
+			break;
+		}
+
+		let value = crate::transact_hybrid::<H, Tr, _>(args, heap_depth, handler, invoker)
+			.map_err(|err| BlockError::Transaction(index, err))?;
+
+		let receipt = Receipt::new(transaction_type, &value, executed.gas_meter.gas_used());
+		executed
+			.gas_meter
+			.record(value.used_gas, blob_count)
+			.map_err(|err| block_gas_error(index, err))?;
+		executed.logs_bloom.accrue_bloom(&receipt.logs_bloom);
+
+		executed.values.push(value);
+		executed.receipts.push(receipt);
+	}
+
+	Ok(executed)
+}
+
+/// Execute a block.
+///
+/// This performs the system calls at the start of the block, then validates
+/// and executes all transactions in order, collects the execution layer
+/// requests (from Prague), and finally applies withdrawals and (for pre-merge
+/// configs) block rewards.
+///
+/// Gas is accounted with a [BlockGasMeter]. Blob gas is only available with
+/// the blob parameters of the hard fork, from Cancun.
+pub fn execute_block<'config, 'resolver, H, Tr, R, F>(
+	invoker: &Invoker<'config, 'resolver, R, F>,
+	transactions: Vec<BlockTransaction>,
+	withdrawals: &[Withdrawal],
+	ommers: &[Ommer],
+	blob_params: Option<&BlobParams>,
+	heap_depth: Option<usize>,
+	handler: &mut H,
+) -> Result<BlockOutcome, BlockError>
+where
+	R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+	R::Interpreter: RunInterpreter<H, Tr>,
+	H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+	R: Resolver<H>,
+	F: FeePolicy<H>,
+	Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+{
+	let config = invoker.config();
+	let ExecutedTransactions {
+		values,
+		receipts,
+		gas_meter,
+		logs_bloom,
+		..
+	} = execute_transactions::<H, Tr, R, F>(
+		invoker,
+		transactions,
+		None,
+		blob_params,
+		heap_depth,
+		handler,
+	)?;
+
+	let mut requests = Vec::new();
+	if config.eip_7685_enabled {
+		let mut deposits = Vec::new();
+		for (index, value) in values.iter().enumerate() {
+			for log in &value.logs {
+				if log.address == config.deposit_contract_address
+					&& log.topics.first() == Some(&DEPOSIT_EVENT_TOPIC)
+				{
+					let request =
+						parse_deposit_log(log).ok_or(BlockError::InvalidDepositLog(index))?;
+					deposits.extend(request);
+				}
+			}
+		}
+
+		let withdrawal_requests = invoker
+			.process_withdrawal_requests::<H, Tr>(handler)
+			.map_err(BlockError::SystemCall)?;
+		let consolidation_requests = invoker
+			.process_consolidation_requests::<H, Tr>(handler)
+			.map_err(BlockError::SystemCall)?;
+
+		for (request_type, data) in [
+			(DEPOSIT_REQUEST_TYPE, deposits),
+			(WITHDRAWAL_REQUEST_TYPE, withdrawal_requests),
+			(CONSOLIDATION_REQUEST_TYPE, consolidation_requests),
+		] {
+			if !data.is_empty() {
+				let mut request = Vec::with_capacity(data.len() + 1);
+				request.push(request_type);
+				request.extend(data);
+				requests.push(request);
+			}
+		}
+	}
+
+	for withdrawal in withdrawals {
+		let amount = U256::from(withdrawal.amount).saturating_mul(U256::from(1_000_000_000u64));
+		handler.deposit(withdrawal.address, amount);
+	}
+
+	if let Some(reward) = config.block_reward {
+		let number = handler.block_number();
+		let mut coinbase_reward = reward;
+
+		for ommer in ommers {
+			coinbase_reward = coinbase_reward.saturating_add(reward / U256::from(32));
+
+			let ommer_reward =
+				(ommer.number + U256::from(8)).saturating_sub(number) * reward / U256::from(8);
+			handler.deposit(ommer.coinbase, ommer_reward);
+		}
+
+		handler.deposit(handler.block_coinbase(), coinbase_reward);
+	}
+
+	Ok(BlockOutcome {
+		values,
+		receipts,
+		gas_used: gas_meter.gas_used(),
+		blob_gas_used: gas_meter.blob_gas_used(),
+		logs_bloom,
+		requests,
+	})
+}
+
+/// Replay a transaction of a block, in the style of `debug_traceTransaction`.
+///
+/// The handler has the state before the block and the environment of the
+/// block, for example a [crate::backend::BlockEnvBackend] over the state at
+/// the parent block. The block is executed as in [execute_block] with
+/// `invoker`, up to the transaction at `index`. That transaction is then
+/// executed with `traced_invoker`, usually the same invoker with a tracer in
+/// its etable or an [crate::standard::inspector::InspectorMiddleware], and
+/// its value is returned. The transactions after it are not executed.
+pub fn replay_transaction<'config, 'resolver, H, Tr, R, F, I>(
+	invoker: &Invoker<'config, 'resolver, R, F>,
+	traced_invoker: &I,
+	transactions: Vec<BlockTransaction>,
+	index: usize,
+	blob_params: Option<&BlobParams>,
+	heap_depth: Option<usize>,
+	handler: &mut H,
+) -> Result<I::TransactValue, BlockError>
+where
+	R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+	R::Interpreter: RunInterpreter<H, Tr>,
+	H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+	R: Resolver<H>,
+	F: FeePolicy<H>,
+	Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+	I: InvokerT<H, Tr, TransactArgs = TransactArgs, Interrupt = Infallible>,
+	I::Interpreter: RunInterpreter<H, Tr>,
+{
+	if index >= transactions.len() {
+		return Err(BlockError::MissingTransaction(index));
+	}
+
+	let args = execute_transactions::<H, Tr, R, F>(
+		invoker,
+		transactions,
+		Some(index),
+		blob_params,
+		heap_depth,
+		handler,
+	)?
+	.stopped
+	.ok_or(BlockError::MissingTransaction(index))?;
+
+	crate::transact_hybrid::<H, Tr, _>(args, heap_depth, handler, traced_invoker)
+		.map_err(|err| BlockError::Transaction(index, err))
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{collections::BTreeSet, vec, vec::Vec};
+
+	use evm_interpreter::runtime::RuntimeBaseBackend;
+	use primitive_types::{H160, U256};
+
+	use super::{
+		execute_block, replay_transaction, BlockError, BlockOutcome, BlockTransaction, Ommer,
+		Withdrawal, DEPOSIT_EVENT_TOPIC, DEPOSIT_REQUEST_TYPE, WITHDRAWAL_REQUEST_TYPE,
+	};
+	use crate::{
+		backend::{OverlayedBackend, TestBackend},
+		standard::{
+			receipt::TransactionType,
+			testing::{self, call_args, etable, CONTRACT},
+			Config, EtableResolver, Invoker, CONSOLIDATION_REQUEST_ADDRESS,
+			DEPOSIT_CONTRACT_ADDRESS, WITHDRAWAL_REQUEST_ADDRESS,
+		},
+	};
+
+	const REVERTING: H160 = H160([0x21; 20]);
+	const COINBASE: H160 = H160([0x30; 20]);
+
+	fn call(address: H160) -> BlockTransaction {
+		call_with_data(address, Vec::new())
+	}
+
+	fn call_with_data(address: H160, data: Vec<u8>) -> BlockTransaction {
+		BlockTransaction::from((
+			TransactionType::Legacy,
+			call_args(address, data, 100_000, 0),
+		))
+	}
+
+	fn backend() -> TestBackend {
+		let mut base = testing::backend();
+		base.environment.block_number = U256::from(10);
+		base.environment.block_coinbase = COINBASE;
+		// Increment the counter at slot 0, and return it.
+		base.set_code(
+			CONTRACT,
+			vec![
+				0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x80, 0x60, 0x00, 0x55, 0x60, 0x00, 0x52, 0x60,
+				0x20, 0x60, 0x00, 0xf3,
+			],
+		);
+		// Log one topic, then revert.
+		base.set_code(
+			REVERTING,
+			vec![
+				0x60, 0x01, 0x60, 0x00, 0x60, 0x00, 0xa1, 0x60, 0x00, 0x60, 0x00, 0xfd,
+			],
+		);
+		// Log the call data as a deposit event.
+		let mut deposit = vec![0x36, 0x60, 0x00, 0x60, 0x00, 0x37, 0x7f];
+		deposit.extend_from_slice(DEPOSIT_EVENT_TOPIC.as_bytes());
+		deposit.extend_from_slice(&[0x36, 0x60, 0x00, 0xa1, 0x00]);
+		base.set_code(DEPOSIT_CONTRACT_ADDRESS, deposit);
+		// Return the request 0xaa.
+		base.set_code(
+			WITHDRAWAL_REQUEST_ADDRESS,
+			vec![0x60, 0xaa, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3],
+		);
+		// Return no request.
+		base.set_code(CONSOLIDATION_REQUEST_ADDRESS, vec![0x00]);
+		base
+	}
+
+	fn execute<'a>(
+		base: &'a TestBackend,
+		config: &Config,
+		transactions: Vec<BlockTransaction>,
+		withdrawals: &[Withdrawal],
+		ommers: &[Ommer],
+	) -> Result<(BlockOutcome, OverlayedBackend<&'a TestBackend>), BlockError> {
+		let etable = etable();
+		let resolver = EtableResolver::new(config, &(), &etable);
+		let invoker = Invoker::new(config, &resolver);
+
+		let mut backend = OverlayedBackend::new(base, BTreeSet::new());
+		let outcome = execute_block(
+			&invoker,
+			transactions,
+			withdrawals,
+			ommers,
+			None,
+			None,
+			&mut backend,
+		)?;
+		Ok((outcome, backend))
+	}
+
+	/// Data of a `DepositEvent` log, each field filled with its position.
+	fn deposit_log_data() -> (Vec<u8>, Vec<u8>) {
+		let fields = [(160, 48), (256, 32), (320, 8), (384, 96), (512, 8)];
+		let mut data = vec![0; 576];
+		let mut request = Vec::new();
+		for (index, (offset, len)) in fields.into_iter().enumerate() {
+			U256::from(offset).to_big_endian(&mut data[(index * 32)..(index * 32 + 32)]);
+			U256::from(len).to_big_endian(&mut data[offset..(offset + 32)]);
+			data[(offset + 32)..(offset + 32 + len)].fill(index as u8 + 1);
+			request.extend(vec![index as u8 + 1; len]);
+		}
+		(data, request)
+	}
+
+	#[test]
+	fn receipts_accumulate_the_gas_of_the_block() {
+		let base = backend();
+		let config = Config::cancun();
+
+		let (outcome, _) = execute(
+			&base,
+			&config,
+			vec![call(CONTRACT), call(REVERTING), call(CONTRACT)],
+			&[],
+			&[],
+		)
+		.unwrap();
+
+		let statuses = outcome
+			.receipts
+			.iter()
+			.map(|receipt| receipt.status)
+			.collect::<Vec<_>>();
+		assert_eq!(statuses, vec![true, false, true]);
+
+		let mut cumulative_gas_used = U256::zero();
+		for (receipt, value) in outcome.receipts.iter().zip(&outcome.values) {
+			cumulative_gas_used += value.used_gas;
+			assert_eq!(receipt.cumulative_gas_used, cumulative_gas_used);
+		}
+		assert_eq!(outcome.gas_used, cumulative_gas_used);
+		assert_eq!(outcome.blob_gas_used, 0);
+
+		// The log of the reverted transaction is dropped.
+		assert!(outcome.receipts[1].logs.is_empty());
+		assert!(!outcome.logs_bloom.contains_input(REVERTING.as_bytes()));
+		// Requests are only collected from Prague.
+		assert!(outcome.requests.is_empty());
+	}
+
+	#[test]
+	fn withdrawals_and_rewards_are_paid() {
+		const OMMER: H160 = H160([0x40; 20]);
+		const RECIPIENT: H160 = H160([0x50; 20]);
+
+		let base = backend();
+		let config = Config::london();
+		let reward = U256::from(2_000_000_000_000_000_000u64);
+
+		let (_, backend) = execute(
+			&base,
+			&config,
+			Vec::new(),
+			&[Withdrawal {
+				index: 0,
+				validator_index: 1,
+				address: RECIPIENT,
+				amount: 3,
+			}],
+			&[Ommer {
+				coinbase: OMMER,
+				number: U256::from(9),
+			}],
+		)
+		.unwrap();
+
+		assert_eq!(backend.balance(RECIPIENT), U256::from(3_000_000_000u64));
+		assert_eq!(backend.balance(OMMER), reward * 7 / 8);
+		assert_eq!(backend.balance(COINBASE), reward + reward / 32);
+
+		// There are no rewards after the merge.
+		let config = Config::cancun();
+		let (_, backend) = execute(&base, &config, Vec::new(), &[], &[]).unwrap();
+		assert_eq!(backend.balance(COINBASE), U256::zero());
+	}
+
+	#[test]
+	fn requests_are_collected_in_order_of_type() {
+		let base = backend();
+		let config = Config::prague();
+		let (data, deposit) = deposit_log_data();
+
+		let (outcome, _) = execute(
+			&base,
+			&config,
+			vec![call_with_data(DEPOSIT_CONTRACT_ADDRESS, data)],
+			&[],
+			&[],
+		)
+		.unwrap();
+
+		let mut deposits = vec![DEPOSIT_REQUEST_TYPE];
+		deposits.extend(deposit);
+		// The consolidation requests are empty, and left out.
+		assert_eq!(
+			outcome.requests,
+			vec![deposits, vec![WITHDRAWAL_REQUEST_TYPE, 0xaa]]
+		);
+
+		let (outcome, _) = execute(&base, &config, Vec::new(), &[], &[]).unwrap();
+		assert_eq!(outcome.requests, vec![vec![WITHDRAWAL_REQUEST_TYPE, 0xaa]]);
+	}
+
+	#[test]
+	fn invalid_deposit_logs_invalidate_the_block() {
+		let base = backend();
+		let config = Config::prague();
+		let (mut data, _) = deposit_log_data();
+		// Offset of the public key.
+		data[31] = 0;
+
+		assert!(matches!(
+			execute(
+				&base,
+				&config,
+				vec![
+					call(CONTRACT),
+					call_with_data(DEPOSIT_CONTRACT_ADDRESS, data)
+				],
+				&[],
+				&[],
+			),
+			Err(BlockError::InvalidDepositLog(1))
+		));
+	}
+
+	#[test]
+	fn replayed_transactions_match_the_block_execution() {
+		let base = backend();
+		let config = Config::cancun();
+		let etable = etable();
+		let resolver = EtableResolver::new(&config, &(), &etable);
+		let invoker = Invoker::new(&config, &resolver);
+		let transactions = vec![call(CONTRACT), call(CONTRACT), call(CONTRACT)];
+
+		let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+		let outcome = execute_block(
+			&invoker,
+			transactions.clone(),
+			&[],
+			&[],
+			None,
+			None,
+			&mut backend,
+		)
+		.unwrap();
+
+		for (index, expected) in outcome.values.iter().enumerate() {
+			let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+			let value = replay_transaction(
+				&invoker,
+				&invoker,
+				transactions.clone(),
+				index,
+				None,
+				None,
+				&mut backend,
+			)
+			.unwrap();
+
+			assert!(value.exit.is_ok());
+			assert_eq!(value.retval, expected.retval);
+			assert_eq!(value.used_gas, expected.used_gas);
+			assert_eq!(U256::from_big_endian(&value.retval), U256::from(index + 1));
+		}
+
+		let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+		assert!(matches!(
+			replay_transaction(
+				&invoker,
+				&invoker,
+				transactions,
+				3,
+				None,
+				None,
+				&mut backend
+			),
+			Err(BlockError::MissingTransaction(3))
+		));
+	}
+}
@@ -0,0 +1,120 @@
+use evm_interpreter::runtime::Log;
+use sha3::{Digest, Keccak256};
+
+/// Size of a logs bloom, in bytes.
+pub const BLOOM_SIZE: usize = 256;
+
+/// 2048-bit logs bloom filter, as in the block header and transaction
+/// receipts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub struct Bloom(pub [u8; BLOOM_SIZE]);
+
+impl Default for Bloom {
+	fn default() -> Self {
+		Self([0; BLOOM_SIZE])
+	}
+}
+
+impl Bloom {
+	/// Compute the bloom of the given logs.
+	pub fn from_logs<'a, I: IntoIterator<Item = &'a Log>>(logs: I) -> Self {
+		let mut bloom = Self::default();
+		for log in logs {
+			bloom.accrue_log(log);
+		}
+		bloom
+	}
+
+	/// Add the address and all topics of a log into the bloom.
+	pub fn accrue_log(&mut self, log: &Log) {
+		self.accrue(log.address.as_bytes());
+		for topic in &log.topics {
+			self.accrue(topic.as_bytes());
+		}
+	}
+
+	/// Add raw input into the bloom.
+	pub fn accrue(&mut self, input: &[u8]) {
+		for (index, bit) in Self::bits(input) {
+			self.0[index] |= bit;
+		}
+	}
+
+	/// Whether the raw input may be in the bloom.
+	pub fn contains_input(&self, input: &[u8]) -> bool {
+		Self::bits(input).all(|(index, bit)| self.0[index] & bit == bit)
+	}
+
+	/// Combine another bloom into this one.
+	pub fn accrue_bloom(&mut self, other: &Bloom) {
+		for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+			*a |= *b;
+		}
+	}
+
+	/// Byte representation of the bloom.
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.0
+	}
+
+	/// Byte index and bit mask for each of the three bits set by the input.
+	fn bits(input: &[u8]) -> impl Iterator<Item = (usize, u8)> {
+		let hash = Keccak256::digest(input);
+		(0..3).map(move |i| {
+			let bit = (((hash[2 * i] as usize) << 8) | hash[2 * i + 1] as usize) % (BLOOM_SIZE * 8);
+			(BLOOM_SIZE - 1 - bit / 8, 1 << (bit % 8))
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::vec::Vec;
+
+	use evm_interpreter::runtime::Log;
+	use primitive_types::{H160, H256};
+
+	use super::Bloom;
+
+	#[test]
+	fn bloom_contains_the_address_and_topics_of_logs() {
+		let log = Log {
+			address: H160::repeat_byte(1),
+			topics: vec![H256::repeat_byte(2), H256::repeat_byte(3)],
+			data: vec![4],
+		};
+		let bloom = Bloom::from_logs(&[log]);
+
+		assert!(bloom.contains_input(H160::repeat_byte(1).as_bytes()));
+		assert!(bloom.contains_input(H256::repeat_byte(2).as_bytes()));
+		assert!(bloom.contains_input(H256::repeat_byte(3).as_bytes()));
+		assert!(!bloom.contains_input(H160::repeat_byte(5).as_bytes()));
+		// Log data is not in the bloom.
+		assert!(!bloom.contains_input(&[4]));
+		// Each input sets at most three bits.
+		assert!(bloom.0.iter().map(|b| b.count_ones()).sum::<u32>() <= 9);
+
+		assert_eq!(Bloom::from_logs(&Vec::new()), Bloom::default());
+	}
+
+	#[test]
+	fn accrued_blooms_are_the_union() {
+		let mut bloom = Bloom::default();
+		bloom.accrue(b"a");
+		let mut other = Bloom::default();
+		other.accrue(b"b");
+
+		bloom.accrue_bloom(&other);
+		assert!(bloom.contains_input(b"a"));
+		assert!(bloom.contains_input(b"b"));
+
+		let mut expected = Bloom::default();
+		expected.accrue(b"b");
+		expected.accrue(b"a");
+		assert_eq!(bloom, expected);
+	}
+}
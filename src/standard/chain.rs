@@ -0,0 +1,189 @@
+use alloc::{string::String, vec::Vec};
+
+use evm_interpreter::runtime::RuntimeEnvironment;
+use primitive_types::U256;
+
+use crate::standard::{Config, Fork};
+
+/// Activation point of a fork.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Activation {
+	/// Active from the block number.
+	Block(U256),
+	/// Active from the block timestamp. Forks activated by timestamp always
+	/// come after the forks activated by block number, as after the Merge.
+	Timestamp(U256),
+}
+
+impl Activation {
+	/// Whether the fork is active in the block.
+	pub fn is_active(&self, number: U256, timestamp: U256) -> bool {
+		match self {
+			Self::Block(block) => number >= *block,
+			Self::Timestamp(time) => timestamp >= *time,
+		}
+	}
+}
+
+/// A fork of a [ChainSpec]: its name, activation point and config.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForkBoundary {
+	pub name: String,
+	pub activation: Activation,
+	pub config: Config,
+}
+
+/// Schedule of the forks of a chain, to select the [Config] of each block.
+///
+/// A node replaying history creates a standard invoker per block with the
+/// config of [ChainSpec::config_at], as the standard invoker and gasometer
+/// read the config at runtime.
+///
+/// A deserialized chain spec is built with [ChainSpec::with_fork], so the forks
+/// can be listed in any order.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "UnorderedChainSpec"))]
+pub struct ChainSpec {
+	forks: Vec<ForkBoundary>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UnorderedChainSpec {
+	forks: Vec<ForkBoundary>,
+}
+
+#[cfg(feature = "serde")]
+impl From<UnorderedChainSpec> for ChainSpec {
+	fn from(spec: UnorderedChainSpec) -> Self {
+		spec.forks.into_iter().fold(Self::new(), |spec, fork| {
+			spec.with_fork(fork.name, fork.activation, fork.config)
+		})
+	}
+}
+
+impl ChainSpec {
+	/// Create a chain spec without forks.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a fork with a custom config. Forks are kept in activation order,
+	/// whatever the order they are added in. A fork at the same activation
+	/// point as an existing fork replaces it.
+	#[must_use]
+	pub fn with_fork(
+		mut self,
+		name: impl Into<String>,
+		activation: Activation,
+		config: Config,
+	) -> Self {
+		let fork = ForkBoundary {
+			name: name.into(),
+			activation,
+			config,
+		};
+
+		match self
+			.forks
+			.binary_search_by(|other| other.activation.cmp(&activation))
+		{
+			Ok(index) => self.forks[index] = fork,
+			Err(index) => self.forks.insert(index, fork),
+		}
+		self
+	}
+
+	/// Add a fork with the preset config of [Fork::config].
+	#[must_use]
+	pub fn with_preset(self, fork: Fork, activation: Activation) -> Self {
+		self.with_fork(fork.name(), activation, fork.config().clone())
+	}
+
+	/// Forks, in activation order.
+	pub fn forks(&self) -> &[ForkBoundary] {
+		&self.forks
+	}
+
+	/// Fork by name.
+	pub fn fork(&self, name: &str) -> Option<&ForkBoundary> {
+		self.forks.iter().find(|fork| fork.name == name)
+	}
+
+	/// The fork active in the block. `None` if the block is before the
+	/// first fork.
+	pub fn fork_at(&self, number: U256, timestamp: U256) -> Option<&ForkBoundary> {
+		self.forks
+			.iter()
+			.rev()
+			.find(|fork| fork.activation.is_active(number, timestamp))
+	}
+
+	/// Config of the fork active in the block.
+	pub fn config_at(&self, number: U256, timestamp: U256) -> Option<&Config> {
+		self.fork_at(number, timestamp).map(|fork| &fork.config)
+	}
+
+	/// Config of the fork active in the block of the environment.
+	pub fn config_for<E: RuntimeEnvironment>(&self, environment: &E) -> Option<&Config> {
+		self.config_at(environment.block_number(), environment.block_timestamp())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use primitive_types::U256;
+
+	use super::{Activation, ChainSpec};
+	use crate::standard::Fork;
+
+	fn names(spec: &ChainSpec) -> alloc::vec::Vec<&str> {
+		spec.forks().iter().map(|fork| fork.name.as_str()).collect()
+	}
+
+	#[test]
+	fn forks_are_kept_in_activation_order() {
+		let spec = ChainSpec::new()
+			.with_preset(Fork::Cancun, Activation::Timestamp(U256::from(100)))
+			.with_preset(Fork::London, Activation::Block(U256::from(10)))
+			.with_preset(Fork::Berlin, Activation::Block(U256::zero()))
+			.with_preset(Fork::Shanghai, Activation::Timestamp(U256::from(50)));
+		assert_eq!(names(&spec), ["berlin", "london", "shanghai", "cancun"]);
+
+		assert_eq!(
+			spec.fork_at(U256::from(5), U256::zero())
+				.map(|fork| fork.name.as_str()),
+			Some("berlin")
+		);
+		assert_eq!(
+			spec.fork_at(U256::from(20), U256::from(60))
+				.map(|fork| fork.name.as_str()),
+			Some("shanghai")
+		);
+
+		let spec = spec.with_preset(Fork::Prague, Activation::Timestamp(U256::from(50)));
+		assert_eq!(names(&spec), ["berlin", "london", "prague", "cancun"]);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn deserialized_forks_are_sorted() {
+		let spec = ChainSpec::new()
+			.with_preset(Fork::Berlin, Activation::Block(U256::zero()))
+			.with_preset(Fork::Cancun, Activation::Timestamp(U256::from(100)));
+		let mut json = serde_json::to_value(spec).unwrap();
+		json["forks"].as_array_mut().unwrap().reverse();
+		assert_eq!(json["forks"][0]["name"], "cancun");
+
+		let spec: ChainSpec = serde_json::from_value(json).unwrap();
+		assert_eq!(names(&spec), ["berlin", "cancun"]);
+		assert_eq!(
+			spec.fork_at(U256::from(1), U256::from(200))
+				.map(|fork| fork.name.as_str()),
+			Some("cancun")
+		);
+	}
+}
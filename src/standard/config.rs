@@ -1,5 +1,127 @@
+use alloc::borrow::Cow;
+use core::fmt;
+
+use evm_interpreter::{opcode::Opcode, runtime::RuntimeEnvironment};
+use primitive_types::{H160, U256};
+
+use crate::standard::{blob::BlobParams, DEPOSIT_CONTRACT_ADDRESS};
+
+/// Code prefix banned by EIP-3541.
+pub const EIP_3541_BANNED_CODE_PREFIXES: &[Cow<'static, [u8]>] = &[Cow::Borrowed(&[0xef])];
+
+/// Destination of the base fee paid by transactions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BaseFeeSink {
+	/// The base fee is burned, as on Ethereum.
+	Burn,
+	/// The base fee is deposited to the address, for example a treasury.
+	Address(H160),
+}
+
+/// Hard fork with a preset [Config].
+///
+/// The standard invoker and gasometer read the config at runtime, so a node
+/// can select the fork of each block with [Fork::config], with a single
+/// invoker type for all forks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fork {
+	/// Frontier, see [Config::frontier].
+	Frontier,
+	/// Istanbul, see [Config::istanbul].
+	Istanbul,
+	/// Berlin, see [Config::berlin].
+	Berlin,
+	/// London, see [Config::london].
+	London,
+	/// The Merge (Paris), see [Config::merge].
+	Merge,
+	/// Shanghai, see [Config::shanghai].
+	Shanghai,
+	/// Cancun, see [Config::cancun].
+	Cancun,
+	/// Prague, see [Config::prague].
+	Prague,
+}
+
+static FRONTIER: Config = Config::frontier();
+static ISTANBUL: Config = Config::istanbul();
+static BERLIN: Config = Config::berlin();
+static LONDON: Config = Config::london();
+static MERGE: Config = Config::merge();
+static SHANGHAI: Config = Config::shanghai();
+static CANCUN: Config = Config::cancun();
+static PRAGUE: Config = Config::prague();
+
+impl Fork {
+	/// All forks, in activation order.
+	pub const ALL: [Fork; 8] = [
+		Fork::Frontier,
+		Fork::Istanbul,
+		Fork::Berlin,
+		Fork::London,
+		Fork::Merge,
+		Fork::Shanghai,
+		Fork::Cancun,
+		Fork::Prague,
+	];
+
+	/// Preset config of the fork.
+	pub fn config(self) -> &'static Config {
+		match self {
+			Self::Frontier => &FRONTIER,
+			Self::Istanbul => &ISTANBUL,
+			Self::Berlin => &BERLIN,
+			Self::London => &LONDON,
+			Self::Merge => &MERGE,
+			Self::Shanghai => &SHANGHAI,
+			Self::Cancun => &CANCUN,
+			Self::Prague => &PRAGUE,
+		}
+	}
+
+	/// Blob gas market parameters of the fork. `None` before Cancun.
+	pub fn blob_params(self) -> Option<BlobParams> {
+		match self {
+			Self::Cancun => Some(BlobParams::cancun()),
+			Self::Prague => Some(BlobParams::prague()),
+			_ => None,
+		}
+	}
+
+	/// Name of the fork.
+	pub const fn name(self) -> &'static str {
+		match self {
+			Self::Frontier => "frontier",
+			Self::Istanbul => "istanbul",
+			Self::Berlin => "berlin",
+			Self::London => "london",
+			Self::Merge => "merge",
+			Self::Shanghai => "shanghai",
+			Self::Cancun => "cancun",
+			Self::Prague => "prague",
+		}
+	}
+}
+
+impl fmt::Display for Fork {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
 /// Runtime configuration.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Config {
 	/// Gas paid for extcode.
 	pub gas_ext_code: u64,
@@ -51,8 +173,9 @@ pub struct Config {
 	pub increase_state_access_gas: bool,
 	/// EIP-3529
 	pub decrease_clears_refund: bool,
-	/// EIP-3541
-	pub disallow_executable_format: bool,
+	/// Code prefixes rejected when deploying a contract. EIP-3541 rejects
+	/// code starting with `0xEF`. Empty prefixes are ignored.
+	pub banned_code_prefixes: Cow<'static, [Cow<'static, [u8]>]>,
 	/// EIP-3651
 	pub warm_coinbase_address: bool,
 	/// Whether to throw out of gas error when
@@ -65,6 +188,8 @@ pub struct Config {
 	pub empty_considered_exists: bool,
 	/// Whether create transactions and create opcode increases nonce by one.
 	pub create_increase_nonce: bool,
+	/// Fail creates at an address that already has code or a non-zero nonce. See [EIP-684](https://github.com/ethereum/EIPs/issues/684)
+	pub eip_684_enabled: bool,
 	/// Stack limit.
 	pub stack_limit: usize,
 	/// Memory limit.
@@ -97,12 +222,64 @@ pub struct Config {
 	pub has_base_fee: bool,
 	/// Has PUSH0 opcode. See [EIP-3855](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3855.md)
 	pub has_push0: bool,
+	/// `DIFFICULTY` is `PREVRANDAO`, the randomness of the beacon chain, and
+	/// not the block difficulty. See [EIP-4399](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4399.md)
+	pub has_prevrandao: bool,
 	/// Enables transient storage. See [EIP-1153](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-1153.md)
 	pub eip_1153_enabled: bool,
 	/// Enables MCOPY instruction. See [EIP-5656](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-5656.md)
 	pub eip_5656_enabled: bool,
 	/// Uses EIP-1559 (Base fee is burned when this flag is enabled) [EIP-1559](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-1559.md)
 	pub eip_1559_enabled: bool,
+	/// Stores the parent beacon block root in the beacon roots contract at the start of each block. See [EIP-4788](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4788.md)
+	pub eip_4788_enabled: bool,
+	/// Stores historical block hashes in the history storage contract at the start of each block. See [EIP-2935](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-2935.md)
+	pub eip_2935_enabled: bool,
+	/// Rejects transactions from senders with deployed code, except for EIP-7702 delegation designators. See [EIP-3607](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3607.md)
+	pub eip_3607_enabled: bool,
+	/// Enables the `DUPN`, `SWAPN` and `EXCHANGE` opcodes in legacy code. This is not part of any hard fork. See [EIP-663](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-663.md)
+	pub eip_663_enabled: bool,
+	/// Enables the `AUTH` and `AUTHCALL` opcodes. This is not part of any hard fork. See [EIP-3074](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3074.md)
+	pub eip_3074_enabled: bool,
+	/// Charges at least a floor cost per calldata token at the end of a transaction. See [EIP-7623](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7623.md)
+	pub eip_7623_enabled: bool,
+	/// Charges state accesses by the stems and leaves of the state tree they
+	/// touch, instead of the cold access costs. This is not part of any hard
+	/// fork yet. See [EIP-4762](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4762.md)
+	pub eip_4762_enabled: bool,
+	/// Collects the execution layer requests of a block: deposits, withdrawal
+	/// requests and consolidation requests. See [EIP-7685](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7685.md)
+	pub eip_7685_enabled: bool,
+	/// Prices the `MODEXP` precompile with the repriced formula. See [EIP-2565](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-2565.md)
+	pub eip_2565_enabled: bool,
+	/// Maximum length of each of the base, exponent and modulus of the
+	/// `MODEXP` precompile, in bytes. Longer inputs fail. `None` for no limit.
+	pub modexp_max_len: Option<u64>,
+	/// Enables the `P256VERIFY` precompile at `0x100`, verifying secp256r1
+	/// signatures, of the precompile set of the `evm-precompile` crate with
+	/// its `secp256r1` feature. It is not part of any Ethereum fork, but of
+	/// rollups. See [RIP-7212](https://github.com/ethereum/RIPs/blob/master/RIPS/rip-7212.md)
+	pub rip_7212_enabled: bool,
+	/// Block reward paid to the coinbase. `None` after the merge.
+	pub block_reward: Option<U256>,
+	/// Destination of the base fee paid by transactions. Only used with
+	/// EIP-1559.
+	pub base_fee_sink: BaseFeeSink,
+	/// Address of the deposit contract, whose deposit logs are deposit requests
+	/// (see EIP-6110).
+	pub deposit_contract_address: H160,
+	/// Gas cost overrides of opcodes. An override replaces the static or
+	/// dynamic cost of the opcode, while memory expansion and refunds are still
+	/// charged as usual. The opcode is checked as without the override, so it
+	/// stays invalid where the fork or a static call does not allow it.
+	pub opcode_gas_overrides: Cow<'static, [(Opcode, u64)]>,
+	/// Gas cost overrides of precompiles, in the format of (address, base cost,
+	/// cost per word). An override replaces the cost charged by the precompile.
+	pub precompile_gas_overrides: Cow<'static, [(H160, u64, u64)]>,
+	/// Chain ID, served to `CHAINID` and `AUTH`, and checked against the chain
+	/// ID of transactions. If unset, the chain ID of the backend is used
+	/// instead, which defaults to 1.
+	pub chain_id: Option<U256>,
 }
 
 impl Config {
@@ -134,11 +311,12 @@ impl Config {
 			sstore_revert_under_stipend: false,
 			increase_state_access_gas: false,
 			decrease_clears_refund: false,
-			disallow_executable_format: false,
+			banned_code_prefixes: Cow::Borrowed(&[]),
 			warm_coinbase_address: false,
 			err_on_call_with_more_gas: true,
 			empty_considered_exists: true,
 			create_increase_nonce: false,
+			eip_684_enabled: true,
 			call_l64_after_gas: false,
 			stack_limit: 1024,
 			memory_limit: usize::MAX,
@@ -156,9 +334,27 @@ impl Config {
 			has_ext_code_hash: false,
 			has_base_fee: false,
 			has_push0: false,
+			has_prevrandao: false,
 			eip_1153_enabled: false,
 			eip_5656_enabled: false,
 			eip_1559_enabled: false,
+			eip_4788_enabled: false,
+			eip_2935_enabled: false,
+			eip_3607_enabled: false,
+			eip_663_enabled: false,
+			eip_3074_enabled: false,
+			eip_7623_enabled: false,
+			eip_7685_enabled: false,
+			eip_4762_enabled: false,
+			eip_2565_enabled: false,
+			modexp_max_len: None,
+			rip_7212_enabled: false,
+			block_reward: Some(U256([5_000_000_000_000_000_000, 0, 0, 0])),
+			base_fee_sink: BaseFeeSink::Burn,
+			deposit_contract_address: DEPOSIT_CONTRACT_ADDRESS,
+			opcode_gas_overrides: Cow::Borrowed(&[]),
+			precompile_gas_overrides: Cow::Borrowed(&[]),
+			chain_id: None,
 		}
 	}
 
@@ -190,11 +386,12 @@ impl Config {
 			sstore_revert_under_stipend: true,
 			increase_state_access_gas: false,
 			decrease_clears_refund: false,
-			disallow_executable_format: false,
+			banned_code_prefixes: Cow::Borrowed(&[]),
 			warm_coinbase_address: false,
 			err_on_call_with_more_gas: false,
 			empty_considered_exists: false,
 			create_increase_nonce: true,
+			eip_684_enabled: true,
 			call_l64_after_gas: true,
 			stack_limit: 1024,
 			memory_limit: usize::MAX,
@@ -212,9 +409,27 @@ impl Config {
 			has_ext_code_hash: true,
 			has_base_fee: false,
 			has_push0: false,
+			has_prevrandao: false,
 			eip_1153_enabled: false,
 			eip_5656_enabled: false,
 			eip_1559_enabled: false,
+			eip_4788_enabled: false,
+			eip_2935_enabled: false,
+			eip_3607_enabled: false,
+			eip_663_enabled: false,
+			eip_3074_enabled: false,
+			eip_7623_enabled: false,
+			eip_7685_enabled: false,
+			eip_4762_enabled: false,
+			eip_2565_enabled: false,
+			modexp_max_len: None,
+			rip_7212_enabled: false,
+			block_reward: Some(U256([2_000_000_000_000_000_000, 0, 0, 0])),
+			base_fee_sink: BaseFeeSink::Burn,
+			deposit_contract_address: DEPOSIT_CONTRACT_ADDRESS,
+			opcode_gas_overrides: Cow::Borrowed(&[]),
+			precompile_gas_overrides: Cow::Borrowed(&[]),
+			chain_id: None,
 		}
 	}
 
@@ -243,6 +458,34 @@ impl Config {
 		Self::config_with_derived_values(DerivedConfigInputs::cancun())
 	}
 
+	/// Prague hard fork configuration.
+	pub const fn prague() -> Config {
+		Self::config_with_derived_values(DerivedConfigInputs::prague())
+	}
+
+	/// Gas cost override of the opcode, if any.
+	pub fn opcode_gas_override(&self, opcode: Opcode) -> Option<u64> {
+		self.opcode_gas_overrides
+			.iter()
+			.find(|(overridden, _)| *overridden == opcode)
+			.map(|(_, cost)| *cost)
+	}
+
+	/// Gas cost override of the precompile, if any, as (base cost, cost per
+	/// word).
+	pub fn precompile_gas_override(&self, address: H160) -> Option<(u64, u64)> {
+		self.precompile_gas_overrides
+			.iter()
+			.find(|(overridden, _, _)| *overridden == address)
+			.map(|(_, base, word)| (*base, *word))
+	}
+
+	/// Chain ID of the execution, [Config::chain_id] if set, or the chain ID of
+	/// the backend.
+	pub fn effective_chain_id<H: RuntimeEnvironment>(&self, handler: &H) -> U256 {
+		self.chain_id.unwrap_or_else(|| handler.chain_id())
+	}
+
 	const fn config_with_derived_values(inputs: DerivedConfigInputs) -> Config {
 		let DerivedConfigInputs {
 			gas_storage_read_warm,
@@ -251,12 +494,18 @@ impl Config {
 			decrease_clears_refund,
 			has_base_fee,
 			has_push0,
+			has_prevrandao,
 			disallow_executable_format,
 			warm_coinbase_address,
 			max_initcode_size,
 			eip_1153_enabled,
 			eip_5656_enabled,
 			eip_1559_enabled,
+			eip_4788_enabled,
+			eip_2935_enabled,
+			eip_7623_enabled,
+			eip_7685_enabled,
+			block_reward,
 		} = inputs;
 
 		// See https://eips.ethereum.org/EIPS/eip-2929
@@ -297,11 +546,16 @@ impl Config {
 			sstore_revert_under_stipend: true,
 			increase_state_access_gas: true,
 			decrease_clears_refund,
-			disallow_executable_format,
+			banned_code_prefixes: Cow::Borrowed(if disallow_executable_format {
+				EIP_3541_BANNED_CODE_PREFIXES
+			} else {
+				&[]
+			}),
 			warm_coinbase_address,
 			err_on_call_with_more_gas: false,
 			empty_considered_exists: false,
 			create_increase_nonce: true,
+			eip_684_enabled: true,
 			call_l64_after_gas: true,
 			stack_limit: 1024,
 			memory_limit: usize::MAX,
@@ -319,9 +573,27 @@ impl Config {
 			has_ext_code_hash: true,
 			has_base_fee,
 			has_push0,
+			has_prevrandao,
 			eip_1153_enabled,
 			eip_5656_enabled,
 			eip_1559_enabled,
+			eip_4788_enabled,
+			eip_2935_enabled,
+			eip_3607_enabled: true,
+			eip_663_enabled: false,
+			eip_3074_enabled: false,
+			eip_7623_enabled,
+			eip_7685_enabled,
+			eip_4762_enabled: false,
+			eip_2565_enabled: true,
+			modexp_max_len: None,
+			rip_7212_enabled: false,
+			block_reward,
+			base_fee_sink: BaseFeeSink::Burn,
+			deposit_contract_address: DEPOSIT_CONTRACT_ADDRESS,
+			opcode_gas_overrides: Cow::Borrowed(&[]),
+			precompile_gas_overrides: Cow::Borrowed(&[]),
+			chain_id: None,
 		}
 	}
 }
@@ -338,12 +610,18 @@ struct DerivedConfigInputs {
 	decrease_clears_refund: bool,
 	has_base_fee: bool,
 	has_push0: bool,
+	has_prevrandao: bool,
 	disallow_executable_format: bool,
 	warm_coinbase_address: bool,
 	max_initcode_size: Option<usize>,
 	eip_1153_enabled: bool,
 	eip_5656_enabled: bool,
 	eip_1559_enabled: bool,
+	eip_4788_enabled: bool,
+	eip_2935_enabled: bool,
+	eip_7623_enabled: bool,
+	eip_7685_enabled: bool,
+	block_reward: Option<U256>,
 }
 
 impl DerivedConfigInputs {
@@ -355,12 +633,18 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: false,
 			has_base_fee: false,
 			has_push0: false,
+			has_prevrandao: false,
 			disallow_executable_format: false,
 			warm_coinbase_address: false,
 			max_initcode_size: None,
 			eip_1153_enabled: false,
 			eip_5656_enabled: false,
 			eip_1559_enabled: false,
+			eip_4788_enabled: false,
+			eip_2935_enabled: false,
+			eip_7623_enabled: false,
+			eip_7685_enabled: false,
+			block_reward: Some(U256([2_000_000_000_000_000_000, 0, 0, 0])),
 		}
 	}
 
@@ -372,12 +656,18 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: true,
 			has_base_fee: true,
 			has_push0: false,
+			has_prevrandao: false,
 			disallow_executable_format: true,
 			warm_coinbase_address: false,
 			max_initcode_size: None,
 			eip_1153_enabled: false,
 			eip_5656_enabled: false,
 			eip_1559_enabled: true,
+			eip_4788_enabled: false,
+			eip_2935_enabled: false,
+			eip_7623_enabled: false,
+			eip_7685_enabled: false,
+			block_reward: Some(U256([2_000_000_000_000_000_000, 0, 0, 0])),
 		}
 	}
 
@@ -389,12 +679,18 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: true,
 			has_base_fee: true,
 			has_push0: false,
+			has_prevrandao: true,
 			disallow_executable_format: true,
 			warm_coinbase_address: false,
 			max_initcode_size: None,
 			eip_1153_enabled: false,
 			eip_5656_enabled: false,
 			eip_1559_enabled: true,
+			eip_4788_enabled: false,
+			eip_2935_enabled: false,
+			eip_7623_enabled: false,
+			eip_7685_enabled: false,
+			block_reward: None,
 		}
 	}
 
@@ -406,6 +702,7 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: true,
 			has_base_fee: true,
 			has_push0: true,
+			has_prevrandao: true,
 			disallow_executable_format: true,
 			warm_coinbase_address: true,
 			// 2 * 24576 as per EIP-3860
@@ -413,6 +710,11 @@ impl DerivedConfigInputs {
 			eip_1153_enabled: false,
 			eip_5656_enabled: false,
 			eip_1559_enabled: true,
+			eip_4788_enabled: false,
+			eip_2935_enabled: false,
+			eip_7623_enabled: false,
+			eip_7685_enabled: false,
+			block_reward: None,
 		}
 	}
 
@@ -424,6 +726,7 @@ impl DerivedConfigInputs {
 			decrease_clears_refund: true,
 			has_base_fee: true,
 			has_push0: true,
+			has_prevrandao: true,
 			disallow_executable_format: true,
 			warm_coinbase_address: true,
 			// 2 * (MAX_CODE_SIZE = `24576`) = (0xC000 = 49152) as per EIP-3860
@@ -431,6 +734,206 @@ impl DerivedConfigInputs {
 			eip_1153_enabled: true,
 			eip_5656_enabled: true,
 			eip_1559_enabled: true,
+			eip_4788_enabled: true,
+			eip_2935_enabled: false,
+			eip_7623_enabled: false,
+			eip_7685_enabled: false,
+			block_reward: None,
 		}
 	}
+
+	const fn prague() -> Self {
+		Self {
+			gas_storage_read_warm: 100,
+			gas_sload_cold: 2100,
+			gas_access_list_storage_key: 1900,
+			decrease_clears_refund: true,
+			has_base_fee: true,
+			has_push0: true,
+			has_prevrandao: true,
+			disallow_executable_format: true,
+			warm_coinbase_address: true,
+			// 2 * (MAX_CODE_SIZE = `24576`) = (0xC000 = 49152) as per EIP-3860
+			max_initcode_size: Some(0xC000),
+			eip_1153_enabled: true,
+			eip_5656_enabled: true,
+			eip_1559_enabled: true,
+			eip_4788_enabled: true,
+			eip_2935_enabled: true,
+			eip_7623_enabled: true,
+			eip_7685_enabled: true,
+			block_reward: None,
+		}
+	}
+}
+
+/// Builder for [Config], starting from a hard fork preset and overriding
+/// individual values, for example
+/// `ConfigBuilder::new(Config::cancun()).gas_sstore_set(10000).build()`.
+#[derive(Clone, Debug)]
+pub struct ConfigBuilder {
+	config: Config,
+}
+
+impl ConfigBuilder {
+	/// Start building from the given preset.
+	pub const fn new(base: Config) -> Self {
+		Self { config: base }
+	}
+
+	/// Finish building the config.
+	pub fn build(self) -> Config {
+		self.config
+	}
+}
+
+impl From<Config> for ConfigBuilder {
+	fn from(base: Config) -> Self {
+		Self::new(base)
+	}
+}
+
+macro_rules! config_builder_setters {
+	( $( $field:ident: $ty:ty, )* ) => {
+		impl ConfigBuilder {
+			$(
+				#[doc = concat!("Override [Config::", stringify!($field), "].")]
+				pub const fn $field(mut self, value: $ty) -> Self {
+					self.config.$field = value;
+					self
+				}
+			)*
+		}
+	};
+}
+
+macro_rules! config_builder_list_setters {
+	( $( $field:ident: $item:ty, )* ) => {
+		impl ConfigBuilder {
+			$(
+				#[doc = concat!("Override [Config::", stringify!($field), "], with a static or owned list.")]
+				pub fn $field(mut self, value: impl Into<Cow<'static, [$item]>>) -> Self {
+					self.config.$field = value.into();
+					self
+				}
+			)*
+		}
+	};
+}
+
+config_builder_setters! {
+	gas_ext_code: u64,
+	gas_ext_code_hash: u64,
+	gas_sstore_set: u64,
+	gas_sstore_reset: u64,
+	refund_sstore_clears: i64,
+	max_refund_quotient: u64,
+	gas_balance: u64,
+	gas_sload: u64,
+	gas_sload_cold: u64,
+	gas_suicide: u64,
+	gas_suicide_new_account: u64,
+	gas_call: u64,
+	gas_expbyte: u64,
+	gas_transaction_create: u64,
+	gas_transaction_call: u64,
+	gas_transaction_zero_data: u64,
+	gas_transaction_non_zero_data: u64,
+	gas_access_list_address: u64,
+	gas_access_list_storage_key: u64,
+	gas_account_access_cold: u64,
+	gas_storage_read_warm: u64,
+	sstore_gas_metering: bool,
+	sstore_revert_under_stipend: bool,
+	increase_state_access_gas: bool,
+	decrease_clears_refund: bool,
+	warm_coinbase_address: bool,
+	err_on_call_with_more_gas: bool,
+	call_l64_after_gas: bool,
+	empty_considered_exists: bool,
+	create_increase_nonce: bool,
+	eip_684_enabled: bool,
+	stack_limit: usize,
+	memory_limit: usize,
+	call_stack_limit: usize,
+	create_contract_limit: Option<usize>,
+	max_initcode_size: Option<usize>,
+	call_stipend: u64,
+	has_delegate_call: bool,
+	has_create2: bool,
+	has_revert: bool,
+	has_return_data: bool,
+	has_bitwise_shifting: bool,
+	has_chain_id: bool,
+	has_self_balance: bool,
+	has_ext_code_hash: bool,
+	has_base_fee: bool,
+	has_push0: bool,
+	has_prevrandao: bool,
+	eip_1153_enabled: bool,
+	eip_5656_enabled: bool,
+	eip_1559_enabled: bool,
+	eip_4788_enabled: bool,
+	eip_2935_enabled: bool,
+	eip_3607_enabled: bool,
+	eip_663_enabled: bool,
+	eip_3074_enabled: bool,
+	eip_7623_enabled: bool,
+	eip_7685_enabled: bool,
+	eip_4762_enabled: bool,
+	eip_2565_enabled: bool,
+	modexp_max_len: Option<u64>,
+	rip_7212_enabled: bool,
+	block_reward: Option<U256>,
+	base_fee_sink: BaseFeeSink,
+	deposit_contract_address: H160,
+	chain_id: Option<U256>,
+}
+
+config_builder_list_setters! {
+	banned_code_prefixes: Cow<'static, [u8]>,
+	opcode_gas_overrides: (Opcode, u64),
+	precompile_gas_overrides: (H160, u64, u64),
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{borrow::Cow, vec};
+
+	use evm_interpreter::opcode::Opcode;
+	use primitive_types::H160;
+
+	use super::{Config, ConfigBuilder, EIP_3541_BANNED_CODE_PREFIXES};
+
+	#[test]
+	fn builder_takes_owned_lists() {
+		let config = ConfigBuilder::new(Config::cancun())
+			.banned_code_prefixes(vec![Cow::Owned(vec![0xef, 0x00])])
+			.opcode_gas_overrides(vec![(Opcode::SSTORE, 100)])
+			.precompile_gas_overrides(&[(H160([1; 20]), 10, 1)][..])
+			.build();
+
+		assert_eq!(&*config.banned_code_prefixes, &[vec![0xef, 0x00]][..]);
+		assert_eq!(config.opcode_gas_override(Opcode::SSTORE), Some(100));
+		assert_eq!(config.opcode_gas_override(Opcode::SLOAD), None);
+		assert_eq!(config.precompile_gas_override(H160([1; 20])), Some((10, 1)));
+		assert_eq!(
+			&*Config::cancun().banned_code_prefixes,
+			EIP_3541_BANNED_CODE_PREFIXES
+		);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn lists_deserialize_into_owned_storage() {
+		let config = ConfigBuilder::new(Config::cancun())
+			.opcode_gas_overrides(vec![(Opcode::SSTORE, 100)])
+			.build();
+		let json = serde_json::to_string(&config).unwrap();
+		let config: Config = serde_json::from_str(&json).unwrap();
+
+		assert!(matches!(config.banned_code_prefixes, Cow::Owned(_)));
+		assert_eq!(&*config.banned_code_prefixes, &[vec![0xef]][..]);
+		assert_eq!(config.opcode_gas_override(Opcode::SSTORE), Some(100));
+	}
 }
@@ -0,0 +1,85 @@
+use evm_interpreter::opcode::Opcode;
+
+/// Execution counters of a transaction, enabled by the `counters` feature.
+///
+/// Every opcode that reaches the gasometer is counted, including the ones
+/// that then fail, and counts of substacks are kept regardless of whether
+/// they are committed or reverted. Counts are not kept for precompiles.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub struct Counters {
+	opcodes: [u64; 256],
+}
+
+impl Default for Counters {
+	fn default() -> Self {
+		Self { opcodes: [0; 256] }
+	}
+}
+
+impl Counters {
+	/// Create empty counters.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Record one execution of the opcode.
+	#[inline]
+	pub fn record_opcode(&mut self, opcode: Opcode) {
+		self.opcodes[opcode.as_usize()] += 1;
+	}
+
+	/// Add the counts of a substack.
+	pub fn merge(&mut self, other: &Self) {
+		for (count, other) in self.opcodes.iter_mut().zip(other.opcodes.iter()) {
+			*count += other;
+		}
+	}
+
+	/// Number of executions of the opcode.
+	pub fn opcode(&self, opcode: Opcode) -> u64 {
+		self.opcodes[opcode.as_usize()]
+	}
+
+	/// Executed opcodes with non-zero counts, in opcode order.
+	pub fn opcodes(&self) -> impl Iterator<Item = (Opcode, u64)> + '_ {
+		self.opcodes
+			.iter()
+			.enumerate()
+			.filter(|(_, count)| **count != 0)
+			.map(|(opcode, count)| (Opcode(opcode as u8), *count))
+	}
+
+	/// Total number of executed opcodes.
+	pub fn total_opcodes(&self) -> u64 {
+		self.opcodes.iter().sum()
+	}
+
+	/// Number of `CALL`, `CALLCODE`, `DELEGATECALL`, `STATICCALL` and
+	/// `AUTHCALL` executions.
+	pub fn calls(&self) -> u64 {
+		self.opcode(Opcode::CALL)
+			+ self.opcode(Opcode::CALLCODE)
+			+ self.opcode(Opcode::DELEGATECALL)
+			+ self.opcode(Opcode::STATICCALL)
+			+ self.opcode(Opcode::AUTHCALL)
+	}
+
+	/// Number of `CREATE` and `CREATE2` executions.
+	pub fn creates(&self) -> u64 {
+		self.opcode(Opcode::CREATE) + self.opcode(Opcode::CREATE2)
+	}
+
+	/// Number of `SLOAD` executions.
+	pub fn sloads(&self) -> u64 {
+		self.opcode(Opcode::SLOAD)
+	}
+
+	/// Number of `SSTORE` executions.
+	pub fn sstores(&self) -> u64 {
+		self.opcode(Opcode::SSTORE)
+	}
+}
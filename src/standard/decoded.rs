@@ -0,0 +1,209 @@
+//! Fast path for decoded instructions, see [DecodedEtable].
+
+#[cfg(feature = "fusion")]
+use evm_interpreter::Superinstruction;
+use evm_interpreter::{
+	error::ExitException,
+	etable::{Control, EtableSet},
+	machine::{Machine, Stack},
+	opcode::Opcode,
+	Instruction,
+};
+
+use crate::standard::GasometerState;
+
+/// An etable set that evaluates the stack opcodes of decoded instructions
+/// (`PUSH1` to `PUSH32`, `DUP*`, `SWAP*`, `POP` and `JUMPDEST`) directly, with
+/// the decoded immediate data, static gas and stack inputs and outputs,
+/// instead of through the wrapped etable set. All other opcodes, and all
+/// opcodes if stateless gas (EIP-4762) is enabled, are evaluated by the
+/// wrapped etable set.
+///
+/// The wrapped etable set must be the standard gasometer and runtime etables,
+/// or behave as them for the stack opcodes. Wrap this in an inspector or
+/// tracer, not the other way around, so that they see every opcode.
+pub struct DecodedEtable<ES> {
+	etable: ES,
+}
+
+impl<ES> DecodedEtable<ES> {
+	pub fn new(etable: ES) -> Self {
+		Self { etable }
+	}
+}
+
+impl<'config, ES> EtableSet for DecodedEtable<ES>
+where
+	ES: EtableSet,
+	ES::State: AsRef<GasometerState<'config>> + AsMut<GasometerState<'config>>,
+{
+	type State = ES::State;
+	type Handle = ES::Handle;
+	type Trap = ES::Trap;
+
+	fn eval(
+		&self,
+		machine: &mut Machine<ES::State>,
+		handle: &mut ES::Handle,
+		opcode: Opcode,
+		position: usize,
+	) -> Control<ES::Trap> {
+		self.etable.eval(machine, handle, opcode, position)
+	}
+
+	fn eval_instruction(
+		&self,
+		machine: &mut Machine<ES::State>,
+		handle: &mut ES::Handle,
+		instruction: &Instruction,
+	) -> Control<ES::Trap> {
+		let static_gas = match instruction.static_gas {
+			Some(static_gas)
+				if is_stack_opcode(instruction.opcode)
+					&& !AsRef::<GasometerState>::as_ref(&machine.state)
+						.config
+						.eip_4762_enabled =>
+			{
+				static_gas
+			}
+			_ => return self.etable.eval_instruction(machine, handle, instruction),
+		};
+
+		#[cfg(feature = "counters")]
+		machine
+			.state
+			.as_mut()
+			.counters
+			.record_opcode(instruction.opcode);
+
+		if let Err(err) = machine
+			.state
+			.as_mut()
+			.perform(|gasometer| gasometer.record_gas64(static_gas))
+		{
+			return Control::Exit(Err(err));
+		}
+
+		match eval_stack(&mut machine.stack, instruction) {
+			Ok(()) if instruction.immediate_size > 0 => {
+				Control::ContinueN(1 + instruction.immediate_size)
+			}
+			Ok(()) => Control::Continue,
+			Err(err) => Control::Exit(err.into()),
+		}
+	}
+
+	#[cfg(feature = "fusion")]
+	fn eval_superinstruction(
+		&self,
+		machine: &mut Machine<ES::State>,
+		handle: &mut ES::Handle,
+		superinstruction: &Superinstruction,
+		position: usize,
+	) -> (usize, Control<ES::Trap>) {
+		self.etable
+			.eval_superinstruction(machine, handle, superinstruction, position)
+	}
+}
+
+fn is_stack_opcode(opcode: Opcode) -> bool {
+	(Opcode::PUSH1.0..=Opcode::SWAP16.0).contains(&opcode.0)
+		|| opcode == Opcode::POP
+		|| opcode == Opcode::JUMPDEST
+}
+
+/// Apply a stack opcode to the stack, after checking the stack bounds with
+/// its inputs and outputs.
+fn eval_stack(stack: &mut Stack, instruction: &Instruction) -> Result<(), ExitException> {
+	let opcode = instruction.opcode;
+	if let Some((inputs, outputs)) = instruction.stack_io {
+		if stack.len() < inputs as usize {
+			return Err(ExitException::StackUnderflow);
+		}
+		if stack.len() - inputs as usize + outputs as usize > stack.limit() {
+			return Err(ExitException::StackOverflow);
+		}
+	}
+
+	if opcode.is_push().is_some() {
+		stack.push(instruction.immediate)
+	} else if (Opcode::DUP1.0..=Opcode::DUP16.0).contains(&opcode.0) {
+		let value = stack.peek((opcode.0 - Opcode::DUP1.0) as usize)?;
+		stack.push(value)
+	} else if (Opcode::SWAP1.0..=Opcode::SWAP16.0).contains(&opcode.0) {
+		let n = (opcode.0 - Opcode::SWAP1.0) as usize + 1;
+		let top = stack.peek(0)?;
+		let value = stack.peek(n)?;
+		stack.set(0, value)?;
+		stack.set(n, top)
+	} else if opcode == Opcode::POP {
+		stack.pop().map(|_| ())
+	} else {
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{collections::BTreeSet, vec, vec::Vec};
+	use core::cell::RefCell;
+
+	use evm_interpreter::{runtime::RuntimeBaseBackend, DecodeCache};
+	use primitive_types::{H256, U256};
+
+	use super::DecodedEtable;
+	use crate::{
+		backend::{OverlayedBackend, TestBackend},
+		standard::{
+			testing::{backend, call_args, etable, CONTRACT},
+			Config, EtableResolver, Invoker,
+		},
+	};
+
+	fn call(base: &TestBackend, cache: Option<&RefCell<DecodeCache>>) -> (Vec<u8>, U256) {
+		let config = Config::cancun();
+		let decoded_etable = DecodedEtable::new(etable());
+		let etable = etable();
+		let args = call_args(CONTRACT, Vec::new(), 100_000, 0);
+		let mut backend = OverlayedBackend::new(base, BTreeSet::new());
+
+		let value = match cache {
+			Some(cache) => {
+				let resolver =
+					EtableResolver::new(&config, &(), &decoded_etable).with_decode_cache(cache);
+				let invoker = Invoker::new(&config, &resolver);
+				crate::transact_hybrid(args, None, &mut backend, &invoker)
+			}
+			None => {
+				let resolver = EtableResolver::new(&config, &(), &etable);
+				let invoker = Invoker::new(&config, &resolver);
+				crate::transact_hybrid(args, None, &mut backend, &invoker)
+			}
+		}
+		.unwrap();
+		assert!(value.exit.is_ok());
+		(value.retval, value.used_gas)
+	}
+
+	#[test]
+	fn decoded_execution_matches_plain_execution() {
+		let mut base = backend();
+		// Return (2 + 2) after a jump over an invalid opcode, with PUSH, DUP,
+		// SWAP and POP.
+		base.set_code(
+			CONTRACT,
+			vec![
+				0x60, 0x02, 0x60, 0x03, 0x81, 0x90, 0x50, 0x01, 0x60, 0x0c, 0x56, 0xfe, 0x5b, 0x60,
+				0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+			],
+		);
+		let cache = RefCell::new(DecodeCache::new());
+
+		let plain = call(&base, None);
+		let decoded = call(&base, Some(&cache));
+		assert_eq!(plain.0, H256::from_low_u64_be(4).as_bytes());
+		assert_eq!(plain, decoded);
+		assert!(cache.borrow().get(&base.code_hash(CONTRACT)).is_some());
+		assert_eq!(call(&base, Some(&cache)), plain);
+	}
+}
@@ -0,0 +1,455 @@
+//! Validation rules of ERC-4337 bundlers (see ERC-7562), enabled by the
+//! `inspector` feature.
+//!
+//! A bundler simulates the validation phase of a user operation with a
+//! [ValidationInspector], which records the rule violations and the accessed
+//! entities instead of stopping the execution:
+//!
+//! ```ignore
+//! let inspector = RefCell::new(ValidationInspector::new(sender));
+//! let etable = InspectedEtable::new((gas_etable, exec_etable), &inspector);
+//! let resolver = EtableResolver::new(&config, &precompiles, &etable);
+//! let middleware = InspectorMiddleware::new(&inspector);
+//! let invoker = MiddlewareInvoker::new(Invoker::new(&config, &resolver), middleware);
+//! evm::transact(args, Some(4), &mut backend, &invoker)?;
+//! let report = inspector.into_inner().into_report();
+//! ```
+//!
+//! Only the opcode, storage and out-of-gas rules are checked. Rules that
+//! depend on the mempool, the entry point or the reputation of the entities
+//! are left to the bundler.
+
+use alloc::{
+	collections::{BTreeMap, BTreeSet},
+	vec::Vec,
+};
+
+use evm_interpreter::{
+	error::{ExitError, ExitException, ExitResult},
+	etable::Control,
+	machine::Machine,
+	opcode::Opcode,
+	runtime::{RuntimeBaseBackend, RuntimeState},
+};
+use primitive_types::{H160, H256, U256};
+
+use crate::standard::{inspector::Inspector, SubstackInvoke};
+
+/// Opcodes that the validation phase must not use (OP-011, OP-031, OP-080).
+pub const BANNED_OPCODES: [Opcode; 16] = [
+	Opcode::ORIGIN,
+	Opcode::GASPRICE,
+	Opcode::BLOCKHASH,
+	Opcode::COINBASE,
+	Opcode::TIMESTAMP,
+	Opcode::NUMBER,
+	Opcode::DIFFICULTY,
+	Opcode::GASLIMIT,
+	Opcode::BASEFEE,
+	Opcode::BLOBHASH,
+	Opcode::BLOBBASEFEE,
+	Opcode::BALANCE,
+	Opcode::SELFBALANCE,
+	Opcode::CREATE,
+	Opcode::SUICIDE,
+	Opcode::INVALID,
+];
+
+/// Number of slots after an associated slot that are still associated with
+/// the sender (STO-021), for mapping values spanning several slots.
+pub const ASSOCIATED_SLOT_SPAN: u64 = 128;
+
+/// A violation of the validation rules.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Violation {
+	/// A banned opcode was executed by the contract.
+	BannedOpcode {
+		address: H160,
+		opcode: Opcode,
+		position: usize,
+	},
+	/// `GAS` was not immediately followed by a call (OP-012).
+	GasNotFollowedByCall { address: H160, position: usize },
+	/// `CREATE2` was executed more than once (OP-031).
+	RepeatedCreate2 { address: H160, position: usize },
+	/// The contract accessed a storage slot that is neither its own as the
+	/// sender, nor associated with the sender, and the contract is not
+	/// staked (STO-010, STO-021, STO-031).
+	StorageAccess {
+		address: H160,
+		index: H256,
+		opcode: Opcode,
+	},
+	/// A call ran out of gas (OP-020).
+	OutOfGas { address: H160 },
+}
+
+/// Entities accessed by the validation phase, to check the rules depending
+/// on them and to invalidate the user operation when they change.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AccessedEntities {
+	/// Storage slots read, by contract.
+	pub reads: BTreeMap<H160, BTreeSet<H256>>,
+	/// Storage slots written, by contract.
+	pub writes: BTreeMap<H160, BTreeSet<H256>>,
+	/// Contracts called, created, or whose code was accessed.
+	pub contracts: BTreeSet<H160>,
+}
+
+/// Result of a validation phase simulated with a [ValidationInspector].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidationReport {
+	/// Rule violations, in the order they happened. Repeated violations are
+	/// reported once.
+	pub violations: Vec<Violation>,
+	/// Accessed entities.
+	pub accessed: AccessedEntities,
+}
+
+impl ValidationReport {
+	/// Whether the validation phase followed the rules.
+	pub fn is_valid(&self) -> bool {
+		self.violations.is_empty()
+	}
+}
+
+/// An [Inspector] checking the validation rules of ERC-7562 for a sender.
+#[derive(Clone, Debug)]
+pub struct ValidationInspector {
+	sender: H160,
+	staked: BTreeSet<H160>,
+	associated: BTreeSet<U256>,
+	create2: bool,
+	report: ValidationReport,
+}
+
+impl ValidationInspector {
+	/// Create an inspector for the validation phase of a user operation of
+	/// the sender.
+	pub fn new(sender: H160) -> Self {
+		Self {
+			sender,
+			staked: BTreeSet::new(),
+			associated: BTreeSet::new(),
+			create2: false,
+			report: ValidationReport::default(),
+		}
+	}
+
+	/// Allow the staked entities to access their own storage.
+	#[must_use]
+	pub fn with_staked(mut self, staked: impl IntoIterator<Item = H160>) -> Self {
+		self.staked.extend(staked);
+		self
+	}
+
+	/// Violations so far.
+	pub fn violations(&self) -> &[Violation] {
+		&self.report.violations
+	}
+
+	/// Entities accessed so far.
+	pub fn accessed(&self) -> &AccessedEntities {
+		&self.report.accessed
+	}
+
+	/// The report of the validation phase.
+	pub fn into_report(self) -> ValidationReport {
+		self.report
+	}
+
+	fn violate(&mut self, violation: Violation) {
+		if !self.report.violations.contains(&violation) {
+			self.report.violations.push(violation);
+		}
+	}
+
+	/// Whether the slot is the sender address, or within
+	/// [ASSOCIATED_SLOT_SPAN] after a hash of the sender address.
+	fn is_associated(&self, index: H256) -> bool {
+		if index == H256::from(self.sender) {
+			return true;
+		}
+
+		let index = U256::from_big_endian(&index[..]);
+		match self.associated.range(..=index).next_back() {
+			Some(base) => index - *base <= U256::from(ASSOCIATED_SLOT_SPAN),
+			None => false,
+		}
+	}
+
+	fn check_storage<S: AsRef<RuntimeState>>(&mut self, machine: &Machine<S>, opcode: Opcode) {
+		let address = machine.state.as_ref().context.address;
+		let index = match machine.stack.peek(0) {
+			Ok(index) => index,
+			Err(_) => return,
+		};
+
+		let slots = if opcode == Opcode::SSTORE || opcode == Opcode::TSTORE {
+			&mut self.report.accessed.writes
+		} else {
+			&mut self.report.accessed.reads
+		};
+		slots.entry(address).or_default().insert(index);
+
+		if address != self.sender && !self.staked.contains(&address) && !self.is_associated(index) {
+			self.violate(Violation::StorageAccess {
+				address,
+				index,
+				opcode,
+			});
+		}
+	}
+
+	/// Record the hashes of data starting with the sender address, the slots
+	/// of mappings keyed by the sender.
+	///
+	/// This runs before the gas of the `SHA3` is charged, so only data within
+	/// the current memory is hashed. The key and the slot of a mapping are
+	/// written to memory before they are hashed.
+	fn record_hash<S, H: RuntimeBaseBackend>(&mut self, machine: &Machine<S>, handler: &H) {
+		let (offset, len) = match (machine.stack.peek(0), machine.stack.peek(1)) {
+			(Ok(offset), Ok(len)) => (
+				U256::from_big_endian(&offset[..]),
+				U256::from_big_endian(&len[..]),
+			),
+			_ => return,
+		};
+		let end = match offset.checked_add(len) {
+			Some(end) => end,
+			None => return,
+		};
+		if len < U256::from(32) || end > U256::from(machine.memory.len()) {
+			return;
+		}
+
+		let data = machine.memory.slice(offset.as_usize(), len.as_usize());
+		if data[..32] == H256::from(self.sender)[..] {
+			let hash = handler.keccak256(data);
+			self.associated.insert(U256::from_big_endian(&hash[..]));
+		}
+	}
+}
+
+impl<S, H, Tr> Inspector<S, H, Tr> for ValidationInspector
+where
+	S: AsRef<RuntimeState>,
+	H: RuntimeBaseBackend,
+{
+	fn step(
+		&mut self,
+		machine: &mut Machine<S>,
+		handler: &mut H,
+		opcode: Opcode,
+		position: usize,
+	) -> Option<Control<Tr>> {
+		let address = machine.state.as_ref().context.address;
+		self.report.accessed.contracts.insert(address);
+
+		match opcode {
+			Opcode::GAS => {
+				let next = machine.code().get(position + 1).map(|op| Opcode(*op));
+				let call = [
+					Opcode::CALL,
+					Opcode::CALLCODE,
+					Opcode::DELEGATECALL,
+					Opcode::STATICCALL,
+				];
+				if !next.map_or(false, |next| call.contains(&next)) {
+					self.violate(Violation::GasNotFollowedByCall { address, position });
+				}
+			}
+			Opcode::CREATE2 => {
+				if self.create2 {
+					self.violate(Violation::RepeatedCreate2 { address, position });
+				}
+				self.create2 = true;
+			}
+			Opcode::SLOAD | Opcode::SSTORE | Opcode::TLOAD | Opcode::TSTORE => {
+				self.check_storage(machine, opcode);
+			}
+			Opcode::SHA3 => self.record_hash(machine, handler),
+			Opcode::EXTCODESIZE | Opcode::EXTCODECOPY | Opcode::EXTCODEHASH => {
+				if let Ok(target) = machine.stack.peek(0) {
+					self.report.accessed.contracts.insert(target.into());
+				}
+			}
+			opcode if BANNED_OPCODES.contains(&opcode) => {
+				self.violate(Violation::BannedOpcode {
+					address,
+					opcode,
+					position,
+				});
+			}
+			_ => (),
+		}
+
+		None
+	}
+
+	fn enter_substack(
+		&mut self,
+		invoke: &SubstackInvoke,
+		_handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		let address = match invoke {
+			SubstackInvoke::Call { trap, .. } => trap.target,
+			SubstackInvoke::Create { address, .. } => *address,
+		};
+		self.report.accessed.contracts.insert(address);
+
+		None
+	}
+
+	fn exit_substack(
+		&mut self,
+		invoke: &SubstackInvoke,
+		_state: &S,
+		result: &mut ExitResult,
+		_retval: &mut Vec<u8>,
+		_handler: &mut H,
+	) {
+		if let Err(ExitError::Exception(ExitException::OutOfGas)) = result {
+			let address = match invoke {
+				SubstackInvoke::Call { trap, .. } => trap.target,
+				SubstackInvoke::Create { address, .. } => *address,
+			};
+			self.violate(Violation::OutOfGas { address });
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{sync::Arc, vec, vec::Vec};
+
+	use evm_interpreter::{
+		machine::Machine,
+		opcode::Opcode,
+		runtime::{Context, RuntimeBaseBackend, RuntimeState, TransactionContext},
+	};
+	use primitive_types::{H160, H256, U256};
+
+	use super::{ValidationInspector, Violation};
+	use crate::{
+		backend::{TestBackend, TestEnvironment},
+		standard::inspector::Inspector,
+	};
+
+	const SENDER: H160 = H160([0x11; 20]);
+	const PAYMASTER: H160 = H160([0x22; 20]);
+
+	fn machine(address: H160) -> Machine<RuntimeState> {
+		let state = RuntimeState {
+			context: Context {
+				address,
+				caller: SENDER,
+				apparent_value: U256::zero(),
+			},
+			transaction_context: Arc::new(TransactionContext {
+				gas_price: U256::zero(),
+				origin: SENDER,
+				chain_id: U256::one(),
+				prevrandao: true,
+			}),
+			retbuf: Vec::new(),
+			authorized: None,
+		};
+		Machine::new(
+			Arc::new(Vec::new()),
+			Arc::new(Vec::new()),
+			1024,
+			1024,
+			state,
+		)
+	}
+
+	fn step(
+		inspector: &mut ValidationInspector,
+		machine: &mut Machine<RuntimeState>,
+		backend: &mut TestBackend,
+		opcode: Opcode,
+	) {
+		let control = Inspector::<_, _, ()>::step(inspector, machine, backend, opcode, 0);
+		assert!(control.is_none());
+	}
+
+	#[test]
+	fn blob_opcodes_are_banned() {
+		let mut backend = TestBackend::new(TestEnvironment::default());
+		let mut machine = machine(SENDER);
+		let mut inspector = ValidationInspector::new(SENDER);
+
+		step(&mut inspector, &mut machine, &mut backend, Opcode::BLOBHASH);
+		step(
+			&mut inspector,
+			&mut machine,
+			&mut backend,
+			Opcode::BLOBBASEFEE,
+		);
+
+		assert_eq!(
+			inspector.violations(),
+			&[
+				Violation::BannedOpcode {
+					address: SENDER,
+					opcode: Opcode::BLOBHASH,
+					position: 0,
+				},
+				Violation::BannedOpcode {
+					address: SENDER,
+					opcode: Opcode::BLOBBASEFEE,
+					position: 0,
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn hashes_beyond_the_memory_are_ignored() {
+		let mut backend = TestBackend::new(TestEnvironment::default());
+		let mut machine = machine(PAYMASTER);
+		let mut inspector = ValidationInspector::new(SENDER);
+
+		machine.stack.push(H256::repeat_byte(0xff)).unwrap();
+		machine.stack.push(H256::zero()).unwrap();
+		step(&mut inspector, &mut machine, &mut backend, Opcode::SHA3);
+
+		assert!(inspector.violations().is_empty());
+		assert!(machine.memory.is_empty());
+	}
+
+	#[test]
+	fn slots_of_mappings_keyed_by_the_sender_are_associated() {
+		let mut backend = TestBackend::new(TestEnvironment::default());
+		let mut machine = machine(PAYMASTER);
+		let mut inspector = ValidationInspector::new(SENDER);
+
+		let mut key = vec![0; 64];
+		key[12..32].copy_from_slice(&SENDER[..]);
+		key[63] = 3;
+		machine.memory.set(0, &key, None).unwrap();
+		machine.stack.push(H256::from_low_u64_be(64)).unwrap();
+		machine.stack.push(H256::zero()).unwrap();
+		step(&mut inspector, &mut machine, &mut backend, Opcode::SHA3);
+
+		let slot = U256::from_big_endian(&backend.keccak256(&key)[..]) + U256::one();
+		let mut associated = H256::zero();
+		slot.to_big_endian(&mut associated[..]);
+		machine.stack.push(associated).unwrap();
+		step(&mut inspector, &mut machine, &mut backend, Opcode::SLOAD);
+		assert!(inspector.violations().is_empty());
+
+		let unrelated = H256::from_low_u64_be(3);
+		machine.stack.push(unrelated).unwrap();
+		step(&mut inspector, &mut machine, &mut backend, Opcode::SLOAD);
+		assert_eq!(
+			inspector.violations(),
+			&[Violation::StorageAccess {
+				address: PAYMASTER,
+				index: unrelated,
+				opcode: Opcode::SLOAD,
+			}]
+		);
+	}
+}
@@ -0,0 +1,31 @@
+use evm_interpreter::{error::ExitResult, opcode::Opcode};
+use primitive_types::H160;
+
+use crate::standard::RevertReason;
+
+/// Gas usage of a substack, recorded when it exits.
+///
+/// Precompiles and calls to accounts without code get a frame as well.
+/// Calls that fail before entering the substack, for example on the call
+/// depth limit, do not.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub struct FrameGas {
+	/// Context address of the frame. For a create, the created address.
+	pub address: H160,
+	/// Call or create opcode that entered the frame.
+	pub opcode: Opcode,
+	/// Gas limit of the frame, including the call stipend.
+	pub gas_limit: u64,
+	/// Gas used by the frame, including its substacks and the code deposit,
+	/// before refunds. A frame that failed with an error uses all of its gas.
+	pub gas_used: u64,
+	/// Exit result of the frame.
+	pub result: ExitResult,
+	/// Decoded revert data, if the frame reverted with a Solidity error or
+	/// panic.
+	pub revert_reason: Option<RevertReason>,
+}
@@ -11,6 +11,8 @@ pub const R_SUICIDE: i64 = 24000;
 pub const G_CREATE: u64 = 32000;
 pub const G_CALLVALUE: u64 = 9000;
 pub const G_NEWACCOUNT: u64 = 25000;
+pub const G_AUTH: u64 = 3100;
+pub const G_AUTHCALL_VALUE: u64 = 6700;
 pub const G_EXP: u64 = 10;
 pub const G_MEMORY: u64 = 3;
 pub const G_LOG: u64 = 375;
@@ -21,6 +23,8 @@ pub const G_SHA3WORD: u64 = 6;
 pub const G_COPY: u64 = 3;
 pub const G_BLOCKHASH: u64 = 20;
 pub const G_CODEDEPOSIT: u64 = 200;
+pub const G_TX_FLOOR_PER_TOKEN: u64 = 10;
+pub const G_TX_TOKENS_PER_NON_ZERO_BYTE: u64 = 4;
 
 pub static STATIC_COST_TABLE: [Option<u64>; 256] = {
 	let mut table = [None; 256];
@@ -12,6 +12,15 @@ pub fn call_extra_check(gas: U256, after_gas: u64, config: &Config) -> Result<()
 	}
 }
 
+pub fn authcall_extra_check(gas: U256, after_gas: u64) -> Result<(), ExitException> {
+	// Unlike other calls, the requested gas is not capped (EIP-3074).
+	if U256::from(after_gas - after_gas / 64) < gas {
+		Err(ExitException::OutOfGas)
+	} else {
+		Ok(())
+	}
+}
+
 pub fn suicide_refund(already_removed: bool) -> i64 {
 	if already_removed {
 		0
@@ -284,6 +293,21 @@ pub fn call_cost(
 		+ new_cost(is_call_or_staticcall, new_account, transfers_value, config)
 }
 
+pub fn auth_cost(is_cold: bool, config: &Config) -> u64 {
+	if config.increase_state_access_gas && is_cold {
+		G_AUTH + config.gas_account_access_cold
+	} else {
+		G_AUTH
+	}
+}
+
+pub fn authcall_cost(value: U256, is_cold: bool, new_account: bool, config: &Config) -> u64 {
+	let transfers_value = value != U256::default();
+	address_access_cost(is_cold, config.gas_call, config)
+		+ if transfers_value { G_AUTHCALL_VALUE } else { 0 }
+		+ new_cost(true, new_account, transfers_value, config)
+}
+
 pub fn address_access_cost(is_cold: bool, regular_value: u64, config: &Config) -> u64 {
 	if config.increase_state_access_gas {
 		if is_cold {
@@ -328,8 +352,12 @@ fn new_cost(
 	}
 }
 
-pub fn memory_gas(a: usize) -> Result<u64, ExitException> {
-	let a = a as u64;
+pub fn memory_gas(a: u64) -> Result<u64, ExitException> {
+	// Neither the quadratic term nor the sum can overflow below this.
+	if a <= u32::MAX as u64 {
+		return Ok(G_MEMORY * a + a * a / 512);
+	}
+
 	G_MEMORY
 		.checked_mul(a)
 		.ok_or(ExitException::OutOfGas)?
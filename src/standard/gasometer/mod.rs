@@ -1,6 +1,7 @@
 mod consts;
 mod costs;
 mod utils;
+mod witness;
 
 use alloc::vec::Vec;
 use core::cmp::{max, min};
@@ -14,15 +15,31 @@ use evm_interpreter::{
 };
 use primitive_types::{H160, H256, U256};
 
+pub use self::witness::{AccessWitness, TreeKey};
+#[cfg(feature = "counters")]
+use crate::standard::Counters;
+#[cfg(feature = "frames")]
+use crate::standard::FrameGas;
 use crate::{standard::Config, MergeStrategy};
 
 pub struct GasometerState<'config> {
 	gas_limit: u64,
 	memory_gas: u64,
+	memory_words: u64,
 	used_gas: u64,
-	refunded_gas: u64,
+	refunded_gas: i64,
+	floor_gas: u64,
 	pub is_static: bool,
 	pub config: &'config Config,
+	/// Execution counters of this call frame and its substacks.
+	#[cfg(feature = "counters")]
+	pub counters: Counters,
+	/// Gas usage of the exited substacks of this call frame, recursively.
+	#[cfg(feature = "frames")]
+	pub frames: Vec<FrameGas>,
+	/// State tree accesses of the transaction so far, with stateless gas
+	/// (EIP-4762).
+	pub witness: AccessWitness,
 }
 
 impl<'config> GasometerState<'config> {
@@ -45,6 +62,7 @@ impl<'config> GasometerState<'config> {
 	/// Set the current gasometer to `OutOfGas`.
 	pub fn oog(&mut self) {
 		self.memory_gas = 0;
+		self.memory_words = 0;
 		self.refunded_gas = 0;
 		self.used_gas = self.gas_limit;
 	}
@@ -54,6 +72,11 @@ impl<'config> GasometerState<'config> {
 		self.used_gas + self.memory_gas
 	}
 
+	/// Gas limit of the current interpreter.
+	pub fn gas_limit(&self) -> u64 {
+		self.gas_limit
+	}
+
 	/// Left gas that is supposed to be available to the current interpreter.
 	pub fn gas64(&self) -> u64 {
 		self.gas_limit - self.memory_gas - self.used_gas
@@ -109,15 +132,76 @@ impl<'config> GasometerState<'config> {
 		}
 	}
 
+	/// Record memory expansion to the given number of words. Only the
+	/// quadratic cost is recomputed, and only if the memory actually expands.
+	pub fn record_memory_words(&mut self, words: u64) -> Result<(), ExitError> {
+		if words <= self.memory_words {
+			return Ok(());
+		}
+
+		let memory_cost = costs::memory_gas(words)?;
+		self.set_memory_gas(memory_cost)?;
+		self.memory_words = words;
+		Ok(())
+	}
+
+	/// Record memory expansion to cover the given range.
+	pub fn record_memory_expansion(&mut self, offset: U256, len: U256) -> Result<(), ExitError> {
+		match (MemoryCost { offset, len }).words()? {
+			Some(words) => self.record_memory_words(words),
+			None => Ok(()),
+		}
+	}
+
+	/// Add to the refund counter.
+	pub fn record_refund(&mut self, refund: i64) {
+		self.refunded_gas += refund;
+	}
+
+	/// Charge a read of a leaf of the state tree, if stateless gas is enabled
+	/// (EIP-4762). Only the first access to each stem and leaf is charged.
+	/// Custom opcodes and precompiles can charge their state accesses with
+	/// this.
+	pub fn record_witness_read(&mut self, key: TreeKey) -> Result<(), ExitError> {
+		if !self.config.eip_4762_enabled {
+			return Ok(());
+		}
+
+		let cost = self.witness.read(key);
+		self.record_gas64(cost)
+	}
+
+	/// Charge a write of a leaf of the state tree, including its read, if
+	/// stateless gas is enabled (EIP-4762). Whether the leaf had no value
+	/// before is given by `fill`.
+	pub fn record_witness_write(&mut self, key: TreeKey, fill: bool) -> Result<(), ExitError> {
+		if !self.config.eip_4762_enabled {
+			return Ok(());
+		}
+
+		let cost = self
+			.witness
+			.read(key)
+			.saturating_add(self.witness.write(key, fill));
+		self.record_gas64(cost)
+	}
+
 	/// Create a new gasometer with the given gas limit and chain config.
 	pub fn new(gas_limit: u64, is_static: bool, config: &'config Config) -> Self {
 		Self {
 			gas_limit,
 			memory_gas: 0,
+			memory_words: 0,
 			used_gas: 0,
 			refunded_gas: 0,
+			floor_gas: 0,
 			is_static,
 			config,
+			#[cfg(feature = "counters")]
+			counters: Counters::new(),
+			#[cfg(feature = "frames")]
+			frames: Vec::new(),
+			witness: AccessWitness::new(),
 		}
 	}
 
@@ -134,9 +218,13 @@ impl<'config> GasometerState<'config> {
 		};
 
 		let mut s = Self::new(gas_limit, false, config);
-		let transaction_cost = TransactionCost::call(data, access_list).cost(config);
+		let transaction_cost = TransactionCost::call(data, access_list);
 
-		s.record_gas64(transaction_cost)?;
+		s.record_gas64(transaction_cost.cost(config))?;
+		s.floor_gas = transaction_cost.floor_cost(config);
+		if s.floor_gas > gas_limit {
+			return Err(ExitException::OutOfGas.into());
+		}
 		Ok(s)
 	}
 
@@ -153,45 +241,69 @@ impl<'config> GasometerState<'config> {
 		};
 
 		let mut s = Self::new(gas_limit, false, config);
-		let transaction_cost = TransactionCost::create(code, access_list).cost(config);
+		let transaction_cost = TransactionCost::create(code, access_list);
 
-		s.record_gas64(transaction_cost)?;
+		s.record_gas64(transaction_cost.cost(config))?;
+		s.floor_gas = transaction_cost.floor_cost(config);
+		if s.floor_gas > gas_limit {
+			return Err(ExitException::OutOfGas.into());
+		}
 		Ok(s)
 	}
 
-	pub fn effective_gas(&self) -> U256 {
-		U256::from(
-			self.gas_limit
-				- (self.total_used_gas()
-					- min(
-						self.total_used_gas() / self.config.max_refund_quotient,
-						self.refunded_gas,
-					)),
+	/// Refund applied at the end of the transaction. The refund counter can
+	/// go negative within a call frame, in which case no refund is applied, and
+	/// it is capped at a quotient of the used gas as set in the config (EIP-3529).
+	pub fn refunded_gas(&self) -> u64 {
+		let refunded_gas = if self.refunded_gas > 0 {
+			self.refunded_gas as u64
+		} else {
+			0
+		};
+
+		min(
+			self.total_used_gas() / self.config.max_refund_quotient,
+			refunded_gas,
 		)
 	}
 
+	/// Left gas at the end of the transaction, after refunds. If the used gas
+	/// is below the calldata floor cost (EIP-7623), the floor is charged
+	/// instead.
+	pub fn effective_gas(&self) -> U256 {
+		let used_gas = max(self.total_used_gas() - self.refunded_gas(), self.floor_gas);
+		U256::from(self.gas_limit - used_gas)
+	}
+
 	pub fn submeter(
 		&mut self,
-		gas_limit: U256,
+		mut gas_limit: u64,
 		is_static: bool,
 		call_has_value: bool,
 	) -> Result<Self, ExitError> {
-		let mut gas_limit = if gas_limit > U256::from(u64::MAX) {
-			return Err(ExitException::OutOfGas.into());
-		} else {
-			gas_limit.as_u64()
-		};
-
 		self.record_gas64(gas_limit)?;
 
 		if call_has_value {
 			gas_limit = gas_limit.saturating_add(self.config.call_stipend);
 		}
 
-		Ok(Self::new(gas_limit, is_static, self.config))
+		let mut submeter = Self::new(gas_limit, is_static, self.config);
+		if self.config.eip_4762_enabled {
+			submeter.witness = self.witness.clone();
+		}
+		Ok(submeter)
 	}
 
-	pub fn merge(&mut self, other: Self, strategy: MergeStrategy) {
+	pub fn merge(&mut self, mut other: Self, strategy: MergeStrategy) {
+		#[cfg(feature = "counters")]
+		self.counters.merge(&other.counters);
+		#[cfg(feature = "frames")]
+		self.frames.append(&mut other.frames);
+		// Accesses stay in the witness even if the substack is reverted.
+		if self.config.eip_4762_enabled {
+			self.witness = core::mem::take(&mut other.witness);
+		}
+
 		match strategy {
 			MergeStrategy::Commit => {
 				self.used_gas -= other.gas64();
@@ -205,6 +317,16 @@ impl<'config> GasometerState<'config> {
 	}
 }
 
+/// Static gas of each opcode with the config, `None` for opcodes whose gas is
+/// dynamic, to decode code with [evm_interpreter::DecodedCode].
+pub fn static_gas_costs(config: &Config) -> [Option<u64>; 256] {
+	let mut costs = consts::STATIC_COST_TABLE;
+	for (opcode, cost) in config.opcode_gas_overrides.iter() {
+		costs[opcode.as_usize()] = Some(*cost);
+	}
+	costs
+}
+
 pub fn eval<'config, S, H, Tr>(
 	machine: &mut Machine<S>,
 	handler: &mut H,
@@ -239,9 +361,31 @@ where
 		.context
 		.address;
 
+	let code_size = machine.code().len() as u64;
+
+	#[cfg(feature = "counters")]
+	machine.state.as_mut().counters.record_opcode(opcode);
+
 	machine.state.as_mut().perform(|gasometer| {
+		let cost_override = if gasometer.config.opcode_gas_overrides.is_empty() {
+			None
+		} else {
+			gasometer.config.opcode_gas_override(opcode)
+		};
+
+		if gasometer.config.eip_4762_enabled {
+			record_witness(
+				address,
+				opcode,
+				&machine.stack,
+				code_size,
+				handler,
+				gasometer,
+			)?;
+		}
+
 		if let Some(cost) = consts::STATIC_COST_TABLE[opcode.as_usize()] {
-			gasometer.record_gas64(cost)?;
+			gasometer.record_gas64(cost_override.unwrap_or(cost))?;
 		} else {
 			let (gas, memory_gas) = dynamic_opcode_cost(
 				address,
@@ -251,19 +395,17 @@ where
 				gasometer.config,
 				handler,
 			)?;
+			// The computed cost is replaced only after it is checked, so that
+			// an override keeps the static call and fork rules of the opcode.
 			let cost = gas.cost(gasometer.gas64(), gasometer.config)?;
+			let cost = cost_override.unwrap_or(cost);
 			let refund = gas.refund(gasometer.config);
 
 			gasometer.record_gas64(cost)?;
-			if refund >= 0 {
-				gasometer.refunded_gas += refund as u64;
-			} else {
-				gasometer.refunded_gas = gasometer.refunded_gas.saturating_sub(-refund as u64);
-			}
+			gasometer.refunded_gas += refund;
 			if let Some(memory_gas) = memory_gas {
-				let memory_cost = memory_gas.cost()?;
-				if let Some(memory_cost) = memory_cost {
-					gasometer.set_memory_gas(max(gasometer.memory_gas, memory_cost))?;
+				if let Some(words) = memory_gas.words()? {
+					gasometer.record_memory_words(words)?;
 				}
 			}
 
@@ -275,6 +417,88 @@ where
 	})
 }
 
+/// Whether the access is cold (EIP-2929). Stateless gas replaces the cold
+/// access costs (EIP-4762).
+fn is_cold<H: RuntimeBackend>(
+	config: &Config,
+	handler: &H,
+	address: H160,
+	index: Option<H256>,
+) -> bool {
+	!config.eip_4762_enabled && handler.is_cold(address, index)
+}
+
+/// Charge the state tree accesses of the opcode, with stateless gas
+/// (EIP-4762). Chunks of the executed code are not charged here, because the
+/// gasometer does not know the code address of the frame.
+fn record_witness<H: RuntimeBackend>(
+	address: H160,
+	opcode: Opcode,
+	stack: &Stack,
+	code_size: u64,
+	handler: &H,
+	gasometer: &mut GasometerState,
+) -> Result<(), ExitError> {
+	let word = |n: usize| -> Result<u64, ExitError> {
+		Ok(low_u64(U256::from_big_endian(&stack.peek(n)?[..])).unwrap_or(u64::MAX))
+	};
+
+	match opcode {
+		Opcode::SLOAD => {
+			gasometer.record_witness_read(TreeKey::storage_slot(address, stack.peek(0)?))?;
+		}
+		Opcode::SSTORE => {
+			let index = stack.peek(0)?;
+			let fill = handler.storage(address, index) == H256::default();
+			gasometer.record_witness_write(TreeKey::storage_slot(address, index), fill)?;
+		}
+		Opcode::BALANCE | Opcode::EXTCODESIZE => {
+			gasometer.record_witness_read(TreeKey::basic_data(stack.peek(0)?.into()))?;
+		}
+		Opcode::EXTCODEHASH => {
+			gasometer.record_witness_read(TreeKey::code_hash(stack.peek(0)?.into()))?;
+		}
+		Opcode::EXTCODECOPY => {
+			let target = stack.peek(0)?.into();
+			gasometer.record_witness_read(TreeKey::basic_data(target))?;
+
+			let code_size = low_u64(handler.code_size(target)).unwrap_or(u64::MAX);
+			for key in TreeKey::code_chunks(target, word(2)?, word(3)?, code_size) {
+				gasometer.record_witness_read(key)?;
+			}
+		}
+		Opcode::CODECOPY => {
+			for key in TreeKey::code_chunks(address, word(1)?, word(2)?, code_size) {
+				gasometer.record_witness_read(key)?;
+			}
+		}
+		Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL => {
+			let target = stack.peek(1)?.into();
+			gasometer.record_witness_read(TreeKey::basic_data(target))?;
+
+			if opcode == Opcode::CALL && stack.peek(2)? != H256::default() {
+				gasometer.record_witness_write(TreeKey::basic_data(address), false)?;
+				gasometer
+					.record_witness_write(TreeKey::basic_data(target), !handler.exists(target))?;
+			}
+		}
+		Opcode::SUICIDE => {
+			let target = stack.peek(0)?.into();
+			gasometer.record_witness_read(TreeKey::basic_data(address))?;
+			gasometer.record_witness_read(TreeKey::basic_data(target))?;
+
+			if handler.balance(address) != U256::zero() {
+				gasometer.record_witness_write(TreeKey::basic_data(address), false)?;
+				gasometer
+					.record_witness_write(TreeKey::basic_data(target), !handler.exists(target))?;
+			}
+		}
+		_ => (),
+	}
+
+	Ok(())
+}
+
 /// Calculate the opcode cost.
 #[allow(clippy::nonminimal_bool)]
 fn dynamic_opcode_cost<H: RuntimeBackend>(
@@ -309,7 +533,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let target = stack.peek(0)?.into();
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(target, None);
+			let target_is_cold = is_cold(config, handler, target, None);
 			handler.mark_hot(target, None);
 
 			GasCost::ExtCodeSize { target_is_cold }
@@ -318,7 +542,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let target = stack.peek(0)?.into();
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(target, None);
+			let target_is_cold = is_cold(config, handler, target, None);
 			handler.mark_hot(target, None);
 
 			GasCost::Balance { target_is_cold }
@@ -329,7 +553,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let target = stack.peek(0)?.into();
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(target, None);
+			let target_is_cold = is_cold(config, handler, target, None);
 			handler.mark_hot(target, None);
 
 			GasCost::ExtCodeHash { target_is_cold }
@@ -340,7 +564,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let target = stack.peek(1)?.into();
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(target, None);
+			let target_is_cold = is_cold(config, handler, target, None);
 			handler.mark_hot(target, None);
 
 			GasCost::CallCode {
@@ -354,7 +578,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let target = stack.peek(1)?.into();
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(target, None);
+			let target_is_cold = is_cold(config, handler, target, None);
 			handler.mark_hot(target, None);
 
 			GasCost::StaticCall {
@@ -370,7 +594,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let target = stack.peek(0)?.into();
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(target, None);
+			let target_is_cold = is_cold(config, handler, target, None);
 			handler.mark_hot(target, None);
 
 			GasCost::ExtCodeCopy {
@@ -391,7 +615,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let index = stack.peek(0)?;
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(address, Some(index));
+			let target_is_cold = is_cold(config, handler, address, Some(index));
 			handler.mark_hot(address, Some(index));
 
 			GasCost::SLoad { target_is_cold }
@@ -402,7 +626,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let target = stack.peek(1)?.into();
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(target, None);
+			let target_is_cold = is_cold(config, handler, target, None);
 			handler.mark_hot(target, None);
 
 			GasCost::DelegateCall {
@@ -424,7 +648,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let value = stack.peek(1)?;
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(address, Some(index));
+			let target_is_cold = is_cold(config, handler, address, Some(index));
 			handler.mark_hot(address, Some(index));
 
 			GasCost::SStore {
@@ -463,7 +687,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let target = stack.peek(0)?.into();
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(target, None);
+			let target_is_cold = is_cold(config, handler, target, None);
 			handler.mark_hot(target, None);
 
 			GasCost::Suicide {
@@ -480,7 +704,7 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			let target = stack.peek(1)?.into();
 
 			// https://eips.ethereum.org/EIPS/eip-2929
-			let target_is_cold = handler.is_cold(target, None);
+			let target_is_cold = is_cold(config, handler, target, None);
 			handler.mark_hot(target, None);
 
 			GasCost::Call {
@@ -491,8 +715,39 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			}
 		}
 
+		Opcode::AUTH if config.eip_3074_enabled => {
+			let target = stack.peek(0)?.into();
+
+			// https://eips.ethereum.org/EIPS/eip-2929
+			let target_is_cold = is_cold(config, handler, target, None);
+			handler.mark_hot(target, None);
+
+			GasCost::Auth { target_is_cold }
+		}
+		Opcode::AUTHCALL
+			if config.eip_3074_enabled
+				&& (!is_static || U256::from_big_endian(&stack.peek(2)?[..]) == U256::zero()) =>
+		{
+			let target = stack.peek(1)?.into();
+
+			// https://eips.ethereum.org/EIPS/eip-2929
+			let target_is_cold = is_cold(config, handler, target, None);
+			handler.mark_hot(target, None);
+
+			GasCost::AuthCall {
+				value: U256::from_big_endian(&stack.peek(2)?[..]),
+				gas: U256::from_big_endian(&stack.peek(0)?[..]),
+				target_is_cold,
+				target_exists: { handler.exists(target) },
+			}
+		}
+
 		Opcode::PUSH0 if config.has_push0 => GasCost::Base,
 
+		Opcode::DUPN | Opcode::SWAPN | Opcode::EXCHANGE if config.eip_663_enabled => {
+			GasCost::VeryLow
+		}
+
 		_ => GasCost::Invalid(opcode),
 	};
 
@@ -544,7 +799,12 @@ fn dynamic_opcode_cost<H: RuntimeBackend>(
 			len: U256::from_big_endian(&stack.peek(2)?[..]),
 		}),
 
-		Opcode::CALL | Opcode::CALLCODE => Some(
+		Opcode::AUTH => Some(MemoryCost {
+			offset: U256::from_big_endian(&stack.peek(1)?[..]),
+			len: U256::from_big_endian(&stack.peek(2)?[..]),
+		}),
+
+		Opcode::CALL | Opcode::CALLCODE | Opcode::AUTHCALL => Some(
 			MemoryCost {
 				offset: U256::from_big_endian(&stack.peek(3)?[..]),
 				len: U256::from_big_endian(&stack.peek(4)?[..]),
@@ -644,6 +904,23 @@ enum GasCost {
 		/// Whether the target exists.
 		target_exists: bool,
 	},
+	/// Gas cost for `AUTH`.
+	Auth {
+		/// True if authority has not been previously accessed in this
+		/// transaction
+		target_is_cold: bool,
+	},
+	/// Gas cost for `AUTHCALL`.
+	AuthCall {
+		/// Call value.
+		value: U256,
+		/// Call gas.
+		gas: U256,
+		/// True if target has not been previously accessed in this transaction
+		target_is_cold: bool,
+		/// Whether the target exists.
+		target_exists: bool,
+	},
 	/// Gas cost for `SUICIDE`.
 	Suicide {
 		/// Value.
@@ -753,6 +1030,13 @@ impl GasCost {
 				!target_exists,
 				config,
 			),
+			GasCost::Auth { target_is_cold } => costs::auth_cost(target_is_cold, config),
+			GasCost::AuthCall {
+				value,
+				target_is_cold,
+				target_exists,
+				..
+			} => costs::authcall_cost(value, target_is_cold, !target_exists, config),
 
 			GasCost::Suicide {
 				value,
@@ -822,6 +1106,7 @@ impl GasCost {
 			GasCost::CallCode { gas, .. } => costs::call_extra_check(gas, after_gas, config),
 			GasCost::DelegateCall { gas, .. } => costs::call_extra_check(gas, after_gas, config),
 			GasCost::StaticCall { gas, .. } => costs::call_extra_check(gas, after_gas, config),
+			GasCost::AuthCall { gas, .. } => costs::authcall_extra_check(gas, after_gas),
 			_ => Ok(()),
 		}
 	}
@@ -857,32 +1142,40 @@ impl MemoryCost {
 		}
 	}
 
-	/// Numeric value of the cost.
-	pub fn cost(&self) -> Result<Option<u64>, ExitError> {
-		let from = self.offset;
-		let len = self.len;
-
-		if len == U256::zero() {
+	/// Number of memory words needed, or `None` if no memory is touched.
+	pub fn words(&self) -> Result<Option<u64>, ExitError> {
+		if self.len == U256::zero() {
 			return Ok(None);
 		}
 
+		// Any offset or length that does not fit in `u64` can never be paid
+		// for.
+		let (from, len) = match (low_u64(self.offset), low_u64(self.len)) {
+			(Some(from), Some(len)) => (from, len),
+			_ => return Err(ExitException::OutOfGas.into()),
+		};
+
 		let end = from.checked_add(len).ok_or(ExitException::OutOfGas)?;
 
-		if end > U256::from(usize::MAX) {
+		if end > usize::MAX as u64 {
 			return Err(ExitException::OutOfGas.into());
 		}
-		let end = end.as_usize();
 
-		let rem = end % 32;
-		let new = if rem == 0 { end / 32 } else { end / 32 + 1 };
+		Ok(Some(end / 32 + u64::from(end % 32 != 0)))
+	}
+}
 
-		Ok(Some(costs::memory_gas(new)?))
+fn low_u64(value: U256) -> Option<u64> {
+	if value.0[1] == 0 && value.0[2] == 0 && value.0[3] == 0 {
+		Some(value.0[0])
+	} else {
+		None
 	}
 }
 
 /// Transaction cost.
 #[derive(Debug, Clone, Copy)]
-enum TransactionCost {
+pub(crate) enum TransactionCost {
 	/// Call transaction cost.
 	Call {
 		/// Length of zeros in transaction data.
@@ -975,6 +1268,30 @@ impl TransactionCost {
 			}
 		}
 	}
+
+	/// Floor cost of the transaction data (EIP-7623), or zero if it is not
+	/// enabled.
+	pub fn floor_cost(&self, config: &Config) -> u64 {
+		if !config.eip_7623_enabled {
+			return 0;
+		}
+
+		let (zero_data_len, non_zero_data_len) = match self {
+			TransactionCost::Call {
+				zero_data_len,
+				non_zero_data_len,
+				..
+			}
+			| TransactionCost::Create {
+				zero_data_len,
+				non_zero_data_len,
+				..
+			} => (*zero_data_len as u64, *non_zero_data_len as u64),
+		};
+		let tokens = zero_data_len + non_zero_data_len * consts::G_TX_TOKENS_PER_NON_ZERO_BYTE;
+
+		config.gas_transaction_call + tokens * consts::G_TX_FLOOR_PER_TOKEN
+	}
 }
 
 /// Counts the number of addresses and storage keys in the access list
@@ -991,3 +1308,65 @@ fn init_code_cost(data: &[u8]) -> u64 {
 	// where INITCODE_WORD_COST is 2.
 	2 * ((data.len() as u64 + 31) / 32)
 }
+
+#[cfg(test)]
+mod tests {
+	use alloc::{vec, vec::Vec};
+
+	use evm_interpreter::{
+		error::{ExitError, ExitException},
+		opcode::Opcode,
+		runtime::RuntimeBaseBackend,
+	};
+	use primitive_types::{H160, H256, U256};
+
+	use crate::standard::{
+		testing::{backend, call_args, transact, CONTRACT},
+		Config, ConfigBuilder, TransactArgs,
+	};
+
+	const STORING: H160 = H160([0x30; 20]);
+
+	fn call(address: H160) -> TransactArgs {
+		call_args(address, Vec::new(), 100_000, 0)
+	}
+
+	#[test]
+	fn overridden_opcodes_keep_the_static_call_and_fork_rules() {
+		let mut base = backend();
+		// STATICCALL the storing contract with 10000 gas, and store the result
+		// at slot 0.
+		let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73];
+		code.extend_from_slice(STORING.as_bytes());
+		code.extend_from_slice(&[0x61, 0x27, 0x10, 0xfa, 0x60, 0x00, 0x55, 0x00]);
+		base.set_code(CONTRACT, code);
+		// Store 1 at slot 0.
+		base.set_code(STORING, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+		let config = ConfigBuilder::new(Config::cancun())
+			.opcode_gas_overrides(vec![(Opcode::SSTORE, 100)])
+			.build();
+
+		// The override is charged instead of 22100.
+		let (value, _) = transact(&base, &config, call(STORING));
+		assert!(value.exit.is_ok());
+		assert_eq!(value.used_gas, U256::from(21_000 + 6 + 100));
+
+		let (value, backend) = transact(&base, &config, call(CONTRACT));
+		assert!(value.exit.is_ok());
+		assert_eq!(backend.storage(CONTRACT, H256::zero()), H256::zero());
+		assert_eq!(backend.storage(STORING, H256::zero()), H256::zero());
+
+		// An override does not enable an opcode of a later fork.
+		base.set_code(CONTRACT, vec![0x60, 0x00, 0x60, 0x00, 0xfd]);
+		let config = ConfigBuilder::new(Config::frontier())
+			.opcode_gas_overrides(vec![(Opcode::REVERT, 0)])
+			.build();
+		let (value, _) = transact(&base, &config, call(CONTRACT));
+		assert_eq!(
+			value.exit,
+			Err(ExitError::Exception(ExitException::InvalidOpcode(
+				Opcode::REVERT
+			)))
+		);
+	}
+}
@@ -0,0 +1,157 @@
+use alloc::collections::BTreeSet;
+
+use primitive_types::{H160, H256, U256};
+
+/// Cost of the first access to a stem of the state tree (EIP-4762).
+pub const WITNESS_BRANCH_COST: u64 = 1900;
+/// Cost of the first access to a leaf of the state tree (EIP-4762).
+pub const WITNESS_CHUNK_COST: u64 = 200;
+/// Cost of the first write to a stem of the state tree (EIP-4762).
+pub const SUBTREE_EDIT_COST: u64 = 3000;
+/// Cost of the first write to a leaf of the state tree (EIP-4762).
+pub const CHUNK_EDIT_COST: u64 = 500;
+/// Cost of a write to a leaf without a value (EIP-4762).
+pub const CHUNK_FILL_COST: u64 = 6200;
+
+const BASIC_DATA_LEAF_KEY: u8 = 0;
+const CODE_HASH_LEAF_KEY: u8 = 1;
+const HEADER_STORAGE_OFFSET: u64 = 64;
+const CODE_OFFSET: u64 = 128;
+const CODE_CHUNK_SIZE: u64 = 31;
+
+/// Key of a leaf of the state tree, the stem as the address and the tree
+/// index, and the leaf within the stem as the sub index (see EIP-6800).
+///
+/// Stems are not hashed, because the costs only depend on which keys are
+/// equal.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct TreeKey {
+	/// Account address.
+	pub address: H160,
+	/// Index of the stem in the account.
+	pub tree_index: U256,
+	/// Index of the leaf in the stem.
+	pub sub_index: u8,
+}
+
+impl TreeKey {
+	fn at(address: H160, position: U256) -> Self {
+		Self {
+			address,
+			tree_index: position >> 8,
+			sub_index: position.low_u32() as u8,
+		}
+	}
+
+	/// Key of the balance, nonce and code size of the account.
+	pub fn basic_data(address: H160) -> Self {
+		Self::at(address, U256::from(BASIC_DATA_LEAF_KEY))
+	}
+
+	/// Key of the code hash of the account.
+	pub fn code_hash(address: H160) -> Self {
+		Self::at(address, U256::from(CODE_HASH_LEAF_KEY))
+	}
+
+	/// Key of a storage slot of the account. The first slots are in the
+	/// account header, the others in the main storage.
+	pub fn storage_slot(address: H160, index: H256) -> Self {
+		let index = U256::from_big_endian(&index[..]);
+		if index < U256::from(CODE_OFFSET - HEADER_STORAGE_OFFSET) {
+			Self::at(address, index + U256::from(HEADER_STORAGE_OFFSET))
+		} else {
+			// The main storage starts at 256^31, a multiple of the stem width,
+			// so the offset only adds to the tree index.
+			Self {
+				address,
+				tree_index: (index >> 8) + (U256::one() << 240),
+				sub_index: index.low_u32() as u8,
+			}
+		}
+	}
+
+	/// Key of a 31-byte chunk of the code of the account.
+	pub fn code_chunk(address: H160, chunk: u64) -> Self {
+		Self::at(address, U256::from(CODE_OFFSET) + U256::from(chunk))
+	}
+
+	/// Keys of the code chunks overlapping with the code range, clamped to
+	/// the code size.
+	pub fn code_chunks(
+		address: H160,
+		offset: u64,
+		len: u64,
+		code_size: u64,
+	) -> impl Iterator<Item = TreeKey> {
+		let end = offset.saturating_add(len).min(code_size);
+		let chunks = if len == 0 || offset >= end {
+			0..0
+		} else {
+			(offset / CODE_CHUNK_SIZE)..((end - 1) / CODE_CHUNK_SIZE + 1)
+		};
+		chunks.map(move |chunk| Self::code_chunk(address, chunk))
+	}
+
+	fn stem(&self) -> (H160, U256) {
+		(self.address, self.tree_index)
+	}
+}
+
+/// Stems and leaves of the state tree accessed by a transaction, charging
+/// the stateless gas costs of EIP-4762 on the first access to each.
+///
+/// Accesses are kept even if the frame that made them is reverted, since
+/// they are still part of the witness of the block.
+#[derive(Clone, Debug, Default)]
+pub struct AccessWitness {
+	branches: BTreeSet<(H160, U256)>,
+	chunks: BTreeSet<TreeKey>,
+	edited_branches: BTreeSet<(H160, U256)>,
+	edited_chunks: BTreeSet<TreeKey>,
+}
+
+impl AccessWitness {
+	/// Create an empty witness.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Record a read of the leaf, and return its cost.
+	pub fn read(&mut self, key: TreeKey) -> u64 {
+		let mut cost = 0;
+		if self.branches.insert(key.stem()) {
+			cost += WITNESS_BRANCH_COST;
+		}
+		if self.chunks.insert(key) {
+			cost += WITNESS_CHUNK_COST;
+		}
+		cost
+	}
+
+	/// Record a write of the leaf, and return its cost. Whether the leaf had
+	/// no value before is given by `fill`. A write is also a read, which is
+	/// charged separately with [AccessWitness::read].
+	pub fn write(&mut self, key: TreeKey, fill: bool) -> u64 {
+		let mut cost = 0;
+		if self.edited_branches.insert(key.stem()) {
+			cost += SUBTREE_EDIT_COST;
+		}
+		if self.edited_chunks.insert(key) {
+			cost += CHUNK_EDIT_COST;
+			if fill {
+				cost += CHUNK_FILL_COST;
+			}
+		}
+		cost
+	}
+
+	/// Whether the leaf was accessed.
+	pub fn contains(&self, key: &TreeKey) -> bool {
+		self.chunks.contains(key)
+	}
+
+	/// Accessed leaves.
+	pub fn keys(&self) -> impl Iterator<Item = &TreeKey> {
+		self.chunks.iter()
+	}
+}
@@ -0,0 +1,255 @@
+//! Inspectors that can modify the execution, enabled by the `inspector`
+//! feature.
+//!
+//! An [Inspector] is shared by an [InspectedEtable], which calls it before
+//! each opcode, and an [InspectorMiddleware] of a [crate::MiddlewareInvoker],
+//! which calls it when the transaction and its substacks are entered and
+//! exited. The etable and the invoker wrap the components used without an
+//! inspector:
+//!
+//! ```ignore
+//! let inspector = RefCell::new(inspector);
+//! let etable = InspectedEtable::new((gas_etable, exec_etable), &inspector);
+//! let resolver = EtableResolver::new(&config, &precompiles, &etable);
+//! let middleware = InspectorMiddleware::new(&inspector);
+//! let invoker = MiddlewareInvoker::new(Invoker::new(&config, &resolver), middleware);
+//! ```
+
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use evm_interpreter::{
+	error::{Capture, ExitError, ExitResult},
+	etable::{Control, EtableSet},
+	machine::Machine,
+	opcode::Opcode,
+	Instruction, Interpreter,
+};
+
+use crate::{
+	invoker::{Invoker as InvokerT, InvokerControl, InvokerMiddleware},
+	standard::SubstackInvoke,
+};
+
+/// Hooks that can modify the execution at defined points.
+pub trait Inspector<S, H, Tr> {
+	/// Called before each opcode is evaluated. The machine can be modified,
+	/// for example to patch stack values. Return a control to use it instead
+	/// of evaluating the opcode, for example `Control::Exit(Err(ExitError::Reverted))`
+	/// to force-revert the frame.
+	fn step(
+		&mut self,
+		_machine: &mut Machine<S>,
+		_handler: &mut H,
+		_opcode: Opcode,
+		_position: usize,
+	) -> Option<Control<Tr>> {
+		None
+	}
+
+	/// Called when the call frame of the transaction is entered, with its
+	/// machine, before any opcode is evaluated. Not called if the transaction
+	/// exits directly, for example for a call to a precompile.
+	fn enter_transact(&mut self, _machine: &Machine<S>, _handler: &mut H) {}
+
+	/// Called when the transaction is finalized, with the state of its call
+	/// frame and the exit result.
+	fn exit_transact(&mut self, _state: &S, _result: &ExitResult, _handler: &mut H) {}
+
+	/// Called when a substack is about to be entered. Return an exit result
+	/// and return data to skip it, as if the callee had exited with them
+	/// without executing anything.
+	fn enter_substack(
+		&mut self,
+		_invoke: &SubstackInvoke,
+		_handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		None
+	}
+
+	/// Called when a substack exits, before it is merged into the parent. The
+	/// state is the state of the exited substack. The exit result and the
+	/// return data can be overwritten.
+	fn exit_substack(
+		&mut self,
+		_invoke: &SubstackInvoke,
+		_state: &S,
+		_result: &mut ExitResult,
+		_retval: &mut Vec<u8>,
+		_handler: &mut H,
+	) {
+	}
+}
+
+/// An etable set that calls [Inspector::step] before each opcode.
+pub struct InspectedEtable<'inspector, ES, N> {
+	etable: ES,
+	inspector: &'inspector RefCell<N>,
+}
+
+impl<'inspector, ES, N> InspectedEtable<'inspector, ES, N> {
+	pub fn new(etable: ES, inspector: &'inspector RefCell<N>) -> Self {
+		Self { etable, inspector }
+	}
+}
+
+impl<'inspector, ES, N> EtableSet for InspectedEtable<'inspector, ES, N>
+where
+	ES: EtableSet,
+	N: Inspector<ES::State, ES::Handle, ES::Trap>,
+{
+	type State = ES::State;
+	type Handle = ES::Handle;
+	type Trap = ES::Trap;
+
+	fn eval(
+		&self,
+		machine: &mut Machine<ES::State>,
+		handle: &mut ES::Handle,
+		opcode: Opcode,
+		position: usize,
+	) -> Control<ES::Trap> {
+		if let Some(control) = self
+			.inspector
+			.borrow_mut()
+			.step(machine, handle, opcode, position)
+		{
+			return control;
+		}
+
+		self.etable.eval(machine, handle, opcode, position)
+	}
+
+	fn eval_instruction(
+		&self,
+		machine: &mut Machine<ES::State>,
+		handle: &mut ES::Handle,
+		instruction: &Instruction,
+	) -> Control<ES::Trap> {
+		if let Some(control) = self.inspector.borrow_mut().step(
+			machine,
+			handle,
+			instruction.opcode,
+			instruction.position,
+		) {
+			return control;
+		}
+
+		self.etable.eval_instruction(machine, handle, instruction)
+	}
+}
+
+/// An invoker middleware that calls [Inspector::enter_transact],
+/// [Inspector::exit_transact], [Inspector::enter_substack] and
+/// [Inspector::exit_substack] around the transaction and the substacks, in a
+/// [crate::MiddlewareInvoker].
+pub struct InspectorMiddleware<'inspector, N> {
+	inspector: &'inspector RefCell<N>,
+}
+
+impl<'inspector, N> InspectorMiddleware<'inspector, N> {
+	pub fn new(inspector: &'inspector RefCell<N>) -> Self {
+		Self { inspector }
+	}
+}
+
+impl<'inspector, H, Tr, I, N> InvokerMiddleware<H, Tr, I> for InspectorMiddleware<'inspector, N>
+where
+	I: InvokerT<H, Tr, SubstackInvoke = SubstackInvoke>,
+	N: Inspector<I::State, H, Tr>,
+{
+	fn new_transact(
+		&self,
+		invoker: &I,
+		args: I::TransactArgs,
+		handler: &mut H,
+	) -> Result<
+		(
+			I::TransactInvoke,
+			InvokerControl<I::Interpreter, (ExitResult, (I::State, Vec<u8>))>,
+		),
+		ExitError,
+	> {
+		let (invoke, control) = invoker.new_transact(args, handler)?;
+		if let InvokerControl::Enter(interpreter) = &control {
+			self.inspector
+				.borrow_mut()
+				.enter_transact(interpreter.machine(), handler);
+		}
+
+		Ok((invoke, control))
+	}
+
+	fn finalize_transact(
+		&self,
+		invoker: &I,
+		invoke: &I::TransactInvoke,
+		exit: ExitResult,
+		machine: (I::State, Vec<u8>),
+		handler: &mut H,
+	) -> Result<I::TransactValue, ExitError> {
+		self.inspector
+			.borrow_mut()
+			.exit_transact(&machine.0, &exit, handler);
+
+		invoker.finalize_transact(invoke, exit, machine, handler)
+	}
+
+	fn enter_substack(
+		&self,
+		invoker: &I,
+		trap: Tr,
+		machine: &mut I::Interpreter,
+		handler: &mut H,
+		depth: usize,
+	) -> Capture<
+		Result<
+			(
+				I::SubstackInvoke,
+				InvokerControl<I::Interpreter, (ExitResult, (I::State, Vec<u8>))>,
+			),
+			ExitError,
+		>,
+		I::Interrupt,
+	> {
+		match invoker.enter_substack(trap, machine, handler, depth) {
+			Capture::Exit(Ok((invoke, control))) => {
+				let skipped = self.inspector.borrow_mut().enter_substack(&invoke, handler);
+
+				let control = match (skipped, control) {
+					(Some((result, retval)), InvokerControl::Enter(interpreter)) => {
+						let (state, _) = interpreter.deconstruct();
+						InvokerControl::DirectExit((result, (state, retval)))
+					}
+					(Some((result, retval)), InvokerControl::DirectExit((_, (state, _)))) => {
+						InvokerControl::DirectExit((result, (state, retval)))
+					}
+					(None, control) => control,
+				};
+
+				Capture::Exit(Ok((invoke, control)))
+			}
+			ret => ret,
+		}
+	}
+
+	fn exit_substack(
+		&self,
+		invoker: &I,
+		mut result: ExitResult,
+		(state, mut retval): (I::State, Vec<u8>),
+		trap_data: I::SubstackInvoke,
+		parent: &mut I::Interpreter,
+		handler: &mut H,
+	) -> Result<(), ExitError> {
+		self.inspector.borrow_mut().exit_substack(
+			&trap_data,
+			&state,
+			&mut result,
+			&mut retval,
+			handler,
+		);
+
+		invoker.exit_substack(result, (state, retval), trap_data, parent, handler)
+	}
+}
@@ -0,0 +1,120 @@
+use core::cmp::min;
+
+use evm_interpreter::{
+	error::ExitError,
+	runtime::{RuntimeBackend, RuntimeEnvironment},
+};
+use primitive_types::U256;
+
+use super::{TransactArgs, TransactInvoke};
+use crate::standard::{BaseFeeSink, Config};
+
+/// Fee paid by a transaction, as settled by a [FeePolicy].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub struct TransactFee {
+	/// Fee paid by the fee payer of the transaction.
+	pub effective_fee: U256,
+	/// Base fee portion of the effective fee.
+	pub base_fee: U256,
+}
+
+/// How a transaction pays for its gas. The invoker calls the policy for all
+/// transactions except system and deposit transactions.
+///
+/// Chains with different fee handling, for example partial fee rebates, fee
+/// currencies or operator cost surcharges, can implement this instead of
+/// reimplementing the invoker.
+pub trait FeePolicy<H> {
+	/// Data fee of the transaction on top of the gas fee, for example the L1
+	/// data fee of a rollup. It is set as [TransactInvoke::data_fee] and
+	/// withdrawn with the gas fee, and is not refunded. An error makes the
+	/// transaction invalid. The default is zero.
+	fn data_fee(
+		&self,
+		_args: &TransactArgs,
+		_handler: &H,
+		_config: &Config,
+	) -> Result<U256, ExitError> {
+		Ok(U256::zero())
+	}
+
+	/// Withdraw the fee for the gas limit and the data fee, before the
+	/// transaction executes. An error makes the transaction invalid, with no
+	/// state change applied.
+	fn withdraw_fee(
+		&self,
+		invoke: &TransactInvoke,
+		handler: &mut H,
+		config: &Config,
+	) -> Result<(), ExitError>;
+
+	/// Refund the fee of the gas left, and pay the block producer, after the
+	/// transaction executes. Return the fee paid.
+	fn settle_fee(
+		&self,
+		invoke: &TransactInvoke,
+		left_gas: U256,
+		handler: &mut H,
+		config: &Config,
+	) -> TransactFee;
+}
+
+/// Fee policy of Ethereum. The gas limit is paid at the gas price upfront,
+/// and the gas left is refunded at the same price. With EIP-1559, the block
+/// coinbase gets the priority fee, and the base fee goes to
+/// [Config::base_fee_sink]. Otherwise, the coinbase gets the whole fee.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EthereumFeePolicy;
+
+impl<H: RuntimeEnvironment + RuntimeBackend> FeePolicy<H> for EthereumFeePolicy {
+	fn withdraw_fee(
+		&self,
+		invoke: &TransactInvoke,
+		handler: &mut H,
+		_config: &Config,
+	) -> Result<(), ExitError> {
+		let fee = invoke
+			.gas_limit
+			.saturating_mul(invoke.gas_price)
+			.saturating_add(invoke.data_fee);
+		handler.withdrawal(invoke.fee_payer, fee)
+	}
+
+	fn settle_fee(
+		&self,
+		invoke: &TransactInvoke,
+		left_gas: U256,
+		handler: &mut H,
+		config: &Config,
+	) -> TransactFee {
+		let used_gas = invoke.gas_limit.saturating_sub(left_gas);
+		let base_fee = if config.eip_1559_enabled {
+			// EIP-1559 updated the fee system so that miners only get to keep the
+			// priority fee. The base fee goes to the configured sink.
+			let base_fee_per_gas = handler.block_base_fee_per_gas();
+			used_gas.saturating_mul(min(invoke.gas_price, base_fee_per_gas))
+		} else {
+			U256::zero()
+		};
+
+		let refunded_fee = left_gas.saturating_mul(invoke.gas_price);
+		handler.deposit(invoke.fee_payer, refunded_fee);
+
+		// Reward coinbase address
+		let effective_fee = used_gas.saturating_mul(invoke.gas_price);
+		let coinbase_reward = effective_fee.saturating_sub(base_fee);
+		handler.deposit(handler.block_coinbase(), coinbase_reward);
+		if let BaseFeeSink::Address(sink) = config.base_fee_sink {
+			handler.deposit(sink, base_fee);
+		}
+
+		TransactFee {
+			effective_fee,
+			base_fee,
+		}
+	}
+}
@@ -0,0 +1,374 @@
+use alloc::{sync::Arc, vec::Vec};
+
+use evm_interpreter::{
+	error::{CreateScheme, ExitError, ExitException, ExitResult},
+	runtime::{
+		Context, RuntimeBackend, RuntimeEnvironment, RuntimeState, TransactionContext, Transfer,
+	},
+};
+use primitive_types::U256;
+
+use super::{
+	routines, FeePolicy, InvokerState, Resolver, TransactArgs, TransactFee, TransactInvoke,
+};
+use crate::{backend::TransactionalBackend, invoker::InvokerControl, standard::Config};
+
+/// A kind of transaction that the standard [crate::standard::Invoker] can
+/// execute. [TransactArgs] is the kind of the Ethereum transactions.
+///
+/// Embedders can implement this for new transaction types, for example
+/// privileged system transactions or account abstraction bundles, and reuse
+/// the call stack of the invoker. The invoker first calls
+/// [TransactionKind::prefetch] and [TransactionKind::start], then pushes the transaction substate and calls
+/// [TransactionKind::enter_machine]. After execution, it pops the substate
+/// and calls [TransactionKind::settle_fee].
+pub trait TransactionKind<'config, H, R: Resolver<H>>: Sized {
+	/// Intrinsic gas of the transaction, charged before any execution.
+	fn intrinsic_gas(&self, config: &Config) -> u64;
+
+	/// Give the backend prefetch hints of the state the transaction is known
+	/// to access, before anything is read. The default gives no hints.
+	fn prefetch(&self, _handler: &H) {}
+
+	/// Apply the state changes made before execution, outside of the
+	/// transaction substate, such as the fee withdrawal and the nonce
+	/// increase. An error makes the transaction invalid, and should leave the
+	/// state unchanged.
+	fn start<F: FeePolicy<H>>(
+		&self,
+		fee_policy: &F,
+		config: &Config,
+		resolver: &R,
+		handler: &mut H,
+	) -> Result<TransactInvoke, ExitError>;
+
+	/// Create the entry machine of the transaction, within the transaction
+	/// substate. This charges the intrinsic gas. An error makes the
+	/// transaction invalid.
+	#[allow(clippy::type_complexity)]
+	fn enter_machine(
+		self,
+		invoke: &TransactInvoke,
+		config: &'config Config,
+		resolver: &R,
+		handler: &mut H,
+	) -> Result<InvokerControl<R::Interpreter, (ExitResult, (R::State, Vec<u8>))>, ExitError>;
+
+	/// Refund the fee of the gas left and pay the block producer, after the
+	/// transaction substate is popped. The default settles with the fee
+	/// policy, except for system and deposit transactions, which pay no fee.
+	fn settle_fee<F: FeePolicy<H>>(
+		invoke: &TransactInvoke,
+		left_gas: U256,
+		fee_policy: &F,
+		handler: &mut H,
+		config: &Config,
+	) -> TransactFee {
+		if invoke.system || invoke.deposit {
+			TransactFee::default()
+		} else {
+			fee_policy.settle_fee(invoke, left_gas, handler, config)
+		}
+	}
+}
+
+impl<'config, H, R> TransactionKind<'config, H, R> for TransactArgs
+where
+	R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+	H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+	R: Resolver<H>,
+{
+	fn intrinsic_gas(&self, config: &Config) -> u64 {
+		crate::standard::intrinsic_gas(self, config)
+	}
+
+	fn prefetch(&self, handler: &H) {
+		let (addresses, keys) = self.prefetch_hints();
+		handler.prefetch(&addresses, &keys);
+	}
+
+	fn start<F: FeePolicy<H>>(
+		&self,
+		fee_policy: &F,
+		config: &Config,
+		resolver: &R,
+		handler: &mut H,
+	) -> Result<TransactInvoke, ExitError> {
+		let caller = self.caller();
+
+		if !self.is_system() && routines::is_max_nonce(caller, handler) {
+			return Err(ExitException::MaxNonce.into());
+		}
+
+		// Checked again when the entry machine is created, but before any state
+		// change here.
+		let intrinsic_gas = crate::standard::intrinsic_gas(self, config)
+			.max(crate::standard::floor_gas(self, config));
+		if U256::from(intrinsic_gas) > self.gas_limit() {
+			return Err(ExitException::OutOfGas.into());
+		}
+
+		let create_address = match self {
+			TransactArgs::Call { .. } => None,
+			TransactArgs::Create {
+				caller,
+				salt,
+				init_code,
+				..
+			} => Some(match salt {
+				Some(salt) => {
+					let scheme = CreateScheme::Create2 {
+						caller: *caller,
+						code_hash: handler.keccak256(init_code),
+						salt: *salt,
+					};
+					resolver.create_address(&scheme, handler)
+				}
+				None => {
+					let scheme = CreateScheme::Legacy { caller: *caller };
+					resolver.create_address(&scheme, handler)
+				}
+			}),
+		};
+
+		let mut invoke = TransactInvoke {
+			gas_limit: self.gas_limit(),
+			gas_price: self.gas_price(),
+			caller,
+			fee_payer: self.fee_payer(),
+			system: self.is_system(),
+			deposit: self.is_deposit(),
+			data_fee: U256::zero(),
+			create_address,
+		};
+
+		if !invoke.system && !invoke.deposit {
+			invoke.data_fee = fee_policy.data_fee(self, handler, config)?;
+			fee_policy.withdraw_fee(&invoke, handler, config)?;
+		}
+
+		// The minted value is deposited outside of the transaction substate, so
+		// that it is kept if the execution fails.
+		if let Some(mint) = self.mint() {
+			handler.deposit(caller, mint);
+		}
+
+		// Increase the nonce after the create address is derived from it.
+		if !self.is_system() {
+			routines::inc_nonce(caller, handler)?;
+		}
+
+		Ok(invoke)
+	}
+
+	fn enter_machine(
+		self,
+		invoke: &TransactInvoke,
+		config: &'config Config,
+		resolver: &R,
+		handler: &mut H,
+	) -> Result<InvokerControl<R::Interpreter, (ExitResult, (R::State, Vec<u8>))>, ExitError> {
+		let caller = self.caller();
+		let value = self.value();
+		let address = match &self {
+			TransactArgs::Call { address, .. } => *address,
+			// Always derived by `start` for a create transaction.
+			TransactArgs::Create { .. } => invoke.create_address.unwrap_or_default(),
+		};
+
+		let context = Context {
+			caller,
+			address,
+			apparent_value: value,
+		};
+		let transaction_context = TransactionContext {
+			origin: caller,
+			gas_price: invoke.gas_price,
+			chain_id: config.effective_chain_id(handler),
+			prevrandao: config.has_prevrandao,
+		};
+		let transfer = Transfer {
+			source: caller,
+			target: address,
+			value,
+		};
+		let runtime_state = RuntimeState {
+			context,
+			transaction_context: Arc::new(transaction_context),
+			retbuf: Vec::new(),
+			authorized: None,
+		};
+
+		match self {
+			TransactArgs::Call {
+				caller,
+				address,
+				data,
+				gas_limit,
+				access_list,
+				..
+			} => {
+				for (address, keys) in &access_list {
+					handler.mark_hot(*address, None);
+					for key in keys {
+						handler.mark_hot(*address, Some(*key));
+					}
+				}
+
+				let state = <R::State>::new_transact_call(
+					runtime_state,
+					gas_limit,
+					&data,
+					&access_list,
+					config,
+				)?;
+
+				let machine = routines::make_enter_call_machine(
+					config,
+					resolver,
+					address,
+					data,
+					Some(transfer),
+					state,
+					handler,
+				)?;
+
+				if config.increase_state_access_gas {
+					if config.warm_coinbase_address {
+						let coinbase = handler.block_coinbase();
+						handler.mark_hot(coinbase, None);
+					}
+					handler.mark_hot(caller, None);
+					handler.mark_hot(address, None);
+					resolver.warm_precompiles(handler);
+				}
+
+				Ok(machine)
+			}
+			TransactArgs::Create {
+				caller,
+				init_code,
+				gas_limit,
+				access_list,
+				..
+			} => {
+				let state = <R::State>::new_transact_create(
+					runtime_state,
+					gas_limit,
+					&init_code,
+					&access_list,
+					config,
+				)?;
+
+				let machine = routines::make_enter_create_machine(
+					config, resolver, caller, init_code, transfer, state, handler,
+				)?;
+
+				if config.increase_state_access_gas {
+					resolver.warm_precompiles(handler);
+				}
+
+				Ok(machine)
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{collections::BTreeSet, vec::Vec};
+
+	use evm_interpreter::error::ExitError;
+	use primitive_types::U256;
+
+	use crate::{
+		backend::{OverlayedBackend, RuntimeBaseBackend},
+		standard::{
+			testing::{backend, call_args, etable, CALLER, CONTRACT},
+			Config, EtableResolver, EthereumFeePolicy, FeePolicy, Invoker, TransactArgs,
+			TransactFee, TransactInvoke,
+		},
+	};
+
+	/// Ethereum fee policy with a data fee of 1000.
+	struct DataFeePolicy;
+
+	impl<H> FeePolicy<H> for DataFeePolicy
+	where
+		EthereumFeePolicy: FeePolicy<H>,
+	{
+		fn data_fee(
+			&self,
+			_args: &TransactArgs,
+			_handler: &H,
+			_config: &Config,
+		) -> Result<U256, ExitError> {
+			Ok(U256::from(1000))
+		}
+
+		fn withdraw_fee(
+			&self,
+			invoke: &TransactInvoke,
+			handler: &mut H,
+			config: &Config,
+		) -> Result<(), ExitError> {
+			EthereumFeePolicy.withdraw_fee(invoke, handler, config)
+		}
+
+		fn settle_fee(
+			&self,
+			invoke: &TransactInvoke,
+			left_gas: U256,
+			handler: &mut H,
+			config: &Config,
+		) -> TransactFee {
+			EthereumFeePolicy.settle_fee(invoke, left_gas, handler, config)
+		}
+	}
+
+	fn args(gas_limit: u64, gas_price: u64, deposit: Option<U256>) -> TransactArgs {
+		let mut args = call_args(CONTRACT, Vec::new(), gas_limit, gas_price);
+		if let TransactArgs::Call { mint, .. } = &mut args {
+			*mint = deposit;
+		}
+		args
+	}
+
+	#[test]
+	fn invalid_transactions_neither_mint_nor_charge_the_data_fee() {
+		let mut base = backend();
+		// Enough for the data fee, but not for the gas fee on top of it.
+		base.set_balance(CALLER, U256::from(21_000 + 999));
+
+		let config = Config::cancun();
+		let etable = etable();
+		let resolver = EtableResolver::new(&config, &(), &etable);
+		let invoker = Invoker::new_with_fee_policy(&config, &resolver, DataFeePolicy);
+
+		let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+		let result = crate::transact_hybrid(args(21_000, 1, None), None, &mut backend, &invoker);
+		assert!(result.is_err());
+		assert_eq!(backend.balance(CALLER), U256::from(21_000 + 999));
+		assert_eq!(backend.nonce(CALLER), U256::zero());
+
+		// A deposit below the intrinsic gas.
+		let result = crate::transact_hybrid(
+			args(20_000, 0, Some(U256::from(5))),
+			None,
+			&mut backend,
+			&invoker,
+		);
+		assert!(result.is_err());
+		assert_eq!(backend.balance(CALLER), U256::from(21_000 + 999));
+
+		let value = crate::transact_hybrid(
+			args(21_000, 0, Some(U256::from(5))),
+			None,
+			&mut backend,
+			&invoker,
+		)
+		.unwrap();
+		assert!(value.exit.is_ok());
+		assert_eq!(backend.balance(CALLER), U256::from(21_000 + 999 + 5));
+	}
+}
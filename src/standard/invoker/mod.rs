@@ -1,33 +1,46 @@
+mod fee;
+mod kind;
 mod resolver;
 pub mod routines;
 mod state;
+mod system;
 
-use alloc::{rc::Rc, vec::Vec};
-use core::{cmp::min, convert::Infallible};
+use alloc::vec::Vec;
+use core::{convert::Infallible, marker::PhantomData};
 
 use evm_interpreter::{
 	error::{
-		CallCreateTrap, CallCreateTrapData, CallTrapData, Capture, CreateScheme, CreateTrapData,
-		ExitError, ExitException, ExitResult, ExitSucceed, TrapConsume,
+		CallCreateTrap, CallCreateTrapData, CallTrapData, Capture, CreateTrapData, ExitError,
+		ExitException, ExitResult, ExitSucceed, TrapConsume,
 	},
 	opcode::Opcode,
 	runtime::{
-		Context, GasState, RuntimeBackend, RuntimeEnvironment, RuntimeState, SetCodeOrigin,
-		TransactionContext, Transfer,
+		Address, Context, GasState, Log, RuntimeBackend, RuntimeEnvironment, RuntimeState,
+		SetCodeOrigin,
 	},
 	Interpreter,
 };
 use primitive_types::{H160, H256, U256};
-use sha3::{Digest, Keccak256};
 
 pub use self::{
-	resolver::{EtableResolver, PrecompileSet, Resolver},
+	fee::{EthereumFeePolicy, FeePolicy, TransactFee},
+	kind::TransactionKind,
+	resolver::{EtableResolver, Precompile, PrecompileRegistry, PrecompileSet, Resolver},
 	state::InvokerState,
+	system::{
+		history_block_hash, BEACON_ROOTS_ADDRESS, CONSOLIDATION_REQUEST_ADDRESS,
+		DEPOSIT_CONTRACT_ADDRESS, HISTORY_SERVE_WINDOW, HISTORY_STORAGE_ADDRESS, SYSTEM_ADDRESS,
+		SYSTEM_CALL_GAS_LIMIT, WITHDRAWAL_REQUEST_ADDRESS,
+	},
 };
+#[cfg(feature = "counters")]
+use crate::standard::Counters;
+#[cfg(feature = "frames")]
+use crate::standard::FrameGas;
 use crate::{
 	backend::TransactionalBackend,
 	invoker::{Invoker as InvokerT, InvokerControl},
-	standard::Config,
+	standard::{Bloom, Config, RevertReason},
 	MergeStrategy,
 };
 
@@ -49,28 +62,66 @@ impl IntoCallCreateTrap for Opcode {
 	}
 }
 
-/// The invoke used in a substack.
+/// The invoke used in a substack, returned by
+/// [routines::enter_call_substack] and [routines::enter_create_substack], and
+/// given back to [routines::exit_substack].
 pub enum SubstackInvoke {
-	Call { trap: CallTrapData },
+	/// A call, with the opcode that made it.
+	Call { trap: CallTrapData, opcode: Opcode },
+	/// A create, with the address of the new contract.
 	Create { trap: CreateTrapData, address: H160 },
 }
 
 /// Return value of a transaction.
-pub enum TransactValue {
-	Call {
-		/// The exit result. If we return a value, then it will be an
-		/// `ExitSucceed`.
-		succeed: ExitSucceed,
-		/// The return value, if any.
-		retval: Vec<u8>,
-	},
-	Create {
-		/// The exit result. If we return a value, then it will be an
-		/// `ExitSucceed`.
-		succeed: ExitSucceed,
-		/// The contract address created.
-		address: H160,
-	},
+///
+/// A transaction that reverts or fails during execution is still a completed
+/// transaction that pays fees, so it is returned as a value with `exit` set to
+/// the error. Only invalid transactions and fatal errors are returned as
+/// errors of [crate::transact].
+#[derive(Clone, Debug)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub struct TransactValue {
+	/// The exit result of the execution.
+	pub exit: ExitResult,
+	/// The return value, or the revert data. For a create transaction, this
+	/// is the deployed code.
+	pub retval: Vec<u8>,
+	/// Decoded revert data, if the transaction reverted with a Solidity error
+	/// or panic.
+	pub revert_reason: Option<RevertReason>,
+	/// The contract address created, if this is a successful create
+	/// transaction.
+	pub create_address: Option<H160>,
+	/// Gas used by the transaction, after refunds.
+	pub used_gas: U256,
+	/// Gas refunded to the transaction sender on top of the gas left, after
+	/// the refund cap.
+	pub refunded_gas: U256,
+	/// Fee paid by the fee payer, as settled by the [FeePolicy]. With
+	/// the default policy, used gas times the effective gas price.
+	pub effective_fee: U256,
+	/// Base fee portion of the effective fee, burned or deposited to the
+	/// [crate::standard::BaseFeeSink]. Zero without EIP-1559.
+	pub base_fee: U256,
+	/// Data fee charged by [FeePolicy::data_fee], on top of the effective
+	/// fee.
+	pub data_fee: U256,
+	/// Logs emitted by the transaction. Empty if the execution did not
+	/// succeed.
+	pub logs: Vec<Log>,
+	/// Bloom of the emitted logs.
+	pub logs_bloom: Bloom,
+	/// Execution counters of the transaction, empty if the invoker state does
+	/// not count.
+	#[cfg(feature = "counters")]
+	pub counters: Counters,
+	/// Gas usage of each substack of the transaction, in the order they
+	/// exited, so that a frame comes after its own substacks.
+	#[cfg(feature = "frames")]
+	pub frames: Vec<FrameGas>,
 }
 
 /// The invoke used in a top-layer transaction stack.
@@ -79,17 +130,34 @@ pub struct TransactInvoke {
 	pub gas_limit: U256,
 	pub gas_price: U256,
 	pub caller: H160,
+	pub fee_payer: H160,
+	pub system: bool,
+	pub deposit: bool,
+	pub data_fee: U256,
 }
 
-/// Transaction arguments.
+/// Transaction arguments, generic over the address type (see [Address]), so
+/// that custom invokers of other address types can share them. The standard
+/// invoker executes transactions with [H160] addresses.
 #[derive(Clone, Debug)]
-pub enum TransactArgs {
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub enum TransactArgs<A = H160> {
 	/// A call transaction.
 	Call {
 		/// Transaction sender.
-		caller: H160,
+		caller: A,
 		/// Transaction target.
-		address: H160,
+		address: A,
+		/// Transaction nonce, checked against the sender's nonce in
+		/// [crate::standard::validate_transaction].
+		nonce: Option<U256>,
+		/// Chain ID the transaction is signed for, checked in
+		/// [crate::standard::validate_transaction]. `None` for a transaction
+		/// without replay protection.
+		chain_id: Option<U256>,
 		/// Transaction value.
 		value: U256,
 		/// Transaction call data.
@@ -99,12 +167,27 @@ pub enum TransactArgs {
 		/// Transaction gas price.
 		gas_price: U256,
 		/// Access list information, in the format of (address, storage keys).
-		access_list: Vec<(H160, Vec<H256>)>,
+		access_list: Vec<(A, Vec<H256>)>,
+		/// Whether this is a system transaction. See [TransactArgs::is_system].
+		system: bool,
+		/// Value minted to the sender, for a deposit transaction. See
+		/// [TransactArgs::is_deposit].
+		mint: Option<U256>,
+		/// Account paying the fees, if not the sender. See
+		/// [TransactArgs::fee_payer].
+		fee_payer: Option<A>,
 	},
 	/// A create transaction.
 	Create {
 		/// Transaction sender.
-		caller: H160,
+		caller: A,
+		/// Transaction nonce, checked against the sender's nonce in
+		/// [crate::standard::validate_transaction].
+		nonce: Option<U256>,
+		/// Chain ID the transaction is signed for, checked in
+		/// [crate::standard::validate_transaction]. `None` for a transaction
+		/// without replay protection.
+		chain_id: Option<U256>,
 		/// Transaction value.
 		value: U256,
 		/// Init code.
@@ -116,11 +199,19 @@ pub enum TransactArgs {
 		/// Transaction gas price.
 		gas_price: U256,
 		/// Access list information, in the format of (address, storage keys).
-		access_list: Vec<(H160, Vec<H256>)>,
+		access_list: Vec<(A, Vec<H256>)>,
+		/// Whether this is a system transaction. See [TransactArgs::is_system].
+		system: bool,
+		/// Value minted to the sender, for a deposit transaction. See
+		/// [TransactArgs::is_deposit].
+		mint: Option<U256>,
+		/// Account paying the fees, if not the sender. See
+		/// [TransactArgs::fee_payer].
+		fee_payer: Option<A>,
 	},
 }
 
-impl TransactArgs {
+impl<A: Address> TransactArgs<A> {
 	/// Transaction gas limit.
 	pub fn gas_limit(&self) -> U256 {
 		match self {
@@ -138,21 +229,55 @@ impl TransactArgs {
 	}
 
 	/// Access list information.
-	pub fn access_list(&self) -> &Vec<(H160, Vec<H256>)> {
+	pub fn access_list(&self) -> &Vec<(A, Vec<H256>)> {
 		match self {
 			Self::Call { access_list, .. } => access_list,
 			Self::Create { access_list, .. } => access_list,
 		}
 	}
 
+	/// Accounts and storage slots the transaction is known to access before
+	/// it executes: the sender, the callee and the access list.
+	pub fn prefetch_hints(&self) -> (Vec<A>, Vec<(A, H256)>) {
+		let mut addresses = Vec::with_capacity(self.access_list().len() + 2);
+		let mut keys = Vec::new();
+
+		addresses.push(self.caller());
+		if let Self::Call { address, .. } = self {
+			addresses.push(*address);
+		}
+		for (address, slots) in self.access_list() {
+			addresses.push(*address);
+			keys.extend(slots.iter().map(|slot| (*address, *slot)));
+		}
+
+		(addresses, keys)
+	}
+
 	/// Transaction sender.
-	pub fn caller(&self) -> H160 {
+	pub fn caller(&self) -> A {
 		match self {
 			Self::Call { caller, .. } => *caller,
 			Self::Create { caller, .. } => *caller,
 		}
 	}
 
+	/// Transaction nonce.
+	pub fn nonce(&self) -> Option<U256> {
+		match self {
+			Self::Call { nonce, .. } => *nonce,
+			Self::Create { nonce, .. } => *nonce,
+		}
+	}
+
+	/// Chain ID the transaction is signed for.
+	pub fn chain_id(&self) -> Option<U256> {
+		match self {
+			Self::Call { chain_id, .. } => *chain_id,
+			Self::Create { chain_id, .. } => *chain_id,
+		}
+	}
+
 	/// Transaction value.
 	pub fn value(&self) -> U256 {
 		match self {
@@ -160,6 +285,49 @@ impl TransactArgs {
 			Self::Create { value, .. } => *value,
 		}
 	}
+
+	/// Whether this is a system transaction. A system transaction does not
+	/// withdraw the gas fee, does not pay the coinbase and does not increase
+	/// the sender nonce, but otherwise executes normally.
+	pub fn is_system(&self) -> bool {
+		match self {
+			Self::Call { system, .. } => *system,
+			Self::Create { system, .. } => *system,
+		}
+	}
+
+	/// Account paying the fees. The fees are withdrawn from and refunded to
+	/// this account, while the nonce and the caller are still the ones of the
+	/// sender. This is the sender unless the transaction is sponsored.
+	pub fn fee_payer(&self) -> A {
+		match self {
+			Self::Call {
+				caller, fee_payer, ..
+			} => fee_payer.unwrap_or(*caller),
+			Self::Create {
+				caller, fee_payer, ..
+			} => fee_payer.unwrap_or(*caller),
+		}
+	}
+
+	/// Value minted to the sender, for a deposit transaction.
+	pub fn mint(&self) -> Option<U256> {
+		match self {
+			Self::Call { mint, .. } => *mint,
+			Self::Create { mint, .. } => *mint,
+		}
+	}
+
+	/// Whether this is a deposit transaction, as of Optimism-style rollups. A
+	/// deposit transaction mints its value to the sender before execution,
+	/// and the minted value is kept even if the execution fails. It does not
+	/// buy gas: no fee is withdrawn, refunded or paid to the coinbase,
+	/// whatever the gas price is. The sender nonce is still increased, and
+	/// transaction validation is skipped, so that the deposit is always
+	/// included.
+	pub fn is_deposit(&self) -> bool {
+		self.mint().is_some()
+	}
 }
 
 /// Standard invoker.
@@ -170,29 +338,67 @@ impl TransactArgs {
 /// * `R`: Code resolver type, also handle precompiles. Usually
 ///   [EtableResolver] but can be customized.
 /// * `Tr`: Trap type, usually [crate::Opcode] but can be customized.
-pub struct Invoker<'config, 'resolver, R> {
+/// * `F`: Fee policy, usually [EthereumFeePolicy] but can be customized.
+/// * `K`: Transaction kind, usually [TransactArgs] but can be customized.
+pub struct Invoker<'config, 'resolver, R, F = EthereumFeePolicy, K = TransactArgs> {
 	config: &'config Config,
 	resolver: &'resolver R,
+	fee_policy: F,
+	_marker: PhantomData<fn(K)>,
 }
 
 impl<'config, 'resolver, R> Invoker<'config, 'resolver, R> {
 	/// Create a new standard invoker with the given config and resolver.
 	pub fn new(config: &'config Config, resolver: &'resolver R) -> Self {
-		Self { config, resolver }
+		Self::new_with_fee_policy(config, resolver, EthereumFeePolicy)
+	}
+}
+
+impl<'config, 'resolver, R, F, K> Invoker<'config, 'resolver, R, F, K> {
+	/// Create a new standard invoker with the given config, resolver and fee
+	/// policy.
+	pub fn new_with_fee_policy(
+		config: &'config Config,
+		resolver: &'resolver R,
+		fee_policy: F,
+	) -> Self {
+		Self {
+			config,
+			resolver,
+			fee_policy,
+			_marker: PhantomData,
+		}
+	}
+
+	/// Config of the invoker.
+	pub fn config(&self) -> &'config Config {
+		self.config
+	}
+
+	/// Code resolver of the invoker.
+	pub fn resolver(&self) -> &'resolver R {
+		self.resolver
+	}
+
+	/// Fee policy of the invoker.
+	pub fn fee_policy(&self) -> &F {
+		&self.fee_policy
 	}
 }
 
-impl<'config, 'resolver, H, R, Tr> InvokerT<H, Tr> for Invoker<'config, 'resolver, R>
+impl<'config, 'resolver, H, R, Tr, F, K> InvokerT<H, Tr> for Invoker<'config, 'resolver, R, F, K>
 where
 	R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
 	H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
 	R: Resolver<H>,
+	F: FeePolicy<H>,
+	K: TransactionKind<'config, H, R>,
 	Tr: TrapConsume<CallCreateTrap>,
 {
 	type State = R::State;
 	type Interpreter = R::Interpreter;
 	type Interrupt = Tr::Rest;
-	type TransactArgs = TransactArgs;
+	type TransactArgs = K;
 	type TransactInvoke = TransactInvoke;
 	type TransactValue = TransactValue;
 	type SubstackInvoke = SubstackInvoke;
@@ -208,150 +414,19 @@ where
 		),
 		ExitError,
 	> {
-		let caller = args.caller();
-		let gas_price = args.gas_price();
-
-		let gas_fee = args.gas_limit().saturating_mul(gas_price);
-		handler.withdrawal(caller, gas_fee)?;
-
-		handler.inc_nonce(caller)?;
-
-		let address = match &args {
-			TransactArgs::Call { address, .. } => *address,
-			TransactArgs::Create {
-				caller,
-				salt,
-				init_code,
-				..
-			} => match salt {
-				Some(salt) => {
-					let scheme = CreateScheme::Create2 {
-						caller: *caller,
-						code_hash: H256::from_slice(Keccak256::digest(init_code).as_slice()),
-						salt: *salt,
-					};
-					scheme.address(handler)
-				}
-				None => {
-					let scheme = CreateScheme::Legacy { caller: *caller };
-					scheme.address(handler)
-				}
-			},
-		};
-		let value = args.value();
-
-		let invoke = TransactInvoke {
-			gas_limit: args.gas_limit(),
-			gas_price: args.gas_price(),
-			caller: args.caller(),
-			create_address: match &args {
-				TransactArgs::Call { .. } => None,
-				TransactArgs::Create { .. } => Some(address),
-			},
-		};
-
-		handler.push_substate();
-
-		let context = Context {
-			caller,
-			address,
-			apparent_value: value,
-		};
-		let transaction_context = TransactionContext {
-			origin: caller,
-			gas_price,
-		};
-		let transfer = Transfer {
-			source: caller,
-			target: address,
-			value,
-		};
-		let runtime_state = RuntimeState {
-			context,
-			transaction_context: Rc::new(transaction_context),
-			retbuf: Vec::new(),
-		};
+		args.prefetch(handler);
 
-		let work = || -> Result<(TransactInvoke, _), ExitError> {
-			match args {
-				TransactArgs::Call {
-					caller,
-					address,
-					data,
-					gas_limit,
-					access_list,
-					..
-				} => {
-					for (address, keys) in &access_list {
-						handler.mark_hot(*address, None);
-						for key in keys {
-							handler.mark_hot(*address, Some(*key));
-						}
-					}
+		let invoke = args.start(&self.fee_policy, self.config, self.resolver, handler)?;
 
-					let state = <R::State>::new_transact_call(
-						runtime_state,
-						gas_limit,
-						&data,
-						&access_list,
-						self.config,
-					)?;
-
-					let machine = routines::make_enter_call_machine(
-						self.config,
-						self.resolver,
-						address,
-						data,
-						Some(transfer),
-						state,
-						handler,
-					)?;
-
-					if self.config.increase_state_access_gas {
-						if self.config.warm_coinbase_address {
-							let coinbase = handler.block_coinbase();
-							handler.mark_hot(coinbase, None);
-						}
-						handler.mark_hot(caller, None);
-						handler.mark_hot(address, None);
-					}
+		handler.push_substate();
 
-					Ok((invoke, machine))
-				}
-				TransactArgs::Create {
-					caller,
-					init_code,
-					gas_limit,
-					access_list,
-					..
-				} => {
-					let state = <R::State>::new_transact_create(
-						runtime_state,
-						gas_limit,
-						&init_code,
-						&access_list,
-						self.config,
-					)?;
-
-					let machine = routines::make_enter_create_machine(
-						self.config,
-						self.resolver,
-						caller,
-						init_code,
-						transfer,
-						state,
-						handler,
-					)?;
-
-					Ok((invoke, machine))
-				}
+		match args.enter_machine(&invoke, self.config, self.resolver, handler) {
+			Ok(machine) => Ok((invoke, machine)),
+			Err(err) => {
+				handler.pop_substate(MergeStrategy::Discard);
+				Err(err)
 			}
-		};
-
-		work().map_err(|err| {
-			handler.pop_substate(MergeStrategy::Discard);
-			err
-		})
+		}
 	}
 
 	fn finalize_transact(
@@ -361,32 +436,22 @@ where
 		(mut substate, retval): (R::State, Vec<u8>),
 		handler: &mut H,
 	) -> Result<Self::TransactValue, ExitError> {
-		let left_gas = substate.effective_gas();
-
-		let work = || -> Result<Self::TransactValue, ExitError> {
+		let work = || -> Result<(ExitSucceed, Option<H160>), ExitError> {
 			match result {
 				Ok(result) => {
 					if let Some(address) = invoke.create_address {
-						let retbuf = retval;
-
 						routines::deploy_create_code(
 							self.config,
 							address,
-							retbuf,
+							retval.clone(),
 							&mut substate,
 							handler,
 							SetCodeOrigin::Transaction,
 						)?;
 
-						Ok(TransactValue::Create {
-							succeed: result,
-							address,
-						})
+						Ok((result, Some(address)))
 					} else {
-						Ok(TransactValue::Call {
-							succeed: result,
-							retval,
-						})
+						Ok((result, None))
 					}
 				}
 				Err(result) => Err(result),
@@ -395,39 +460,67 @@ where
 
 		let result = work();
 
-		let refunded_gas = match result {
-			Ok(_) | Err(ExitError::Reverted) => left_gas,
-			Err(_) => U256::zero(),
+		let (left_gas, refunded_gas) = match result {
+			Ok(_) | Err(ExitError::Reverted) => {
+				let left_gas = substate.effective_gas();
+				(
+					left_gas,
+					left_gas.saturating_sub(U256::from(substate.gas())),
+				)
+			}
+			Err(_) => (U256::zero(), U256::zero()),
 		};
 
-		match &result {
+		let logs = match &result {
 			Ok(_) => {
+				let logs = handler.substate_logs();
 				handler.pop_substate(MergeStrategy::Commit);
+				logs
 			}
 			Err(_) => {
 				handler.pop_substate(MergeStrategy::Discard);
+				Vec::new()
 			}
+		};
+
+		let fee = K::settle_fee(
+			invoke,
+			left_gas,
+			&self.fee_policy,
+			handler,
+			substate.config(),
+		);
+
+		let touched = handler.take_touched();
+		if !substate.config().empty_considered_exists {
+			routines::delete_empty_accounts(touched, handler);
 		}
 
-		let refunded_fee = refunded_gas.saturating_mul(invoke.gas_price);
-		handler.deposit(invoke.caller, refunded_fee);
-		// Reward coinbase address
-		// EIP-1559 updated the fee system so that miners only get to keep the priority fee.
-		// The base fee is always burned.
-		let coinbase_gas_price = if substate.config().eip_1559_enabled {
-			invoke
-				.gas_price
-				.saturating_sub(handler.block_base_fee_per_gas())
-		} else {
-			invoke.gas_price
+		let (exit, create_address) = match result {
+			Ok((succeed, create_address)) => (Ok(succeed), create_address),
+			Err(ExitError::Fatal(fatal)) => return Err(ExitError::Fatal(fatal)),
+			Err(err) => (Err(err), None),
 		};
-		let coinbase_reward = invoke
-			.gas_limit
-			.saturating_mul(coinbase_gas_price)
-			.saturating_sub(refunded_fee);
-		handler.deposit(handler.block_coinbase(), coinbase_reward);
 
-		result
+		let used_gas = invoke.gas_limit.saturating_sub(left_gas);
+		let revert_reason = revert_reason(&exit, &retval);
+		Ok(TransactValue {
+			exit,
+			retval,
+			revert_reason,
+			create_address,
+			used_gas,
+			refunded_gas,
+			effective_fee: fee.effective_fee,
+			base_fee: fee.base_fee,
+			data_fee: invoke.data_fee,
+			logs_bloom: Bloom::from_logs(&logs),
+			logs,
+			#[cfg(feature = "counters")]
+			counters: substate.counters().cloned().unwrap_or_default(),
+			#[cfg(feature = "frames")]
+			frames: substate.take_frames(),
+		})
 	}
 
 	fn enter_substack(
@@ -446,8 +539,8 @@ where
 		>,
 		Self::Interrupt,
 	> {
-		fn l64(gas: U256) -> U256 {
-			gas - gas / U256::from(64)
+		fn l64(gas: u64) -> u64 {
+			gas - gas / 64
 		}
 
 		let opcode = match trap.consume() {
@@ -459,7 +552,11 @@ where
 			return Capture::Exit(Err(ExitException::CallTooDeep.into()));
 		}
 
-		let trap_data = match CallCreateTrapData::new_from(opcode, machine.machine_mut()) {
+		// `AUTHCALL` does not add the stipend of value transfers (EIP-3074).
+		let has_stipend = !matches!(opcode, CallCreateTrap::AuthCall);
+		let trap_opcode = opcode.opcode();
+
+		let trap_data = match CallCreateTrapData::new_from(opcode, machine.machine_mut(), handler) {
 			Ok(trap_data) => trap_data,
 			Err(err) => return Capture::Exit(Err(err)),
 		};
@@ -469,11 +566,15 @@ where
 		} else {
 			machine.machine().state.gas()
 		};
-		let target_gas = trap_data.target_gas().unwrap_or(after_gas);
-		let gas_limit = min(after_gas, target_gas);
+		// The requested gas is clamped to the gas available, so that it always
+		// fits in `u64`.
+		let gas_limit = match trap_data.target_gas() {
+			Some(target_gas) if target_gas < U256::from(after_gas) => target_gas.as_u64(),
+			_ => after_gas,
+		};
 
 		let call_has_value =
-			matches!(&trap_data, CallCreateTrapData::Call(call) if call.has_value());
+			has_stipend && matches!(&trap_data, CallCreateTrapData::Call(call) if call.has_value());
 
 		let is_static = if machine.machine().state.is_static() {
 			true
@@ -493,6 +594,7 @@ where
 						context: call_trap_data.context.clone(),
 						transaction_context,
 						retbuf: Vec::new(),
+						authorized: None,
 					},
 					gas_limit,
 					is_static,
@@ -508,6 +610,7 @@ where
 					self.config,
 					self.resolver,
 					call_trap_data,
+					trap_opcode,
 					target,
 					substate,
 					handler,
@@ -515,7 +618,9 @@ where
 			}
 			CallCreateTrapData::Create(create_trap_data) => {
 				let caller = create_trap_data.scheme.caller();
-				let address = create_trap_data.scheme.address(handler);
+				let address = self
+					.resolver
+					.create_address(&create_trap_data.scheme, handler);
 				let code = create_trap_data.code.clone();
 
 				let substate = match machine.machine_mut().state.substate(
@@ -527,6 +632,7 @@ where
 						},
 						transaction_context,
 						retbuf: Vec::new(),
+						authorized: None,
 					},
 					gas_limit,
 					is_static,
@@ -551,52 +657,305 @@ where
 	fn exit_substack(
 		&self,
 		result: ExitResult,
-		(mut substate, retval): (R::State, Vec<u8>),
+		(substate, retval): (R::State, Vec<u8>),
 		trap_data: Self::SubstackInvoke,
 		parent: &mut Self::Interpreter,
 		handler: &mut H,
 	) -> Result<(), ExitError> {
-		let strategy = match &result {
-			Ok(_) => MergeStrategy::Commit,
-			Err(ExitError::Reverted) => MergeStrategy::Revert,
-			Err(_) => MergeStrategy::Discard,
-		};
+		routines::exit_substack(
+			self.config,
+			result,
+			(substate, retval),
+			trap_data,
+			parent,
+			handler,
+		)
+	}
+}
 
-		match trap_data {
-			SubstackInvoke::Create { address, trap } => {
-				let retbuf = retval;
-				let caller = trap.scheme.caller();
+/// Decode the revert data of a reverted execution.
+fn revert_reason(result: &ExitResult, retval: &[u8]) -> Option<RevertReason> {
+	match result {
+		Err(ExitError::Reverted) => RevertReason::decode(retval),
+		_ => None,
+	}
+}
 
-				let result = result.and_then(|_| {
-					routines::deploy_create_code(
-						self.config,
-						address,
-						retbuf.clone(),
-						&mut substate,
-						handler,
-						SetCodeOrigin::Subcall(caller),
-					)?;
+#[cfg(test)]
+mod tests {
+	use alloc::{vec, vec::Vec};
+
+	use evm_interpreter::error::ExitException;
+	use primitive_types::{H160, H256, U256};
+
+	use super::TransactArgs;
+	use crate::{
+		backend::{RuntimeBaseBackend, TestBackend},
+		standard::{
+			testing::{backend, call_args, transact, try_transact, CALLER, CONTRACT},
+			BaseFeeSink, Bloom, Config, ConfigBuilder,
+		},
+	};
+
+	const REVERTING: H160 = H160([0x30; 20]);
+
+	/// LOG1 with topic 1 and no data, then call the reverting contract and
+	/// stop.
+	fn logging_code() -> Vec<u8> {
+		let mut code = vec![
+			0x60, 0x01, 0x60, 0x00, 0x60, 0x00, 0xa1, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60,
+			0x00, 0x60, 0x00, 0x73,
+		];
+		code.extend_from_slice(REVERTING.as_bytes());
+		code.extend_from_slice(&[0x5a, 0xf1, 0x50, 0x00]);
+		code
+	}
 
-					Ok(address)
-				});
+	/// LOG1 with topic 2 and no data, then revert.
+	const REVERTING_CODE: [u8; 13] = [
+		0x60, 0x02, 0x60, 0x00, 0x60, 0x00, 0xa1, 0x60, 0x00, 0x60, 0x00, 0xfd, 0x00,
+	];
 
-				parent.machine_mut().state.merge(substate, strategy);
-				handler.pop_substate(strategy);
+	fn call(address: H160, gas_price: u64) -> TransactArgs {
+		call_args(address, Vec::new(), 100_000, gas_price)
+	}
 
-				trap.feedback(result, retbuf, parent)?;
+	fn base() -> TestBackend {
+		let mut base = backend();
+		base.set_balance(CALLER, U256::from(10_000_000));
+		base
+	}
 
-				Ok(())
-			}
-			SubstackInvoke::Call { trap } => {
-				let retbuf = retval;
+	#[test]
+	fn logs_of_reverted_frames_are_discarded() {
+		let mut base = base();
+		base.set_code(CONTRACT, logging_code());
+		base.set_code(REVERTING, REVERTING_CODE.to_vec());
+		let config = Config::cancun();
+
+		let (value, _) = transact(&base, &config, call(CONTRACT, 0));
+		assert!(value.exit.is_ok());
+		assert_eq!(value.logs.len(), 1);
+		assert_eq!(value.logs[0].address, CONTRACT);
+		assert_eq!(value.logs[0].topics, vec![H256::from_low_u64_be(1)]);
+		assert_eq!(value.logs_bloom, Bloom::from_logs(&value.logs));
+		assert!(value.logs_bloom.contains_input(CONTRACT.as_bytes()));
+		assert!(!value
+			.logs_bloom
+			.contains_input(H256::from_low_u64_be(2).as_bytes()));
+
+		let (value, _) = transact(&base, &config, call(REVERTING, 0));
+		assert!(value.exit.is_err());
+		assert!(value.logs.is_empty());
+		assert_eq!(value.logs_bloom, Bloom::default());
+	}
+
+	#[test]
+	fn transact_args_take_other_address_types() {
+		let args = TransactArgs::<H256>::Call {
+			caller: H256::repeat_byte(1),
+			address: H256::repeat_byte(2),
+			nonce: None,
+			chain_id: None,
+			value: U256::zero(),
+			data: Vec::new(),
+			gas_limit: U256::from(21_000),
+			gas_price: U256::zero(),
+			access_list: vec![(H256::repeat_byte(3), vec![H256::repeat_byte(4)])],
+			system: false,
+			mint: None,
+			fee_payer: None,
+		};
 
-				parent.machine_mut().state.merge(substate, strategy);
-				handler.pop_substate(strategy);
+		assert_eq!(args.caller(), H256::repeat_byte(1));
+		assert_eq!(args.fee_payer(), H256::repeat_byte(1));
+		assert_eq!(
+			args.prefetch_hints(),
+			(
+				vec![
+					H256::repeat_byte(1),
+					H256::repeat_byte(2),
+					H256::repeat_byte(3)
+				],
+				vec![(H256::repeat_byte(3), H256::repeat_byte(4))]
+			)
+		);
+	}
 
-				trap.feedback(result, retbuf, parent)?;
+	#[test]
+	fn cleared_storage_is_refunded_up_to_the_cap() {
+		// Clear slots 0 and 1, or slot 0 only.
+		let clear_both = vec![
+			0x60, 0x00, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x01, 0x55, 0x00,
+		];
+		let clear_one = vec![0x60, 0x00, 0x60, 0x00, 0x55, 0x00];
+		let mut base = base();
+		base.set_storage(CONTRACT, H256::zero(), H256::from_low_u64_be(1));
+		base.set_storage(CONTRACT, H256::from_low_u64_be(1), H256::from_low_u64_be(1));
+
+		// 21000 intrinsic, 6 for the pushes, and 2100 + 2900 for the cold
+		// storage reset, with a refund of 4800 under the cap of a fifth.
+		base.set_code(CONTRACT, clear_one.clone());
+		let (value, backend) = transact(&base, &Config::cancun(), call(CONTRACT, 2));
+		assert!(value.exit.is_ok());
+		assert_eq!(value.refunded_gas, U256::from(4800));
+		assert_eq!(value.used_gas, U256::from(26_006 - 4800));
+		assert_eq!(value.effective_fee, value.used_gas * 2);
+		assert_eq!(
+			backend.balance(CALLER),
+			U256::from(10_000_000) - value.effective_fee
+		);
+
+		// Two refunds of 4800 exceed the cap.
+		base.set_code(CONTRACT, clear_both);
+		let (value, _) = transact(&base, &Config::cancun(), call(CONTRACT, 2));
+		assert_eq!(value.refunded_gas, U256::from(31_012 / 5));
+		assert_eq!(value.used_gas, U256::from(31_012 - 31_012 / 5));
+
+		// Before EIP-3529, the refund is 15000 and capped at a half.
+		base.set_code(CONTRACT, clear_one);
+		let (value, _) = transact(&base, &Config::istanbul(), call(CONTRACT, 2));
+		assert_eq!(value.refunded_gas, U256::from(26_006 / 2));
+		assert_eq!(value.used_gas, U256::from(26_006 - 26_006 / 2));
+	}
 
-				Ok(())
-			}
+	#[test]
+	fn system_transactions_skip_the_fee_and_the_nonce() {
+		const COINBASE: H160 = H160([0xc0; 20]);
+		let mut base = backend();
+		base.environment.block_coinbase = COINBASE;
+		// Store 1 at slot 0.
+		base.set_code(CONTRACT, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+		let config = Config::cancun();
+
+		let mut args = call(CONTRACT, 10);
+		if let TransactArgs::Call { system, .. } = &mut args {
+			*system = true;
 		}
+		let (value, backend) = transact(&base, &config, args);
+		assert!(value.exit.is_ok());
+		assert_eq!(value.effective_fee, U256::zero());
+		assert_eq!(
+			backend.storage(CONTRACT, H256::zero()),
+			H256::from_low_u64_be(1)
+		);
+		assert_eq!(backend.balance(CALLER), U256::zero());
+		assert_eq!(backend.nonce(CALLER), U256::zero());
+		assert_eq!(backend.balance(COINBASE), U256::zero());
+
+		// The same transaction, not as a system one, fails for the missing
+		// fee.
+		let (result, backend) = try_transact(&base, &config, call(CONTRACT, 10));
+		assert!(result.is_err());
+		assert_eq!(backend.nonce(CALLER), U256::zero());
+
+		base.set_balance(CALLER, U256::from(1_000_000));
+		let (value, backend) = transact(&base, &config, call(CONTRACT, 10));
+		assert!(value.exit.is_ok());
+		assert_eq!(
+			backend.balance(CALLER),
+			U256::from(1_000_000) - value.used_gas * 10
+		);
+		assert_eq!(backend.nonce(CALLER), U256::one());
+		assert_eq!(backend.balance(COINBASE), value.used_gas * 10);
+	}
+
+	#[test]
+	fn transactions_from_the_nonce_cap_are_rejected() {
+		let mut base = base();
+		base.set_nonce(CALLER, U256::from(u64::MAX));
+
+		let (result, backend) = try_transact(&base, &Config::cancun(), call(CONTRACT, 1));
+		assert_eq!(result.err(), Some(ExitException::MaxNonce.into()));
+		assert_eq!(backend.balance(CALLER), U256::from(10_000_000));
+		assert_eq!(backend.nonce(CALLER), U256::from(u64::MAX));
+	}
+
+	#[test]
+	fn calldata_floor_is_charged_when_above_the_execution_gas() {
+		const STORING: H160 = H160([0x40; 20]);
+		let mut base = base();
+		// Store 1 at slot 0.
+		base.set_code(STORING, vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]);
+		let with_data = |address| call_args(address, vec![0xff; 100], 100_000, 1);
+
+		// 100 non-zero bytes are 400 tokens. The intrinsic gas is
+		// 21000 + 16 * 100, and the floor 21000 + 10 * 400.
+		let (value, backend) = transact(&base, &Config::prague(), with_data(CONTRACT));
+		assert!(value.exit.is_ok());
+		assert_eq!(value.used_gas, U256::from(25_000));
+		assert_eq!(backend.balance(CALLER), U256::from(10_000_000 - 25_000));
+		let (value, _) = transact(&base, &Config::cancun(), with_data(CONTRACT));
+		assert_eq!(value.used_gas, U256::from(22_600));
+
+		// 22100 + 6 for storing the slot puts the execution gas above the
+		// floor.
+		let (value, _) = transact(&base, &Config::prague(), with_data(STORING));
+		assert_eq!(value.used_gas, U256::from(22_600 + 22_106));
+
+		// The gas limit has to cover the floor.
+		let args = call_args(CONTRACT, vec![0xff; 100], 24_999, 1);
+		let (result, _) = try_transact(&base, &Config::prague(), args);
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn base_fee_goes_to_the_sink_and_the_priority_fee_to_the_coinbase() {
+		const COINBASE: H160 = H160([0xc0; 20]);
+		const SINK: H160 = H160([0x50; 20]);
+		let mut base = base();
+		base.environment.block_coinbase = COINBASE;
+		base.environment.block_base_fee_per_gas = U256::from(7);
+
+		let (value, backend) = transact(&base, &Config::cancun(), call(CONTRACT, 10));
+		assert_eq!(value.used_gas, U256::from(21_000));
+		assert_eq!(value.effective_fee, U256::from(21_000 * 10));
+		assert_eq!(value.base_fee, U256::from(21_000 * 7));
+		assert_eq!(
+			backend.balance(CALLER),
+			U256::from(10_000_000 - 21_000 * 10)
+		);
+		assert_eq!(backend.balance(COINBASE), U256::from(21_000 * 3));
+		assert_eq!(backend.balance(SINK), U256::zero());
+
+		let config = ConfigBuilder::new(Config::cancun())
+			.base_fee_sink(BaseFeeSink::Address(SINK))
+			.build();
+		let (value, backend) = transact(&base, &config, call(CONTRACT, 10));
+		assert_eq!(value.base_fee, U256::from(21_000 * 7));
+		assert_eq!(backend.balance(COINBASE), U256::from(21_000 * 3));
+		assert_eq!(backend.balance(SINK), U256::from(21_000 * 7));
+
+		// Without EIP-1559, the coinbase gets the whole fee.
+		let config = ConfigBuilder::new(Config::istanbul())
+			.base_fee_sink(BaseFeeSink::Address(SINK))
+			.build();
+		let (value, backend) = transact(&base, &config, call(CONTRACT, 10));
+		assert_eq!(value.base_fee, U256::zero());
+		assert_eq!(backend.balance(COINBASE), U256::from(21_000 * 10));
+		assert_eq!(backend.balance(SINK), U256::zero());
+	}
+
+	#[test]
+	fn chain_id_of_the_config_takes_precedence_over_the_backend() {
+		let mut base = backend();
+		base.environment.chain_id = U256::from(7);
+		// Store CHAINID at slot 0.
+		base.set_code(CONTRACT, vec![0x46, 0x60, 0x00, 0x55, 0x00]);
+
+		let config = ConfigBuilder::new(Config::cancun())
+			.chain_id(Some(U256::from(5)))
+			.build();
+		let (_, backend) = transact(&base, &config, call(CONTRACT, 0));
+		assert_eq!(
+			backend.storage(CONTRACT, H256::zero()),
+			H256::from_low_u64_be(5)
+		);
+
+		let (_, backend) = transact(&base, &Config::cancun(), call(CONTRACT, 0));
+		assert_eq!(
+			backend.storage(CONTRACT, H256::zero()),
+			H256::from_low_u64_be(7)
+		);
 	}
 }
@@ -1,15 +1,19 @@
-use alloc::{rc::Rc, vec::Vec};
+use alloc::{boxed::Box, collections::BTreeMap, sync::Arc, vec::Vec};
+use core::cell::RefCell;
 
 use evm_interpreter::{
-	error::{ExitError, ExitResult},
+	error::{CreateScheme, ExitError, ExitResult},
 	etable::EtableSet,
 	machine::Machine,
 	runtime::{RuntimeBackend, RuntimeState},
-	EtableInterpreter, Interpreter,
+	CodeAnalysis, DecodeCache, EtableInterpreter, Interpreter,
 };
 use primitive_types::H160;
 
-use crate::{invoker::InvokerControl, standard::Config};
+use crate::{
+	invoker::InvokerControl,
+	standard::{static_gas_costs, Config},
+};
 
 /// A code resolver.
 ///
@@ -39,6 +43,24 @@ pub trait Resolver<H> {
 		state: Self::State,
 		handler: &mut H,
 	) -> Result<InvokerControl<Self::Interpreter, (ExitResult, (Self::State, Vec<u8>))>, ExitError>;
+
+	/// Mark the precompiles warm, as they are from the start of a transaction
+	/// since Berlin (see EIP-2929). The default marks none.
+	fn warm_precompiles(&self, _handler: &mut H)
+	where
+		H: RuntimeBackend,
+	{
+	}
+
+	/// Derive the address of a contract created with the given scheme, for both
+	/// create transactions and `CREATE`/`CREATE2`. Override this for chains
+	/// with non-standard address derivation.
+	fn create_address(&self, scheme: &CreateScheme, handler: &H) -> H160
+	where
+		H: RuntimeBackend,
+	{
+		scheme.address(handler)
+	}
 }
 
 /// A set of precompiles.
@@ -52,6 +74,30 @@ pub trait PrecompileSet<S, H> {
 		state: &mut S,
 		handler: &mut H,
 	) -> Option<(ExitResult, Vec<u8>)>;
+
+	/// All addresses of this precompile set. The default lists none, in which
+	/// case the precompiles are not warm at the start of a transaction, and
+	/// [PrecompileSet::is_precompile] should be implemented.
+	fn addresses(&self) -> Vec<H160> {
+		Vec::new()
+	}
+
+	/// Whether the address is a precompile of this set.
+	fn is_precompile(&self, address: H160) -> bool {
+		self.addresses().contains(&address)
+	}
+
+	/// Mark the precompiles of this set warm, as they are from the start of a
+	/// transaction since Berlin (see EIP-2929). The default marks the
+	/// [PrecompileSet::addresses].
+	fn warm_addresses(&self, handler: &mut H)
+	where
+		H: RuntimeBackend,
+	{
+		for address in self.addresses() {
+			handler.mark_hot(address, None);
+		}
+	}
 }
 
 impl<S, H> PrecompileSet<S, H> for () {
@@ -64,6 +110,145 @@ impl<S, H> PrecompileSet<S, H> for () {
 	) -> Option<(ExitResult, Vec<u8>)> {
 		None
 	}
+
+	fn addresses(&self) -> Vec<H160> {
+		Vec::new()
+	}
+
+	fn is_precompile(&self, _address: H160) -> bool {
+		false
+	}
+
+	fn warm_addresses(&self, _handler: &mut H)
+	where
+		H: RuntimeBackend,
+	{
+	}
+}
+
+/// Chain two precompile sets. The first set takes priority.
+impl<S, H, A, B> PrecompileSet<S, H> for (A, B)
+where
+	A: PrecompileSet<S, H>,
+	B: PrecompileSet<S, H>,
+{
+	fn execute(
+		&self,
+		code_address: H160,
+		input: &[u8],
+		state: &mut S,
+		handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		if let Some(ret) = self.0.execute(code_address, input, state, handler) {
+			return Some(ret);
+		}
+
+		self.1.execute(code_address, input, state, handler)
+	}
+
+	fn addresses(&self) -> Vec<H160> {
+		let mut addresses = self.0.addresses();
+		for address in self.1.addresses() {
+			if !addresses.contains(&address) {
+				addresses.push(address);
+			}
+		}
+		addresses
+	}
+
+	fn is_precompile(&self, address: H160) -> bool {
+		self.0.is_precompile(address) || self.1.is_precompile(address)
+	}
+
+	fn warm_addresses(&self, handler: &mut H)
+	where
+		H: RuntimeBackend,
+	{
+		self.0.warm_addresses(handler);
+		self.1.warm_addresses(handler);
+	}
+}
+
+/// A single precompile, to be registered in a [PrecompileRegistry].
+pub trait Precompile<S, H> {
+	/// Execute the precompile.
+	fn execute(&self, input: &[u8], state: &mut S, handler: &mut H) -> (ExitResult, Vec<u8>);
+}
+
+impl<S, H, F> Precompile<S, H> for F
+where
+	F: Fn(&[u8], &mut S, &mut H) -> (ExitResult, Vec<u8>),
+{
+	fn execute(&self, input: &[u8], state: &mut S, handler: &mut H) -> (ExitResult, Vec<u8>) {
+		self(input, state, handler)
+	}
+}
+
+/// A precompile set whose precompiles are registered at runtime, at arbitrary
+/// addresses.
+pub struct PrecompileRegistry<'precompile, S, H> {
+	precompiles: BTreeMap<H160, Box<dyn Precompile<S, H> + 'precompile>>,
+}
+
+impl<'precompile, S, H> Default for PrecompileRegistry<'precompile, S, H> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<'precompile, S, H> PrecompileRegistry<'precompile, S, H> {
+	/// Create an empty registry.
+	pub fn new() -> Self {
+		Self {
+			precompiles: BTreeMap::new(),
+		}
+	}
+
+	/// Register a precompile at the given address, returning the precompile
+	/// previously registered there, if any.
+	pub fn insert<P: Precompile<S, H> + 'precompile>(
+		&mut self,
+		address: H160,
+		precompile: P,
+	) -> Option<Box<dyn Precompile<S, H> + 'precompile>> {
+		self.precompiles.insert(address, Box::new(precompile))
+	}
+
+	/// Unregister the precompile at the given address.
+	pub fn remove(&mut self, address: H160) -> Option<Box<dyn Precompile<S, H> + 'precompile>> {
+		self.precompiles.remove(&address)
+	}
+}
+
+impl<'precompile, S, H> PrecompileSet<S, H> for PrecompileRegistry<'precompile, S, H> {
+	fn execute(
+		&self,
+		code_address: H160,
+		input: &[u8],
+		state: &mut S,
+		handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		self.precompiles
+			.get(&code_address)
+			.map(|precompile| precompile.execute(input, state, handler))
+	}
+
+	fn addresses(&self) -> Vec<H160> {
+		self.precompiles.keys().cloned().collect()
+	}
+
+	fn is_precompile(&self, address: H160) -> bool {
+		self.precompiles.contains_key(&address)
+	}
+
+	fn warm_addresses(&self, handler: &mut H)
+	where
+		H: RuntimeBackend,
+	{
+		for address in self.precompiles.keys() {
+			handler.mark_hot(*address, None);
+		}
+	}
 }
 
 /// The standard code resolver where the color is an [Etable]. This is usually
@@ -72,6 +257,7 @@ pub struct EtableResolver<'config, 'precompile, 'etable, Pre, ES> {
 	config: &'config Config,
 	etable: &'etable ES,
 	precompiles: &'precompile Pre,
+	decode_cache: Option<(&'etable RefCell<DecodeCache>, [Option<u64>; 256])>,
 }
 
 impl<'config, 'precompile, 'etable, Pre, ES>
@@ -86,8 +272,19 @@ impl<'config, 'precompile, 'etable, Pre, ES>
 			config,
 			precompiles,
 			etable,
+			decode_cache: None,
 		}
 	}
+
+	/// Execute the code of calls as decoded instructions, decoded once per
+	/// code hash into the cache. The cache must only be used with the config
+	/// of the resolver. Wrap the etable in a [crate::standard::DecodedEtable]
+	/// to skip re-reading the code.
+	#[must_use]
+	pub fn with_decode_cache(mut self, cache: &'etable RefCell<DecodeCache>) -> Self {
+		self.decode_cache = Some((cache, static_gas_costs(self.config)));
+		self
+	}
 }
 
 impl<'config, 'precompile, 'etable, H, Pre, ES> Resolver<H>
@@ -118,16 +315,40 @@ where
 		}
 
 		let code = handler.code(code_address);
+		let decoded = self.decode_cache.as_ref().map(|(cache, static_costs)| {
+			cache.borrow_mut().get_or_decode(
+				handler.code_hash(code_address),
+				&code,
+				static_costs,
+				self.config.eip_663_enabled,
+			)
+		});
 
 		let machine = Machine::<ES::State>::new(
-			Rc::new(code),
-			Rc::new(input),
+			Arc::new(code),
+			Arc::new(input),
 			self.config.stack_limit,
 			self.config.memory_limit,
 			state,
 		);
 
-		let ret = InvokerControl::Enter(EtableInterpreter::new(machine, self.etable));
+		let interpreter = match decoded {
+			// Decoded code of another code, from a wrong backend code hash,
+			// is not used.
+			Some(decoded) if decoded.code() == machine.code() => {
+				EtableInterpreter::new_with_analysis(
+					machine,
+					self.etable,
+					decoded.analysis().clone(),
+				)
+				.with_decoded(decoded)
+			}
+			_ => {
+				let analysis = CodeAnalysis::new(machine.code(), self.config.eip_663_enabled);
+				EtableInterpreter::new_with_analysis(machine, self.etable, analysis)
+			}
+		};
+		let ret = InvokerControl::Enter(interpreter);
 
 		Ok(ret)
 	}
@@ -141,15 +362,157 @@ where
 		_handler: &mut H,
 	) -> Result<InvokerControl<Self::Interpreter, (ExitResult, (ES::State, Vec<u8>))>, ExitError> {
 		let machine = Machine::new(
-			Rc::new(init_code),
-			Rc::new(Vec::new()),
+			Arc::new(init_code),
+			Arc::new(Vec::new()),
 			self.config.stack_limit,
 			self.config.memory_limit,
 			state,
 		);
 
-		let ret = InvokerControl::Enter(EtableInterpreter::new(machine, self.etable));
+		let analysis = CodeAnalysis::new(machine.code(), self.config.eip_663_enabled);
+		let ret = InvokerControl::Enter(EtableInterpreter::new_with_analysis(
+			machine,
+			self.etable,
+			analysis,
+		));
 
 		Ok(ret)
 	}
+
+	fn warm_precompiles(&self, handler: &mut H) {
+		self.precompiles.warm_addresses(handler);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{collections::BTreeSet, vec, vec::Vec};
+
+	use evm_interpreter::{
+		error::{ExitResult, ExitSucceed},
+		runtime::RuntimeBackend,
+	};
+	use primitive_types::{H160, U256};
+
+	use super::{PrecompileRegistry, PrecompileSet};
+	use crate::{
+		backend::{OverlayedBackend, TestBackend},
+		standard::{
+			testing::{backend, call_args, try_transact_with, TestHandler, CONTRACT},
+			Config, State, TransactValue,
+		},
+	};
+
+	const PRECOMPILE: H160 = H160([0xee; 20]);
+
+	/// A precompile set implementing only `execute`, returning the given byte.
+	struct ExecuteOnly(u8);
+
+	impl<S, H> PrecompileSet<S, H> for ExecuteOnly {
+		fn execute(
+			&self,
+			code_address: H160,
+			_input: &[u8],
+			_state: &mut S,
+			_handler: &mut H,
+		) -> Option<(ExitResult, Vec<u8>)> {
+			if code_address == PRECOMPILE {
+				Some((Ok(ExitSucceed::Returned), vec![self.0]))
+			} else {
+				None
+			}
+		}
+	}
+
+	fn registry<'a>(byte: u8) -> PrecompileRegistry<'a, State<'a>, TestHandler<'a>> {
+		let mut registry = PrecompileRegistry::new();
+		registry.insert(
+			PRECOMPILE,
+			move |_input: &[u8], _state: &mut State<'a>, _handler: &mut TestHandler<'a>| {
+				(Ok(ExitSucceed::Returned), vec![byte])
+			},
+		);
+		registry
+	}
+
+	fn transact<'a, Pre>(
+		config: &'a Config,
+		base: &'a TestBackend,
+		precompiles: &Pre,
+		address: H160,
+	) -> TransactValue
+	where
+		Pre: PrecompileSet<State<'a>, TestHandler<'a>>,
+	{
+		let args = call_args(address, Vec::new(), 100_000, 0);
+		try_transact_with(base, config, precompiles, args)
+			.0
+			.unwrap()
+	}
+
+	fn base() -> TestBackend {
+		let mut base = backend();
+		// PUSH20 PRECOMPILE BALANCE POP STOP
+		let mut code = vec![0x73];
+		code.extend_from_slice(PRECOMPILE.as_bytes());
+		code.extend_from_slice(&[0x31, 0x50, 0x00]);
+		base.set_code(CONTRACT, code);
+		base
+	}
+
+	#[test]
+	fn registered_precompiles_are_called_and_warm() {
+		let config = Config::cancun();
+		let base = base();
+
+		let value = transact(&config, &base, &registry(1), PRECOMPILE);
+		assert!(value.exit.is_ok());
+		assert_eq!(value.retval, vec![1]);
+
+		let cold = transact(&config, &base, &(), CONTRACT);
+		let warm = transact(&config, &base, &registry(1), CONTRACT);
+		assert!(cold.exit.is_ok() && warm.exit.is_ok());
+		assert_eq!(cold.used_gas - warm.used_gas, U256::from(2600 - 100));
+	}
+
+	#[test]
+	fn precompiles_of_sets_implementing_only_execute_are_cold() {
+		let config = Config::cancun();
+		let base = base();
+
+		let value = transact(&config, &base, &ExecuteOnly(1), PRECOMPILE);
+		assert_eq!(value.retval, vec![1]);
+
+		let cold = transact(&config, &base, &(), CONTRACT);
+		let value = transact(&config, &base, &ExecuteOnly(1), CONTRACT);
+		assert_eq!(value.used_gas, cold.used_gas);
+		assert!(!<ExecuteOnly as PrecompileSet<(), ()>>::is_precompile(
+			&ExecuteOnly(1),
+			PRECOMPILE
+		));
+	}
+
+	#[test]
+	fn the_first_set_of_a_pair_takes_priority() {
+		let config = Config::cancun();
+		let base = base();
+
+		let value = transact(&config, &base, &(registry(1), registry(2)), PRECOMPILE);
+		assert_eq!(value.retval, vec![1]);
+		let value = transact(&config, &base, &(ExecuteOnly(1), registry(2)), PRECOMPILE);
+		assert_eq!(value.retval, vec![1]);
+
+		// Warm as long as either set lists the address.
+		let cold = transact(&config, &base, &(), CONTRACT);
+		let warm = transact(&config, &base, &(ExecuteOnly(1), registry(2)), CONTRACT);
+		assert_eq!(cold.used_gas - warm.used_gas, U256::from(2600 - 100));
+
+		let pair = (ExecuteOnly(1), registry(2));
+		assert!(PrecompileSet::<State, TestHandler>::is_precompile(
+			&pair, PRECOMPILE
+		));
+		let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+		PrecompileSet::<State, TestHandler>::warm_addresses(&pair, &mut backend);
+		assert!(!backend.is_cold(PRECOMPILE, None));
+	}
 }
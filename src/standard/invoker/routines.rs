@@ -1,12 +1,27 @@
+//! Building blocks of the standard invoker, for embedders writing their own
+//! [crate::Invoker] implementation, for example with custom precompile
+//! routing.
+//!
+//! A custom invoker creates the substate of a trapped call or create with
+//! [InvokerState::substate], enters it with [enter_call_substack] or
+//! [enter_create_substack], and exits it with [exit_substack], which deploys
+//! created code and feeds the result back to the parent interpreter.
+
 use alloc::vec::Vec;
 
 use evm_interpreter::{
 	error::{CallTrapData, CreateTrapData, ExitError, ExitException, ExitResult},
 	opcode::Opcode,
-	runtime::{RuntimeBackend, RuntimeEnvironment, RuntimeState, SetCodeOrigin, Transfer},
+	runtime::{
+		RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment, RuntimeState, SetCodeOrigin,
+		Transfer,
+	},
+	Interpreter,
 };
 use primitive_types::{H160, U256};
 
+#[cfg(feature = "frames")]
+use crate::standard::FrameGas;
 use crate::{
 	backend::TransactionalBackend,
 	invoker::InvokerControl,
@@ -14,6 +29,25 @@ use crate::{
 	MergeStrategy,
 };
 
+/// Whether the nonce of the account is at the maximum of 2^64-1, so that it
+/// cannot be increased (EIP-2681).
+pub fn is_max_nonce<H: RuntimeBaseBackend>(address: H160, handler: &H) -> bool {
+	handler.nonce(address) >= U256::from(u64::MAX)
+}
+
+/// Increase the nonce of the account, failing if it is already at the maximum
+/// (EIP-2681).
+pub fn inc_nonce<H: RuntimeBackend>(address: H160, handler: &mut H) -> Result<(), ExitError> {
+	if is_max_nonce(address, handler) {
+		return Err(ExitException::MaxNonce.into());
+	}
+
+	handler.inc_nonce(address)
+}
+
+/// Enter the call machine: mark the callee hot, touch it and transfer the
+/// value, then resolve the code with the resolver. The substate must already
+/// be pushed.
 #[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn make_enter_call_machine<H, R>(
 	_config: &Config,
@@ -30,6 +64,8 @@ where
 	R: Resolver<H>,
 {
 	handler.mark_hot(state.as_ref().context.address, None);
+	// Calls touch the callee even without a value transfer (EIP-161).
+	handler.touch(state.as_ref().context.address);
 
 	if let Some(transfer) = transfer {
 		handler.transfer(transfer)?;
@@ -38,6 +74,8 @@ where
 	resolver.resolve_call(code_address, input, state, handler)
 }
 
+/// Enter the create machine. The caller nonce must already be increased, and
+/// the substate must already be pushed.
 #[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub fn make_enter_create_machine<H, R>(
 	config: &Config,
@@ -62,16 +100,22 @@ where
 	handler.mark_hot(caller, None);
 	handler.mark_hot(state.as_ref().context.address, None);
 
-	handler.transfer(transfer)?;
-
-	if handler.code_size(state.as_ref().context.address) != U256::zero()
-		|| handler.nonce(state.as_ref().context.address) > U256::zero()
+	// EIP-684: creating a contract at an address that already has code or a
+	// non-zero nonce fails, consuming all gas given to the create.
+	if config.eip_684_enabled
+		&& (handler.code_size(state.as_ref().context.address) != U256::zero()
+			|| handler.nonce(state.as_ref().context.address) > U256::zero())
 	{
-		return Err(ExitException::CreateCollision.into());
+		return Ok(InvokerControl::DirectExit((
+			Err(ExitException::CreateCollision.into()),
+			(state, Vec::new()),
+		)));
 	}
-	handler.inc_nonce(caller)?;
+
+	handler.transfer(transfer)?;
+
 	if config.create_increase_nonce {
-		handler.inc_nonce(state.as_ref().context.address)?;
+		inc_nonce(state.as_ref().context.address, handler)?;
 	}
 
 	handler.reset_storage(state.as_ref().context.address);
@@ -79,11 +123,15 @@ where
 	resolver.resolve_create(init_code, state, handler)
 }
 
+/// Push the substate of a call and enter its machine, with the state created
+/// for the callee. The substate is popped again if the call cannot be
+/// entered.
 #[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub fn enter_call_substack<H, R>(
 	config: &Config,
 	resolver: &R,
 	trap_data: CallTrapData,
+	opcode: Opcode,
 	code_address: H160,
 	state: R::State,
 	handler: &mut H,
@@ -112,7 +160,13 @@ where
 			handler,
 		)?;
 
-		Ok((SubstackInvoke::Call { trap: trap_data }, machine))
+		Ok((
+			SubstackInvoke::Call {
+				trap: trap_data,
+				opcode,
+			},
+			machine,
+		))
 	};
 
 	match work() {
@@ -124,6 +178,9 @@ where
 	}
 }
 
+/// Increase the caller nonce, push the substate of a create and enter its
+/// init code machine, with the state created for the new contract. The
+/// substate is popped again if the create cannot be entered.
 #[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub fn enter_create_substack<H, R>(
 	config: &Config,
@@ -144,18 +201,39 @@ where
 	H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
 	R: Resolver<H>,
 {
-	handler.push_substate();
+	let CreateTrapData {
+		scheme,
+		value,
+		code: _,
+	} = trap_data.clone();
 
-	let work = || -> Result<(SubstackInvoke, InvokerControl<R::Interpreter, (ExitResult, (R::State, Vec<u8>))>), ExitError> {
-		let CreateTrapData {
-			scheme,
-			value,
-			code: _,
-		} = trap_data.clone();
+	let caller = scheme.caller();
+	let address = resolver.create_address(&scheme, handler);
 
-		let caller = scheme.caller();
-		let address = scheme.address(handler);
+	if handler.balance(caller) < value {
+		return Err(ExitException::OutOfFund.into());
+	}
+
+	// A create from a caller at the maximum nonce fails without entering the
+	// substack, and its gas is given back (EIP-2681).
+	if is_max_nonce(caller, handler) {
+		handler.push_substate();
 
+		return Ok((
+			SubstackInvoke::Create {
+				address,
+				trap: trap_data,
+			},
+			InvokerControl::DirectExit((Err(ExitException::MaxNonce.into()), (state, Vec::new()))),
+		));
+	}
+
+	// The caller nonce is increased outside of the create substate, so that it
+	// is kept even if the create fails.
+	handler.inc_nonce(caller)?;
+	handler.push_substate();
+
+	let work = || -> Result<(SubstackInvoke, InvokerControl<R::Interpreter, (ExitResult, (R::State, Vec<u8>))>), ExitError> {
 		let transfer = Transfer {
 			source: caller,
 			target: address,
@@ -184,13 +262,20 @@ where
 	}
 }
 
-fn check_first_byte(config: &Config, code: &[u8]) -> Result<(), ExitError> {
-	if config.disallow_executable_format && Some(&Opcode::EOFMAGIC.as_u8()) == code.first() {
-		return Err(ExitException::InvalidOpcode(Opcode::EOFMAGIC).into());
+fn check_code_prefix(config: &Config, code: &[u8]) -> Result<(), ExitError> {
+	for prefix in config.banned_code_prefixes.iter() {
+		if let Some(first) = prefix.first() {
+			if code.starts_with(prefix) {
+				return Err(ExitException::InvalidOpcode(Opcode(*first)).into());
+			}
+		}
 	}
 	Ok(())
 }
 
+/// Deploy the code returned by init code at the address, after checking the
+/// banned code prefixes and the code size limit, and charging the code
+/// deposit to the state.
 pub fn deploy_create_code<'config, S, H>(
 	config: &Config,
 	address: H160,
@@ -203,7 +288,7 @@ where
 	S: InvokerState<'config>,
 	H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
 {
-	check_first_byte(config, &retbuf[..])?;
+	check_code_prefix(config, &retbuf[..])?;
 
 	if let Some(limit) = config.create_contract_limit {
 		if retbuf.len() > limit {
@@ -217,3 +302,340 @@ where
 
 	Ok(())
 }
+
+/// Delete the accounts that are empty, with no code, a zero nonce and a zero
+/// balance (EIP-161).
+pub fn delete_empty_accounts<H: RuntimeBackend>(addresses: Vec<H160>, handler: &mut H) {
+	for address in addresses {
+		if handler.code_size(address) == U256::zero()
+			&& handler.nonce(address) == U256::zero()
+			&& handler.balance(address) == U256::zero()
+		{
+			handler.mark_delete(address);
+		}
+	}
+}
+
+/// How the substate of an exited substack is merged into its parent.
+pub fn exit_merge_strategy(result: &ExitResult) -> MergeStrategy {
+	match result {
+		Ok(_) => MergeStrategy::Commit,
+		// A create that failed on the caller nonce has not executed, and
+		// gives its gas back.
+		Err(ExitError::Reverted) | Err(ExitError::Exception(ExitException::MaxNonce)) => {
+			MergeStrategy::Revert
+		}
+		Err(_) => MergeStrategy::Discard,
+	}
+}
+
+/// Record the gas usage of an exited substack into its state, before it is
+/// merged into the parent. A discarded substack uses all of its gas.
+#[cfg(feature = "frames")]
+pub fn record_frame<'config, S: InvokerState<'config>>(
+	substate: &mut S,
+	address: H160,
+	opcode: Opcode,
+	result: &ExitResult,
+	retval: &[u8],
+	strategy: MergeStrategy,
+) {
+	let gas_limit = substate.gas_limit();
+	let gas_used = match strategy {
+		MergeStrategy::Discard => gas_limit,
+		MergeStrategy::Commit | MergeStrategy::Revert => gas_limit.saturating_sub(substate.gas()),
+	};
+
+	substate.record_frame(FrameGas {
+		address,
+		opcode,
+		gas_limit,
+		gas_used,
+		result: result.clone(),
+		revert_reason: super::revert_reason(result, retval),
+	});
+}
+
+/// Exit a substack entered with [enter_call_substack] or
+/// [enter_create_substack]. The code of a successful create is deployed, the
+/// gas of the frame is recorded, the substate is merged into the parent and
+/// popped, and the result is fed back to the parent interpreter.
+pub fn exit_substack<'config, H, I>(
+	config: &Config,
+	result: ExitResult,
+	(mut substate, retval): (I::State, Vec<u8>),
+	trap_data: SubstackInvoke,
+	parent: &mut I,
+	handler: &mut H,
+) -> Result<(), ExitError>
+where
+	I: Interpreter,
+	I::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+	H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+{
+	match trap_data {
+		SubstackInvoke::Create { address, trap } => {
+			let retbuf = retval;
+			let caller = trap.scheme.caller();
+
+			let result = result.and_then(|succeed| {
+				deploy_create_code(
+					config,
+					address,
+					retbuf.clone(),
+					&mut substate,
+					handler,
+					SetCodeOrigin::Subcall(caller),
+				)?;
+
+				Ok(succeed)
+			});
+			// A failed code deposit fails the create, and discards its
+			// substate.
+			let strategy = exit_merge_strategy(&result);
+
+			#[cfg(feature = "frames")]
+			record_frame(
+				&mut substate,
+				address,
+				trap.scheme.opcode(),
+				&result,
+				&retbuf,
+				strategy,
+			);
+			parent.machine_mut().state.merge(substate, strategy);
+			handler.pop_substate(strategy);
+
+			trap.feedback(result.map(|_| address), retbuf, parent)?;
+
+			Ok(())
+		}
+		SubstackInvoke::Call { trap, opcode } => {
+			let retbuf = retval;
+			#[cfg(not(feature = "frames"))]
+			let _ = opcode;
+			let strategy = exit_merge_strategy(&result);
+
+			#[cfg(feature = "frames")]
+			record_frame(
+				&mut substate,
+				trap.context.address,
+				opcode,
+				&result,
+				&retbuf,
+				strategy,
+			);
+			parent.machine_mut().state.merge(substate, strategy);
+			handler.pop_substate(strategy);
+
+			trap.feedback(result, retbuf, parent)?;
+
+			Ok(())
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{collections::BTreeSet, vec, vec::Vec};
+
+	use evm_interpreter::{
+		error::{CreateScheme, ExitError, ExitException},
+		runtime::RuntimeBaseBackend,
+	};
+	use primitive_types::{H160, H256, U256};
+
+	use crate::{
+		backend::{OverlayedBackend, TestBackend},
+		standard::{
+			testing::{backend, call_args, transact, CALLER, CONTRACT},
+			Config, ConfigBuilder, TransactArgs,
+		},
+	};
+
+	#[test]
+	fn failed_code_deposit_discards_the_create() {
+		let mut base = backend();
+		// The initcode stores 1 at slot 1, and returns code starting with
+		// 0xEF, which is rejected.
+		let initcode = [
+			0x60, 0x01, 0x60, 0x01, 0x55, 0x60, 0xef, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00,
+			0xf3,
+		];
+		let mut code = vec![0x6e];
+		code.extend_from_slice(&initcode);
+		code.extend_from_slice(&[
+			0x60, 0x00, 0x52, 0x60, 0x0f, 0x60, 0x11, 0x60, 0x00, 0xf0, 0x50, 0x00,
+		]);
+		base.set_code(CONTRACT, code);
+		base.set_nonce(CONTRACT, U256::one());
+
+		let args = call_args(CONTRACT, Vec::new(), 200_000, 0);
+		let (value, backend) = transact(&base, &Config::cancun(), args);
+		assert!(value.exit.is_ok());
+		#[cfg(feature = "frames")]
+		{
+			assert_eq!(value.frames.len(), 1);
+			assert!(value.frames[0].result.is_err());
+			assert_eq!(value.frames[0].gas_used, value.frames[0].gas_limit);
+		}
+
+		let (_, changes) = backend.deconstruct();
+		assert!(changes.storages.is_empty());
+		assert!(changes.codes.is_empty());
+	}
+
+	/// Deleted accounts after a transaction calling `target` with no value,
+	/// and reverting after the call if `revert`.
+	fn deletes_after_calling(target: H160, revert: bool) -> BTreeSet<H160> {
+		let mut base = backend();
+		let mut code = vec![
+			0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+		];
+		code.extend_from_slice(target.as_bytes());
+		code.extend_from_slice(&[0x5a, 0xf1, 0x50]);
+		if revert {
+			code.extend_from_slice(&[0x60, 0x00, 0x60, 0x00, 0xfd]);
+		}
+		base.set_code(CONTRACT, code);
+		base.set_nonce(CONTRACT, U256::one());
+		base.set_balance(target, U256::zero());
+
+		let args = call_args(CONTRACT, Vec::new(), 200_000, 0);
+		let (value, backend) = transact(&base, &Config::cancun(), args);
+		assert_eq!(value.exit.is_ok(), !revert);
+
+		backend.deconstruct().1.deletes
+	}
+
+	#[test]
+	fn empty_accounts_touched_by_a_call_are_deleted() {
+		const EMPTY: H160 = H160([0x30; 20]);
+
+		assert!(deletes_after_calling(EMPTY, false).contains(&EMPTY));
+	}
+
+	#[test]
+	fn reverted_touches_do_not_delete_empty_accounts() {
+		const EMPTY: H160 = H160([0x30; 20]);
+
+		assert!(!deletes_after_calling(EMPTY, true).contains(&EMPTY));
+	}
+
+	#[test]
+	fn touches_of_ripemd_survive_reverts() {
+		let ripemd = H160::from_low_u64_be(3);
+
+		assert!(deletes_after_calling(ripemd, false).contains(&ripemd));
+		assert!(deletes_after_calling(ripemd, true).contains(&ripemd));
+	}
+
+	fn create_address(base: &TestBackend, caller: H160) -> H160 {
+		CreateScheme::Legacy { caller }.address(&OverlayedBackend::new(base, BTreeSet::new()))
+	}
+
+	/// A create transaction deploying the code `0x5b`.
+	fn create() -> TransactArgs {
+		TransactArgs::Create {
+			caller: CALLER,
+			nonce: None,
+			chain_id: None,
+			value: U256::zero(),
+			init_code: vec![0x60, 0x5b, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3],
+			salt: None,
+			gas_limit: U256::from(100_000),
+			gas_price: U256::zero(),
+			access_list: Vec::new(),
+			system: false,
+			mint: None,
+			fee_payer: None,
+		}
+	}
+
+	#[test]
+	fn create_collisions_consume_the_gas_of_the_create() {
+		let mut base = backend();
+		// Store the address created with empty init code at slot 0.
+		base.set_code(
+			CONTRACT,
+			vec![
+				0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0xf0, 0x60, 0x00, 0x55, 0x00,
+			],
+		);
+		base.set_nonce(CONTRACT, U256::one());
+		let target = create_address(&base, CONTRACT);
+		let args = call_args(CONTRACT, Vec::new(), 1_000_000, 0);
+		let config = Config::cancun();
+
+		let (value, backend) = transact(&base, &config, args.clone());
+		assert!(value.exit.is_ok());
+		assert!(value.used_gas < U256::from(100_000));
+		assert_eq!(backend.storage(CONTRACT, H256::zero()), H256::from(target));
+
+		base.set_nonce(target, U256::one());
+		let (value, backend) = transact(&base, &config, args);
+		assert!(value.exit.is_ok());
+		// All gas but the 1/64 kept by the caller is consumed.
+		assert!(value.used_gas > U256::from(950_000));
+		assert_eq!(backend.storage(CONTRACT, H256::zero()), H256::zero());
+		// The nonce of the creator is still increased.
+		assert_eq!(backend.nonce(CONTRACT), U256::from(2));
+		assert_eq!(backend.nonce(target), U256::one());
+	}
+
+	#[test]
+	fn create_transaction_collisions_consume_all_gas() {
+		let mut base = backend();
+		let target = create_address(&base, CALLER);
+		base.set_code(target, vec![0x00]);
+
+		let (value, backend) = transact(&base, &Config::cancun(), create());
+		assert!(matches!(
+			value.exit,
+			Err(ExitError::Exception(ExitException::CreateCollision))
+		));
+		assert_eq!(value.used_gas, U256::from(100_000));
+		assert_eq!(backend.nonce(CALLER), U256::one());
+		assert_eq!(backend.code(target), vec![0x00]);
+		assert_eq!(backend.nonce(target), U256::zero());
+	}
+
+	#[test]
+	fn create_collisions_are_allowed_without_eip_684() {
+		let mut base = backend();
+		let target = create_address(&base, CALLER);
+		base.set_code(target, vec![0x00]);
+		base.set_nonce(target, U256::one());
+		let config = ConfigBuilder::new(Config::cancun())
+			.eip_684_enabled(false)
+			.build();
+
+		let (value, backend) = transact(&base, &config, create());
+		assert!(value.exit.is_ok());
+		assert_eq!(backend.code(target), vec![0x5b]);
+		assert_eq!(backend.nonce(target), U256::from(2));
+	}
+
+	#[test]
+	fn creates_from_the_nonce_cap_fail_without_consuming_gas() {
+		let mut base = backend();
+		// Store 1, then the address created with empty init code, at slot 0.
+		base.set_code(
+			CONTRACT,
+			vec![
+				0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0xf0, 0x60, 0x00,
+				0x55, 0x00,
+			],
+		);
+		base.set_nonce(CONTRACT, U256::from(u64::MAX));
+		let args = call_args(CONTRACT, Vec::new(), 1_000_000, 0);
+
+		let (value, backend) = transact(&base, &Config::cancun(), args);
+		assert!(value.exit.is_ok());
+		// The gas passed to the create is given back.
+		assert!(value.used_gas < U256::from(100_000));
+		assert_eq!(backend.storage(CONTRACT, H256::zero()), H256::zero());
+		assert_eq!(backend.nonce(CONTRACT), U256::from(u64::MAX));
+		assert_eq!(backend.nonce(create_address(&base, CONTRACT)), U256::zero());
+	}
+}
@@ -6,7 +6,14 @@ use evm_interpreter::{
 };
 use primitive_types::{H160, H256, U256};
 
-use crate::{standard::Config, MergeStrategy};
+#[cfg(feature = "counters")]
+use crate::standard::Counters;
+#[cfg(feature = "frames")]
+use crate::standard::FrameGas;
+use crate::{
+	standard::{gasometer::TransactionCost, Config},
+	MergeStrategy,
+};
 
 pub trait InvokerState<'config>: GasState + Sized {
 	fn new_transact_call(
@@ -23,11 +30,33 @@ pub trait InvokerState<'config>: GasState + Sized {
 		access_list: &[(H160, Vec<H256>)],
 		config: &'config Config,
 	) -> Result<Self, ExitError>;
+	/// Create the state of a system call. Unlike a transaction, a system call
+	/// is not charged any intrinsic gas, and all of `gas_limit` is available
+	/// to execution.
+	///
+	/// The default creates a transaction call state, with the intrinsic gas
+	/// added on top of `gas_limit`. The standard [crate::standard::State]
+	/// does not charge it at all.
+	fn new_system_call(
+		runtime: RuntimeState,
+		gas_limit: u64,
+		data: &[u8],
+		config: &'config Config,
+	) -> Result<Self, ExitError> {
+		let intrinsic_gas = TransactionCost::call(data, &[]).cost(config);
+		Self::new_transact_call(
+			runtime,
+			U256::from(gas_limit) + U256::from(intrinsic_gas),
+			data,
+			&[],
+			config,
+		)
+	}
 
 	fn substate(
 		&mut self,
 		runtime: RuntimeState,
-		gas_limit: U256,
+		gas_limit: u64,
 		is_static: bool,
 		call_has_value: bool,
 	) -> Result<Self, ExitError>;
@@ -38,4 +67,161 @@ pub trait InvokerState<'config>: GasState + Sized {
 	fn is_static(&self) -> bool;
 	fn effective_gas(&self) -> U256;
 	fn config(&self) -> &Config;
+	/// Execution counters of this call frame and its substacks, `None` for
+	/// states that do not count. The standard [crate::standard::State]
+	/// counts.
+	#[cfg(feature = "counters")]
+	fn counters(&self) -> Option<&Counters> {
+		None
+	}
+
+	/// Gas limit of this call frame.
+	fn gas_limit(&self) -> u64;
+	/// Record the gas usage of an exited substack. States that do not keep
+	/// frames ignore it. The standard [crate::standard::State] keeps them.
+	#[cfg(feature = "frames")]
+	fn record_frame(&mut self, _frame: FrameGas) {}
+	/// Take the gas usage of the exited substacks of this call frame and
+	/// their substacks, in the order they exited.
+	#[cfg(feature = "frames")]
+	fn take_frames(&mut self) -> Vec<FrameGas> {
+		Vec::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{sync::Arc, vec::Vec};
+
+	use evm_interpreter::{
+		error::{ExitError, ExitException},
+		runtime::{Context, GasState, RuntimeState, TransactionContext},
+	};
+	use primitive_types::{H160, H256, U256};
+
+	use super::InvokerState;
+	use crate::{
+		standard::{Config, State},
+		MergeStrategy,
+	};
+
+	/// A state implementing only the required methods, which must not
+	/// change with the features of the crate.
+	struct Minimal<'config>(State<'config>);
+
+	impl<'config> GasState for Minimal<'config> {
+		fn gas(&self) -> u64 {
+			self.0.gas()
+		}
+	}
+
+	impl<'config> InvokerState<'config> for Minimal<'config> {
+		fn new_transact_call(
+			runtime: RuntimeState,
+			gas_limit: U256,
+			data: &[u8],
+			access_list: &[(H160, Vec<H256>)],
+			config: &'config Config,
+		) -> Result<Self, ExitError> {
+			State::new_transact_call(runtime, gas_limit, data, access_list, config).map(Self)
+		}
+		fn new_transact_create(
+			runtime: RuntimeState,
+			gas_limit: U256,
+			code: &[u8],
+			access_list: &[(H160, Vec<H256>)],
+			config: &'config Config,
+		) -> Result<Self, ExitError> {
+			State::new_transact_create(runtime, gas_limit, code, access_list, config).map(Self)
+		}
+
+		fn substate(
+			&mut self,
+			runtime: RuntimeState,
+			gas_limit: u64,
+			is_static: bool,
+			call_has_value: bool,
+		) -> Result<Self, ExitError> {
+			self.0
+				.substate(runtime, gas_limit, is_static, call_has_value)
+				.map(Self)
+		}
+		fn merge(&mut self, substate: Self, strategy: MergeStrategy) {
+			self.0.merge(substate.0, strategy)
+		}
+
+		fn record_codedeposit(&mut self, len: usize) -> Result<(), ExitError> {
+			self.0.record_codedeposit(len)
+		}
+
+		fn is_static(&self) -> bool {
+			self.0.is_static()
+		}
+		fn effective_gas(&self) -> U256 {
+			self.0.effective_gas()
+		}
+		fn config(&self) -> &Config {
+			self.0.config()
+		}
+		fn gas_limit(&self) -> u64 {
+			self.0.gas_limit()
+		}
+	}
+
+	fn runtime() -> RuntimeState {
+		RuntimeState {
+			context: Context {
+				address: H160::zero(),
+				caller: H160::zero(),
+				apparent_value: U256::zero(),
+			},
+			transaction_context: Arc::new(TransactionContext {
+				gas_price: U256::zero(),
+				origin: H160::zero(),
+				chain_id: U256::one(),
+				prevrandao: true,
+			}),
+			retbuf: Vec::new(),
+			authorized: None,
+		}
+	}
+
+	#[test]
+	fn optional_methods_have_defaults() {
+		let config = Config::cancun();
+		let state =
+			Minimal::new_transact_call(runtime(), U256::from(100_000), &[], &[], &config).unwrap();
+
+		assert_eq!(state.gas_limit(), 100_000);
+		#[cfg(feature = "counters")]
+		assert!(state.counters().is_none());
+		#[cfg(feature = "frames")]
+		assert!(Minimal(state.0).take_frames().is_empty());
+	}
+
+	#[test]
+	fn gas_limits_beyond_u64_are_rejected() {
+		let config = Config::cancun();
+
+		let state =
+			State::new_transact_call(runtime(), U256::from(u64::MAX), &[], &[], &config).unwrap();
+		assert_eq!(state.gas(), u64::MAX - 21_000);
+
+		let result = State::new_transact_call(
+			runtime(),
+			U256::from(u64::MAX) + U256::one(),
+			&[],
+			&[],
+			&config,
+		);
+		assert_eq!(result.err(), Some(ExitException::OutOfGas.into()));
+		let result = State::new_transact_create(
+			runtime(),
+			U256::from(u64::MAX) + U256::one(),
+			&[],
+			&[],
+			&config,
+		);
+		assert_eq!(result.err(), Some(ExitException::OutOfGas.into()));
+	}
 }
@@ -0,0 +1,446 @@
+use alloc::{sync::Arc, vec::Vec};
+use core::convert::Infallible;
+
+use evm_interpreter::{
+	error::{CallCreateTrap, ExitError, ExitException, TrapConsume},
+	runtime::{
+		recent_block_hash, Context, RuntimeBackend, RuntimeBaseBackend, RuntimeEnvironment,
+		RuntimeState, TransactionContext,
+	},
+	utils::u256_to_h256,
+	Interpreter, RunInterpreter,
+};
+use primitive_types::{H160, H256, U256};
+
+use crate::{
+	backend::TransactionalBackend,
+	call_stack::execute,
+	invoker::InvokerControl,
+	standard::{routines, FeePolicy, Invoker, InvokerState, Resolver, TransactionKind},
+	MergeStrategy,
+};
+
+/// Caller of system calls, `0xff...fe`.
+pub const SYSTEM_ADDRESS: H160 = H160([
+	0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+	0xff, 0xff, 0xff, 0xfe,
+]);
+
+/// Gas limit of system calls.
+pub const SYSTEM_CALL_GAS_LIMIT: u64 = 30_000_000;
+
+/// Address of the beacon roots contract (see EIP-4788).
+pub const BEACON_ROOTS_ADDRESS: H160 = H160([
+	0x00, 0x0f, 0x3d, 0xf6, 0xd7, 0x32, 0x80, 0x7e, 0xf1, 0x31, 0x9f, 0xb7, 0xb8, 0xbb, 0x85, 0x22,
+	0xd0, 0xbe, 0xac, 0x02,
+]);
+
+/// Address of the history storage contract (see EIP-2935).
+pub const HISTORY_STORAGE_ADDRESS: H160 = H160([
+	0x00, 0x00, 0xf9, 0x08, 0x27, 0xf1, 0xc5, 0x3a, 0x10, 0xcb, 0x7a, 0x02, 0x33, 0x5b, 0x17, 0x53,
+	0x20, 0x00, 0x29, 0x35,
+]);
+
+/// Number of block hashes served by the history storage contract (see
+/// EIP-2935).
+pub const HISTORY_SERVE_WINDOW: u64 = 8191;
+
+/// Address of the deposit contract of mainnet (see EIP-6110).
+pub const DEPOSIT_CONTRACT_ADDRESS: H160 = H160([
+	0x00, 0x00, 0x00, 0x00, 0x21, 0x9a, 0xb5, 0x40, 0x35, 0x6c, 0xbb, 0x83, 0x9c, 0xbe, 0x05, 0x30,
+	0x3d, 0x77, 0x05, 0xfa,
+]);
+
+/// Address of the withdrawal request contract (see EIP-7002).
+pub const WITHDRAWAL_REQUEST_ADDRESS: H160 = H160([
+	0x00, 0x00, 0x09, 0x61, 0xef, 0x48, 0x0e, 0xb5, 0x5e, 0x80, 0xd1, 0x9a, 0xd8, 0x35, 0x79, 0xa6,
+	0x4c, 0x00, 0x70, 0x02,
+]);
+
+/// Address of the consolidation request contract (see EIP-7251).
+pub const CONSOLIDATION_REQUEST_ADDRESS: H160 = H160([
+	0x00, 0x00, 0xbb, 0xdd, 0xc7, 0xce, 0x48, 0x86, 0x42, 0xfb, 0x57, 0x9f, 0x8b, 0x00, 0xf3, 0xa5,
+	0x90, 0x00, 0x72, 0x51,
+]);
+
+/// Get the block hash of `number`, served from the history storage contract
+/// over its [HISTORY_SERVE_WINDOW] (see EIP-2935), as a call to the contract
+/// would. `BLOCKHASH` is unchanged by EIP-2935, and still only serves the
+/// [recent_block_hash] window.
+///
+/// Blocks before the fork are not recorded in the contract, and for them the
+/// hash falls back to [recent_block_hash].
+pub fn history_block_hash<H: RuntimeEnvironment + RuntimeBaseBackend>(
+	handler: &H,
+	number: U256,
+) -> H256 {
+	let current = handler.block_number();
+	if number >= current || number.saturating_add(U256::from(HISTORY_SERVE_WINDOW)) < current {
+		return H256::default();
+	}
+
+	let slot = u256_to_h256(number % U256::from(HISTORY_SERVE_WINDOW));
+	let hash = handler.storage(HISTORY_STORAGE_ADDRESS, slot);
+	if hash == H256::default() {
+		recent_block_hash(handler, number)
+	} else {
+		hash
+	}
+}
+
+impl<'config, 'resolver, R, F, K> Invoker<'config, 'resolver, R, F, K> {
+	/// Execute a system call from [SYSTEM_ADDRESS] to `address`.
+	///
+	/// A system call does not pay any fees or intrinsic gas, does not increase
+	/// the nonce of the caller and does not transfer value. State changes are committed if the
+	/// call succeeds, and discarded otherwise.
+	pub fn system_call<H, Tr>(
+		&self,
+		address: H160,
+		data: Vec<u8>,
+		handler: &mut H,
+	) -> Result<Vec<u8>, ExitError>
+	where
+		R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+		R::Interpreter: RunInterpreter<H, Tr>,
+		H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+		R: Resolver<H>,
+		F: FeePolicy<H>,
+		K: TransactionKind<'config, H, R>,
+		Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+	{
+		let runtime_state = RuntimeState {
+			context: Context {
+				caller: SYSTEM_ADDRESS,
+				address,
+				apparent_value: U256::zero(),
+			},
+			transaction_context: Arc::new(TransactionContext {
+				origin: SYSTEM_ADDRESS,
+				gas_price: U256::zero(),
+				chain_id: self.config.effective_chain_id(handler),
+				prevrandao: self.config.has_prevrandao,
+			}),
+			retbuf: Vec::new(),
+			authorized: None,
+		};
+
+		handler.push_substate();
+
+		let work = || -> Result<Vec<u8>, ExitError> {
+			let state = <R::State>::new_system_call(
+				runtime_state,
+				SYSTEM_CALL_GAS_LIMIT,
+				&data,
+				self.config,
+			)?;
+
+			let machine = routines::make_enter_call_machine(
+				self.config,
+				self.resolver,
+				address,
+				data,
+				None,
+				state,
+				handler,
+			)?;
+
+			let (result, retval) = match machine {
+				InvokerControl::Enter(machine) => {
+					let (result, machine) =
+						execute::<H, Tr, Self>(machine, 0, None, handler, self)?;
+					(result, machine.deconstruct().1)
+				}
+				InvokerControl::DirectExit((result, (_, retval))) => (result, retval),
+			};
+
+			result.map(|_| retval)
+		};
+
+		let result = work();
+
+		match &result {
+			Ok(_) => handler.pop_substate(MergeStrategy::Commit),
+			Err(_) => handler.pop_substate(MergeStrategy::Discard),
+		}
+
+		result
+	}
+
+	/// Store the parent beacon block root in the beacon roots contract, as
+	/// required at the start of each block since Cancun (see EIP-4788).
+	///
+	/// This is a no-op if the config does not enable EIP-4788, if the
+	/// environment has no parent beacon block root, or if the beacon roots
+	/// contract is not deployed.
+	pub fn process_beacon_block_root<H, Tr>(&self, handler: &mut H) -> Result<(), ExitError>
+	where
+		R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+		R::Interpreter: RunInterpreter<H, Tr>,
+		H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+		R: Resolver<H>,
+		F: FeePolicy<H>,
+		K: TransactionKind<'config, H, R>,
+		Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+	{
+		if !self.config.eip_4788_enabled {
+			return Ok(());
+		}
+
+		let root = match handler.block_parent_beacon_root() {
+			Some(root) => root,
+			None => return Ok(()),
+		};
+
+		if handler.code_size(BEACON_ROOTS_ADDRESS) == U256::zero() {
+			return Ok(());
+		}
+
+		self.system_call::<H, Tr>(BEACON_ROOTS_ADDRESS, root.as_bytes().to_vec(), handler)?;
+
+		Ok(())
+	}
+
+	/// Store the parent block hash in the history storage contract, as
+	/// required at the start of each block since Prague (see EIP-2935).
+	///
+	/// This is a no-op if the config does not enable EIP-2935, for the
+	/// genesis block, or if the history storage contract is not deployed.
+	pub fn process_parent_block_hash<H, Tr>(&self, handler: &mut H) -> Result<(), ExitError>
+	where
+		R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+		R::Interpreter: RunInterpreter<H, Tr>,
+		H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+		R: Resolver<H>,
+		F: FeePolicy<H>,
+		K: TransactionKind<'config, H, R>,
+		Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+	{
+		if !self.config.eip_2935_enabled {
+			return Ok(());
+		}
+
+		let number = handler.block_number();
+		if number == U256::zero() {
+			return Ok(());
+		}
+
+		if handler.code_size(HISTORY_STORAGE_ADDRESS) == U256::zero() {
+			return Ok(());
+		}
+
+		let parent_hash = handler.block_hash(number - U256::one());
+		self.system_call::<H, Tr>(
+			HISTORY_STORAGE_ADDRESS,
+			parent_hash.as_bytes().to_vec(),
+			handler,
+		)?;
+
+		Ok(())
+	}
+
+	/// Dequeue the withdrawal requests of the block from the withdrawal
+	/// request contract, as required at the end of each block since Prague
+	/// (see EIP-7002). Return the request data, the concatenated requests.
+	///
+	/// The block is invalid if the contract is not deployed, which is
+	/// returned as an error.
+	pub fn process_withdrawal_requests<H, Tr>(&self, handler: &mut H) -> Result<Vec<u8>, ExitError>
+	where
+		R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+		R::Interpreter: RunInterpreter<H, Tr>,
+		H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+		R: Resolver<H>,
+		F: FeePolicy<H>,
+		K: TransactionKind<'config, H, R>,
+		Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+	{
+		self.dequeue_requests::<H, Tr>(WITHDRAWAL_REQUEST_ADDRESS, handler)
+	}
+
+	/// Dequeue the consolidation requests of the block from the
+	/// consolidation request contract, as required at the end of each block
+	/// since Prague (see EIP-7251). Return the request data, the concatenated
+	/// requests.
+	///
+	/// The block is invalid if the contract is not deployed, which is
+	/// returned as an error.
+	pub fn process_consolidation_requests<H, Tr>(
+		&self,
+		handler: &mut H,
+	) -> Result<Vec<u8>, ExitError>
+	where
+		R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+		R::Interpreter: RunInterpreter<H, Tr>,
+		H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+		R: Resolver<H>,
+		F: FeePolicy<H>,
+		K: TransactionKind<'config, H, R>,
+		Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+	{
+		self.dequeue_requests::<H, Tr>(CONSOLIDATION_REQUEST_ADDRESS, handler)
+	}
+
+	fn dequeue_requests<H, Tr>(&self, address: H160, handler: &mut H) -> Result<Vec<u8>, ExitError>
+	where
+		R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+		R::Interpreter: RunInterpreter<H, Tr>,
+		H: RuntimeEnvironment + RuntimeBackend + TransactionalBackend,
+		R: Resolver<H>,
+		F: FeePolicy<H>,
+		K: TransactionKind<'config, H, R>,
+		Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+	{
+		if handler.code_size(address) == U256::zero() {
+			return Err(ExitException::Other("request contract is not deployed".into()).into());
+		}
+
+		self.system_call::<H, Tr>(address, Vec::new(), handler)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::collections::BTreeSet;
+
+	use evm_interpreter::{
+		error::CallCreateTrap,
+		runtime::{recent_block_hash, RuntimeBaseBackend},
+	};
+	use primitive_types::{H160, H256, U256};
+
+	use super::{
+		history_block_hash, BEACON_ROOTS_ADDRESS, HISTORY_SERVE_WINDOW, HISTORY_STORAGE_ADDRESS,
+		SYSTEM_CALL_GAS_LIMIT,
+	};
+	use crate::{
+		backend::{OverlayedBackend, TestBackend, TestEnvironment},
+		standard::{testing::etable, Config, EtableResolver, Invoker},
+	};
+
+	/// Code of the beacon roots contract (see EIP-4788).
+	const BEACON_ROOTS_CODE: &str = "3373fffffffffffffffffffffffffffffffffffffffe14604d57602036146024575f5ffd5b5f35801560495762001fff810690815414603c575f5ffd5b62001fff01545f5260205ff35b5f5ffd5b62001fff42064281555f359062001fff015500";
+	/// Code of the history storage contract (see EIP-2935).
+	const HISTORY_STORAGE_CODE: &str = "3373fffffffffffffffffffffffffffffffffffffffe14604657602036036042575f35600143038111604257611fff81430311604257611fff9006545f5260205ff35b5f5ffd5b5f35611fff60014303065500";
+
+	fn storage_after_system_calls(
+		base: &TestBackend,
+		address: H160,
+		index: u64,
+		config: &Config,
+	) -> H256 {
+		let etable = etable();
+		let resolver = EtableResolver::new(config, &(), &etable);
+		let invoker = Invoker::new(config, &resolver);
+
+		let mut backend = OverlayedBackend::new(base, BTreeSet::new());
+		invoker
+			.process_beacon_block_root::<_, CallCreateTrap>(&mut backend)
+			.unwrap();
+		invoker
+			.process_parent_block_hash::<_, CallCreateTrap>(&mut backend)
+			.unwrap();
+		backend.storage(address, H256::from_low_u64_be(index))
+	}
+
+	#[test]
+	fn beacon_block_root_is_stored() {
+		let mut base = TestBackend::new(TestEnvironment {
+			block_number: U256::from(100),
+			block_timestamp: U256::from(20_000),
+			block_parent_beacon_root: Some(H256::repeat_byte(3)),
+			..Default::default()
+		});
+		base.set_code(
+			BEACON_ROOTS_ADDRESS,
+			hex::decode(BEACON_ROOTS_CODE).unwrap(),
+		);
+		let config = Config::cancun();
+
+		let slot = 20_000 % HISTORY_SERVE_WINDOW;
+		assert_eq!(
+			storage_after_system_calls(&base, BEACON_ROOTS_ADDRESS, slot, &config),
+			H256::from_low_u64_be(20_000)
+		);
+		assert_eq!(
+			storage_after_system_calls(
+				&base,
+				BEACON_ROOTS_ADDRESS,
+				slot + HISTORY_SERVE_WINDOW,
+				&config
+			),
+			H256::repeat_byte(3)
+		);
+	}
+
+	#[test]
+	fn parent_block_hash_is_stored() {
+		let mut base = TestBackend::new(TestEnvironment {
+			block_number: U256::from(10_000),
+			..Default::default()
+		});
+		base.environment
+			.block_hashes
+			.insert(U256::from(9_999), H256::repeat_byte(4));
+		base.set_code(
+			HISTORY_STORAGE_ADDRESS,
+			hex::decode(HISTORY_STORAGE_CODE).unwrap(),
+		);
+		let config = Config::prague();
+
+		assert_eq!(
+			storage_after_system_calls(
+				&base,
+				HISTORY_STORAGE_ADDRESS,
+				9_999 % HISTORY_SERVE_WINDOW,
+				&config
+			),
+			H256::repeat_byte(4)
+		);
+	}
+
+	#[test]
+	fn system_calls_are_not_charged_intrinsic_gas() {
+		let mut base = TestBackend::new(TestEnvironment {
+			block_parent_beacon_root: Some(H256::repeat_byte(3)),
+			..Default::default()
+		});
+		// GAS PUSH0 SSTORE STOP
+		base.set_code(BEACON_ROOTS_ADDRESS, [0x5a, 0x5f, 0x55, 0x00].to_vec());
+		let config = Config::cancun();
+
+		// The gas left after GAS, which costs 2.
+		assert_eq!(
+			storage_after_system_calls(&base, BEACON_ROOTS_ADDRESS, 0, &config),
+			H256::from_low_u64_be(SYSTEM_CALL_GAS_LIMIT - 2)
+		);
+	}
+
+	#[test]
+	fn history_serves_blocks_beyond_the_blockhash_window() {
+		let mut backend = TestBackend::new(TestEnvironment {
+			block_number: U256::from(10_000),
+			..Default::default()
+		});
+		let old = U256::from(10_000 - 300);
+		let recent = U256::from(10_000 - 1);
+		backend
+			.environment
+			.block_hashes
+			.insert(recent, H256::repeat_byte(1));
+		backend.set_storage(
+			HISTORY_STORAGE_ADDRESS,
+			H256::from_low_u64_be(old.as_u64() % HISTORY_SERVE_WINDOW),
+			H256::repeat_byte(2),
+		);
+
+		assert_eq!(history_block_hash(&backend, old), H256::repeat_byte(2));
+		assert_eq!(recent_block_hash(&backend, old), H256::zero());
+		// Blocks not in the contract, from before the fork.
+		assert_eq!(history_block_hash(&backend, recent), H256::repeat_byte(1));
+		assert_eq!(
+			history_block_hash(&backend, U256::from(10_000 - HISTORY_SERVE_WINDOW - 1)),
+			H256::zero()
+		);
+	}
+}
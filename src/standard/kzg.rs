@@ -0,0 +1,112 @@
+//! # Blob sidecar verification
+//!
+//! Verification of the blobs of EIP-4844 transactions against their
+//! versioned hashes, enabled by the `kzg` feature. Block builders and
+//! validators check the sidecar of a blob transaction with
+//! [BlobSidecar::verify] before executing it. The trusted setup is provided
+//! by the caller, see [KzgSettings].
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use c_kzg::KzgProof;
+pub use c_kzg::{Blob, Bytes48, KzgSettings};
+use primitive_types::H256;
+use sha2::{Digest, Sha256};
+
+/// Version byte of versioned hashes of KZG commitments.
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Versioned hash of a KZG commitment, its SHA-256 hash with the first byte
+/// replaced by [VERSIONED_HASH_VERSION_KZG].
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> H256 {
+	let mut hash = H256::from_slice(Sha256::digest(commitment).as_slice());
+	hash.0[0] = VERSIONED_HASH_VERSION_KZG;
+	hash
+}
+
+/// Reason for a blob sidecar to be invalid.
+#[derive(Debug)]
+pub enum BlobSidecarError {
+	/// Numbers of blobs, commitments, proofs and versioned hashes differ.
+	LengthMismatch,
+	/// Commitment at the given index does not match the versioned hash.
+	VersionedHashMismatch(usize),
+	/// KZG proofs do not prove the commitments of the blobs.
+	InvalidProof,
+	/// A blob, commitment or proof is malformed.
+	Kzg(c_kzg::Error),
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BlobSidecarError {}
+
+impl fmt::Display for BlobSidecarError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::LengthMismatch => f.write_str("blob sidecar length mismatch"),
+			Self::VersionedHashMismatch(index) => {
+				write!(f, "commitment {index} does not match its versioned hash")
+			}
+			Self::InvalidProof => f.write_str("invalid blob KZG proof"),
+			Self::Kzg(e) => write!(f, "invalid blob sidecar: {e}"),
+		}
+	}
+}
+
+/// Blobs of a blob transaction, with their KZG commitments and proofs.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BlobSidecar {
+	/// Blobs.
+	pub blobs: Vec<Blob>,
+	/// KZG commitment of each blob.
+	pub commitments: Vec<Bytes48>,
+	/// KZG proof of each blob.
+	pub proofs: Vec<Bytes48>,
+}
+
+impl BlobSidecar {
+	/// Versioned hashes of the commitments.
+	pub fn versioned_hashes(&self) -> Vec<H256> {
+		self.commitments
+			.iter()
+			.map(|commitment| kzg_to_versioned_hash(&commitment[..]))
+			.collect()
+	}
+
+	/// Verify that the commitments match the versioned hashes of the
+	/// transaction, and that the proofs prove the commitments of the blobs.
+	pub fn verify(
+		&self,
+		versioned_hashes: &[H256],
+		settings: &KzgSettings,
+	) -> Result<(), BlobSidecarError> {
+		if self.blobs.len() != versioned_hashes.len()
+			|| self.commitments.len() != versioned_hashes.len()
+			|| self.proofs.len() != versioned_hashes.len()
+		{
+			return Err(BlobSidecarError::LengthMismatch);
+		}
+
+		for (index, (commitment, versioned_hash)) in
+			self.commitments.iter().zip(versioned_hashes).enumerate()
+		{
+			if kzg_to_versioned_hash(&commitment[..]) != *versioned_hash {
+				return Err(BlobSidecarError::VersionedHashMismatch(index));
+			}
+		}
+
+		let valid = KzgProof::verify_blob_kzg_proof_batch(
+			&self.blobs,
+			&self.commitments,
+			&self.proofs,
+			settings,
+		)
+		.map_err(BlobSidecarError::Kzg)?;
+		if valid {
+			Ok(())
+		} else {
+			Err(BlobSidecarError::InvalidProof)
+		}
+	}
+}
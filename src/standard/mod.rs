@@ -3,10 +3,38 @@
 //! This module implements the standard configurations of the interpreter, like how it works on
 //! Ethereum mainnet. Most of them can still be customized to add additional functionality, by
 //! wrapping them or replacing the generic parameters.
-
+//!
+//! Accounts are identified by [H160] addresses. For other address types, the runtime traits of the
+//! interpreter, its runtime etable
+//! ([crate::interpreter::etable::Etable::runtime_with_address]) and [TransactArgs] can be used with
+//! a custom invoker.
+
+pub mod blob;
+pub mod block;
+mod bloom;
+mod chain;
 mod config;
+#[cfg(feature = "counters")]
+mod counters;
+mod decoded;
+#[cfg(feature = "inspector")]
+pub mod erc4337;
+mod frame;
 mod gasometer;
+#[cfg(feature = "inspector")]
+pub mod inspector;
 mod invoker;
+#[cfg(feature = "kzg")]
+pub mod kzg;
+pub mod receipt;
+mod revert;
+#[cfg(feature = "signed-transaction")]
+pub mod signed;
+pub mod simulate;
+#[cfg(test)]
+pub(crate) mod testing;
+mod validation;
+pub mod weight;
 
 use alloc::vec::Vec;
 
@@ -17,12 +45,27 @@ use evm_interpreter::{
 };
 use primitive_types::{H160, H256, U256};
 
+#[cfg(feature = "counters")]
+pub use self::counters::Counters;
 pub use self::{
-	config::Config,
-	gasometer::{eval as eval_gasometer, GasometerState},
+	bloom::{Bloom, BLOOM_SIZE},
+	chain::{Activation, ChainSpec, ForkBoundary},
+	config::{BaseFeeSink, Config, ConfigBuilder, Fork, EIP_3541_BANNED_CODE_PREFIXES},
+	decoded::DecodedEtable,
+	frame::FrameGas,
+	gasometer::{eval as eval_gasometer, static_gas_costs, AccessWitness, GasometerState, TreeKey},
 	invoker::{
-		routines, EtableResolver, Invoker, InvokerState, PrecompileSet, Resolver, SubstackInvoke,
-		TransactArgs, TransactInvoke, TransactValue,
+		history_block_hash, routines, EtableResolver, EthereumFeePolicy, FeePolicy, Invoker,
+		InvokerState, Precompile, PrecompileRegistry, PrecompileSet, Resolver, SubstackInvoke,
+		TransactArgs, TransactFee, TransactInvoke, TransactValue, TransactionKind,
+		BEACON_ROOTS_ADDRESS, CONSOLIDATION_REQUEST_ADDRESS, DEPOSIT_CONTRACT_ADDRESS,
+		HISTORY_SERVE_WINDOW, HISTORY_STORAGE_ADDRESS, SYSTEM_ADDRESS, SYSTEM_CALL_GAS_LIMIT,
+		WITHDRAWAL_REQUEST_ADDRESS,
+	},
+	revert::{RevertReason, ERROR_SELECTOR, PANIC_SELECTOR},
+	validation::{
+		floor_gas, intrinsic_gas, is_delegation_designator, validate_transaction,
+		validate_transaction_fees, FeeCaps, ValidationError,
 	},
 };
 use crate::{gasometer::GasMutState, MergeStrategy};
@@ -67,8 +110,8 @@ impl<'config> AsMut<GasometerState<'config>> for State<'config> {
 }
 
 impl<'config> GasState for State<'config> {
-	fn gas(&self) -> U256 {
-		self.gasometer.gas()
+	fn gas(&self) -> u64 {
+		self.gasometer.gas64()
 	}
 }
 
@@ -76,6 +119,19 @@ impl<'config> GasMutState for State<'config> {
 	fn record_gas(&mut self, gas: U256) -> Result<(), ExitError> {
 		self.gasometer.record_gas(gas)
 	}
+
+	fn record_gas64(&mut self, gas: u64) -> Result<(), ExitError> {
+		self.gasometer.record_gas64(gas)
+	}
+
+	fn record_memory_expansion(&mut self, offset: U256, len: U256) -> Result<(), ExitError> {
+		self.gasometer.record_memory_expansion(offset, len)
+	}
+
+	fn record_refund(&mut self, refund: i64) -> Result<(), ExitError> {
+		self.gasometer.record_refund(refund);
+		Ok(())
+	}
 }
 
 impl<'config> InvokerState<'config> for State<'config> {
@@ -103,11 +159,22 @@ impl<'config> InvokerState<'config> for State<'config> {
 			gasometer: GasometerState::new_transact_create(gas_limit, code, access_list, config)?,
 		})
 	}
+	fn new_system_call(
+		runtime: RuntimeState,
+		gas_limit: u64,
+		_data: &[u8],
+		config: &'config Config,
+	) -> Result<Self, ExitError> {
+		Ok(Self {
+			runtime,
+			gasometer: GasometerState::new(gas_limit, false, config),
+		})
+	}
 
 	fn substate(
 		&mut self,
 		runtime: RuntimeState,
-		gas_limit: U256,
+		gas_limit: u64,
 		is_static: bool,
 		call_has_value: bool,
 	) -> Result<Self, ExitError> {
@@ -122,6 +189,23 @@ impl<'config> InvokerState<'config> for State<'config> {
 		self.gasometer.merge(substate.gasometer, strategy)
 	}
 
+	#[cfg(feature = "counters")]
+	fn counters(&self) -> Option<&Counters> {
+		Some(&self.gasometer.counters)
+	}
+
+	fn gas_limit(&self) -> u64 {
+		self.gasometer.gas_limit()
+	}
+	#[cfg(feature = "frames")]
+	fn record_frame(&mut self, frame: FrameGas) {
+		self.gasometer.frames.push(frame);
+	}
+	#[cfg(feature = "frames")]
+	fn take_frames(&mut self) -> Vec<FrameGas> {
+		core::mem::take(&mut self.gasometer.frames)
+	}
+
 	fn record_codedeposit(&mut self, len: usize) -> Result<(), ExitError> {
 		self.gasometer.record_codedeposit(len)
 	}
@@ -0,0 +1,105 @@
+//! # Transaction receipts
+//!
+//! Build Ethereum receipts out of transaction results, to be RLP-encoded
+//! and used for the receipts root.
+
+use alloc::vec::Vec;
+
+use evm_interpreter::runtime::Log;
+use primitive_types::U256;
+use rlp::RlpStream;
+
+use crate::standard::{Bloom, TransactValue};
+
+/// Transaction type (see EIP-2718).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub enum TransactionType {
+	/// Legacy transaction, without type envelope.
+	Legacy,
+	/// Access list transaction (see EIP-2930).
+	AccessList,
+	/// Dynamic fee transaction (see EIP-1559).
+	DynamicFee,
+	/// Blob transaction (see EIP-4844).
+	Blob,
+}
+
+impl TransactionType {
+	/// The type byte in the envelope. `None` for legacy transactions.
+	pub fn id(&self) -> Option<u8> {
+		match self {
+			Self::Legacy => None,
+			Self::AccessList => Some(0x01),
+			Self::DynamicFee => Some(0x02),
+			Self::Blob => Some(0x03),
+		}
+	}
+}
+
+/// Transaction receipt.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub struct Receipt {
+	/// Type of the transaction.
+	pub transaction_type: TransactionType,
+	/// Whether the execution succeeded (see EIP-658).
+	pub status: bool,
+	/// Gas used in the block up to and including this transaction.
+	pub cumulative_gas_used: U256,
+	/// Bloom of the logs.
+	pub logs_bloom: Bloom,
+	/// Logs emitted by the transaction.
+	pub logs: Vec<Log>,
+}
+
+impl Receipt {
+	/// Build the receipt of a transaction, given the gas used in the block
+	/// before it.
+	pub fn new(
+		transaction_type: TransactionType,
+		value: &TransactValue,
+		previous_cumulative_gas_used: U256,
+	) -> Self {
+		Self {
+			transaction_type,
+			status: value.exit.is_ok(),
+			cumulative_gas_used: previous_cumulative_gas_used.saturating_add(value.used_gas),
+			logs_bloom: value.logs_bloom,
+			logs: value.logs.clone(),
+		}
+	}
+
+	/// Encode the receipt, as it is inserted into the receipts trie. Typed
+	/// receipts are prefixed with the transaction type (see EIP-2718).
+	pub fn encode(&self) -> Vec<u8> {
+		let mut out = Vec::new();
+		if let Some(id) = self.transaction_type.id() {
+			out.push(id);
+		}
+		out.extend_from_slice(&rlp::encode(self));
+		out
+	}
+}
+
+impl rlp::Encodable for Receipt {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.append(&self.status);
+		s.append(&self.cumulative_gas_used);
+		s.append(&self.logs_bloom.as_bytes());
+		s.begin_list(self.logs.len());
+		for log in &self.logs {
+			s.begin_list(3);
+			s.append(&log.address);
+			s.append_list(&log.topics);
+			s.append(&log.data);
+		}
+	}
+}
@@ -0,0 +1,94 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use primitive_types::U256;
+
+/// Selector of `Error(string)`.
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`.
+pub const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Reason of a revert, decoded from the revert data as Solidity encodes it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RevertReason {
+	/// `Error(string)`, from `require` and `revert` with a message.
+	Error(String),
+	/// `Panic(uint256)`, from failed assertions and checked arithmetic, with
+	/// the panic code.
+	Panic(U256),
+}
+
+impl RevertReason {
+	/// Decode the revert data. Return `None` if it is neither an
+	/// `Error(string)` nor a `Panic(uint256)` payload, for example for custom
+	/// errors or an empty revert.
+	pub fn decode(data: &[u8]) -> Option<Self> {
+		if data.len() < 4 {
+			return None;
+		}
+		let (selector, payload) = data.split_at(4);
+
+		if selector == PANIC_SELECTOR {
+			if payload.len() != 32 {
+				return None;
+			}
+			Some(Self::Panic(U256::from_big_endian(payload)))
+		} else if selector == ERROR_SELECTOR {
+			let offset = abi_word(payload, 0)?;
+			let len = abi_word(payload, offset)?;
+			let start = offset.checked_add(32)?;
+			let bytes = payload.get(start..start.checked_add(len)?)?;
+			String::from_utf8(Vec::from(bytes)).ok().map(Self::Error)
+		} else {
+			None
+		}
+	}
+
+	/// Description of a known panic code.
+	pub fn panic_description(code: U256) -> Option<&'static str> {
+		if code > U256::from(u8::MAX) {
+			return None;
+		}
+
+		Some(match code.low_u32() {
+			0x00 => "generic panic",
+			0x01 => "assertion failed",
+			0x11 => "arithmetic overflow or underflow",
+			0x12 => "division or modulo by zero",
+			0x21 => "invalid enum value",
+			0x22 => "invalid storage byte array encoding",
+			0x31 => "pop on an empty array",
+			0x32 => "array index out of bounds",
+			0x41 => "too much memory allocated",
+			0x51 => "call to a zero-initialized function",
+			_ => return None,
+		})
+	}
+}
+
+/// Read an ABI word at the position as an offset or a length.
+fn abi_word(payload: &[u8], position: usize) -> Option<usize> {
+	let word = payload.get(position..position.checked_add(32)?)?;
+	let word = U256::from_big_endian(word);
+	if word > U256::from(usize::MAX) {
+		return None;
+	}
+	Some(word.as_usize())
+}
+
+impl fmt::Display for RevertReason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Error(message) => f.write_str(message),
+			Self::Panic(code) => match Self::panic_description(*code) {
+				Some(description) => write!(f, "panic: {description} ({code:#x})"),
+				None => write!(f, "panic: {code:#x}"),
+			},
+		}
+	}
+}
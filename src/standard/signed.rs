@@ -0,0 +1,483 @@
+//! # Signed transactions
+//!
+//! Decode signed raw transactions as received from the network, verify their
+//! signatures and recover their senders, enabled by the `signed-transaction`
+//! feature. The decoded transaction can then be validated and executed as
+//! [TransactArgs].
+//!
+//! Legacy (with or without EIP-155 replay protection), access list, dynamic
+//! fee and blob transactions are supported. Blob transactions are accepted in
+//! their canonical form, without the blobs, commitments and proofs of the
+//! network form.
+
+use alloc::vec::Vec;
+use core::{cmp::min, fmt};
+
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use primitive_types::{H160, H256, U256};
+use rlp::{DecoderError, Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+
+use crate::standard::{receipt::TransactionType, FeeCaps, TransactArgs};
+
+/// Reason for a signed transaction to be rejected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SignedTransactionError {
+	/// Transaction is not well-formed.
+	Decode(DecoderError),
+	/// Transaction type is not supported.
+	UnsupportedType(u8),
+	/// Transaction is signed for another chain.
+	ChainIdMismatch,
+	/// Signature is invalid, or the sender cannot be recovered from it.
+	InvalidSignature,
+	/// Blob transaction has no target. Blob transactions cannot create
+	/// contracts.
+	BlobCreate,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignedTransactionError {}
+
+impl fmt::Display for SignedTransactionError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Decode(e) => write!(f, "malformed transaction: {e}"),
+			Self::UnsupportedType(ty) => write!(f, "unsupported transaction type 0x{ty:02x}"),
+			Self::ChainIdMismatch => f.write_str("transaction signed for another chain"),
+			Self::InvalidSignature => f.write_str("invalid signature"),
+			Self::BlobCreate => f.write_str("blob transaction cannot create a contract"),
+		}
+	}
+}
+
+impl From<DecoderError> for SignedTransactionError {
+	fn from(err: DecoderError) -> Self {
+		Self::Decode(err)
+	}
+}
+
+/// A signed transaction, with its sender recovered.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedTransaction {
+	/// Transaction type.
+	pub transaction_type: TransactionType,
+	/// Transaction hash.
+	pub hash: H256,
+	/// Sender recovered from the signature.
+	pub sender: H160,
+	/// Chain ID the transaction is signed for. `None` for legacy transactions
+	/// without replay protection.
+	pub chain_id: Option<U256>,
+	/// Transaction nonce.
+	pub nonce: U256,
+	/// Maximum priority fee per gas. The gas price for legacy and access list
+	/// transactions.
+	pub max_priority_fee_per_gas: U256,
+	/// Maximum fee per gas. The gas price for legacy and access list
+	/// transactions.
+	pub max_fee_per_gas: U256,
+	/// Transaction gas limit.
+	pub gas_limit: U256,
+	/// Transaction target. `None` for a create transaction.
+	pub to: Option<H160>,
+	/// Transaction value.
+	pub value: U256,
+	/// Call data, or init code for a create transaction.
+	pub data: Vec<u8>,
+	/// Access list information, in the format of (address, storage keys).
+	pub access_list: Vec<(H160, Vec<H256>)>,
+	/// Maximum fee per blob gas. Zero for transactions without blobs.
+	pub max_fee_per_blob_gas: U256,
+	/// Versioned hashes of the blobs.
+	pub blob_versioned_hashes: Vec<H256>,
+}
+
+impl SignedTransaction {
+	/// Decode a signed raw transaction, either a legacy RLP list or an
+	/// EIP-2718 typed envelope, verify its signature and recover its sender.
+	/// Transactions signed for another chain than `chain_id` are rejected.
+	pub fn decode(raw: &[u8], chain_id: U256) -> Result<Self, SignedTransactionError> {
+		let first = *raw.first().ok_or(DecoderError::RlpIsTooShort)?;
+		if first >= 0xc0 {
+			return Self::decode_legacy(raw, chain_id);
+		}
+
+		let transaction_type = match first {
+			0x01 => TransactionType::AccessList,
+			0x02 => TransactionType::DynamicFee,
+			0x03 => TransactionType::Blob,
+			_ => return Err(SignedTransactionError::UnsupportedType(first)),
+		};
+		let rlp = Rlp::new(&raw[1..]);
+		// Fields of the signed payload, before the signature.
+		let fields = match transaction_type {
+			TransactionType::AccessList => 8,
+			TransactionType::DynamicFee => 9,
+			_ => 11,
+		};
+		if rlp.item_count()? != fields + 3 {
+			return Err(DecoderError::RlpIncorrectListLen.into());
+		}
+
+		let tx_chain_id: U256 = rlp.val_at(0)?;
+		if tx_chain_id != chain_id {
+			return Err(SignedTransactionError::ChainIdMismatch);
+		}
+
+		let (max_priority_fee_per_gas, max_fee_per_gas, rest) = match transaction_type {
+			TransactionType::AccessList => {
+				let gas_price = rlp.val_at(2)?;
+				(gas_price, gas_price, 3)
+			}
+			_ => (rlp.val_at(2)?, rlp.val_at(3)?, 4),
+		};
+		let (max_fee_per_blob_gas, blob_versioned_hashes) = match transaction_type {
+			TransactionType::Blob => (rlp.val_at(9)?, rlp.list_at(10)?),
+			_ => (U256::zero(), Vec::new()),
+		};
+		let to = decode_to(&rlp.at(rest + 1)?)?;
+		if transaction_type == TransactionType::Blob && to.is_none() {
+			return Err(SignedTransactionError::BlobCreate);
+		}
+
+		let y_parity: u8 = rlp.val_at(fields)?;
+		let r: U256 = rlp.val_at(fields + 1)?;
+		let s: U256 = rlp.val_at(fields + 2)?;
+
+		let mut stream = RlpStream::new_list(fields);
+		for i in 0..fields {
+			stream.append_raw(rlp.at(i)?.as_raw(), 1);
+		}
+		let mut payload = Vec::with_capacity(stream.len() + 1);
+		payload.push(first);
+		payload.extend_from_slice(&stream.out());
+		let sender = recover(keccak(&payload), y_parity, r, s)?;
+
+		Ok(Self {
+			transaction_type,
+			hash: keccak(raw),
+			sender,
+			chain_id: Some(tx_chain_id),
+			nonce: rlp.val_at(1)?,
+			max_priority_fee_per_gas,
+			max_fee_per_gas,
+			gas_limit: rlp.val_at(rest)?,
+			to,
+			value: rlp.val_at(rest + 2)?,
+			data: rlp.val_at(rest + 3)?,
+			access_list: decode_access_list(&rlp.at(rest + 4)?)?,
+			max_fee_per_blob_gas,
+			blob_versioned_hashes,
+		})
+	}
+
+	fn decode_legacy(raw: &[u8], chain_id: U256) -> Result<Self, SignedTransactionError> {
+		let rlp = Rlp::new(raw);
+		if rlp.item_count()? != 9 {
+			return Err(DecoderError::RlpIncorrectListLen.into());
+		}
+
+		let v: U256 = rlp.val_at(6)?;
+		let r: U256 = rlp.val_at(7)?;
+		let s: U256 = rlp.val_at(8)?;
+
+		// EIP-155: `v` is `chain_id * 2 + 35 + y_parity` for replay protected
+		// transactions, and `27 + y_parity` otherwise.
+		let (tx_chain_id, y_parity) = if v == U256::from(27) || v == U256::from(28) {
+			(None, (v.low_u64() - 27) as u8)
+		} else if v >= U256::from(35) {
+			let v = v - U256::from(35);
+			(Some(v / 2), (v % 2).low_u64() as u8)
+		} else {
+			return Err(SignedTransactionError::InvalidSignature);
+		};
+		if let Some(tx_chain_id) = tx_chain_id {
+			if tx_chain_id != chain_id {
+				return Err(SignedTransactionError::ChainIdMismatch);
+			}
+		}
+
+		let mut stream = RlpStream::new_list(if tx_chain_id.is_some() { 9 } else { 6 });
+		for i in 0..6 {
+			stream.append_raw(rlp.at(i)?.as_raw(), 1);
+		}
+		if let Some(tx_chain_id) = tx_chain_id {
+			stream.append(&tx_chain_id);
+			stream.append(&0u8);
+			stream.append(&0u8);
+		}
+		let sender = recover(keccak(&stream.out()), y_parity, r, s)?;
+
+		let gas_price = rlp.val_at(1)?;
+		Ok(Self {
+			transaction_type: TransactionType::Legacy,
+			hash: keccak(raw),
+			sender,
+			chain_id: tx_chain_id,
+			nonce: rlp.val_at(0)?,
+			max_priority_fee_per_gas: gas_price,
+			max_fee_per_gas: gas_price,
+			gas_limit: rlp.val_at(2)?,
+			to: decode_to(&rlp.at(3)?)?,
+			value: rlp.val_at(4)?,
+			data: rlp.val_at(5)?,
+			access_list: Vec::new(),
+			max_fee_per_blob_gas: U256::zero(),
+			blob_versioned_hashes: Vec::new(),
+		})
+	}
+
+	/// Gas price paid by the transaction in a block with the base fee. For
+	/// dynamic fee and blob transactions, the priority fee is capped so that
+	/// the price is at most the maximum fee.
+	pub fn effective_gas_price(&self, base_fee: U256) -> U256 {
+		match self.transaction_type {
+			TransactionType::Legacy | TransactionType::AccessList => self.max_fee_per_gas,
+			TransactionType::DynamicFee | TransactionType::Blob => min(
+				self.max_fee_per_gas,
+				base_fee.saturating_add(self.max_priority_fee_per_gas),
+			),
+		}
+	}
+
+	/// Fee caps of the transaction, for [crate::standard::validate_transaction_fees].
+	pub fn fee_caps(&self) -> FeeCaps {
+		FeeCaps {
+			max_fee_per_gas: self.max_fee_per_gas,
+			max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+			max_fee_per_blob_gas: self.max_fee_per_blob_gas,
+			blob_count: self.blob_versioned_hashes.len() as u64,
+		}
+	}
+
+	/// Transaction arguments to execute the transaction in a block with the
+	/// base fee.
+	pub fn into_transact_args(self, base_fee: U256) -> TransactArgs {
+		let gas_price = self.effective_gas_price(base_fee);
+		match self.to {
+			Some(address) => TransactArgs::Call {
+				caller: self.sender,
+				address,
+				nonce: Some(self.nonce),
+				chain_id: self.chain_id,
+				value: self.value,
+				data: self.data,
+				gas_limit: self.gas_limit,
+				gas_price,
+				access_list: self.access_list,
+				system: false,
+				mint: None,
+				fee_payer: None,
+			},
+			None => TransactArgs::Create {
+				caller: self.sender,
+				nonce: Some(self.nonce),
+				chain_id: self.chain_id,
+				value: self.value,
+				init_code: self.data,
+				salt: None,
+				gas_limit: self.gas_limit,
+				gas_price,
+				access_list: self.access_list,
+				system: false,
+				mint: None,
+				fee_payer: None,
+			},
+		}
+	}
+}
+
+fn keccak(data: &[u8]) -> H256 {
+	H256::from_slice(Keccak256::digest(data).as_slice())
+}
+
+fn decode_to(rlp: &Rlp) -> Result<Option<H160>, DecoderError> {
+	if rlp.is_empty() {
+		Ok(None)
+	} else {
+		Ok(Some(rlp.as_val()?))
+	}
+}
+
+fn decode_access_list(rlp: &Rlp) -> Result<Vec<(H160, Vec<H256>)>, DecoderError> {
+	rlp.iter()
+		.map(|item| {
+			if item.item_count()? != 2 {
+				return Err(DecoderError::RlpIncorrectListLen);
+			}
+			Ok((item.val_at(0)?, item.list_at(1)?))
+		})
+		.collect()
+}
+
+/// Recover the signer of a message hash, to implement
+/// [evm_interpreter::runtime::RuntimeBaseBackend::ecrecover] for the `AUTH`
+/// opcode (EIP-3074). Return `None` if the signature is invalid, including
+/// signatures with a high `s`.
+pub fn ecrecover(hash: H256, y_parity: u8, r: H256, s: H256) -> Option<H160> {
+	recover(
+		hash,
+		y_parity,
+		U256::from_big_endian(&r[..]),
+		U256::from_big_endian(&s[..]),
+	)
+	.ok()
+}
+
+/// Recover the signer of the hash. Signatures with a high `s` are rejected
+/// (see EIP-2).
+fn recover(hash: H256, y_parity: u8, r: U256, s: U256) -> Result<H160, SignedTransactionError> {
+	let mut bytes = [0u8; 64];
+	r.to_big_endian(&mut bytes[..32]);
+	s.to_big_endian(&mut bytes[32..]);
+
+	let signature =
+		Signature::from_slice(&bytes).map_err(|_| SignedTransactionError::InvalidSignature)?;
+	if signature.normalize_s().is_some() {
+		return Err(SignedTransactionError::InvalidSignature);
+	}
+	let recovery_id =
+		RecoveryId::from_byte(y_parity).ok_or(SignedTransactionError::InvalidSignature)?;
+	let key = VerifyingKey::recover_from_prehash(hash.as_bytes(), &signature, recovery_id)
+		.map_err(|_| SignedTransactionError::InvalidSignature)?;
+
+	let public = key.to_encoded_point(false);
+	Ok(H160::from_slice(&keccak(&public.as_bytes()[1..])[12..]))
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{vec, vec::Vec};
+
+	use evm_interpreter::{
+		error::{ExitError, ExitException},
+		runtime::RuntimeBaseBackend,
+	};
+	use k256::ecdsa::SigningKey;
+	use primitive_types::{H160, H256, U256};
+
+	use super::{ecrecover, keccak};
+	use crate::standard::{
+		testing::{backend, call_args, transact, CONTRACT as INVOKER},
+		Config,
+	};
+
+	const TARGET: H160 = H160([0x30; 20]);
+	const SECP256K1N: [u8; 32] = [
+		0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+		0xfe, 0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36,
+		0x41, 0x41,
+	];
+
+	fn authority(key: &SigningKey) -> H160 {
+		let public = key.verifying_key().to_encoded_point(false);
+		H160::from_slice(&keccak(&public.as_bytes()[1..])[12..])
+	}
+
+	/// Sign the `AUTH` message, returning `y_parity || r || s || commit`.
+	fn sign_auth(key: &SigningKey, chain_id: u64, nonce: u64, commit: H256) -> Vec<u8> {
+		let mut message = vec![0x04];
+		message.extend_from_slice(H256::from_low_u64_be(chain_id).as_bytes());
+		message.extend_from_slice(H256::from_low_u64_be(nonce).as_bytes());
+		message.extend_from_slice(H256::from(INVOKER).as_bytes());
+		message.extend_from_slice(commit.as_bytes());
+
+		let (signature, recovery_id) = key
+			.sign_prehash_recoverable(keccak(&message).as_bytes())
+			.unwrap();
+		let mut input = vec![recovery_id.to_byte()];
+		input.extend_from_slice(&signature.to_bytes());
+		input.extend_from_slice(commit.as_bytes());
+		input
+	}
+
+	#[test]
+	fn ecrecover_rejects_high_s() {
+		let key = SigningKey::from_slice(&[0x01; 32]).unwrap();
+		let hash = keccak(b"message");
+		let (signature, recovery_id) = key.sign_prehash_recoverable(hash.as_bytes()).unwrap();
+		let r = H256::from_slice(&signature.r().to_bytes());
+		let s = H256::from_slice(&signature.s().to_bytes());
+
+		assert_eq!(
+			ecrecover(hash, recovery_id.to_byte(), r, s),
+			Some(authority(&key))
+		);
+
+		let high_s = U256::from_big_endian(&SECP256K1N) - U256::from_big_endian(&s[..]);
+		let mut high_s_bytes = H256::zero();
+		high_s.to_big_endian(&mut high_s_bytes[..]);
+		assert_eq!(
+			ecrecover(hash, recovery_id.to_byte() ^ 1, r, high_s_bytes),
+			None
+		);
+		assert_eq!(ecrecover(hash, 2, r, s), None);
+	}
+
+	/// Call the invoker with the `AUTH` input, returning the `AUTH` result
+	/// and the caller of the `AUTHCALL` as seen by the target.
+	fn auth_and_call(input: Vec<u8>, authority: H160) -> Result<(H256, H256), ExitError> {
+		let mut config = Config::cancun();
+		config.eip_3074_enabled = true;
+		config.chain_id = Some(U256::one());
+
+		let mut base = backend();
+		// CALLDATACOPY(0, 0, 97), SSTORE(0, AUTH(authority, 0, 97)),
+		// AUTHCALL(0x010000, TARGET, 0, 0, 0, 0, 0).
+		let mut code = vec![
+			0x60, 0x61, 0x60, 0x00, 0x60, 0x00, 0x37, 0x60, 0x61, 0x60, 0x00, 0x73,
+		];
+		code.extend_from_slice(authority.as_bytes());
+		code.extend_from_slice(&[
+			0xf6, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00,
+			0x73,
+		]);
+		code.extend_from_slice(TARGET.as_bytes());
+		code.extend_from_slice(&[0x62, 0x01, 0x00, 0x00, 0xf7, 0x00]);
+		base.set_code(INVOKER, code);
+		// SSTORE(0, CALLER)
+		base.set_code(TARGET, vec![0x33, 0x60, 0x00, 0x55]);
+
+		let args = call_args(INVOKER, input, 500_000, 0);
+		let (value, backend) = transact(&base, &config, args);
+		value.exit.map(|_| {
+			(
+				backend.storage(INVOKER, H256::zero()),
+				backend.storage(TARGET, H256::zero()),
+			)
+		})
+	}
+
+	#[test]
+	fn auth_authorizes_the_signer_for_authcall() {
+		let key = SigningKey::from_slice(&[0x01; 32]).unwrap();
+		let authority = authority(&key);
+		let commit = H256::repeat_byte(0xcc);
+
+		let (authorized, caller) = auth_and_call(sign_auth(&key, 1, 0, commit), authority).unwrap();
+		assert_eq!(authorized, H256::from_low_u64_be(1));
+		assert_eq!(caller, H256::from(authority));
+	}
+
+	#[test]
+	fn auth_rejects_signatures_of_other_messages() {
+		let key = SigningKey::from_slice(&[0x01; 32]).unwrap();
+		let authority = authority(&key);
+		let commit = H256::repeat_byte(0xcc);
+
+		for input in [
+			// Another chain.
+			sign_auth(&key, 2, 0, commit),
+			// Another nonce.
+			sign_auth(&key, 1, 1, commit),
+			// Another signer than the authority.
+			sign_auth(&SigningKey::from_slice(&[0x02; 32]).unwrap(), 1, 0, commit),
+		] {
+			assert_eq!(
+				auth_and_call(input, authority),
+				Err(ExitException::Unauthorized.into())
+			);
+		}
+	}
+}
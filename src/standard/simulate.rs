@@ -0,0 +1,378 @@
+//! # Batch simulation
+//!
+//! Run ordered bundles of calls across a sequence of simulated blocks, in the
+//! style of `eth_simulateV1`. State changes accumulate across calls and
+//! blocks in an [OverlayedBackend], and are never written to the base backend.
+
+use alloc::vec::Vec;
+use core::{convert::Infallible, fmt};
+
+use evm_interpreter::{
+	error::{CallCreateTrap, TrapConsume},
+	runtime::{BlockHashProvider, RuntimeBaseBackend, RuntimeEnvironment, RuntimeState},
+	RunInterpreter,
+};
+use primitive_types::{H160, H256, U256};
+
+use crate::{
+	backend::{OverlayedBackend, OverlayedChangeSet},
+	standard::{
+		block::BlockError, validate_transaction, FeePolicy, Invoker, InvokerState, Resolver,
+		TransactArgs, TransactValue,
+	},
+};
+
+/// Block environment overrides of a simulated block. Fields that are `None`
+/// are taken from the base backend.
+#[derive(Clone, Debug, Default)]
+pub struct BlockOverrides {
+	/// Block number.
+	pub number: Option<U256>,
+	/// Block timestamp.
+	pub timestamp: Option<U256>,
+	/// Block base fee per gas.
+	pub base_fee_per_gas: Option<U256>,
+	/// Block coinbase.
+	pub coinbase: Option<H160>,
+}
+
+/// A simulated block.
+#[derive(Clone, Debug)]
+pub struct SimulatedBlock {
+	/// Block environment overrides.
+	pub overrides: BlockOverrides,
+	/// Calls of the block, in order.
+	pub calls: Vec<TransactArgs>,
+}
+
+/// Outcome of a simulated block.
+#[derive(Clone, Debug)]
+pub struct SimulatedBlockOutcome {
+	/// Call results, in order.
+	pub values: Vec<TransactValue>,
+	/// Total gas used by the block.
+	pub gas_used: U256,
+}
+
+/// Outcome of a batch simulation.
+#[derive(Clone, Debug)]
+pub struct SimulateOutcome {
+	/// Block outcomes, in order.
+	pub blocks: Vec<SimulatedBlockOutcome>,
+	/// State changes accumulated over all blocks.
+	pub changes: OverlayedChangeSet,
+}
+
+/// Error of a batch simulation.
+#[derive(Clone, Debug)]
+pub struct SimulateError {
+	/// Index of the simulated block that failed.
+	pub block: usize,
+	/// The error, with the call index inside the block.
+	pub error: BlockError,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SimulateError {}
+
+impl fmt::Display for SimulateError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "simulated block {}: {}", self.block, self.error)
+	}
+}
+
+/// Backend with the block environment overridden by [BlockOverrides].
+pub struct OverriddenBackend<B> {
+	backend: B,
+	overrides: BlockOverrides,
+}
+
+impl<B> OverriddenBackend<B> {
+	/// Create a new overridden backend.
+	pub fn new(backend: B, overrides: BlockOverrides) -> Self {
+		Self { backend, overrides }
+	}
+
+	/// Replace the current overrides.
+	pub fn set_overrides(&mut self, overrides: BlockOverrides) {
+		self.overrides = overrides;
+	}
+}
+
+impl<B: BlockHashProvider> BlockHashProvider for OverriddenBackend<B> {
+	fn block_hash(&self, number: U256) -> H256 {
+		self.backend.block_hash(number)
+	}
+}
+
+impl<B: RuntimeEnvironment> RuntimeEnvironment for OverriddenBackend<B> {
+	fn block_number(&self) -> U256 {
+		self.overrides
+			.number
+			.unwrap_or_else(|| self.backend.block_number())
+	}
+
+	fn block_coinbase(&self) -> H160 {
+		self.overrides
+			.coinbase
+			.unwrap_or_else(|| self.backend.block_coinbase())
+	}
+
+	fn block_timestamp(&self) -> U256 {
+		self.overrides
+			.timestamp
+			.unwrap_or_else(|| self.backend.block_timestamp())
+	}
+
+	fn block_difficulty(&self) -> U256 {
+		self.backend.block_difficulty()
+	}
+
+	fn block_randomness(&self) -> Option<H256> {
+		self.backend.block_randomness()
+	}
+
+	fn block_parent_beacon_root(&self) -> Option<H256> {
+		self.backend.block_parent_beacon_root()
+	}
+
+	fn block_gas_limit(&self) -> U256 {
+		self.backend.block_gas_limit()
+	}
+
+	fn block_base_fee_per_gas(&self) -> U256 {
+		self.overrides
+			.base_fee_per_gas
+			.unwrap_or_else(|| self.backend.block_base_fee_per_gas())
+	}
+
+	fn blob_base_fee(&self) -> U256 {
+		self.backend.blob_base_fee()
+	}
+
+	fn chain_id(&self) -> U256 {
+		self.backend.chain_id()
+	}
+}
+
+impl<B: RuntimeBaseBackend> RuntimeBaseBackend for OverriddenBackend<B> {
+	fn balance(&self, address: H160) -> U256 {
+		self.backend.balance(address)
+	}
+
+	fn code_size(&self, address: H160) -> U256 {
+		self.backend.code_size(address)
+	}
+
+	fn code_hash(&self, address: H160) -> H256 {
+		self.backend.code_hash(address)
+	}
+
+	fn code(&self, address: H160) -> Vec<u8> {
+		self.backend.code(address)
+	}
+
+	fn storage(&self, address: H160, index: H256) -> H256 {
+		self.backend.storage(address, index)
+	}
+
+	fn transient_storage(&self, address: H160, index: H256) -> H256 {
+		self.backend.transient_storage(address, index)
+	}
+
+	fn exists(&self, address: H160) -> bool {
+		self.backend.exists(address)
+	}
+
+	fn nonce(&self, address: H160) -> U256 {
+		self.backend.nonce(address)
+	}
+
+	fn keccak256(&self, data: &[u8]) -> H256 {
+		self.backend.keccak256(data)
+	}
+
+	fn ecrecover(&self, hash: H256, y_parity: u8, r: H256, s: H256) -> Option<H160> {
+		self.backend.ecrecover(hash, y_parity, r, s)
+	}
+
+	fn prefetch(&self, addresses: &[H160], keys: &[(H160, H256)]) {
+		self.backend.prefetch(addresses, keys)
+	}
+}
+
+/// Simulate a sequence of blocks against the base backend.
+///
+/// Calls are only validated if `validation` is set. Otherwise, nonce, fee and
+/// balance checks are skipped, but the block gas limit is still enforced. The
+/// gas fee is still withdrawn, so unvalidated calls from unfunded senders
+/// should either use a zero gas price or be marked as system transactions.
+/// The first invalid call or fatal error aborts the whole simulation.
+pub fn simulate_blocks<'config, 'resolver, B, Tr, R, F>(
+	invoker: &Invoker<'config, 'resolver, R, F>,
+	blocks: Vec<SimulatedBlock>,
+	validation: bool,
+	heap_depth: Option<usize>,
+	backend: B,
+) -> Result<SimulateOutcome, SimulateError>
+where
+	B: RuntimeEnvironment + RuntimeBaseBackend,
+	R::State: InvokerState<'config> + AsRef<RuntimeState> + AsMut<RuntimeState>,
+	R::Interpreter: RunInterpreter<OverlayedBackend<OverriddenBackend<B>>, Tr>,
+	R: Resolver<OverlayedBackend<OverriddenBackend<B>>>,
+	F: FeePolicy<OverlayedBackend<OverriddenBackend<B>>>,
+	Tr: TrapConsume<CallCreateTrap, Rest = Infallible>,
+{
+	let config = invoker.config();
+	let mut handler = OverlayedBackend::new(
+		OverriddenBackend::new(backend, BlockOverrides::default()),
+		Default::default(),
+	);
+	let mut outcomes = Vec::with_capacity(blocks.len());
+
+	for (block, simulated) in blocks.into_iter().enumerate() {
+		handler.backend_mut().set_overrides(simulated.overrides);
+
+		let mut values = Vec::with_capacity(simulated.calls.len());
+		let mut gas_used = U256::zero();
+
+		for (index, args) in simulated.calls.into_iter().enumerate() {
+			if args.gas_limit() > handler.block_gas_limit().saturating_sub(gas_used) {
+				return Err(SimulateError {
+					block,
+					error: BlockError::BlockGasLimitReached(index),
+				});
+			}
+			if validation {
+				validate_transaction(&args, &handler, config).map_err(|err| SimulateError {
+					block,
+					error: BlockError::InvalidTransaction(index, err),
+				})?;
+			}
+
+			let value = crate::transact_hybrid::<_, Tr, _>(args, heap_depth, &mut handler, invoker)
+				.map_err(|err| SimulateError {
+					block,
+					error: BlockError::Transaction(index, err),
+				})?;
+
+			gas_used = gas_used.saturating_add(value.used_gas);
+			values.push(value);
+		}
+
+		outcomes.push(SimulatedBlockOutcome { values, gas_used });
+	}
+
+	let (_, changes) = handler.deconstruct();
+	Ok(SimulateOutcome {
+		blocks: outcomes,
+		changes,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{vec, vec::Vec};
+
+	use primitive_types::{H160, H256, U256};
+
+	use super::{simulate_blocks, BlockOverrides, OverriddenBackend, SimulatedBlock};
+	use crate::{
+		backend::{OverlayedBackend, TestBackend},
+		standard::{
+			block::BlockError,
+			testing::{backend, call_args, etable, CALLER, CONTRACT},
+			Config, EtableResolver, Invoker, TransactArgs,
+		},
+	};
+
+	const COINBASE: H160 = H160([0x30; 20]);
+
+	type TestHandler<'a> = OverlayedBackend<OverriddenBackend<&'a TestBackend>>;
+
+	fn call(gas_limit: u64) -> TransactArgs {
+		call_args(CONTRACT, Vec::new(), gas_limit, 0)
+	}
+
+	#[test]
+	fn blocks_are_simulated_with_their_overrides() {
+		let mut base = backend();
+		base.environment.block_number = U256::one();
+		base.environment.block_timestamp = U256::from(100);
+		base.environment.block_coinbase = COINBASE;
+		// Store the number at the slot of the timestamp, and return the
+		// coinbase.
+		base.set_code(
+			CONTRACT,
+			vec![
+				0x43, 0x42, 0x55, 0x41, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+			],
+		);
+
+		let config = Config::cancun();
+		let etable = etable::<TestHandler<'_>>();
+		let resolver = EtableResolver::new(&config, &(), &etable);
+		let invoker = Invoker::new(&config, &resolver);
+		let other = H160([0x40; 20]);
+
+		let blocks = vec![
+			SimulatedBlock {
+				overrides: BlockOverrides {
+					number: Some(U256::from(5)),
+					timestamp: Some(U256::from(50)),
+					..Default::default()
+				},
+				calls: vec![call(100_000)],
+			},
+			SimulatedBlock {
+				overrides: BlockOverrides {
+					timestamp: Some(U256::from(60)),
+					coinbase: Some(other),
+					..Default::default()
+				},
+				calls: vec![call(100_000), call(100_000)],
+			},
+		];
+		let outcome = simulate_blocks(&invoker, blocks, false, None, &base).unwrap();
+
+		assert_eq!(outcome.blocks.len(), 2);
+		assert_eq!(outcome.blocks[0].values[0].retval, H256::from(COINBASE).0);
+		assert_eq!(outcome.blocks[1].values[1].retval, H256::from(other).0);
+		for block in &outcome.blocks {
+			let used_gas = block
+				.values
+				.iter()
+				.fold(U256::zero(), |gas, value| gas + value.used_gas);
+			assert_eq!(block.gas_used, used_gas);
+		}
+
+		let slot = |timestamp| (CONTRACT, H256::from_low_u64_be(timestamp));
+		let slot_key = |timestamp| (CONTRACT, Some(H256::from_low_u64_be(timestamp)));
+		assert_eq!(
+			outcome.changes.storages.get(&slot(50)),
+			Some(&H256::from_low_u64_be(5))
+		);
+		assert_eq!(
+			outcome.changes.storages.get(&slot(60)),
+			Some(&H256::from_low_u64_be(1))
+		);
+		assert_eq!(outcome.changes.nonces.get(&CALLER), Some(&U256::from(3)));
+		// The warm set is the one of the last call.
+		assert!(outcome.changes.accessed.contains(&slot_key(60)));
+		assert!(base.state[&CONTRACT].storage.is_empty());
+
+		let blocks = vec![
+			SimulatedBlock {
+				overrides: BlockOverrides::default(),
+				calls: vec![call(100_000)],
+			},
+			SimulatedBlock {
+				overrides: BlockOverrides::default(),
+				calls: vec![call(100_000), call(2_000_000)],
+			},
+		];
+		let err = simulate_blocks(&invoker, blocks, false, None, &base).unwrap_err();
+		assert_eq!(err.block, 1);
+		assert!(matches!(err.error, BlockError::BlockGasLimitReached(1)));
+	}
+}
@@ -0,0 +1,99 @@
+//! Fixture shared by the tests of the crate: a caller and a contract, a test
+//! backend, and transactions run with the standard etable on an overlay of it.
+
+use alloc::{collections::BTreeSet, vec::Vec};
+
+use evm_interpreter::{
+	error::ExitError,
+	runtime::{RuntimeBackend, RuntimeEnvironment},
+};
+use primitive_types::{H160, U256};
+
+use crate::{
+	backend::{OverlayedBackend, TestBackend, TestEnvironment},
+	standard::{
+		eval_gasometer, Config, Etable, EtableResolver, Invoker, PrecompileSet, State,
+		TransactArgs, TransactValue,
+	},
+};
+
+/// Sender of the transactions.
+pub const CALLER: H160 = H160([0x10; 20]);
+/// Contract called by the transactions.
+pub const CONTRACT: H160 = H160([0x20; 20]);
+
+/// Handler of the transactions, an overlay of the test backend.
+pub type TestHandler<'a> = OverlayedBackend<&'a TestBackend>;
+
+/// Test backend with a block gas limit of 1_000_000, and no accounts.
+pub fn backend() -> TestBackend {
+	TestBackend::new(TestEnvironment {
+		block_gas_limit: U256::from(1_000_000),
+		..Default::default()
+	})
+}
+
+/// Standard etable set, with the gasometer before the runtime.
+pub fn etable<'config, H>() -> (Etable<'config, H>, Etable<'config, H>)
+where
+	H: RuntimeEnvironment + RuntimeBackend,
+{
+	(Etable::single(eval_gasometer), Etable::runtime())
+}
+
+/// Call from the caller, with no value.
+pub fn call_args(address: H160, data: Vec<u8>, gas_limit: u64, gas_price: u64) -> TransactArgs {
+	TransactArgs::Call {
+		caller: CALLER,
+		address,
+		nonce: None,
+		chain_id: None,
+		value: U256::zero(),
+		data,
+		gas_limit: U256::from(gas_limit),
+		gas_price: U256::from(gas_price),
+		access_list: Vec::new(),
+		system: false,
+		mint: None,
+		fee_payer: None,
+	}
+}
+
+/// Run the transaction with the precompiles on an overlay of the base
+/// backend, returning the overlay with its changes.
+pub fn try_transact_with<'config, 'a, Pre>(
+	base: &'a TestBackend,
+	config: &'config Config,
+	precompiles: &Pre,
+	args: TransactArgs,
+) -> (Result<TransactValue, ExitError>, TestHandler<'a>)
+where
+	Pre: PrecompileSet<State<'config>, TestHandler<'a>>,
+{
+	let etable = etable();
+	let resolver = EtableResolver::new(config, precompiles, &etable);
+	let invoker = Invoker::new(config, &resolver);
+
+	let mut backend = OverlayedBackend::new(base, BTreeSet::new());
+	let result = crate::transact_hybrid(args, None, &mut backend, &invoker);
+	(result, backend)
+}
+
+/// Run the transaction without precompiles.
+pub fn try_transact<'a>(
+	base: &'a TestBackend,
+	config: &Config,
+	args: TransactArgs,
+) -> (Result<TransactValue, ExitError>, TestHandler<'a>) {
+	try_transact_with(base, config, &(), args)
+}
+
+/// Run the transaction without precompiles, and unwrap its result.
+pub fn transact<'a>(
+	base: &'a TestBackend,
+	config: &Config,
+	args: TransactArgs,
+) -> (TransactValue, TestHandler<'a>) {
+	let (result, backend) = try_transact(base, config, args);
+	(result.unwrap(), backend)
+}
@@ -0,0 +1,690 @@
+use core::{cmp::max, fmt};
+
+use evm_interpreter::runtime::{RuntimeBaseBackend, RuntimeEnvironment};
+use primitive_types::{H160, U256};
+
+use crate::standard::{blob, gasometer::TransactionCost, Config, TransactArgs};
+
+/// Reason for a transaction to be invalid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValidationError {
+	/// Transaction nonce is lower than the sender's nonce.
+	NonceTooLow {
+		/// Nonce of the sender.
+		expected: U256,
+		/// Nonce of the transaction.
+		actual: U256,
+	},
+	/// Transaction nonce is higher than the sender's nonce.
+	NonceTooHigh {
+		/// Nonce of the sender.
+		expected: U256,
+		/// Nonce of the transaction.
+		actual: U256,
+	},
+	/// Sender nonce is at the maximum of 2^64-1, and cannot be increased (see
+	/// EIP-2681).
+	NonceMax,
+	/// Sender balance does not cover the value and the maximum fee. With a
+	/// separate fee payer, its balance does not cover the maximum fee, or the
+	/// sender balance does not cover the value.
+	InsufficientFunds {
+		/// Amount the account has to cover, `U256::MAX` if it overflows.
+		required: U256,
+		/// Balance of the account.
+		available: U256,
+	},
+	/// Gas limit does not cover the intrinsic gas, or the floor gas.
+	IntrinsicGasTooLow {
+		/// Intrinsic gas, or floor gas if it is higher.
+		required: u64,
+		/// Gas limit of the transaction.
+		gas_limit: U256,
+	},
+	/// Gas limit is higher than the block gas limit.
+	GasLimitExceedsBlockGasLimit {
+		/// Gas limit of the transaction.
+		gas_limit: U256,
+		/// Gas limit of the block.
+		block_gas_limit: U256,
+	},
+	/// Gas price is lower than the block base fee.
+	GasPriceLessThanBaseFee {
+		/// Gas price of the transaction.
+		gas_price: U256,
+		/// Base fee of the block.
+		base_fee: U256,
+	},
+	/// Init code is larger than the limit (see EIP-3860).
+	InitCodeTooLarge {
+		/// Init code size.
+		size: u64,
+		/// Init code size limit.
+		limit: u64,
+	},
+	/// Sender has deployed code, and is not an externally owned account (see
+	/// EIP-3607).
+	SenderHasCode,
+	/// Maximum fee per gas is lower than the block base fee.
+	MaxFeePerGasLessThanBaseFee {
+		/// Maximum fee per gas of the transaction.
+		max_fee_per_gas: U256,
+		/// Base fee of the block.
+		base_fee: U256,
+	},
+	/// Maximum priority fee per gas is higher than the maximum fee per gas.
+	PriorityFeeGreaterThanMaxFee {
+		/// Maximum priority fee per gas of the transaction.
+		max_priority_fee_per_gas: U256,
+		/// Maximum fee per gas of the transaction.
+		max_fee_per_gas: U256,
+	},
+	/// Maximum fee per blob gas is lower than the blob base fee.
+	MaxFeePerBlobGasLessThanBlobBaseFee {
+		/// Maximum fee per blob gas of the transaction.
+		max_fee_per_blob_gas: U256,
+		/// Blob base fee of the block.
+		blob_base_fee: U256,
+	},
+	/// Sender balance does not cover the value, the maximum fee and the
+	/// maximum blob fee. With a separate fee payer, the fees are covered by
+	/// its balance instead.
+	InsufficientFundsForMaxFee {
+		/// Amount the account has to cover, `U256::MAX` if it overflows.
+		required: U256,
+		/// Balance of the account.
+		available: U256,
+	},
+	/// Transaction is signed for another chain.
+	ChainIdMismatch {
+		/// Chain ID of the execution.
+		expected: U256,
+		/// Chain ID of the transaction.
+		actual: U256,
+	},
+	/// Blob gas of the blobs of the transaction overflows.
+	BlobGasOverflow {
+		/// Number of blobs of the transaction.
+		blob_count: u64,
+	},
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+impl ValidationError {
+	/// Stable numeric code of the error. Codes are never reused.
+	#[must_use]
+	pub const fn code(&self) -> u16 {
+		match self {
+			Self::NonceTooLow { .. } => 0,
+			Self::NonceTooHigh { .. } => 1,
+			Self::NonceMax => 2,
+			Self::InsufficientFunds { .. } => 3,
+			Self::IntrinsicGasTooLow { .. } => 4,
+			Self::GasLimitExceedsBlockGasLimit { .. } => 5,
+			Self::GasPriceLessThanBaseFee { .. } => 6,
+			Self::InitCodeTooLarge { .. } => 7,
+			Self::SenderHasCode => 8,
+			Self::MaxFeePerGasLessThanBaseFee { .. } => 9,
+			Self::PriorityFeeGreaterThanMaxFee { .. } => 10,
+			Self::MaxFeePerBlobGasLessThanBlobBaseFee { .. } => 11,
+			Self::InsufficientFundsForMaxFee { .. } => 12,
+			Self::ChainIdMismatch { .. } => 13,
+			Self::BlobGasOverflow { .. } => 14,
+		}
+	}
+
+	/// Stable name of the error.
+	#[must_use]
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::NonceTooLow { .. } => "nonce_too_low",
+			Self::NonceTooHigh { .. } => "nonce_too_high",
+			Self::NonceMax => "nonce_max",
+			Self::InsufficientFunds { .. } => "insufficient_funds",
+			Self::IntrinsicGasTooLow { .. } => "intrinsic_gas_too_low",
+			Self::GasLimitExceedsBlockGasLimit { .. } => "gas_limit_exceeds_block_gas_limit",
+			Self::GasPriceLessThanBaseFee { .. } => "gas_price_less_than_base_fee",
+			Self::InitCodeTooLarge { .. } => "init_code_too_large",
+			Self::SenderHasCode => "sender_has_code",
+			Self::MaxFeePerGasLessThanBaseFee { .. } => "max_fee_per_gas_less_than_base_fee",
+			Self::PriorityFeeGreaterThanMaxFee { .. } => "priority_fee_greater_than_max_fee",
+			Self::MaxFeePerBlobGasLessThanBlobBaseFee { .. } => {
+				"max_fee_per_blob_gas_less_than_blob_base_fee"
+			}
+			Self::InsufficientFundsForMaxFee { .. } => "insufficient_funds_for_max_fee",
+			Self::ChainIdMismatch { .. } => "chain_id_mismatch",
+			Self::BlobGasOverflow { .. } => "blob_gas_overflow",
+		}
+	}
+}
+
+impl fmt::Display for ValidationError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NonceTooLow { expected, actual } => {
+				write!(f, "nonce too low: expected {expected}, got {actual}")
+			}
+			Self::NonceTooHigh { expected, actual } => {
+				write!(f, "nonce too high: expected {expected}, got {actual}")
+			}
+			Self::NonceMax => f.write_str("sender nonce at maximum"),
+			Self::InsufficientFunds {
+				required,
+				available,
+			} => write!(
+				f,
+				"insufficient funds: required {required}, available {available}"
+			),
+			Self::IntrinsicGasTooLow {
+				required,
+				gas_limit,
+			} => write!(
+				f,
+				"intrinsic gas too low: required {required}, gas limit {gas_limit}"
+			),
+			Self::GasLimitExceedsBlockGasLimit {
+				gas_limit,
+				block_gas_limit,
+			} => write!(
+				f,
+				"gas limit {gas_limit} exceeds block gas limit {block_gas_limit}"
+			),
+			Self::GasPriceLessThanBaseFee {
+				gas_price,
+				base_fee,
+			} => write!(f, "gas price {gas_price} less than base fee {base_fee}"),
+			Self::InitCodeTooLarge { size, limit } => {
+				write!(f, "init code size {size} exceeds limit {limit}")
+			}
+			Self::SenderHasCode => f.write_str("sender has deployed code"),
+			Self::MaxFeePerGasLessThanBaseFee {
+				max_fee_per_gas,
+				base_fee,
+			} => write!(
+				f,
+				"max fee per gas {max_fee_per_gas} less than base fee {base_fee}"
+			),
+			Self::PriorityFeeGreaterThanMaxFee {
+				max_priority_fee_per_gas,
+				max_fee_per_gas,
+			} => write!(
+				f,
+				"max priority fee per gas {max_priority_fee_per_gas} greater than max fee per gas {max_fee_per_gas}"
+			),
+			Self::MaxFeePerBlobGasLessThanBlobBaseFee {
+				max_fee_per_blob_gas,
+				blob_base_fee,
+			} => write!(
+				f,
+				"max fee per blob gas {max_fee_per_blob_gas} less than blob base fee {blob_base_fee}"
+			),
+			Self::InsufficientFundsForMaxFee {
+				required,
+				available,
+			} => write!(
+				f,
+				"insufficient funds for max fee: required {required}, available {available}"
+			),
+			Self::ChainIdMismatch { expected, actual } => {
+				write!(f, "chain id mismatch: expected {expected}, got {actual}")
+			}
+			Self::BlobGasOverflow { blob_count } => {
+				write!(f, "blob gas of {blob_count} blobs overflows")
+			}
+		}
+	}
+}
+
+/// Fee caps of a transaction, as signed by the sender. Unlike the gas price
+/// of [TransactArgs], which is the price actually paid, these are the upper
+/// bounds a transaction pool admits the transaction with.
+///
+/// For legacy and EIP-2930 transactions, both fee caps are the gas price.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+pub struct FeeCaps {
+	/// Maximum fee per gas.
+	pub max_fee_per_gas: U256,
+	/// Maximum priority fee per gas, paid to the coinbase.
+	pub max_priority_fee_per_gas: U256,
+	/// Maximum fee per blob gas. Only checked if the transaction has blobs.
+	pub max_fee_per_blob_gas: U256,
+	/// Number of blobs of the transaction.
+	pub blob_count: u64,
+}
+
+/// Whether the code is an EIP-7702 delegation designator, `0xef0100`
+/// followed by the address of the delegate.
+pub fn is_delegation_designator(code: &[u8]) -> bool {
+	code.len() == 23 && code.starts_with(&[0xef, 0x01, 0x00])
+}
+
+/// Whether the sender is refused for having deployed code (see EIP-3607).
+/// Accounts with a delegation designator are still allowed to send
+/// transactions.
+fn sender_has_code<H: RuntimeBaseBackend>(caller: H160, handler: &H, config: &Config) -> bool {
+	config.eip_3607_enabled
+		&& handler.code_size(caller) != U256::zero()
+		&& !is_delegation_designator(&handler.code(caller))
+}
+
+/// Check that the fee payer balance covers the fee, and the sender balance
+/// covers the value. Both are the same account unless the transaction has a
+/// separate fee payer. A fee of `None` overflowed, and is never covered.
+fn check_funds<H: RuntimeBaseBackend>(
+	args: &TransactArgs,
+	fee: Option<U256>,
+	handler: &H,
+	error: fn(U256, U256) -> ValidationError,
+) -> Result<(), ValidationError> {
+	let caller = args.caller();
+	let fee_payer = args.fee_payer();
+
+	let check = |address: H160, required: Option<U256>| {
+		let available = handler.balance(address);
+		match required {
+			Some(required) if available >= required => Ok(()),
+			required => Err(error(required.unwrap_or(U256::MAX), available)),
+		}
+	};
+
+	if fee_payer == caller {
+		check(caller, fee.and_then(|fee| fee.checked_add(args.value())))
+	} else {
+		check(fee_payer, fee)?;
+		check(caller, Some(args.value()))
+	}
+}
+
+/// Intrinsic gas of a transaction, charged before any execution.
+pub fn intrinsic_gas(args: &TransactArgs, config: &Config) -> u64 {
+	match args {
+		TransactArgs::Call {
+			data, access_list, ..
+		} => TransactionCost::call(data, access_list).cost(config),
+		TransactArgs::Create {
+			init_code,
+			access_list,
+			..
+		} => TransactionCost::create(init_code, access_list).cost(config),
+	}
+}
+
+/// Floor gas of a transaction, the minimum charged for its data (see
+/// EIP-7623). Zero if the config does not enable it.
+pub fn floor_gas(args: &TransactArgs, config: &Config) -> u64 {
+	match args {
+		TransactArgs::Call {
+			data, access_list, ..
+		} => TransactionCost::call(data, access_list).floor_cost(config),
+		TransactArgs::Create {
+			init_code,
+			access_list,
+			..
+		} => TransactionCost::create(init_code, access_list).floor_cost(config),
+	}
+}
+
+/// Perform the consensus checks of a transaction, before it is passed to
+/// [crate::transact].
+///
+/// The nonce and the chain ID are only checked if they are set in the
+/// transaction arguments, the chain ID against [Config::effective_chain_id].
+/// For system transactions, the base fee is not checked and only the value
+/// needs to be covered by the caller balance. Deposit transactions are always
+/// valid.
+pub fn validate_transaction<H: RuntimeEnvironment + RuntimeBaseBackend>(
+	args: &TransactArgs,
+	handler: &H,
+	config: &Config,
+) -> Result<(), ValidationError> {
+	if args.is_deposit() {
+		return Ok(());
+	}
+
+	if let Some(actual) = args.chain_id() {
+		let expected = config.effective_chain_id(handler);
+		if actual != expected {
+			return Err(ValidationError::ChainIdMismatch { expected, actual });
+		}
+	}
+
+	let caller = args.caller();
+
+	if !args.is_system() && handler.nonce(caller) >= U256::from(u64::MAX) {
+		return Err(ValidationError::NonceMax);
+	}
+
+	if !args.is_system() && sender_has_code(caller, handler, config) {
+		return Err(ValidationError::SenderHasCode);
+	}
+
+	if let Some(nonce) = args.nonce() {
+		let expected = handler.nonce(caller);
+		if nonce < expected {
+			return Err(ValidationError::NonceTooLow {
+				expected,
+				actual: nonce,
+			});
+		}
+		if nonce > expected {
+			return Err(ValidationError::NonceTooHigh {
+				expected,
+				actual: nonce,
+			});
+		}
+	}
+
+	if let TransactArgs::Create { init_code, .. } = args {
+		if let Some(limit) = config.max_initcode_size {
+			if init_code.len() > limit {
+				return Err(ValidationError::InitCodeTooLarge {
+					size: init_code.len() as u64,
+					limit: limit as u64,
+				});
+			}
+		}
+	}
+
+	let block_gas_limit = handler.block_gas_limit();
+	if args.gas_limit() > block_gas_limit {
+		return Err(ValidationError::GasLimitExceedsBlockGasLimit {
+			gas_limit: args.gas_limit(),
+			block_gas_limit,
+		});
+	}
+
+	let required_gas = max(intrinsic_gas(args, config), floor_gas(args, config));
+	if U256::from(required_gas) > args.gas_limit() {
+		return Err(ValidationError::IntrinsicGasTooLow {
+			required: required_gas,
+			gas_limit: args.gas_limit(),
+		});
+	}
+
+	if !args.is_system() && config.eip_1559_enabled {
+		let base_fee = handler.block_base_fee_per_gas();
+		if args.gas_price() < base_fee {
+			return Err(ValidationError::GasPriceLessThanBaseFee {
+				gas_price: args.gas_price(),
+				base_fee,
+			});
+		}
+	}
+
+	let max_fee = if args.is_system() {
+		Some(U256::zero())
+	} else {
+		args.gas_limit().checked_mul(args.gas_price())
+	};
+	check_funds(args, max_fee, handler, |required, available| {
+		ValidationError::InsufficientFunds {
+			required,
+			available,
+		}
+	})
+}
+
+/// Perform the fee and balance checks of a transaction pool, against the fee
+/// caps of a transaction. These are stricter than the ones of
+/// [validate_transaction], which only checks the gas price that is paid, and
+/// are meant to be performed in addition to it before admitting a
+/// transaction.
+///
+/// System and deposit transactions are not checked.
+pub fn validate_transaction_fees<H: RuntimeEnvironment + RuntimeBaseBackend>(
+	args: &TransactArgs,
+	fees: &FeeCaps,
+	handler: &H,
+	config: &Config,
+) -> Result<(), ValidationError> {
+	if args.is_system() || args.is_deposit() {
+		return Ok(());
+	}
+
+	if config.eip_1559_enabled {
+		let base_fee = handler.block_base_fee_per_gas();
+		if fees.max_fee_per_gas < base_fee {
+			return Err(ValidationError::MaxFeePerGasLessThanBaseFee {
+				max_fee_per_gas: fees.max_fee_per_gas,
+				base_fee,
+			});
+		}
+
+		if fees.max_priority_fee_per_gas > fees.max_fee_per_gas {
+			return Err(ValidationError::PriorityFeeGreaterThanMaxFee {
+				max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+				max_fee_per_gas: fees.max_fee_per_gas,
+			});
+		}
+	}
+
+	let blob_gas =
+		blob::blob_gas(fees.blob_count).map_err(|_| ValidationError::BlobGasOverflow {
+			blob_count: fees.blob_count,
+		})?;
+	let blob_gas = U256::from(blob_gas);
+	if fees.blob_count != 0 {
+		let blob_base_fee = handler.blob_base_fee();
+		if fees.max_fee_per_blob_gas < blob_base_fee {
+			return Err(ValidationError::MaxFeePerBlobGasLessThanBlobBaseFee {
+				max_fee_per_blob_gas: fees.max_fee_per_blob_gas,
+				blob_base_fee,
+			});
+		}
+	}
+
+	let max_fee = args.gas_limit().checked_mul(fees.max_fee_per_gas);
+	let max_blob_fee = blob_gas.checked_mul(fees.max_fee_per_blob_gas);
+	let total_fee = max_fee
+		.zip(max_blob_fee)
+		.and_then(|(max_fee, max_blob_fee)| max_fee.checked_add(max_blob_fee));
+	check_funds(args, total_fee, handler, |required, available| {
+		ValidationError::InsufficientFundsForMaxFee {
+			required,
+			available,
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{vec, vec::Vec};
+
+	use primitive_types::U256;
+
+	use super::{is_delegation_designator, validate_transaction, ValidationError};
+	use crate::{
+		backend::TestBackend,
+		standard::{
+			testing::{self, call_args, CALLER, CONTRACT},
+			Config, ConfigBuilder, TransactArgs,
+		},
+	};
+
+	fn backend() -> TestBackend {
+		let mut backend = testing::backend();
+		backend.set_nonce(CALLER, U256::from(5));
+		backend.set_balance(CALLER, U256::from(1_000_000));
+		backend
+	}
+
+	fn call(nonce: u64, value: u64, data: Vec<u8>, gas_limit: u64, gas_price: u64) -> TransactArgs {
+		let mut args = call_args(CONTRACT, data, gas_limit, gas_price);
+		if let TransactArgs::Call {
+			nonce: args_nonce,
+			value: args_value,
+			..
+		} = &mut args
+		{
+			*args_nonce = Some(U256::from(nonce));
+			*args_value = U256::from(value);
+		}
+		args
+	}
+
+	#[test]
+	fn valid_transactions_pass() {
+		let config = Config::cancun();
+		let backend = backend();
+
+		let args = call(5, 789_840, vec![1], 21_016, 10);
+		assert_eq!(validate_transaction(&args, &backend, &config), Ok(()));
+
+		// The nonce is not checked if the transaction does not set one.
+		let mut args = call(0, 0, Vec::new(), 21_000, 1);
+		if let TransactArgs::Call { nonce, .. } = &mut args {
+			*nonce = None;
+		}
+		assert_eq!(validate_transaction(&args, &backend, &config), Ok(()));
+	}
+
+	#[test]
+	fn nonce_must_match_the_sender() {
+		let config = Config::cancun();
+		let backend = backend();
+
+		assert_eq!(
+			validate_transaction(&call(4, 0, Vec::new(), 21_000, 1), &backend, &config),
+			Err(ValidationError::NonceTooLow {
+				expected: U256::from(5),
+				actual: U256::from(4),
+			})
+		);
+		assert_eq!(
+			validate_transaction(&call(6, 0, Vec::new(), 21_000, 1), &backend, &config),
+			Err(ValidationError::NonceTooHigh {
+				expected: U256::from(5),
+				actual: U256::from(6),
+			})
+		);
+	}
+
+	#[test]
+	fn balance_must_cover_value_and_max_fee() {
+		let config = Config::cancun();
+		let backend = backend();
+
+		assert_eq!(
+			validate_transaction(&call(5, 789_841, vec![1], 21_016, 10), &backend, &config),
+			Err(ValidationError::InsufficientFunds {
+				required: U256::from(1_000_001),
+				available: U256::from(1_000_000),
+			})
+		);
+		assert_eq!(
+			validate_transaction(&call(5, 0, Vec::new(), 21_000, u64::MAX), &backend, &config),
+			Err(ValidationError::InsufficientFunds {
+				required: U256::from(21_000) * U256::from(u64::MAX),
+				available: U256::from(1_000_000),
+			})
+		);
+	}
+
+	#[test]
+	fn gas_limit_must_cover_intrinsic_gas_within_the_block_gas_limit() {
+		let config = Config::cancun();
+		let backend = backend();
+
+		assert_eq!(
+			validate_transaction(&call(5, 0, vec![1], 21_015, 1), &backend, &config),
+			Err(ValidationError::IntrinsicGasTooLow {
+				required: 21_016,
+				gas_limit: U256::from(21_015),
+			})
+		);
+		assert_eq!(
+			validate_transaction(&call(5, 0, Vec::new(), 1_000_001, 0), &backend, &config),
+			Err(ValidationError::GasLimitExceedsBlockGasLimit {
+				gas_limit: U256::from(1_000_001),
+				block_gas_limit: U256::from(1_000_000),
+			})
+		);
+	}
+
+	#[test]
+	fn senders_at_the_nonce_cap_are_rejected() {
+		let config = Config::cancun();
+		let mut backend = backend();
+		backend.set_nonce(CALLER, U256::from(u64::MAX));
+
+		assert_eq!(
+			validate_transaction(&call(u64::MAX, 0, Vec::new(), 21_000, 1), &backend, &config),
+			Err(ValidationError::NonceMax)
+		);
+
+		backend.set_nonce(CALLER, U256::from(u64::MAX - 1));
+		assert_eq!(
+			validate_transaction(
+				&call(u64::MAX - 1, 0, Vec::new(), 21_000, 1),
+				&backend,
+				&config
+			),
+			Ok(())
+		);
+	}
+
+	#[test]
+	fn senders_with_code_are_rejected_unless_delegated() {
+		let config = Config::cancun();
+		let mut backend = backend();
+		let args = call(5, 0, Vec::new(), 21_000, 1);
+
+		backend.set_code(CALLER, vec![0x00]);
+		assert_eq!(
+			validate_transaction(&args, &backend, &config),
+			Err(ValidationError::SenderHasCode)
+		);
+
+		let disabled = ConfigBuilder::new(Config::cancun())
+			.eip_3607_enabled(false)
+			.build();
+		assert_eq!(validate_transaction(&args, &backend, &disabled), Ok(()));
+
+		// An EIP-7702 delegation designator is not deployed code.
+		let mut designator = vec![0xef, 0x01, 0x00];
+		designator.extend_from_slice(CONTRACT.as_bytes());
+		assert!(is_delegation_designator(&designator));
+		backend.set_code(CALLER, designator.clone());
+		assert_eq!(validate_transaction(&args, &backend, &config), Ok(()));
+
+		// Code starting with the designator prefix, but of another length, is.
+		designator.push(0x00);
+		assert!(!is_delegation_designator(&designator));
+		backend.set_code(CALLER, designator);
+		assert_eq!(
+			validate_transaction(&args, &backend, &config),
+			Err(ValidationError::SenderHasCode)
+		);
+	}
+
+	#[test]
+	fn gas_limit_must_cover_the_calldata_floor() {
+		let backend = backend();
+		// 100 non-zero bytes, with an intrinsic gas of 22600 and a floor of
+		// 25000.
+		let args = call(5, 0, vec![0xff; 100], 24_999, 1);
+
+		assert_eq!(
+			validate_transaction(&args, &backend, &Config::cancun()),
+			Ok(())
+		);
+		assert_eq!(
+			validate_transaction(&args, &backend, &Config::prague()),
+			Err(ValidationError::IntrinsicGasTooLow {
+				required: 25_000,
+				gas_limit: U256::from(24_999),
+			})
+		);
+	}
+}
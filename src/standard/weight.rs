@@ -0,0 +1,432 @@
+//! # Weight metering
+//!
+//! Meter the execution in Substrate weights, the `ref_time` and `proof_size`
+//! of a runtime, on top of the gas. The gas used by each opcode is converted
+//! to weight with a [GasWeightMapping], and the execution fails with
+//! `OutOfGas` as soon as either the gas limit or the weight limit of the host
+//! is exceeded.
+//!
+//! A [WeightMeter] is shared by a [crate::HookedEtable], which meters the
+//! opcodes, and the host, which meters anything else it wants to count
+//! against the weight limit:
+//!
+//! ```ignore
+//! let meter = RefCell::new(WeightMeter::new(mapping, weight_limit));
+//! let etable = HookedEtable::new((gas_etable, exec_etable), &meter);
+//! let precompiles = WeightedPrecompileSet::new(precompiles, &meter);
+//! let resolver = EtableResolver::new(&config, &precompiles, &etable);
+//! let gas_limit = gas_limit.min(meter.borrow().gas_limit().into());
+//! ```
+//!
+//! The gas of opcodes and of the precompiles of a [WeightedPrecompileSet] is
+//! metered. The intrinsic gas of the transaction and the code deposit are
+//! not, so the gas limit of the transaction should be capped with
+//! [WeightMeter::gas_limit], which covers them.
+
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use evm_interpreter::{
+	error::{ExitException, ExitResult},
+	etable::Control,
+	machine::Machine,
+	opcode::Opcode,
+	runtime::{GasState, RuntimeBackend},
+};
+use primitive_types::H160;
+
+use crate::{standard::PrecompileSet, EtableHook};
+
+/// A Substrate weight: computation time and size of the storage proof.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+	feature = "scale",
+	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Weight {
+	/// Computation time, in picoseconds.
+	pub ref_time: u64,
+	/// Size of the storage proof, in bytes.
+	pub proof_size: u64,
+}
+
+impl Weight {
+	pub const fn from_parts(ref_time: u64, proof_size: u64) -> Self {
+		Self {
+			ref_time,
+			proof_size,
+		}
+	}
+
+	#[must_use]
+	pub const fn saturating_add(self, other: Self) -> Self {
+		Self {
+			ref_time: self.ref_time.saturating_add(other.ref_time),
+			proof_size: self.proof_size.saturating_add(other.proof_size),
+		}
+	}
+
+	#[must_use]
+	pub const fn saturating_sub(self, other: Self) -> Self {
+		Self {
+			ref_time: self.ref_time.saturating_sub(other.ref_time),
+			proof_size: self.proof_size.saturating_sub(other.proof_size),
+		}
+	}
+
+	/// Whether both components are at most those of the other weight.
+	pub const fn all_lte(self, other: Self) -> bool {
+		self.ref_time <= other.ref_time && self.proof_size <= other.proof_size
+	}
+}
+
+/// Conversion between gas and weight.
+pub trait GasWeightMapping {
+	/// Weight of the gas.
+	fn gas_to_weight(&self, gas: u64) -> Weight;
+	/// Gas that fits in the weight.
+	fn weight_to_gas(&self, weight: Weight) -> u64;
+}
+
+/// A mapping where each unit of gas has the same weight.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FixedGasWeightMapping {
+	/// Weight of one unit of gas. A component of zero is not limited by gas.
+	pub weight_per_gas: Weight,
+}
+
+impl FixedGasWeightMapping {
+	pub const fn new(weight_per_gas: Weight) -> Self {
+		Self { weight_per_gas }
+	}
+}
+
+impl GasWeightMapping for FixedGasWeightMapping {
+	fn gas_to_weight(&self, gas: u64) -> Weight {
+		Weight {
+			ref_time: gas.saturating_mul(self.weight_per_gas.ref_time),
+			proof_size: gas.saturating_mul(self.weight_per_gas.proof_size),
+		}
+	}
+
+	fn weight_to_gas(&self, weight: Weight) -> u64 {
+		let ref_time = weight
+			.ref_time
+			.checked_div(self.weight_per_gas.ref_time)
+			.unwrap_or(u64::MAX);
+		let proof_size = weight
+			.proof_size
+			.checked_div(self.weight_per_gas.proof_size)
+			.unwrap_or(u64::MAX);
+		ref_time.min(proof_size)
+	}
+}
+
+/// Weight used by a transaction, against a weight limit.
+#[derive(Clone, Debug)]
+pub struct WeightMeter<M> {
+	mapping: M,
+	limit: Weight,
+	consumed: Weight,
+}
+
+impl<M: GasWeightMapping> WeightMeter<M> {
+	pub fn new(mapping: M, limit: Weight) -> Self {
+		Self {
+			mapping,
+			limit,
+			consumed: Weight::default(),
+		}
+	}
+
+	/// The mapping between gas and weight.
+	pub fn mapping(&self) -> &M {
+		&self.mapping
+	}
+
+	/// Weight limit.
+	pub fn limit(&self) -> Weight {
+		self.limit
+	}
+
+	/// Weight used so far.
+	pub fn consumed(&self) -> Weight {
+		self.consumed
+	}
+
+	/// Weight left under the limit.
+	pub fn remaining(&self) -> Weight {
+		self.limit.saturating_sub(self.consumed)
+	}
+
+	/// Whether the weight limit has been exceeded.
+	pub fn is_exhausted(&self) -> bool {
+		!self.consumed.all_lte(self.limit)
+	}
+
+	/// Gas that fits in the weight left, to cap the gas limit of a
+	/// transaction.
+	pub fn gas_limit(&self) -> u64 {
+		self.mapping.weight_to_gas(self.remaining())
+	}
+
+	/// Record a weight. Fail with `OutOfGas` if the limit is exceeded, in
+	/// which case the weight is still recorded.
+	pub fn record_weight(&mut self, weight: Weight) -> Result<(), ExitException> {
+		self.consumed = self.consumed.saturating_add(weight);
+		if self.is_exhausted() {
+			Err(ExitException::OutOfGas)
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Record the weight of the gas.
+	pub fn record_gas(&mut self, gas: u64) -> Result<(), ExitException> {
+		self.record_weight(self.mapping.gas_to_weight(gas))
+	}
+
+	/// Record storage proof size, for example of the storage reads of the
+	/// backend.
+	pub fn record_proof_size(&mut self, proof_size: u64) -> Result<(), ExitException> {
+		self.record_weight(Weight::from_parts(0, proof_size))
+	}
+}
+
+/// Records the weight of the gas used by each opcode, in a
+/// [crate::HookedEtable].
+///
+/// The opcode is evaluated first, and then fails with `OutOfGas` if the
+/// weight limit is exceeded, so that its effects are reverted with the call
+/// frame.
+impl<S: GasState, H, Tr, M: GasWeightMapping> EtableHook<S, H, Tr> for WeightMeter<M> {
+	type Data = u64;
+
+	fn before_eval(
+		&mut self,
+		machine: &Machine<S>,
+		_handle: &H,
+		_opcode: Opcode,
+		_position: usize,
+	) -> u64 {
+		machine.state.gas()
+	}
+
+	fn after_eval(
+		&mut self,
+		gas: u64,
+		machine: &Machine<S>,
+		_handle: &H,
+		_opcode: Opcode,
+		_position: usize,
+		control: Control<Tr>,
+	) -> Control<Tr> {
+		let used = gas.saturating_sub(machine.state.gas());
+		match self.record_gas(used) {
+			Ok(()) => control,
+			Err(err) => Control::Exit(Err(err.into())),
+		}
+	}
+}
+
+/// A [PrecompileSet] recording the weight of the gas used by the wrapped
+/// precompiles, which a [crate::HookedEtable] does not see. A precompile
+/// exceeding the weight limit fails with `OutOfGas`.
+pub struct WeightedPrecompileSet<'meter, P, M> {
+	precompiles: P,
+	meter: &'meter RefCell<WeightMeter<M>>,
+}
+
+impl<'meter, P, M> WeightedPrecompileSet<'meter, P, M> {
+	pub fn new(precompiles: P, meter: &'meter RefCell<WeightMeter<M>>) -> Self {
+		Self { precompiles, meter }
+	}
+}
+
+impl<'meter, S, H, P, M> PrecompileSet<S, H> for WeightedPrecompileSet<'meter, P, M>
+where
+	S: GasState,
+	P: PrecompileSet<S, H>,
+	M: GasWeightMapping,
+{
+	fn execute(
+		&self,
+		code_address: H160,
+		input: &[u8],
+		state: &mut S,
+		handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		let gas = state.gas();
+		let (result, retval) = self
+			.precompiles
+			.execute(code_address, input, state, handler)?;
+
+		let used = gas.saturating_sub(state.gas());
+		match self.meter.borrow_mut().record_gas(used) {
+			Ok(()) => Some((result, retval)),
+			Err(err) => Some((Err(err.into()), Vec::new())),
+		}
+	}
+
+	fn addresses(&self) -> Vec<H160> {
+		self.precompiles.addresses()
+	}
+
+	fn is_precompile(&self, address: H160) -> bool {
+		self.precompiles.is_precompile(address)
+	}
+
+	fn warm_addresses(&self, handler: &mut H)
+	where
+		H: RuntimeBackend,
+	{
+		self.precompiles.warm_addresses(handler);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{sync::Arc, vec, vec::Vec};
+	use core::cell::RefCell;
+
+	use evm_interpreter::{
+		error::{ExitError, ExitException, ExitResult, ExitSucceed},
+		etable::Control,
+		machine::Machine,
+		opcode::Opcode,
+		runtime::GasState,
+	};
+	use primitive_types::H160;
+
+	use super::{
+		FixedGasWeightMapping, GasWeightMapping, Weight, WeightMeter, WeightedPrecompileSet,
+	};
+	use crate::{standard::PrecompileSet, EtableHook};
+
+	struct Gas(u64);
+
+	impl GasState for Gas {
+		fn gas(&self) -> u64 {
+			self.0
+		}
+	}
+
+	/// A precompile at address 1 using 100 gas.
+	struct Precompile;
+
+	impl PrecompileSet<Gas, ()> for Precompile {
+		fn execute(
+			&self,
+			code_address: H160,
+			_input: &[u8],
+			state: &mut Gas,
+			_handler: &mut (),
+		) -> Option<(ExitResult, Vec<u8>)> {
+			if code_address != H160::from_low_u64_be(1) {
+				return None;
+			}
+			state.0 -= 100;
+			Some((Ok(ExitSucceed::Returned), vec![1]))
+		}
+
+		fn addresses(&self) -> Vec<H160> {
+			vec![H160::from_low_u64_be(1)]
+		}
+	}
+
+	fn meter(ref_time: u64, proof_size: u64) -> WeightMeter<FixedGasWeightMapping> {
+		WeightMeter::new(
+			FixedGasWeightMapping::new(Weight::from_parts(10, 1)),
+			Weight::from_parts(ref_time, proof_size),
+		)
+	}
+
+	#[test]
+	fn zero_weight_components_are_not_limited_by_gas() {
+		let weight = Weight::from_parts(1_000, 50);
+
+		let mapping = FixedGasWeightMapping::new(Weight::from_parts(0, 0));
+		assert_eq!(mapping.weight_to_gas(weight), u64::MAX);
+		assert_eq!(mapping.gas_to_weight(100), Weight::default());
+
+		let mapping = FixedGasWeightMapping::new(Weight::from_parts(10, 0));
+		assert_eq!(mapping.weight_to_gas(weight), 100);
+
+		let mapping = FixedGasWeightMapping::new(Weight::from_parts(0, 1));
+		assert_eq!(mapping.weight_to_gas(weight), 50);
+	}
+
+	#[test]
+	fn opcodes_exceeding_the_weight_fail_with_out_of_gas() {
+		let mut meter = meter(1_000, 1_000);
+		let machine = Machine::new(
+			Arc::new(Vec::new()),
+			Arc::new(Vec::new()),
+			1024,
+			10_000,
+			Gas(1_000 - 60),
+		);
+
+		let control = EtableHook::<Gas, (), ()>::after_eval(
+			&mut meter,
+			1_000,
+			&machine,
+			&(),
+			Opcode::ADD,
+			0,
+			Control::Continue,
+		);
+		assert!(matches!(control, Control::Continue));
+
+		for control in [Control::Continue, Control::Trap(())] {
+			let control = EtableHook::<Gas, (), ()>::after_eval(
+				&mut meter,
+				1_000,
+				&machine,
+				&(),
+				Opcode::CALL,
+				0,
+				control,
+			);
+			assert!(matches!(
+				control,
+				Control::Exit(Err(ExitError::Exception(ExitException::OutOfGas)))
+			));
+		}
+	}
+
+	#[test]
+	fn proof_size_exhausts_the_weight() {
+		let mut meter = meter(1_000_000, 100);
+		assert_eq!(meter.gas_limit(), 100);
+
+		assert_eq!(meter.record_proof_size(60), Ok(()));
+		assert_eq!(meter.gas_limit(), 40);
+		assert_eq!(meter.record_proof_size(60), Err(ExitException::OutOfGas));
+		assert!(meter.is_exhausted());
+		assert_eq!(meter.consumed(), Weight::from_parts(0, 120));
+		assert_eq!(meter.gas_limit(), 0);
+		assert_eq!(meter.record_gas(1), Err(ExitException::OutOfGas));
+	}
+
+	#[test]
+	fn precompile_gas_is_metered() {
+		let meter = RefCell::new(meter(1_500, 1_000));
+		let precompiles = WeightedPrecompileSet::new(Precompile, &meter);
+		let mut state = Gas(10_000);
+
+		assert!(precompiles
+			.execute(H160::from_low_u64_be(2), &[], &mut state, &mut ())
+			.is_none());
+		assert_eq!(
+			precompiles.execute(H160::from_low_u64_be(1), &[], &mut state, &mut ()),
+			Some((Ok(ExitSucceed::Returned), vec![1]))
+		);
+		assert_eq!(meter.borrow().consumed(), Weight::from_parts(1_000, 100));
+		assert_eq!(
+			precompiles.execute(H160::from_low_u64_be(1), &[], &mut state, &mut ()),
+			Some((Err(ExitException::OutOfGas.into()), Vec::new()))
+		);
+	}
+}
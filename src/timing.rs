@@ -0,0 +1,438 @@
+//! # Timing
+//!
+//! Measure where the interpreter spends its time: the execution time of
+//! each class of opcodes, and of each call frame. Time is read from a
+//! [Clock], so that `no_std` hosts can provide their own. With `std`,
+//! [StdClock] reads [std::time::Instant].
+//!
+//! The [Timings] are shared by a [crate::HookedEtable], which times the
+//! opcodes, and a [crate::MiddlewareInvoker], which times the transaction and
+//! its substacks:
+//!
+//! ```ignore
+//! let timings = RefCell::new(Timings::new(StdClock::new()));
+//! let etable = HookedEtable::new((gas_etable, exec_etable), &timings);
+//! let resolver = EtableResolver::new(&config, &precompiles, &etable);
+//! let invoker = MiddlewareInvoker::new(Invoker::new(&config, &resolver), &timings);
+//! ```
+//!
+//! The time of a call or create opcode only covers the opcode itself. The
+//! time of its substack is the time of the frame, which includes the time of
+//! the frames it calls. Reading the clock has a cost of its own, so timings
+//! are only meant to be compared with each other.
+
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use evm_interpreter::{
+	error::{Capture, ExitError, ExitResult},
+	etable::Control,
+	machine::Machine,
+	opcode::Opcode,
+};
+use primitive_types::H160;
+
+use crate::{
+	invoker::{Invoker as InvokerT, InvokerControl, InvokerMiddleware},
+	standard::SubstackInvoke,
+	EtableHook,
+};
+
+/// Source of time of [Timings].
+pub trait Clock {
+	/// Current time in nanoseconds, from an arbitrary origin. It must never
+	/// go backwards.
+	fn now(&self) -> u64;
+}
+
+/// A [Clock] reading [std::time::Instant].
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct StdClock {
+	origin: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl StdClock {
+	pub fn new() -> Self {
+		Self {
+			origin: std::time::Instant::now(),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+	fn now(&self) -> u64 {
+		u64::try_from(self.origin.elapsed().as_nanos()).unwrap_or(u64::MAX)
+	}
+}
+
+/// Class of opcodes timed together.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum OpcodeClass {
+	/// Arithmetic, from `ADD` to `SIGNEXTEND`.
+	Arithmetic,
+	/// Comparison and bitwise, from `LT` to `SAR`.
+	Bitwise,
+	/// `SHA3`.
+	Keccak,
+	/// Execution environment, from `ADDRESS` to `EXTCODEHASH`, and `GAS`.
+	Environment,
+	/// Block information, from `BLOCKHASH` to `BLOBBASEFEE`.
+	Block,
+	/// `POP`, pushes, duplications and swaps.
+	Stack,
+	/// Memory reads and writes, `MSIZE` and `MCOPY`.
+	Memory,
+	/// Storage and transient storage reads and writes.
+	Storage,
+	/// Jumps, `PC` and `JUMPDEST`.
+	Flow,
+	/// `LOG0` to `LOG4`.
+	Log,
+	/// Calls, creates, returns, reverts and `SELFDESTRUCT`.
+	System,
+	/// Any other opcode, including invalid ones.
+	Other,
+}
+
+impl OpcodeClass {
+	/// All classes, in the order of [Timings::classes].
+	pub const ALL: [Self; 12] = [
+		Self::Arithmetic,
+		Self::Bitwise,
+		Self::Keccak,
+		Self::Environment,
+		Self::Block,
+		Self::Stack,
+		Self::Memory,
+		Self::Storage,
+		Self::Flow,
+		Self::Log,
+		Self::System,
+		Self::Other,
+	];
+
+	/// Class of the opcode.
+	pub const fn of(opcode: Opcode) -> Self {
+		match opcode.0 {
+			0x01..=0x0b => Self::Arithmetic,
+			0x10..=0x1d => Self::Bitwise,
+			0x20 => Self::Keccak,
+			0x30..=0x3f | 0x5a => Self::Environment,
+			0x40..=0x4a => Self::Block,
+			0x50 | 0x5f..=0x9f => Self::Stack,
+			0x51..=0x53 | 0x59 | 0x5e => Self::Memory,
+			0x54 | 0x55 | 0x5c | 0x5d => Self::Storage,
+			0x56..=0x58 | 0x5b => Self::Flow,
+			0xa0..=0xa4 => Self::Log,
+			0x00 | 0xf0..=0xff => Self::System,
+			_ => Self::Other,
+		}
+	}
+
+	const fn index(self) -> usize {
+		self as usize
+	}
+}
+
+/// Executions and total time of a class of opcodes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ClassTiming {
+	pub count: u64,
+	pub nanos: u64,
+}
+
+/// Time of a call frame, including the frames it called.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FrameTiming {
+	/// Depth of the frame. The top-level frame of the transaction, which is
+	/// not recorded, is at depth zero.
+	pub depth: usize,
+	/// Code address of a call, or address of a create.
+	pub address: H160,
+	/// Whether the frame is a create.
+	pub create: bool,
+	pub nanos: u64,
+}
+
+/// Execution times, measured with a [Clock].
+#[derive(Clone, Debug)]
+pub struct Timings<C> {
+	clock: C,
+	classes: [ClassTiming; OpcodeClass::ALL.len()],
+	frames: Vec<FrameTiming>,
+	open: Vec<(u64, H160, bool)>,
+	transaction_start: Option<u64>,
+	transactions: u64,
+}
+
+impl<C: Clock> Timings<C> {
+	pub fn new(clock: C) -> Self {
+		Self {
+			clock,
+			classes: [ClassTiming::default(); OpcodeClass::ALL.len()],
+			frames: Vec::new(),
+			open: Vec::new(),
+			transaction_start: None,
+			transactions: 0,
+		}
+	}
+
+	/// The clock.
+	pub fn clock(&self) -> &C {
+		&self.clock
+	}
+
+	/// Timing of the class of opcodes.
+	pub fn class(&self, class: OpcodeClass) -> ClassTiming {
+		self.classes[class.index()]
+	}
+
+	/// Timings of all classes of opcodes, in the order of
+	/// [OpcodeClass::ALL].
+	pub fn classes(&self) -> impl Iterator<Item = (OpcodeClass, ClassTiming)> + '_ {
+		OpcodeClass::ALL
+			.iter()
+			.map(move |class| (*class, self.class(*class)))
+	}
+
+	/// Timings of the substacks that exited, in the order they exited.
+	pub fn frames(&self) -> &[FrameTiming] {
+		&self.frames
+	}
+
+	/// Total time of the transactions that finished.
+	pub fn transactions(&self) -> u64 {
+		self.transactions
+	}
+
+	/// Clear all timings, keeping the clock.
+	pub fn clear(&mut self) {
+		self.classes = [ClassTiming::default(); OpcodeClass::ALL.len()];
+		self.frames.clear();
+		self.open.clear();
+		self.transaction_start = None;
+		self.transactions = 0;
+	}
+
+	fn record_opcode(&mut self, opcode: Opcode, nanos: u64) {
+		let timing = &mut self.classes[OpcodeClass::of(opcode).index()];
+		timing.count += 1;
+		timing.nanos = timing.nanos.saturating_add(nanos);
+	}
+}
+
+/// Times each opcode, in a [crate::HookedEtable].
+impl<S, H, Tr, C: Clock> EtableHook<S, H, Tr> for Timings<C> {
+	type Data = u64;
+
+	fn before_eval(
+		&mut self,
+		_machine: &Machine<S>,
+		_handle: &H,
+		_opcode: Opcode,
+		_position: usize,
+	) -> u64 {
+		self.clock.now()
+	}
+
+	fn after_eval(
+		&mut self,
+		start: u64,
+		_machine: &Machine<S>,
+		_handle: &H,
+		opcode: Opcode,
+		_position: usize,
+		control: Control<Tr>,
+	) -> Control<Tr> {
+		let nanos = self.clock.now().saturating_sub(start);
+		self.record_opcode(opcode, nanos);
+		control
+	}
+}
+
+/// Times the transactions and the substacks, in a [crate::MiddlewareInvoker].
+impl<'timings, H, Tr, I, C> InvokerMiddleware<H, Tr, I> for &'timings RefCell<Timings<C>>
+where
+	I: InvokerT<H, Tr, SubstackInvoke = SubstackInvoke>,
+	C: Clock,
+{
+	fn new_transact(
+		&self,
+		invoker: &I,
+		args: I::TransactArgs,
+		handler: &mut H,
+	) -> Result<
+		(
+			I::TransactInvoke,
+			InvokerControl<I::Interpreter, (ExitResult, (I::State, Vec<u8>))>,
+		),
+		ExitError,
+	> {
+		let start = self.borrow().clock.now();
+		let ret = invoker.new_transact(args, handler);
+		if ret.is_ok() {
+			self.borrow_mut().transaction_start = Some(start);
+		}
+		ret
+	}
+
+	fn finalize_transact(
+		&self,
+		invoker: &I,
+		invoke: &I::TransactInvoke,
+		exit: ExitResult,
+		machine: (I::State, Vec<u8>),
+		handler: &mut H,
+	) -> Result<I::TransactValue, ExitError> {
+		let ret = invoker.finalize_transact(invoke, exit, machine, handler);
+
+		let mut timings = self.borrow_mut();
+		if let Some(start) = timings.transaction_start.take() {
+			let nanos = timings.clock.now().saturating_sub(start);
+			timings.transactions = timings.transactions.saturating_add(nanos);
+		}
+		ret
+	}
+
+	fn enter_substack(
+		&self,
+		invoker: &I,
+		trap: Tr,
+		machine: &mut I::Interpreter,
+		handler: &mut H,
+		depth: usize,
+	) -> Capture<
+		Result<
+			(
+				I::SubstackInvoke,
+				InvokerControl<I::Interpreter, (ExitResult, (I::State, Vec<u8>))>,
+			),
+			ExitError,
+		>,
+		I::Interrupt,
+	> {
+		let start = self.borrow().clock.now();
+		let ret = invoker.enter_substack(trap, machine, handler, depth);
+		if let Capture::Exit(Ok((invoke, _))) = &ret {
+			let (address, create) = match invoke {
+				SubstackInvoke::Call { trap, .. } => (trap.target, false),
+				SubstackInvoke::Create { address, .. } => (*address, true),
+			};
+			self.borrow_mut().open.push((start, address, create));
+		}
+		ret
+	}
+
+	fn exit_substack(
+		&self,
+		invoker: &I,
+		result: ExitResult,
+		child: (I::State, Vec<u8>),
+		trap_data: I::SubstackInvoke,
+		parent: &mut I::Interpreter,
+		handler: &mut H,
+	) -> Result<(), ExitError> {
+		let ret = invoker.exit_substack(result, child, trap_data, parent, handler);
+
+		let mut timings = self.borrow_mut();
+		if let Some((start, address, create)) = timings.open.pop() {
+			let nanos = timings.clock.now().saturating_sub(start);
+			let depth = timings.open.len() + 1;
+			timings.frames.push(FrameTiming {
+				depth,
+				address,
+				create,
+				nanos,
+			});
+		}
+		ret
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::{collections::BTreeSet, vec, vec::Vec};
+	use core::cell::{Cell, RefCell};
+
+	use primitive_types::H160;
+
+	use super::{Clock, OpcodeClass, Timings};
+	use crate::{
+		backend::OverlayedBackend,
+		standard::{
+			testing::{backend, call_args, etable, CONTRACT as CALLER_CONTRACT},
+			Config, EtableResolver, Invoker,
+		},
+		HookedEtable, MiddlewareInvoker,
+	};
+
+	const CALLEE: H160 = H160([0x30; 20]);
+
+	/// A clock that advances by one nanosecond every time it is read.
+	#[derive(Default)]
+	struct TickClock(Cell<u64>);
+
+	impl Clock for TickClock {
+		fn now(&self) -> u64 {
+			let now = self.0.get() + 1;
+			self.0.set(now);
+			now
+		}
+	}
+
+	#[test]
+	fn times_opcodes_frames_and_transactions() {
+		let mut base = backend();
+		// Calls the callee with no value and no input.
+		let mut code = vec![
+			0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+		];
+		code.extend_from_slice(&CALLEE[..]);
+		code.extend_from_slice(&[0x5a, 0xf1, 0x00]);
+		base.set_code(CALLER_CONTRACT, code);
+		base.set_code(CALLEE, vec![0x60, 0x01, 0x60, 0x02, 0x01, 0x00]);
+
+		let timings = RefCell::new(Timings::new(TickClock::default()));
+		let config = Config::cancun();
+		let etable = HookedEtable::new(etable(), &timings);
+		let resolver = EtableResolver::new(&config, &(), &etable);
+		let invoker = MiddlewareInvoker::new(Invoker::new(&config, &resolver), &timings);
+		let args = call_args(CALLER_CONTRACT, Vec::new(), 100_000, 0);
+
+		let mut backend = OverlayedBackend::new(&base, BTreeSet::new());
+		let value = crate::transact_hybrid(args, None, &mut backend, &invoker).unwrap();
+		assert!(value.exit.is_ok());
+
+		let timings = timings.into_inner();
+		let count = |class| timings.class(class).count;
+		// Six pushes in the caller, two in the callee.
+		assert_eq!(count(OpcodeClass::Stack), 8);
+		assert_eq!(count(OpcodeClass::Arithmetic), 1);
+		assert_eq!(count(OpcodeClass::Environment), 1);
+		// The call and the two stops.
+		assert_eq!(count(OpcodeClass::System), 3);
+		// Each opcode reads the clock twice, one tick apart.
+		assert!(timings
+			.classes()
+			.all(|(_, timing)| timing.nanos == timing.count));
+
+		assert_eq!(timings.frames().len(), 1);
+		let frame = timings.frames()[0];
+		assert_eq!(
+			(frame.depth, frame.address, frame.create),
+			(1, CALLEE, false)
+		);
+		assert!(frame.nanos > 8);
+		assert!(timings.transactions() > frame.nanos);
+	}
+}
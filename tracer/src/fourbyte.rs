@@ -0,0 +1,129 @@
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	io::{self, Write},
+};
+
+use evm::{
+	interpreter::{error::ExitResult, etable::Control, opcode::Opcode},
+	standard::{inspector::Inspector, Machine, State, SubstackInvoke},
+};
+use primitive_types::H160;
+
+/// A tracer that counts the calls of each function selector and call data
+/// size across the call tree, like the `4byteTracer` of geth.
+///
+/// Only calls with at least 4 bytes of call data are counted, and calls to
+/// precompiles given with [FourByteTracer::with_precompiles] are skipped.
+/// The top-level call is counted on its first opcode, so a transaction to an
+/// account without code is not counted.
+///
+/// The tracer is an [Inspector], to be shared by an
+/// [evm::standard::inspector::InspectedEtable] and an
+/// [evm::standard::inspector::InspectorMiddleware]. It can be reused across
+/// transactions with [FourByteTracer::next_transaction], to aggregate the
+/// counts of a workload.
+#[derive(Clone, Debug, Default)]
+pub struct FourByteTracer {
+	precompiles: BTreeSet<H160>,
+	started: bool,
+	counts: BTreeMap<([u8; 4], usize), u64>,
+}
+
+impl FourByteTracer {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Skip calls to the precompiles.
+	#[must_use]
+	pub fn with_precompiles(mut self, precompiles: impl IntoIterator<Item = H160>) -> Self {
+		self.precompiles.extend(precompiles);
+		self
+	}
+
+	/// Count the top-level call of the next transaction. The counts are kept.
+	pub fn next_transaction(&mut self) {
+		self.started = false;
+	}
+
+	/// Number of calls, by selector and size of the call data after the
+	/// selector.
+	pub fn counts(&self) -> &BTreeMap<([u8; 4], usize), u64> {
+		&self.counts
+	}
+
+	/// Number of calls keyed as geth does, `0x<selector>-<size>`.
+	pub fn result(&self) -> BTreeMap<String, u64> {
+		self.counts
+			.iter()
+			.map(|((selector, size), count)| {
+				let key = format!(
+					"0x{:02x}{:02x}{:02x}{:02x}-{}",
+					selector[0], selector[1], selector[2], selector[3], size
+				);
+				(key, *count)
+			})
+			.collect()
+	}
+
+	/// Write the result as a JSON object, in the format of geth.
+	pub fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		writer.write_all(b"{")?;
+		for (i, (key, count)) in self.result().iter().enumerate() {
+			if i != 0 {
+				writer.write_all(b",")?;
+			}
+			write!(writer, "\"{}\":{}", key, count)?;
+		}
+		writer.write_all(b"}")
+	}
+
+	fn record(&mut self, input: &[u8]) {
+		if input.len() < 4 {
+			return;
+		}
+
+		let mut selector = [0u8; 4];
+		selector.copy_from_slice(&input[..4]);
+		*self.counts.entry((selector, input.len() - 4)).or_default() += 1;
+	}
+}
+
+impl<'config, H, Tr> Inspector<State<'config>, H, Tr> for FourByteTracer {
+	fn step(
+		&mut self,
+		machine: &mut Machine<'config>,
+		_handler: &mut H,
+		_opcode: Opcode,
+		_position: usize,
+	) -> Option<Control<Tr>> {
+		if !self.started {
+			self.started = true;
+			self.record(machine.data());
+		}
+
+		None
+	}
+
+	fn enter_substack(
+		&mut self,
+		invoke: &SubstackInvoke,
+		_handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		// A substack is always entered after the first step of the
+		// top-level call.
+		self.started = true;
+
+		if let SubstackInvoke::Call { trap, opcode } = invoke {
+			let counted = matches!(
+				*opcode,
+				Opcode::CALL | Opcode::CALLCODE | Opcode::DELEGATECALL | Opcode::STATICCALL
+			);
+			if counted && !self.precompiles.contains(&trap.target) {
+				self.record(&trap.input);
+			}
+		}
+
+		None
+	}
+}
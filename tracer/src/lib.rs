@@ -1,7 +1,21 @@
+mod fourbyte;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+mod profiler;
+#[cfg(feature = "revm")]
+mod revm;
+mod security;
 mod standard;
+#[cfg(all(test, any(feature = "opentelemetry", feature = "revm")))]
+mod testing;
 
-use evm::interpreter::{machine::Machine, opcode::Opcode};
-
-pub trait EvalTracer<S, H> {
-	fn on_eval(&mut self, machine: &Machine<S>, handle: &H, opcode: Opcode, position: usize);
-}
+#[cfg(feature = "opentelemetry")]
+pub use crate::otel::OtelTracer;
+#[cfg(feature = "revm")]
+pub use crate::revm::{BackendDatabase, RevmInspector};
+pub use crate::{
+	fourbyte::FourByteTracer,
+	profiler::GasProfiler,
+	security::{Finding, SecurityTracer},
+	standard::{Step, StepCallback, StreamingStructLogger, StructLog, StructLogger},
+};
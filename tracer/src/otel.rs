@@ -0,0 +1,338 @@
+use std::borrow::Cow;
+
+use evm::{
+	interpreter::{
+		error::{CreateScheme, ExitError, ExitResult},
+		opcode::Opcode,
+		runtime::GasState,
+	},
+	standard::{inspector::Inspector, Machine, State, SubstackInvoke},
+};
+use opentelemetry::{
+	trace::{SpanKind, Status, TraceContextExt, Tracer},
+	Context, KeyValue,
+};
+use primitive_types::U256;
+
+/// A tracer that exports an OpenTelemetry span for each call and create
+/// frame, with the target, the function selector, the gas and the exit
+/// status as attributes.
+///
+/// The span of the call frame of the transaction is a child of the context
+/// given to [OtelTracer::new], usually the span the node opened for the
+/// transaction, and the spans of nested calls are children of their caller.
+/// Spans of frames that never exit, because of a fatal error, end when the
+/// tracer is dropped.
+///
+/// The tracer is an [Inspector], to be shared by an
+/// [evm::standard::inspector::InspectedEtable] and an
+/// [evm::standard::inspector::InspectorMiddleware].
+pub struct OtelTracer<T> {
+	tracer: T,
+	parent: Context,
+	frames: Vec<Context>,
+}
+
+impl<T: Tracer> OtelTracer<T>
+where
+	T::Span: Send + Sync + 'static,
+{
+	pub fn new(tracer: T, parent: Context) -> Self {
+		Self {
+			tracer,
+			parent,
+			frames: Vec::new(),
+		}
+	}
+
+	fn enter(&mut self, name: &'static str, mut attributes: Vec<KeyValue>) {
+		attributes.push(KeyValue::new("evm.depth", self.frames.len() as i64));
+
+		let parent = self.frames.last().unwrap_or(&self.parent);
+		let span = self
+			.tracer
+			.span_builder(Cow::Borrowed(name))
+			.with_kind(SpanKind::Internal)
+			.with_attributes(attributes)
+			.start_with_context(&self.tracer, parent);
+		let context = parent.with_span(span);
+		self.frames.push(context);
+	}
+
+	fn exit(&mut self, state: &State, result: &ExitResult) {
+		let context = match self.frames.pop() {
+			Some(context) => context,
+			None => return,
+		};
+
+		let span = context.span();
+		span.set_attribute(KeyValue::new(
+			"evm.gas_left",
+			state.gas().min(i64::MAX as u64) as i64,
+		));
+		let status = match result {
+			Ok(_) => Status::Ok,
+			Err(ExitError::Reverted) => Status::error("reverted"),
+			Err(err) => Status::error(format!("{:?}", err)),
+		};
+		span.set_status(status);
+		span.end();
+	}
+}
+
+/// The selector attribute, if the input has one.
+fn selector(input: &[u8]) -> Option<KeyValue> {
+	match input {
+		[a, b, c, d, ..] => Some(KeyValue::new(
+			"evm.selector",
+			format!("0x{:02x}{:02x}{:02x}{:02x}", a, b, c, d),
+		)),
+		_ => None,
+	}
+}
+
+impl<'config, H, Tr, T: Tracer> Inspector<State<'config>, H, Tr> for OtelTracer<T>
+where
+	T::Span: Send + Sync + 'static,
+{
+	fn enter_transact(&mut self, machine: &Machine<'config>, _handler: &mut H) {
+		let mut attributes = vec![
+			KeyValue::new(
+				"evm.target",
+				format!("0x{:x}", machine.state.runtime.context.address),
+			),
+			KeyValue::new("evm.gas", machine.state.gas().min(i64::MAX as u64) as i64),
+		];
+		attributes.extend(selector(machine.data()));
+		self.enter("transact", attributes);
+	}
+
+	fn exit_transact(&mut self, state: &State<'config>, result: &ExitResult, _handler: &mut H) {
+		self.exit(state, result);
+	}
+
+	fn enter_substack(
+		&mut self,
+		invoke: &SubstackInvoke,
+		_handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		let (name, attributes) = match invoke {
+			SubstackInvoke::Call { trap, opcode } => {
+				let name = match *opcode {
+					Opcode::CALLCODE => "callcode",
+					Opcode::DELEGATECALL => "delegatecall",
+					Opcode::STATICCALL => "staticcall",
+					_ => "call",
+				};
+				let mut attributes = vec![
+					KeyValue::new("evm.target", format!("0x{:x}", trap.target)),
+					KeyValue::new(
+						"evm.gas",
+						trap.gas.min(U256::from(i64::MAX)).as_u64() as i64,
+					),
+				];
+				attributes.extend(selector(&trap.input));
+				(name, attributes)
+			}
+			SubstackInvoke::Create { trap, address } => {
+				let name = match trap.scheme {
+					CreateScheme::Create2 { .. } => "create2",
+					_ => "create",
+				};
+				(
+					name,
+					vec![KeyValue::new("evm.target", format!("0x{:x}", address))],
+				)
+			}
+		};
+		self.enter(name, attributes);
+
+		None
+	}
+
+	fn exit_substack(
+		&mut self,
+		_invoke: &SubstackInvoke,
+		state: &State<'config>,
+		result: &mut ExitResult,
+		_retval: &mut Vec<u8>,
+		_handler: &mut H,
+	) {
+		self.exit(state, result);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		borrow::Cow,
+		cell::RefCell,
+		sync::{Arc, Mutex},
+		time::SystemTime,
+	};
+
+	use evm::standard::Config;
+	use opentelemetry::{
+		trace::{
+			Span, SpanBuilder, SpanContext, SpanId, Status, TraceContextExt, TraceFlags, TraceId,
+			TraceState, Tracer,
+		},
+		Context, KeyValue,
+	};
+	use primitive_types::H160;
+
+	use super::OtelTracer;
+	use crate::testing::{backend, call_args, transact, CONTRACT as CALLER_CONTRACT};
+
+	const REVERTER: H160 = H160([0x30; 20]);
+
+	#[derive(Debug)]
+	struct Record {
+		parent: SpanId,
+		name: Cow<'static, str>,
+		attributes: Vec<KeyValue>,
+		status: Status,
+		ended: bool,
+	}
+
+	impl Record {
+		fn attribute(&self, key: &str) -> Option<String> {
+			self.attributes
+				.iter()
+				.find(|attribute| attribute.key.as_str() == key)
+				.map(|attribute| attribute.value.to_string())
+		}
+	}
+
+	/// A tracer that records the spans, with the index of a span as its id.
+	#[derive(Clone, Default)]
+	struct Recorder(Arc<Mutex<Vec<Record>>>);
+
+	struct RecordedSpan {
+		records: Arc<Mutex<Vec<Record>>>,
+		context: SpanContext,
+	}
+
+	impl RecordedSpan {
+		fn with_record(&mut self, f: impl FnOnce(&mut Record)) {
+			let index = u64::from_be_bytes(self.context.span_id().to_bytes()) as usize - 1;
+			f(&mut self.records.lock().unwrap()[index]);
+		}
+	}
+
+	impl Span for RecordedSpan {
+		fn add_event_with_timestamp<T>(&mut self, _: T, _: SystemTime, _: Vec<KeyValue>)
+		where
+			T: Into<Cow<'static, str>>,
+		{
+		}
+
+		fn span_context(&self) -> &SpanContext {
+			&self.context
+		}
+
+		fn is_recording(&self) -> bool {
+			true
+		}
+
+		fn set_attribute(&mut self, attribute: KeyValue) {
+			self.with_record(|record| record.attributes.push(attribute));
+		}
+
+		fn set_status(&mut self, status: Status) {
+			self.with_record(|record| record.status = status);
+		}
+
+		fn update_name<T>(&mut self, name: T)
+		where
+			T: Into<Cow<'static, str>>,
+		{
+			let name = name.into();
+			self.with_record(|record| record.name = name);
+		}
+
+		fn end_with_timestamp(&mut self, _: SystemTime) {
+			self.with_record(|record| record.ended = true);
+		}
+	}
+
+	impl Tracer for Recorder {
+		type Span = RecordedSpan;
+
+		fn build_with_context(&self, builder: SpanBuilder, parent_cx: &Context) -> RecordedSpan {
+			let mut records = self.0.lock().unwrap();
+			records.push(Record {
+				parent: parent_cx.span().span_context().span_id(),
+				name: builder.name,
+				attributes: builder.attributes.unwrap_or_default(),
+				status: Status::Unset,
+				ended: false,
+			});
+			let span_id = SpanId::from_bytes((records.len() as u64).to_be_bytes());
+			RecordedSpan {
+				records: self.0.clone(),
+				context: SpanContext::new(
+					TraceId::from_bytes([1; 16]),
+					span_id,
+					TraceFlags::SAMPLED,
+					false,
+					TraceState::default(),
+				),
+			}
+		}
+	}
+
+	#[test]
+	fn transaction_frame_has_a_span() {
+		let mut base = backend();
+		// Calls the reverter with no value and the input 0x01020304.
+		let mut code = vec![
+			0x63, 0x01, 0x02, 0x03, 0x04, 0x60, 0xe0, 0x1b, 0x60, 0x00, 0x52, 0x60, 0x00, 0x60,
+			0x00, 0x60, 0x04, 0x60, 0x00, 0x60, 0x00, 0x73,
+		];
+		code.extend_from_slice(&REVERTER[..]);
+		code.extend_from_slice(&[0x5a, 0xf1, 0x00]);
+		base.set_code(CALLER_CONTRACT, code);
+		base.set_code(REVERTER, vec![0x60, 0x00, 0x60, 0x00, 0xfd]);
+
+		let recorder = Recorder::default();
+		let tracer = RefCell::new(OtelTracer::new(recorder.clone(), Context::new()));
+		let args = call_args(vec![0xaa, 0xbb, 0xcc, 0xdd, 0xee]);
+		let value = transact(&base, &Config::cancun(), args, &tracer);
+		assert!(value.exit.is_ok());
+		drop(tracer);
+
+		let records = recorder.0.lock().unwrap();
+		assert_eq!(records.len(), 2);
+		let (transact, call) = (&records[0], &records[1]);
+
+		assert_eq!(transact.name, "transact");
+		assert_eq!(transact.parent, SpanId::INVALID);
+		assert_eq!(
+			transact.attribute("evm.target"),
+			Some(format!("0x{:x}", CALLER_CONTRACT))
+		);
+		assert_eq!(
+			transact.attribute("evm.selector"),
+			Some("0xaabbccdd".to_string())
+		);
+		assert_eq!(transact.attribute("evm.depth"), Some("0".to_string()));
+		assert!(transact.attribute("evm.gas_left").is_some());
+		assert_eq!(transact.status, Status::Ok);
+		assert!(transact.ended);
+
+		assert_eq!(call.name, "call");
+		assert_eq!(call.parent, SpanId::from_bytes(1u64.to_be_bytes()));
+		assert_eq!(
+			call.attribute("evm.target"),
+			Some(format!("0x{:x}", REVERTER))
+		);
+		assert_eq!(
+			call.attribute("evm.selector"),
+			Some("0x01020304".to_string())
+		);
+		assert_eq!(call.attribute("evm.depth"), Some("1".to_string()));
+		assert_eq!(call.status, Status::error("reverted"));
+		assert!(call.ended);
+	}
+}
@@ -0,0 +1,165 @@
+use std::{
+	collections::BTreeMap,
+	fmt::Write as _,
+	io::{self, Write},
+};
+
+use evm::{
+	interpreter::{error::ExitResult, etable::Control, opcode::Opcode, runtime::GasState},
+	standard::{inspector::Inspector, Machine, State, SubstackInvoke},
+};
+use primitive_types::H160;
+
+struct Frame {
+	path: String,
+	last_gas: Option<u64>,
+	self_gas: u64,
+	child_gas: u64,
+	pending_child_gas: u64,
+}
+
+impl Frame {
+	fn new(parent: Option<&Frame>, label: &str) -> Self {
+		let path = match parent {
+			Some(parent) => format!("{};{}", parent.path, label),
+			None => label.to_string(),
+		};
+
+		Self {
+			path,
+			last_gas: None,
+			self_gas: 0,
+			child_gas: 0,
+			pending_child_gas: 0,
+		}
+	}
+}
+
+fn label(address: H160, input: &[u8]) -> String {
+	let mut label = format!("0x{:x}", address);
+	if input.len() >= 4 {
+		label.push_str(":0x");
+		for byte in &input[..4] {
+			let _ = write!(label, "{:02x}", byte);
+		}
+	}
+	label
+}
+
+/// A profiler that attributes gas to the call tree, and writes it in the
+/// folded-stack format of flamegraph tooling.
+///
+/// Frames are labeled by the context address, and the function selector if
+/// the call data has one. Created contracts are labeled with `create:` and
+/// their address. The gas of each frame is the gas of its own opcodes;
+/// flamegraph tools add up the gas of the callees. Precompiles and calls to
+/// accounts without code do not get frames, and their gas goes to the caller.
+///
+/// The profiler is an [Inspector], to be shared by an
+/// [evm::standard::inspector::InspectedEtable] and an
+/// [evm::standard::inspector::InspectorMiddleware]. Use one profiler per
+/// transaction.
+#[derive(Default)]
+pub struct GasProfiler {
+	frames: Vec<Frame>,
+	folded: BTreeMap<String, u64>,
+}
+
+impl GasProfiler {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Gas of the opcodes of each call path, including the frames that have
+	/// not exited yet.
+	pub fn folded(&self) -> BTreeMap<String, u64> {
+		let mut folded = self.folded.clone();
+		for frame in &self.frames {
+			*folded.entry(frame.path.clone()).or_default() += frame.self_gas;
+		}
+		folded.retain(|_, gas| *gas != 0);
+		folded
+	}
+
+	/// Write the profile in the folded-stack format, one call path per line.
+	pub fn write_folded<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		for (path, gas) in self.folded() {
+			writeln!(writer, "{} {}", path, gas)?;
+		}
+		Ok(())
+	}
+}
+
+impl<'config, H, Tr> Inspector<State<'config>, H, Tr> for GasProfiler {
+	fn step(
+		&mut self,
+		machine: &mut Machine<'config>,
+		_handler: &mut H,
+		_opcode: Opcode,
+		_position: usize,
+	) -> Option<Control<Tr>> {
+		if self.frames.is_empty() {
+			self.frames.push(Frame::new(
+				None,
+				&label(machine.state.runtime.context.address, machine.data()),
+			));
+		}
+
+		let gas = machine.state.gas();
+		if let Some(frame) = self.frames.last_mut() {
+			if let Some(last_gas) = frame.last_gas {
+				// The gas used by a call includes the gas used by the callee,
+				// which is already attributed to the callee.
+				frame.self_gas += last_gas
+					.saturating_sub(gas)
+					.saturating_sub(frame.pending_child_gas);
+				frame.pending_child_gas = 0;
+			}
+			frame.last_gas = Some(gas);
+		}
+
+		None
+	}
+
+	fn enter_substack(
+		&mut self,
+		invoke: &SubstackInvoke,
+		_handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		let label = match invoke {
+			SubstackInvoke::Call { trap, .. } => label(trap.context.address, &trap.input),
+			SubstackInvoke::Create { address, .. } => format!("create:0x{:x}", address),
+		};
+		let frame = Frame::new(self.frames.last(), &label);
+		self.frames.push(frame);
+
+		None
+	}
+
+	fn exit_substack(
+		&mut self,
+		_invoke: &SubstackInvoke,
+		state: &State<'config>,
+		_result: &mut ExitResult,
+		_retval: &mut Vec<u8>,
+		_handler: &mut H,
+	) {
+		let mut frame = match self.frames.pop() {
+			Some(frame) => frame,
+			None => return,
+		};
+
+		// The last opcode has no following step to measure it against.
+		if let Some(last_gas) = frame.last_gas {
+			frame.self_gas += last_gas.saturating_sub(state.gas());
+		}
+
+		let gas = frame.self_gas + frame.child_gas;
+		*self.folded.entry(frame.path).or_default() += frame.self_gas;
+
+		if let Some(parent) = self.frames.last_mut() {
+			parent.child_gas += gas;
+			parent.pending_child_gas += gas;
+		}
+	}
+}
@@ -0,0 +1,854 @@
+use std::{borrow::Cow, convert::Infallible};
+
+use evm::{
+	interpreter::{
+		error::{CreateScheme, ExitError, ExitException, ExitFatal, ExitResult, ExitSucceed},
+		etable::Control,
+		opcode::Opcode,
+		runtime::{recent_block_hash, RuntimeBaseBackend, RuntimeEnvironment, RuntimeState},
+	},
+	standard::{inspector::Inspector, GasometerState, Machine, State, SubstackInvoke},
+};
+use primitive_types::{H160, H256, U256};
+use revm::{
+	interpreter::{
+		CallContext, CallInputs, CallScheme as RevmCallScheme, Contract, CreateInputs, Gas,
+		InstructionResult, Interpreter, Memory, Stack, Transfer,
+	},
+	precompile::Precompiles,
+	primitives::{
+		AccountInfo, Address, BlockEnv, Bytecode, Bytes, CreateScheme as RevmCreateScheme, Env,
+		SpecId, TxEnv, B256, U256 as RU256,
+	},
+	Database, EVMData, JournaledState,
+};
+
+/// A read-only revm [Database] over the backend of this crate.
+///
+/// Accounts are returned with their code, so [Database::code_by_hash] is never
+/// needed and returns empty code.
+pub struct BackendDatabase<'h, H>(pub &'h H);
+
+impl<'h, H: RuntimeBaseBackend + RuntimeEnvironment> Database for BackendDatabase<'h, H> {
+	type Error = Infallible;
+
+	fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Infallible> {
+		let address = from_address(address);
+		if !self.0.exists(address) {
+			return Ok(None);
+		}
+
+		Ok(Some(AccountInfo {
+			balance: to_u256(self.0.balance(address)),
+			nonce: self.0.nonce(address).low_u64(),
+			code_hash: B256::from(self.0.code_hash(address).0),
+			code: Some(Bytecode::new_raw(self.0.code(address).into())),
+		}))
+	}
+
+	fn code_by_hash(&mut self, _code_hash: B256) -> Result<Bytecode, Infallible> {
+		Ok(Bytecode::new())
+	}
+
+	fn storage(&mut self, address: Address, index: RU256) -> Result<RU256, Infallible> {
+		let value = self.0.storage(from_address(address), ru256_to_h256(index));
+		Ok(h256_to_ru256(value))
+	}
+
+	fn block_hash(&mut self, number: RU256) -> Result<B256, Infallible> {
+		Ok(B256::from(recent_block_hash(self.0, from_u256(number)).0))
+	}
+}
+
+/// Inputs given to the revm inspector when a frame was entered.
+enum Inputs {
+	Call(CallInputs),
+	Create(CreateInputs),
+}
+
+/// A log or a self-destruct of an opcode, reported once the opcode succeeded.
+enum Effect {
+	Log {
+		address: H160,
+		topics: Vec<H256>,
+		offset: usize,
+		len: usize,
+	},
+	SelfDestruct {
+		contract: H160,
+		target: H160,
+		value: U256,
+	},
+}
+
+#[derive(Default)]
+struct Frame {
+	/// Inputs of the call or create, `None` for the transaction frame.
+	inputs: Option<Inputs>,
+	/// Whether the revm inspector skipped the frame, so that it is not told
+	/// about its end.
+	skipped: bool,
+	/// revm's view of the frame, created on its first opcode.
+	interp: Option<Interpreter>,
+	/// Whether an opcode was stepped and its `step_end` is pending.
+	stepped: bool,
+	effect: Option<Effect>,
+}
+
+/// An adapter running a [revm::Inspector], such as a tracer or a coverage
+/// collector written for revm, against this crate's interpreter.
+///
+/// The adapter is an [Inspector], to be shared by an
+/// [evm::standard::inspector::InspectedEtable] and an
+/// [evm::standard::inspector::InspectorMiddleware]. Before each opcode, revm's
+/// [Interpreter] is synced with the machine, and changes the revm inspector
+/// makes to the stack and memory are written back.
+///
+/// The [EVMData] reads the accounts from the backend through
+/// [BackendDatabase], but its journaled state is empty, since the state
+/// changes are kept by the backend of this crate. `step_end` is called
+/// before the next opcode of the frame, or when the frame exits, and logs and
+/// self-destructs are reported once their opcode succeeded. The gas limit of
+/// a call is the gas requested by the opcode, and the gas limit of a create
+/// is the gas left in the creating frame, before the create is charged. The
+/// transaction frame does not exit through a substack, so the hooks of its
+/// last opcode are not called.
+pub struct RevmInspector<I> {
+	inspector: I,
+	frames: Vec<Frame>,
+}
+
+impl<I> RevmInspector<I> {
+	pub fn new(inspector: I) -> Self {
+		Self {
+			inspector,
+			frames: Vec::new(),
+		}
+	}
+
+	/// The revm inspector.
+	pub fn inspector(&self) -> &I {
+		&self.inspector
+	}
+
+	/// The revm inspector, once the transaction is done.
+	pub fn into_inner(self) -> I {
+		self.inspector
+	}
+}
+
+impl<'config, H, Tr, I> Inspector<State<'config>, H, Tr> for RevmInspector<I>
+where
+	H: RuntimeBaseBackend + RuntimeEnvironment,
+	I: for<'h> revm::Inspector<BackendDatabase<'h, H>>,
+{
+	fn step(
+		&mut self,
+		machine: &mut Machine<'config>,
+		handler: &mut H,
+		opcode: Opcode,
+		position: usize,
+	) -> Option<Control<Tr>> {
+		if self.frames.is_empty() {
+			self.frames.push(Frame::default());
+		}
+		let frame = self.frames.last_mut().expect("a frame was pushed");
+
+		let mut env = env(&*handler, Some(&machine.state.runtime));
+		let mut db = BackendDatabase(&*handler);
+		let mut data = evm_data(&mut env, &mut db);
+
+		let interp = frame.interp.get_or_insert_with(|| {
+			let runtime = &machine.state.runtime;
+			let contract = Contract::new(
+				machine.data().to_vec().into(),
+				Bytecode::new_raw(machine.code().to_vec().into()),
+				B256::from(handler.keccak256(machine.code()).0),
+				to_address(runtime.context.address),
+				to_address(runtime.context.caller),
+				to_u256(runtime.context.apparent_value),
+			);
+			Interpreter::new(
+				Box::new(contract),
+				machine.state.gasometer.gas_limit(),
+				machine.state.gasometer.is_static,
+			)
+		});
+		sync(interp, machine, position);
+
+		if frame.stepped {
+			if let Some(effect) = frame.effect.take() {
+				report(&mut self.inspector, &mut data, interp, effect);
+			}
+			let ret = self
+				.inspector
+				.step_end(interp, &mut data, InstructionResult::Continue);
+			if let Err(err) = write_back(interp, machine) {
+				return Some(Control::Exit(Err(err)));
+			}
+			if ret != InstructionResult::Continue {
+				return Some(Control::Exit(exit_result(ret)));
+			}
+		}
+
+		let ret = self.inspector.step(interp, &mut data);
+		if let Err(err) = write_back(interp, machine) {
+			return Some(Control::Exit(Err(err)));
+		}
+		frame.stepped = true;
+		frame.effect = effect(machine, &*handler, opcode);
+
+		if ret != InstructionResult::Continue {
+			return Some(Control::Exit(exit_result(ret)));
+		}
+		None
+	}
+
+	fn enter_substack(
+		&mut self,
+		invoke: &SubstackInvoke,
+		handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		let mut env = env(&*handler, None);
+		let mut db = BackendDatabase(&*handler);
+		let mut data = evm_data(&mut env, &mut db);
+
+		let (mut frame, skipped) = match invoke {
+			SubstackInvoke::Call { trap, opcode } => {
+				let scheme = match *opcode {
+					Opcode::CALLCODE => RevmCallScheme::CallCode,
+					Opcode::DELEGATECALL => RevmCallScheme::DelegateCall,
+					Opcode::STATICCALL => RevmCallScheme::StaticCall,
+					_ => RevmCallScheme::Call,
+				};
+				let transfer = match &trap.transfer {
+					Some(transfer) => Transfer {
+						source: to_address(transfer.source),
+						target: to_address(transfer.target),
+						value: to_u256(transfer.value),
+					},
+					None => Transfer {
+						source: to_address(trap.context.caller),
+						target: to_address(trap.context.address),
+						value: RU256::ZERO,
+					},
+				};
+				let mut inputs = CallInputs {
+					contract: to_address(trap.target),
+					transfer,
+					input: trap.input.clone().into(),
+					gas_limit: trap.gas.min(U256::from(u64::MAX)).as_u64(),
+					context: CallContext {
+						address: to_address(trap.context.address),
+						caller: to_address(trap.context.caller),
+						code_address: to_address(trap.target),
+						apparent_value: to_u256(trap.context.apparent_value),
+						scheme,
+					},
+					is_static: trap.is_static,
+				};
+
+				let (ret, _, out) = self.inspector.call(&mut data, &mut inputs);
+				let skipped =
+					(ret != InstructionResult::Continue).then(|| (exit_result(ret), out.to_vec()));
+				(Frame::from(Inputs::Call(inputs)), skipped)
+			}
+			SubstackInvoke::Create { trap, .. } => {
+				let (caller, scheme) = match trap.scheme {
+					CreateScheme::Legacy { caller } => (caller, RevmCreateScheme::Create),
+					CreateScheme::Create2 { caller, salt, .. } => (
+						caller,
+						RevmCreateScheme::Create2 {
+							salt: h256_to_ru256(salt),
+						},
+					),
+				};
+				let gas_limit = self
+					.frames
+					.last()
+					.and_then(|frame| frame.interp.as_ref())
+					.map_or(0, |interp| interp.gas.remaining());
+				let mut inputs = CreateInputs {
+					caller: to_address(caller),
+					scheme,
+					value: to_u256(trap.value),
+					init_code: trap.code.clone().into(),
+					gas_limit,
+				};
+
+				let (ret, _, _, out) = self.inspector.create(&mut data, &mut inputs);
+				let skipped =
+					(ret != InstructionResult::Continue).then(|| (exit_result(ret), out.to_vec()));
+				(Frame::from(Inputs::Create(inputs)), skipped)
+			}
+		};
+
+		frame.skipped = skipped.is_some();
+		self.frames.push(frame);
+		skipped
+	}
+
+	fn exit_substack(
+		&mut self,
+		invoke: &SubstackInvoke,
+		state: &State<'config>,
+		result: &mut ExitResult,
+		retval: &mut Vec<u8>,
+		handler: &mut H,
+	) {
+		let frame = match self.frames.pop() {
+			Some(frame) => frame,
+			None => return,
+		};
+
+		let mut env = env(&*handler, Some(&state.runtime));
+		let mut db = BackendDatabase(&*handler);
+		let mut data = evm_data(&mut env, &mut db);
+		let ret = instruction_result(result);
+
+		if let Some(mut interp) = frame.interp {
+			if frame.stepped {
+				if let (Some(effect), true) = (frame.effect, result.is_ok()) {
+					report(&mut self.inspector, &mut data, &interp, effect);
+				}
+				interp.gas = gas(&state.gasometer);
+				interp.instruction_result = ret;
+				self.inspector.step_end(&mut interp, &mut data, ret);
+			}
+		}
+		if frame.skipped {
+			return;
+		}
+
+		let out = Bytes::from(retval.clone());
+		let (new_ret, new_out) = match (frame.inputs, invoke) {
+			(Some(Inputs::Call(inputs)), _) => {
+				let (ret, _, out) =
+					self.inspector
+						.call_end(&mut data, &inputs, gas(&state.gasometer), ret, out);
+				(ret, out)
+			}
+			(Some(Inputs::Create(inputs)), SubstackInvoke::Create { address, .. }) => {
+				let address = result.is_ok().then(|| to_address(*address));
+				let (ret, _, _, out) = self.inspector.create_end(
+					&mut data,
+					&inputs,
+					ret,
+					address,
+					gas(&state.gasometer),
+					out,
+				);
+				(ret, out)
+			}
+			_ => return,
+		};
+
+		if new_ret != ret {
+			*result = exit_result(new_ret);
+		}
+		if new_out[..] != retval[..] {
+			*retval = new_out.to_vec();
+		}
+	}
+}
+
+impl From<Inputs> for Frame {
+	fn from(inputs: Inputs) -> Self {
+		Self {
+			inputs: Some(inputs),
+			..Self::default()
+		}
+	}
+}
+
+/// The environment of revm, with the transaction of the runtime if there is
+/// one.
+fn env<H: RuntimeEnvironment>(handler: &H, runtime: Option<&RuntimeState>) -> Env {
+	let tx = match runtime {
+		Some(runtime) => {
+			let transaction = &runtime.transaction_context;
+			TxEnv {
+				caller: to_address(transaction.origin),
+				gas_price: to_u256(transaction.gas_price),
+				chain_id: Some(transaction.chain_id.low_u64()),
+				..TxEnv::default()
+			}
+		}
+		None => TxEnv::default(),
+	};
+
+	Env {
+		block: BlockEnv {
+			number: to_u256(handler.block_number()),
+			coinbase: to_address(handler.block_coinbase()),
+			timestamp: to_u256(handler.block_timestamp()),
+			gas_limit: to_u256(handler.block_gas_limit()),
+			basefee: to_u256(handler.block_base_fee_per_gas()),
+			difficulty: to_u256(handler.block_difficulty()),
+			prevrandao: handler
+				.block_randomness()
+				.map(|randomness| B256::from(randomness.0)),
+			..BlockEnv::default()
+		},
+		tx,
+		..Env::default()
+	}
+}
+
+fn evm_data<'a, DB: Database>(env: &'a mut Env, db: &'a mut DB) -> EVMData<'a, DB> {
+	EVMData {
+		env,
+		journaled_state: JournaledState::new(0, SpecId::LATEST),
+		db,
+		error: None,
+		precompiles: Precompiles::default(),
+	}
+}
+
+fn gas(gasometer: &GasometerState) -> Gas {
+	let mut gas = Gas::new(gasometer.gas_limit());
+	gas.record_cost(gasometer.total_used_gas());
+	gas
+}
+
+/// Sync revm's view of the frame with the machine, about to evaluate the
+/// opcode at the position.
+fn sync(interp: &mut Interpreter, machine: &Machine, position: usize) {
+	interp.instruction_pointer = interp.contract.bytecode.as_ptr().wrapping_add(position);
+	interp.gas = gas(&machine.state.gasometer);
+
+	interp.stack = Stack::new();
+	for value in machine.stack.data() {
+		// The machine stack is never deeper than revm's stack limit.
+		let _ = interp.stack.push(h256_to_ru256(*value));
+	}
+
+	interp.memory = Memory::new();
+	interp.memory.resize(machine.memory.len());
+	interp.memory.set(0, machine.memory.data());
+
+	interp.return_data_buffer = machine.state.runtime.retbuf.clone().into();
+}
+
+/// Write the changes of the revm inspector to the stack and memory back to
+/// the machine.
+fn write_back(interp: &Interpreter, machine: &mut Machine) -> Result<(), ExitError> {
+	let stack = interp.stack.data();
+	let changed = stack.len() != machine.stack.len()
+		|| stack
+			.iter()
+			.zip(machine.stack.data())
+			.any(|(revm, evm)| ru256_to_h256(*revm) != *evm);
+	if changed {
+		machine.stack.clear();
+		for value in stack {
+			machine.stack.push(ru256_to_h256(*value))?;
+		}
+	}
+
+	if interp.memory.data()[..] != machine.memory.data()[..] {
+		machine.memory.set(0, interp.memory.data(), None)?;
+	}
+
+	Ok(())
+}
+
+/// The log or self-destruct the opcode makes if it succeeds.
+fn effect<H: RuntimeBaseBackend>(machine: &Machine, handler: &H, opcode: Opcode) -> Option<Effect> {
+	let address = machine.state.runtime.context.address;
+	match opcode {
+		Opcode::LOG0 | Opcode::LOG1 | Opcode::LOG2 | Opcode::LOG3 | Opcode::LOG4 => {
+			let count = usize::from(opcode.0 - Opcode::LOG0.0);
+			let offset = U256::from_big_endian(&machine.stack.peek(0).ok()?[..]);
+			let len = U256::from_big_endian(&machine.stack.peek(1).ok()?[..]);
+			let topics = (0..count)
+				.map(|i| machine.stack.peek(2 + i))
+				.collect::<Result<Vec<_>, _>>()
+				.ok()?;
+			// A log of more than the address space runs out of gas.
+			if offset > U256::from(usize::MAX) || len > U256::from(usize::MAX) {
+				return None;
+			}
+
+			Some(Effect::Log {
+				address,
+				topics,
+				offset: offset.as_usize(),
+				len: len.as_usize(),
+			})
+		}
+		Opcode::SUICIDE => Some(Effect::SelfDestruct {
+			contract: address,
+			target: machine.stack.peek(0).ok()?.into(),
+			value: handler.balance(address),
+		}),
+		_ => None,
+	}
+}
+
+fn report<DB: Database, I: revm::Inspector<DB>>(
+	inspector: &mut I,
+	data: &mut EVMData<'_, DB>,
+	interp: &Interpreter,
+	effect: Effect,
+) {
+	match effect {
+		Effect::Log {
+			address,
+			topics,
+			offset,
+			len,
+		} => {
+			// The log succeeded, so the memory it expanded is zeros.
+			let memory = interp.memory.data();
+			let mut log = vec![0; len];
+			if offset < memory.len() {
+				let end = memory.len().min(offset + len);
+				log[..end - offset].copy_from_slice(&memory[offset..end]);
+			}
+			let topics = topics
+				.into_iter()
+				.map(|topic| B256::from(topic.0))
+				.collect::<Vec<_>>();
+			inspector.log(data, &to_address(address), &topics, &log.into());
+		}
+		Effect::SelfDestruct {
+			contract,
+			target,
+			value,
+		} => inspector.selfdestruct(to_address(contract), to_address(target), to_u256(value)),
+	}
+}
+
+fn instruction_result(result: &ExitResult) -> InstructionResult {
+	match result {
+		Ok(ExitSucceed::Stopped) => InstructionResult::Stop,
+		Ok(ExitSucceed::Returned) => InstructionResult::Return,
+		Ok(ExitSucceed::Suicided) => InstructionResult::SelfDestruct,
+		Err(ExitError::Reverted) => InstructionResult::Revert,
+		Err(ExitError::Exception(exception)) => match exception {
+			ExitException::StackUnderflow => InstructionResult::StackUnderflow,
+			ExitException::StackOverflow => InstructionResult::StackOverflow,
+			ExitException::InvalidJump | ExitException::PCUnderflow => {
+				InstructionResult::InvalidJump
+			}
+			ExitException::InvalidRange | ExitException::OutOfOffset => {
+				InstructionResult::OutOfOffset
+			}
+			ExitException::DesignatedInvalid => InstructionResult::InvalidFEOpcode,
+			ExitException::CallTooDeep => InstructionResult::CallTooDeep,
+			ExitException::CreateCollision => InstructionResult::CreateCollision,
+			ExitException::CreateContractLimit => InstructionResult::CreateContractSizeLimit,
+			ExitException::InvalidOpcode(_) => InstructionResult::OpcodeNotFound,
+			ExitException::OutOfGas => InstructionResult::OutOfGas,
+			ExitException::OutOfFund => InstructionResult::OutOfFund,
+			ExitException::MaxNonce => InstructionResult::NonceOverflow,
+			ExitException::CreateEmpty | ExitException::Unauthorized | ExitException::Other(_) => {
+				InstructionResult::PrecompileError
+			}
+		},
+		Err(ExitError::Fatal(_)) => InstructionResult::FatalExternalError,
+	}
+}
+
+fn exit_result(ret: InstructionResult) -> ExitResult {
+	let exception = match ret {
+		InstructionResult::Continue | InstructionResult::Stop => return Ok(ExitSucceed::Stopped),
+		InstructionResult::Return => return Ok(ExitSucceed::Returned),
+		InstructionResult::SelfDestruct => return Ok(ExitSucceed::Suicided),
+		InstructionResult::Revert => return Err(ExitError::Reverted),
+		InstructionResult::FatalExternalError => {
+			return Err(ExitFatal::Other(Cow::Borrowed("revm inspector fatal error")).into())
+		}
+		InstructionResult::CallTooDeep => ExitException::CallTooDeep,
+		InstructionResult::OutOfFund => ExitException::OutOfFund,
+		InstructionResult::OutOfGas
+		| InstructionResult::MemoryOOG
+		| InstructionResult::MemoryLimitOOG
+		| InstructionResult::PrecompileOOG
+		| InstructionResult::InvalidOperandOOG => ExitException::OutOfGas,
+		InstructionResult::InvalidFEOpcode => ExitException::DesignatedInvalid,
+		InstructionResult::InvalidJump => ExitException::InvalidJump,
+		InstructionResult::StackUnderflow => ExitException::StackUnderflow,
+		InstructionResult::StackOverflow => ExitException::StackOverflow,
+		InstructionResult::OutOfOffset => ExitException::OutOfOffset,
+		InstructionResult::CreateCollision => ExitException::CreateCollision,
+		InstructionResult::NonceOverflow => ExitException::MaxNonce,
+		InstructionResult::CreateContractSizeLimit => ExitException::CreateContractLimit,
+		ret => ExitException::Other(Cow::Owned(format!("{:?}", ret))),
+	};
+	Err(exception.into())
+}
+
+fn to_address(address: H160) -> Address {
+	Address::from_slice(address.as_bytes())
+}
+
+fn from_address(address: Address) -> H160 {
+	H160::from_slice(address.as_slice())
+}
+
+fn to_u256(value: U256) -> RU256 {
+	let mut bytes = [0u8; 32];
+	value.to_big_endian(&mut bytes);
+	RU256::from_be_bytes(bytes)
+}
+
+fn from_u256(value: RU256) -> U256 {
+	U256::from_big_endian(&value.to_be_bytes::<32>())
+}
+
+fn h256_to_ru256(value: H256) -> RU256 {
+	RU256::from_be_bytes(value.0)
+}
+
+fn ru256_to_h256(value: RU256) -> H256 {
+	H256(value.to_be_bytes::<32>())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::RefCell;
+
+	use evm::standard::Config;
+	use primitive_types::H160;
+	use revm::{
+		interpreter::{CallInputs, CreateInputs, Gas, InstructionResult, Interpreter},
+		primitives::{Address, Bytes, B256, U256 as RU256},
+		Database, EVMData,
+	};
+
+	use super::{to_address, RevmInspector};
+	use crate::testing::{backend, call_args, transact, CONTRACT as CALLER_CONTRACT};
+
+	const LOGGER: H160 = H160([0x30; 20]);
+
+	#[derive(Default)]
+	struct Recorder {
+		steps: Vec<(Address, usize, u8)>,
+		step_ends: usize,
+		calls: Vec<Address>,
+		call_ends: Vec<InstructionResult>,
+		logs: Vec<(Address, Vec<B256>, Bytes)>,
+		creates: Vec<Address>,
+		create_ends: Vec<(InstructionResult, Option<Address>)>,
+		skip_calls: bool,
+		/// Replace the top of the stack before each `ADD` with this value.
+		add_override: Option<u8>,
+	}
+
+	impl<DB: Database> revm::Inspector<DB> for Recorder {
+		fn step(
+			&mut self,
+			interp: &mut Interpreter,
+			_data: &mut EVMData<'_, DB>,
+		) -> InstructionResult {
+			self.steps.push((
+				interp.contract.address,
+				interp.program_counter(),
+				interp.current_opcode(),
+			));
+			if let (Some(value), 0x01) = (self.add_override, interp.current_opcode()) {
+				interp.stack.pop().unwrap();
+				interp.stack.push(RU256::from(value)).unwrap();
+			}
+			InstructionResult::Continue
+		}
+
+		fn step_end(
+			&mut self,
+			_interp: &mut Interpreter,
+			_data: &mut EVMData<'_, DB>,
+			_eval: InstructionResult,
+		) -> InstructionResult {
+			self.step_ends += 1;
+			InstructionResult::Continue
+		}
+
+		fn log(
+			&mut self,
+			_data: &mut EVMData<'_, DB>,
+			address: &Address,
+			topics: &[B256],
+			data: &Bytes,
+		) {
+			self.logs.push((*address, topics.to_vec(), data.clone()));
+		}
+
+		fn call(
+			&mut self,
+			_data: &mut EVMData<'_, DB>,
+			inputs: &mut CallInputs,
+		) -> (InstructionResult, Gas, Bytes) {
+			self.calls.push(inputs.contract);
+			if self.skip_calls {
+				(InstructionResult::Revert, Gas::new(0), Bytes::new())
+			} else {
+				(InstructionResult::Continue, Gas::new(0), Bytes::new())
+			}
+		}
+
+		fn call_end(
+			&mut self,
+			_data: &mut EVMData<'_, DB>,
+			_inputs: &CallInputs,
+			remaining_gas: Gas,
+			ret: InstructionResult,
+			out: Bytes,
+		) -> (InstructionResult, Gas, Bytes) {
+			self.call_ends.push(ret);
+			(ret, remaining_gas, out)
+		}
+
+		fn create(
+			&mut self,
+			_data: &mut EVMData<'_, DB>,
+			inputs: &mut CreateInputs,
+		) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+			self.creates.push(inputs.caller);
+			(InstructionResult::Continue, None, Gas::new(0), Bytes::new())
+		}
+
+		fn create_end(
+			&mut self,
+			_data: &mut EVMData<'_, DB>,
+			_inputs: &CreateInputs,
+			ret: InstructionResult,
+			address: Option<Address>,
+			remaining_gas: Gas,
+			out: Bytes,
+		) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+			self.create_ends.push((ret, address));
+			(ret, address, remaining_gas, out)
+		}
+	}
+
+	/// Calls the logger with no value and no input.
+	fn call_logger() -> Vec<u8> {
+		let mut code = vec![
+			0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73,
+		];
+		code.extend_from_slice(&LOGGER[..]);
+		code.extend_from_slice(&[0x5a, 0xf1, 0x00]);
+		code
+	}
+
+	/// Runs the code as the caller contract, returning the recorder and the
+	/// return value.
+	fn run(recorder: Recorder, code: Vec<u8>) -> (Recorder, Vec<u8>) {
+		let mut base = backend();
+		base.set_code(CALLER_CONTRACT, code);
+		// Stores 0xaa at memory 0, and logs it with topic 7.
+		base.set_code(
+			LOGGER,
+			vec![
+				0x60, 0xaa, 0x60, 0x00, 0x53, 0x60, 0x07, 0x60, 0x01, 0x60, 0x00, 0xa1,
+			],
+		);
+
+		let inspector = RefCell::new(RevmInspector::new(recorder));
+		let value = transact(&base, &Config::cancun(), call_args(Vec::new()), &inspector);
+		assert!(value.exit.is_ok());
+		(inspector.into_inner().into_inner(), value.retval)
+	}
+
+	#[test]
+	fn revm_inspector_sees_steps_calls_and_logs() {
+		let (recorder, _) = run(Recorder::default(), call_logger());
+
+		let caller = to_address(CALLER_CONTRACT);
+		let logger = to_address(LOGGER);
+		let steps = recorder
+			.steps
+			.iter()
+			.map(|(address, pc, opcode)| (*address == logger, *pc, *opcode))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			&steps[..],
+			&[
+				(false, 0, 0x60),
+				(false, 2, 0x60),
+				(false, 4, 0x60),
+				(false, 6, 0x60),
+				(false, 8, 0x60),
+				(false, 10, 0x73),
+				(false, 31, 0x5a),
+				(false, 32, 0xf1),
+				(true, 0, 0x60),
+				(true, 2, 0x60),
+				(true, 4, 0x53),
+				(true, 5, 0x60),
+				(true, 7, 0x60),
+				(true, 9, 0x60),
+				(true, 11, 0xa1),
+				(false, 33, 0x00),
+			]
+		);
+		assert_eq!(recorder.steps[0].0, caller);
+		// Every step ends, except the last one of the transaction frame.
+		assert_eq!(recorder.step_ends, recorder.steps.len() - 1);
+		assert_eq!(recorder.calls, vec![logger]);
+		assert_eq!(recorder.call_ends, vec![InstructionResult::Stop]);
+		assert_eq!(
+			recorder.logs,
+			vec![(
+				logger,
+				vec![B256::with_last_byte(7)],
+				Bytes::from(vec![0xaa])
+			)]
+		);
+	}
+
+	#[test]
+	fn calls_skipped_by_the_revm_inspector_are_not_entered() {
+		let (recorder, _) = run(
+			Recorder {
+				skip_calls: true,
+				..Recorder::default()
+			},
+			call_logger(),
+		);
+
+		assert_eq!(recorder.calls, vec![to_address(LOGGER)]);
+		assert!(recorder.call_ends.is_empty());
+		assert!(recorder.logs.is_empty());
+		assert!(recorder
+			.steps
+			.iter()
+			.all(|(address, _, _)| *address == to_address(CALLER_CONTRACT)));
+	}
+
+	#[test]
+	fn revm_inspector_sees_creates() {
+		// Creates a contract with empty init code.
+		let code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0xf0, 0x00];
+		let (recorder, _) = run(Recorder::default(), code);
+
+		let caller = to_address(CALLER_CONTRACT);
+		assert_eq!(recorder.creates, vec![caller]);
+		assert_eq!(
+			recorder.create_ends,
+			vec![(InstructionResult::Stop, Some(caller.create(0)))]
+		);
+	}
+
+	#[test]
+	fn stack_changes_of_the_revm_inspector_are_written_back() {
+		// Returns 1 + 2 as a word.
+		let code = vec![
+			0x60, 0x01, 0x60, 0x02, 0x01, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+		];
+
+		let (_, retval) = run(Recorder::default(), code.clone());
+		assert_eq!(retval[31], 3);
+
+		let (_, retval) = run(
+			Recorder {
+				add_override: Some(7),
+				..Recorder::default()
+			},
+			code,
+		);
+		assert_eq!(retval[31], 8);
+	}
+}
@@ -0,0 +1,227 @@
+use std::{
+	collections::BTreeSet,
+	io::{self, Write},
+};
+
+use evm::{
+	interpreter::{error::ExitResult, etable::Control, opcode::Opcode},
+	standard::{inspector::Inspector, Machine, State, SubstackInvoke},
+};
+use primitive_types::{H160, U256};
+
+/// A suspicious pattern found by the [SecurityTracer].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Finding {
+	/// A call entered `address` while a frame of `address` with storage
+	/// writes was still executing, at the given call depth.
+	Reentrancy { address: H160, depth: usize },
+	/// A `CALL` with value to a precompile was attempted in a static context.
+	/// The call fails, but the transfer was attempted.
+	StaticValueTransfer {
+		caller: H160,
+		precompile: H160,
+		value: U256,
+	},
+	/// A `DELEGATECALL` or `CALLCODE` targeted code created earlier in the
+	/// same transaction.
+	DelegateCallToCreated { caller: H160, target: H160 },
+}
+
+impl Finding {
+	/// Write the finding as a JSON object.
+	pub fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		match self {
+			Self::Reentrancy { address, depth } => write!(
+				writer,
+				"{{\"kind\":\"reentrancy\",\"address\":\"0x{:x}\",\"depth\":{}}}",
+				address, depth
+			),
+			Self::StaticValueTransfer {
+				caller,
+				precompile,
+				value,
+			} => write!(
+				writer,
+				"{{\"kind\":\"staticValueTransfer\",\"caller\":\"0x{:x}\",\"precompile\":\"0x{:x}\",\"value\":\"0x{:x}\"}}",
+				caller, precompile, value
+			),
+			Self::DelegateCallToCreated { caller, target } => write!(
+				writer,
+				"{{\"kind\":\"delegateCallToCreated\",\"caller\":\"0x{:x}\",\"target\":\"0x{:x}\"}}",
+				caller, target
+			),
+		}
+	}
+}
+
+struct Frame {
+	address: H160,
+	wrote: bool,
+}
+
+/// A tracer that flags call patterns that are often the sign of a bug or an
+/// attack, for simulation-based security tooling:
+///
+/// * Reentrancy: a `CALL` or `STATICCALL` into an address that has a frame
+///   with storage writes further up the call stack. Writes of delegated
+///   frames count for their context address once they exit successfully.
+/// * Value transfers to the precompiles given with
+///   [SecurityTracer::with_precompiles] attempted in a static context.
+/// * Delegated calls to contracts created by `CREATE` or `CREATE2` earlier in
+///   the transaction.
+///
+/// The tracer is an [Inspector], to be shared by an
+/// [evm::standard::inspector::InspectedEtable] and an
+/// [evm::standard::inspector::InspectorMiddleware]. It does not modify the
+/// execution. Use one tracer per transaction.
+#[derive(Default)]
+pub struct SecurityTracer {
+	precompiles: BTreeSet<H160>,
+	frames: Vec<Frame>,
+	created: BTreeSet<H160>,
+	findings: Vec<Finding>,
+}
+
+impl SecurityTracer {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Flag value transfers to the precompiles in static contexts.
+	#[must_use]
+	pub fn with_precompiles(mut self, precompiles: impl IntoIterator<Item = H160>) -> Self {
+		self.precompiles.extend(precompiles);
+		self
+	}
+
+	/// Findings so far, in the order they were found.
+	pub fn findings(&self) -> &[Finding] {
+		&self.findings
+	}
+
+	/// Write the findings as a JSON array.
+	pub fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		writer.write_all(b"[")?;
+		for (i, finding) in self.findings.iter().enumerate() {
+			if i != 0 {
+				writer.write_all(b",")?;
+			}
+			finding.write_json(writer)?;
+		}
+		writer.write_all(b"]")
+	}
+}
+
+impl<'config, H, Tr> Inspector<State<'config>, H, Tr> for SecurityTracer {
+	fn step(
+		&mut self,
+		machine: &mut Machine<'config>,
+		_handler: &mut H,
+		opcode: Opcode,
+		_position: usize,
+	) -> Option<Control<Tr>> {
+		let address = machine.state.runtime.context.address;
+		if self.frames.is_empty() {
+			self.frames.push(Frame {
+				address,
+				wrote: false,
+			});
+		}
+
+		match opcode {
+			Opcode::SSTORE if !machine.state.gasometer.is_static => {
+				if let Some(frame) = self.frames.last_mut() {
+					frame.wrote = true;
+				}
+			}
+			// A value transfer in a static context fails before the call is
+			// trapped, so it is only seen here.
+			Opcode::CALL if machine.state.gasometer.is_static => {
+				if let (Ok(target), Ok(value)) = (machine.stack.peek(1), machine.stack.peek(2)) {
+					let target = H160::from(target);
+					let value = U256::from_big_endian(&value[..]);
+					if !value.is_zero() && self.precompiles.contains(&target) {
+						self.findings.push(Finding::StaticValueTransfer {
+							caller: address,
+							precompile: target,
+							value,
+						});
+					}
+				}
+			}
+			_ => (),
+		}
+
+		None
+	}
+
+	fn enter_substack(
+		&mut self,
+		invoke: &SubstackInvoke,
+		_handler: &mut H,
+	) -> Option<(ExitResult, Vec<u8>)> {
+		let address = match invoke {
+			SubstackInvoke::Call { trap, opcode } => {
+				let depth = self.frames.len();
+				match *opcode {
+					Opcode::CALL | Opcode::STATICCALL => {
+						let reentered = self
+							.frames
+							.iter()
+							.any(|frame| frame.address == trap.context.address && frame.wrote);
+						if reentered {
+							self.findings.push(Finding::Reentrancy {
+								address: trap.context.address,
+								depth,
+							});
+						}
+					}
+					Opcode::DELEGATECALL | Opcode::CALLCODE => {
+						if self.created.contains(&trap.target) {
+							self.findings.push(Finding::DelegateCallToCreated {
+								caller: trap.context.address,
+								target: trap.target,
+							});
+						}
+					}
+					_ => (),
+				}
+				trap.context.address
+			}
+			SubstackInvoke::Create { address, .. } => *address,
+		};
+
+		self.frames.push(Frame {
+			address,
+			wrote: false,
+		});
+
+		None
+	}
+
+	fn exit_substack(
+		&mut self,
+		invoke: &SubstackInvoke,
+		_state: &State<'config>,
+		result: &mut ExitResult,
+		_retval: &mut Vec<u8>,
+		_handler: &mut H,
+	) {
+		let frame = match self.frames.pop() {
+			Some(frame) => frame,
+			None => return,
+		};
+		if result.is_err() {
+			return;
+		}
+
+		if let SubstackInvoke::Create { address, .. } = invoke {
+			self.created.insert(*address);
+		}
+		if let Some(parent) = self.frames.last_mut() {
+			if parent.address == frame.address {
+				parent.wrote |= frame.wrote;
+			}
+		}
+	}
+}
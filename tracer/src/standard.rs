@@ -1,14 +1,210 @@
+use std::io::{self, Write};
+
 use evm::{
-	interpreter::opcode::Opcode,
+	interpreter::{etable::Control, opcode::Opcode, runtime::GasState},
 	standard::{Machine, State},
+	EtableHook,
 };
+use primitive_types::{H160, H256, U256};
+
+/// A borrowed view of the machine before an opcode is evaluated. Creating it
+/// does not allocate.
+#[derive(Clone, Copy, Debug)]
+pub struct Step<'a> {
+	/// Context address of the call frame.
+	pub address: H160,
+	/// Position of the opcode in the code.
+	pub position: usize,
+	/// The opcode.
+	pub opcode: Opcode,
+	/// Gas left before the opcode.
+	pub gas: u64,
+	/// Stack, with the top of the stack last.
+	pub stack: &'a [H256],
+	/// Memory.
+	pub memory: &'a [u8],
+}
+
+impl<'a> Step<'a> {
+	pub fn new(machine: &'a Machine, opcode: Opcode, position: usize) -> Self {
+		Self {
+			address: machine.state.runtime.context.address,
+			position,
+			opcode,
+			gas: machine.state.gas(),
+			stack: machine.stack.data(),
+			memory: machine.memory.data(),
+		}
+	}
+
+	/// Copy the view into an owned struct log.
+	pub fn to_struct_log(&self) -> StructLog {
+		StructLog {
+			address: self.address,
+			position: self.position,
+			opcode: self.opcode,
+			gas: self.gas,
+			stack: self.stack.to_vec(),
+			memory: self.memory.to_vec(),
+		}
+	}
+
+	/// Write the step as one line of JSON, with its `pc`, `op`, `gas`,
+	/// `memSize` and `stack`. This is a subset of the fields of EIP-3155: the
+	/// depth, gas cost, refund and return data are not known to the step.
+	pub fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		write!(
+			writer,
+			"{{\"pc\":{},\"op\":{},\"gas\":\"0x{:x}\",\"memSize\":{},\"stack\":[",
+			self.position,
+			self.opcode.0,
+			self.gas,
+			self.memory.len()
+		)?;
+		for (i, value) in self.stack.iter().enumerate() {
+			if i != 0 {
+				writer.write_all(b",")?;
+			}
+			write!(writer, "\"0x{:x}\"", U256::from_big_endian(&value[..]))?;
+		}
+		writer.write_all(b"]}\n")
+	}
+}
 
-pub trait EvalTracer<H> {
-	fn on_eval(&mut self, machine: &Machine, handle: &H, opcode: Opcode, position: usize);
+/// An owned copy of a [Step].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StructLog {
+	pub address: H160,
+	pub position: usize,
+	pub opcode: Opcode,
+	pub gas: u64,
+	pub stack: Vec<H256>,
+	pub memory: Vec<u8>,
 }
 
-impl<'config, H, T: EvalTracer<H>> crate::EvalTracer<State<'config>, H> for T {
-	fn on_eval(&mut self, machine: &Machine, handle: &H, opcode: Opcode, position: usize) {
-		EvalTracer::<H>::on_eval(self, machine, handle, opcode, position)
+/// A tracer that keeps a [StructLog] of every step in memory. For long
+/// executions, use [StreamingStructLogger] instead.
+///
+/// The tracers of steps are [EtableHook]s, to be used in an
+/// [evm::HookedEtable].
+#[derive(Clone, Debug, Default)]
+pub struct StructLogger {
+	pub logs: Vec<StructLog>,
+}
+
+impl<'config, H, Tr> EtableHook<State<'config>, H, Tr> for StructLogger {
+	type Data = ();
+
+	fn before_eval(
+		&mut self,
+		machine: &Machine<'config>,
+		_handle: &H,
+		opcode: Opcode,
+		position: usize,
+	) {
+		self.logs
+			.push(Step::new(machine, opcode, position).to_struct_log());
+	}
+
+	fn after_eval(
+		&mut self,
+		_data: (),
+		_machine: &Machine<'config>,
+		_handle: &H,
+		_opcode: Opcode,
+		_position: usize,
+		control: Control<Tr>,
+	) -> Control<Tr> {
+		control
+	}
+}
+
+/// A tracer that writes every step to the writer as newline-delimited JSON,
+/// without keeping it in memory.
+///
+/// Writing stops at the first error, which is returned by
+/// [StreamingStructLogger::finish].
+pub struct StreamingStructLogger<W> {
+	writer: W,
+	error: Option<io::Error>,
+}
+
+impl<W: Write> StreamingStructLogger<W> {
+	pub fn new(writer: W) -> Self {
+		Self {
+			writer,
+			error: None,
+		}
+	}
+
+	/// Flush the writer, and return it, or the first error.
+	pub fn finish(mut self) -> io::Result<W> {
+		if let Some(error) = self.error {
+			return Err(error);
+		}
+
+		self.writer.flush()?;
+		Ok(self.writer)
+	}
+}
+
+impl<'config, H, Tr, W: Write> EtableHook<State<'config>, H, Tr> for StreamingStructLogger<W> {
+	type Data = ();
+
+	fn before_eval(
+		&mut self,
+		machine: &Machine<'config>,
+		_handle: &H,
+		opcode: Opcode,
+		position: usize,
+	) {
+		if self.error.is_some() {
+			return;
+		}
+
+		if let Err(error) = Step::new(machine, opcode, position).write_json(&mut self.writer) {
+			self.error = Some(error);
+		}
+	}
+
+	fn after_eval(
+		&mut self,
+		_data: (),
+		_machine: &Machine<'config>,
+		_handle: &H,
+		_opcode: Opcode,
+		_position: usize,
+		control: Control<Tr>,
+	) -> Control<Tr> {
+		control
+	}
+}
+
+/// A tracer that passes every step to the callback.
+pub struct StepCallback<F>(pub F);
+
+impl<'config, H, Tr, F: FnMut(&Step<'_>)> EtableHook<State<'config>, H, Tr> for StepCallback<F> {
+	type Data = ();
+
+	fn before_eval(
+		&mut self,
+		machine: &Machine<'config>,
+		_handle: &H,
+		opcode: Opcode,
+		position: usize,
+	) {
+		(self.0)(&Step::new(machine, opcode, position))
+	}
+
+	fn after_eval(
+		&mut self,
+		_data: (),
+		_machine: &Machine<'config>,
+		_handle: &H,
+		_opcode: Opcode,
+		_position: usize,
+		control: Control<Tr>,
+	) -> Control<Tr> {
+		control
 	}
 }
@@ -0,0 +1,75 @@
+//! Fixture shared by the tests of the tracers: a caller and a contract, a test
+//! backend, and transactions run with an inspector on an overlay of it.
+
+use std::{cell::RefCell, collections::BTreeSet};
+
+use evm::{
+	backend::{OverlayedBackend, TestBackend, TestEnvironment},
+	interpreter::error::CallCreateTrap,
+	standard::{
+		eval_gasometer,
+		inspector::{InspectedEtable, Inspector, InspectorMiddleware},
+		Config, Etable, EtableResolver, Invoker, State, TransactArgs, TransactValue,
+	},
+	MiddlewareInvoker,
+};
+use primitive_types::{H160, U256};
+
+/// Sender of the transactions.
+pub const CALLER: H160 = H160([0x10; 20]);
+/// Contract called by the transactions.
+pub const CONTRACT: H160 = H160([0x20; 20]);
+
+/// Handler of the transactions, an overlay of the test backend.
+pub type TestHandler<'a> = OverlayedBackend<&'a TestBackend>;
+
+/// Test backend with a block gas limit of 1_000_000, and no accounts.
+pub fn backend() -> TestBackend {
+	TestBackend::new(TestEnvironment {
+		block_gas_limit: U256::from(1_000_000),
+		..Default::default()
+	})
+}
+
+/// Call of the contract from the caller, with no value and a gas limit of
+/// 100_000.
+pub fn call_args(data: Vec<u8>) -> TransactArgs {
+	TransactArgs::Call {
+		caller: CALLER,
+		address: CONTRACT,
+		nonce: None,
+		chain_id: None,
+		value: U256::zero(),
+		data,
+		gas_limit: U256::from(100_000),
+		gas_price: U256::zero(),
+		access_list: Vec::new(),
+		system: false,
+		mint: None,
+		fee_payer: None,
+	}
+}
+
+/// Run the transaction on an overlay of the base backend, with the inspector
+/// on the steps and the frames, and unwrap its result.
+pub fn transact<'config, 'a, N>(
+	base: &'a TestBackend,
+	config: &'config Config,
+	args: TransactArgs,
+	inspector: &RefCell<N>,
+) -> TransactValue
+where
+	N: Inspector<State<'config>, TestHandler<'a>, CallCreateTrap>,
+{
+	let etable: (Etable<TestHandler<'a>>, Etable<TestHandler<'a>>) =
+		(Etable::single(eval_gasometer), Etable::runtime());
+	let etable = InspectedEtable::new(etable, inspector);
+	let resolver = EtableResolver::new(config, &(), &etable);
+	let invoker = MiddlewareInvoker::new(
+		Invoker::new(config, &resolver),
+		InspectorMiddleware::new(inspector),
+	);
+
+	let mut backend = OverlayedBackend::new(base, BTreeSet::new());
+	evm::transact_hybrid(args, None, &mut backend, &invoker).unwrap()
+}